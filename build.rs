@@ -0,0 +1,35 @@
+//! Generates the shell completion scripts and the man page of the
+//! serde_datalog binary from its command-line arguments, so that the
+//! `completions` and `man` subcommands print them as they were built.
+
+#[cfg(feature = "bin_only")]
+#[allow(dead_code)]
+mod cli {
+    use clap::{CommandFactory, Parser, Subcommand, ValueEnum};
+    use std::{collections::BTreeMap, env, fs::File, io, path::PathBuf};
+
+    include!("src/cli.rs");
+
+    /// Writes a completion script for each shell and the man page into
+    /// `OUT_DIR`.
+    pub fn generate() -> io::Result<()> {
+        let out_dir = PathBuf::from(env::var_os("OUT_DIR").expect("OUT_DIR is set by Cargo"));
+        let mut command = Args::command();
+        for shell in clap_complete::Shell::value_variants() {
+            let mut file = File::create(out_dir.join(format!("completions.{}", shell)))?;
+            clap_complete::generate(*shell, &mut command, "serde_datalog", &mut file);
+        }
+        let mut file = File::create(out_dir.join("serde_datalog.1"))?;
+        clap_mangen::Man::new(command).render(&mut file)
+    }
+}
+
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+
+    #[cfg(feature = "bin_only")]
+    {
+        println!("cargo:rerun-if-changed=src/cli.rs");
+        cli::generate().expect("cannot generate shell completions and the man page");
+    }
+}
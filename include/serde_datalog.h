@@ -0,0 +1,56 @@
+#ifndef SERDE_DATALOG_H
+#define SERDE_DATALOG_H
+
+/* Generated with cbindgen from src/ffi.rs; do not edit by hand. */
+
+#include <stdbool.h>
+
+// An extractor whose facts have not yet been stored in a database.
+typedef struct SerdeDatalogExtractor SerdeDatalogExtractor;
+
+// Create an extractor that stores facts in a Souffle SQLite database. If
+// `string_keys` is set, map keys are stored as strings, as in
+// [souffle_sqlite::StringKeyBackend]; otherwise map keys are elements, as in
+// [souffle_sqlite::Backend].
+struct SerdeDatalogExtractor *serde_datalog_extractor_new(bool string_keys);
+
+// Release `extractor`, discarding facts that were not stored.
+//
+// # Safety
+//
+// `extractor` must be null or have been returned by
+// [serde_datalog_extractor_new], and not released before.
+void serde_datalog_extractor_free(struct SerdeDatalogExtractor *extractor);
+
+// Extract facts from the NUL-terminated JSON document `json`, read from the
+// file named `file`. Returns 0 on success and -1 on failure.
+//
+// # Safety
+//
+// `extractor` must have been returned by [serde_datalog_extractor_new], and
+// `file` and `json` must be NUL-terminated strings.
+int serde_datalog_extract_json(struct SerdeDatalogExtractor *extractor,
+                               const char *file,
+                               const char *json);
+
+// Store the facts of `extractor` in a SQLite file with name `filename`,
+// which must not already contain the tables of the schema. Facts can only
+// be stored once; if storing them fails, they are kept and can be stored
+// again. Returns 0 on success and -1 on failure.
+//
+// # Safety
+//
+// `extractor` must have been returned by [serde_datalog_extractor_new], and
+// `filename` must be a NUL-terminated string.
+int serde_datalog_dump_to_sqlite(struct SerdeDatalogExtractor *extractor, const char *filename);
+
+// Message describing the last failure of a function called with
+// `extractor`, or null if no function failed. The message is owned by
+// `extractor` and valid until the next call with it.
+//
+// # Safety
+//
+// `extractor` must have been returned by [serde_datalog_extractor_new].
+const char *serde_datalog_last_error(const struct SerdeDatalogExtractor *extractor);
+
+#endif  /* SERDE_DATALOG_H */
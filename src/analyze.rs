@@ -0,0 +1,181 @@
+//! Relations derived from the facts of databases generated by the Souffle
+//! SQLite backends, computed with [Ascent](https://github.com/s-arash/ascent)
+//! so that common measures need not be recomputed by every Datalog program:
+//!
+//! - `reachable(root, elem)`: element `elem` is reachable from the root
+//!   element `root`, including `root` itself;
+//! - `depth(elem, depth)`: the number of entries between a root element and
+//!   `elem`, which is 0 for root elements;
+//! - `subtreeSize(elem, size)`: the number of elements reachable from `elem`,
+//!   including `elem` itself.
+//!
+//! Elements are reachable through the values of structs, maps, sequences,
+//! and tuples, and through map keys that are elements. [Analysis::write_to]
+//! writes the derived relations into the database alongside the facts they
+//! are derived from, with a table and a view for each relation as for the
+//! relations of the facts.
+
+use self::program::Program;
+use crate::{
+    backend::souffle_sqlite::{db_has_string_keys, SchemaConfig},
+    Result,
+};
+
+/// The Ascent program deriving the relations, in a module of its own so
+/// that lints about the code expanded from the macro can be allowed.
+#[allow(clippy::unused_unit, clippy::clone_on_copy)]
+mod program {
+    use ascent::{aggregators::count, ascent};
+
+    ascent! {
+        pub struct Program;
+
+        relation root_elem(i64);
+        relation edge(i64, i64);
+
+        relation reachable(i64, i64);
+        reachable(r, r) <-- root_elem(r);
+        reachable(r, child) <-- reachable(r, parent), edge(parent, child);
+
+        relation depth(i64, i64);
+        depth(r, 0) <-- root_elem(r);
+        depth(child, d + 1) <-- depth(parent, d), edge(parent, child);
+
+        relation descendant(i64, i64);
+        descendant(elem, elem) <-- reachable(_, elem);
+        descendant(ancestor, child) <-- descendant(ancestor, parent), edge(parent, child);
+
+        relation subtree_size(i64, usize);
+        subtree_size(elem, size) <-- reachable(_, elem), agg size = count() in descendant(elem, _);
+    }
+
+    impl Program {
+        /// A program with input facts `root_elem` and `edge`.
+        pub fn new(root_elem: Vec<(i64,)>, edge: Vec<(i64, i64)>) -> Self {
+            Program {
+                root_elem,
+                edge,
+                ..Default::default()
+            }
+        }
+    }
+}
+
+/// The relations derived from a database of facts by [analyze].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Analysis {
+    /// Facts `reachable(root, elem)`.
+    pub reachable: Vec<(i64, i64)>,
+
+    /// Facts `depth(elem, depth)`.
+    pub depth: Vec<(i64, i64)>,
+
+    /// Facts `subtreeSize(elem, size)`.
+    pub subtree_size: Vec<(i64, usize)>,
+}
+
+/// Derive the relations of [Analysis] from the facts of the database of
+/// `conn`, whose tables are named according to `schema`. Facts are sorted.
+pub fn analyze(conn: &rusqlite::Connection, schema: &SchemaConfig) -> Result<Analysis> {
+    let root_elem = conn
+        .prepare(&format!(
+            "SELECT elem FROM {};",
+            schema.table_name("rootElem")
+        ))?
+        .query_map((), |row| rusqlite::Result::Ok((row.get(0)?,)))?
+        .collect::<rusqlite::Result<_>>()?;
+
+    let mut edge_tables = vec![
+        ("struct", "value"),
+        ("seq", "value"),
+        ("tuple", "value"),
+        ("map", "value"),
+    ];
+    if !db_has_string_keys(conn, schema)? {
+        edge_tables.push(("map", "key"));
+    }
+
+    let mut edge = Vec::new();
+    for (relation, column) in edge_tables {
+        let edges = conn
+            .prepare(&format!(
+                "SELECT id, {} FROM {};",
+                column,
+                schema.table_name(relation)
+            ))?
+            .query_map((), |row| rusqlite::Result::Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        edge.extend(edges);
+    }
+
+    let mut program = Program::new(root_elem, edge);
+    program.run();
+
+    let mut analysis = Analysis {
+        reachable: program.reachable,
+        depth: program.depth,
+        subtree_size: program.subtree_size,
+    };
+    analysis.reachable.sort_unstable();
+    analysis.depth.sort_unstable();
+    analysis.subtree_size.sort_unstable();
+
+    Result::Ok(analysis)
+}
+
+impl Analysis {
+    /// Write the derived relations into the database of `conn`, whose tables
+    /// are named according to `schema`, replacing the derived relations
+    /// written by an earlier analysis.
+    pub fn write_to(&self, conn: &mut rusqlite::Connection, schema: &SchemaConfig) -> Result<()> {
+        let tx = conn.transaction()?;
+
+        write_relation(&tx, schema, "reachable", ["root", "elem"], &self.reachable)?;
+        write_relation(&tx, schema, "depth", ["id", "depth"], &self.depth)?;
+        let subtree_size: Vec<(i64, i64)> = self
+            .subtree_size
+            .iter()
+            .map(|(elem, size)| (*elem, *size as i64))
+            .collect();
+        write_relation(&tx, schema, "subtreeSize", ["id", "size"], &subtree_size)?;
+
+        tx.commit()?;
+        Result::Ok(())
+    }
+}
+
+/// Replace the table of `relation` in the database of `tx` with a table of
+/// `facts`, whose columns are named `columns`, and a view over it if `schema`
+/// creates views.
+fn write_relation(
+    tx: &rusqlite::Transaction,
+    schema: &SchemaConfig,
+    relation: &str,
+    [first, second]: [&str; 2],
+    facts: &[(i64, i64)],
+) -> rusqlite::Result<()> {
+    let table = schema.table_name(relation);
+    let view = schema.view_name(relation);
+
+    tx.execute_batch(&format!(
+        "DROP VIEW IF EXISTS {view};
+        DROP TABLE IF EXISTS {table};
+        CREATE TABLE {table} (
+            {first} INTEGER NOT NULL,
+            {second} INTEGER NOT NULL,
+            PRIMARY KEY ({first}, {second})
+        );"
+    ))?;
+    if schema.create_views {
+        tx.execute_batch(&format!(
+            "CREATE VIEW {view} AS SELECT {first}, {second} FROM {table};"
+        ))?;
+    }
+
+    let mut insert = tx.prepare(&format!("INSERT INTO {} VALUES (?, ?);", table))?;
+    for fact in facts {
+        insert.execute(*fact)?;
+    }
+
+    rusqlite::Result::Ok(())
+}
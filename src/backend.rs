@@ -1,4 +1,64 @@
 //! Implementations of [DatalogExtractorBackend][crate::DatalogExtractorBackend].
 
+#[cfg(feature = "arrow")]
+pub mod arrow;
+pub mod clingo;
+pub mod mangle;
+pub mod nemo;
+pub mod prolog;
+pub mod record;
+#[cfg(all(feature = "sqlite", not(target_arch = "wasm32")))]
 pub mod souffle_sqlite;
+#[cfg(feature = "stream")]
+pub mod stream;
+pub mod surrealql;
+pub mod tuples;
 pub mod vector;
+
+use std::fmt::{self, Display};
+
+/// Error encountered when a backend stores facts in, or loads facts from,
+/// a database.
+#[derive(Debug)]
+pub enum BackendError {
+    /// SQLite database operation failed
+    #[cfg(all(feature = "sqlite", not(target_arch = "wasm32")))]
+    Sqlite(rusqlite::Error),
+
+    /// Database does not conform to the schema expected by the backend
+    Schema(String),
+
+    /// Delivering facts to an external sink failed
+    Io(std::io::Error),
+}
+
+impl Display for BackendError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            #[cfg(all(feature = "sqlite", not(target_arch = "wasm32")))]
+            BackendError::Sqlite(err) => write!(f, "{}", err),
+
+            BackendError::Schema(msg) => write!(f, "unexpected database schema: {}", msg),
+
+            BackendError::Io(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl std::error::Error for BackendError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            #[cfg(all(feature = "sqlite", not(target_arch = "wasm32")))]
+            BackendError::Sqlite(err) => Some(err),
+            BackendError::Schema(_) => None,
+            BackendError::Io(err) => Some(err),
+        }
+    }
+}
+
+#[cfg(all(feature = "sqlite", not(target_arch = "wasm32")))]
+impl From<rusqlite::Error> for BackendError {
+    fn from(err: rusqlite::Error) -> Self {
+        BackendError::Sqlite(err)
+    }
+}
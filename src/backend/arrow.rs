@@ -0,0 +1,237 @@
+//! A backend that keeps facts in memory as [Arrow](https://arrow.apache.org/)
+//! record batches, one for each relation, so that in-process query engines
+//! such as [DataFusion](https://datafusion.apache.org/) can run SQL over
+//! extracted facts without writing them to disk.
+//!
+//! Batches have the relations and columns of the Souffle schema (see
+//! [souffle_sqlite][crate::backend::souffle_sqlite]). Element identifiers and
+//! numbers are `Int64` columns, booleans are `Boolean` columns, and names and
+//! text are `Utf8` columns. Columns of relations without facts have type
+//! `Null`. The backends stop at record batches: DataFusion is not a
+//! dependency of this crate, so they do not create a `SessionContext`.
+//! Register the batches with one, which keeps each one as a `MemTable`:
+//!
+//! ```ignore
+//! let ctx = SessionContext::new();
+//! let backend = extractor.get_backend();
+//! for (name, batch) in backend.record_batches()? {
+//!     ctx.register_batch(name, batch)?;
+//! }
+//! let df = ctx.sql("SELECT id FROM type WHERE type = 'Map'").await?;
+//! ```
+
+use arrow_array::{ArrayRef, BooleanArray, Int64Array, NullArray, RecordBatch, StringArray};
+use arrow_schema::{DataType, Field, Schema};
+use delegate::delegate;
+use std::{net::IpAddr, sync::Arc};
+
+use crate::{
+    backend::{
+        tuples::{self, MapKey, Relation, Term},
+        vector::{self, BackendData},
+        BackendError,
+    },
+    bytes_policy::BytesPolicy,
+    DatalogExtractorBackend, ElemId, ElemKind, ElemType, Result,
+};
+
+/// The facts of every relation stored in `data` as record batches, named by
+/// their relation, in the order of the Souffle schema.
+pub fn record_batches<K: MapKey>(
+    data: &BackendData<K>,
+) -> Result<Vec<(&'static str, RecordBatch)>> {
+    let mut batches = Vec::new();
+    for relation in tuples::relations(data).iter() {
+        batches.push((relation.name, record_batch(relation)?));
+    }
+    Result::Ok(batches)
+}
+
+fn record_batch(relation: &Relation) -> Result<RecordBatch> {
+    let mut fields = Vec::with_capacity(relation.arity());
+    let mut columns: Vec<ArrayRef> = Vec::with_capacity(relation.arity());
+
+    for (i, name) in relation.columns.iter().enumerate() {
+        let terms = relation.facts.iter().map(|fact| &fact[i]);
+        let column: ArrayRef = match relation.facts.first().map(|fact| &fact[i]) {
+            None => Arc::new(NullArray::new(0)),
+
+            Some(Term::Elem(_) | Term::Number(_)) => {
+                Arc::new(Int64Array::from_iter_values(terms.map(|term| match term {
+                    Term::Elem(elem) => elem.0 as i64,
+                    Term::Number(value) => *value,
+                    _ => 0,
+                })))
+            }
+
+            Some(Term::Bool(_)) => Arc::new(BooleanArray::from_iter(
+                terms.map(|term| Some(matches!(term, Term::Bool(true)))),
+            )),
+
+            Some(Term::Name(_) | Term::Text(_) | Term::Unresolved(_)) => Arc::new(
+                StringArray::from_iter_values(terms.map(|term| match term {
+                    Term::Name(text) | Term::Text(text) => text.to_string(),
+                    Term::Unresolved(sym) => format!("#{}", sym),
+                    _ => String::new(),
+                })),
+            ),
+        };
+
+        let data_type = column.data_type().clone();
+        let nullable = data_type == DataType::Null;
+        fields.push(Field::new(*name, data_type, nullable));
+        columns.push(column);
+    }
+
+    RecordBatch::try_new(Arc::new(Schema::new(fields)), columns)
+        .map_err(|err| BackendError::Schema(err.to_string()).into())
+}
+
+/// DatalogExtractorBackend impl that keeps facts in memory as Arrow record
+/// batches. Map keys are elements, as in [vector::Backend].
+#[derive(Default)]
+pub struct Backend {
+    vector_backend: vector::Backend,
+}
+
+impl Backend {
+    /// Intern strings of each [namespace][vector::SymbolNamespace]
+    /// separately; see [vector::Backend::with_separate_namespaces].
+    pub fn with_separate_namespaces(mut self) -> Self {
+        self.vector_backend = self.vector_backend.with_separate_namespaces();
+        self
+    }
+
+    /// Extract byte arrays as text encoded with `policy`; see
+    /// [vector::Backend::with_bytes_policy].
+    pub fn with_bytes_policy(mut self, policy: BytesPolicy) -> Self {
+        self.vector_backend = self.vector_backend.with_bytes_policy(policy);
+        self
+    }
+
+    /// The facts of every relation as record batches; see [record_batches].
+    pub fn record_batches(self) -> Result<Vec<(&'static str, RecordBatch)>> {
+        record_batches(&self.vector_backend.get_data())
+    }
+}
+
+impl DatalogExtractorBackend for Backend {
+    delegate! {
+        to (&mut self.vector_backend) {
+            fn add_root_elem(&mut self, file: &str, elem: ElemId) -> Result<()>;
+            fn add_file_format(&mut self, file: &str, format: &str) -> Result<()>;
+            fn add_key_field(&mut self, struct_name: &str, field: &str) -> Result<()>;
+            fn add_elem(&mut self, elem: ElemId, elem_type: ElemType) -> Result<()>;
+            fn add_bool(&mut self, elem: ElemId, value: bool) -> Result<()>;
+            fn add_i64(&mut self, elem: ElemId, value: i64) -> Result<()>;
+            fn add_u64(&mut self, elem: ElemId, value: u64) -> Result<()>;
+            fn add_str(&mut self, elem: ElemId, value: &str) -> Result<()>;
+            fn add_bytes(&mut self, elem: ElemId, value: &[u8]) -> Result<()>;
+            fn add_full_str(&mut self, elem: ElemId, value: &str) -> Result<()>;
+            fn add_map_entry(&mut self, elem: ElemId, key: ElemId, value: ElemId) -> Result<()>;
+            fn add_struct_type(&mut self, elem: ElemId, struct_name: &str) -> Result<()>;
+            fn add_struct_entry(&mut self, elem: ElemId, key: &str, value: ElemId) -> Result<()>;
+            fn add_key(&mut self, elem: ElemId, key: ElemId) -> Result<()>;
+            fn add_seq_entry(&mut self, elem: ElemId, pos: usize, value: ElemId) -> Result<()>;
+            fn add_variant_type(&mut self, elem: ElemId, type_name: &str, variant_name: &str) -> Result<()>;
+            fn add_tuple_entry(&mut self, elem: ElemId, pos: usize, value: ElemId) -> Result<()>;
+            fn add_schema_type(&mut self, elem: ElemId, schema_ref: &str) -> Result<()>;
+            fn add_datetime(&mut self, elem: ElemId, epoch_micros: i64, tz_offset: i32) -> Result<()>;
+            fn add_uuid(&mut self, elem: ElemId, hi: u64, lo: u64) -> Result<()>;
+            fn add_ip(&mut self, elem: ElemId, address: IpAddr) -> Result<()>;
+            fn add_url(&mut self, elem: ElemId, scheme: &str, host: &str) -> Result<()>;
+            fn add_email(&mut self, elem: ElemId, local: &str, domain: &str) -> Result<()>;
+            fn add_declared_len(&mut self, elem: ElemId, len: usize) -> Result<()>;
+            fn add_absent_field(&mut self, elem: ElemId, field: &str) -> Result<()>;
+            fn add_unit(&mut self, elem: ElemId) -> Result<()>;
+            fn add_truncated(&mut self, relation: &str, quota: usize) -> Result<()>;
+            fn add_redacted(&mut self, elem: ElemId) -> Result<()>;
+            fn add_sampled(&mut self, elem: ElemId, len: usize, kept: usize) -> Result<()>;
+            fn add_elem_type_kind(&mut self, elem_type: ElemType, kind: ElemKind) -> Result<()>;
+            fn add_same_string(&mut self, elem: ElemId, other: ElemId) -> Result<()>;
+            fn add_shape(&mut self, elem: ElemId, shape: u64) -> Result<()>;
+            fn add_shape_field(&mut self, shape: u64, pos: usize, field: &str) -> Result<()>;
+            fn add_parse_error(&mut self, file: &str, line: usize, message: &str) -> Result<()>;
+            fn add_string_hash(&mut self, elem: ElemId, hash: u64, len: usize) -> Result<()>;
+            fn add_root_meta(&mut self, file: &str, key: &str, value: &str) -> Result<()>;
+            fn add_depth(&mut self, elem: ElemId, depth: usize) -> Result<()>;
+            fn add_subtree_size(&mut self, elem: ElemId, size: usize) -> Result<()>;
+            fn add_codepoint(&mut self, elem: ElemId, codepoint: u32) -> Result<()>;
+        }
+    }
+}
+
+/// DatalogExtractorBackend impl that keeps facts in memory as Arrow record
+/// batches, with map keys stored as strings, as in
+/// [vector::StringKeyBackend].
+#[derive(Default)]
+pub struct StringKeyBackend {
+    vector_backend: vector::StringKeyBackend,
+}
+
+impl StringKeyBackend {
+    /// Intern strings of each [namespace][vector::SymbolNamespace]
+    /// separately; see [vector::StringKeyBackend::with_separate_namespaces].
+    pub fn with_separate_namespaces(mut self) -> Self {
+        self.vector_backend = self.vector_backend.with_separate_namespaces();
+        self
+    }
+
+    /// Extract byte arrays as text encoded with `policy`; see
+    /// [vector::StringKeyBackend::with_bytes_policy].
+    pub fn with_bytes_policy(mut self, policy: BytesPolicy) -> Self {
+        self.vector_backend = self.vector_backend.with_bytes_policy(policy);
+        self
+    }
+
+    /// The facts of every relation as record batches; see [record_batches].
+    pub fn record_batches(self) -> Result<Vec<(&'static str, RecordBatch)>> {
+        record_batches(&self.vector_backend.get_data())
+    }
+}
+
+impl DatalogExtractorBackend for StringKeyBackend {
+    delegate! {
+        to (&mut self.vector_backend) {
+            fn add_root_elem(&mut self, file: &str, elem: ElemId) -> Result<()>;
+            fn add_file_format(&mut self, file: &str, format: &str) -> Result<()>;
+            fn add_key_field(&mut self, struct_name: &str, field: &str) -> Result<()>;
+            fn add_elem(&mut self, elem: ElemId, elem_type: ElemType) -> Result<()>;
+            fn add_bool(&mut self, elem: ElemId, value: bool) -> Result<()>;
+            fn add_i64(&mut self, elem: ElemId, value: i64) -> Result<()>;
+            fn add_u64(&mut self, elem: ElemId, value: u64) -> Result<()>;
+            fn add_str(&mut self, elem: ElemId, value: &str) -> Result<()>;
+            fn add_bytes(&mut self, elem: ElemId, value: &[u8]) -> Result<()>;
+            fn add_full_str(&mut self, elem: ElemId, value: &str) -> Result<()>;
+            fn add_map_entry(&mut self, elem: ElemId, key: ElemId, value: ElemId) -> Result<()>;
+            fn add_struct_type(&mut self, elem: ElemId, struct_name: &str) -> Result<()>;
+            fn add_struct_entry(&mut self, elem: ElemId, key: &str, value: ElemId) -> Result<()>;
+            fn add_key(&mut self, elem: ElemId, key: ElemId) -> Result<()>;
+            fn add_seq_entry(&mut self, elem: ElemId, pos: usize, value: ElemId) -> Result<()>;
+            fn add_variant_type(&mut self, elem: ElemId, type_name: &str, variant_name: &str) -> Result<()>;
+            fn add_tuple_entry(&mut self, elem: ElemId, pos: usize, value: ElemId) -> Result<()>;
+            fn add_schema_type(&mut self, elem: ElemId, schema_ref: &str) -> Result<()>;
+            fn add_datetime(&mut self, elem: ElemId, epoch_micros: i64, tz_offset: i32) -> Result<()>;
+            fn add_uuid(&mut self, elem: ElemId, hi: u64, lo: u64) -> Result<()>;
+            fn add_ip(&mut self, elem: ElemId, address: IpAddr) -> Result<()>;
+            fn add_url(&mut self, elem: ElemId, scheme: &str, host: &str) -> Result<()>;
+            fn add_email(&mut self, elem: ElemId, local: &str, domain: &str) -> Result<()>;
+            fn add_declared_len(&mut self, elem: ElemId, len: usize) -> Result<()>;
+            fn add_absent_field(&mut self, elem: ElemId, field: &str) -> Result<()>;
+            fn add_unit(&mut self, elem: ElemId) -> Result<()>;
+            fn add_truncated(&mut self, relation: &str, quota: usize) -> Result<()>;
+            fn add_redacted(&mut self, elem: ElemId) -> Result<()>;
+            fn add_sampled(&mut self, elem: ElemId, len: usize, kept: usize) -> Result<()>;
+            fn add_elem_type_kind(&mut self, elem_type: ElemType, kind: ElemKind) -> Result<()>;
+            fn add_same_string(&mut self, elem: ElemId, other: ElemId) -> Result<()>;
+            fn add_shape(&mut self, elem: ElemId, shape: u64) -> Result<()>;
+            fn add_shape_field(&mut self, shape: u64, pos: usize, field: &str) -> Result<()>;
+            fn add_parse_error(&mut self, file: &str, line: usize, message: &str) -> Result<()>;
+            fn add_string_hash(&mut self, elem: ElemId, hash: u64, len: usize) -> Result<()>;
+            fn add_root_meta(&mut self, file: &str, key: &str, value: &str) -> Result<()>;
+            fn add_depth(&mut self, elem: ElemId, depth: usize) -> Result<()>;
+            fn add_subtree_size(&mut self, elem: ElemId, size: usize) -> Result<()>;
+            fn add_codepoint(&mut self, elem: ElemId, codepoint: u32) -> Result<()>;
+        }
+    }
+}
@@ -0,0 +1,276 @@
+//! A backend that writes facts in the textual format of
+//! [Mangle](https://github.com/google/mangle), a Datalog language from
+//! Google:
+//!
+//! ```text
+//! Decl type(Id, Type).
+//! type(1, /Map).
+//! type(2, /Str).
+//! string(2, "abc").
+//! map(1, 2, 3).
+//! ```
+//!
+//! Facts use the relations of the Souffle schema (see
+//! [souffle_sqlite][crate::backend::souffle_sqlite]), and each relation is
+//! declared, so that rules can refer to relations without facts. Names, such
+//! as element types and field names, are written as Mangle's name constants,
+//! e.g. `/Map`, unless they contain characters that names cannot have, and
+//! text from input values as strings. Booleans are the names `/true` and
+//! `/false`.
+
+use delegate::delegate;
+use std::{
+    fs::File,
+    io::{self, BufWriter, Write},
+    net::IpAddr,
+};
+
+use crate::{
+    backend::{
+        tuples::{self, MapKey, Term},
+        vector::{self, BackendData},
+        BackendError,
+    },
+    bytes_policy::BytesPolicy,
+    DatalogExtractorBackend, ElemId, ElemKind, ElemType, Result,
+};
+
+/// Write the facts of `data` in Mangle's textual format to `writer`.
+pub fn write_facts<K: MapKey, W: Write>(data: &BackendData<K>, writer: &mut W) -> io::Result<()> {
+    let relations = tuples::relations(data);
+
+    for relation in relations.iter() {
+        let vars: Vec<String> = relation
+            .columns
+            .iter()
+            .map(|column| variable(column))
+            .collect();
+        writeln!(writer, "Decl {}({}).", relation.name, vars.join(", "))?;
+    }
+
+    for relation in relations.iter() {
+        for fact in relation.facts.iter() {
+            let args: Vec<String> = fact.iter().map(term).collect();
+            writeln!(writer, "{}({}).", relation.name, args.join(", "))?;
+        }
+    }
+
+    io::Result::Ok(())
+}
+
+/// Capitalize `column` as a variable of a declaration.
+fn variable(column: &str) -> String {
+    let mut chars = column.chars();
+    match chars.next() {
+        Some(first) => first.to_ascii_uppercase().to_string() + chars.as_str(),
+        None => String::new(),
+    }
+}
+
+fn term(term: &Term) -> String {
+    match term {
+        Term::Elem(elem) => elem.to_string(),
+        Term::Number(value) => value.to_string(),
+        Term::Bool(value) => format!("/{}", value),
+        Term::Name(name) => {
+            let is_name = !name.is_empty()
+                && name
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.' | '~'));
+            if is_name {
+                format!("/{}", name)
+            } else {
+                quoted(name)
+            }
+        }
+        Term::Text(text) => quoted(text),
+        Term::Unresolved(sym) => quoted(&format!("#{}", sym)),
+    }
+}
+
+/// Quote `text` as a Mangle string, with the escapes of Go strings.
+fn quoted(text: &str) -> String {
+    let mut quoted = String::with_capacity(text.len() + 2);
+    quoted.push('"');
+    for c in text.chars() {
+        match c {
+            '\\' => quoted.push_str("\\\\"),
+            '"' => quoted.push_str("\\\""),
+            '\n' => quoted.push_str("\\n"),
+            '\t' => quoted.push_str("\\t"),
+            '\r' => quoted.push_str("\\r"),
+            c if c.is_control() => quoted.push_str(&format!("\\u{:04x}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// DatalogExtractorBackend impl that writes facts for Mangle.
+/// Map keys are elements, as in [vector::Backend].
+#[derive(Default)]
+pub struct Backend {
+    vector_backend: vector::Backend,
+}
+
+impl Backend {
+    /// Intern strings of each [namespace][vector::SymbolNamespace]
+    /// separately; see [vector::Backend::with_separate_namespaces].
+    pub fn with_separate_namespaces(mut self) -> Self {
+        self.vector_backend = self.vector_backend.with_separate_namespaces();
+        self
+    }
+
+    /// Extract byte arrays as text encoded with `policy`; see
+    /// [vector::Backend::with_bytes_policy].
+    pub fn with_bytes_policy(mut self, policy: BytesPolicy) -> Self {
+        self.vector_backend = self.vector_backend.with_bytes_policy(policy);
+        self
+    }
+
+    /// Write Mangle facts to `writer`.
+    pub fn write_to<W: Write>(self, writer: &mut W) -> Result<()> {
+        write_facts(&self.vector_backend.get_data(), writer).map_err(BackendError::Io)?;
+        Result::Ok(())
+    }
+
+    /// Write Mangle facts to the file with name `filename`, which
+    /// is overwritten if it exists.
+    pub fn dump_to_file(self, filename: &str) -> Result<()> {
+        let mut writer = BufWriter::new(File::create(filename).map_err(BackendError::Io)?);
+        self.write_to(&mut writer)?;
+        writer.flush().map_err(BackendError::Io)?;
+        Result::Ok(())
+    }
+}
+
+impl DatalogExtractorBackend for Backend {
+    delegate! {
+        to (&mut self.vector_backend) {
+            fn add_root_elem(&mut self, file: &str, elem: ElemId) -> Result<()>;
+            fn add_file_format(&mut self, file: &str, format: &str) -> Result<()>;
+            fn add_key_field(&mut self, struct_name: &str, field: &str) -> Result<()>;
+            fn add_elem(&mut self, elem: ElemId, elem_type: ElemType) -> Result<()>;
+            fn add_bool(&mut self, elem: ElemId, value: bool) -> Result<()>;
+            fn add_i64(&mut self, elem: ElemId, value: i64) -> Result<()>;
+            fn add_u64(&mut self, elem: ElemId, value: u64) -> Result<()>;
+            fn add_str(&mut self, elem: ElemId, value: &str) -> Result<()>;
+            fn add_bytes(&mut self, elem: ElemId, value: &[u8]) -> Result<()>;
+            fn add_full_str(&mut self, elem: ElemId, value: &str) -> Result<()>;
+            fn add_map_entry(&mut self, elem: ElemId, key: ElemId, value: ElemId) -> Result<()>;
+            fn add_struct_type(&mut self, elem: ElemId, struct_name: &str) -> Result<()>;
+            fn add_struct_entry(&mut self, elem: ElemId, key: &str, value: ElemId) -> Result<()>;
+            fn add_key(&mut self, elem: ElemId, key: ElemId) -> Result<()>;
+            fn add_seq_entry(&mut self, elem: ElemId, pos: usize, value: ElemId) -> Result<()>;
+            fn add_variant_type(&mut self, elem: ElemId, type_name: &str, variant_name: &str) -> Result<()>;
+            fn add_tuple_entry(&mut self, elem: ElemId, pos: usize, value: ElemId) -> Result<()>;
+            fn add_schema_type(&mut self, elem: ElemId, schema_ref: &str) -> Result<()>;
+            fn add_datetime(&mut self, elem: ElemId, epoch_micros: i64, tz_offset: i32) -> Result<()>;
+            fn add_uuid(&mut self, elem: ElemId, hi: u64, lo: u64) -> Result<()>;
+            fn add_ip(&mut self, elem: ElemId, address: IpAddr) -> Result<()>;
+            fn add_url(&mut self, elem: ElemId, scheme: &str, host: &str) -> Result<()>;
+            fn add_email(&mut self, elem: ElemId, local: &str, domain: &str) -> Result<()>;
+            fn add_declared_len(&mut self, elem: ElemId, len: usize) -> Result<()>;
+            fn add_absent_field(&mut self, elem: ElemId, field: &str) -> Result<()>;
+            fn add_unit(&mut self, elem: ElemId) -> Result<()>;
+            fn add_truncated(&mut self, relation: &str, quota: usize) -> Result<()>;
+            fn add_redacted(&mut self, elem: ElemId) -> Result<()>;
+            fn add_sampled(&mut self, elem: ElemId, len: usize, kept: usize) -> Result<()>;
+            fn add_elem_type_kind(&mut self, elem_type: ElemType, kind: ElemKind) -> Result<()>;
+            fn add_same_string(&mut self, elem: ElemId, other: ElemId) -> Result<()>;
+            fn add_shape(&mut self, elem: ElemId, shape: u64) -> Result<()>;
+            fn add_shape_field(&mut self, shape: u64, pos: usize, field: &str) -> Result<()>;
+            fn add_parse_error(&mut self, file: &str, line: usize, message: &str) -> Result<()>;
+            fn add_string_hash(&mut self, elem: ElemId, hash: u64, len: usize) -> Result<()>;
+            fn add_root_meta(&mut self, file: &str, key: &str, value: &str) -> Result<()>;
+            fn add_depth(&mut self, elem: ElemId, depth: usize) -> Result<()>;
+            fn add_subtree_size(&mut self, elem: ElemId, size: usize) -> Result<()>;
+            fn add_codepoint(&mut self, elem: ElemId, codepoint: u32) -> Result<()>;
+        }
+    }
+}
+
+/// DatalogExtractorBackend impl that writes facts for Mangle, with
+/// map keys written as strings, as in [vector::StringKeyBackend].
+#[derive(Default)]
+pub struct StringKeyBackend {
+    vector_backend: vector::StringKeyBackend,
+}
+
+impl StringKeyBackend {
+    /// Intern strings of each [namespace][vector::SymbolNamespace]
+    /// separately; see [vector::StringKeyBackend::with_separate_namespaces].
+    pub fn with_separate_namespaces(mut self) -> Self {
+        self.vector_backend = self.vector_backend.with_separate_namespaces();
+        self
+    }
+
+    /// Extract byte arrays as text encoded with `policy`; see
+    /// [vector::StringKeyBackend::with_bytes_policy].
+    pub fn with_bytes_policy(mut self, policy: BytesPolicy) -> Self {
+        self.vector_backend = self.vector_backend.with_bytes_policy(policy);
+        self
+    }
+
+    /// Write Mangle facts to `writer`.
+    pub fn write_to<W: Write>(self, writer: &mut W) -> Result<()> {
+        write_facts(&self.vector_backend.get_data(), writer).map_err(BackendError::Io)?;
+        Result::Ok(())
+    }
+
+    /// Write Mangle facts to the file with name `filename`, which
+    /// is overwritten if it exists.
+    pub fn dump_to_file(self, filename: &str) -> Result<()> {
+        let mut writer = BufWriter::new(File::create(filename).map_err(BackendError::Io)?);
+        self.write_to(&mut writer)?;
+        writer.flush().map_err(BackendError::Io)?;
+        Result::Ok(())
+    }
+}
+
+impl DatalogExtractorBackend for StringKeyBackend {
+    delegate! {
+        to (&mut self.vector_backend) {
+            fn add_root_elem(&mut self, file: &str, elem: ElemId) -> Result<()>;
+            fn add_file_format(&mut self, file: &str, format: &str) -> Result<()>;
+            fn add_key_field(&mut self, struct_name: &str, field: &str) -> Result<()>;
+            fn add_elem(&mut self, elem: ElemId, elem_type: ElemType) -> Result<()>;
+            fn add_bool(&mut self, elem: ElemId, value: bool) -> Result<()>;
+            fn add_i64(&mut self, elem: ElemId, value: i64) -> Result<()>;
+            fn add_u64(&mut self, elem: ElemId, value: u64) -> Result<()>;
+            fn add_str(&mut self, elem: ElemId, value: &str) -> Result<()>;
+            fn add_bytes(&mut self, elem: ElemId, value: &[u8]) -> Result<()>;
+            fn add_full_str(&mut self, elem: ElemId, value: &str) -> Result<()>;
+            fn add_map_entry(&mut self, elem: ElemId, key: ElemId, value: ElemId) -> Result<()>;
+            fn add_struct_type(&mut self, elem: ElemId, struct_name: &str) -> Result<()>;
+            fn add_struct_entry(&mut self, elem: ElemId, key: &str, value: ElemId) -> Result<()>;
+            fn add_key(&mut self, elem: ElemId, key: ElemId) -> Result<()>;
+            fn add_seq_entry(&mut self, elem: ElemId, pos: usize, value: ElemId) -> Result<()>;
+            fn add_variant_type(&mut self, elem: ElemId, type_name: &str, variant_name: &str) -> Result<()>;
+            fn add_tuple_entry(&mut self, elem: ElemId, pos: usize, value: ElemId) -> Result<()>;
+            fn add_schema_type(&mut self, elem: ElemId, schema_ref: &str) -> Result<()>;
+            fn add_datetime(&mut self, elem: ElemId, epoch_micros: i64, tz_offset: i32) -> Result<()>;
+            fn add_uuid(&mut self, elem: ElemId, hi: u64, lo: u64) -> Result<()>;
+            fn add_ip(&mut self, elem: ElemId, address: IpAddr) -> Result<()>;
+            fn add_url(&mut self, elem: ElemId, scheme: &str, host: &str) -> Result<()>;
+            fn add_email(&mut self, elem: ElemId, local: &str, domain: &str) -> Result<()>;
+            fn add_declared_len(&mut self, elem: ElemId, len: usize) -> Result<()>;
+            fn add_absent_field(&mut self, elem: ElemId, field: &str) -> Result<()>;
+            fn add_unit(&mut self, elem: ElemId) -> Result<()>;
+            fn add_truncated(&mut self, relation: &str, quota: usize) -> Result<()>;
+            fn add_redacted(&mut self, elem: ElemId) -> Result<()>;
+            fn add_sampled(&mut self, elem: ElemId, len: usize, kept: usize) -> Result<()>;
+            fn add_elem_type_kind(&mut self, elem_type: ElemType, kind: ElemKind) -> Result<()>;
+            fn add_same_string(&mut self, elem: ElemId, other: ElemId) -> Result<()>;
+            fn add_shape(&mut self, elem: ElemId, shape: u64) -> Result<()>;
+            fn add_shape_field(&mut self, shape: u64, pos: usize, field: &str) -> Result<()>;
+            fn add_parse_error(&mut self, file: &str, line: usize, message: &str) -> Result<()>;
+            fn add_string_hash(&mut self, elem: ElemId, hash: u64, len: usize) -> Result<()>;
+            fn add_root_meta(&mut self, file: &str, key: &str, value: &str) -> Result<()>;
+            fn add_depth(&mut self, elem: ElemId, depth: usize) -> Result<()>;
+            fn add_subtree_size(&mut self, elem: ElemId, size: usize) -> Result<()>;
+            fn add_codepoint(&mut self, elem: ElemId, codepoint: u32) -> Result<()>;
+        }
+    }
+}
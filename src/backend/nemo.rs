@@ -0,0 +1,355 @@
+//! A backend that writes facts for the [Nemo](https://github.com/knowsys/nemo)
+//! rule engine: a CSV file for each relation, and a ruleset that imports
+//! them, which Nemo programs can start from:
+//!
+//! ```text
+//! @import type :- csv{resource="type.csv", format=(int, string)}.
+//! @import string :- csv{resource="string.csv", format=(int, string)}.
+//! ```
+//!
+//! Facts use the relations of the Souffle schema (see
+//! [souffle_sqlite][crate::backend::souffle_sqlite]). Element identifiers and
+//! numbers are imported as integers, and all other values, including names
+//! such as element types and booleans (`true` and `false`), as strings.
+//! Relations without facts have empty CSV files, whose columns are imported
+//! with format `any`.
+//!
+//! With [inline scalars][Backend::with_inline_scalars], facts use the
+//! relations of the [inline schema][tuples::inline_scalars] instead.
+//!
+//! [read_dir] reads the facts of such directories back, e.g. to store them
+//! in a database without extracting them again.
+
+use delegate::delegate;
+use std::{
+    fs::{self, File},
+    io::{self, BufWriter, Write},
+    net::IpAddr,
+    path::Path,
+};
+
+use crate::{
+    backend::{
+        tuples::{self, MapKey, Relation, Term},
+        vector::{self, BackendData},
+        BackendError,
+    },
+    bytes_policy::BytesPolicy,
+    DatalogExtractorBackend, ElemId, ElemKind, ElemType, Result,
+};
+
+/// Name of the ruleset written by [write_dir].
+pub const RULESET_FILE: &str = "facts.rls";
+
+/// Write the facts of `data` to the directory `dir`, which is created if it
+/// does not exist: a file `{relation}.csv` for each relation, and a ruleset
+/// named [RULESET_FILE] with an `@import` directive for each file. Existing
+/// files are overwritten.
+pub fn write_dir<K: MapKey>(data: &BackendData<K>, dir: &Path) -> io::Result<()> {
+    write_relations(tuples::relations(data), dir)
+}
+
+/// Write the facts of `data` to the directory `dir` like [write_dir], with
+/// scalar values inlined in the facts of their parents; see
+/// [inline_scalars][tuples::inline_scalars]. [read_dir] cannot read such
+/// directories back.
+pub fn write_inline_dir<K: MapKey>(data: &BackendData<K>, dir: &Path) -> io::Result<()> {
+    write_relations(tuples::inline_scalars(tuples::relations(data)), dir)
+}
+
+fn write_relations(relations: Vec<Relation>, dir: &Path) -> io::Result<()> {
+    fs::create_dir_all(dir)?;
+
+    let mut ruleset = BufWriter::new(File::create(dir.join(RULESET_FILE))?);
+    writeln!(
+        ruleset,
+        "% Nemo imports of facts generated by serde_datalog\n"
+    )?;
+
+    for relation in relations {
+        let file = format!("{}.csv", relation.name);
+        let mut csv = BufWriter::new(File::create(dir.join(&file))?);
+        for fact in relation.facts.iter() {
+            let fields: Vec<String> = fact.iter().map(field).collect();
+            writeln!(csv, "{}", fields.join(","))?;
+        }
+        csv.flush()?;
+
+        let formats: Vec<&str> = match relation.facts.first() {
+            Some(fact) => fact.iter().map(format).collect(),
+            None => vec!["any"; relation.arity()],
+        };
+        writeln!(
+            ruleset,
+            "@import {} :- csv{{resource=\"{}\", format=({})}}.",
+            relation.name,
+            file,
+            formats.join(", ")
+        )?;
+    }
+
+    ruleset.flush()
+}
+
+/// Read the facts of the directory `dir` written by [write_dir] for a
+/// [Backend] into the data of a vector backend; see
+/// [data_from_text][tuples::data_from_text]. Every file `{relation}.csv` of
+/// the directory is read, and other files are ignored.
+pub fn read_dir(dir: &Path) -> Result<BackendData<ElemId>> {
+    let mut facts = Vec::new();
+    for entry in fs::read_dir(dir).map_err(BackendError::Io)? {
+        let path = entry.map_err(BackendError::Io)?.path();
+        if path.extension().is_none_or(|ext| ext != "csv") {
+            continue;
+        }
+
+        let relation = path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let csv = fs::read_to_string(&path).map_err(BackendError::Io)?;
+        for record in records(&csv) {
+            facts.push((relation.clone(), record));
+        }
+    }
+
+    tuples::data_from_text(facts)
+}
+
+/// The records of CSV text, with fields unquoted as in RFC 4180.
+fn records(csv: &str) -> Vec<Vec<String>> {
+    let mut records = Vec::new();
+    let mut record = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = csv.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                chars.next();
+                field.push('"');
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => record.push(std::mem::take(&mut field)),
+            '\r' if !quoted && chars.peek() == Some(&'\n') => {}
+            '\n' if !quoted => {
+                record.push(std::mem::take(&mut field));
+                records.push(std::mem::take(&mut record));
+            }
+            c => field.push(c),
+        }
+    }
+
+    if !field.is_empty() || !record.is_empty() {
+        record.push(field);
+        records.push(record);
+    }
+
+    records
+}
+
+/// Nemo format of the column of `term`.
+fn format(term: &Term) -> &'static str {
+    match term {
+        Term::Elem(_) | Term::Number(_) => "int",
+        _ => "string",
+    }
+}
+
+/// Write `term` as a CSV field, quoting it as in RFC 4180 if needed.
+fn field(term: &Term) -> String {
+    let text = match term {
+        Term::Elem(elem) => return elem.to_string(),
+        Term::Number(value) => return value.to_string(),
+        Term::Bool(value) => return value.to_string(),
+        Term::Name(text) | Term::Text(text) => text.to_string(),
+        Term::Unresolved(sym) => format!("#{}", sym),
+    };
+
+    if text.contains([',', '"', '\n', '\r']) || text.trim() != text {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text
+    }
+}
+
+/// DatalogExtractorBackend impl that writes facts for Nemo.
+/// Map keys are elements, as in [vector::Backend].
+#[derive(Default)]
+pub struct Backend {
+    vector_backend: vector::Backend,
+    inline_scalars: bool,
+}
+
+impl Backend {
+    /// Intern strings of each [namespace][vector::SymbolNamespace]
+    /// separately; see [vector::Backend::with_separate_namespaces].
+    pub fn with_separate_namespaces(mut self) -> Self {
+        self.vector_backend = self.vector_backend.with_separate_namespaces();
+        self
+    }
+
+    /// Extract byte arrays as text encoded with `policy`; see
+    /// [vector::Backend::with_bytes_policy].
+    pub fn with_bytes_policy(mut self, policy: BytesPolicy) -> Self {
+        self.vector_backend = self.vector_backend.with_bytes_policy(policy);
+        self
+    }
+
+    /// Inline scalar values in the facts of their parents; see
+    /// [write_inline_dir].
+    pub fn with_inline_scalars(mut self) -> Self {
+        self.inline_scalars = true;
+        self
+    }
+
+    /// Write a CSV file for each relation, and a ruleset importing them, to
+    /// the directory `dir`, which is created if it does not exist; see
+    /// [write_dir].
+    pub fn dump_to_dir(self, dir: impl AsRef<Path>) -> Result<()> {
+        let data = self.vector_backend.get_data();
+        if self.inline_scalars {
+            write_inline_dir(&data, dir.as_ref()).map_err(BackendError::Io)?;
+        } else {
+            write_dir(&data, dir.as_ref()).map_err(BackendError::Io)?;
+        }
+        Result::Ok(())
+    }
+}
+
+impl DatalogExtractorBackend for Backend {
+    delegate! {
+        to (&mut self.vector_backend) {
+            fn add_root_elem(&mut self, file: &str, elem: ElemId) -> Result<()>;
+            fn add_file_format(&mut self, file: &str, format: &str) -> Result<()>;
+            fn add_key_field(&mut self, struct_name: &str, field: &str) -> Result<()>;
+            fn add_elem(&mut self, elem: ElemId, elem_type: ElemType) -> Result<()>;
+            fn add_bool(&mut self, elem: ElemId, value: bool) -> Result<()>;
+            fn add_i64(&mut self, elem: ElemId, value: i64) -> Result<()>;
+            fn add_u64(&mut self, elem: ElemId, value: u64) -> Result<()>;
+            fn add_str(&mut self, elem: ElemId, value: &str) -> Result<()>;
+            fn add_bytes(&mut self, elem: ElemId, value: &[u8]) -> Result<()>;
+            fn add_full_str(&mut self, elem: ElemId, value: &str) -> Result<()>;
+            fn add_map_entry(&mut self, elem: ElemId, key: ElemId, value: ElemId) -> Result<()>;
+            fn add_struct_type(&mut self, elem: ElemId, struct_name: &str) -> Result<()>;
+            fn add_struct_entry(&mut self, elem: ElemId, key: &str, value: ElemId) -> Result<()>;
+            fn add_key(&mut self, elem: ElemId, key: ElemId) -> Result<()>;
+            fn add_seq_entry(&mut self, elem: ElemId, pos: usize, value: ElemId) -> Result<()>;
+            fn add_variant_type(&mut self, elem: ElemId, type_name: &str, variant_name: &str) -> Result<()>;
+            fn add_tuple_entry(&mut self, elem: ElemId, pos: usize, value: ElemId) -> Result<()>;
+            fn add_schema_type(&mut self, elem: ElemId, schema_ref: &str) -> Result<()>;
+            fn add_datetime(&mut self, elem: ElemId, epoch_micros: i64, tz_offset: i32) -> Result<()>;
+            fn add_uuid(&mut self, elem: ElemId, hi: u64, lo: u64) -> Result<()>;
+            fn add_ip(&mut self, elem: ElemId, address: IpAddr) -> Result<()>;
+            fn add_url(&mut self, elem: ElemId, scheme: &str, host: &str) -> Result<()>;
+            fn add_email(&mut self, elem: ElemId, local: &str, domain: &str) -> Result<()>;
+            fn add_declared_len(&mut self, elem: ElemId, len: usize) -> Result<()>;
+            fn add_absent_field(&mut self, elem: ElemId, field: &str) -> Result<()>;
+            fn add_unit(&mut self, elem: ElemId) -> Result<()>;
+            fn add_truncated(&mut self, relation: &str, quota: usize) -> Result<()>;
+            fn add_redacted(&mut self, elem: ElemId) -> Result<()>;
+            fn add_sampled(&mut self, elem: ElemId, len: usize, kept: usize) -> Result<()>;
+            fn add_elem_type_kind(&mut self, elem_type: ElemType, kind: ElemKind) -> Result<()>;
+            fn add_same_string(&mut self, elem: ElemId, other: ElemId) -> Result<()>;
+            fn add_shape(&mut self, elem: ElemId, shape: u64) -> Result<()>;
+            fn add_shape_field(&mut self, shape: u64, pos: usize, field: &str) -> Result<()>;
+            fn add_parse_error(&mut self, file: &str, line: usize, message: &str) -> Result<()>;
+            fn add_string_hash(&mut self, elem: ElemId, hash: u64, len: usize) -> Result<()>;
+            fn add_root_meta(&mut self, file: &str, key: &str, value: &str) -> Result<()>;
+            fn add_depth(&mut self, elem: ElemId, depth: usize) -> Result<()>;
+            fn add_subtree_size(&mut self, elem: ElemId, size: usize) -> Result<()>;
+            fn add_codepoint(&mut self, elem: ElemId, codepoint: u32) -> Result<()>;
+        }
+    }
+}
+
+/// DatalogExtractorBackend impl that writes facts for Nemo, with
+/// map keys written as strings, as in [vector::StringKeyBackend].
+#[derive(Default)]
+pub struct StringKeyBackend {
+    vector_backend: vector::StringKeyBackend,
+    inline_scalars: bool,
+}
+
+impl StringKeyBackend {
+    /// Intern strings of each [namespace][vector::SymbolNamespace]
+    /// separately; see [vector::StringKeyBackend::with_separate_namespaces].
+    pub fn with_separate_namespaces(mut self) -> Self {
+        self.vector_backend = self.vector_backend.with_separate_namespaces();
+        self
+    }
+
+    /// Extract byte arrays as text encoded with `policy`; see
+    /// [vector::StringKeyBackend::with_bytes_policy].
+    pub fn with_bytes_policy(mut self, policy: BytesPolicy) -> Self {
+        self.vector_backend = self.vector_backend.with_bytes_policy(policy);
+        self
+    }
+
+    /// Inline scalar values in the facts of their parents; see
+    /// [write_inline_dir].
+    pub fn with_inline_scalars(mut self) -> Self {
+        self.inline_scalars = true;
+        self
+    }
+
+    /// Write a CSV file for each relation, and a ruleset importing them, to
+    /// the directory `dir`, which is created if it does not exist; see
+    /// [write_dir].
+    pub fn dump_to_dir(self, dir: impl AsRef<Path>) -> Result<()> {
+        let data = self.vector_backend.get_data();
+        if self.inline_scalars {
+            write_inline_dir(&data, dir.as_ref()).map_err(BackendError::Io)?;
+        } else {
+            write_dir(&data, dir.as_ref()).map_err(BackendError::Io)?;
+        }
+        Result::Ok(())
+    }
+}
+
+impl DatalogExtractorBackend for StringKeyBackend {
+    delegate! {
+        to (&mut self.vector_backend) {
+            fn add_root_elem(&mut self, file: &str, elem: ElemId) -> Result<()>;
+            fn add_file_format(&mut self, file: &str, format: &str) -> Result<()>;
+            fn add_key_field(&mut self, struct_name: &str, field: &str) -> Result<()>;
+            fn add_elem(&mut self, elem: ElemId, elem_type: ElemType) -> Result<()>;
+            fn add_bool(&mut self, elem: ElemId, value: bool) -> Result<()>;
+            fn add_i64(&mut self, elem: ElemId, value: i64) -> Result<()>;
+            fn add_u64(&mut self, elem: ElemId, value: u64) -> Result<()>;
+            fn add_str(&mut self, elem: ElemId, value: &str) -> Result<()>;
+            fn add_bytes(&mut self, elem: ElemId, value: &[u8]) -> Result<()>;
+            fn add_full_str(&mut self, elem: ElemId, value: &str) -> Result<()>;
+            fn add_map_entry(&mut self, elem: ElemId, key: ElemId, value: ElemId) -> Result<()>;
+            fn add_struct_type(&mut self, elem: ElemId, struct_name: &str) -> Result<()>;
+            fn add_struct_entry(&mut self, elem: ElemId, key: &str, value: ElemId) -> Result<()>;
+            fn add_key(&mut self, elem: ElemId, key: ElemId) -> Result<()>;
+            fn add_seq_entry(&mut self, elem: ElemId, pos: usize, value: ElemId) -> Result<()>;
+            fn add_variant_type(&mut self, elem: ElemId, type_name: &str, variant_name: &str) -> Result<()>;
+            fn add_tuple_entry(&mut self, elem: ElemId, pos: usize, value: ElemId) -> Result<()>;
+            fn add_schema_type(&mut self, elem: ElemId, schema_ref: &str) -> Result<()>;
+            fn add_datetime(&mut self, elem: ElemId, epoch_micros: i64, tz_offset: i32) -> Result<()>;
+            fn add_uuid(&mut self, elem: ElemId, hi: u64, lo: u64) -> Result<()>;
+            fn add_ip(&mut self, elem: ElemId, address: IpAddr) -> Result<()>;
+            fn add_url(&mut self, elem: ElemId, scheme: &str, host: &str) -> Result<()>;
+            fn add_email(&mut self, elem: ElemId, local: &str, domain: &str) -> Result<()>;
+            fn add_declared_len(&mut self, elem: ElemId, len: usize) -> Result<()>;
+            fn add_absent_field(&mut self, elem: ElemId, field: &str) -> Result<()>;
+            fn add_unit(&mut self, elem: ElemId) -> Result<()>;
+            fn add_truncated(&mut self, relation: &str, quota: usize) -> Result<()>;
+            fn add_redacted(&mut self, elem: ElemId) -> Result<()>;
+            fn add_sampled(&mut self, elem: ElemId, len: usize, kept: usize) -> Result<()>;
+            fn add_elem_type_kind(&mut self, elem_type: ElemType, kind: ElemKind) -> Result<()>;
+            fn add_same_string(&mut self, elem: ElemId, other: ElemId) -> Result<()>;
+            fn add_shape(&mut self, elem: ElemId, shape: u64) -> Result<()>;
+            fn add_shape_field(&mut self, shape: u64, pos: usize, field: &str) -> Result<()>;
+            fn add_parse_error(&mut self, file: &str, line: usize, message: &str) -> Result<()>;
+            fn add_string_hash(&mut self, elem: ElemId, hash: u64, len: usize) -> Result<()>;
+            fn add_root_meta(&mut self, file: &str, key: &str, value: &str) -> Result<()>;
+            fn add_depth(&mut self, elem: ElemId, depth: usize) -> Result<()>;
+            fn add_subtree_size(&mut self, elem: ElemId, size: usize) -> Result<()>;
+            fn add_codepoint(&mut self, elem: ElemId, codepoint: u32) -> Result<()>;
+        }
+    }
+}
@@ -0,0 +1,235 @@
+//! A backend that records the exact sequence of calls it receives, so that
+//! the calls can be replayed into any other backend later:
+//!
+//! ```ignore
+//! let mut extractor = DatalogExtractor::new(backend::record::Backend::default());
+//! value.serialize(&mut extractor)?;
+//! let calls = extractor.get_backend().into_calls();
+//!
+//! let mut sqlite = backend::souffle_sqlite::Backend::default();
+//! backend::record::replay(&calls, &mut sqlite)?;
+//! ```
+//!
+//! Recorded calls implement `Serialize` and `Deserialize`. With the `json`
+//! feature, [write_trace] and [read_trace] store them as JSON lines, so that
+//! traces captured from real-world inputs can be shared and replayed while
+//! developing backends, or extracted once and loaded into databases later.
+
+use serde::{Deserialize, Serialize};
+use std::net::IpAddr;
+
+use crate::{DatalogExtractorBackend, ElemId, ElemKind, ElemType, Fact, Result};
+
+/// A call received by a backend.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Call {
+    /// [begin][DatalogExtractorBackend::begin], before the facts of a root
+    /// value.
+    Begin,
+
+    /// [finish][DatalogExtractorBackend::finish], after the facts of a root
+    /// value.
+    Finish,
+
+    /// A call of the method that materializes the fact.
+    Fact(Fact),
+
+    /// [add_facts][DatalogExtractorBackend::add_facts], with a batch of
+    /// facts.
+    Batch(Vec<Fact>),
+}
+
+/// Make the calls of `calls` to `backend`, in order.
+pub fn replay<B: DatalogExtractorBackend + ?Sized>(calls: &[Call], backend: &mut B) -> Result<()> {
+    for call in calls {
+        match call {
+            Call::Begin => backend.begin()?,
+            Call::Finish => backend.finish()?,
+            Call::Fact(fact) => fact.add_to(backend)?,
+            Call::Batch(batch) => backend.add_facts(batch)?,
+        }
+    }
+
+    Result::Ok(())
+}
+
+/// Write `calls` to `writer` as JSON lines, one call per line.
+#[cfg(feature = "json")]
+pub fn write_trace<W: std::io::Write>(calls: &[Call], mut writer: W) -> std::io::Result<()> {
+    for call in calls {
+        serde_json::to_writer(&mut writer, call)?;
+        writer.write_all(b"\n")?;
+    }
+
+    writer.flush()
+}
+
+/// Read calls written by [write_trace] from `reader`.
+#[cfg(feature = "json")]
+pub fn read_trace<R: std::io::BufRead>(reader: R) -> std::io::Result<Vec<Call>> {
+    let mut calls = Vec::new();
+    for line in reader.lines() {
+        let line = line?;
+        if !line.trim().is_empty() {
+            calls.push(serde_json::from_str(&line)?);
+        }
+    }
+
+    std::io::Result::Ok(calls)
+}
+
+/// Backend that records the calls it receives, without materializing facts.
+#[derive(Clone, Debug, Default)]
+pub struct Backend {
+    calls: Vec<Call>,
+}
+
+impl Backend {
+    /// Return the calls recorded so far, in the order they were received.
+    pub fn calls(&self) -> &[Call] {
+        &self.calls
+    }
+
+    /// Return the recorded calls.
+    pub fn into_calls(self) -> Vec<Call> {
+        self.calls
+    }
+
+    /// Iterate over the recorded facts, including the facts of batches.
+    pub fn facts(&self) -> impl Iterator<Item = &Fact> {
+        self.calls.iter().flat_map(|call| match call {
+            Call::Fact(fact) => std::slice::from_ref(fact),
+            Call::Batch(batch) => batch.as_slice(),
+            Call::Begin | Call::Finish => &[],
+        })
+    }
+}
+
+macro_rules! record_calls {
+    ($($method:ident($($arg:ident: $ty:ty),*) => $fact:expr;)*) => {
+        $(
+            fn $method(&mut self, $($arg: $ty),*) -> Result<()> {
+                self.calls.push(Call::Fact($fact));
+                Result::Ok(())
+            }
+        )*
+    };
+}
+
+impl DatalogExtractorBackend for Backend {
+    fn begin(&mut self) -> Result<()> {
+        self.calls.push(Call::Begin);
+        Result::Ok(())
+    }
+
+    fn finish(&mut self) -> Result<()> {
+        self.calls.push(Call::Finish);
+        Result::Ok(())
+    }
+
+    fn add_facts(&mut self, batch: &[Fact]) -> Result<()> {
+        self.calls.push(Call::Batch(batch.to_vec()));
+        Result::Ok(())
+    }
+
+    record_calls! {
+        add_root_elem(file: &str, elem: ElemId) => Fact::RootElem { file: file.to_string(), elem };
+        add_file_format(file: &str, format: &str) => Fact::FileFormat {
+            file: file.to_string(),
+            format: format.to_string(),
+        };
+        add_key_field(struct_name: &str, field: &str) => Fact::KeyField {
+            struct_name: struct_name.to_string(),
+            field: field.to_string(),
+        };
+        add_elem(elem: ElemId, elem_type: ElemType) => Fact::Elem { elem, elem_type };
+        add_bool(elem: ElemId, value: bool) => Fact::Bool { elem, value };
+        add_i8(elem: ElemId, value: i8) => Fact::I8 { elem, value };
+        add_i16(elem: ElemId, value: i16) => Fact::I16 { elem, value };
+        add_i32(elem: ElemId, value: i32) => Fact::I32 { elem, value };
+        add_i64(elem: ElemId, value: i64) => Fact::I64 { elem, value };
+        add_u8(elem: ElemId, value: u8) => Fact::U8 { elem, value };
+        add_u16(elem: ElemId, value: u16) => Fact::U16 { elem, value };
+        add_u32(elem: ElemId, value: u32) => Fact::U32 { elem, value };
+        add_u64(elem: ElemId, value: u64) => Fact::U64 { elem, value };
+        add_f32(elem: ElemId, value: f32) => Fact::F32 { elem, value };
+        add_f64(elem: ElemId, value: f64) => Fact::F64 { elem, value };
+        add_char(elem: ElemId, value: char) => Fact::Char { elem, value };
+        add_str(elem: ElemId, value: &str) => Fact::Str { elem, value: value.to_string() };
+        add_full_str(elem: ElemId, value: &str) => Fact::FullStr { elem, value: value.to_string() };
+        add_bytes(elem: ElemId, value: &[u8]) => Fact::Bytes { elem, value: value.to_vec() };
+        add_map_entry(elem: ElemId, key: ElemId, value: ElemId) => Fact::MapEntry { elem, key, value };
+        add_struct_type(elem: ElemId, struct_name: &str) => Fact::StructType {
+            elem,
+            struct_name: struct_name.to_string(),
+        };
+        add_struct_entry(elem: ElemId, key: &str, value: ElemId) => Fact::StructEntry {
+            elem,
+            key: key.to_string(),
+            value,
+        };
+        add_key(elem: ElemId, key: ElemId) => Fact::Key { elem, key };
+        add_seq_entry(elem: ElemId, pos: usize, value: ElemId) => Fact::SeqEntry { elem, pos, value };
+        add_variant_type(elem: ElemId, type_name: &str, variant_name: &str) => Fact::VariantType {
+            elem,
+            type_name: type_name.to_string(),
+            variant_name: variant_name.to_string(),
+        };
+        add_tuple_entry(elem: ElemId, pos: usize, value: ElemId) => Fact::TupleEntry { elem, pos, value };
+        add_schema_type(elem: ElemId, schema_ref: &str) => Fact::SchemaType {
+            elem,
+            schema_ref: schema_ref.to_string(),
+        };
+        add_datetime(elem: ElemId, epoch_micros: i64, tz_offset: i32) => Fact::Datetime {
+            elem,
+            epoch_micros,
+            tz_offset,
+        };
+        add_uuid(elem: ElemId, hi: u64, lo: u64) => Fact::Uuid { elem, hi, lo };
+        add_ip(elem: ElemId, address: IpAddr) => Fact::Ip { elem, address };
+        add_url(elem: ElemId, scheme: &str, host: &str) => Fact::Url {
+            elem,
+            scheme: scheme.to_string(),
+            host: host.to_string(),
+        };
+        add_email(elem: ElemId, local: &str, domain: &str) => Fact::Email {
+            elem,
+            local: local.to_string(),
+            domain: domain.to_string(),
+        };
+        add_declared_len(elem: ElemId, len: usize) => Fact::DeclaredLen { elem, len };
+        add_absent_field(elem: ElemId, field: &str) => Fact::AbsentField {
+            elem,
+            field: field.to_string(),
+        };
+        add_unit(elem: ElemId) => Fact::Unit { elem };
+        add_truncated(relation: &str, quota: usize) => Fact::Truncated {
+            relation: relation.to_string(),
+            quota,
+        };
+        add_redacted(elem: ElemId) => Fact::Redacted { elem };
+        add_sampled(elem: ElemId, len: usize, kept: usize) => Fact::Sampled { elem, len, kept };
+        add_elem_type_kind(elem_type: ElemType, kind: ElemKind) => Fact::ElemTypeKind { elem_type, kind };
+        add_same_string(elem: ElemId, other: ElemId) => Fact::SameString { elem, other };
+        add_shape(elem: ElemId, shape: u64) => Fact::Shape { elem, shape };
+        add_shape_field(shape: u64, pos: usize, field: &str) => Fact::ShapeField {
+            shape,
+            pos,
+            field: field.to_string(),
+        };
+        add_parse_error(file: &str, line: usize, message: &str) => Fact::ParseError {
+            file: file.to_string(),
+            line,
+            message: message.to_string(),
+        };
+        add_string_hash(elem: ElemId, hash: u64, len: usize) => Fact::StringHash { elem, hash, len };
+        add_root_meta(file: &str, key: &str, value: &str) => Fact::RootMeta {
+            file: file.to_string(),
+            key: key.to_string(),
+            value: value.to_string(),
+        };
+        add_depth(elem: ElemId, depth: usize) => Fact::Depth { elem, depth };
+        add_subtree_size(elem: ElemId, size: usize) => Fact::SubtreeSize { elem, size };
+        add_codepoint(elem: ElemId, codepoint: u32) => Fact::Codepoint { elem, codepoint };
+    }
+}
@@ -2,244 +2,2717 @@
 //! in the format expected by [Souffle](https://souffle-lang.github.io/).
 
 use delegate::delegate;
-use std::{fmt::Display, hash::Hash};
+use rusqlite::OptionalExtension;
+use std::{fmt::Display, hash::Hash, net::IpAddr};
 
 use crate::{
-    backend::vector::{self, BackendData},
-    DatalogExtractorBackend, ElemId, ElemType, Result,
+    backend::{
+        vector::{self, BackendData, StringReport, SymbolId},
+        BackendError,
+    },
+    bytes_policy::BytesPolicy,
+    DatalogExtractionError, DatalogExtractorBackend, ElemId, ElemKind, ElemType, Result,
 };
 
 pub trait AbstractBackend: DatalogExtractorBackend {
     /// Print generated table facts to stdout.
     fn dump(self);
 
+    /// Report the `top_n` most frequently referenced interned strings.
+    fn string_report(&self, top_n: usize) -> StringReport;
+
+    /// Souffle declarations of the relations stored by this backend, along
+    /// with directives that read them from the database `dbname`; see
+    /// [input_directives].
+    fn input_directives(&self, dbname: &str) -> String;
+
+    /// Store facts in the database of `conn`, which can be an in-memory
+    /// database or a database owned by the caller. The tables of the schema
+    /// must not already exist in the database.
+    fn dump_into(self, conn: &rusqlite::Connection) -> Result<()>;
+
+    /// Prepare the backend to append facts to the existing database of `conn`,
+    /// which must have been generated by the same kind of backend.
+    /// The backend reuses the symbol table of the database.
+    ///
+    /// Returns the first element identifier not used by the database;
+    /// extraction should continue numbering elements from this identifier
+    /// (see [DatalogExtractor::new_with_first_elem_id][crate::DatalogExtractor::new_with_first_elem_id]).
+    fn load_from(&mut self, conn: &rusqlite::Connection) -> Result<ElemId>;
+
+    /// Append facts to the existing database of `conn`.
+    /// The backend must have been prepared with [load_from][Self::load_from].
+    ///
+    /// If `replace` is set, facts about files that were extracted again are
+    /// removed from the database before new facts are inserted. This removes
+    /// root elements with the same name as the new root elements, root
+    /// elements of documents within re-extracted files (named `file:line`),
+    /// and all elements reachable from these root elements. Symbols that no
+    /// fact refers to once the new facts are inserted are removed as well.
+    fn append_into(self, conn: &rusqlite::Connection, replace: bool) -> Result<()>;
+
     /// Store facts in a SQLite file with name `filename`.
-    fn dump_to_db(self, filename: &str) -> rusqlite::Result<()>;
+    /// Use the name `":memory:"` to store facts in an in-memory database;
+    /// note that the database is discarded once facts are stored.
+    fn dump_to_db(self, filename: &str) -> Result<()>
+    where
+        Self: Sized,
+    {
+        self.dump_into(&rusqlite::Connection::open(filename)?)
+    }
+
+    /// Like [load_from][Self::load_from], but for the existing SQLite file
+    /// with name `filename`.
+    fn load_db(&mut self, filename: &str) -> Result<ElemId> {
+        self.load_from(&rusqlite::Connection::open(filename)?)
+    }
+
+    /// Like [append_into][Self::append_into], but for the existing SQLite file
+    /// with name `filename`.
+    fn append_to_db(self, filename: &str, replace: bool) -> Result<()>
+    where
+        Self: Sized,
+    {
+        self.append_into(&rusqlite::Connection::open(filename)?, replace)
+    }
+}
+
+/// Names of the tables and views created by the Souffle SQLite backends.
+///
+/// Facts are stored in tables named `{table_prefix}{relation}`, e.g. `_type`,
+/// and interned strings are stored in a table named
+/// `{table_prefix}_SymbolTable`. Views named `{view_prefix}{relation}`,
+/// e.g. `type`, resolve interned strings and are the relations read by
+/// Souffle programs. Prefixes should only contain characters that are valid
+/// in unquoted SQL identifiers.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SchemaConfig {
+    /// Prefix of table names. Defaults to `_`.
+    pub table_prefix: String,
+
+    /// Prefix of view names. Defaults to the empty string.
+    pub view_prefix: String,
+
+    /// Whether to create views. Defaults to true.
+    pub create_views: bool,
+}
+
+impl Default for SchemaConfig {
+    fn default() -> Self {
+        SchemaConfig {
+            table_prefix: "_".to_string(),
+            view_prefix: String::new(),
+            create_views: true,
+        }
+    }
+}
+
+impl SchemaConfig {
+    /// Name of the table storing facts of `relation`.
+    pub fn table_name(&self, relation: &str) -> String {
+        format!("{}{}", self.table_prefix, relation)
+    }
+
+    /// Name of the view over the facts of `relation`.
+    pub fn view_name(&self, relation: &str) -> String {
+        format!("{}{}", self.view_prefix, relation)
+    }
+
+    /// Name of the table storing interned strings.
+    pub fn symbol_table_name(&self) -> String {
+        self.table_name("_SymbolTable")
+    }
+
+    /// Substitute the table prefix for `{t}` and the view prefix for `{v}`
+    /// in `sql`.
+    fn render(&self, sql: &str) -> String {
+        sql.replace("{t}", &self.table_prefix)
+            .replace("{v}", &self.view_prefix)
+    }
+}
+
+/// Options that control how the Souffle SQLite backends load facts into a
+/// database. Build options by chaining methods on [LoadOptions::default]:
+///
+/// ```
+/// # use serde_datalog::backend::souffle_sqlite::LoadOptions;
+/// let options = LoadOptions::default().fast_journal(true).create_indexes(true);
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LoadOptions {
+    single_transaction: bool,
+    fast_journal: bool,
+    create_indexes: bool,
+    compress_strings: Option<usize>,
+    statistics: bool,
+    analyze: bool,
+    number_domain: Option<(u32, WideNumbers)>,
+    provenance: bool,
+}
+
+impl Default for LoadOptions {
+    fn default() -> Self {
+        LoadOptions {
+            single_transaction: true,
+            fast_journal: false,
+            create_indexes: false,
+            compress_strings: None,
+            statistics: false,
+            analyze: false,
+            number_domain: None,
+            provenance: false,
+        }
+    }
+}
+
+impl LoadOptions {
+    /// Load all facts in a single transaction, which is rolled back if
+    /// loading fails. Enabled by default.
+    pub fn single_transaction(mut self, enable: bool) -> Self {
+        self.single_transaction = enable;
+        self
+    }
+
+    /// Use write-ahead logging and disable synchronous writes while loading
+    /// facts, restoring the previous settings afterwards. This speeds up
+    /// loading, but the database can be corrupted if the machine crashes
+    /// during loading. Disabled by default.
+    pub fn fast_journal(mut self, enable: bool) -> Self {
+        self.fast_journal = enable;
+        self
+    }
+
+    /// Create indexes on the value columns of tables and on the text of
+    /// interned strings after loading facts, which speeds up queries that
+    /// look up elements by their values or children, or strings by their
+    /// text. Disabled by default.
+    pub fn create_indexes(mut self, enable: bool) -> Self {
+        self.create_indexes = enable;
+        self
+    }
+
+    /// Compress strings of at least `min_len` bytes with zstd, which shrinks
+    /// databases dominated by large text fields. Short strings are stored in
+    /// a table named `{table_prefix}_ShortSymbolTable` and compressed strings
+    /// in a table named `{table_prefix}_CompressedSymbolTable`, and the symbol
+    /// table becomes a view over both tables that decompresses strings.
+    ///
+    /// Reading the views of such a database requires the SQL function
+    /// registered by [register_functions]; in particular, Souffle cannot read
+    /// them. Compression only applies to new databases; strings appended to
+    /// an existing database are compressed only if the database compresses
+    /// strings. Disabled by default.
+    #[cfg(feature = "string_compression")]
+    pub fn compress_strings(mut self, min_len: usize) -> Self {
+        self.compress_strings = Some(min_len);
+        self
+    }
+
+    /// Create summary tables of the facts in the database after loading
+    /// facts, so that rules and SQL queries can consult statistics without
+    /// scanning the tables of relations:
+    ///
+    /// - `{table_prefix}_RelationStats(relation, facts)`, with the number of
+    ///   facts of each relation,
+    /// - `{table_prefix}_NumberHistogram(bucket, low, high, count)`, which
+    ///   divides the range of numbers into [STATS_NUMBER_BUCKETS] buckets of
+    ///   equal width, with the least and greatest number and the number of
+    ///   facts in each nonempty bucket,
+    /// - `{table_prefix}_TopStrings(symbol, count)`, with the
+    ///   [STATS_TOP_STRINGS] most frequent string values and their number of
+    ///   facts.
+    ///
+    /// The tables are recreated every time facts are loaded, so they describe
+    /// the whole database after facts are appended to it. Disabled by
+    /// default.
+    pub fn statistics(mut self, enable: bool) -> Self {
+        self.statistics = enable;
+        self
+    }
+
+    /// Record the time of extraction in the metadata table. Databases of the
+    /// same inputs then differ from each other, so this is disabled by
+    /// default.
+    pub fn provenance(mut self, enable: bool) -> Self {
+        self.provenance = enable;
+        self
+    }
+
+    /// Run `ANALYZE` after loading facts, which gathers the statistics the
+    /// SQLite query planner uses to choose between indexes. This is most
+    /// useful along with [create_indexes][Self::create_indexes]. Disabled by
+    /// default.
+    pub fn analyze(mut self, enable: bool) -> Self {
+        self.analyze = enable;
+        self
+    }
+
+    /// Store only numbers that fit in signed integers of `bits` bits in the
+    /// `number` relation, e.g. 32 for Souffle's default number type, and
+    /// store or refuse wider numbers as `wide` says. By default, every
+    /// number is stored in `number`, which requires Souffle to be built with
+    /// 64-bit numbers (`-DSOUFFLE_DOMAIN_64BIT=ON`); otherwise Souffle reads
+    /// wide numbers as other numbers without warning.
+    pub fn number_domain(mut self, bits: u32, wide: WideNumbers) -> Self {
+        self.number_domain = Some((bits.clamp(1, 64), wide));
+        self
+    }
+
+    /// Whether `value` is a wide number, which does not fit in the number
+    /// domain.
+    fn is_wide(&self, value: i64) -> bool {
+        match self.number_domain {
+            Some((bits, _)) if bits < 64 => {
+                let max = (1i64 << (bits - 1)) - 1;
+                value < -max - 1 || value > max
+            }
+            _ => false,
+        }
+    }
+
+    /// Refuse number `value` if it is wide and wide numbers are refused.
+    fn check_number(&self, value: i64) -> Result<()> {
+        match self.number_domain {
+            Some((bits, WideNumbers::Error)) if self.is_wide(value) => {
+                Result::Err(DatalogExtractionError::UnextractableData(format!(
+                    "number {} wider than {} bits",
+                    value, bits
+                )))
+            }
+            _ => Result::Ok(()),
+        }
+    }
+}
+
+/// How numbers that do not fit in the number domain given with
+/// [LoadOptions::number_domain] are stored.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum WideNumbers {
+    /// Store wide numbers in `numberParts(id, hi, lo)` instead of `number`,
+    /// where `lo` is the unsigned low 32 bits of the number and `hi` the
+    /// signed high 32 bits, so that the number is `hi * 2^32 + lo`.
+    #[default]
+    Split,
+
+    /// Store wide numbers as decimal text in `numberText(id, value)` instead
+    /// of `number`.
+    Symbol,
+
+    /// Refuse to extract wide numbers, returning an
+    /// [UnextractableData][DatalogExtractionError::UnextractableData] error.
+    Error,
+}
+
+/// Number of buckets of the number histogram created by
+/// [LoadOptions::statistics].
+pub const STATS_NUMBER_BUCKETS: usize = 16;
+
+/// Number of string values listed in the top strings table created by
+/// [LoadOptions::statistics].
+pub const STATS_TOP_STRINGS: usize = 100;
+
+/// Name of the SQL function that decompresses strings compressed with
+/// [LoadOptions::compress_strings].
+#[cfg(feature = "string_compression")]
+pub const DECOMPRESS_FUNCTION: &str = "serde_datalog_decompress";
+
+/// Register the SQL functions needed to read databases whose strings are
+/// compressed (see [LoadOptions::compress_strings]) with `conn`.
+#[cfg(feature = "string_compression")]
+pub fn register_functions(conn: &rusqlite::Connection) -> Result<()> {
+    BackendUtil::create_decompress_function(conn)?;
+    Result::Ok(())
+}
+
+/// Returns true if the existing database of `conn` was generated by a backend
+/// that assumes map keys are always strings, e.g. [StringKeyBackend].
+pub fn db_has_string_keys(conn: &rusqlite::Connection, schema: &SchemaConfig) -> Result<bool> {
+    let map_table = schema.table_name("map");
+    let map_sql: String = conn
+        .query_row(
+            "SELECT sql FROM sqlite_master WHERE type = 'table' AND name = ?1;",
+            [&map_table],
+            |row| row.get(0),
+        )
+        .optional()?
+        .ok_or_else(|| BackendError::Schema(format!("missing table {}", map_table)))?;
+
+    Result::Ok(map_sql.contains(&schema.render("FOREIGN KEY(key) REFERENCES {t}_SymbolTable")))
+}
+
+/// Souffle schema of databases generated by [Backend].
+pub const SOUFFLE_SCHEMA: &str = include_str!("../../schemas/serde.dl");
+
+/// Souffle schema of databases generated by [StringKeyBackend].
+pub const SOUFFLE_STRING_KEY_SCHEMA: &str = include_str!("../../schemas/serde_string_key.dl");
+
+/// Generate Souffle declarations of all relations, followed by `.input`
+/// directives that read them from the views of the SQLite database `dbname`,
+/// so that Souffle programs can `#include` the result instead of writing
+/// directives by hand:
+///
+/// ```text
+/// .decl type(id: ElemId, type: ElemType)
+/// ...
+/// .input type(IO=sqlite, dbname="facts.db")
+/// ```
+///
+/// If `string_keys` is set, the declarations are those of databases
+/// generated by [StringKeyBackend]. Directives of schemas with a view prefix
+/// name the views to read; Souffle cannot read databases without views.
+pub fn input_directives(schema: &SchemaConfig, string_keys: bool, dbname: &str) -> String {
+    let souffle_schema = if string_keys {
+        SOUFFLE_STRING_KEY_SCHEMA
+    } else {
+        SOUFFLE_SCHEMA
+    };
+
+    let mut program = format!(
+        "// Souffle declarations and input directives for {}, generated by serde_datalog\n",
+        dbname
+    );
+
+    // skip the comment heading the schema
+    for line in souffle_schema
+        .lines()
+        .skip_while(|line| line.starts_with("//"))
+    {
+        program.push_str(line);
+        program.push('\n');
+    }
+
+    program.push('\n');
+    let relations = souffle_schema.lines().filter_map(|line| {
+        let decl = line.strip_prefix(".decl ")?;
+        decl.split('(').next()
+    });
+
+    for relation in relations {
+        let view = schema.view_name(relation);
+        if view == relation {
+            program.push_str(&format!(
+                ".input {}(IO=sqlite, dbname={:?})\n",
+                relation, dbname
+            ));
+        } else {
+            program.push_str(&format!(
+                ".input {}(IO=sqlite, dbname={:?}, name={:?})\n",
+                relation, dbname, view
+            ));
+        }
+    }
+
+    program
+}
+
+/// Version of the schema of the databases created by the Souffle SQLite
+/// backends. It is stored in the `{table_prefix}_Metadata` table of every
+/// database, along with the version of serde_datalog, the source files,
+/// entries added with [Backend::with_metadata], and the time of extraction if
+/// [LoadOptions::provenance] is enabled.
+pub const SCHEMA_VERSION: i64 = 1;
+
+/// Returns the schema version of the existing database of `conn`. Databases
+/// created before schemas were versioned have no metadata table and are of
+/// version 0.
+pub fn db_schema_version(conn: &rusqlite::Connection, schema: &SchemaConfig) -> Result<i64> {
+    let metadata_table = schema.table_name("_Metadata");
+    if !BackendUtil::has_table(conn, &metadata_table)? {
+        return Result::Ok(0);
+    }
+
+    let version: Option<String> = conn
+        .query_row(
+            &format!(
+                "SELECT value FROM {} WHERE key = 'schemaVersion';",
+                metadata_table
+            ),
+            (),
+            |row| row.get(0),
+        )
+        .optional()?;
+
+    match version {
+        Some(version) => version.parse().map_err(|_| {
+            BackendError::Schema(format!("invalid schema version {}", version)).into()
+        }),
+        None => Result::Ok(0),
+    }
+}
+
+/// Returns the number of facts of each relation of the existing database of
+/// `conn`, in order of relation name, as stored in the `_RelationStats` table
+/// by [LoadOptions::statistics].
+pub fn relation_fact_counts(
+    conn: &rusqlite::Connection,
+    schema: &SchemaConfig,
+) -> Result<Vec<(String, usize)>> {
+    let counts = BackendUtil::relation_fact_counts(conn, schema)?;
+    Result::Ok(
+        counts
+            .into_iter()
+            .map(|(relation, facts)| (relation, facts as usize))
+            .collect(),
+    )
+}
+
+/// Copy each relation of the database `filename` into a separate database in
+/// the directory `dir`, named `{relation}.db`, so that Souffle jobs reading
+/// different relations can run independently. Every database gets a copy of
+/// the symbol table, so symbol identifiers agree across databases and the
+/// databases can be combined again with [merge_dbs].
+///
+/// Returns the file names of the databases, which are overwritten if they
+/// exist.
+pub fn split_db_by_relation(
+    filename: &str,
+    schema: &SchemaConfig,
+    dir: &std::path::Path,
+) -> Result<Vec<String>> {
+    let src = rusqlite::Connection::open(filename)?;
+    let objects = BackendUtil::schema_objects(&src, "main")?;
+    drop(src);
+
+    // tables and views with names starting with `{t}_` hold interned strings
+    let shared_prefix = schema.table_name("_");
+    let is_shared = |name: &str| name.starts_with(&shared_prefix);
+
+    let mut split_files = Vec::new();
+    for (kind, table, _, _) in objects.iter() {
+        if kind != "table" || is_shared(table) {
+            continue;
+        }
+
+        let relation = table.strip_prefix(&schema.table_prefix).unwrap_or(table);
+        let view = schema.view_name(relation);
+        let split_file = dir.join(format!("{}.db", relation));
+        let split_file = split_file.to_string_lossy().to_string();
+        if std::path::Path::new(&split_file).is_file() {
+            std::fs::remove_file(&split_file).map_err(BackendError::Io)?;
+        }
+
+        let conn = rusqlite::Connection::open(&split_file)?;
+        conn.execute("ATTACH DATABASE ?1 AS src;", [filename])?;
+        let split_objects = objects.iter().filter(|(kind, name, tbl_name, _)| {
+            is_shared(tbl_name)
+                || tbl_name == table
+                || (kind == "view" && *name == view && view != *table)
+        });
+        BackendUtil::copy_objects(&conn, "src", split_objects)?;
+        conn.execute_batch("DETACH DATABASE src;")?;
+
+        split_files.push(split_file);
+    }
+
+    Result::Ok(split_files)
+}
+
+/// Merge the databases `inputs`, e.g. databases written for separate input
+/// files or relations, into the database `filename`, which is created if it
+/// does not exist. Tables, indexes, and views missing from `filename` are
+/// created, and rows that are already present (such as symbols shared by the
+/// databases) are skipped. The databases must have been generated by the same
+/// kind of backend, with agreeing symbol and element identifiers.
+pub fn merge_dbs<P: AsRef<std::path::Path>>(filename: &str, inputs: &[P]) -> Result<()> {
+    let conn = rusqlite::Connection::open(filename)?;
+
+    for input in inputs.iter() {
+        conn.execute(
+            "ATTACH DATABASE ?1 AS src;",
+            [input.as_ref().to_string_lossy()],
+        )?;
+        let objects = BackendUtil::schema_objects(&conn, "src")?;
+        BackendUtil::copy_objects(&conn, "src", objects.iter())?;
+        conn.execute_batch("DETACH DATABASE src;")?;
+    }
+
+    Result::Ok(())
+}
+
+/// Read the facts of the database `filename`, generated by [Backend] with the
+/// default [SchemaConfig], e.g. by an earlier run of the `serde_datalog`
+/// commandline tool, so that they can be post-processed in Rust:
+///
+/// ```ignore
+/// let data = backend::souffle_sqlite::read_db("facts.db")?;
+/// for (elem, value) in data.string_table.iter() {
+///     println!("{} {}", elem, data.symbol(value).unwrap());
+/// }
+/// ```
+///
+/// The database is opened read-only. See [read_from] for databases with
+/// other schemas.
+pub fn read_db<P: AsRef<std::path::Path>>(filename: P) -> Result<BackendData<ElemId>> {
+    read_from(
+        &BackendUtil::open_read_only(filename)?,
+        &SchemaConfig::default(),
+    )
+}
+
+/// Like [read_db], but for databases generated by [StringKeyBackend].
+pub fn read_string_key_db<P: AsRef<std::path::Path>>(filename: P) -> Result<BackendData<SymbolId>> {
+    read_string_key_from(
+        &BackendUtil::open_read_only(filename)?,
+        &SchemaConfig::default(),
+    )
+}
+
+/// Read the facts of the existing database of `conn`, generated by [Backend]
+/// with `schema`. Interned strings keep their identifiers and are resolved
+/// with the symbol table of the database, including strings compressed with
+/// [LoadOptions::compress_strings]. Relations that databases of older schema
+/// versions lack are read as empty.
+///
+/// Returns a [Schema][BackendError::Schema] error if the database was created
+/// by a newer version of serde_datalog, or by a [StringKeyBackend].
+pub fn read_from(
+    conn: &rusqlite::Connection,
+    schema: &SchemaConfig,
+) -> Result<BackendData<ElemId>> {
+    BackendUtil::check_schema_version(conn, schema)?;
+    if db_has_string_keys(conn, schema)? {
+        return Result::Err(
+            BackendError::Schema("map keys are strings, not elements".to_string()).into(),
+        );
+    }
+
+    Result::Ok(BackendUtil::read_from(conn, schema, ElemId)?)
+}
+
+/// Like [read_from], but for databases generated by [StringKeyBackend].
+pub fn read_string_key_from(
+    conn: &rusqlite::Connection,
+    schema: &SchemaConfig,
+) -> Result<BackendData<SymbolId>> {
+    BackendUtil::check_schema_version(conn, schema)?;
+    if !db_has_string_keys(conn, schema)? {
+        return Result::Err(
+            BackendError::Schema("map keys are elements, not strings".to_string()).into(),
+        );
+    }
+
+    Result::Ok(BackendUtil::read_from(conn, schema, SymbolId)?)
 }
 
 struct BackendUtil;
 
-impl BackendUtil {
-    fn dump_to_db<K: Display + Eq + Hash>(
-        data: &BackendData<K>,
-        filename: &str,
-    ) -> rusqlite::Result<rusqlite::Connection> {
-        let conn = rusqlite::Connection::open(filename)?;
-        {
-            conn.execute_batch(
-                "BEGIN;
+impl BackendUtil {
+    /// Run `load`, which loads facts into the database of `conn`,
+    /// according to `options`.
+    fn load(
+        conn: &rusqlite::Connection,
+        schema: &SchemaConfig,
+        options: &LoadOptions,
+        load: impl FnOnce() -> rusqlite::Result<()>,
+    ) -> rusqlite::Result<()> {
+        let prev_journal: Option<(String, i64)> = if options.fast_journal {
+            let journal_mode: String =
+                conn.query_row("PRAGMA journal_mode;", (), |row| row.get(0))?;
+            let synchronous: i64 = conn.query_row("PRAGMA synchronous;", (), |row| row.get(0))?;
+            conn.query_row("PRAGMA journal_mode = WAL;", (), |_| Ok(()))?;
+            conn.execute_batch("PRAGMA synchronous = OFF;")?;
+            Some((journal_mode, synchronous))
+        } else {
+            None
+        };
+
+        if options.single_transaction {
+            conn.execute_batch("BEGIN;")?;
+        }
+
+        let res = load().and_then(|_| {
+            if options.single_transaction {
+                conn.execute_batch("COMMIT;")
+            } else {
+                rusqlite::Result::Ok(())
+            }
+        });
+
+        if res.is_err() && options.single_transaction && !conn.is_autocommit() {
+            conn.execute_batch("ROLLBACK;")?;
+        }
+
+        if let Some((journal_mode, synchronous)) = prev_journal {
+            conn.query_row(
+                &format!("PRAGMA journal_mode = {};", journal_mode),
+                (),
+                |_| Ok(()),
+            )?;
+            conn.execute_batch(&format!("PRAGMA synchronous = {};", synchronous))?;
+        }
+
+        res?;
+
+        if options.create_indexes {
+            Self::create_indexes(conn, schema)?;
+        }
+
+        if options.statistics {
+            Self::create_statistics(conn, schema)?;
+        }
+
+        if options.analyze {
+            conn.execute_batch("ANALYZE;")?;
+        }
+
+        rusqlite::Result::Ok(())
+    }
+
+    #[cfg(feature = "string_compression")]
+    fn create_decompress_function(conn: &rusqlite::Connection) -> rusqlite::Result<()> {
+        use rusqlite::functions::FunctionFlags;
+
+        conn.create_scalar_function(
+            DECOMPRESS_FUNCTION,
+            1,
+            FunctionFlags::SQLITE_UTF8 | FunctionFlags::SQLITE_DETERMINISTIC,
+            |ctx| {
+                let data: Vec<u8> = ctx.get(0)?;
+                let text = zstd::decode_all(data.as_slice())
+                    .map_err(|err| rusqlite::Error::UserFunctionError(Box::new(err)))?;
+                String::from_utf8(text)
+                    .map_err(|err| rusqlite::Error::UserFunctionError(Box::new(err)))
+            },
+        )
+    }
+
+    /// Register the function decompressing strings with `conn` if its
+    /// database compresses strings, so that its symbol table can be read.
+    #[cfg(feature = "string_compression")]
+    fn register_decompress_function(
+        conn: &rusqlite::Connection,
+        schema: &SchemaConfig,
+    ) -> rusqlite::Result<()> {
+        if Self::has_table(conn, &schema.table_name("_CompressedSymbolTable"))? {
+            Self::create_decompress_function(conn)?;
+        }
+        rusqlite::Result::Ok(())
+    }
+
+    fn create_indexes(conn: &rusqlite::Connection, schema: &SchemaConfig) -> rusqlite::Result<()> {
+        conn.execute_batch(&schema.render(
+            "CREATE INDEX IF NOT EXISTS {t}type_type ON {t}type (type);
+            CREATE INDEX IF NOT EXISTS {t}number_value ON {t}number (value);
+            CREATE INDEX IF NOT EXISTS {t}string_value ON {t}string (value);
+            CREATE INDEX IF NOT EXISTS {t}map_value ON {t}map (value);
+            CREATE INDEX IF NOT EXISTS {t}struct_value ON {t}struct (value);
+            CREATE INDEX IF NOT EXISTS {t}seq_value ON {t}seq (value);
+            CREATE INDEX IF NOT EXISTS {t}tuple_value ON {t}tuple (value);",
+        ))?;
+
+        // the symbol table is a view if strings are compressed, and
+        // compressed strings cannot be looked up by their text
+        let symbol_table = if Self::has_table(conn, &schema.table_name("_ShortSymbolTable"))? {
+            schema.table_name("_ShortSymbolTable")
+        } else {
+            schema.symbol_table_name()
+        };
+        conn.execute_batch(&format!(
+            "CREATE INDEX IF NOT EXISTS {0}_symbol ON {0} (symbol);",
+            symbol_table
+        ))
+    }
+
+    /// The number of facts of each relation of the database of `conn`, by
+    /// relation name.
+    fn relation_fact_counts(
+        conn: &rusqlite::Connection,
+        schema: &SchemaConfig,
+    ) -> rusqlite::Result<Vec<(String, i64)>> {
+        // tables with names starting with `{t}_` are not relations, e.g. the
+        // symbol table and the statistics tables
+        let shared_prefix = schema.table_name("_");
+        let tables: Vec<String> = conn
+            .prepare("SELECT name FROM sqlite_master WHERE type = 'table' ORDER BY name;")?
+            .query_map((), |row| row.get(0))?
+            .collect::<rusqlite::Result<_>>()?;
+
+        let mut counts = Vec::new();
+        for table in tables.iter() {
+            if !table.starts_with(&schema.table_prefix) || table.starts_with(&shared_prefix) {
+                continue;
+            }
+
+            let relation = table.strip_prefix(&schema.table_prefix).unwrap_or(table);
+            let facts: i64 =
+                conn.query_row(&format!("SELECT COUNT(*) FROM \"{}\";", table), (), |row| {
+                    row.get(0)
+                })?;
+            counts.push((relation.to_string(), facts));
+        }
+
+        rusqlite::Result::Ok(counts)
+    }
+
+    /// (Re)create the summary tables of [LoadOptions::statistics].
+    fn create_statistics(
+        conn: &rusqlite::Connection,
+        schema: &SchemaConfig,
+    ) -> rusqlite::Result<()> {
+        #[cfg(feature = "string_compression")]
+        Self::register_decompress_function(conn, schema)?;
+
+        conn.execute_batch(&schema.render(
+            "DROP TABLE IF EXISTS {t}_RelationStats;
+            DROP TABLE IF EXISTS {t}_NumberHistogram;
+            DROP TABLE IF EXISTS {t}_TopStrings;
+            CREATE TABLE {t}_RelationStats (
+                relation TEXT NOT NULL,
+                facts INTEGER NOT NULL,
+                PRIMARY KEY (relation)
+            );
+            CREATE TABLE {t}_NumberHistogram (
+                bucket INTEGER NOT NULL,
+                low INTEGER NOT NULL,
+                high INTEGER NOT NULL,
+                count INTEGER NOT NULL,
+                PRIMARY KEY (bucket)
+            );
+            CREATE TABLE {t}_TopStrings (
+                symbol TEXT NOT NULL,
+                count INTEGER NOT NULL
+            );",
+        ))?;
+
+        let mut insert_relation = conn.prepare(
+            &schema.render("INSERT INTO {t}_RelationStats (relation, facts) VALUES (?1, ?2);"),
+        )?;
+        for (relation, facts) in Self::relation_fact_counts(conn, schema)? {
+            insert_relation.execute((relation, facts))?;
+        }
+
+        // bucket widths are computed with reals, since the range of numbers
+        // may not fit in an integer
+        conn.execute_batch(&schema.render(&format!(
+            "INSERT INTO {{t}}_NumberHistogram (bucket, low, high, count)
+            WITH
+                bounds(lo, hi) AS (SELECT MIN(value), MAX(value) FROM {{t}}number),
+                bucketed(bucket, value) AS (
+                    SELECT
+                        CASE WHEN hi = lo THEN 0
+                        ELSE MIN({buckets} - 1, CAST(
+                            (CAST(value AS REAL) - lo) * {buckets} / (CAST(hi AS REAL) - lo)
+                            AS INTEGER))
+                        END,
+                        value
+                    FROM {{t}}number, bounds
+                )
+            SELECT bucket, MIN(value), MAX(value), COUNT(*) FROM bucketed
+            GROUP BY bucket ORDER BY bucket;
+
+            INSERT INTO {{t}}_TopStrings (symbol, count)
+            WITH top(value, count) AS (
+                SELECT value, COUNT(*) FROM {{t}}string
+                GROUP BY value ORDER BY COUNT(*) DESC, value LIMIT {top}
+            )
+            SELECT {{t}}_SymbolTable.symbol, top.count
+            FROM top INNER JOIN {{t}}_SymbolTable ON top.value = {{t}}_SymbolTable.id
+            ORDER BY top.count DESC, {{t}}_SymbolTable.symbol;",
+            buckets = STATS_NUMBER_BUCKETS,
+            top = STATS_TOP_STRINGS,
+        )))
+    }
+
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, err))]
+    fn dump_into<K: Display + Eq + Hash>(
+        conn: &rusqlite::Connection,
+        schema: &SchemaConfig,
+        options: &LoadOptions,
+        data: &BackendData<K>,
+        metadata: &[(String, String)],
+    ) -> rusqlite::Result<()> {
+        Self::create_tables(conn, schema, options, false)?;
+        Self::create_metadata_table(conn, schema)?;
+        Self::insert_facts(conn, schema, options, data)?;
+        Self::insert_metadata(conn, schema, options, data, metadata)
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(skip_all, fields(replace), err)
+    )]
+    fn append_into<K: Display + Eq + Hash>(
+        conn: &rusqlite::Connection,
+        schema: &SchemaConfig,
+        options: &LoadOptions,
+        data: &BackendData<K>,
+        metadata: &[(String, String)],
+        replace: bool,
+        map_keys_are_elems: bool,
+    ) -> rusqlite::Result<()> {
+        #[cfg(feature = "string_compression")]
+        Self::register_decompress_function(conn, schema)?;
+
+        // databases of older schema versions lack tables of relations that
+        // were added since; all other tables are unchanged
+        if !Self::has_table(conn, &schema.table_name("_Metadata"))? {
+            Self::create_tables(conn, schema, options, true)?;
+            Self::create_metadata_table(conn, schema)?;
+        }
+
+        // every extraction has element type kinds, which databases created
+        // before they were extracted have no table for
+        Self::create_elem_type_kind_table(conn, schema, true)?;
+
+        if replace {
+            Self::remove_files(conn, schema, data, map_keys_are_elems)?;
+        }
+        Self::insert_facts(conn, schema, options, data)?;
+        Self::insert_metadata(conn, schema, options, data, metadata)
+    }
+
+    /// Refuse to append to the existing database of `conn` if it was created
+    /// by a newer version of serde_datalog, with a schema this version does
+    /// not know how to migrate.
+    fn check_schema_version(conn: &rusqlite::Connection, schema: &SchemaConfig) -> Result<()> {
+        let version = db_schema_version(conn, schema)?;
+        if version > SCHEMA_VERSION {
+            return Result::Err(
+                BackendError::Schema(format!(
+                    "schema version {} is newer than the supported version {}",
+                    version, SCHEMA_VERSION
+                ))
+                .into(),
+            );
+        }
+
+        Result::Ok(())
+    }
+
+    fn create_metadata_table(
+        conn: &rusqlite::Connection,
+        schema: &SchemaConfig,
+    ) -> rusqlite::Result<()> {
+        conn.execute_batch(&schema.render(
+            "CREATE TABLE {t}_Metadata (
+                key TEXT NOT NULL,
+                value TEXT NOT NULL,
+                PRIMARY KEY (key, value)
+            );",
+        ))
+    }
+
+    /// Record the schema and crate versions, the time of extraction if
+    /// `options` record provenance, the source files of `data`, and the
+    /// entries of `metadata` in the metadata table. Entries with a single
+    /// value, such as the time of extraction, are replaced, while source files
+    /// accumulate as facts are appended.
+    fn insert_metadata<K: Display + Eq + Hash>(
+        conn: &rusqlite::Connection,
+        schema: &SchemaConfig,
+        options: &LoadOptions,
+        data: &BackendData<K>,
+        metadata: &[(String, String)],
+    ) -> rusqlite::Result<()> {
+        let mut delete_entry =
+            conn.prepare(&schema.render("DELETE FROM {t}_Metadata WHERE key = ?1;"))?;
+        let mut insert_entry = conn.prepare(
+            &schema.render("INSERT OR IGNORE INTO {t}_Metadata (key, value) VALUES (?1, ?2);"),
+        )?;
+
+        let mut entries = vec![
+            ("schemaVersion", SCHEMA_VERSION.to_string()),
+            ("crateVersion", env!("CARGO_PKG_VERSION").to_string()),
+        ];
+        if options.provenance {
+            let extracted_at: String =
+                conn.query_row("SELECT strftime('%Y-%m-%dT%H:%M:%SZ', 'now');", (), |row| {
+                    row.get(0)
+                })?;
+            entries.push(("extractedAt", extracted_at));
+        }
+
+        let entries = entries
+            .iter()
+            .map(|(key, value)| (*key, value.as_str()))
+            .chain(
+                metadata
+                    .iter()
+                    .map(|(key, value)| (key.as_str(), value.as_str())),
+            );
+
+        for (key, value) in entries {
+            delete_entry.execute([key])?;
+            insert_entry.execute([key, value])?;
+        }
+
+        // documents within files (named `file:line`) are not source files
+        let files: Vec<&str> = data
+            .file_format_table
+            .keys()
+            .filter_map(|file| data.symbol_table.get_by_right(file))
+            .map(|file| file.as_str())
+            .collect();
+        let roots = data
+            .root_elem_table
+            .keys()
+            .filter_map(|root| data.symbol_table.get_by_right(root))
+            .filter(|root| {
+                !files.iter().any(|file| {
+                    root.strip_prefix(file)
+                        .is_some_and(|rest| rest.starts_with(':'))
+                })
+            });
+
+        for file in data.ordered(
+            files.iter().copied().chain(roots.map(|root| root.as_str())),
+            |file| *file,
+        ) {
+            insert_entry.execute(["sourceFile", file])?;
+        }
+
+        rusqlite::Result::Ok(())
+    }
+
+    /// Returns the symbol table of an existing database, along with the
+    /// first element identifier not used by the database.
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, err))]
+    fn load_from(
+        conn: &rusqlite::Connection,
+        schema: &SchemaConfig,
+    ) -> rusqlite::Result<(Vec<(String, SymbolId)>, ElemId)> {
+        #[cfg(feature = "string_compression")]
+        Self::register_decompress_function(conn, schema)?;
+
+        let mut select_symbols =
+            conn.prepare(&schema.render("SELECT symbol, id FROM {t}_SymbolTable;"))?;
+        let symbols = select_symbols
+            .query_map((), |row| {
+                rusqlite::Result::Ok((row.get::<_, String>(0)?, SymbolId(row.get(1)?)))
+            })?
+            .collect::<rusqlite::Result<Vec<(String, SymbolId)>>>()?;
+
+        let next_elem_id: usize = conn.query_row(
+            &schema.render("SELECT COALESCE(MAX(id), 0) + 1 FROM {t}type;"),
+            (),
+            |row| row.get(0),
+        )?;
+
+        rusqlite::Result::Ok((symbols, ElemId(next_elem_id)))
+    }
+
+    /// Open the existing database `filename` without write access.
+    fn open_read_only<P: AsRef<std::path::Path>>(
+        filename: P,
+    ) -> rusqlite::Result<rusqlite::Connection> {
+        rusqlite::Connection::open_with_flags(filename, rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY)
+    }
+
+    /// Returns the facts of an existing database, with map keys converted
+    /// with `map_key`. Tables missing from databases of older schema versions
+    /// are read as empty.
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, err))]
+    fn read_from<K: Display + Eq + Hash>(
+        conn: &rusqlite::Connection,
+        schema: &SchemaConfig,
+        map_key: impl Fn(usize) -> K,
+    ) -> rusqlite::Result<BackendData<K>> {
+        let (symbols, _) = Self::load_from(conn, schema)?;
+        let mut data = BackendData::default();
+        for (symbol, id) in symbols {
+            data.insert_symbol(symbol, id);
+        }
+
+        let sym = |row: &rusqlite::Row, i: usize| row.get(i).map(SymbolId);
+        let elem = |row: &rusqlite::Row, i: usize| row.get(i).map(ElemId);
+
+        data.root_elem_table = Self::select(conn, schema, "rootElem", "file, elem", |row| {
+            rusqlite::Result::Ok((sym(row, 0)?, elem(row, 1)?))
+        })?;
+        data.file_format_table = Self::select(conn, schema, "fileFormat", "file, format", |row| {
+            rusqlite::Result::Ok((sym(row, 0)?, sym(row, 1)?))
+        })?;
+        data.key_field_table = Self::select(conn, schema, "keyField", "type, field", |row| {
+            rusqlite::Result::Ok((sym(row, 0)?, sym(row, 1)?))
+        })?;
+        data.type_table = Self::select(conn, schema, "type", "id, type", |row| {
+            rusqlite::Result::Ok((elem(row, 0)?, sym(row, 1)?))
+        })?;
+        data.bool_table = Self::select(conn, schema, "bool", "id, value", |row| {
+            rusqlite::Result::Ok((elem(row, 0)?, row.get(1)?))
+        })?;
+        data.number_table = Self::select(conn, schema, "number", "id, value", |row| {
+            rusqlite::Result::Ok((elem(row, 0)?, row.get(1)?))
+        })?;
+        // wide numbers stored in parts or as text are numbers again
+        let number_parts: Vec<(ElemId, i64)> =
+            Self::select(conn, schema, "numberParts", "id, hi, lo", |row| {
+                let (hi, lo): (i64, i64) = (row.get(1)?, row.get(2)?);
+                rusqlite::Result::Ok((elem(row, 0)?, (hi << 32) | lo))
+            })?;
+        let number_text: Vec<(ElemId, String)> =
+            Self::select(conn, schema, "numberText", "id, value", |row| {
+                rusqlite::Result::Ok((elem(row, 0)?, row.get(1)?))
+            })?;
+        data.number_table.extend(number_parts);
+        for (id, value) in number_text {
+            let value = value.parse().map_err(|_| {
+                rusqlite::Error::FromSqlConversionFailure(
+                    1,
+                    rusqlite::types::Type::Text,
+                    format!("invalid number {}", value).into(),
+                )
+            })?;
+            data.number_table.insert(id, value);
+        }
+        data.string_table = Self::select(conn, schema, "string", "id, value", |row| {
+            rusqlite::Result::Ok((elem(row, 0)?, sym(row, 1)?))
+        })?;
+        data.full_string_table = Self::select(conn, schema, "fullString", "id, value", |row| {
+            rusqlite::Result::Ok((elem(row, 0)?, row.get(1)?))
+        })?;
+        data.map_table = Self::select(conn, schema, "map", "id, key, value", |row| {
+            rusqlite::Result::Ok(((elem(row, 0)?, map_key(row.get(1)?)), elem(row, 2)?))
+        })?;
+        data.struct_table = Self::select(conn, schema, "struct", "id, field, value", |row| {
+            rusqlite::Result::Ok(((elem(row, 0)?, sym(row, 1)?), elem(row, 2)?))
+        })?;
+        data.key_table = Self::select(conn, schema, "key", "id, key", |row| {
+            rusqlite::Result::Ok((elem(row, 0)?, elem(row, 1)?))
+        })?;
+        data.seq_table = Self::select(conn, schema, "seq", "id, pos, value", |row| {
+            rusqlite::Result::Ok(((elem(row, 0)?, row.get(1)?), elem(row, 2)?))
+        })?;
+        data.tuple_table = Self::select(conn, schema, "tuple", "id, pos, value", |row| {
+            rusqlite::Result::Ok(((elem(row, 0)?, row.get(1)?), elem(row, 2)?))
+        })?;
+        data.struct_type_table = Self::select(conn, schema, "structType", "id, type", |row| {
+            rusqlite::Result::Ok((elem(row, 0)?, sym(row, 1)?))
+        })?;
+        data.variant_type_table =
+            Self::select(conn, schema, "variantType", "id, type, variant", |row| {
+                rusqlite::Result::Ok((elem(row, 0)?, (sym(row, 1)?, sym(row, 2)?)))
+            })?;
+        data.schema_type_table = Self::select(conn, schema, "schemaType", "id, schema", |row| {
+            rusqlite::Result::Ok((elem(row, 0)?, sym(row, 1)?))
+        })?;
+        data.datetime_table =
+            Self::select(conn, schema, "datetime", "id, micros, offset", |row| {
+                rusqlite::Result::Ok((elem(row, 0)?, (row.get(1)?, row.get(2)?)))
+            })?;
+        // the bits of UUIDs are stored as signed integers
+        data.uuid_table = Self::select(conn, schema, "uuid", "id, hi, lo", |row| {
+            let (hi, lo): (i64, i64) = (row.get(1)?, row.get(2)?);
+            rusqlite::Result::Ok((elem(row, 0)?, (hi as u64, lo as u64)))
+        })?;
+        data.ip_table = Self::select(conn, schema, "ip", "id, version, address", |row| {
+            rusqlite::Result::Ok((elem(row, 0)?, (row.get(1)?, sym(row, 2)?)))
+        })?;
+        data.url_table = Self::select(conn, schema, "url", "id, scheme, host", |row| {
+            rusqlite::Result::Ok((elem(row, 0)?, (sym(row, 1)?, sym(row, 2)?)))
+        })?;
+        data.email_table = Self::select(conn, schema, "email", "id, local, domain", |row| {
+            rusqlite::Result::Ok((elem(row, 0)?, (sym(row, 1)?, sym(row, 2)?)))
+        })?;
+        data.bytes_table = Self::select(conn, schema, "bytes", "id, value", |row| {
+            rusqlite::Result::Ok((elem(row, 0)?, sym(row, 1)?))
+        })?;
+        data.bytes_encoding_table = Self::select(
+            conn,
+            schema,
+            "bytesEncoding",
+            "id, encoding, length",
+            |row| rusqlite::Result::Ok((elem(row, 0)?, (sym(row, 1)?, row.get(2)?))),
+        )?;
+        data.declared_len_table = Self::select(conn, schema, "declaredLen", "id, len", |row| {
+            rusqlite::Result::Ok((elem(row, 0)?, row.get(1)?))
+        })?;
+        data.absent_field_table = Self::select(conn, schema, "absentField", "id, field", |row| {
+            rusqlite::Result::Ok((elem(row, 0)?, sym(row, 1)?))
+        })?;
+        data.unit_table = Self::select(conn, schema, "unit", "id", |row| elem(row, 0))?;
+        data.truncated_table = Self::select(conn, schema, "truncated", "relation, quota", |row| {
+            rusqlite::Result::Ok((sym(row, 0)?, row.get(1)?))
+        })?;
+        data.redacted_table = Self::select(conn, schema, "redacted", "id", |row| elem(row, 0))?;
+        data.sampled_table = Self::select(conn, schema, "sampled", "id, len, kept", |row| {
+            rusqlite::Result::Ok((elem(row, 0)?, (row.get(1)?, row.get(2)?)))
+        })?;
+        data.elem_type_kind_table =
+            Self::select(conn, schema, "elemTypeKind", "type, kind", |row| {
+                rusqlite::Result::Ok((sym(row, 0)?, sym(row, 1)?))
+            })?;
+        data.same_string_table = Self::select(conn, schema, "sameString", "id, other", |row| {
+            rusqlite::Result::Ok((elem(row, 0)?, elem(row, 1)?))
+        })?;
+        // shapes are hashes, which are stored as signed integers
+        data.shape_table = Self::select(conn, schema, "shape", "id, shape", |row| {
+            let shape: i64 = row.get(1)?;
+            rusqlite::Result::Ok((elem(row, 0)?, shape as u64))
+        })?;
+        data.string_hash_table =
+            Self::select(conn, schema, "stringHash", "id, hash, len", |row| {
+                let hash: i64 = row.get(1)?;
+                rusqlite::Result::Ok((elem(row, 0)?, (hash as u64, row.get(2)?)))
+            })?;
+        data.shape_field_table =
+            Self::select(conn, schema, "shapeField", "shape, pos, field", |row| {
+                let shape: i64 = row.get(0)?;
+                rusqlite::Result::Ok(((shape as u64, row.get(1)?), sym(row, 2)?))
+            })?;
+        data.parse_error_table =
+            Self::select(conn, schema, "parseError", "file, line, message", |row| {
+                rusqlite::Result::Ok(((sym(row, 0)?, row.get(1)?), sym(row, 2)?))
+            })?;
+        data.root_meta_table = Self::select(conn, schema, "rootMeta", "file, key, value", |row| {
+            rusqlite::Result::Ok((sym(row, 0)?, sym(row, 1)?, sym(row, 2)?))
+        })?;
+        data.depth_table = Self::select(conn, schema, "depth", "id, depth", |row| {
+            rusqlite::Result::Ok((elem(row, 0)?, row.get(1)?))
+        })?;
+        data.subtree_size_table = Self::select(conn, schema, "subtreeSize", "id, size", |row| {
+            rusqlite::Result::Ok((elem(row, 0)?, row.get(1)?))
+        })?;
+        data.char_table = Self::select(conn, schema, "char", "id, codepoint", |row| {
+            rusqlite::Result::Ok((elem(row, 0)?, row.get(1)?))
+        })?;
+
+        rusqlite::Result::Ok(data)
+    }
+
+    /// Returns the `columns` of every row of the table of `relation`,
+    /// converted with `row_fn`, or no rows if the database has no such table.
+    fn select<T, C: FromIterator<T>>(
+        conn: &rusqlite::Connection,
+        schema: &SchemaConfig,
+        relation: &str,
+        columns: &str,
+        row_fn: impl FnMut(&rusqlite::Row) -> rusqlite::Result<T>,
+    ) -> rusqlite::Result<C> {
+        let table = schema.table_name(relation);
+        if !Self::has_table(conn, &table)? {
+            return rusqlite::Result::Ok(std::iter::empty().collect());
+        }
+
+        let mut select_rows = conn.prepare(&format!("SELECT {} FROM {};", columns, table))?;
+        let rows = select_rows.query_map((), row_fn)?.collect();
+        rows
+    }
+
+    /// Remove facts about files in `data` from an existing database.
+    fn remove_files<K: Display + Eq + Hash>(
+        conn: &rusqlite::Connection,
+        schema: &SchemaConfig,
+        data: &BackendData<K>,
+        map_keys_are_elems: bool,
+    ) -> rusqlite::Result<()> {
+        conn.execute_batch(
+            "CREATE TEMP TABLE __removedRoot (file INTEGER NOT NULL);
+            CREATE TEMP TABLE __removedElem (root INTEGER NOT NULL, id INTEGER NOT NULL);",
+        )?;
+
+        {
+            let mut insert_root = conn.prepare(&schema.render(
+                "INSERT INTO __removedRoot
+                SELECT {t}rootElem.file FROM {t}rootElem INNER JOIN {t}_SymbolTable
+                ON {t}rootElem.file = {t}_SymbolTable.id
+                WHERE {t}_SymbolTable.symbol = ?1;",
+            ))?;
+
+            let mut insert_document_roots = conn.prepare(&schema.render(
+                "INSERT INTO __removedRoot
+                SELECT {t}rootElem.file FROM {t}rootElem INNER JOIN {t}_SymbolTable
+                ON {t}rootElem.file = {t}_SymbolTable.id
+                WHERE substr({t}_SymbolTable.symbol, 1, length(?1) + 1) = ?1 || ':';",
+            ))?;
+
+            let mut delete_file_format = conn.prepare(&schema.render(
+                "DELETE FROM {t}fileFormat
+                WHERE file IN (SELECT id FROM {t}_SymbolTable WHERE symbol = ?1);",
+            ))?;
+
+            for file in data.root_elem_table.keys() {
+                if let Some(name) = data.symbol_table.get_by_right(file) {
+                    insert_root.execute([name])?;
+                }
+            }
+
+            for file in data.file_format_table.keys() {
+                if let Some(name) = data.symbol_table.get_by_right(file) {
+                    insert_root.execute([name])?;
+                    insert_document_roots.execute([name])?;
+                    delete_file_format.execute([name])?;
+                }
+            }
+
+            // databases created before invalid documents could be skipped
+            // have no parseError table
+            if Self::has_table(conn, &schema.table_name("parseError"))? {
+                let mut delete_parse_error = conn.prepare(&schema.render(
+                    "DELETE FROM {t}parseError
+                    WHERE file IN (SELECT id FROM {t}_SymbolTable WHERE symbol = ?1);",
+                ))?;
+
+                for file in data.file_format_table.keys() {
+                    if let Some(name) = data.symbol_table.get_by_right(file) {
+                        delete_parse_error.execute([name])?;
+                    }
+                }
+            }
+        }
+
+        // databases created before roots had metadata have no rootMeta table
+        let root_meta_table = schema.table_name("rootMeta");
+        if Self::has_table(conn, &root_meta_table)? {
+            conn.execute_batch(&format!(
+                "DELETE FROM {} WHERE file IN (SELECT file FROM __removedRoot);",
+                root_meta_table
+            ))?;
+        }
+
+        let map_key_edges = if map_keys_are_elems {
+            "UNION ALL SELECT id, key FROM {t}map"
+        } else {
+            ""
+        };
+
+        conn.execute_batch(&schema.render(&format!(
+            "INSERT INTO __removedElem
+            WITH RECURSIVE
+                edge(parent, child) AS (
+                    SELECT id, value FROM {{t}}struct
+                    UNION ALL SELECT id, value FROM {{t}}seq
+                    UNION ALL SELECT id, value FROM {{t}}tuple
+                    UNION ALL SELECT id, value FROM {{t}}map
+                    {}
+                ),
+                subtree(root, id) AS (
+                    SELECT elem, elem FROM {{t}}rootElem
+                    WHERE file IN (SELECT file FROM __removedRoot)
+                    UNION
+                    SELECT subtree.root, edge.child
+                    FROM edge INNER JOIN subtree ON edge.parent = subtree.id
+                )
+            SELECT root, id FROM subtree;",
+            map_key_edges
+        )))?;
+
+        // map keys of the string-key schema are elements that no fact refers
+        // to. Elements of a root are numbered consecutively, so the keys of a
+        // removed root are the unreferenced elements among its identifiers.
+        if !map_keys_are_elems {
+            conn.execute_batch(&schema.render(
+                "INSERT INTO __removedElem
+                SELECT removed.root, {t}type.id FROM {t}type INNER JOIN (
+                    SELECT root, MIN(id) AS first, MAX(id) AS last
+                    FROM __removedElem GROUP BY root
+                ) AS removed ON {t}type.id BETWEEN removed.first AND removed.last
+                WHERE {t}type.id NOT IN (SELECT id FROM __removedElem)
+                AND {t}type.id NOT IN (
+                    SELECT value FROM {t}struct
+                    UNION SELECT value FROM {t}seq
+                    UNION SELECT value FROM {t}tuple
+                    UNION SELECT value FROM {t}map
+                    UNION SELECT elem FROM {t}rootElem
+                );",
+            ))?;
+        }
+
+        conn.execute_batch(&schema.render(
+            "DELETE FROM {t}bool WHERE id IN (SELECT id FROM __removedElem);
+            DELETE FROM {t}number WHERE id IN (SELECT id FROM __removedElem);
+            DELETE FROM {t}string WHERE id IN (SELECT id FROM __removedElem);
+            DELETE FROM {t}map WHERE id IN (SELECT id FROM __removedElem);
+            DELETE FROM {t}struct WHERE id IN (SELECT id FROM __removedElem);
+            DELETE FROM {t}seq WHERE id IN (SELECT id FROM __removedElem);
+            DELETE FROM {t}tuple WHERE id IN (SELECT id FROM __removedElem);
+            DELETE FROM {t}structType WHERE id IN (SELECT id FROM __removedElem);
+            DELETE FROM {t}variantType WHERE id IN (SELECT id FROM __removedElem);
+            DELETE FROM {t}type WHERE id IN (SELECT id FROM __removedElem);
+            DELETE FROM {t}rootElem WHERE file IN (SELECT file FROM __removedRoot);",
+        ))?;
+
+        // databases created by older versions may not have these tables
+        for relation in [
+            "key",
+            "fullString",
+            "schemaType",
+            "datetime",
+            "uuid",
+            "ip",
+            "url",
+            "email",
+            "bytes",
+            "bytesEncoding",
+            "declaredLen",
+            "absentField",
+            "unit",
+            "redacted",
+            "sampled",
+            "shape",
+            "stringHash",
+            "numberParts",
+            "numberText",
+            "depth",
+            "subtreeSize",
+            "char",
+        ] {
+            let table = schema.table_name(relation);
+            if Self::has_table(conn, &table)? {
+                conn.execute_batch(&format!(
+                    "DELETE FROM {} WHERE id IN (SELECT id FROM __removedElem);",
+                    table
+                ))?;
+            }
+        }
+
+        // elements of other files may have the same strings as removed elements
+        let same_string_table = schema.table_name("sameString");
+        if Self::has_table(conn, &same_string_table)? {
+            conn.execute_batch(&format!(
+                "DELETE FROM {} WHERE id IN (SELECT id FROM __removedElem)
+                OR other IN (SELECT id FROM __removedElem);",
+                same_string_table
+            ))?;
+        }
+
+        conn.execute_batch(
+            "DROP TABLE __removedRoot;
+            DROP TABLE __removedElem;",
+        )
+    }
+
+    /// Remove symbols that no fact refers to from the symbol table, except the
+    /// names of element types, which are always interned.
+    fn remove_orphan_symbols(
+        conn: &rusqlite::Connection,
+        schema: &SchemaConfig,
+    ) -> rusqlite::Result<()> {
+        let symbol_table = schema.symbol_table_name();
+        let tables: Vec<String> = conn
+            .prepare("SELECT name FROM sqlite_master WHERE type = 'table';")?
+            .query_map((), |row| row.get(0))?
+            .collect::<rusqlite::Result<_>>()?;
+
+        let mut referenced = vec![
+            schema.render("SELECT file FROM {t}rootElem"),
+            schema.render("SELECT type FROM {t}type"),
+        ];
+        for table in tables.iter() {
+            let mut select_columns = conn.prepare(
+                "SELECT \"from\" FROM pragma_foreign_key_list(?1) WHERE \"table\" = ?2;",
+            )?;
+            let columns = select_columns
+                .query_map((table, &symbol_table), |row| row.get::<_, String>(0))?
+                .collect::<rusqlite::Result<Vec<_>>>()?;
+            for column in columns {
+                referenced.push(format!("SELECT \"{}\" FROM \"{}\"", column, table));
+            }
+        }
+
+        let elem_type_names: Vec<String> = vector::ELEM_TYPE_NAMES
+            .iter()
+            .map(|name| format!("'{}'", name))
+            .collect();
+        let orphans = format!(
+            "SELECT id FROM {} WHERE id NOT IN ({}) AND symbol NOT IN ({})",
+            symbol_table,
+            referenced.join(" UNION "),
+            elem_type_names.join(", ")
+        );
+
+        // symbol tables of databases with compressed strings are views
+        let symbol_tables = if Self::has_table(conn, &schema.table_name("_CompressedSymbolTable"))?
+        {
+            vec![
+                schema.table_name("_ShortSymbolTable"),
+                schema.table_name("_CompressedSymbolTable"),
+            ]
+        } else {
+            vec![symbol_table.clone()]
+        };
+
+        conn.execute_batch(&format!("CREATE TEMP TABLE __orphanSymbol AS {};", orphans))?;
+        for table in symbol_tables {
+            conn.execute_batch(&format!(
+                "DELETE FROM {} WHERE id IN (SELECT id FROM __orphanSymbol);",
+                table
+            ))?;
+        }
+        conn.execute_batch("DROP TABLE __orphanSymbol;")
+    }
+
+    /// The type, name, table name, and SQL of every table, index, and view of
+    /// the database `db` of `conn`, ordered so that objects can be created in
+    /// order.
+    fn schema_objects(
+        conn: &rusqlite::Connection,
+        db: &str,
+    ) -> rusqlite::Result<Vec<(String, String, String, String)>> {
+        let mut select_objects = conn.prepare(&format!(
+            "SELECT type, name, tbl_name, sql FROM {db}.sqlite_master
+            WHERE type IN ('table', 'index', 'view') AND sql IS NOT NULL
+            AND name NOT LIKE 'sqlite_%'
+            ORDER BY CASE type WHEN 'table' THEN 0 WHEN 'index' THEN 1 ELSE 2 END, rowid;"
+        ))?;
+
+        let objects = select_objects
+            .query_map((), |row| {
+                rusqlite::Result::Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?))
+            })?
+            .collect();
+        objects
+    }
+
+    /// Create the `objects` of the attached database `db` that are missing from
+    /// the main database of `conn`, and copy the rows of their tables.
+    fn copy_objects<'a>(
+        conn: &rusqlite::Connection,
+        db: &str,
+        objects: impl Iterator<Item = &'a (String, String, String, String)>,
+    ) -> rusqlite::Result<()> {
+        let tx = conn.unchecked_transaction()?;
+        for (kind, name, _, sql) in objects {
+            let exists: bool = tx.query_row(
+                "SELECT EXISTS (SELECT 1 FROM main.sqlite_master WHERE type = ?1 AND name = ?2);",
+                [kind, name],
+                |row| row.get(0),
+            )?;
+            if !exists {
+                // unqualified names in the SQL of objects refer to the main database
+                tx.execute_batch(sql)?;
+            }
+
+            if kind == "table" {
+                tx.execute_batch(&format!(
+                    "INSERT OR IGNORE INTO main.\"{name}\" SELECT * FROM {db}.\"{name}\";"
+                ))?;
+            }
+        }
+        tx.commit()
+    }
+
+    /// Whether the database has a table named `name`.
+    fn has_table(conn: &rusqlite::Connection, name: &str) -> rusqlite::Result<bool> {
+        conn.query_row(
+            "SELECT EXISTS (SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = ?1);",
+            [name],
+            |row| row.get(0),
+        )
+    }
+
+    /// Create a table, and its view if the schema has views enabled. If
+    /// `if_missing` is set, an existing table or view is kept.
+    fn create_table(
+        conn: &rusqlite::Connection,
+        schema: &SchemaConfig,
+        if_missing: bool,
+        table_sql: &str,
+        view_sql: &str,
+    ) -> rusqlite::Result<()> {
+        let render = |sql: &str, kind: &str| {
+            let sql = schema.render(sql);
+            if if_missing {
+                sql.replacen(
+                    &format!("CREATE {} ", kind),
+                    &format!("CREATE {} IF NOT EXISTS ", kind),
+                    1,
+                )
+            } else {
+                sql
+            }
+        };
+
+        conn.execute_batch(&render(table_sql, "TABLE"))?;
+        if schema.create_views {
+            conn.execute_batch(&render(view_sql, "VIEW"))?;
+        }
+
+        rusqlite::Result::Ok(())
+    }
+
+    /// Create the tables of relations other than `map`, and the symbol
+    /// table. If `if_missing` is set, only missing tables of relations are
+    /// created, e.g. to migrate a database of an older schema version.
+    fn create_tables(
+        conn: &rusqlite::Connection,
+        schema: &SchemaConfig,
+        options: &LoadOptions,
+        if_missing: bool,
+    ) -> rusqlite::Result<()> {
+        if if_missing {
+            // the symbol table exists in every schema version
+        } else if options.compress_strings.is_some() {
+            conn.execute_batch(&schema.render(
+                "CREATE TABLE {t}_ShortSymbolTable (
+                    id INTEGER NOT NULL,
+                    symbol TEXT NOT NULL,
+                    PRIMARY KEY (id)
+                );
+                CREATE TABLE {t}_CompressedSymbolTable (
+                    id INTEGER NOT NULL,
+                    data BLOB NOT NULL,
+                    PRIMARY KEY (id)
+                );
+                CREATE VIEW {t}_SymbolTable AS
+                SELECT id, symbol FROM {t}_ShortSymbolTable
+                UNION ALL
+                SELECT id, serde_datalog_decompress(data) AS symbol
+                FROM {t}_CompressedSymbolTable;",
+            ))?;
+        } else {
+            conn.execute_batch(&schema.render(
+                "CREATE TABLE {t}_SymbolTable (
+                    id INTEGER NOT NULL,
+                    symbol TEXT NOT NULL,
+                    PRIMARY KEY (id)
+                );",
+            ))?;
+        }
+
+        Self::create_table(
+            conn,
+            schema,
+            if_missing,
+            "CREATE TABLE {t}rootElem (
+                file INTEGER NOT NULL,
+                elem INTEGER NOT NULL,
+                PRIMARY KEY (file)
+            );",
+            "CREATE VIEW {v}rootElem AS
+            SELECT {t}_SymbolTable.symbol AS file, {t}rootElem.elem as elem
+            FROM {t}rootElem INNER JOIN {t}_SymbolTable
+            ON {t}rootElem.file = {t}_SymbolTable.id;",
+        )?;
+
+        Self::create_table(
+            conn,
+            schema,
+            if_missing,
+            "CREATE TABLE {t}fileFormat (
+                file INTEGER NOT NULL,
+                format INTEGER NOT NULL,
+                PRIMARY KEY (file),
+                FOREIGN KEY(file) REFERENCES {t}_SymbolTable(id),
+                FOREIGN KEY(format) REFERENCES {t}_SymbolTable(id)
+            );",
+            "CREATE VIEW {v}fileFormat AS
+            SELECT s1.symbol AS file, s2.symbol AS format
+            FROM {t}fileFormat
+                INNER JOIN {t}_SymbolTable AS s1 ON {t}fileFormat.file = s1.id
+                INNER JOIN {t}_SymbolTable AS s2 ON {t}fileFormat.format = s2.id;",
+        )?;
+
+        Self::create_table(
+            conn,
+            schema,
+            if_missing,
+            "CREATE TABLE {t}keyField (
+                type INTEGER NOT NULL,
+                field INTEGER NOT NULL,
+                PRIMARY KEY (type, field),
+                FOREIGN KEY(type) REFERENCES {t}_SymbolTable(id),
+                FOREIGN KEY(field) REFERENCES {t}_SymbolTable(id)
+            );",
+            "CREATE VIEW {v}keyField AS
+            SELECT s1.symbol AS type, s2.symbol AS field
+            FROM {t}keyField
+                INNER JOIN {t}_SymbolTable AS s1 ON {t}keyField.type = s1.id
+                INNER JOIN {t}_SymbolTable AS s2 ON {t}keyField.field = s2.id;",
+        )?;
+
+        Self::create_table(
+            conn,
+            schema,
+            if_missing,
+            "CREATE TABLE {t}type (
+                id INTEGER NOT NULL,
+                type INTEGER NOT NULL,
+                PRIMARY KEY (id)
+            );",
+            "CREATE VIEW {v}type AS
+            SELECT {t}type.id AS id, {t}_SymbolTable.symbol AS type
+            FROM {t}type INNER JOIN {t}_SymbolTable
+            ON {t}type.type = {t}_SymbolTable.id;",
+        )?;
+
+        Self::create_table(
+            conn,
+            schema,
+            if_missing,
+            "CREATE TABLE {t}bool (
+                id INTEGER NOT NULL,
+                value INTEGER NOT NULL,
+                PRIMARY KEY (id),
+                FOREIGN KEY(id) REFERENCES {t}type(id)
+            );",
+            "CREATE VIEW {v}bool AS
+            SELECT id, value FROM {t}bool;",
+        )?;
+
+        Self::create_table(
+            conn,
+            schema,
+            if_missing,
+            "CREATE TABLE {t}number (
+                id INTEGER NOT NULL,
+                value INTEGER NOT NULL,
+                PRIMARY KEY (id),
+                FOREIGN KEY(id) REFERENCES {t}type(id)
+            );",
+            "CREATE VIEW {v}number AS
+            SELECT id, value FROM {t}number;",
+        )?;
+
+        Self::create_table(
+            conn,
+            schema,
+            if_missing,
+            "CREATE TABLE {t}numberParts (
+                id INTEGER NOT NULL,
+                hi INTEGER NOT NULL,
+                lo INTEGER NOT NULL,
+                PRIMARY KEY (id),
+                FOREIGN KEY(id) REFERENCES {t}type(id)
+            );",
+            "CREATE VIEW {v}numberParts AS
+            SELECT id, hi, lo FROM {t}numberParts;",
+        )?;
+
+        Self::create_table(
+            conn,
+            schema,
+            if_missing,
+            "CREATE TABLE {t}numberText (
+                id INTEGER NOT NULL,
+                value TEXT NOT NULL,
+                PRIMARY KEY (id),
+                FOREIGN KEY(id) REFERENCES {t}type(id)
+            );",
+            "CREATE VIEW {v}numberText AS
+            SELECT id, value FROM {t}numberText;",
+        )?;
+
+        Self::create_table(
+            conn,
+            schema,
+            if_missing,
+            "CREATE TABLE {t}string (
+                id INTEGER NOT NULL,
+                value INTEGER NOT NULL,
+                PRIMARY KEY (id),
+                FOREIGN KEY(id) REFERENCES {t}type(id),
+                FOREIGN KEY(value) REFERENCES {t}_SymbolTable(id)
+            );",
+            "CREATE VIEW {v}string AS
+            SELECT {t}string.id AS id, {t}_SymbolTable.symbol AS value
+            FROM {t}string INNER JOIN {t}_SymbolTable
+            ON {t}string.value = {t}_SymbolTable.id;",
+        )?;
+
+        Self::create_table(
+            conn,
+            schema,
+            if_missing,
+            "CREATE TABLE {t}fullString (
+                id INTEGER NOT NULL,
+                value TEXT NOT NULL,
+                PRIMARY KEY (id),
+                FOREIGN KEY(id) REFERENCES {t}type(id)
+            );",
+            "CREATE VIEW {v}fullString AS
+            SELECT id, value FROM {t}fullString;",
+        )?;
+
+        Self::create_table(
+            conn,
+            schema,
+            if_missing,
+            "CREATE TABLE {t}struct (
+                id INTEGER NOT NULL,
+                field INTEGER NOT NULL,
+                value INTEGER NOT NULL,
+                PRIMARY KEY (id, field),
+                FOREIGN KEY(id) REFERENCES {t}type(id),
+                FOREIGN KEY(field) REFERENCES {t}_SymbolTable(id),
+                FOREIGN KEY(value) REFERENCES {t}type(id)
+            );",
+            "CREATE VIEW {v}struct AS
+            SELECT {t}struct.id AS id, {t}_SymbolTable.symbol AS field, {t}struct.value AS value
+            FROM {t}struct INNER JOIN {t}_SymbolTable
+            ON {t}struct.field = {t}_SymbolTable.id;",
+        )?;
+
+        Self::create_table(
+            conn,
+            schema,
+            if_missing,
+            "CREATE TABLE {t}key (
+                id INTEGER NOT NULL,
+                key INTEGER NOT NULL,
+                PRIMARY KEY (id, key),
+                FOREIGN KEY(id) REFERENCES {t}type(id),
+                FOREIGN KEY(key) REFERENCES {t}type(id)
+            );",
+            "CREATE VIEW {v}key AS
+            SELECT id, key FROM {t}key;",
+        )?;
+
+        Self::create_table(
+            conn,
+            schema,
+            if_missing,
+            "CREATE TABLE {t}seq (
+                id INTEGER NOT NULL,
+                pos INTEGER NOT NULL,
+                value INTEGER NOT NULL,
+                PRIMARY KEY (id, pos),
+                FOREIGN KEY(id) REFERENCES {t}type(id),
+                FOREIGN KEY(value) REFERENCES {t}type(id)
+            );",
+            "CREATE VIEW {v}seq AS
+            SELECT id, pos, value FROM {t}seq;",
+        )?;
+
+        Self::create_table(
+            conn,
+            schema,
+            if_missing,
+            "CREATE TABLE {t}tuple (
+                id INTEGER NOT NULL,
+                pos INTEGER NOT NULL,
+                value INTEGER NOT NULL,
+                PRIMARY KEY (id, pos),
+                FOREIGN KEY(id) REFERENCES {t}type(id),
+                FOREIGN KEY(value) REFERENCES {t}type(id)
+            );",
+            "CREATE VIEW {v}tuple AS
+            SELECT id, pos, value FROM {t}tuple;",
+        )?;
+
+        Self::create_table(
+            conn,
+            schema,
+            if_missing,
+            "CREATE TABLE {t}structType (
+                id INTEGER NOT NULL,
+                type INTEGER NOT NULL,
+                PRIMARY KEY (id),
+                FOREIGN KEY(id) REFERENCES {t}type(id),
+                FOREIGN KEY(type) REFERENCES {t}_SymbolTable(id)
+            );",
+            "CREATE VIEW {v}structType AS
+            SELECT {t}structType.id AS id, {t}_SymbolTable.symbol AS type
+            FROM {t}structType INNER JOIN {t}_SymbolTable
+            ON {t}structType.type = {t}_SymbolTable.id;",
+        )?;
+
+        Self::create_table(
+            conn,
+            schema,
+            if_missing,
+            "CREATE TABLE {t}variantType (
+                id INTEGER NOT NULL,
+                type INTEGER NOT NULL,
+                variant INTEGER NOT NULL,
+                PRIMARY KEY (id),
+                FOREIGN KEY(id) REFERENCES {t}type(id),
+                FOREIGN KEY(type) REFERENCES {t}_SymbolTable(id),
+                FOREIGN KEY(variant) REFERENCES {t}_SymbolTable(id)
+            );",
+            "CREATE VIEW {v}variantType AS
+            SELECT {t}variantType.id AS id, s1.symbol AS type, s2.symbol AS variant
+            FROM {t}variantType
+                INNER JOIN {t}_SymbolTable AS s1 ON {t}variantType.type = s1.id
+                INNER JOIN {t}_SymbolTable AS s2 ON {t}variantType.variant = s2.id;",
+        )?;
+
+        Self::create_table(
+            conn,
+            schema,
+            if_missing,
+            "CREATE TABLE {t}schemaType (
+                id INTEGER NOT NULL,
+                schema INTEGER NOT NULL,
+                PRIMARY KEY (id),
+                FOREIGN KEY(id) REFERENCES {t}type(id),
+                FOREIGN KEY(schema) REFERENCES {t}_SymbolTable(id)
+            );",
+            "CREATE VIEW {v}schemaType AS
+            SELECT {t}schemaType.id AS id, {t}_SymbolTable.symbol AS schema
+            FROM {t}schemaType INNER JOIN {t}_SymbolTable
+            ON {t}schemaType.schema = {t}_SymbolTable.id;",
+        )?;
+
+        Self::create_table(
+            conn,
+            schema,
+            if_missing,
+            "CREATE TABLE {t}datetime (
+                id INTEGER NOT NULL,
+                micros INTEGER NOT NULL,
+                offset INTEGER NOT NULL,
+                PRIMARY KEY (id),
+                FOREIGN KEY(id) REFERENCES {t}type(id)
+            );",
+            "CREATE VIEW {v}datetime AS
+            SELECT id, micros, offset FROM {t}datetime;",
+        )?;
+
+        Self::create_table(
+            conn,
+            schema,
+            if_missing,
+            "CREATE TABLE {t}uuid (
+                id INTEGER NOT NULL,
+                hi INTEGER NOT NULL,
+                lo INTEGER NOT NULL,
+                PRIMARY KEY (id),
+                FOREIGN KEY(id) REFERENCES {t}type(id)
+            );",
+            "CREATE VIEW {v}uuid AS
+            SELECT id, hi, lo FROM {t}uuid;",
+        )?;
+
+        Self::create_table(
+            conn,
+            schema,
+            if_missing,
+            "CREATE TABLE {t}ip (
+                id INTEGER NOT NULL,
+                version INTEGER NOT NULL,
+                address INTEGER NOT NULL,
+                PRIMARY KEY (id),
+                FOREIGN KEY(id) REFERENCES {t}type(id),
+                FOREIGN KEY(address) REFERENCES {t}_SymbolTable(id)
+            );",
+            "CREATE VIEW {v}ip AS
+            SELECT {t}ip.id AS id, {t}ip.version AS version, {t}_SymbolTable.symbol AS address
+            FROM {t}ip INNER JOIN {t}_SymbolTable
+            ON {t}ip.address = {t}_SymbolTable.id;",
+        )?;
 
-                CREATE TABLE __SymbolTable (
-                    id INTEGER NOT NULL,
-                    symbol TEXT NOT NULL,
-                    PRIMARY KEY (id)
-                );
+        Self::create_table(
+            conn,
+            schema,
+            if_missing,
+            "CREATE TABLE {t}url (
+                id INTEGER NOT NULL,
+                scheme INTEGER NOT NULL,
+                host INTEGER NOT NULL,
+                PRIMARY KEY (id),
+                FOREIGN KEY(id) REFERENCES {t}type(id),
+                FOREIGN KEY(scheme) REFERENCES {t}_SymbolTable(id),
+                FOREIGN KEY(host) REFERENCES {t}_SymbolTable(id)
+            );",
+            "CREATE VIEW {v}url AS
+            SELECT {t}url.id AS id, s1.symbol AS scheme, s2.symbol AS host
+            FROM {t}url
+                INNER JOIN {t}_SymbolTable AS s1 ON {t}url.scheme = s1.id
+                INNER JOIN {t}_SymbolTable AS s2 ON {t}url.host = s2.id;",
+        )?;
 
-                CREATE TABLE _rootElem (
-                    file INTEGER NOT NULL,
-                    elem INTEGER NOT NULL,
-                    PRIMARY KEY (file)
-                );
+        Self::create_table(
+            conn,
+            schema,
+            if_missing,
+            "CREATE TABLE {t}email (
+                id INTEGER NOT NULL,
+                local INTEGER NOT NULL,
+                domain INTEGER NOT NULL,
+                PRIMARY KEY (id),
+                FOREIGN KEY(id) REFERENCES {t}type(id),
+                FOREIGN KEY(local) REFERENCES {t}_SymbolTable(id),
+                FOREIGN KEY(domain) REFERENCES {t}_SymbolTable(id)
+            );",
+            "CREATE VIEW {v}email AS
+            SELECT {t}email.id AS id, s1.symbol AS local, s2.symbol AS domain
+            FROM {t}email
+                INNER JOIN {t}_SymbolTable AS s1 ON {t}email.local = s1.id
+                INNER JOIN {t}_SymbolTable AS s2 ON {t}email.domain = s2.id;",
+        )?;
 
-                CREATE VIEW rootElem AS
-                SELECT __SymbolTable.symbol AS file, _rootElem.elem as elem
-                FROM _rootElem INNER JOIN __SymbolTable
-                ON _rootElem.file = __SymbolTable.id;
+        Self::create_table(
+            conn,
+            schema,
+            if_missing,
+            "CREATE TABLE {t}bytes (
+                id INTEGER NOT NULL,
+                value INTEGER NOT NULL,
+                PRIMARY KEY (id),
+                FOREIGN KEY(id) REFERENCES {t}type(id),
+                FOREIGN KEY(value) REFERENCES {t}_SymbolTable(id)
+            );",
+            "CREATE VIEW {v}bytes AS
+            SELECT {t}bytes.id AS id, {t}_SymbolTable.symbol AS value
+            FROM {t}bytes INNER JOIN {t}_SymbolTable
+            ON {t}bytes.value = {t}_SymbolTable.id;",
+        )?;
 
-                CREATE TABLE _type (
-                    id INTEGER NOT NULL,
-                    type INTEGER NOT NULL,
-                    PRIMARY KEY (id)
-                );
+        Self::create_table(
+            conn,
+            schema,
+            if_missing,
+            "CREATE TABLE {t}bytesEncoding (
+                id INTEGER NOT NULL,
+                encoding INTEGER NOT NULL,
+                length INTEGER NOT NULL,
+                PRIMARY KEY (id),
+                FOREIGN KEY(id) REFERENCES {t}type(id),
+                FOREIGN KEY(encoding) REFERENCES {t}_SymbolTable(id)
+            );",
+            "CREATE VIEW {v}bytesEncoding AS
+            SELECT {t}bytesEncoding.id AS id, {t}_SymbolTable.symbol AS encoding,
+                {t}bytesEncoding.length AS length
+            FROM {t}bytesEncoding INNER JOIN {t}_SymbolTable
+            ON {t}bytesEncoding.encoding = {t}_SymbolTable.id;",
+        )?;
 
-                CREATE VIEW type AS
-                SELECT _type.id AS id, __SymbolTable.symbol AS type
-                FROM _type INNER JOIN __SymbolTable
-                ON _type.type = __SymbolTable.id;
+        Self::create_table(
+            conn,
+            schema,
+            if_missing,
+            "CREATE TABLE {t}declaredLen (
+                id INTEGER NOT NULL,
+                len INTEGER NOT NULL,
+                PRIMARY KEY (id),
+                FOREIGN KEY(id) REFERENCES {t}type(id)
+            );",
+            "CREATE VIEW {v}declaredLen AS
+            SELECT id, len FROM {t}declaredLen;",
+        )?;
 
-                CREATE TABLE _bool (
-                    id INTEGER NOT NULL,
-                    value INTEGER NOT NULL,
-                    PRIMARY KEY (id),
-                    FOREIGN KEY(id) REFERENCES _type(id)
-                );
+        Self::create_table(
+            conn,
+            schema,
+            if_missing,
+            "CREATE TABLE {t}absentField (
+                id INTEGER NOT NULL,
+                field INTEGER NOT NULL,
+                PRIMARY KEY (id, field),
+                FOREIGN KEY(id) REFERENCES {t}type(id),
+                FOREIGN KEY(field) REFERENCES {t}_SymbolTable(id)
+            );",
+            "CREATE VIEW {v}absentField AS
+            SELECT {t}absentField.id AS id, {t}_SymbolTable.symbol AS field
+            FROM {t}absentField INNER JOIN {t}_SymbolTable
+            ON {t}absentField.field = {t}_SymbolTable.id;",
+        )?;
 
-                CREATE VIEW bool AS
-                SELECT id, value FROM _bool;
+        Self::create_table(
+            conn,
+            schema,
+            if_missing,
+            "CREATE TABLE {t}unit (
+                id INTEGER NOT NULL,
+                PRIMARY KEY (id),
+                FOREIGN KEY(id) REFERENCES {t}type(id)
+            );",
+            "CREATE VIEW {v}unit AS
+            SELECT id FROM {t}unit;",
+        )?;
 
-                CREATE TABLE _number (
-                    id INTEGER NOT NULL,
-                    value INTEGER NOT NULL,
-                    PRIMARY KEY (id),
-                    FOREIGN KEY(id) REFERENCES _type(id)
-                );
+        Self::create_table(
+            conn,
+            schema,
+            if_missing,
+            "CREATE TABLE {t}truncated (
+                relation INTEGER NOT NULL,
+                quota INTEGER NOT NULL,
+                PRIMARY KEY (relation),
+                FOREIGN KEY(relation) REFERENCES {t}_SymbolTable(id)
+            );",
+            "CREATE VIEW {v}truncated AS
+            SELECT {t}_SymbolTable.symbol AS relation, {t}truncated.quota AS quota
+            FROM {t}truncated INNER JOIN {t}_SymbolTable
+            ON {t}truncated.relation = {t}_SymbolTable.id;",
+        )?;
 
-                CREATE VIEW number AS
-                SELECT id, value FROM _number;
+        Self::create_table(
+            conn,
+            schema,
+            if_missing,
+            "CREATE TABLE {t}redacted (
+                id INTEGER NOT NULL,
+                PRIMARY KEY (id),
+                FOREIGN KEY(id) REFERENCES {t}type(id)
+            );",
+            "CREATE VIEW {v}redacted AS
+            SELECT id FROM {t}redacted;",
+        )?;
 
-                CREATE TABLE _string (
-                    id INTEGER NOT NULL,
-                    value INTEGER NOT NULL,
-                    PRIMARY KEY (id),
-                    FOREIGN KEY(id) REFERENCES _type(id),
-                    FOREIGN KEY(value) REFERENCES __SymbolTable(id)
-                );
+        Self::create_table(
+            conn,
+            schema,
+            if_missing,
+            "CREATE TABLE {t}sampled (
+                id INTEGER NOT NULL,
+                len INTEGER NOT NULL,
+                kept INTEGER NOT NULL,
+                PRIMARY KEY (id),
+                FOREIGN KEY(id) REFERENCES {t}type(id)
+            );",
+            "CREATE VIEW {v}sampled AS
+            SELECT id, len, kept FROM {t}sampled;",
+        )?;
 
-                CREATE VIEW string AS
-                SELECT _string.id AS id, __SymbolTable.symbol AS value
-                FROM _string INNER JOIN __SymbolTable
-                ON _string.value = __SymbolTable.id;
+        Self::create_table(
+            conn,
+            schema,
+            if_missing,
+            "CREATE TABLE {t}sameString (
+                id INTEGER NOT NULL,
+                other INTEGER NOT NULL,
+                PRIMARY KEY (id, other),
+                FOREIGN KEY(id) REFERENCES {t}type(id),
+                FOREIGN KEY(other) REFERENCES {t}type(id)
+            );",
+            "CREATE VIEW {v}sameString AS
+            SELECT id, other FROM {t}sameString;",
+        )?;
 
-                CREATE TABLE _struct (
-                    id INTEGER NOT NULL,
-                    field INTEGER NOT NULL,
-                    value INTEGER NOT NULL,
-                    PRIMARY KEY (id, field),
-                    FOREIGN KEY(id) REFERENCES _type(id),
-                    FOREIGN KEY(field) REFERENCES __SymbolTable(id),
-                    FOREIGN KEY(value) REFERENCES _type(id)
-                );
+        Self::create_table(
+            conn,
+            schema,
+            if_missing,
+            "CREATE TABLE {t}shape (
+                id INTEGER NOT NULL,
+                shape INTEGER NOT NULL,
+                PRIMARY KEY (id),
+                FOREIGN KEY(id) REFERENCES {t}type(id)
+            );",
+            "CREATE VIEW {v}shape AS
+            SELECT id, shape FROM {t}shape;",
+        )?;
 
-                CREATE VIEW struct AS
-                SELECT _struct.id AS id, __SymbolTable.symbol AS field, _struct.value AS value
-                FROM _struct INNER JOIN __SymbolTable
-                ON _struct.field = __SymbolTable.id;
+        Self::create_table(
+            conn,
+            schema,
+            if_missing,
+            "CREATE TABLE {t}stringHash (
+                id INTEGER NOT NULL,
+                hash INTEGER NOT NULL,
+                len INTEGER NOT NULL,
+                PRIMARY KEY (id),
+                FOREIGN KEY(id) REFERENCES {t}type(id)
+            );",
+            "CREATE VIEW {v}stringHash AS
+            SELECT id, hash, len FROM {t}stringHash;",
+        )?;
 
-                CREATE TABLE _seq (
-                    id INTEGER NOT NULL,
-                    pos INTEGER NOT NULL,
-                    value INTEGER NOT NULL,
-                    PRIMARY KEY (id, pos),
-                    FOREIGN KEY(id) REFERENCES _type(id),
-                    FOREIGN KEY(value) REFERENCES _type(id)
-                );
+        Self::create_table(
+            conn,
+            schema,
+            if_missing,
+            "CREATE TABLE {t}depth (
+                id INTEGER NOT NULL,
+                depth INTEGER NOT NULL,
+                PRIMARY KEY (id),
+                FOREIGN KEY(id) REFERENCES {t}type(id)
+            );",
+            "CREATE VIEW {v}depth AS
+            SELECT id, depth FROM {t}depth;",
+        )?;
 
-                CREATE VIEW seq AS
-                SELECT id, pos, value FROM _seq;
+        Self::create_table(
+            conn,
+            schema,
+            if_missing,
+            "CREATE TABLE {t}subtreeSize (
+                id INTEGER NOT NULL,
+                size INTEGER NOT NULL,
+                PRIMARY KEY (id),
+                FOREIGN KEY(id) REFERENCES {t}type(id)
+            );",
+            "CREATE VIEW {v}subtreeSize AS
+            SELECT id, size FROM {t}subtreeSize;",
+        )?;
 
-                CREATE TABLE _tuple (
-                    id INTEGER NOT NULL,
-                    pos INTEGER NOT NULL,
-                    value INTEGER NOT NULL,
-                    PRIMARY KEY (id, pos),
-                    FOREIGN KEY(id) REFERENCES _type(id),
-                    FOREIGN KEY(value) REFERENCES _type(id)
-                );
+        Self::create_table(
+            conn,
+            schema,
+            if_missing,
+            "CREATE TABLE {t}char (
+                id INTEGER NOT NULL,
+                codepoint INTEGER NOT NULL,
+                PRIMARY KEY (id),
+                FOREIGN KEY(id) REFERENCES {t}type(id)
+            );",
+            "CREATE VIEW {v}char AS
+            SELECT id, codepoint FROM {t}char;",
+        )?;
 
-                CREATE VIEW tuple AS
-                SELECT id, pos, value FROM _tuple;
+        Self::create_table(
+            conn,
+            schema,
+            if_missing,
+            "CREATE TABLE {t}shapeField (
+                shape INTEGER NOT NULL,
+                pos INTEGER NOT NULL,
+                field INTEGER NOT NULL,
+                PRIMARY KEY (shape, pos),
+                FOREIGN KEY(field) REFERENCES {t}_SymbolTable(id)
+            );",
+            "CREATE VIEW {v}shapeField AS
+            SELECT {t}shapeField.shape AS shape, {t}shapeField.pos AS pos,
+                {t}_SymbolTable.symbol AS field
+            FROM {t}shapeField INNER JOIN {t}_SymbolTable
+            ON {t}shapeField.field = {t}_SymbolTable.id;",
+        )?;
 
-                CREATE TABLE _structType (
-                    id INTEGER NOT NULL,
-                    type INTEGER NOT NULL,
-                    PRIMARY KEY (id),
-                    FOREIGN KEY(id) REFERENCES _type(id),
-                    FOREIGN KEY(type) REFERENCES __SymbolTable(id)
-                );
+        Self::create_table(
+            conn,
+            schema,
+            if_missing,
+            "CREATE TABLE {t}parseError (
+                file INTEGER NOT NULL,
+                line INTEGER NOT NULL,
+                message INTEGER NOT NULL,
+                PRIMARY KEY (file, line),
+                FOREIGN KEY(file) REFERENCES {t}_SymbolTable(id),
+                FOREIGN KEY(message) REFERENCES {t}_SymbolTable(id)
+            );",
+            "CREATE VIEW {v}parseError AS
+            SELECT s1.symbol AS file, {t}parseError.line AS line, s2.symbol AS message
+            FROM {t}parseError
+                INNER JOIN {t}_SymbolTable AS s1 ON {t}parseError.file = s1.id
+                INNER JOIN {t}_SymbolTable AS s2 ON {t}parseError.message = s2.id;",
+        )?;
 
-                CREATE VIEW structType AS
-                SELECT _structType.id AS id, __SymbolTable.symbol AS type
-                FROM _structType INNER JOIN __SymbolTable
-                ON _structType.type = __SymbolTable.id;
+        Self::create_table(
+            conn,
+            schema,
+            if_missing,
+            "CREATE TABLE {t}rootMeta (
+                file INTEGER NOT NULL,
+                key INTEGER NOT NULL,
+                value INTEGER NOT NULL,
+                PRIMARY KEY (file, key, value),
+                FOREIGN KEY(file) REFERENCES {t}_SymbolTable(id),
+                FOREIGN KEY(key) REFERENCES {t}_SymbolTable(id),
+                FOREIGN KEY(value) REFERENCES {t}_SymbolTable(id)
+            );",
+            "CREATE VIEW {v}rootMeta AS
+            SELECT s1.symbol AS file, s2.symbol AS key, s3.symbol AS value
+            FROM {t}rootMeta
+                INNER JOIN {t}_SymbolTable AS s1 ON {t}rootMeta.file = s1.id
+                INNER JOIN {t}_SymbolTable AS s2 ON {t}rootMeta.key = s2.id
+                INNER JOIN {t}_SymbolTable AS s3 ON {t}rootMeta.value = s3.id;",
+        )?;
 
-                CREATE TABLE _variantType (
-                    id INTEGER NOT NULL,
-                    type INTEGER NOT NULL,
-                    variant INTEGER NOT NULL,
-                    PRIMARY KEY (id),
-                    FOREIGN KEY(id) REFERENCES _type(id),
-                    FOREIGN KEY(type) REFERENCES __SymbolTable(id),
-                    FOREIGN KEY(variant) REFERENCES __SymbolTable(id)
-                );
+        Self::create_elem_type_kind_table(conn, schema, if_missing)
+    }
 
-                CREATE VIEW variantType AS
-                SELECT _variantType.id AS id, s1.symbol AS type, s2.symbol AS variant
-                FROM _variantType
-                    INNER JOIN __SymbolTable AS s1 ON _variantType.type = s1.id
-                    INNER JOIN __SymbolTable AS s2 ON _variantType.variant = s2.id;
+    /// Create the table of the kinds of element types. If `if_missing` is
+    /// set, an existing table is kept.
+    fn create_elem_type_kind_table(
+        conn: &rusqlite::Connection,
+        schema: &SchemaConfig,
+        if_missing: bool,
+    ) -> rusqlite::Result<()> {
+        Self::create_table(
+            conn,
+            schema,
+            if_missing,
+            "CREATE TABLE {t}elemTypeKind (
+                type INTEGER NOT NULL,
+                kind INTEGER NOT NULL,
+                PRIMARY KEY (type),
+                FOREIGN KEY(type) REFERENCES {t}_SymbolTable(id),
+                FOREIGN KEY(kind) REFERENCES {t}_SymbolTable(id)
+            );",
+            "CREATE VIEW {v}elemTypeKind AS
+            SELECT s1.symbol AS type, s2.symbol AS kind
+            FROM {t}elemTypeKind
+                INNER JOIN {t}_SymbolTable AS s1 ON {t}elemTypeKind.type = s1.id
+                INNER JOIN {t}_SymbolTable AS s2 ON {t}elemTypeKind.kind = s2.id;",
+        )
+    }
 
-                COMMIT;",
-            )?;
+    /// Insert symbols into the tables of a database that compresses strings.
+    #[cfg(feature = "string_compression")]
+    fn insert_compressed_symbols<K: Display + Eq + Hash>(
+        conn: &rusqlite::Connection,
+        schema: &SchemaConfig,
+        options: &LoadOptions,
+        data: &BackendData<K>,
+    ) -> rusqlite::Result<()> {
+        let min_len = options.compress_strings.unwrap_or(usize::MAX);
+
+        // symbols loaded from an existing database are already stored in
+        // one of its tables
+        let mut insert_short = conn.prepare(&schema.render(
+            "INSERT OR IGNORE INTO {t}_ShortSymbolTable (id, symbol)
+            SELECT ?1, ?2
+            WHERE NOT EXISTS (SELECT 1 FROM {t}_CompressedSymbolTable WHERE id = ?1);",
+        ))?;
+        let mut insert_compressed = conn.prepare(&schema.render(
+            "INSERT OR IGNORE INTO {t}_CompressedSymbolTable (id, data)
+            SELECT ?1, ?2
+            WHERE NOT EXISTS (SELECT 1 FROM {t}_ShortSymbolTable WHERE id = ?1);",
+        ))?;
+
+        for (sym, id) in data.ordered(data.symbols(), |(_, id)| **id) {
+            if sym.len() >= min_len {
+                let compressed = zstd::encode_all(sym.as_bytes(), 0)
+                    .map_err(|err| rusqlite::Error::ToSqlConversionFailure(Box::new(err)))?;
+                insert_compressed.execute((id.0, compressed))?;
+            } else {
+                insert_short.execute((id.0, sym))?;
+            }
+        }
 
-            let mut insert_symbol_table =
-                conn.prepare("INSERT INTO __SymbolTable (id, symbol) VALUES (?1, ?2);")?;
+        rusqlite::Result::Ok(())
+    }
 
-            for (sym, id) in data.symbol_table.iter() {
+    #[cfg_attr(not(feature = "string_compression"), allow(unused_variables))]
+    fn insert_facts<K: Display + Eq + Hash>(
+        conn: &rusqlite::Connection,
+        schema: &SchemaConfig,
+        options: &LoadOptions,
+        data: &BackendData<K>,
+    ) -> rusqlite::Result<()> {
+        #[cfg(feature = "string_compression")]
+        let compressed = Self::has_table(conn, &schema.table_name("_CompressedSymbolTable"))?;
+        #[cfg(not(feature = "string_compression"))]
+        let compressed = false;
+
+        if compressed {
+            #[cfg(feature = "string_compression")]
+            Self::insert_compressed_symbols(conn, schema, options, data)?;
+        } else {
+            // symbols loaded from an existing database are already stored in it
+            let mut insert_symbol_table = conn
+                .prepare(&schema.render(
+                    "INSERT OR IGNORE INTO {t}_SymbolTable (id, symbol) VALUES (?1, ?2);",
+                ))?;
+
+            for (sym, id) in data.ordered(data.symbols(), |(_, id)| **id) {
                 insert_symbol_table.execute((id.0, sym))?;
             }
+        }
 
-            let mut insert_root_elem_table =
-                conn.prepare("INSERT INTO _rootElem (file, elem) VALUES (?1, ?2);")?;
+        {
+            let mut insert_root_elem_table = conn
+                .prepare(&schema.render("INSERT INTO {t}rootElem (file, elem) VALUES (?1, ?2);"))?;
 
-            for (file, elem) in data.root_elem_table.iter() {
+            for (file, elem) in data.ordered(data.root_elem_table.iter(), |(_, elem)| **elem) {
                 insert_root_elem_table.execute((file.0, elem.0))?;
             }
 
+            let mut insert_file_format_table = conn.prepare(
+                &schema.render("INSERT INTO {t}fileFormat (file, format) VALUES (?1, ?2);"),
+            )?;
+
+            for (file, format) in data.ordered(data.file_format_table.iter(), |(file, _)| **file) {
+                insert_file_format_table.execute((file.0, format.0))?;
+            }
+
+            // databases created before key fields were extracted have no
+            // keyField table, so it is only used if there are key fields
+            if !data.key_field_table.is_empty() {
+                // key fields may already be declared in an existing database
+                let mut insert_key_field_table = conn
+                    .prepare(&schema.render(
+                        "INSERT OR IGNORE INTO {t}keyField (type, field) VALUES (?1, ?2);",
+                    ))?;
+
+                for (struct_name, field) in data.ordered(data.key_field_table.iter(), |fact| **fact)
+                {
+                    insert_key_field_table.execute((struct_name.0, field.0))?;
+                }
+            }
+
             let mut insert_type_table =
-                conn.prepare("INSERT INTO _type (id, type) VALUES (?1, ?2);")?;
+                conn.prepare(&schema.render("INSERT INTO {t}type (id, type) VALUES (?1, ?2);"))?;
 
-            for (id, sym) in data.type_table.iter() {
+            for (id, sym) in data.ordered(data.type_table.iter(), |(id, _)| **id) {
                 insert_type_table.execute((id.0, sym.0))?;
             }
 
             let mut insert_bool_table =
-                conn.prepare("INSERT INTO _bool (id, value) VALUES (?1, ?2);")?;
+                conn.prepare(&schema.render("INSERT INTO {t}bool (id, value) VALUES (?1, ?2);"))?;
 
-            for (id, value) in data.bool_table.iter() {
+            for (id, value) in data.ordered(data.bool_table.iter(), |(id, _)| **id) {
                 insert_bool_table.execute((id.0, if *value { 1 } else { 0 }))?;
             }
 
             let mut insert_number_table =
-                conn.prepare("INSERT INTO _number (id, value) VALUES (?1, ?2);")?;
+                conn.prepare(&schema.render("INSERT INTO {t}number (id, value) VALUES (?1, ?2);"))?;
+
+            let mut insert_number_parts_table = conn.prepare(
+                &schema.render("INSERT INTO {t}numberParts (id, hi, lo) VALUES (?1, ?2, ?3);"),
+            )?;
+            let mut insert_number_text_table = conn.prepare(
+                &schema.render("INSERT INTO {t}numberText (id, value) VALUES (?1, ?2);"),
+            )?;
 
-            for (id, value) in data.number_table.iter() {
-                insert_number_table.execute((id.0, *value))?;
+            for (id, value) in data.ordered(data.numbers(), |(id, _)| **id) {
+                match options.number_domain {
+                    Some((_, WideNumbers::Split)) if options.is_wide(value) => {
+                        let (hi, lo) = (value >> 32, value & 0xffff_ffff);
+                        insert_number_parts_table.execute((id.0, hi, lo))?;
+                    }
+                    Some((_, WideNumbers::Symbol)) if options.is_wide(value) => {
+                        insert_number_text_table.execute((id.0, value.to_string()))?;
+                    }
+                    _ => {
+                        insert_number_table.execute((id.0, value))?;
+                    }
+                }
             }
 
             let mut insert_string_table =
-                conn.prepare("INSERT INTO _string (id, value) VALUES (?1, ?2);")?;
+                conn.prepare(&schema.render("INSERT INTO {t}string (id, value) VALUES (?1, ?2);"))?;
 
-            for (id, value) in data.string_table.iter() {
+            for (id, value) in data.ordered(data.string_table.iter(), |(id, _)| **id) {
                 insert_string_table.execute((id.0, value.0))?;
             }
 
-            let mut insert_struct_table =
-                conn.prepare("INSERT INTO _struct (id, field, value) VALUES (?1, ?2, ?3);")?;
+            // databases created before string policies were applied have no
+            // fullString table, so it is only used if there are full strings
+            if !data.full_string_table.is_empty() {
+                let mut insert_full_string_table = conn.prepare(
+                    &schema.render("INSERT INTO {t}fullString (id, value) VALUES (?1, ?2);"),
+                )?;
+
+                for (id, value) in data.ordered(data.full_string_table.iter(), |(id, _)| **id) {
+                    insert_full_string_table.execute((id.0, value))?;
+                }
+            }
+
+            let mut insert_struct_table = conn.prepare(
+                &schema.render("INSERT INTO {t}struct (id, field, value) VALUES (?1, ?2, ?3);"),
+            )?;
 
-            for ((id, field), value) in data.struct_table.iter() {
+            for ((id, field), value) in data.ordered(data.struct_table.iter(), |(key, _)| **key) {
                 insert_struct_table.execute((id.0, field.0, value.0))?;
             }
 
-            let mut insert_seq_table =
-                conn.prepare("INSERT INTO _seq (id, pos, value) VALUES (?1, ?2, ?3);")?;
+            // databases created before key fields were extracted have no key
+            // table, so it is only used if there are keys
+            if !data.key_table.is_empty() {
+                let mut insert_key_table =
+                    conn.prepare(&schema.render("INSERT INTO {t}key (id, key) VALUES (?1, ?2);"))?;
+
+                for (id, key) in data.ordered(data.key_table.iter(), |fact| **fact) {
+                    insert_key_table.execute((id.0, key.0))?;
+                }
+            }
+
+            let mut insert_seq_table = conn.prepare(
+                &schema.render("INSERT INTO {t}seq (id, pos, value) VALUES (?1, ?2, ?3);"),
+            )?;
 
-            for ((id, pos), value) in data.seq_table.iter() {
+            for ((id, pos), value) in data.ordered(data.seq_table.iter(), |(key, _)| **key) {
                 insert_seq_table.execute((id.0, pos, value.0))?;
             }
 
-            let mut insert_tuple_table =
-                conn.prepare("INSERT INTO _tuple (id, pos, value) VALUES (?1, ?2, ?3);")?;
+            let mut insert_tuple_table = conn.prepare(
+                &schema.render("INSERT INTO {t}tuple (id, pos, value) VALUES (?1, ?2, ?3);"),
+            )?;
 
-            for ((id, pos), value) in data.tuple_table.iter() {
+            for ((id, pos), value) in data.ordered(data.tuple_table.iter(), |(key, _)| **key) {
                 insert_tuple_table.execute((id.0, pos, value.0))?;
             }
 
-            let mut insert_struct_type_table =
-                conn.prepare("INSERT INTO _structType (id, type) VALUES (?1, ?2);")?;
+            let mut insert_struct_type_table = conn
+                .prepare(&schema.render("INSERT INTO {t}structType (id, type) VALUES (?1, ?2);"))?;
 
-            for (id, type_name) in data.struct_type_table.iter() {
+            for (id, type_name) in data.ordered(data.struct_type_table.iter(), |(id, _)| **id) {
                 insert_struct_type_table.execute((id.0, type_name.0))?;
             }
 
-            let mut insert_variant_type_table =
-                conn.prepare("INSERT INTO _variantType (id, type, variant) VALUES (?1, ?2, ?3);")?;
+            let mut insert_variant_type_table = conn
+                .prepare(&schema.render(
+                    "INSERT INTO {t}variantType (id, type, variant) VALUES (?1, ?2, ?3);",
+                ))?;
 
-            for (id, (type_name, variant_name)) in data.variant_type_table.iter() {
+            for (id, (type_name, variant_name)) in
+                data.ordered(data.variant_type_table.iter(), |(id, _)| **id)
+            {
                 insert_variant_type_table.execute((id.0, type_name.0, variant_name.0))?;
             }
+
+            // databases created before schema-guided extraction have no
+            // schemaType table, so it is only used if there are schema types
+            if !data.schema_type_table.is_empty() {
+                let mut insert_schema_type_table = conn.prepare(
+                    &schema.render("INSERT INTO {t}schemaType (id, schema) VALUES (?1, ?2);"),
+                )?;
+
+                for (id, schema_ref) in data.ordered(data.schema_type_table.iter(), |(id, _)| **id)
+                {
+                    insert_schema_type_table.execute((id.0, schema_ref.0))?;
+                }
+            }
+
+            // databases created before datetime extraction have no datetime
+            // table, so it is only used if there are date-times
+            if !data.datetime_table.is_empty() {
+                let mut insert_datetime_table =
+                    conn.prepare(&schema.render(
+                        "INSERT INTO {t}datetime (id, micros, offset) VALUES (?1, ?2, ?3);",
+                    ))?;
+
+                for (id, (micros, offset)) in
+                    data.ordered(data.datetime_table.iter(), |(id, _)| **id)
+                {
+                    insert_datetime_table.execute((id.0, *micros, *offset))?;
+                }
+            }
+
+            // databases created before scalar recognition have no tables for
+            // recognized scalars, so they are only used if there are scalars
+            if !data.uuid_table.is_empty() {
+                let mut insert_uuid_table = conn.prepare(
+                    &schema.render("INSERT INTO {t}uuid (id, hi, lo) VALUES (?1, ?2, ?3);"),
+                )?;
+
+                // SQLite integers are signed, so the bits are stored as is
+                for (id, (hi, lo)) in data.ordered(data.uuid_table.iter(), |(id, _)| **id) {
+                    insert_uuid_table.execute((id.0, *hi as i64, *lo as i64))?;
+                }
+            }
+
+            if !data.ip_table.is_empty() {
+                let mut insert_ip_table = conn.prepare(
+                    &schema.render("INSERT INTO {t}ip (id, version, address) VALUES (?1, ?2, ?3);"),
+                )?;
+
+                for (id, (version, address)) in data.ordered(data.ip_table.iter(), |(id, _)| **id) {
+                    insert_ip_table.execute((id.0, version, address.0))?;
+                }
+            }
+
+            if !data.url_table.is_empty() {
+                let mut insert_url_table = conn.prepare(
+                    &schema.render("INSERT INTO {t}url (id, scheme, host) VALUES (?1, ?2, ?3);"),
+                )?;
+
+                for (id, (scheme, host)) in data.ordered(data.url_table.iter(), |(id, _)| **id) {
+                    insert_url_table.execute((id.0, scheme.0, host.0))?;
+                }
+            }
+
+            if !data.email_table.is_empty() {
+                let mut insert_email_table = conn.prepare(
+                    &schema.render("INSERT INTO {t}email (id, local, domain) VALUES (?1, ?2, ?3);"),
+                )?;
+
+                for (id, (local, domain)) in data.ordered(data.email_table.iter(), |(id, _)| **id) {
+                    insert_email_table.execute((id.0, local.0, domain.0))?;
+                }
+            }
+
+            // databases created before byte arrays could be extracted have no
+            // tables for them, so they are only used if there are byte arrays
+            if !data.bytes_table.is_empty() {
+                let mut insert_bytes_table = conn
+                    .prepare(&schema.render("INSERT INTO {t}bytes (id, value) VALUES (?1, ?2);"))?;
+                let mut insert_bytes_encoding_table = conn.prepare(&schema.render(
+                    "INSERT INTO {t}bytesEncoding (id, encoding, length) VALUES (?1, ?2, ?3);",
+                ))?;
+
+                for (id, value) in data.ordered(data.bytes_table.iter(), |(id, _)| **id) {
+                    insert_bytes_table.execute((id.0, value.0))?;
+                }
+
+                for (id, (encoding, len)) in
+                    data.ordered(data.bytes_encoding_table.iter(), |(id, _)| **id)
+                {
+                    insert_bytes_encoding_table.execute((id.0, encoding.0, len))?;
+                }
+            }
+
+            // databases created before sparse structs have no tables for
+            // them, so they are only used if sparse structs are enabled
+            if !data.declared_len_table.is_empty() {
+                let mut insert_declared_len_table = conn.prepare(
+                    &schema.render("INSERT INTO {t}declaredLen (id, len) VALUES (?1, ?2);"),
+                )?;
+
+                for (id, len) in data.ordered(data.declared_len_table.iter(), |(id, _)| **id) {
+                    insert_declared_len_table.execute((id.0, len))?;
+                }
+            }
+
+            if !data.absent_field_table.is_empty() {
+                let mut insert_absent_field_table = conn.prepare(
+                    &schema.render("INSERT INTO {t}absentField (id, field) VALUES (?1, ?2);"),
+                )?;
+
+                for (id, field) in data.ordered(data.absent_field_table.iter(), |fact| **fact) {
+                    insert_absent_field_table.execute((id.0, field.0))?;
+                }
+            }
+
+            // databases created before unit policies have no unit table, so
+            // it is only used if there are unit facts
+            if !data.unit_table.is_empty() {
+                let mut insert_unit_table =
+                    conn.prepare(&schema.render("INSERT INTO {t}unit (id) VALUES (?1);"))?;
+
+                for id in data.ordered(data.unit_table.iter(), |id| **id) {
+                    insert_unit_table.execute((id.0,))?;
+                }
+            }
+
+            // databases created before fact quotas have no truncated table,
+            // so it is only used if there are truncation facts; relations
+            // truncated in an existing database keep their quota
+            if !data.truncated_table.is_empty() {
+                let mut insert_truncated_table = conn.prepare(&schema.render(
+                    "INSERT OR IGNORE INTO {t}truncated (relation, quota) VALUES (?1, ?2);",
+                ))?;
+
+                for (relation, quota) in
+                    data.ordered(data.truncated_table.iter(), |(relation, _)| **relation)
+                {
+                    insert_truncated_table.execute((relation.0, quota))?;
+                }
+            }
+
+            // databases created before redaction have no redacted table, so
+            // it is only used if there are redacted values
+            if !data.redacted_table.is_empty() {
+                let mut insert_redacted_table =
+                    conn.prepare(&schema.render("INSERT INTO {t}redacted (id) VALUES (?1);"))?;
+
+                for id in data.ordered(data.redacted_table.iter(), |id| **id) {
+                    insert_redacted_table.execute((id.0,))?;
+                }
+            }
+
+            // databases created before sampling have no sampled table, so it
+            // is only used if there are sampled sequences
+            if !data.sampled_table.is_empty() {
+                let mut insert_sampled_table = conn.prepare(
+                    &schema.render("INSERT INTO {t}sampled (id, len, kept) VALUES (?1, ?2, ?3);"),
+                )?;
+
+                for (id, (len, kept)) in data.ordered(data.sampled_table.iter(), |(id, _)| **id) {
+                    insert_sampled_table.execute((id.0, len, kept))?;
+                }
+            }
+
+            // databases created before strings were correlated have no
+            // sameString table, so it is only used if there are such facts
+            if !data.same_string_table.is_empty() {
+                let mut insert_same_string_table = conn.prepare(
+                    &schema.render("INSERT INTO {t}sameString (id, other) VALUES (?1, ?2);"),
+                )?;
+
+                for (id, other) in data.ordered(data.same_string_table.iter(), |fact| **fact) {
+                    insert_same_string_table.execute((id.0, other.0))?;
+                }
+            }
+
+            // databases created before shapes have no shape tables, so they
+            // are only used if there are shapes; shapes are identified by
+            // hashes, so their fields may already be in an existing database
+            if !data.shape_table.is_empty() {
+                let mut insert_shape_table = conn
+                    .prepare(&schema.render("INSERT INTO {t}shape (id, shape) VALUES (?1, ?2);"))?;
+
+                for (id, shape) in data.ordered(data.shape_table.iter(), |(id, _)| **id) {
+                    insert_shape_table.execute((id.0, *shape as i64))?;
+                }
+
+                let mut insert_shape_field_table = conn.prepare(&schema.render(
+                    "INSERT OR IGNORE INTO {t}shapeField (shape, pos, field) VALUES (?1, ?2, ?3);",
+                ))?;
+
+                for ((shape, pos), field) in
+                    data.ordered(data.shape_field_table.iter(), |(key, _)| **key)
+                {
+                    insert_shape_field_table.execute((*shape as i64, pos, field.0))?;
+                }
+            }
+
+            // databases created before invalid documents could be skipped
+            // have no parseError table, so it is only used if there are
+            // parse errors
+            if !data.parse_error_table.is_empty() {
+                let mut insert_parse_error_table = conn.prepare(&schema.render(
+                    "INSERT OR REPLACE INTO {t}parseError (file, line, message)
+                    VALUES (?1, ?2, ?3);",
+                ))?;
+
+                for ((file, line), message) in
+                    data.ordered(data.parse_error_table.iter(), |(key, _)| **key)
+                {
+                    insert_parse_error_table.execute((file.0, line, message.0))?;
+                }
+            }
+
+            // databases created before strings could be elided have no
+            // stringHash table, so it is only used if there are elided strings
+            if !data.string_hash_table.is_empty() {
+                let mut insert_string_hash_table = conn
+                    .prepare(&schema.render(
+                        "INSERT INTO {t}stringHash (id, hash, len) VALUES (?1, ?2, ?3);",
+                    ))?;
+
+                for (id, (hash, len)) in data.ordered(data.string_hash_table.iter(), |(id, _)| **id)
+                {
+                    insert_string_hash_table.execute((id.0, *hash as i64, len))?;
+                }
+            }
+
+            // databases created before elements could be measured have no
+            // depth and subtreeSize tables, so they are only used if there
+            // are measures
+            if !data.depth_table.is_empty() {
+                let mut insert_depth_table = conn
+                    .prepare(&schema.render("INSERT INTO {t}depth (id, depth) VALUES (?1, ?2);"))?;
+
+                for (id, depth) in data.ordered(data.depth_table.iter(), |(id, _)| **id) {
+                    insert_depth_table.execute((id.0, depth))?;
+                }
+            }
+
+            if !data.subtree_size_table.is_empty() {
+                let mut insert_subtree_size_table = conn.prepare(
+                    &schema.render("INSERT INTO {t}subtreeSize (id, size) VALUES (?1, ?2);"),
+                )?;
+
+                for (id, size) in data.ordered(data.subtree_size_table.iter(), |(id, _)| **id) {
+                    insert_subtree_size_table.execute((id.0, size))?;
+                }
+            }
+
+            // databases created before chars had code points have no char
+            // table, so it is only used if there are code points
+            if !data.char_table.is_empty() {
+                let mut insert_char_table = conn.prepare(
+                    &schema.render("INSERT INTO {t}char (id, codepoint) VALUES (?1, ?2);"),
+                )?;
+
+                for (id, codepoint) in data.ordered(data.char_table.iter(), |(id, _)| **id) {
+                    insert_char_table.execute((id.0, codepoint))?;
+                }
+            }
+
+            // databases created before roots had metadata have no rootMeta
+            // table, so it is only used if there is root metadata
+            if !data.root_meta_table.is_empty() {
+                let mut insert_root_meta_table = conn.prepare(&schema.render(
+                    "INSERT OR IGNORE INTO {t}rootMeta (file, key, value) VALUES (?1, ?2, ?3);",
+                ))?;
+
+                for (file, key, value) in data.ordered(data.root_meta_table.iter(), |fact| **fact) {
+                    insert_root_meta_table.execute((file.0, key.0, value.0))?;
+                }
+            }
+
+            // element type kinds are the same in every extraction, so they
+            // may already be in an existing database
+            let mut insert_elem_type_kind_table = conn
+                .prepare(&schema.render(
+                    "INSERT OR IGNORE INTO {t}elemTypeKind (type, kind) VALUES (?1, ?2);",
+                ))?;
+
+            for (elem_type, kind) in data
+                .ordered(data.elem_type_kind_table.iter(), |(elem_type, _)| {
+                    **elem_type
+                })
+            {
+                insert_elem_type_kind_table.execute((elem_type.0, kind.0))?;
+            }
         }
 
-        rusqlite::Result::Ok(conn)
+        rusqlite::Result::Ok(())
     }
 }
 
@@ -257,15 +2730,46 @@ impl BackendUtil {
 /// .type TypeName <: symbol
 /// .type VariantName <: symbol
 ///
+/// .decl rootElem(file: symbol, elem: ElemId)
+/// .decl fileFormat(file: symbol, format: symbol)
+/// .decl keyField(type: TypeName, field: Field)
 /// .decl type(id: ElemId, type: ElemType)
 /// .decl number(id: ElemId, value: number)
+/// .decl numberParts(id: ElemId, hi: number, lo: unsigned)
+/// .decl numberText(id: ElemId, value: symbol)
 /// .decl string(id: ElemId, value: symbol)
+/// .decl fullString(id: ElemId, value: symbol)
 /// .decl map(id: ElemId, key: ElemId, value: ElemId)
 /// .decl struct(id: ElemId, field: Field, value: ElemId)
+/// .decl key(id: ElemId, key: ElemId)
 /// .decl seq(id: ElemId, pos: number, value: ElemId)
 /// .decl tuple(id: ElemId, pos: number, value: ElemId)
 /// .decl structType(id: ElemId, type: TypeName)
 /// .decl variantType(id: ElemId, type: TypeName, variant: VariantName)
+/// .decl schemaType(id: ElemId, schema: symbol)
+/// .decl datetime(id: ElemId, micros: number, offset: number)
+/// .decl uuid(id: ElemId, hi: number, lo: number)
+/// .decl ip(id: ElemId, version: number, address: symbol)
+/// .decl url(id: ElemId, scheme: symbol, host: symbol)
+/// .decl email(id: ElemId, local: symbol, domain: symbol)
+/// .decl bytes(id: ElemId, value: symbol)
+/// .decl bytesEncoding(id: ElemId, encoding: symbol, length: number)
+/// .decl declaredLen(id: ElemId, len: number)
+/// .decl absentField(id: ElemId, field: Field)
+/// .decl unit(id: ElemId)
+/// .decl truncated(relation: symbol, quota: number)
+/// .decl redacted(id: ElemId)
+/// .decl sampled(id: ElemId, len: number, kept: number)
+/// .decl elemTypeKind(type: ElemType, kind: symbol)
+/// .decl sameString(id: ElemId, other: ElemId)
+/// .decl shape(id: ElemId, shape: number)
+/// .decl shapeField(shape: number, pos: number, field: Field)
+/// .decl parseError(file: symbol, line: number, message: symbol)
+/// .decl stringHash(id: ElemId, hash: number, len: number)
+/// .decl rootMeta(file: symbol, key: symbol, value: symbol)
+/// .decl depth(id: ElemId, depth: number)
+/// .decl subtreeSize(id: ElemId, size: number)
+/// .decl char(id: ElemId, codepoint: number)
 /// ```
 ///
 /// Note that this backend does **not** support extraction of
@@ -275,6 +2779,9 @@ impl BackendUtil {
 #[derive(Default)]
 pub struct Backend {
     vector_backend: vector::Backend,
+    schema: SchemaConfig,
+    load_options: LoadOptions,
+    metadata: Vec<(String, String)>,
 }
 
 impl AbstractBackend for Backend {
@@ -283,34 +2790,137 @@ impl AbstractBackend for Backend {
         self.vector_backend.dump()
     }
 
-    /// Store facts in a SQLite file with name `filename`.
-    fn dump_to_db(self, filename: &str) -> rusqlite::Result<()> {
-        let data = self.vector_backend.get_data();
-        let conn = BackendUtil::dump_to_db(&data, filename)?;
+    fn string_report(&self, top_n: usize) -> StringReport {
+        self.vector_backend.string_report(top_n)
+    }
 
-        conn.execute_batch(
-            "BEGIN;
+    fn input_directives(&self, dbname: &str) -> String {
+        input_directives(&self.schema, false, dbname)
+    }
 
-            CREATE TABLE _map (
+    fn dump_into(self, conn: &rusqlite::Connection) -> Result<()> {
+        let data = self.vector_backend.get_data();
+        BackendUtil::load(conn, &self.schema, &self.load_options, || {
+            BackendUtil::dump_into(
+                conn,
+                &self.schema,
+                &self.load_options,
+                &data,
+                &self.metadata,
+            )?;
+            BackendUtil::create_table(
+                conn,
+                &self.schema,
+                false,
+                "CREATE TABLE {t}map (
                 id INTEGER NOT NULL,
                 key INTEGER NOT NULL,
                 value INTEGER NOT NULL,
                 PRIMARY KEY (id, key),
-                FOREIGN KEY(id) REFERENCES _type(id),
-                FOREIGN KEY(key) REFERENCES _type(id),
-                FOREIGN KEY(value) REFERENCES _type(id)
-            );
+                FOREIGN KEY(id) REFERENCES {t}type(id),
+                FOREIGN KEY(key) REFERENCES {t}type(id),
+                FOREIGN KEY(value) REFERENCES {t}type(id)
+            );",
+                "CREATE VIEW {v}map AS
+            SELECT id, key, value FROM {t}map;",
+            )?;
 
-            CREATE VIEW map AS
-            SELECT id, key, value FROM _map;
+            Self::insert_map_facts(conn, &self.schema, &data)
+        })?;
 
-            COMMIT;",
-        )?;
+        Result::Ok(())
+    }
+
+    fn load_from(&mut self, conn: &rusqlite::Connection) -> Result<ElemId> {
+        let (symbols, next_elem_id) = BackendUtil::load_from(conn, &self.schema)?;
+        for (symbol, id) in symbols {
+            self.vector_backend.load_symbol(symbol, id);
+        }
+
+        Result::Ok(next_elem_id)
+    }
+
+    fn append_into(self, conn: &rusqlite::Connection, replace: bool) -> Result<()> {
+        BackendUtil::check_schema_version(conn, &self.schema)?;
+        let data = self.vector_backend.get_data();
+        BackendUtil::load(conn, &self.schema, &self.load_options, || {
+            BackendUtil::append_into(
+                conn,
+                &self.schema,
+                &self.load_options,
+                &data,
+                &self.metadata,
+                replace,
+                true,
+            )?;
+            Self::insert_map_facts(conn, &self.schema, &data)?;
+
+            // the symbols of removed files are kept until the facts replacing
+            // them, which reuse the symbol table of the database, are inserted
+            if replace {
+                BackendUtil::remove_orphan_symbols(conn, &self.schema)?;
+            }
+            rusqlite::Result::Ok(())
+        })?;
 
-        let mut insert_map_table =
-            conn.prepare("INSERT INTO _map (id, key, value) VALUES (?1, ?2, ?3);")?;
+        Result::Ok(())
+    }
+}
+
+impl Backend {
+    /// Store facts in tables and views named according to `schema`.
+    pub fn with_schema(mut self, schema: SchemaConfig) -> Self {
+        self.schema = schema;
+        self
+    }
+
+    /// Load facts into databases according to `options`.
+    pub fn with_load_options(mut self, options: LoadOptions) -> Self {
+        self.load_options = options;
+        self
+    }
+
+    /// Record the entry `key` with `value` in the metadata table of
+    /// databases (see [SCHEMA_VERSION]), e.g. the configuration of the
+    /// extractor. An entry replaces earlier values of `key` when facts are
+    /// appended to a database.
+    pub fn with_metadata(mut self, key: &str, value: &str) -> Self {
+        self.metadata.push((key.to_string(), value.to_string()));
+        self
+    }
 
-        for ((id, key), value) in data.map_table.iter() {
+    /// Intern strings of each [namespace][vector::SymbolNamespace]
+    /// separately; see [vector::Backend::with_separate_namespaces].
+    pub fn with_separate_namespaces(mut self) -> Self {
+        self.vector_backend = self.vector_backend.with_separate_namespaces();
+        self
+    }
+
+    /// Print and store facts in order of their identifiers; see
+    /// [vector::Backend::with_deterministic_order].
+    pub fn with_deterministic_order(mut self, enable: bool) -> Self {
+        self.vector_backend = self.vector_backend.with_deterministic_order(enable);
+        self
+    }
+
+    /// Extract byte arrays as text encoded with `policy`; see
+    /// [vector::Backend::with_bytes_policy].
+    pub fn with_bytes_policy(mut self, policy: BytesPolicy) -> Self {
+        self.vector_backend = self.vector_backend.with_bytes_policy(policy);
+        self
+    }
+
+    fn insert_map_facts(
+        conn: &rusqlite::Connection,
+        schema: &SchemaConfig,
+        data: &BackendData<ElemId>,
+    ) -> rusqlite::Result<()> {
+        let mut insert_map_table = conn
+            .prepare(&schema.render("INSERT INTO {t}map (id, key, value) VALUES (?1, ?2, ?3);"))?;
+
+        for ((id, key), value) in
+            data.ordered(data.map_table.iter(), |((id, _), value)| (*id, **value))
+        {
             insert_map_table.execute((id.0, key.0, value.0))?;
         }
 
@@ -318,21 +2928,85 @@ impl AbstractBackend for Backend {
     }
 }
 
+/// Backend that stores the facts of `data` in databases, e.g. data read from
+/// a directory of facts, so that data can be stored without extracting it
+/// again:
+///
+/// ```ignore
+/// let data = backend::nemo::read_dir(Path::new("facts"))?;
+/// backend::souffle_sqlite::Backend::from(data).dump_to_db("facts.db")?;
+/// ```
+impl From<BackendData<ElemId>> for Backend {
+    fn from(data: BackendData<ElemId>) -> Self {
+        Backend {
+            vector_backend: vector::Backend::from(data),
+            ..Default::default()
+        }
+    }
+}
+
+/// Read the facts of a database generated by [Backend] with the default
+/// [SchemaConfig]; see [read_from].
+impl TryFrom<&rusqlite::Connection> for BackendData<ElemId> {
+    type Error = DatalogExtractionError;
+
+    fn try_from(conn: &rusqlite::Connection) -> Result<Self> {
+        read_from(conn, &SchemaConfig::default())
+    }
+}
+
 impl DatalogExtractorBackend for Backend {
+    fn add_i64(&mut self, elem: ElemId, value: i64) -> Result<()> {
+        self.load_options.check_number(value)?;
+        self.vector_backend.add_i64(elem, value)
+    }
+
+    fn add_u64(&mut self, elem: ElemId, value: u64) -> Result<()> {
+        match i64::try_from(value) {
+            Ok(signed_value) => self.add_i64(elem, signed_value),
+            Err(_) => Result::Err(DatalogExtractionError::IntegerCastOverflow(value)),
+        }
+    }
+
     delegate! {
         to (&mut self.vector_backend) {
             fn add_root_elem(&mut self, file: &str, elem: ElemId) -> Result<()>;
+            fn add_file_format(&mut self, file: &str, format: &str) -> Result<()>;
+            fn add_key_field(&mut self, struct_name: &str, field: &str) -> Result<()>;
             fn add_elem(&mut self, elem: ElemId, elem_type: ElemType) -> Result<()>;
             fn add_bool(&mut self, elem: ElemId, value: bool) -> Result<()>;
-            fn add_i64(&mut self, elem: ElemId, value: i64) -> Result<()>;
-            fn add_u64(&mut self, elem: ElemId, value: u64) -> Result<()>;
             fn add_str(&mut self, elem: ElemId, value: &str) -> Result<()>;
+            fn add_bytes(&mut self, elem: ElemId, value: &[u8]) -> Result<()>;
+            fn add_full_str(&mut self, elem: ElemId, value: &str) -> Result<()>;
             fn add_map_entry(&mut self, elem: ElemId, key: ElemId, value: ElemId) -> Result<()>;
             fn add_struct_type(&mut self, elem: ElemId, struct_name: &str) -> Result<()>;
             fn add_struct_entry(&mut self, elem: ElemId, key: &str, value: ElemId) -> Result<()>;
+            fn add_key(&mut self, elem: ElemId, key: ElemId) -> Result<()>;
             fn add_seq_entry(&mut self, elem: ElemId, pos: usize, value: ElemId) -> Result<()>;
             fn add_variant_type(&mut self, elem: ElemId, type_name: &str, variant_name: &str) -> Result<()>;
             fn add_tuple_entry(&mut self, elem: ElemId, pos: usize, value: ElemId) -> Result<()>;
+            fn add_schema_type(&mut self, elem: ElemId, schema_ref: &str) -> Result<()>;
+            fn add_datetime(&mut self, elem: ElemId, epoch_micros: i64, tz_offset: i32) -> Result<()>;
+            fn add_uuid(&mut self, elem: ElemId, hi: u64, lo: u64) -> Result<()>;
+            fn add_ip(&mut self, elem: ElemId, address: IpAddr) -> Result<()>;
+            fn add_url(&mut self, elem: ElemId, scheme: &str, host: &str) -> Result<()>;
+            fn add_email(&mut self, elem: ElemId, local: &str, domain: &str) -> Result<()>;
+            fn add_declared_len(&mut self, elem: ElemId, len: usize) -> Result<()>;
+            fn add_absent_field(&mut self, elem: ElemId, field: &str) -> Result<()>;
+            fn add_unit(&mut self, elem: ElemId) -> Result<()>;
+            fn add_truncated(&mut self, relation: &str, quota: usize) -> Result<()>;
+            fn add_redacted(&mut self, elem: ElemId) -> Result<()>;
+            fn add_sampled(&mut self, elem: ElemId, len: usize, kept: usize) -> Result<()>;
+            fn add_elem_type_kind(&mut self, elem_type: ElemType, kind: ElemKind) -> Result<()>;
+            fn add_same_string(&mut self, elem: ElemId, other: ElemId) -> Result<()>;
+            fn add_shape(&mut self, elem: ElemId, shape: u64) -> Result<()>;
+            fn add_shape_field(&mut self, shape: u64, pos: usize, field: &str) -> Result<()>;
+            fn add_parse_error(&mut self, file: &str, line: usize, message: &str) -> Result<()>;
+            fn add_string_hash(&mut self, elem: ElemId, hash: u64, len: usize) -> Result<()>;
+            fn add_root_meta(&mut self, file: &str, key: &str, value: &str) -> Result<()>;
+            fn add_depth(&mut self, elem: ElemId, depth: usize) -> Result<()>;
+            fn add_subtree_size(&mut self, elem: ElemId, size: usize) -> Result<()>;
+            fn add_codepoint(&mut self, elem: ElemId, codepoint: u32) -> Result<()>;
         }
     }
 }
@@ -343,19 +3017,53 @@ impl DatalogExtractorBackend for Backend {
 /// The backend stores facts in the following Souffle schema:
 ///
 /// ```text
+/// .decl rootElem(file: symbol, elem: ElemId)
+/// .decl fileFormat(file: symbol, format: symbol)
+/// .decl keyField(type: TypeName, field: Field)
 /// .decl type(id: ElemId, type: ElemType)
 /// .decl number(id: ElemId, value: number)
+/// .decl numberParts(id: ElemId, hi: number, lo: unsigned)
+/// .decl numberText(id: ElemId, value: symbol)
 /// .decl string(id: ElemId, value: symbol)
+/// .decl fullString(id: ElemId, value: symbol)
 /// .decl map(id: ElemId, key: symbol, value: ElemId)
 /// .decl struct(id: ElemId, field: Field, value: ElemId)
+/// .decl key(id: ElemId, key: ElemId)
 /// .decl seq(id: ElemId, pos: number, value: ElemId)
 /// .decl tuple(id: ElemId, pos: number, value: ElemId)
 /// .decl structType(id: ElemId, type: TypeName)
 /// .decl variantType(id: ElemId, type: TypeName, variant: VariantName)
+/// .decl schemaType(id: ElemId, schema: symbol)
+/// .decl datetime(id: ElemId, micros: number, offset: number)
+/// .decl uuid(id: ElemId, hi: number, lo: number)
+/// .decl ip(id: ElemId, version: number, address: symbol)
+/// .decl url(id: ElemId, scheme: symbol, host: symbol)
+/// .decl email(id: ElemId, local: symbol, domain: symbol)
+/// .decl bytes(id: ElemId, value: symbol)
+/// .decl bytesEncoding(id: ElemId, encoding: symbol, length: number)
+/// .decl declaredLen(id: ElemId, len: number)
+/// .decl absentField(id: ElemId, field: Field)
+/// .decl unit(id: ElemId)
+/// .decl truncated(relation: symbol, quota: number)
+/// .decl redacted(id: ElemId)
+/// .decl sampled(id: ElemId, len: number, kept: number)
+/// .decl elemTypeKind(type: ElemType, kind: symbol)
+/// .decl sameString(id: ElemId, other: ElemId)
+/// .decl shape(id: ElemId, shape: number)
+/// .decl shapeField(shape: number, pos: number, field: Field)
+/// .decl parseError(file: symbol, line: number, message: symbol)
+/// .decl stringHash(id: ElemId, hash: number, len: number)
+/// .decl rootMeta(file: symbol, key: symbol, value: symbol)
+/// .decl depth(id: ElemId, depth: number)
+/// .decl subtreeSize(id: ElemId, size: number)
+/// .decl char(id: ElemId, codepoint: number)
 /// ```
 #[derive(Default)]
 pub struct StringKeyBackend {
     vector_backend: vector::StringKeyBackend,
+    schema: SchemaConfig,
+    load_options: LoadOptions,
+    metadata: Vec<(String, String)>,
 }
 
 impl AbstractBackend for StringKeyBackend {
@@ -364,36 +3072,139 @@ impl AbstractBackend for StringKeyBackend {
         self.vector_backend.dump()
     }
 
-    /// Store facts in a SQLite file with name `filename`.
-    fn dump_to_db(self, filename: &str) -> rusqlite::Result<()> {
-        let data = self.vector_backend.get_data();
-        let conn = BackendUtil::dump_to_db(&data, filename)?;
+    fn string_report(&self, top_n: usize) -> StringReport {
+        self.vector_backend.string_report(top_n)
+    }
 
-        conn.execute_batch(
-            "BEGIN;
+    fn input_directives(&self, dbname: &str) -> String {
+        input_directives(&self.schema, true, dbname)
+    }
 
-            CREATE TABLE _map (
+    fn dump_into(self, conn: &rusqlite::Connection) -> Result<()> {
+        let data = self.vector_backend.get_data();
+        BackendUtil::load(conn, &self.schema, &self.load_options, || {
+            BackendUtil::dump_into(
+                conn,
+                &self.schema,
+                &self.load_options,
+                &data,
+                &self.metadata,
+            )?;
+            BackendUtil::create_table(
+                conn,
+                &self.schema,
+                false,
+                "CREATE TABLE {t}map (
                 id INTEGER NOT NULL,
                 key INTEGER NOT NULL,
                 value INTEGER NOT NULL,
                 PRIMARY KEY (id, key),
-                FOREIGN KEY(id) REFERENCES _type(id),
-                FOREIGN KEY(key) REFERENCES __SymbolTable(id),
-                FOREIGN KEY(value) REFERENCES _type(id)
-            );
+                FOREIGN KEY(id) REFERENCES {t}type(id),
+                FOREIGN KEY(key) REFERENCES {t}_SymbolTable(id),
+                FOREIGN KEY(value) REFERENCES {t}type(id)
+            );",
+                "CREATE VIEW {v}map AS
+            SELECT {t}map.id AS id, {t}_SymbolTable.symbol AS key, {t}map.value AS value
+            FROM {t}map INNER JOIN {t}_SymbolTable
+            ON {t}map.key = {t}_SymbolTable.id;",
+            )?;
 
-            CREATE VIEW map AS
-            SELECT _map.id AS id, __SymbolTable.symbol AS key, _map.value AS value
-            FROM _map INNER JOIN __SymbolTable
-            ON _map.key = __SymbolTable.id;
+            Self::insert_map_facts(conn, &self.schema, &data)
+        })?;
 
-            COMMIT;",
-        )?;
+        Result::Ok(())
+    }
+
+    fn load_from(&mut self, conn: &rusqlite::Connection) -> Result<ElemId> {
+        let (symbols, next_elem_id) = BackendUtil::load_from(conn, &self.schema)?;
+        for (symbol, id) in symbols {
+            self.vector_backend.load_symbol(symbol, id);
+        }
+
+        Result::Ok(next_elem_id)
+    }
+
+    fn append_into(self, conn: &rusqlite::Connection, replace: bool) -> Result<()> {
+        BackendUtil::check_schema_version(conn, &self.schema)?;
+        let data = self.vector_backend.get_data();
+        BackendUtil::load(conn, &self.schema, &self.load_options, || {
+            BackendUtil::append_into(
+                conn,
+                &self.schema,
+                &self.load_options,
+                &data,
+                &self.metadata,
+                replace,
+                false,
+            )?;
+            Self::insert_map_facts(conn, &self.schema, &data)?;
+
+            // the symbols of removed files are kept until the facts replacing
+            // them, which reuse the symbol table of the database, are inserted
+            if replace {
+                BackendUtil::remove_orphan_symbols(conn, &self.schema)?;
+            }
+            rusqlite::Result::Ok(())
+        })?;
+
+        Result::Ok(())
+    }
+}
+
+impl StringKeyBackend {
+    /// Store facts in tables and views named according to `schema`.
+    pub fn with_schema(mut self, schema: SchemaConfig) -> Self {
+        self.schema = schema;
+        self
+    }
+
+    /// Load facts into databases according to `options`.
+    pub fn with_load_options(mut self, options: LoadOptions) -> Self {
+        self.load_options = options;
+        self
+    }
+
+    /// Record the entry `key` with `value` in the metadata table of
+    /// databases (see [SCHEMA_VERSION]), e.g. the configuration of the
+    /// extractor. An entry replaces earlier values of `key` when facts are
+    /// appended to a database.
+    pub fn with_metadata(mut self, key: &str, value: &str) -> Self {
+        self.metadata.push((key.to_string(), value.to_string()));
+        self
+    }
+
+    /// Intern strings of each [namespace][vector::SymbolNamespace]
+    /// separately; see [vector::Backend::with_separate_namespaces].
+    pub fn with_separate_namespaces(mut self) -> Self {
+        self.vector_backend = self.vector_backend.with_separate_namespaces();
+        self
+    }
+
+    /// Print and store facts in order of their identifiers; see
+    /// [vector::Backend::with_deterministic_order].
+    pub fn with_deterministic_order(mut self, enable: bool) -> Self {
+        self.vector_backend = self.vector_backend.with_deterministic_order(enable);
+        self
+    }
 
-        let mut insert_map_table =
-            conn.prepare("INSERT INTO _map (id, key, value) VALUES (?1, ?2, ?3);")?;
+    /// Extract byte arrays as text encoded with `policy`; see
+    /// [vector::Backend::with_bytes_policy].
+    pub fn with_bytes_policy(mut self, policy: BytesPolicy) -> Self {
+        self.vector_backend = self.vector_backend.with_bytes_policy(policy);
+        self
+    }
+
+    fn insert_map_facts(
+        conn: &rusqlite::Connection,
+        schema: &SchemaConfig,
+        data: &BackendData<SymbolId>,
+    ) -> rusqlite::Result<()> {
+        let mut insert_map_table = conn
+            .prepare(&schema.render("INSERT INTO {t}map (id, key, value) VALUES (?1, ?2, ?3);"))?;
 
-        for ((id, key), value) in data.map_table.iter() {
+        for ((id, key), value) in
+            data.ordered(data.map_table.iter(), |((id, _), value)| (*id, **value))
+        {
             insert_map_table.execute((id.0, key.0, value.0))?;
         }
 
@@ -401,21 +3212,79 @@ impl AbstractBackend for StringKeyBackend {
     }
 }
 
+/// Backend that stores the facts of `data` in databases; see
+/// [Backend::from].
+impl From<BackendData<SymbolId>> for StringKeyBackend {
+    fn from(data: BackendData<SymbolId>) -> Self {
+        StringKeyBackend {
+            vector_backend: vector::StringKeyBackend::from(data),
+            ..Default::default()
+        }
+    }
+}
+
+/// Read the facts of a database generated by [StringKeyBackend] with the
+/// default [SchemaConfig]; see [read_string_key_from].
+impl TryFrom<&rusqlite::Connection> for BackendData<SymbolId> {
+    type Error = DatalogExtractionError;
+
+    fn try_from(conn: &rusqlite::Connection) -> Result<Self> {
+        read_string_key_from(conn, &SchemaConfig::default())
+    }
+}
+
 impl DatalogExtractorBackend for StringKeyBackend {
+    fn add_i64(&mut self, elem: ElemId, value: i64) -> Result<()> {
+        self.load_options.check_number(value)?;
+        self.vector_backend.add_i64(elem, value)
+    }
+
+    fn add_u64(&mut self, elem: ElemId, value: u64) -> Result<()> {
+        match i64::try_from(value) {
+            Ok(signed_value) => self.add_i64(elem, signed_value),
+            Err(_) => Result::Err(DatalogExtractionError::IntegerCastOverflow(value)),
+        }
+    }
+
     delegate! {
         to (&mut self.vector_backend) {
             fn add_root_elem(&mut self, file: &str, elem: ElemId) -> Result<()>;
+            fn add_file_format(&mut self, file: &str, format: &str) -> Result<()>;
+            fn add_key_field(&mut self, struct_name: &str, field: &str) -> Result<()>;
             fn add_elem(&mut self, elem: ElemId, elem_type: ElemType) -> Result<()>;
             fn add_bool(&mut self, elem: ElemId, value: bool) -> Result<()>;
-            fn add_i64(&mut self, elem: ElemId, value: i64) -> Result<()>;
-            fn add_u64(&mut self, elem: ElemId, value: u64) -> Result<()>;
             fn add_str(&mut self, elem: ElemId, value: &str) -> Result<()>;
+            fn add_bytes(&mut self, elem: ElemId, value: &[u8]) -> Result<()>;
+            fn add_full_str(&mut self, elem: ElemId, value: &str) -> Result<()>;
             fn add_map_entry(&mut self, elem: ElemId, key: ElemId, value: ElemId) -> Result<()>;
             fn add_struct_type(&mut self, elem: ElemId, struct_name: &str) -> Result<()>;
             fn add_struct_entry(&mut self, elem: ElemId, key: &str, value: ElemId) -> Result<()>;
+            fn add_key(&mut self, elem: ElemId, key: ElemId) -> Result<()>;
             fn add_seq_entry(&mut self, elem: ElemId, pos: usize, value: ElemId) -> Result<()>;
             fn add_variant_type(&mut self, elem: ElemId, type_name: &str, variant_name: &str) -> Result<()>;
             fn add_tuple_entry(&mut self, elem: ElemId, pos: usize, value: ElemId) -> Result<()>;
+            fn add_schema_type(&mut self, elem: ElemId, schema_ref: &str) -> Result<()>;
+            fn add_datetime(&mut self, elem: ElemId, epoch_micros: i64, tz_offset: i32) -> Result<()>;
+            fn add_uuid(&mut self, elem: ElemId, hi: u64, lo: u64) -> Result<()>;
+            fn add_ip(&mut self, elem: ElemId, address: IpAddr) -> Result<()>;
+            fn add_url(&mut self, elem: ElemId, scheme: &str, host: &str) -> Result<()>;
+            fn add_email(&mut self, elem: ElemId, local: &str, domain: &str) -> Result<()>;
+            fn add_declared_len(&mut self, elem: ElemId, len: usize) -> Result<()>;
+            fn add_absent_field(&mut self, elem: ElemId, field: &str) -> Result<()>;
+            fn add_unit(&mut self, elem: ElemId) -> Result<()>;
+            fn add_truncated(&mut self, relation: &str, quota: usize) -> Result<()>;
+            fn add_redacted(&mut self, elem: ElemId) -> Result<()>;
+            fn add_sampled(&mut self, elem: ElemId, len: usize, kept: usize) -> Result<()>;
+            fn add_elem_type_kind(&mut self, elem_type: ElemType, kind: ElemKind) -> Result<()>;
+            fn add_same_string(&mut self, elem: ElemId, other: ElemId) -> Result<()>;
+            fn add_shape(&mut self, elem: ElemId, shape: u64) -> Result<()>;
+            fn add_shape_field(&mut self, shape: u64, pos: usize, field: &str) -> Result<()>;
+            fn add_parse_error(&mut self, file: &str, line: usize, message: &str) -> Result<()>;
+            fn add_string_hash(&mut self, elem: ElemId, hash: u64, len: usize) -> Result<()>;
+            fn add_root_meta(&mut self, file: &str, key: &str, value: &str) -> Result<()>;
+            fn add_depth(&mut self, elem: ElemId, depth: usize) -> Result<()>;
+            fn add_subtree_size(&mut self, elem: ElemId, size: usize) -> Result<()>;
+            fn add_codepoint(&mut self, elem: ElemId, codepoint: u32) -> Result<()>;
         }
     }
 }
@@ -0,0 +1,471 @@
+//! A backend that publishes facts as messages to a message queue, such as
+//! [NATS](https://nats.io) or [Kafka](https://kafka.apache.org), so that
+//! downstream services can consume facts as they are extracted.
+//!
+//! Each message is a JSON envelope containing one or more facts:
+//!
+//! ```json
+//! {
+//!   "version": 1,
+//!   "file": "input.json",
+//!   "facts": [
+//!     { "relation": "type", "args": [1, "Map"] },
+//!     { "relation": "map", "args": [1, 2, 3] }
+//!   ]
+//! }
+//! ```
+//!
+//! `file` is the root element name of the value the facts were extracted from,
+//! or `null` if the value has no root element name. Facts use the relations of
+//! the Souffle schema (see [souffle_sqlite][crate::backend::souffle_sqlite]),
+//! with these differences: `type` facts record the exact [ElemType] (e.g. `I64`
+//! instead of `Number`), floating point values are recorded in a `float`
+//! relation, byte arrays are recorded in a `bytes` relation as arrays of
+//! numbers unless the backend has a [BytesPolicy], and map keys are element
+//! identifiers.
+//!
+//! Messages are delivered to a [MessageSink]. This module provides sinks that
+//! publish to a NATS server ([NatsSink]) and that write one envelope per line
+//! to any writer ([WriterSink]); the latter can be piped into command-line
+//! producers of other message queues, such as `kcat` for Kafka.
+
+use serde_json::{json, Value};
+use std::{
+    io::{self, BufRead, BufReader, Write},
+    net::{IpAddr, TcpStream, ToSocketAddrs},
+};
+
+use crate::{
+    backend::BackendError, bytes_policy::BytesPolicy, DatalogExtractorBackend, ElemId, ElemKind,
+    ElemType, Result,
+};
+
+/// Version of the message envelope format.
+pub const ENVELOPE_VERSION: u64 = 1;
+
+/// Destination of messages published by [Backend].
+pub trait MessageSink {
+    /// Publish message `payload` to topic `topic`.
+    fn publish(&mut self, topic: &str, payload: &[u8]) -> io::Result<()>;
+
+    /// Ensure that published messages have been delivered.
+    ///
+    /// The default implementation does nothing.
+    fn flush(&mut self) -> io::Result<()> {
+        io::Result::Ok(())
+    }
+}
+
+/// Sink that writes each message on its own line to a writer,
+/// ignoring topics.
+pub struct WriterSink<W: Write> {
+    writer: W,
+}
+
+impl<W: Write> WriterSink<W> {
+    pub fn new(writer: W) -> Self {
+        WriterSink { writer }
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+impl<W: Write> MessageSink for WriterSink<W> {
+    fn publish(&mut self, _topic: &str, payload: &[u8]) -> io::Result<()> {
+        self.writer.write_all(payload)?;
+        self.writer.write_all(b"\n")
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+/// Sink that publishes messages to a [NATS](https://nats.io) server, using
+/// topics as subjects. This is a minimal client of the NATS protocol that
+/// does not support TLS or authentication. It answers the server's pings
+/// whenever it publishes a message, so that the server keeps the connection
+/// open while facts are extracted.
+pub struct NatsSink {
+    reader: BufReader<TcpStream>,
+    writer: TcpStream,
+    /// Start of a line from the server that has not been read entirely.
+    partial_line: String,
+}
+
+impl NatsSink {
+    /// Connect to the NATS server at `addr`, e.g. `localhost:4222`.
+    pub fn connect<A: ToSocketAddrs>(addr: A) -> io::Result<Self> {
+        let writer = TcpStream::connect(addr)?;
+        let mut sink = NatsSink {
+            reader: BufReader::new(writer.try_clone()?),
+            writer,
+            partial_line: String::new(),
+        };
+
+        // the server greets clients with an INFO message
+        let info = sink.read_line()?;
+        if !info.starts_with("INFO") {
+            return io::Result::Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("unexpected greeting from NATS server: {}", info),
+            ));
+        }
+
+        sink.writer
+            .write_all(b"CONNECT {\"verbose\":false,\"pedantic\":false}\r\n")?;
+        io::Result::Ok(sink)
+    }
+
+    fn read_line(&mut self) -> io::Result<String> {
+        let mut line = std::mem::take(&mut self.partial_line);
+        self.reader.read_line(&mut line)?;
+        io::Result::Ok(line.trim_end().to_string())
+    }
+
+    /// Handle `line` sent by the server, answering pings, and return whether
+    /// it is a pong.
+    fn handle_line(&mut self, line: &str) -> io::Result<bool> {
+        if line == "PONG" {
+            return io::Result::Ok(true);
+        } else if line == "PING" {
+            self.writer.write_all(b"PONG\r\n")?;
+        } else if line.starts_with("-ERR") {
+            return io::Result::Err(io::Error::other(line.to_string()));
+        } else if line.is_empty() {
+            return io::Result::Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "NATS server closed the connection",
+            ));
+        }
+
+        // other messages, such as updated INFO messages, need no answer
+        io::Result::Ok(false)
+    }
+
+    /// Handle the lines the server has sent so far without waiting for more.
+    fn handle_pending_lines(&mut self) -> io::Result<()> {
+        let mut lines = Vec::new();
+        self.reader.get_ref().set_nonblocking(true)?;
+        let res = loop {
+            match self.reader.read_line(&mut self.partial_line) {
+                io::Result::Ok(0) => break io::Result::Ok(true),
+                io::Result::Ok(_) => {
+                    if self.partial_line.ends_with('\n') {
+                        lines.push(std::mem::take(&mut self.partial_line));
+                    }
+                }
+                io::Result::Err(err) if err.kind() == io::ErrorKind::WouldBlock => {
+                    break io::Result::Ok(false)
+                }
+                io::Result::Err(err) => break io::Result::Err(err),
+            }
+        };
+        // the reader shares the socket with the writer, which answers pings
+        self.reader.get_ref().set_nonblocking(false)?;
+        let closed = res?;
+
+        for line in lines {
+            self.handle_line(line.trim_end())?;
+        }
+        if closed {
+            self.handle_line("")?;
+        }
+        io::Result::Ok(())
+    }
+}
+
+impl MessageSink for NatsSink {
+    fn publish(&mut self, topic: &str, payload: &[u8]) -> io::Result<()> {
+        self.handle_pending_lines()?;
+        write!(self.writer, "PUB {} {}\r\n", topic, payload.len())?;
+        self.writer.write_all(payload)?;
+        self.writer.write_all(b"\r\n")
+    }
+
+    /// Wait until the server has processed all published messages.
+    fn flush(&mut self) -> io::Result<()> {
+        self.writer.write_all(b"PING\r\n")?;
+        loop {
+            let line = self.read_line()?;
+            if self.handle_line(&line)? {
+                return io::Result::Ok(());
+            }
+        }
+    }
+}
+
+/// How facts are grouped into messages.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Granularity {
+    /// Publish each fact in its own message.
+    Fact,
+
+    /// Publish all facts about a root value in a single message, once the
+    /// extractor has generated all of them. Facts generated between root
+    /// values, such as metadata recorded after a root, are published in
+    /// messages of their own.
+    Root,
+}
+
+/// DatalogExtractorBackend impl that publishes facts to a [MessageSink].
+pub struct Backend<S: MessageSink> {
+    sink: S,
+    topic: String,
+    granularity: Granularity,
+    cur_file: Option<String>,
+    facts: Vec<Value>,
+    bytes_policy: Option<BytesPolicy>,
+}
+
+impl<S: MessageSink> Backend<S> {
+    /// Create a backend that publishes facts to topic `topic` of `sink`.
+    pub fn new(sink: S, topic: &str, granularity: Granularity) -> Self {
+        Backend {
+            sink,
+            topic: topic.to_string(),
+            granularity,
+            cur_file: None,
+            facts: Vec::new(),
+            bytes_policy: None,
+        }
+    }
+
+    /// Record byte arrays in the `bytes` relation as text encoded with
+    /// `policy`, with their encoding and original length in the
+    /// `bytesEncoding` relation, as the Souffle SQLite backends do.
+    pub fn with_bytes_policy(mut self, policy: BytesPolicy) -> Self {
+        self.bytes_policy = Some(policy);
+        self
+    }
+
+    /// Publish the facts that are still buffered, flush the sink, and return
+    /// it.
+    pub fn into_sink(mut self) -> Result<S> {
+        self.publish()?;
+        self.sink.flush().map_err(BackendError::Io)?;
+        Result::Ok(self.sink)
+    }
+
+    fn add_fact(&mut self, relation: &str, args: Value) -> Result<()> {
+        self.facts
+            .push(json!({ "relation": relation, "args": args }));
+        if self.granularity == Granularity::Fact {
+            self.publish()?;
+        }
+
+        Result::Ok(())
+    }
+
+    /// Publish buffered facts in a single message.
+    fn publish(&mut self) -> Result<()> {
+        if self.facts.is_empty() {
+            return Result::Ok(());
+        }
+
+        let envelope = json!({
+            "version": ENVELOPE_VERSION,
+            "file": self.cur_file,
+            "facts": std::mem::take(&mut self.facts),
+        });
+
+        self.sink
+            .publish(&self.topic, envelope.to_string().as_bytes())
+            .map_err(BackendError::Io)?;
+        Result::Ok(())
+    }
+}
+
+impl<S: MessageSink> DatalogExtractorBackend for Backend<S> {
+    fn begin(&mut self) -> Result<()> {
+        // facts generated since the previous root are not about this one
+        self.publish()?;
+        self.cur_file = None;
+        Result::Ok(())
+    }
+
+    fn finish(&mut self) -> Result<()> {
+        self.publish()?;
+        self.cur_file = None;
+        Result::Ok(())
+    }
+
+    fn add_root_elem(&mut self, file: &str, elem: ElemId) -> Result<()> {
+        self.cur_file = Some(file.to_string());
+        self.add_fact("rootElem", json!([file, elem.0]))
+    }
+
+    fn add_file_format(&mut self, file: &str, format: &str) -> Result<()> {
+        self.add_fact("fileFormat", json!([file, format]))
+    }
+
+    fn add_key_field(&mut self, struct_name: &str, field: &str) -> Result<()> {
+        self.add_fact("keyField", json!([struct_name, field]))
+    }
+
+    fn add_elem(&mut self, elem: ElemId, elem_type: ElemType) -> Result<()> {
+        self.add_fact("type", json!([elem.0, format!("{:?}", elem_type)]))
+    }
+
+    fn add_bool(&mut self, elem: ElemId, value: bool) -> Result<()> {
+        self.add_fact("bool", json!([elem.0, value]))
+    }
+
+    fn add_i64(&mut self, elem: ElemId, value: i64) -> Result<()> {
+        self.add_fact("number", json!([elem.0, value]))
+    }
+
+    fn add_u64(&mut self, elem: ElemId, value: u64) -> Result<()> {
+        self.add_fact("number", json!([elem.0, value]))
+    }
+
+    fn add_f64(&mut self, elem: ElemId, value: f64) -> Result<()> {
+        self.add_fact("float", json!([elem.0, value]))
+    }
+
+    fn add_str(&mut self, elem: ElemId, value: &str) -> Result<()> {
+        self.add_fact("string", json!([elem.0, value]))
+    }
+
+    fn add_full_str(&mut self, elem: ElemId, value: &str) -> Result<()> {
+        self.add_fact("fullString", json!([elem.0, value]))
+    }
+
+    fn add_bytes(&mut self, elem: ElemId, value: &[u8]) -> Result<()> {
+        match self.bytes_policy {
+            Some(policy) => {
+                self.add_fact("bytes", json!([elem.0, policy.encode(value)]))?;
+                self.add_fact("bytesEncoding", json!([elem.0, policy.name(), value.len()]))
+            }
+
+            None => self.add_fact("bytes", json!([elem.0, value])),
+        }
+    }
+
+    fn add_map_entry(&mut self, elem: ElemId, key: ElemId, value: ElemId) -> Result<()> {
+        self.add_fact("map", json!([elem.0, key.0, value.0]))
+    }
+
+    fn add_struct_type(&mut self, elem: ElemId, struct_name: &str) -> Result<()> {
+        self.add_fact("structType", json!([elem.0, struct_name]))
+    }
+
+    fn add_struct_entry(&mut self, elem: ElemId, key: &str, value: ElemId) -> Result<()> {
+        self.add_fact("struct", json!([elem.0, key, value.0]))
+    }
+
+    fn add_key(&mut self, elem: ElemId, key: ElemId) -> Result<()> {
+        self.add_fact("key", json!([elem.0, key.0]))
+    }
+
+    fn add_seq_entry(&mut self, elem: ElemId, pos: usize, value: ElemId) -> Result<()> {
+        self.add_fact("seq", json!([elem.0, pos, value.0]))
+    }
+
+    fn add_variant_type(
+        &mut self,
+        elem: ElemId,
+        type_name: &str,
+        variant_name: &str,
+    ) -> Result<()> {
+        self.add_fact("variantType", json!([elem.0, type_name, variant_name]))
+    }
+
+    fn add_schema_type(&mut self, elem: ElemId, schema_ref: &str) -> Result<()> {
+        self.add_fact("schemaType", json!([elem.0, schema_ref]))
+    }
+
+    fn add_datetime(&mut self, elem: ElemId, epoch_micros: i64, tz_offset: i32) -> Result<()> {
+        self.add_fact("datetime", json!([elem.0, epoch_micros, tz_offset]))
+    }
+
+    fn add_uuid(&mut self, elem: ElemId, hi: u64, lo: u64) -> Result<()> {
+        self.add_fact("uuid", json!([elem.0, hi, lo]))
+    }
+
+    fn add_ip(&mut self, elem: ElemId, address: IpAddr) -> Result<()> {
+        let version = if address.is_ipv4() { 4 } else { 6 };
+        self.add_fact("ip", json!([elem.0, version, address.to_string()]))
+    }
+
+    fn add_url(&mut self, elem: ElemId, scheme: &str, host: &str) -> Result<()> {
+        self.add_fact("url", json!([elem.0, scheme, host]))
+    }
+
+    fn add_email(&mut self, elem: ElemId, local: &str, domain: &str) -> Result<()> {
+        self.add_fact("email", json!([elem.0, local, domain]))
+    }
+
+    fn add_declared_len(&mut self, elem: ElemId, len: usize) -> Result<()> {
+        self.add_fact("declaredLen", json!([elem.0, len]))
+    }
+
+    fn add_absent_field(&mut self, elem: ElemId, field: &str) -> Result<()> {
+        self.add_fact("absentField", json!([elem.0, field]))
+    }
+
+    fn add_unit(&mut self, elem: ElemId) -> Result<()> {
+        self.add_fact("unit", json!([elem.0]))
+    }
+
+    fn add_truncated(&mut self, relation: &str, quota: usize) -> Result<()> {
+        self.add_fact("truncated", json!([relation, quota]))
+    }
+
+    fn add_redacted(&mut self, elem: ElemId) -> Result<()> {
+        self.add_fact("redacted", json!([elem.0]))
+    }
+
+    fn add_sampled(&mut self, elem: ElemId, len: usize, kept: usize) -> Result<()> {
+        self.add_fact("sampled", json!([elem.0, len, kept]))
+    }
+
+    fn add_elem_type_kind(&mut self, elem_type: ElemType, kind: ElemKind) -> Result<()> {
+        self.add_fact(
+            "elemTypeKind",
+            json!([format!("{:?}", elem_type), kind.name()]),
+        )
+    }
+
+    fn add_same_string(&mut self, elem: ElemId, other: ElemId) -> Result<()> {
+        self.add_fact("sameString", json!([elem.0, other.0]))
+    }
+
+    fn add_shape(&mut self, elem: ElemId, shape: u64) -> Result<()> {
+        self.add_fact("shape", json!([elem.0, shape as i64]))
+    }
+
+    fn add_shape_field(&mut self, shape: u64, pos: usize, field: &str) -> Result<()> {
+        self.add_fact("shapeField", json!([shape as i64, pos, field]))
+    }
+
+    fn add_parse_error(&mut self, file: &str, line: usize, message: &str) -> Result<()> {
+        self.add_fact("parseError", json!([file, line, message]))
+    }
+
+    fn add_string_hash(&mut self, elem: ElemId, hash: u64, len: usize) -> Result<()> {
+        self.add_fact("stringHash", json!([elem.0, hash as i64, len]))
+    }
+
+    fn add_root_meta(&mut self, file: &str, key: &str, value: &str) -> Result<()> {
+        self.add_fact("rootMeta", json!([file, key, value]))
+    }
+
+    fn add_depth(&mut self, elem: ElemId, depth: usize) -> Result<()> {
+        self.add_fact("depth", json!([elem.0, depth]))
+    }
+
+    fn add_subtree_size(&mut self, elem: ElemId, size: usize) -> Result<()> {
+        self.add_fact("subtreeSize", json!([elem.0, size]))
+    }
+
+    fn add_codepoint(&mut self, elem: ElemId, codepoint: u32) -> Result<()> {
+        self.add_fact("char", json!([elem.0, codepoint]))
+    }
+
+    fn add_tuple_entry(&mut self, elem: ElemId, pos: usize, value: ElemId) -> Result<()> {
+        self.add_fact("tuple", json!([elem.0, pos, value.0]))
+    }
+}
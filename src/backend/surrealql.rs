@@ -0,0 +1,423 @@
+//! A backend that exports facts as a [SurrealQL](https://surrealdb.com/docs/surrealql)
+//! script, which stores elements as records and entries as graph edges when
+//! it is run by SurrealDB. The backend only writes the script; it does not
+//! connect to a SurrealDB server:
+//!
+//! ```text
+//! BEGIN TRANSACTION;
+//! CREATE elem:1 CONTENT {"type": "Map"};
+//! CREATE elem:2 CONTENT {"type": "Str", "string": "abc"};
+//! CREATE elem:3 CONTENT {"type": "Number", "number": 5};
+//! RELATE elem:1->map_entry->elem:3 CONTENT {"key": elem:2};
+//! CREATE file CONTENT {"name": "input.json", "root": elem:1, "format": "json"};
+//! COMMIT TRANSACTION;
+//! ```
+//!
+//! Each element is a record of the `elem` table whose identifier is the
+//! element identifier. Facts about a single element, such as its element
+//! type, value, or struct type, are fields of its record named after their
+//! relation in the Souffle schema (see
+//! [souffle_sqlite][crate::backend::souffle_sqlite]), e.g. `struct_type` for
+//! `structType`. Facts with more than one value, such as `variantType`, are
+//! objects with a field for each column. An element can have many absent
+//! fields, so the `absent_field` field is an array of their names.
+//!
+//! Entries are edges from the element to its entry: `map_entry` edges with
+//! the map key, `struct_entry` edges with the field name, `seq_entry` and
+//! `tuple_entry` edges with the position, `struct_key` edges to the key
+//! fields of structs, and `same_string` edges to string elements of earlier
+//! files with the same value (see
+//! [with_same_strings][crate::DatalogExtractor::with_same_strings]). Root
+//! elements and file formats are records of the `file` table, key fields of
+//! struct types are records of the `key_field` table, relations truncated by
+//! fact quotas are records of the `truncated` table, the kinds of element
+//! types are records of the `elem_type_kind` table, the fields of shapes are
+//! records of the `shape_field` table, documents that could not be read are
+//! records of the `parse_error` table, and metadata of roots are records of
+//! the `root_meta` table. Queries can then traverse values
+//! with SurrealDB's graph syntax:
+//!
+//! ```text
+//! SELECT ->struct_entry[WHERE field = "name"]->elem.string FROM elem:1;
+//! ```
+//!
+//! Scripts can be imported with `surreal import`, or run by SurrealDB's Rust
+//! client. The client is not a dependency of this crate, so callers that
+//! want to store facts in a running server pass it the script themselves:
+//!
+//! ```ignore
+//! let mut script = Vec::new();
+//! extractor.get_backend().write_to(&mut script)?;
+//! db.query(String::from_utf8(script)?).await?;
+//! ```
+
+use delegate::delegate;
+use std::{
+    collections::BTreeMap,
+    fs::File,
+    io::{self, BufWriter, Write},
+    net::IpAddr,
+};
+
+use crate::{
+    backend::{
+        tuples::{self, MapKey, Term},
+        vector::{self, BackendData},
+        BackendError,
+    },
+    bytes_policy::BytesPolicy,
+    DatalogExtractorBackend, ElemId, ElemKind, ElemType, Result,
+};
+
+/// Table of element records.
+pub const ELEM_TABLE: &str = "elem";
+
+/// Write the facts of `data` as a SurrealQL script to `writer`.
+pub fn write_statements<K: MapKey, W: Write>(
+    data: &BackendData<K>,
+    writer: &mut W,
+) -> io::Result<()> {
+    let mut records: BTreeMap<ElemId, Vec<(String, String)>> = BTreeMap::new();
+    let mut edges: Vec<String> = Vec::new();
+    let mut files: BTreeMap<String, Vec<(&str, String)>> = BTreeMap::new();
+    let mut others: Vec<String> = Vec::new();
+
+    for relation in tuples::relations(data).iter() {
+        for fact in relation.facts.iter() {
+            match relation.name {
+                "rootElem" | "fileFormat" => {
+                    files
+                        .entry(term(&fact[0]))
+                        .or_default()
+                        .push((relation.columns[1], term(&fact[1])));
+                }
+
+                "keyField" | "truncated" | "elemTypeKind" | "shapeField" | "parseError"
+                | "rootMeta" => {
+                    others.push(format!(
+                        "CREATE {} CONTENT {};",
+                        field_name(relation.name),
+                        object(relation.columns, fact)
+                    ));
+                }
+
+                "map" | "struct" | "seq" | "tuple" | "key" | "sameString" => {
+                    let last = fact.len() - 1;
+                    let content = if last > 1 {
+                        format!(
+                            " CONTENT {}",
+                            object(&relation.columns[1..last], &fact[1..last])
+                        )
+                    } else {
+                        String::new()
+                    };
+                    edges.push(format!(
+                        "RELATE {}->{}->{}{};",
+                        term(&fact[0]),
+                        edge_table(relation.name),
+                        term(&fact[last]),
+                        content
+                    ));
+                }
+
+                _ => {
+                    let Term::Elem(elem) = fact[0] else {
+                        continue;
+                    };
+                    let value = match fact.len() {
+                        1 => "true".to_string(),
+                        2 => term(&fact[1]),
+                        _ => object(&relation.columns[1..], &fact[1..]),
+                    };
+                    records
+                        .entry(elem)
+                        .or_default()
+                        .push((field_name(relation.name), value));
+                }
+            }
+        }
+    }
+
+    writeln!(writer, "BEGIN TRANSACTION;")?;
+
+    for (elem, fields) in records.iter() {
+        // absent fields are the only relation with many facts per element
+        let mut content: Vec<String> = Vec::new();
+        let mut absent_fields: Vec<&str> = Vec::new();
+        for (field, value) in fields.iter() {
+            if field == "absent_field" {
+                absent_fields.push(value);
+            } else {
+                content.push(format!("{}: {}", quoted(field), value));
+            }
+        }
+        if !absent_fields.is_empty() {
+            content.push(format!(
+                "{}: [{}]",
+                quoted("absent_field"),
+                absent_fields.join(", ")
+            ));
+        }
+
+        writeln!(
+            writer,
+            "CREATE {}:{} CONTENT {{{}}};",
+            ELEM_TABLE,
+            elem,
+            content.join(", ")
+        )?;
+    }
+
+    for edge in edges.iter() {
+        writeln!(writer, "{}", edge)?;
+    }
+
+    for (file, fields) in files.iter() {
+        let mut content = vec![format!("{}: {}", quoted("name"), file)];
+        for (field, value) in fields.iter() {
+            content.push(format!("{}: {}", quoted(field), value));
+        }
+        writeln!(writer, "CREATE file CONTENT {{{}}};", content.join(", "))?;
+    }
+
+    for other in others.iter() {
+        writeln!(writer, "{}", other)?;
+    }
+
+    writeln!(writer, "COMMIT TRANSACTION;")?;
+
+    io::Result::Ok(())
+}
+
+/// Name of the edge table of an entry relation.
+fn edge_table(relation: &str) -> &'static str {
+    match relation {
+        "map" => "map_entry",
+        "struct" => "struct_entry",
+        "seq" => "seq_entry",
+        "tuple" => "tuple_entry",
+        "sameString" => "same_string",
+        _ => "struct_key",
+    }
+}
+
+/// Name of the record field of a relation, in snake case.
+fn field_name(relation: &str) -> String {
+    let mut name = String::with_capacity(relation.len() + 2);
+    for c in relation.chars() {
+        if c.is_ascii_uppercase() {
+            name.push('_');
+            name.push(c.to_ascii_lowercase());
+        } else {
+            name.push(c);
+        }
+    }
+    name
+}
+
+fn object(columns: &[&str], terms: &[Term]) -> String {
+    let fields: Vec<String> = columns
+        .iter()
+        .zip(terms.iter())
+        .map(|(column, arg)| format!("{}: {}", quoted(column), term(arg)))
+        .collect();
+    format!("{{{}}}", fields.join(", "))
+}
+
+fn term(term: &Term) -> String {
+    match term {
+        Term::Elem(elem) => format!("{}:{}", ELEM_TABLE, elem),
+        Term::Number(value) => value.to_string(),
+        Term::Bool(value) => value.to_string(),
+        Term::Name(text) | Term::Text(text) => quoted(text),
+        Term::Unresolved(sym) => quoted(&format!("#{}", sym)),
+    }
+}
+
+/// Quote `text` as a SurrealQL string, escaping quotes, backslashes, and
+/// control characters as in JSON.
+fn quoted(text: &str) -> String {
+    let mut quoted = String::with_capacity(text.len() + 2);
+    quoted.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\t' => quoted.push_str("\\t"),
+            '\r' => quoted.push_str("\\r"),
+            c if c.is_control() => quoted.push_str(&format!("\\u{:04x}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// DatalogExtractorBackend impl that writes facts as a SurrealQL script.
+/// Map keys are elements, as in [vector::Backend].
+#[derive(Default)]
+pub struct Backend {
+    vector_backend: vector::Backend,
+}
+
+impl Backend {
+    /// Intern strings of each [namespace][vector::SymbolNamespace]
+    /// separately; see [vector::Backend::with_separate_namespaces].
+    pub fn with_separate_namespaces(mut self) -> Self {
+        self.vector_backend = self.vector_backend.with_separate_namespaces();
+        self
+    }
+
+    /// Extract byte arrays as text encoded with `policy`; see
+    /// [vector::Backend::with_bytes_policy].
+    pub fn with_bytes_policy(mut self, policy: BytesPolicy) -> Self {
+        self.vector_backend = self.vector_backend.with_bytes_policy(policy);
+        self
+    }
+
+    /// Write facts as a SurrealQL script to `writer`.
+    pub fn write_to<W: Write>(self, writer: &mut W) -> Result<()> {
+        write_statements(&self.vector_backend.get_data(), writer).map_err(BackendError::Io)?;
+        Result::Ok(())
+    }
+
+    /// Write facts as a SurrealQL script to the file with name `filename`,
+    /// which is overwritten if it exists.
+    pub fn dump_to_file(self, filename: &str) -> Result<()> {
+        let mut writer = BufWriter::new(File::create(filename).map_err(BackendError::Io)?);
+        self.write_to(&mut writer)?;
+        writer.flush().map_err(BackendError::Io)?;
+        Result::Ok(())
+    }
+}
+
+impl DatalogExtractorBackend for Backend {
+    delegate! {
+        to (&mut self.vector_backend) {
+            fn add_root_elem(&mut self, file: &str, elem: ElemId) -> Result<()>;
+            fn add_file_format(&mut self, file: &str, format: &str) -> Result<()>;
+            fn add_key_field(&mut self, struct_name: &str, field: &str) -> Result<()>;
+            fn add_elem(&mut self, elem: ElemId, elem_type: ElemType) -> Result<()>;
+            fn add_bool(&mut self, elem: ElemId, value: bool) -> Result<()>;
+            fn add_i64(&mut self, elem: ElemId, value: i64) -> Result<()>;
+            fn add_u64(&mut self, elem: ElemId, value: u64) -> Result<()>;
+            fn add_str(&mut self, elem: ElemId, value: &str) -> Result<()>;
+            fn add_bytes(&mut self, elem: ElemId, value: &[u8]) -> Result<()>;
+            fn add_full_str(&mut self, elem: ElemId, value: &str) -> Result<()>;
+            fn add_map_entry(&mut self, elem: ElemId, key: ElemId, value: ElemId) -> Result<()>;
+            fn add_struct_type(&mut self, elem: ElemId, struct_name: &str) -> Result<()>;
+            fn add_struct_entry(&mut self, elem: ElemId, key: &str, value: ElemId) -> Result<()>;
+            fn add_key(&mut self, elem: ElemId, key: ElemId) -> Result<()>;
+            fn add_seq_entry(&mut self, elem: ElemId, pos: usize, value: ElemId) -> Result<()>;
+            fn add_variant_type(&mut self, elem: ElemId, type_name: &str, variant_name: &str) -> Result<()>;
+            fn add_tuple_entry(&mut self, elem: ElemId, pos: usize, value: ElemId) -> Result<()>;
+            fn add_schema_type(&mut self, elem: ElemId, schema_ref: &str) -> Result<()>;
+            fn add_datetime(&mut self, elem: ElemId, epoch_micros: i64, tz_offset: i32) -> Result<()>;
+            fn add_uuid(&mut self, elem: ElemId, hi: u64, lo: u64) -> Result<()>;
+            fn add_ip(&mut self, elem: ElemId, address: IpAddr) -> Result<()>;
+            fn add_url(&mut self, elem: ElemId, scheme: &str, host: &str) -> Result<()>;
+            fn add_email(&mut self, elem: ElemId, local: &str, domain: &str) -> Result<()>;
+            fn add_declared_len(&mut self, elem: ElemId, len: usize) -> Result<()>;
+            fn add_absent_field(&mut self, elem: ElemId, field: &str) -> Result<()>;
+            fn add_unit(&mut self, elem: ElemId) -> Result<()>;
+            fn add_truncated(&mut self, relation: &str, quota: usize) -> Result<()>;
+            fn add_redacted(&mut self, elem: ElemId) -> Result<()>;
+            fn add_sampled(&mut self, elem: ElemId, len: usize, kept: usize) -> Result<()>;
+            fn add_elem_type_kind(&mut self, elem_type: ElemType, kind: ElemKind) -> Result<()>;
+            fn add_same_string(&mut self, elem: ElemId, other: ElemId) -> Result<()>;
+            fn add_shape(&mut self, elem: ElemId, shape: u64) -> Result<()>;
+            fn add_shape_field(&mut self, shape: u64, pos: usize, field: &str) -> Result<()>;
+            fn add_parse_error(&mut self, file: &str, line: usize, message: &str) -> Result<()>;
+            fn add_string_hash(&mut self, elem: ElemId, hash: u64, len: usize) -> Result<()>;
+            fn add_root_meta(&mut self, file: &str, key: &str, value: &str) -> Result<()>;
+            fn add_depth(&mut self, elem: ElemId, depth: usize) -> Result<()>;
+            fn add_subtree_size(&mut self, elem: ElemId, size: usize) -> Result<()>;
+            fn add_codepoint(&mut self, elem: ElemId, codepoint: u32) -> Result<()>;
+        }
+    }
+}
+
+/// DatalogExtractorBackend impl that writes facts as a SurrealQL script,
+/// with map keys written as strings, as in [vector::StringKeyBackend].
+#[derive(Default)]
+pub struct StringKeyBackend {
+    vector_backend: vector::StringKeyBackend,
+}
+
+impl StringKeyBackend {
+    /// Intern strings of each [namespace][vector::SymbolNamespace]
+    /// separately; see [vector::StringKeyBackend::with_separate_namespaces].
+    pub fn with_separate_namespaces(mut self) -> Self {
+        self.vector_backend = self.vector_backend.with_separate_namespaces();
+        self
+    }
+
+    /// Extract byte arrays as text encoded with `policy`; see
+    /// [vector::StringKeyBackend::with_bytes_policy].
+    pub fn with_bytes_policy(mut self, policy: BytesPolicy) -> Self {
+        self.vector_backend = self.vector_backend.with_bytes_policy(policy);
+        self
+    }
+
+    /// Write facts as a SurrealQL script to `writer`.
+    pub fn write_to<W: Write>(self, writer: &mut W) -> Result<()> {
+        write_statements(&self.vector_backend.get_data(), writer).map_err(BackendError::Io)?;
+        Result::Ok(())
+    }
+
+    /// Write facts as a SurrealQL script to the file with name `filename`,
+    /// which is overwritten if it exists.
+    pub fn dump_to_file(self, filename: &str) -> Result<()> {
+        let mut writer = BufWriter::new(File::create(filename).map_err(BackendError::Io)?);
+        self.write_to(&mut writer)?;
+        writer.flush().map_err(BackendError::Io)?;
+        Result::Ok(())
+    }
+}
+
+impl DatalogExtractorBackend for StringKeyBackend {
+    delegate! {
+        to (&mut self.vector_backend) {
+            fn add_root_elem(&mut self, file: &str, elem: ElemId) -> Result<()>;
+            fn add_file_format(&mut self, file: &str, format: &str) -> Result<()>;
+            fn add_key_field(&mut self, struct_name: &str, field: &str) -> Result<()>;
+            fn add_elem(&mut self, elem: ElemId, elem_type: ElemType) -> Result<()>;
+            fn add_bool(&mut self, elem: ElemId, value: bool) -> Result<()>;
+            fn add_i64(&mut self, elem: ElemId, value: i64) -> Result<()>;
+            fn add_u64(&mut self, elem: ElemId, value: u64) -> Result<()>;
+            fn add_str(&mut self, elem: ElemId, value: &str) -> Result<()>;
+            fn add_bytes(&mut self, elem: ElemId, value: &[u8]) -> Result<()>;
+            fn add_full_str(&mut self, elem: ElemId, value: &str) -> Result<()>;
+            fn add_map_entry(&mut self, elem: ElemId, key: ElemId, value: ElemId) -> Result<()>;
+            fn add_struct_type(&mut self, elem: ElemId, struct_name: &str) -> Result<()>;
+            fn add_struct_entry(&mut self, elem: ElemId, key: &str, value: ElemId) -> Result<()>;
+            fn add_key(&mut self, elem: ElemId, key: ElemId) -> Result<()>;
+            fn add_seq_entry(&mut self, elem: ElemId, pos: usize, value: ElemId) -> Result<()>;
+            fn add_variant_type(&mut self, elem: ElemId, type_name: &str, variant_name: &str) -> Result<()>;
+            fn add_tuple_entry(&mut self, elem: ElemId, pos: usize, value: ElemId) -> Result<()>;
+            fn add_schema_type(&mut self, elem: ElemId, schema_ref: &str) -> Result<()>;
+            fn add_datetime(&mut self, elem: ElemId, epoch_micros: i64, tz_offset: i32) -> Result<()>;
+            fn add_uuid(&mut self, elem: ElemId, hi: u64, lo: u64) -> Result<()>;
+            fn add_ip(&mut self, elem: ElemId, address: IpAddr) -> Result<()>;
+            fn add_url(&mut self, elem: ElemId, scheme: &str, host: &str) -> Result<()>;
+            fn add_email(&mut self, elem: ElemId, local: &str, domain: &str) -> Result<()>;
+            fn add_declared_len(&mut self, elem: ElemId, len: usize) -> Result<()>;
+            fn add_absent_field(&mut self, elem: ElemId, field: &str) -> Result<()>;
+            fn add_unit(&mut self, elem: ElemId) -> Result<()>;
+            fn add_truncated(&mut self, relation: &str, quota: usize) -> Result<()>;
+            fn add_redacted(&mut self, elem: ElemId) -> Result<()>;
+            fn add_sampled(&mut self, elem: ElemId, len: usize, kept: usize) -> Result<()>;
+            fn add_elem_type_kind(&mut self, elem_type: ElemType, kind: ElemKind) -> Result<()>;
+            fn add_same_string(&mut self, elem: ElemId, other: ElemId) -> Result<()>;
+            fn add_shape(&mut self, elem: ElemId, shape: u64) -> Result<()>;
+            fn add_shape_field(&mut self, shape: u64, pos: usize, field: &str) -> Result<()>;
+            fn add_parse_error(&mut self, file: &str, line: usize, message: &str) -> Result<()>;
+            fn add_string_hash(&mut self, elem: ElemId, hash: u64, len: usize) -> Result<()>;
+            fn add_root_meta(&mut self, file: &str, key: &str, value: &str) -> Result<()>;
+            fn add_depth(&mut self, elem: ElemId, depth: usize) -> Result<()>;
+            fn add_subtree_size(&mut self, elem: ElemId, size: usize) -> Result<()>;
+            fn add_codepoint(&mut self, elem: ElemId, codepoint: u32) -> Result<()>;
+        }
+    }
+}
@@ -0,0 +1,804 @@
+//! Facts stored by the vector backends as tuples of terms, for backends that
+//! write facts in the textual formats of other logic programming systems.
+//!
+//! [relations] lists the facts of each relation of the Souffle schema (see
+//! [souffle_sqlite][crate::backend::souffle_sqlite]), with interned strings
+//! resolved. Terms distinguish names, such as element types and field names,
+//! from text taken from input values, such as string values and file names,
+//! since formats often write the former as identifiers and the latter as
+//! quoted strings.
+//!
+//! [inline_scalars] converts relations to an alternative schema, in which
+//! scalar values are inlined in the facts of their parents.
+//!
+//! [data_from_text] materializes facts read back from such formats.
+
+use std::{collections::HashMap, hash::Hash, str::FromStr};
+
+use crate::{
+    backend::{
+        vector::{BackendData, SymbolId},
+        BackendError,
+    },
+    ElemId, Result,
+};
+
+/// An argument of a fact.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Term<'a> {
+    /// Element identifier.
+    Elem(ElemId),
+
+    /// Integer, e.g. the value of a number element or a position in a
+    /// sequence.
+    Number(i64),
+
+    /// Boolean value.
+    Bool(bool),
+
+    /// Name from the schema of input values, e.g. an element type, a field
+    /// name, or a type name.
+    Name(&'a str),
+
+    /// Text from input values, e.g. a string value or a file name.
+    Text(&'a str),
+
+    /// Interned string missing from the symbol tables.
+    Unresolved(SymbolId),
+}
+
+/// The facts of a relation, sorted by their arguments.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Relation<'a> {
+    /// Name of the relation, as in the Souffle schema.
+    pub name: &'static str,
+
+    /// Names of the columns of the relation, as in the Souffle schema.
+    pub columns: &'static [&'static str],
+
+    /// Arguments of the facts of the relation.
+    pub facts: Vec<Vec<Term<'a>>>,
+}
+
+impl<'a> Relation<'a> {
+    /// Number of arguments of the facts of the relation.
+    pub fn arity(&self) -> usize {
+        self.columns.len()
+    }
+}
+
+/// Types of map keys stored by vector backends.
+pub trait MapKey: Eq + Hash + Sized {
+    /// The term of map key `key`.
+    fn term<'a>(data: &'a BackendData<Self>, key: &Self) -> Term<'a>;
+}
+
+impl MapKey for ElemId {
+    fn term<'a>(_data: &'a BackendData<Self>, key: &Self) -> Term<'a> {
+        Term::Elem(*key)
+    }
+}
+
+impl MapKey for SymbolId {
+    fn term<'a>(data: &'a BackendData<Self>, key: &Self) -> Term<'a> {
+        text(data, key)
+    }
+}
+
+fn name<'a, K: Eq + Hash>(data: &'a BackendData<K>, sym: &SymbolId) -> Term<'a> {
+    match data.symbol(sym) {
+        Some(symbol) => Term::Name(symbol),
+        None => Term::Unresolved(*sym),
+    }
+}
+
+fn text<'a, K: Eq + Hash>(data: &'a BackendData<K>, sym: &SymbolId) -> Term<'a> {
+    match data.symbol(sym) {
+        Some(symbol) => Term::Text(symbol),
+        None => Term::Unresolved(*sym),
+    }
+}
+
+/// The facts of every relation of the Souffle schema stored in `data`, in
+/// the order of the schema. Relations without facts are included, so that
+/// formats can declare them.
+pub fn relations<'a, K: MapKey>(data: &'a BackendData<K>) -> Vec<Relation<'a>> {
+    let elem = |elem: &ElemId| Term::Elem(*elem);
+    let number = |value: i64| Term::Number(value);
+
+    let mut relations = Vec::new();
+    let mut add =
+        |name: &'static str, columns: &'static [&'static str], mut facts: Vec<Vec<Term<'a>>>| {
+            facts.sort();
+            relations.push(Relation {
+                name,
+                columns,
+                facts,
+            });
+        };
+
+    add(
+        "rootElem",
+        &["file", "elem"],
+        data.root_elem_table
+            .iter()
+            .map(|(file, root)| vec![text(data, file), elem(root)])
+            .collect(),
+    );
+    add(
+        "fileFormat",
+        &["file", "format"],
+        data.file_format_table
+            .iter()
+            .map(|(file, format)| vec![text(data, file), name(data, format)])
+            .collect(),
+    );
+    add(
+        "keyField",
+        &["type", "field"],
+        data.key_field_table
+            .iter()
+            .map(|(struct_name, field)| vec![name(data, struct_name), name(data, field)])
+            .collect(),
+    );
+    add(
+        "type",
+        &["id", "type"],
+        data.type_table
+            .iter()
+            .map(|(id, ty)| vec![elem(id), name(data, ty)])
+            .collect(),
+    );
+    add(
+        "bool",
+        &["id", "value"],
+        data.bool_table
+            .iter()
+            .map(|(id, value)| vec![elem(id), Term::Bool(*value)])
+            .collect(),
+    );
+    add(
+        "number",
+        &["id", "value"],
+        data.numbers()
+            .map(|(id, value)| vec![elem(id), number(value)])
+            .collect(),
+    );
+    add(
+        "string",
+        &["id", "value"],
+        data.string_table
+            .iter()
+            .map(|(id, value)| vec![elem(id), text(data, value)])
+            .collect(),
+    );
+    add(
+        "fullString",
+        &["id", "value"],
+        data.full_string_table
+            .iter()
+            .map(|(id, value)| vec![elem(id), Term::Text(value)])
+            .collect(),
+    );
+    add(
+        "map",
+        &["id", "key", "value"],
+        data.map_table
+            .iter()
+            .map(|((id, key), value)| vec![elem(id), K::term(data, key), elem(value)])
+            .collect(),
+    );
+    add(
+        "struct",
+        &["id", "field", "value"],
+        data.struct_table
+            .iter()
+            .map(|((id, field), value)| vec![elem(id), name(data, field), elem(value)])
+            .collect(),
+    );
+    add(
+        "key",
+        &["id", "key"],
+        data.key_table
+            .iter()
+            .map(|(id, key)| vec![elem(id), elem(key)])
+            .collect(),
+    );
+    add(
+        "seq",
+        &["id", "pos", "value"],
+        data.seq_table
+            .iter()
+            .map(|((id, pos), value)| vec![elem(id), number(*pos as i64), elem(value)])
+            .collect(),
+    );
+    add(
+        "tuple",
+        &["id", "pos", "value"],
+        data.tuple_table
+            .iter()
+            .map(|((id, pos), value)| vec![elem(id), number(*pos as i64), elem(value)])
+            .collect(),
+    );
+    add(
+        "structType",
+        &["id", "type"],
+        data.struct_type_table
+            .iter()
+            .map(|(id, ty)| vec![elem(id), name(data, ty)])
+            .collect(),
+    );
+    add(
+        "variantType",
+        &["id", "type", "variant"],
+        data.variant_type_table
+            .iter()
+            .map(|(id, (ty, variant))| vec![elem(id), name(data, ty), name(data, variant)])
+            .collect(),
+    );
+    add(
+        "schemaType",
+        &["id", "schema"],
+        data.schema_type_table
+            .iter()
+            .map(|(id, schema_ref)| vec![elem(id), text(data, schema_ref)])
+            .collect(),
+    );
+    add(
+        "datetime",
+        &["id", "micros", "offset"],
+        data.datetime_table
+            .iter()
+            .map(|(id, (micros, offset))| vec![elem(id), number(*micros), number(*offset as i64)])
+            .collect(),
+    );
+    add(
+        "uuid",
+        &["id", "hi", "lo"],
+        data.uuid_table
+            .iter()
+            .map(|(id, (hi, lo))| vec![elem(id), number(*hi as i64), number(*lo as i64)])
+            .collect(),
+    );
+    add(
+        "ip",
+        &["id", "version", "address"],
+        data.ip_table
+            .iter()
+            .map(|(id, (version, address))| {
+                vec![elem(id), number(*version as i64), text(data, address)]
+            })
+            .collect(),
+    );
+    add(
+        "url",
+        &["id", "scheme", "host"],
+        data.url_table
+            .iter()
+            .map(|(id, (scheme, host))| vec![elem(id), text(data, scheme), text(data, host)])
+            .collect(),
+    );
+    add(
+        "email",
+        &["id", "local", "domain"],
+        data.email_table
+            .iter()
+            .map(|(id, (local, domain))| vec![elem(id), text(data, local), text(data, domain)])
+            .collect(),
+    );
+    add(
+        "bytes",
+        &["id", "value"],
+        data.bytes_table
+            .iter()
+            .map(|(id, value)| vec![elem(id), text(data, value)])
+            .collect(),
+    );
+    add(
+        "bytesEncoding",
+        &["id", "encoding", "length"],
+        data.bytes_encoding_table
+            .iter()
+            .map(|(id, (encoding, len))| vec![elem(id), name(data, encoding), number(*len as i64)])
+            .collect(),
+    );
+    add(
+        "declaredLen",
+        &["id", "len"],
+        data.declared_len_table
+            .iter()
+            .map(|(id, len)| vec![elem(id), number(*len as i64)])
+            .collect(),
+    );
+    add(
+        "absentField",
+        &["id", "field"],
+        data.absent_field_table
+            .iter()
+            .map(|(id, field)| vec![elem(id), name(data, field)])
+            .collect(),
+    );
+    add(
+        "unit",
+        &["id"],
+        data.unit_table.iter().map(|id| vec![elem(id)]).collect(),
+    );
+    add(
+        "truncated",
+        &["relation", "quota"],
+        data.truncated_table
+            .iter()
+            .map(|(relation, quota)| vec![name(data, relation), number(*quota as i64)])
+            .collect(),
+    );
+    add(
+        "redacted",
+        &["id"],
+        data.redacted_table
+            .iter()
+            .map(|id| vec![elem(id)])
+            .collect(),
+    );
+    add(
+        "sampled",
+        &["id", "len", "kept"],
+        data.sampled_table
+            .iter()
+            .map(|(id, (len, kept))| vec![elem(id), number(*len as i64), number(*kept as i64)])
+            .collect(),
+    );
+    add(
+        "elemTypeKind",
+        &["type", "kind"],
+        data.elem_type_kind_table
+            .iter()
+            .map(|(elem_type, kind)| vec![name(data, elem_type), name(data, kind)])
+            .collect(),
+    );
+    add(
+        "sameString",
+        &["id", "other"],
+        data.same_string_table
+            .iter()
+            .map(|(id, other)| vec![elem(id), elem(other)])
+            .collect(),
+    );
+    add(
+        "shape",
+        &["id", "shape"],
+        data.shape_table
+            .iter()
+            .map(|(id, shape)| vec![elem(id), number(*shape as i64)])
+            .collect(),
+    );
+    add(
+        "stringHash",
+        &["id", "hash", "len"],
+        data.string_hash_table
+            .iter()
+            .map(|(id, (hash, len))| vec![elem(id), number(*hash as i64), number(*len as i64)])
+            .collect(),
+    );
+    add(
+        "depth",
+        &["id", "depth"],
+        data.depth_table
+            .iter()
+            .map(|(id, depth)| vec![elem(id), number(*depth as i64)])
+            .collect(),
+    );
+    add(
+        "subtreeSize",
+        &["id", "size"],
+        data.subtree_size_table
+            .iter()
+            .map(|(id, size)| vec![elem(id), number(*size as i64)])
+            .collect(),
+    );
+    add(
+        "char",
+        &["id", "codepoint"],
+        data.char_table
+            .iter()
+            .map(|(id, codepoint)| vec![elem(id), number(*codepoint as i64)])
+            .collect(),
+    );
+    add(
+        "shapeField",
+        &["shape", "pos", "field"],
+        data.shape_field_table
+            .iter()
+            .map(|((shape, pos), field)| {
+                vec![
+                    number(*shape as i64),
+                    number(*pos as i64),
+                    name(data, field),
+                ]
+            })
+            .collect(),
+    );
+    add(
+        "parseError",
+        &["file", "line", "message"],
+        data.parse_error_table
+            .iter()
+            .map(|((file, line), message)| {
+                vec![text(data, file), number(*line as i64), text(data, message)]
+            })
+            .collect(),
+    );
+    add(
+        "rootMeta",
+        &["file", "key", "value"],
+        data.root_meta_table
+            .iter()
+            .map(|(file, key, value)| vec![text(data, file), text(data, key), text(data, value)])
+            .collect(),
+    );
+
+    relations
+}
+
+/// Relations of entries whose scalar values are inlined by [inline_scalars],
+/// and the relations whose facts replace them.
+const INLINE_RELATIONS: &[(&str, &str, &[&str])] = &[
+    ("map", "mapInline", &["id", "key", "type", "num", "sym"]),
+    (
+        "struct",
+        "structInline",
+        &["id", "field", "type", "num", "sym"],
+    ),
+    ("seq", "seqInline", &["id", "pos", "type", "num", "sym"]),
+    ("tuple", "tupleInline", &["id", "pos", "type", "num", "sym"]),
+];
+
+/// Convert `relations`, as returned by [relations], to a schema in which
+/// booleans, numbers, and strings that are the values of map, struct,
+/// sequence, and tuple entries are inlined in the facts of the entries,
+/// instead of being elements with facts of their own. A struct field with a
+/// string value is a single fact of `structInline(id, field, type, num, sym)`
+/// instead of facts of `struct`, `type`, and `string`, which roughly halves
+/// the number of facts of flat records:
+///
+/// ```text
+/// structInline(1, "name", "Str", 0, "abc").
+/// structInline(1, "size", "Number", 3, "").
+/// ```
+///
+/// `type` is the element type of the value, `num` its number, or 1 and 0 for
+/// `true` and `false`, and `sym` its string. Unused columns are 0 and the
+/// empty string. Values with other facts, such as recognized scalars,
+/// redacted values, and map keys, are not inlined. The relations of the
+/// schema are declared in `schemas/serde_inline.dl`, or in
+/// `schemas/serde_string_key_inline.dl` if map keys are strings, and each
+/// follows the relation it complements.
+pub fn inline_scalars(relations: Vec<Relation<'_>>) -> Vec<Relation<'_>> {
+    let mut occurrences: HashMap<ElemId, usize> = HashMap::new();
+    for fact in relations.iter().flat_map(|relation| relation.facts.iter()) {
+        for term in fact.iter() {
+            if let Term::Elem(elem) = term {
+                *occurrences.entry(*elem).or_default() += 1;
+            }
+        }
+    }
+
+    let mut types: HashMap<ElemId, Term> = HashMap::new();
+    let mut values: HashMap<ElemId, [Term; 2]> = HashMap::new();
+    for relation in relations.iter() {
+        for fact in relation.facts.iter() {
+            match (relation.name, fact.as_slice()) {
+                ("type", [Term::Elem(elem), ty]) => {
+                    types.insert(*elem, ty.clone());
+                }
+                ("bool", [Term::Elem(elem), Term::Bool(value)]) => {
+                    values.insert(*elem, [Term::Number(*value as i64), Term::Text("")]);
+                }
+                ("number", [Term::Elem(elem), value]) => {
+                    values.insert(*elem, [value.clone(), Term::Text("")]);
+                }
+                ("string", [Term::Elem(elem), value]) => {
+                    values.insert(*elem, [Term::Number(0), value.clone()]);
+                }
+                _ => {}
+            }
+        }
+    }
+
+    // values with facts of their type, their value, and a single entry
+    let mut inlined: HashMap<ElemId, Vec<Term>> = HashMap::new();
+    for relation in relations.iter() {
+        if !INLINE_RELATIONS
+            .iter()
+            .any(|(name, ..)| *name == relation.name)
+        {
+            continue;
+        }
+
+        for fact in relation.facts.iter() {
+            if let Some(Term::Elem(value)) = fact.last() {
+                if let (Some(ty), Some([num, sym]), Some(3)) =
+                    (types.get(value), values.get(value), occurrences.get(value))
+                {
+                    inlined.insert(*value, vec![ty.clone(), num.clone(), sym.clone()]);
+                }
+            }
+        }
+    }
+
+    let mut inline_relations = Vec::new();
+    for mut relation in relations {
+        let inline = INLINE_RELATIONS
+            .iter()
+            .find(|(name, ..)| *name == relation.name);
+
+        match (relation.name, inline) {
+            ("type" | "bool" | "number" | "string", _) => {
+                relation.facts.retain(|fact| match fact.first() {
+                    Some(Term::Elem(elem)) => !inlined.contains_key(elem),
+                    _ => true,
+                });
+                inline_relations.push(relation);
+            }
+
+            (_, Some((_, name, columns))) => {
+                let mut facts = Vec::new();
+                relation.facts.retain(|fact| match fact.last() {
+                    Some(Term::Elem(value)) if inlined.contains_key(value) => {
+                        let mut fact = fact.clone();
+                        fact.pop();
+                        fact.extend(inlined[value].iter().cloned());
+                        facts.push(fact);
+                        false
+                    }
+                    _ => true,
+                });
+                facts.sort();
+
+                inline_relations.push(relation);
+                inline_relations.push(Relation {
+                    name,
+                    columns,
+                    facts,
+                });
+            }
+
+            _ => inline_relations.push(relation),
+        }
+    }
+
+    inline_relations
+}
+
+/// Materialize facts given as the names of their relations and their
+/// arguments as text, e.g. facts read from files written by other backends,
+/// in the data of a vector backend. Element identifiers and numbers are
+/// parsed as integers, and booleans as `true` or `false`. Map keys must be
+/// element identifiers.
+///
+/// Returns a [Schema][BackendError::Schema] error if a fact does not match
+/// its relation.
+pub fn data_from_text<R: AsRef<str>>(
+    facts: impl IntoIterator<Item = (R, Vec<String>)>,
+) -> Result<BackendData<ElemId>> {
+    let mut reader = TextReader::default();
+    for (relation, args) in facts {
+        reader.add(relation.as_ref(), &args)?;
+    }
+
+    Result::Ok(reader.data)
+}
+
+#[derive(Default)]
+struct TextReader {
+    data: BackendData<ElemId>,
+    cur_symbol_id: usize,
+}
+
+impl TextReader {
+    fn intern(&mut self, symbol: &str) -> SymbolId {
+        match self.data.symbol_table.get_by_left(symbol) {
+            Some(id) => *id,
+            None => {
+                self.cur_symbol_id += 1;
+                let id = SymbolId(self.cur_symbol_id);
+                self.data.symbol_table.insert(symbol.to_string(), id);
+                id
+            }
+        }
+    }
+
+    fn add(&mut self, relation: &str, args: &[String]) -> Result<()> {
+        match (relation, args) {
+            ("rootElem", [file, root]) => {
+                let file = self.intern(file);
+                self.data.root_elem_table.insert(file, elem(root)?);
+            }
+            ("fileFormat", [file, format]) => {
+                let fact = (self.intern(file), self.intern(format));
+                self.data.file_format_table.insert(fact.0, fact.1);
+            }
+            ("keyField", [struct_name, field]) => {
+                let fact = (self.intern(struct_name), self.intern(field));
+                self.data.key_field_table.insert(fact);
+            }
+            ("type", [id, ty]) => {
+                let ty = self.intern(ty);
+                self.data.type_table.insert(elem(id)?, ty);
+            }
+            ("bool", [id, value]) => {
+                let value = match value.as_str() {
+                    "true" | "1" => true,
+                    "false" | "0" => false,
+                    _ => return Result::Err(invalid("boolean", value)),
+                };
+                self.data.bool_table.insert(elem(id)?, value);
+            }
+            ("number", [id, value]) => {
+                self.data.number_table.insert(elem(id)?, number(value)?);
+            }
+            ("string", [id, value]) => {
+                let value = self.intern(value);
+                self.data.string_table.insert(elem(id)?, value);
+            }
+            ("fullString", [id, value]) => {
+                self.data.full_string_table.insert(elem(id)?, value.clone());
+            }
+            ("map", [id, key, value]) => {
+                self.data
+                    .map_table
+                    .insert((elem(id)?, elem(key)?), elem(value)?);
+            }
+            ("struct", [id, field, value]) => {
+                let field = self.intern(field);
+                self.data
+                    .struct_table
+                    .insert((elem(id)?, field), elem(value)?);
+            }
+            ("key", [id, key]) => {
+                self.data.key_table.insert((elem(id)?, elem(key)?));
+            }
+            ("seq", [id, pos, value]) => {
+                self.data
+                    .seq_table
+                    .insert((elem(id)?, number(pos)?), elem(value)?);
+            }
+            ("tuple", [id, pos, value]) => {
+                self.data
+                    .tuple_table
+                    .insert((elem(id)?, number(pos)?), elem(value)?);
+            }
+            ("structType", [id, ty]) => {
+                let ty = self.intern(ty);
+                self.data.struct_type_table.insert(elem(id)?, ty);
+            }
+            ("variantType", [id, ty, variant]) => {
+                let fact = (self.intern(ty), self.intern(variant));
+                self.data.variant_type_table.insert(elem(id)?, fact);
+            }
+            ("schemaType", [id, schema_ref]) => {
+                let schema_ref = self.intern(schema_ref);
+                self.data.schema_type_table.insert(elem(id)?, schema_ref);
+            }
+            ("datetime", [id, micros, offset]) => {
+                let fact = (number(micros)?, number(offset)?);
+                self.data.datetime_table.insert(elem(id)?, fact);
+            }
+            // the bits of UUIDs are written as signed integers
+            ("uuid", [id, hi, lo]) => {
+                let fact = (number::<i64>(hi)? as u64, number::<i64>(lo)? as u64);
+                self.data.uuid_table.insert(elem(id)?, fact);
+            }
+            ("ip", [id, version, address]) => {
+                let fact = (number(version)?, self.intern(address));
+                self.data.ip_table.insert(elem(id)?, fact);
+            }
+            ("url", [id, scheme, host]) => {
+                let fact = (self.intern(scheme), self.intern(host));
+                self.data.url_table.insert(elem(id)?, fact);
+            }
+            ("email", [id, local, domain]) => {
+                let fact = (self.intern(local), self.intern(domain));
+                self.data.email_table.insert(elem(id)?, fact);
+            }
+            ("bytes", [id, value]) => {
+                let value = self.intern(value);
+                self.data.bytes_table.insert(elem(id)?, value);
+            }
+            ("bytesEncoding", [id, encoding, len]) => {
+                let fact = (self.intern(encoding), number(len)?);
+                self.data.bytes_encoding_table.insert(elem(id)?, fact);
+            }
+            ("declaredLen", [id, len]) => {
+                self.data.declared_len_table.insert(elem(id)?, number(len)?);
+            }
+            ("absentField", [id, field]) => {
+                let field = self.intern(field);
+                self.data.absent_field_table.insert((elem(id)?, field));
+            }
+            ("unit", [id]) => {
+                self.data.unit_table.insert(elem(id)?);
+            }
+            ("truncated", [relation, quota]) => {
+                let relation = self.intern(relation);
+                self.data.truncated_table.insert(relation, number(quota)?);
+            }
+            ("redacted", [id]) => {
+                self.data.redacted_table.insert(elem(id)?);
+            }
+            ("sampled", [id, len, kept]) => {
+                let fact = (number(len)?, number(kept)?);
+                self.data.sampled_table.insert(elem(id)?, fact);
+            }
+            ("elemTypeKind", [ty, kind]) => {
+                let fact = (self.intern(ty), self.intern(kind));
+                self.data.elem_type_kind_table.insert(fact.0, fact.1);
+            }
+            // shapes are hashes, which are written as signed integers
+            ("shape", [id, shape]) => {
+                let shape = number::<i64>(shape)? as u64;
+                self.data.shape_table.insert(elem(id)?, shape);
+            }
+            // string hashes are written as signed integers
+            ("stringHash", [id, hash, len]) => {
+                let hash = number::<i64>(hash)? as u64;
+                self.data
+                    .string_hash_table
+                    .insert(elem(id)?, (hash, number(len)?));
+            }
+            ("depth", [id, depth]) => {
+                self.data.depth_table.insert(elem(id)?, number(depth)?);
+            }
+            ("subtreeSize", [id, size]) => {
+                self.data
+                    .subtree_size_table
+                    .insert(elem(id)?, number(size)?);
+            }
+            ("char", [id, codepoint]) => {
+                self.data.char_table.insert(elem(id)?, number(codepoint)?);
+            }
+            ("shapeField", [shape, pos, field]) => {
+                let key = (number::<i64>(shape)? as u64, number(pos)?);
+                let field = self.intern(field);
+                self.data.shape_field_table.insert(key, field);
+            }
+            ("parseError", [file, line, message]) => {
+                let key = (self.intern(file), number(line)?);
+                let message = self.intern(message);
+                self.data.parse_error_table.insert(key, message);
+            }
+            ("rootMeta", [file, key, value]) => {
+                let fact = (self.intern(file), self.intern(key), self.intern(value));
+                self.data.root_meta_table.insert(fact);
+            }
+            ("sameString", [id, other]) => {
+                self.data
+                    .same_string_table
+                    .insert((elem(id)?, elem(other)?));
+            }
+            _ => {
+                return Result::Err(
+                    BackendError::Schema(format!(
+                        "unexpected fact of {} with {} arguments",
+                        relation,
+                        args.len()
+                    ))
+                    .into(),
+                )
+            }
+        }
+
+        Result::Ok(())
+    }
+}
+
+fn invalid(what: &str, arg: &str) -> crate::DatalogExtractionError {
+    BackendError::Schema(format!("invalid {} {:?}", what, arg)).into()
+}
+
+fn number<T: FromStr>(arg: &str) -> Result<T> {
+    arg.parse().map_err(|_| invalid("number", arg))
+}
+
+fn elem(arg: &str) -> Result<ElemId> {
+    arg.parse().map(ElemId).map_err(|_| invalid("element", arg))
+}
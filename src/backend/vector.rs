@@ -3,15 +3,20 @@
 use bimap::BiHashMap;
 use delegate::delegate;
 use std::{
-    collections::HashMap,
+    collections::{HashMap, HashSet},
     fmt::{Debug, Display},
     hash::Hash,
+    net::IpAddr,
+    ops::RangeInclusive,
 };
 
-use crate::{DatalogExtractionError, DatalogExtractorBackend, ElemId, ElemType, Result};
+use crate::{
+    bytes_policy::BytesPolicy, DatalogExtractionError, DatalogExtractorBackend, ElemId, ElemKind,
+    ElemType, Fact, Result,
+};
 
 /// Identifier for an interned string.
-#[derive(PartialEq, Eq, Hash, Clone, Copy, Debug)]
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy, Debug)]
 pub struct SymbolId(pub usize);
 
 impl Display for SymbolId {
@@ -20,6 +25,17 @@ impl Display for SymbolId {
     }
 }
 
+/// Identifier for an interned number in the number pool of a backend; see
+/// [Backend::with_number_pool].
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy, Debug)]
+pub struct NumberId(pub usize);
+
+impl Display for NumberId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
 const BOOL_NAME: &str = "Bool";
 const NUMBER_NAME: &str = "Number";
 const STR_NAME: &str = "Str";
@@ -34,13 +50,157 @@ const UNIT_NAME: &str = "Unit";
 const UNIT_STRUCT_NAME: &str = "UnitStruct";
 const UNIT_VARIANT_NAME: &str = "UnitVariant";
 
+/// Name of the element type of byte arrays, which is only interned by
+/// backends with a [BytesPolicy].
+const BYTES_NAME: &str = "Bytes";
+
+/// Names of element types, which are interned before any other string.
+pub(crate) const ELEM_TYPE_NAMES: [&str; 13] = [
+    BOOL_NAME,
+    NUMBER_NAME,
+    STR_NAME,
+    MAP_NAME,
+    SEQ_NAME,
+    STRUCT_NAME,
+    STRUCT_VARIANT_NAME,
+    TUPLE_NAME,
+    TUPLE_STRUCT_NAME,
+    TUPLE_VARIANT_NAME,
+    UNIT_NAME,
+    UNIT_STRUCT_NAME,
+    UNIT_VARIANT_NAME,
+];
+
+/// Namespaces of interned strings. By default, all namespaces share one
+/// symbol table, so equal strings have the same identifier regardless of
+/// their namespace. Backends created with
+/// [with_separate_namespaces][Backend::with_separate_namespaces] intern
+/// strings of each namespace separately, so that e.g. a field called `Map`
+/// and the element type `Map` have distinct identifiers.
+#[derive(PartialEq, Eq, Hash, Clone, Copy, Debug)]
+pub enum SymbolNamespace {
+    /// Names of element types, e.g. `Map`, and of their kinds, e.g.
+    /// `container`.
+    ElemType,
+
+    /// Names of struct fields.
+    Field,
+
+    /// Names of struct and enum types.
+    TypeName,
+
+    /// Names of enum variants.
+    VariantName,
+
+    /// String values, file names, input format names, schema references, the
+    /// parts of recognized scalars, and the relations of truncation facts.
+    Value,
+}
+
+impl SymbolNamespace {
+    const SEPARATE: [SymbolNamespace; 4] = [
+        SymbolNamespace::ElemType,
+        SymbolNamespace::Field,
+        SymbolNamespace::TypeName,
+        SymbolNamespace::VariantName,
+    ];
+}
+
+/// Number of references to an interned string in the facts of a backend.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SymbolFrequency {
+    pub symbol: String,
+
+    /// Number of facts referencing the string.
+    pub count: usize,
+
+    /// Size of the references to the string in bytes, i.e. the length of the
+    /// string times [count][Self::count]. This is the space taken by the
+    /// string in formats that do not intern strings, such as facts files.
+    pub bytes: usize,
+}
+
+/// Report of the most frequently referenced interned strings of a backend,
+/// which helps to decide which fields to elide or hash when databases of
+/// facts get too large.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct StringReport {
+    /// The most frequently referenced strings, in decreasing order of their
+    /// number of references.
+    pub top: Vec<SymbolFrequency>,
+
+    /// Number of distinct referenced strings.
+    pub distinct: usize,
+
+    /// Total number of references to interned strings.
+    pub total_count: usize,
+
+    /// Total size of the references to interned strings in bytes.
+    pub total_bytes: usize,
+}
+
+impl StringReport {
+    /// Share of the total size of references taken by `freq`, from 0 to 1.
+    pub fn byte_share(&self, freq: &SymbolFrequency) -> f64 {
+        if self.total_bytes == 0 {
+            0.0
+        } else {
+            freq.bytes as f64 / self.total_bytes as f64
+        }
+    }
+}
+
+impl Display for StringReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "{} references to {} distinct strings, {} bytes in total",
+            self.total_count, self.distinct, self.total_bytes
+        )?;
+        writeln!(
+            f,
+            "{:<32} | {:>10} | {:>12} | {:>7}",
+            "String", "Count", "Bytes", "Share"
+        )?;
+        writeln!(f, "{}", "-".repeat(70))?;
+        for freq in self.top.iter() {
+            // long strings are cut off so that columns stay aligned
+            let symbol: String = freq.symbol.chars().take(32).collect();
+            writeln!(
+                f,
+                "{:<32} | {:>10} | {:>12} | {:>6.2}%",
+                symbol.escape_debug().to_string(),
+                freq.count,
+                freq.bytes,
+                self.byte_share(freq) * 100.0
+            )?;
+        }
+
+        std::fmt::Result::Ok(())
+    }
+}
+
 /// Table data generated by vector backends.
 pub struct BackendData<K: Eq + Hash> {
+    /// Stores interned strings. If namespaces are separated, only stores
+    /// strings in the [Value][SymbolNamespace::Value] namespace.
     pub symbol_table: BiHashMap<String, SymbolId>,
 
+    /// Stores interned strings of the other namespaces, if namespaces are
+    /// separated. Identifiers are distinct across all symbol tables.
+    pub namespace_symbol_tables: HashMap<SymbolNamespace, BiHashMap<String, SymbolId>>,
+
     /// Stores root elemets of files.
     pub root_elem_table: HashMap<SymbolId, ElemId>,
 
+    /// Stores input formats of files.
+    /// Columns: (file, format)
+    pub file_format_table: HashMap<SymbolId, SymbolId>,
+
+    /// Stores fields that identify structs.
+    /// Columns: (struct name, field name)
+    pub key_field_table: HashSet<(SymbolId, SymbolId)>,
+
     /// Stores types of elements.
     /// Columns: (elem, elem type)
     pub type_table: HashMap<ElemId, SymbolId>,
@@ -49,14 +209,27 @@ pub struct BackendData<K: Eq + Hash> {
     /// Columns: (elem, value)
     pub bool_table: HashMap<ElemId, bool>,
 
-    /// Stores values of number elements.
+    /// Stores values of number elements, except numbers in the number pool.
     /// Columns: (elem, value)
     pub number_table: HashMap<ElemId, i64>,
 
+    /// Stores interned numbers, if the backend has a number pool.
+    /// Columns: (value, number id)
+    pub number_pool: BiHashMap<i64, NumberId>,
+
+    /// Stores the interned values of number elements whose values are in the
+    /// number pool.
+    /// Columns: (elem, number id)
+    pub pooled_number_table: HashMap<ElemId, NumberId>,
+
     /// Stores values of string elements.
     /// Columns: (elem, symbol)
     pub string_table: HashMap<ElemId, SymbolId>,
 
+    /// Stores original text of string elements changed by a string policy.
+    /// The text is not interned. Columns: (elem, text)
+    pub full_string_table: HashMap<ElemId, String>,
+
     /// Stores map entry facts.
     /// Columns: (elem, key, value)
     pub map_table: HashMap<(ElemId, K), ElemId>,
@@ -69,6 +242,10 @@ pub struct BackendData<K: Eq + Hash> {
     /// Columns: (elem, field name, value elem)
     pub struct_table: HashMap<(ElemId, SymbolId), ElemId>,
 
+    /// Stores the values of key fields of structs.
+    /// Columns: (elem, key elem)
+    pub key_table: HashSet<(ElemId, ElemId)>,
+
     /// Stores sequence entry facts.
     /// Columns: (elem, index, value)
     pub seq_table: HashMap<(ElemId, usize), ElemId>,
@@ -80,40 +257,264 @@ pub struct BackendData<K: Eq + Hash> {
     /// Stores tuple entry facts.
     /// Columns: (elem, index, value)
     pub tuple_table: HashMap<(ElemId, usize), ElemId>,
+
+    /// Stores references of the schemas that elements match.
+    /// Columns: (elem, schema reference)
+    pub schema_type_table: HashMap<ElemId, SymbolId>,
+
+    /// Stores the instants and offsets of string elements that are date-times.
+    /// Columns: (elem, microseconds since the Unix epoch, offset in seconds)
+    pub datetime_table: HashMap<ElemId, (i64, i32)>,
+
+    /// Stores the high and low 64 bits of string elements that are UUIDs.
+    /// Columns: (elem, high bits, low bits)
+    pub uuid_table: HashMap<ElemId, (u64, u64)>,
+
+    /// Stores the versions and canonical text of string elements that are
+    /// IP addresses.
+    /// Columns: (elem, version, address)
+    pub ip_table: HashMap<ElemId, (u8, SymbolId)>,
+
+    /// Stores the schemes and hosts of string elements that are URLs.
+    /// Columns: (elem, scheme, host)
+    pub url_table: HashMap<ElemId, (SymbolId, SymbolId)>,
+
+    /// Stores the local parts and domains of string elements that are email
+    /// addresses.
+    /// Columns: (elem, local part, domain)
+    pub email_table: HashMap<ElemId, (SymbolId, SymbolId)>,
+
+    /// Stores the values of byte arrays, encoded as text by the backend's
+    /// [BytesPolicy].
+    /// Columns: (elem, encoded value)
+    pub bytes_table: HashMap<ElemId, SymbolId>,
+
+    /// Stores the encodings and original lengths of byte arrays.
+    /// Columns: (elem, encoding, length in bytes)
+    pub bytes_encoding_table: HashMap<ElemId, (SymbolId, usize)>,
+
+    /// Stores the numbers of fields that structs declare they serialize.
+    /// Columns: (elem, number of fields)
+    pub declared_len_table: HashMap<ElemId, usize>,
+
+    /// Stores the fields that structs skipped or did not serialize.
+    /// Columns: (elem, field name)
+    pub absent_field_table: HashSet<(ElemId, SymbolId)>,
+
+    /// Stores the unit and unit struct elements materialized as unit facts.
+    /// Columns: (elem)
+    pub unit_table: HashSet<ElemId>,
+
+    /// Stores the relations whose facts were dropped by fact quotas.
+    /// Columns: (relation name, quota)
+    pub truncated_table: HashMap<SymbolId, usize>,
+
+    /// Stores the elements whose values were redacted.
+    /// Columns: (elem)
+    pub redacted_table: HashSet<ElemId>,
+
+    /// Stores the lengths of sampled sequences and the number of their
+    /// elements that were extracted.
+    /// Columns: (elem, length, number of extracted elements)
+    pub sampled_table: HashMap<ElemId, (usize, usize)>,
+
+    /// Stores the kinds of element types.
+    /// Columns: (elem type, kind)
+    pub elem_type_kind_table: HashMap<SymbolId, SymbolId>,
+
+    /// Stores string elements with the same value as the first element with
+    /// the value in an earlier input file.
+    /// Columns: (elem, other elem)
+    pub same_string_table: HashSet<(ElemId, ElemId)>,
+
+    /// Stores the shapes of structs and maps.
+    /// Columns: (elem, shape)
+    pub shape_table: HashMap<ElemId, u64>,
+
+    /// Stores the fields of shapes, sorted by name.
+    /// Columns: (shape, position, field)
+    pub shape_field_table: HashMap<(u64, usize), SymbolId>,
+
+    /// Stores the documents of input files that could not be read or
+    /// extracted, at line 0 if their position is unknown.
+    /// Columns: (file, line, message)
+    pub parse_error_table: HashMap<(SymbolId, usize), SymbolId>,
+
+    /// Stores the hashes and lengths of elided strings.
+    /// Columns: (elem, hash, length)
+    pub string_hash_table: HashMap<ElemId, (u64, usize)>,
+
+    /// Stores the metadata of roots, e.g. their logical names and URIs.
+    /// Columns: (file, key, value)
+    pub root_meta_table: HashSet<(SymbolId, SymbolId, SymbolId)>,
+
+    /// Stores the depths of elements below their root elements.
+    /// Columns: (elem, depth)
+    pub depth_table: HashMap<ElemId, usize>,
+
+    /// Stores the numbers of elements in the values of elements.
+    /// Columns: (elem, size)
+    pub subtree_size_table: HashMap<ElemId, usize>,
+
+    /// Stores the Unicode code points of char elements.
+    /// Columns: (elem, code point)
+    pub char_table: HashMap<ElemId, u32>,
+
+    /// Whether facts are dumped in order of their identifiers, so that
+    /// dumps of the same data are identical. Enabled by default.
+    pub deterministic_order: bool,
 }
 
 impl<K: Display + Eq + Hash> Default for BackendData<K> {
     fn default() -> Self {
         Self {
             symbol_table: Default::default(),
+            namespace_symbol_tables: Default::default(),
             root_elem_table: Default::default(),
+            file_format_table: Default::default(),
+            key_field_table: Default::default(),
             type_table: Default::default(),
             bool_table: Default::default(),
             number_table: Default::default(),
+            number_pool: Default::default(),
+            pooled_number_table: Default::default(),
             string_table: Default::default(),
+            full_string_table: Default::default(),
             map_table: Default::default(),
             struct_type_table: Default::default(),
             struct_table: Default::default(),
+            key_table: Default::default(),
             seq_table: Default::default(),
             variant_type_table: Default::default(),
             tuple_table: Default::default(),
+            schema_type_table: Default::default(),
+            datetime_table: Default::default(),
+            uuid_table: Default::default(),
+            ip_table: Default::default(),
+            url_table: Default::default(),
+            email_table: Default::default(),
+            bytes_table: Default::default(),
+            bytes_encoding_table: Default::default(),
+            declared_len_table: Default::default(),
+            absent_field_table: Default::default(),
+            unit_table: Default::default(),
+            truncated_table: Default::default(),
+            redacted_table: Default::default(),
+            sampled_table: Default::default(),
+            elem_type_kind_table: Default::default(),
+            same_string_table: Default::default(),
+            shape_table: Default::default(),
+            shape_field_table: Default::default(),
+            parse_error_table: Default::default(),
+            string_hash_table: Default::default(),
+            root_meta_table: Default::default(),
+            depth_table: Default::default(),
+            subtree_size_table: Default::default(),
+            char_table: Default::default(),
+            deterministic_order: true,
         }
     }
 }
 
 impl<K: Eq + Hash> BackendData<K> {
+    /// Return the interned string with identifier `id`, in any namespace.
+    pub fn symbol(&self, id: &SymbolId) -> Option<&String> {
+        self.symbol_table.get_by_right(id).or_else(|| {
+            self.namespace_symbol_tables
+                .values()
+                .find_map(|table| table.get_by_right(id))
+        })
+    }
+
+    /// Return the identifier of interned string `symbol` in `namespace`.
+    pub fn symbol_id(&self, namespace: SymbolNamespace, symbol: &str) -> Option<SymbolId> {
+        self.namespace_symbol_tables
+            .get(&namespace)
+            .unwrap_or(&self.symbol_table)
+            .get_by_left(symbol)
+            .copied()
+    }
+
+    /// Return the value of number element `elem`, whether or not its value
+    /// is in the number pool.
+    pub fn number(&self, elem: &ElemId) -> Option<i64> {
+        match self.pooled_number_table.get(elem) {
+            Some(id) => self.number_pool.get_by_right(id).copied(),
+            None => self.number_table.get(elem).copied(),
+        }
+    }
+
+    /// Iterate over number elements with their values, including elements
+    /// whose values are in the number pool.
+    pub fn numbers(&self) -> impl Iterator<Item = (&ElemId, i64)> {
+        let pooled = self.pooled_number_table.iter().filter_map(|(elem, id)| {
+            self.number_pool
+                .get_by_right(id)
+                .map(|value| (elem, *value))
+        });
+
+        self.number_table
+            .iter()
+            .map(|(elem, value)| (elem, *value))
+            .chain(pooled)
+    }
+
+    /// Iterate over interned strings of all namespaces with their identifiers.
+    pub fn symbols(&self) -> impl Iterator<Item = (&String, &SymbolId)> {
+        self.symbol_table.iter().chain(
+            self.namespace_symbol_tables
+                .values()
+                .flat_map(|table| table.iter()),
+        )
+    }
+
+    /// Intern `symbol` with identifier `id`, e.g. a symbol read from a
+    /// database. Symbols whose text is already interned with another
+    /// identifier, as in data whose namespaces were separated, are placed in
+    /// the symbol table of a separate namespace, so that both identifiers
+    /// resolve.
+    #[cfg(all(feature = "sqlite", not(target_arch = "wasm32")))]
+    pub(crate) fn insert_symbol(&mut self, symbol: String, id: SymbolId) {
+        if !self.symbol_table.contains_left(&symbol) {
+            self.symbol_table.insert(symbol, id);
+            return;
+        }
+
+        for namespace in SymbolNamespace::SEPARATE {
+            let table = self.namespace_symbol_tables.entry(namespace).or_default();
+            if !table.contains_left(&symbol) {
+                table.insert(symbol, id);
+                return;
+            }
+        }
+    }
+
+    /// Collect the facts of a table, sorted by `key` if facts are dumped in
+    /// [deterministic order][Self::deterministic_order].
+    pub fn ordered<T, O: Ord>(
+        &self,
+        facts: impl Iterator<Item = T>,
+        key: impl FnMut(&T) -> O,
+    ) -> Vec<T> {
+        let mut facts: Vec<T> = facts.collect();
+        if self.deterministic_order {
+            facts.sort_unstable_by_key(key);
+        }
+        facts
+    }
+
     /// Print generated fact tables to standard output.
-    pub fn dump_with_fn<'a, S: Display>(&'a self, map_key_fn: impl Fn(&'a Self, &'a K) -> S)
+    pub fn dump_with_fn<'a, S>(&'a self, map_key_fn: impl Fn(&'a Self, &'a K) -> S)
     where
         K: 'a,
-        S: 'a,
+        S: Display + 'a,
     {
-        if !self.symbol_table.is_empty() {
+        if self.symbols().next().is_some() {
             println!("{:^33}", "Symbol Table");
             println!("---------------------------------");
             println!("{:<15} | {:<15}", "String", "Symbol Id");
             println!("---------------------------------");
-            for (str, sym) in self.symbol_table.iter() {
+            for (str, sym) in self.ordered(self.symbols(), |(_, sym)| **sym) {
                 println!("{:<15} | {:<15}", str, sym.0);
             }
             println!();
@@ -124,18 +525,40 @@ impl<K: Eq + Hash> BackendData<K> {
             println!("---------------------------------");
             println!("{:<15} | {:<15}", "File", "Elem Id");
             println!("---------------------------------");
-            for (file, elem) in self.root_elem_table.iter() {
+            for (file, elem) in self.ordered(self.root_elem_table.iter(), |(_, elem)| **elem) {
                 println!("{:<15} | {:<15}", file.0, elem.0);
             }
             println!();
         }
 
+        if !self.file_format_table.is_empty() {
+            println!("{:^33}", "File Format Table");
+            println!("---------------------------------");
+            println!("{:<15} | {:<15}", "File", "Format");
+            println!("---------------------------------");
+            for (file, format) in self.ordered(self.file_format_table.iter(), |(file, _)| **file) {
+                println!("{:<15} | {:<15}", file.0, format.0);
+            }
+            println!();
+        }
+
+        if !self.key_field_table.is_empty() {
+            println!("{:^33}", "Key Field Table");
+            println!("---------------------------------");
+            println!("{:<15} | {:<15}", "Struct Name", "Field Name");
+            println!("---------------------------------");
+            for (struct_name, field) in self.ordered(self.key_field_table.iter(), |fact| **fact) {
+                println!("{:<15} | {:<15}", struct_name.0, field.0);
+            }
+            println!();
+        }
+
         if !self.type_table.is_empty() {
             println!("{:^33}", "Type Table");
             println!("---------------------------------");
             println!("{:<15} | {:<15}", "Elem Id", "Elem Type");
             println!("---------------------------------");
-            for (elem, elem_type) in self.type_table.iter() {
+            for (elem, elem_type) in self.ordered(self.type_table.iter(), |(elem, _)| **elem) {
                 println!("{:<15} | {:<15}", elem.0, elem_type.0);
             }
             println!();
@@ -146,18 +569,29 @@ impl<K: Eq + Hash> BackendData<K> {
             println!("---------------------------------");
             println!("{:<15} | {:<15}", "Elem Id", "Value");
             println!("---------------------------------");
-            for (elem, value) in self.bool_table.iter() {
+            for (elem, value) in self.ordered(self.bool_table.iter(), |(elem, _)| **elem) {
                 println!("{:<15} | {:<15}", elem.0, value);
             }
             println!();
         }
 
-        if !self.number_table.is_empty() {
+        if !self.number_pool.is_empty() {
+            println!("{:^33}", "Number Pool");
+            println!("---------------------------------");
+            println!("{:<15} | {:<15}", "Value", "Number Id");
+            println!("---------------------------------");
+            for (value, id) in self.ordered(self.number_pool.iter(), |(_, id)| **id) {
+                println!("{:<15} | {:<15}", value, id.0);
+            }
+            println!();
+        }
+
+        if !self.number_table.is_empty() || !self.pooled_number_table.is_empty() {
             println!("{:^33}", "Number Table");
             println!("---------------------------------");
             println!("{:<15} | {:<15}", "Elem Id", "Value");
             println!("---------------------------------");
-            for (elem, value) in self.number_table.iter() {
+            for (elem, value) in self.ordered(self.numbers(), |(elem, _)| **elem) {
                 println!("{:<15} | {:<15}", elem.0, value);
             }
             println!();
@@ -168,18 +602,31 @@ impl<K: Eq + Hash> BackendData<K> {
             println!("---------------------------------");
             println!("{:<15} | {:<15}", "Elem Id", "Value");
             println!("---------------------------------");
-            for (elem, value) in self.string_table.iter() {
+            for (elem, value) in self.ordered(self.string_table.iter(), |(elem, _)| **elem) {
                 println!("{:<15} | {:<15}", elem.0, value.0);
             }
             println!();
         }
 
+        if !self.full_string_table.is_empty() {
+            println!("{:^33}", "Full String Table");
+            println!("---------------------------------");
+            println!("{:<15} | {:<15}", "Elem Id", "Text");
+            println!("---------------------------------");
+            for (elem, text) in self.ordered(self.full_string_table.iter(), |(elem, _)| **elem) {
+                println!("{:<15} | {:<15}", elem.0, text);
+            }
+            println!();
+        }
+
         if !self.map_table.is_empty() {
             println!("{:^51}", "Map Table");
             println!("---------------------------------------------------");
             println!("{:<15} | {:<15} | {:<15}", "Elem Id", "Key", "Value");
             println!("---------------------------------------------------");
-            for ((elem, key), val) in self.map_table.iter() {
+            for ((elem, key), val) in
+                self.ordered(self.map_table.iter(), |((elem, _), val)| (*elem, **val))
+            {
                 println!(
                     "{:<15} | {:<15} | {:<15}",
                     elem.0,
@@ -195,8 +642,10 @@ impl<K: Eq + Hash> BackendData<K> {
             println!("---------------------------------");
             println!("{:<15} | {:<15}", "Elem Id", "Struct Type");
             println!("---------------------------------");
-            for (elem, struct_type) in self.string_table.iter() {
-                println!("{:<15} | {:<15?}", elem.0, struct_type.0);
+            for (elem, struct_type) in
+                self.ordered(self.struct_type_table.iter(), |(elem, _)| **elem)
+            {
+                println!("{:<15} | {:<15}", elem.0, struct_type.0);
             }
             println!();
         }
@@ -206,23 +655,34 @@ impl<K: Eq + Hash> BackendData<K> {
             println!("---------------------------------------------------");
             println!("{:<15} | {:<15} | {:<15}", "Elem Id", "Field", "Value");
             println!("---------------------------------------------------");
-            for ((elem, field), val) in self.struct_table.iter() {
+            for ((elem, field), val) in self.ordered(self.struct_table.iter(), |(key, _)| **key) {
                 println!(
                     "{:<15} | {:<15} | {:<15}",
                     elem.0,
-                    self.symbol_table.get_by_right(field).unwrap(),
+                    self.symbol(field).unwrap(),
                     val.0
                 );
             }
             println!();
         }
 
+        if !self.key_table.is_empty() {
+            println!("{:^33}", "Key Table");
+            println!("---------------------------------");
+            println!("{:<15} | {:<15}", "Elem Id", "Key");
+            println!("---------------------------------");
+            for (elem, key) in self.ordered(self.key_table.iter(), |fact| **fact) {
+                println!("{:<15} | {:<15}", elem.0, key.0);
+            }
+            println!();
+        }
+
         if !self.seq_table.is_empty() {
             println!("{:^51}", "Seq Table");
             println!("---------------------------------------------------");
             println!("{:<15} | {:<15} | {:<15}", "Elem Id", "Index", "Value");
             println!("---------------------------------------------------");
-            for ((elem, index), val) in self.seq_table.iter() {
+            for ((elem, index), val) in self.ordered(self.seq_table.iter(), |(key, _)| **key) {
                 println!("{:<15} | {:<15} | {:<15}", elem.0, index, val.0);
             }
             println!();
@@ -236,12 +696,14 @@ impl<K: Eq + Hash> BackendData<K> {
                 "Elem Id", "Enum Type", "Variant Name"
             );
             println!("---------------------------------------------------");
-            for (elem, (enum_type, variant_name)) in self.variant_type_table.iter() {
+            for (elem, (enum_type, variant_name)) in
+                self.ordered(self.variant_type_table.iter(), |(elem, _)| **elem)
+            {
                 println!(
                     "{:<15} | {:<15} | {:<15}",
                     elem.0,
-                    self.symbol_table.get_by_right(enum_type).unwrap(),
-                    self.symbol_table.get_by_right(variant_name).unwrap(),
+                    self.symbol(enum_type).unwrap(),
+                    self.symbol(variant_name).unwrap(),
                 );
             }
             println!();
@@ -252,11 +714,288 @@ impl<K: Eq + Hash> BackendData<K> {
             println!("---------------------------------------------------");
             println!("{:<15} | {:<15} | {:<15}", "Elem Id", "Index", "Value");
             println!("---------------------------------------------------");
-            for ((elem, index), val) in self.tuple_table.iter() {
+            for ((elem, index), val) in self.ordered(self.tuple_table.iter(), |(key, _)| **key) {
                 println!("{:<15} | {:<15?} | {:<15?}", elem.0, index, val.0);
             }
             println!();
         }
+
+        if !self.schema_type_table.is_empty() {
+            println!("{:^33}", "Schema Type Table");
+            println!("---------------------------------");
+            println!("{:<15} | {:<15}", "Elem Id", "Schema");
+            println!("---------------------------------");
+            for (elem, schema) in self.ordered(self.schema_type_table.iter(), |(elem, _)| **elem) {
+                println!("{:<15} | {:<15}", elem.0, self.symbol(schema).unwrap());
+            }
+            println!();
+        }
+
+        if !self.datetime_table.is_empty() {
+            println!("{:^51}", "Datetime Table");
+            println!("---------------------------------------------------");
+            println!("{:<15} | {:<15} | {:<15}", "Elem Id", "Micros", "Offset");
+            println!("---------------------------------------------------");
+            for (elem, (micros, offset)) in
+                self.ordered(self.datetime_table.iter(), |(elem, _)| **elem)
+            {
+                println!("{:<15} | {:<15} | {:<15}", elem.0, micros, offset);
+            }
+            println!();
+        }
+
+        if !self.uuid_table.is_empty() {
+            println!("{:^51}", "UUID Table");
+            println!("---------------------------------------------------");
+            println!("{:<15} | {:<15} | {:<15}", "Elem Id", "High", "Low");
+            println!("---------------------------------------------------");
+            for (elem, (hi, lo)) in self.ordered(self.uuid_table.iter(), |(elem, _)| **elem) {
+                println!("{:<15} | {:<15x} | {:<15x}", elem.0, hi, lo);
+            }
+            println!();
+        }
+
+        if !self.ip_table.is_empty() {
+            println!("{:^51}", "IP Table");
+            println!("---------------------------------------------------");
+            println!("{:<15} | {:<15} | {:<15}", "Elem Id", "Version", "Address");
+            println!("---------------------------------------------------");
+            for (elem, (version, address)) in self.ordered(self.ip_table.iter(), |(elem, _)| **elem)
+            {
+                println!(
+                    "{:<15} | {:<15} | {:<15}",
+                    elem.0,
+                    version,
+                    self.symbol(address).unwrap()
+                );
+            }
+            println!();
+        }
+
+        for (title, header, table) in [
+            ("URL Table", ("Scheme", "Host"), &self.url_table),
+            ("Email Table", ("Local Part", "Domain"), &self.email_table),
+        ] {
+            if !table.is_empty() {
+                println!("{:^51}", title);
+                println!("---------------------------------------------------");
+                println!("{:<15} | {:<15} | {:<15}", "Elem Id", header.0, header.1);
+                println!("---------------------------------------------------");
+                for (elem, (first, second)) in self.ordered(table.iter(), |(elem, _)| **elem) {
+                    println!(
+                        "{:<15} | {:<15} | {:<15}",
+                        elem.0,
+                        self.symbol(first).unwrap(),
+                        self.symbol(second).unwrap()
+                    );
+                }
+                println!();
+            }
+        }
+
+        if !self.bytes_table.is_empty() {
+            println!("{:^69}", "Bytes Table");
+            println!("---------------------------------------------------------------------");
+            println!(
+                "{:<15} | {:<15} | {:<15} | {:<15}",
+                "Elem Id", "Encoding", "Length", "Value"
+            );
+            println!("---------------------------------------------------------------------");
+            for (elem, value) in self.ordered(self.bytes_table.iter(), |(elem, _)| **elem) {
+                let (encoding, len) = self.bytes_encoding_table[elem];
+                println!(
+                    "{:<15} | {:<15} | {:<15} | {:<15}",
+                    elem.0,
+                    self.symbol(&encoding).unwrap(),
+                    len,
+                    self.symbol(value).unwrap()
+                );
+            }
+            println!();
+        }
+
+        if !self.declared_len_table.is_empty() {
+            println!("{:^33}", "Declared Length Table");
+            println!("---------------------------------");
+            println!("{:<15} | {:<15}", "Elem Id", "Length");
+            println!("---------------------------------");
+            for (elem, len) in self.ordered(self.declared_len_table.iter(), |(elem, _)| **elem) {
+                println!("{:<15} | {:<15}", elem.0, len);
+            }
+            println!();
+        }
+
+        if !self.absent_field_table.is_empty() {
+            println!("{:^33}", "Absent Field Table");
+            println!("---------------------------------");
+            println!("{:<15} | {:<15}", "Elem Id", "Field");
+            println!("---------------------------------");
+            for (elem, field) in self.ordered(self.absent_field_table.iter(), |fact| **fact) {
+                println!("{:<15} | {:<15}", elem.0, self.symbol(field).unwrap());
+            }
+            println!();
+        }
+
+        if !self.unit_table.is_empty() {
+            println!("{:^15}", "Unit Table");
+            println!("---------------");
+            println!("{:<15}", "Elem Id");
+            println!("---------------");
+            for elem in self.ordered(self.unit_table.iter(), |elem| **elem) {
+                println!("{:<15}", elem.0);
+            }
+            println!();
+        }
+
+        if !self.truncated_table.is_empty() {
+            println!("{:^33}", "Truncated Table");
+            println!("---------------------------------");
+            println!("{:<15} | {:<15}", "Relation", "Quota");
+            println!("---------------------------------");
+            for (relation, quota) in self.ordered(self.truncated_table.iter(), |(rel, _)| **rel) {
+                println!("{:<15} | {:<15}", self.symbol(relation).unwrap(), quota);
+            }
+            println!();
+        }
+
+        if !self.redacted_table.is_empty() {
+            println!("{:^15}", "Redacted Table");
+            println!("---------------");
+            println!("{:<15}", "Elem Id");
+            println!("---------------");
+            for elem in self.ordered(self.redacted_table.iter(), |elem| **elem) {
+                println!("{:<15}", elem.0);
+            }
+            println!();
+        }
+
+        if !self.sampled_table.is_empty() {
+            println!("{:^51}", "Sampled Table");
+            println!("---------------------------------------------------");
+            println!("{:<15} | {:<15} | {:<15}", "Elem Id", "Length", "Kept");
+            println!("---------------------------------------------------");
+            for (elem, (len, kept)) in self.ordered(self.sampled_table.iter(), |(elem, _)| **elem) {
+                println!("{:<15} | {:<15} | {:<15}", elem.0, len, kept);
+            }
+            println!();
+        }
+
+        if !self.elem_type_kind_table.is_empty() {
+            println!("{:^33}", "Elem Type Kind Table");
+            println!("---------------------------------");
+            println!("{:<15} | {:<15}", "Elem Type", "Kind");
+            println!("---------------------------------");
+            for (elem_type, kind) in self
+                .ordered(self.elem_type_kind_table.iter(), |(elem_type, _)| {
+                    **elem_type
+                })
+            {
+                println!("{:<15} | {:<15}", elem_type.0, kind.0);
+            }
+            println!();
+        }
+
+        if !self.same_string_table.is_empty() {
+            println!("{:^33}", "Same String Table");
+            println!("---------------------------------");
+            println!("{:<15} | {:<15}", "Elem Id", "Other Elem Id");
+            println!("---------------------------------");
+            for (elem, other) in self.ordered(self.same_string_table.iter(), |fact| **fact) {
+                println!("{:<15} | {:<15}", elem.0, other.0);
+            }
+            println!();
+        }
+
+        if !self.shape_table.is_empty() {
+            println!("{:^33}", "Shape Table");
+            println!("---------------------------------");
+            println!("{:<15} | {:<15}", "Elem Id", "Shape");
+            println!("---------------------------------");
+            for (elem, shape) in self.ordered(self.shape_table.iter(), |(elem, _)| **elem) {
+                println!("{:<15} | {:<15x}", elem.0, shape);
+            }
+            println!();
+        }
+
+        if !self.shape_field_table.is_empty() {
+            println!("{:^51}", "Shape Field Table");
+            println!("---------------------------------------------------");
+            println!("{:<15} | {:<15} | {:<15}", "Shape", "Position", "Field");
+            println!("---------------------------------------------------");
+            for ((shape, pos), field) in self.ordered(self.shape_field_table.iter(), |fact| *fact.0)
+            {
+                println!("{:<15x} | {:<15} | {:<15}", shape, pos, field.0);
+            }
+            println!();
+        }
+
+        if !self.parse_error_table.is_empty() {
+            println!("{:^51}", "Parse Error Table");
+            println!("---------------------------------------------------");
+            println!("{:<15} | {:<15} | {:<15}", "File", "Line", "Message");
+            println!("---------------------------------------------------");
+            for ((file, line), message) in
+                self.ordered(self.parse_error_table.iter(), |fact| *fact.0)
+            {
+                println!("{:<15} | {:<15} | {:<15}", file.0, line, message.0);
+            }
+            println!();
+        }
+
+        if !self.string_hash_table.is_empty() {
+            println!("{:^51}", "String Hash Table");
+            println!("---------------------------------------------------");
+            println!("{:<15} | {:<15} | {:<15}", "Elem Id", "Hash", "Length");
+            println!("---------------------------------------------------");
+            for (elem, (hash, len)) in self.ordered(self.string_hash_table.iter(), |fact| *fact.0) {
+                println!("{:<15} | {:<15x} | {:<15}", elem.0, hash, len);
+            }
+            println!();
+        }
+
+        if !self.root_meta_table.is_empty() {
+            println!("{:^51}", "Root Meta Table");
+            println!("---------------------------------------------------");
+            println!("{:<15} | {:<15} | {:<15}", "File", "Key", "Value");
+            println!("---------------------------------------------------");
+            for (file, key, value) in self.ordered(self.root_meta_table.iter(), |fact| **fact) {
+                println!("{:<15} | {:<15} | {:<15}", file.0, key.0, value.0);
+            }
+            println!();
+        }
+
+        if !self.depth_table.is_empty() {
+            println!("{:^33}", "Depth Table");
+            println!("---------------------------------");
+            println!("{:<15} | {:<15}", "Elem Id", "Depth");
+            println!("---------------------------------");
+            for (elem, depth) in self.ordered(self.depth_table.iter(), |(elem, _)| **elem) {
+                println!("{:<15} | {:<15}", elem.0, depth);
+            }
+            println!();
+        }
+
+        if !self.subtree_size_table.is_empty() {
+            println!("{:^33}", "Subtree Size Table");
+            println!("---------------------------------");
+            println!("{:<15} | {:<15}", "Elem Id", "Size");
+            println!("---------------------------------");
+            for (elem, size) in self.ordered(self.subtree_size_table.iter(), |(elem, _)| **elem) {
+                println!("{:<15} | {:<15}", elem.0, size);
+            }
+            println!();
+        }
+
+        if !self.char_table.is_empty() {
+            println!("{:^33}", "Char Table");
+            println!("---------------------------------");
+            println!("{:<15} | {:<15}", "Elem Id", "Code Point");
+            println!("---------------------------------");
+            for (elem, codepoint) in self.ordered(self.char_table.iter(), |(elem, _)| **elem) {
+                println!("{:<15} | {:<15}", elem.0, codepoint);
+            }
+            println!();
+        }
     }
 
     /// dump function that does not require a printing function for map keys;
@@ -267,6 +1006,412 @@ impl<K: Eq + Hash> BackendData<K> {
     {
         self.dump_with_fn(|_, key| key)
     }
+
+    /// Report the `top_n` most frequently referenced interned strings.
+    /// `map_key_fn` returns the interned string of a map key, if map keys are
+    /// interned strings.
+    pub fn string_report_with_fn(
+        &self,
+        top_n: usize,
+        map_key_fn: impl Fn(&K) -> Option<SymbolId>,
+    ) -> StringReport {
+        let mut counts: HashMap<SymbolId, usize> = HashMap::new();
+        let mut count = |sym: &SymbolId| *counts.entry(*sym).or_default() += 1;
+
+        self.root_elem_table.keys().for_each(&mut count);
+        for (file, format) in self.file_format_table.iter() {
+            count(file);
+            count(format);
+        }
+        for (struct_name, field) in self.key_field_table.iter() {
+            count(struct_name);
+            count(field);
+        }
+        self.type_table.values().for_each(&mut count);
+        self.string_table.values().for_each(&mut count);
+        for (_, key) in self.map_table.keys() {
+            if let Some(sym) = map_key_fn(key) {
+                count(&sym);
+            }
+        }
+        self.struct_type_table.values().for_each(&mut count);
+        for (_, field) in self.struct_table.keys() {
+            count(field);
+        }
+        for (type_name, variant_name) in self.variant_type_table.values() {
+            count(type_name);
+            count(variant_name);
+        }
+        self.schema_type_table.values().for_each(&mut count);
+        for (_, address) in self.ip_table.values() {
+            count(address);
+        }
+        for (first, second) in self.url_table.values().chain(self.email_table.values()) {
+            count(first);
+            count(second);
+        }
+        self.bytes_table.values().for_each(&mut count);
+        for (encoding, _) in self.bytes_encoding_table.values() {
+            count(encoding);
+        }
+        for (_, field) in self.absent_field_table.iter() {
+            count(field);
+        }
+        self.truncated_table.keys().for_each(&mut count);
+        for ((file, _), message) in self.parse_error_table.iter() {
+            count(file);
+            count(message);
+        }
+        for (file, key, value) in self.root_meta_table.iter() {
+            count(file);
+            count(key);
+            count(value);
+        }
+
+        let mut freqs: Vec<SymbolFrequency> = counts
+            .into_iter()
+            .filter_map(|(sym, count)| {
+                self.symbol(&sym).map(|symbol| SymbolFrequency {
+                    symbol: symbol.clone(),
+                    count,
+                    bytes: symbol.len() * count,
+                })
+            })
+            .collect();
+
+        let mut report = StringReport {
+            distinct: freqs.len(),
+            total_count: freqs.iter().map(|freq| freq.count).sum(),
+            total_bytes: freqs.iter().map(|freq| freq.bytes).sum(),
+            ..StringReport::default()
+        };
+
+        freqs.sort_by(|a, b| {
+            b.count
+                .cmp(&a.count)
+                .then(b.bytes.cmp(&a.bytes))
+                .then_with(|| a.symbol.cmp(&b.symbol))
+        });
+        freqs.truncate(top_n);
+        report.top = freqs;
+        report
+    }
+}
+
+impl BackendData<ElemId> {
+    /// The facts of the data, which materialize the data again when given to
+    /// a vector [Backend], e.g. with [from_facts][Self::from_facts]. Numbers
+    /// are `i64` facts and chars are string facts, since their types are not
+    /// stored, and byte arrays whose contents were hashed have no value
+    /// facts. Facts are ordered by relation, so that the values of elements
+    /// precede the entries referencing them.
+    pub fn facts(&self) -> Vec<Fact> {
+        let sym = |id: &SymbolId| self.symbol(id).cloned().unwrap_or_default();
+        let mut facts: Vec<Fact> = Vec::new();
+
+        for (elem_type, kind) in self.ordered(self.elem_type_kind_table.iter(), |fact| *fact.0) {
+            let elem_type = elem_type_of_name(&sym(elem_type));
+            let kind = ElemKind::from_name(&sym(kind));
+            if let (Some(elem_type), Some(kind)) = (elem_type, kind) {
+                facts.push(Fact::ElemTypeKind { elem_type, kind });
+            }
+        }
+        for ((shape, pos), field) in self.ordered(self.shape_field_table.iter(), |fact| *fact.0) {
+            facts.push(Fact::ShapeField {
+                shape: *shape,
+                pos: *pos,
+                field: sym(field),
+            });
+        }
+        for ((file, line), message) in self.ordered(self.parse_error_table.iter(), |fact| *fact.0) {
+            facts.push(Fact::ParseError {
+                file: sym(file),
+                line: *line,
+                message: sym(message),
+            });
+        }
+        for (struct_name, field) in self.ordered(self.key_field_table.iter(), |fact| *fact) {
+            facts.push(Fact::KeyField {
+                struct_name: sym(struct_name),
+                field: sym(field),
+            });
+        }
+        for (file, elem) in self.ordered(self.root_elem_table.iter(), |fact| *fact.1) {
+            facts.push(Fact::RootElem {
+                file: sym(file),
+                elem: *elem,
+            });
+        }
+        for (file, format) in self.ordered(self.file_format_table.iter(), |fact| *fact.0) {
+            facts.push(Fact::FileFormat {
+                file: sym(file),
+                format: sym(format),
+            });
+        }
+        for (file, key, value) in self.ordered(self.root_meta_table.iter(), |fact| *fact) {
+            facts.push(Fact::RootMeta {
+                file: sym(file),
+                key: sym(key),
+                value: sym(value),
+            });
+        }
+        for (elem, elem_type) in self.ordered(self.type_table.iter(), |fact| *fact.0) {
+            if let Some(elem_type) = elem_type_of_name(&sym(elem_type)) {
+                facts.push(Fact::Elem {
+                    elem: *elem,
+                    elem_type,
+                });
+            }
+        }
+
+        for (elem, value) in self.ordered(self.bool_table.iter(), |fact| *fact.0) {
+            facts.push(Fact::Bool {
+                elem: *elem,
+                value: *value,
+            });
+        }
+        for (elem, value) in self.ordered(self.numbers(), |fact| *fact.0) {
+            facts.push(Fact::I64 { elem: *elem, value });
+        }
+        for (elem, value) in self.ordered(self.string_table.iter(), |fact| *fact.0) {
+            facts.push(Fact::Str {
+                elem: *elem,
+                value: sym(value),
+            });
+        }
+        for (elem, value) in self.ordered(self.full_string_table.iter(), |fact| *fact.0) {
+            facts.push(Fact::FullStr {
+                elem: *elem,
+                value: value.clone(),
+            });
+        }
+        for (elem, value) in self.ordered(self.bytes_table.iter(), |fact| *fact.0) {
+            let value = self
+                .bytes_encoding_table
+                .get(elem)
+                .and_then(|(encoding, _)| BytesPolicy::from_name(&sym(encoding)))
+                .and_then(|policy| policy.decode(&sym(value)));
+            if let Some(value) = value {
+                facts.push(Fact::Bytes { elem: *elem, value });
+            }
+        }
+        for (elem, (epoch_micros, tz_offset)) in
+            self.ordered(self.datetime_table.iter(), |fact| *fact.0)
+        {
+            facts.push(Fact::Datetime {
+                elem: *elem,
+                epoch_micros: *epoch_micros,
+                tz_offset: *tz_offset,
+            });
+        }
+        for (elem, (hi, lo)) in self.ordered(self.uuid_table.iter(), |fact| *fact.0) {
+            facts.push(Fact::Uuid {
+                elem: *elem,
+                hi: *hi,
+                lo: *lo,
+            });
+        }
+        for (elem, (_, address)) in self.ordered(self.ip_table.iter(), |fact| *fact.0) {
+            if let Ok(address) = sym(address).parse::<IpAddr>() {
+                facts.push(Fact::Ip {
+                    elem: *elem,
+                    address,
+                });
+            }
+        }
+        for (elem, (scheme, host)) in self.ordered(self.url_table.iter(), |fact| *fact.0) {
+            facts.push(Fact::Url {
+                elem: *elem,
+                scheme: sym(scheme),
+                host: sym(host),
+            });
+        }
+        for (elem, (local, domain)) in self.ordered(self.email_table.iter(), |fact| *fact.0) {
+            facts.push(Fact::Email {
+                elem: *elem,
+                local: sym(local),
+                domain: sym(domain),
+            });
+        }
+
+        for (elem, struct_name) in self.ordered(self.struct_type_table.iter(), |fact| *fact.0) {
+            facts.push(Fact::StructType {
+                elem: *elem,
+                struct_name: sym(struct_name),
+            });
+        }
+        for (elem, (type_name, variant_name)) in
+            self.ordered(self.variant_type_table.iter(), |fact| *fact.0)
+        {
+            facts.push(Fact::VariantType {
+                elem: *elem,
+                type_name: sym(type_name),
+                variant_name: sym(variant_name),
+            });
+        }
+        for (elem, schema_ref) in self.ordered(self.schema_type_table.iter(), |fact| *fact.0) {
+            facts.push(Fact::SchemaType {
+                elem: *elem,
+                schema_ref: sym(schema_ref),
+            });
+        }
+
+        for ((elem, key), value) in self.ordered(self.map_table.iter(), |fact| *fact.0) {
+            facts.push(Fact::MapEntry {
+                elem: *elem,
+                key: *key,
+                value: *value,
+            });
+        }
+        for ((elem, key), value) in self.ordered(self.struct_table.iter(), |fact| *fact.0) {
+            facts.push(Fact::StructEntry {
+                elem: *elem,
+                key: sym(key),
+                value: *value,
+            });
+        }
+        for (elem, key) in self.ordered(self.key_table.iter(), |fact| **fact) {
+            facts.push(Fact::Key {
+                elem: *elem,
+                key: *key,
+            });
+        }
+        for ((elem, pos), value) in self.ordered(self.seq_table.iter(), |fact| *fact.0) {
+            facts.push(Fact::SeqEntry {
+                elem: *elem,
+                pos: *pos,
+                value: *value,
+            });
+        }
+        for ((elem, pos), value) in self.ordered(self.tuple_table.iter(), |fact| *fact.0) {
+            facts.push(Fact::TupleEntry {
+                elem: *elem,
+                pos: *pos,
+                value: *value,
+            });
+        }
+
+        for (elem, len) in self.ordered(self.declared_len_table.iter(), |fact| *fact.0) {
+            facts.push(Fact::DeclaredLen {
+                elem: *elem,
+                len: *len,
+            });
+        }
+        for (elem, field) in self.ordered(self.absent_field_table.iter(), |fact| *fact) {
+            facts.push(Fact::AbsentField {
+                elem: *elem,
+                field: sym(field),
+            });
+        }
+        for elem in self.ordered(self.unit_table.iter(), |elem| **elem) {
+            facts.push(Fact::Unit { elem: *elem });
+        }
+        for elem in self.ordered(self.redacted_table.iter(), |elem| **elem) {
+            facts.push(Fact::Redacted { elem: *elem });
+        }
+        for (elem, (len, kept)) in self.ordered(self.sampled_table.iter(), |fact| *fact.0) {
+            facts.push(Fact::Sampled {
+                elem: *elem,
+                len: *len,
+                kept: *kept,
+            });
+        }
+        for (elem, other) in self.ordered(self.same_string_table.iter(), |fact| **fact) {
+            facts.push(Fact::SameString {
+                elem: *elem,
+                other: *other,
+            });
+        }
+        for (elem, shape) in self.ordered(self.shape_table.iter(), |fact| *fact.0) {
+            facts.push(Fact::Shape {
+                elem: *elem,
+                shape: *shape,
+            });
+        }
+        for (elem, (hash, len)) in self.ordered(self.string_hash_table.iter(), |fact| *fact.0) {
+            facts.push(Fact::StringHash {
+                elem: *elem,
+                hash: *hash,
+                len: *len,
+            });
+        }
+        for (elem, depth) in self.ordered(self.depth_table.iter(), |fact| *fact.0) {
+            facts.push(Fact::Depth {
+                elem: *elem,
+                depth: *depth,
+            });
+        }
+        for (elem, size) in self.ordered(self.subtree_size_table.iter(), |fact| *fact.0) {
+            facts.push(Fact::SubtreeSize {
+                elem: *elem,
+                size: *size,
+            });
+        }
+        for (elem, codepoint) in self.ordered(self.char_table.iter(), |fact| *fact.0) {
+            facts.push(Fact::Codepoint {
+                elem: *elem,
+                codepoint: *codepoint,
+            });
+        }
+        for (relation, quota) in self.ordered(self.truncated_table.iter(), |fact| *fact.0) {
+            facts.push(Fact::Truncated {
+                relation: sym(relation),
+                quota: *quota,
+            });
+        }
+
+        facts
+    }
+
+    /// Materialize `facts` in the data of a vector [Backend] that encodes
+    /// byte arrays with the default [BytesPolicy].
+    pub fn from_facts<'a>(facts: impl IntoIterator<Item = &'a Fact>) -> Result<Self> {
+        let mut backend = Backend::default().with_bytes_policy(BytesPolicy::default());
+        for fact in facts {
+            fact.add_to(&mut backend)?;
+        }
+
+        Result::Ok(backend.get_data())
+    }
+}
+
+impl From<&BackendData<ElemId>> for Vec<Fact> {
+    fn from(data: &BackendData<ElemId>) -> Self {
+        data.facts()
+    }
+}
+
+impl TryFrom<&[Fact]> for BackendData<ElemId> {
+    type Error = DatalogExtractionError;
+
+    fn try_from(facts: &[Fact]) -> Result<Self> {
+        Self::from_facts(facts)
+    }
+}
+
+/// The element type whose facts have type name `name`. Numbers are `i64`s
+/// and chars are strings, and newtype structs and variants are tuple structs
+/// and variants, since they share type names.
+fn elem_type_of_name(name: &str) -> Option<ElemType> {
+    let elem_type = match name {
+        BOOL_NAME => ElemType::Bool,
+        NUMBER_NAME => ElemType::I64,
+        STR_NAME => ElemType::Str,
+        BYTES_NAME => ElemType::Bytes,
+        MAP_NAME => ElemType::Map,
+        SEQ_NAME => ElemType::Seq,
+        STRUCT_NAME => ElemType::Struct,
+        STRUCT_VARIANT_NAME => ElemType::StructVariant,
+        TUPLE_NAME => ElemType::Tuple,
+        TUPLE_STRUCT_NAME => ElemType::TupleStruct,
+        TUPLE_VARIANT_NAME => ElemType::TupleVariant,
+        UNIT_NAME => ElemType::Unit,
+        UNIT_STRUCT_NAME => ElemType::UnitStruct,
+        UNIT_VARIANT_NAME => ElemType::UnitVariant,
+        _ => return None,
+    };
+
+    Some(elem_type)
 }
 
 /// DatalogExtractorBackend impl that stores facts in vectors.
@@ -280,6 +1425,8 @@ impl<K: Eq + Hash> BackendData<K> {
 struct AbstractBackend<K: Display + Eq + Hash> {
     pub(crate) cur_symbol_id: SymbolId,
     pub(crate) data: BackendData<K>,
+    bytes_policy: Option<BytesPolicy>,
+    number_pool: Option<RangeInclusive<i64>>,
 }
 
 impl<K: Display + Eq + Hash> Default for AbstractBackend<K> {
@@ -287,59 +1434,59 @@ impl<K: Display + Eq + Hash> Default for AbstractBackend<K> {
         let mut backend = Self {
             cur_symbol_id: SymbolId(1),
             data: Default::default(),
+            bytes_policy: None,
+            number_pool: None,
         };
 
-        backend.intern_string(BOOL_NAME);
-        backend.intern_string(NUMBER_NAME);
-        backend.intern_string(STR_NAME);
-        backend.intern_string(MAP_NAME);
-        backend.intern_string(SEQ_NAME);
-        backend.intern_string(STRUCT_NAME);
-        backend.intern_string(STRUCT_VARIANT_NAME);
-        backend.intern_string(TUPLE_NAME);
-        backend.intern_string(TUPLE_STRUCT_NAME);
-        backend.intern_string(TUPLE_VARIANT_NAME);
-        backend.intern_string(UNIT_NAME);
-        backend.intern_string(UNIT_STRUCT_NAME);
-        backend.intern_string(UNIT_VARIANT_NAME);
+        for name in ELEM_TYPE_NAMES {
+            backend.intern_string(SymbolNamespace::ElemType, name);
+        }
 
         backend
     }
 }
 
 impl<K: Display + Eq + Hash> AbstractBackend<K> {
-    fn intern_string(&mut self, s: &str) -> SymbolId {
-        match self.data.symbol_table.get_by_left(s) {
+    fn intern_string(&mut self, namespace: SymbolNamespace, s: &str) -> SymbolId {
+        let table = match self.data.namespace_symbol_tables.get_mut(&namespace) {
+            Some(table) => table,
+            None => &mut self.data.symbol_table,
+        };
+
+        match table.get_by_left(s) {
             Some(id) => *id,
             None => {
                 let SymbolId(id) = self.cur_symbol_id;
-                self.data
-                    .symbol_table
-                    .insert(s.to_string(), self.cur_symbol_id);
+                table.insert(s.to_string(), self.cur_symbol_id);
                 self.cur_symbol_id.0 += 1;
                 SymbolId(id)
             }
         }
     }
 
-    fn get_data(self) -> BackendData<K> {
-        self.data
-    }
-
-    fn process_prev_value<T>(elem: ElemId, prev: Option<T>) -> Result<()> {
-        prev.map_or(Result::Ok(()), |_| {
-            Result::Err(DatalogExtractionError::NonuniqueIdentifier(elem))
-        })
-    }
-
-    fn add_root_elem(&mut self, file: &str, elem: ElemId) -> Result<()> {
-        let sym = self.intern_string(file);
+    fn separate_namespaces(&mut self) {
+        for namespace in SymbolNamespace::SEPARATE {
+            self.data
+                .namespace_symbol_tables
+                .entry(namespace)
+                .or_default();
+        }
 
-        Self::process_prev_value(elem, self.data.root_elem_table.insert(sym, elem))
+        // element type names keep their identifiers
+        for name in ELEM_TYPE_NAMES {
+            if let Some((name, id)) = self.data.symbol_table.remove_by_left(name) {
+                self.data
+                    .namespace_symbol_tables
+                    .get_mut(&SymbolNamespace::ElemType)
+                    .unwrap()
+                    .insert(name, id);
+            }
+        }
     }
 
-    fn add_elem(&mut self, elem: ElemId, elem_type: ElemType) -> Result<()> {
-        let type_name: &str = match elem_type {
+    /// Name of element type `elem_type` in the type table.
+    fn elem_type_name(&self, elem_type: &ElemType) -> Result<&'static str> {
+        let type_name = match elem_type {
             ElemType::Bool => BOOL_NAME,
 
             ElemType::I8
@@ -359,6 +1506,8 @@ impl<K: Display + Eq + Hash> AbstractBackend<K> {
                 ));
             }
 
+            ElemType::Bytes if self.bytes_policy.is_some() => BYTES_NAME,
+
             ElemType::Bytes => {
                 return Result::Err(DatalogExtractionError::UnextractableData(
                     "byte array".to_string(),
@@ -379,35 +1528,156 @@ impl<K: Display + Eq + Hash> AbstractBackend<K> {
             ElemType::UnitVariant => UNIT_VARIANT_NAME,
         };
 
-        let elem_type_sym = self.intern_string(type_name);
+        Result::Ok(type_name)
+    }
+
+    /// Backend that stores facts in `data`, numbering new interned strings
+    /// after the strings of `data`.
+    fn from_data(data: BackendData<K>) -> Self {
+        let next_symbol_id = data.symbols().map(|(_, id)| id.0 + 1).max();
+        Self {
+            cur_symbol_id: SymbolId(next_symbol_id.unwrap_or(1)),
+            data,
+            bytes_policy: None,
+            number_pool: None,
+        }
+    }
+
+    fn get_data(self) -> BackendData<K> {
+        self.data
+    }
+
+    fn load_symbol(&mut self, symbol: String, id: SymbolId) {
+        if id.0 >= self.cur_symbol_id.0 {
+            self.cur_symbol_id = SymbolId(id.0 + 1);
+        }
+
+        // element type names are already interned
+        let is_interned = self
+            .data
+            .namespace_symbol_tables
+            .values()
+            .any(|table| table.contains_right(&id));
+
+        if !is_interned {
+            self.data.symbol_table.insert(symbol, id);
+        }
+    }
+
+    fn process_prev_value<T>(elem: ElemId, prev: Option<T>) -> Result<()> {
+        prev.map_or(Result::Ok(()), |_| {
+            Result::Err(DatalogExtractionError::NonuniqueIdentifier(elem))
+        })
+    }
+
+    fn add_root_elem(&mut self, file: &str, elem: ElemId) -> Result<()> {
+        let sym = self.intern_string(SymbolNamespace::Value, file);
+
+        Self::process_prev_value(elem, self.data.root_elem_table.insert(sym, elem))
+    }
+
+    fn add_file_format(&mut self, file: &str, format: &str) -> Result<()> {
+        let file_sym = self.intern_string(SymbolNamespace::Value, file);
+        let format_sym = self.intern_string(SymbolNamespace::Value, format);
+        self.data.file_format_table.insert(file_sym, format_sym);
+        Result::Ok(())
+    }
+
+    fn add_key_field(&mut self, struct_name: &str, field: &str) -> Result<()> {
+        let struct_sym = self.intern_string(SymbolNamespace::TypeName, struct_name);
+        let field_sym = self.intern_string(SymbolNamespace::Field, field);
+        self.data.key_field_table.insert((struct_sym, field_sym));
+        Result::Ok(())
+    }
+
+    fn add_elem(&mut self, elem: ElemId, elem_type: ElemType) -> Result<()> {
+        let type_name = self.elem_type_name(&elem_type)?;
+        let elem_type_sym = self.intern_string(SymbolNamespace::ElemType, type_name);
         Self::process_prev_value(elem, self.data.type_table.insert(elem, elem_type_sym))
     }
 
+    fn add_elem_type_kind(&mut self, elem_type: ElemType, kind: ElemKind) -> Result<()> {
+        // element types that the backend cannot extract have no kind
+        let Result::Ok(type_name) = self.elem_type_name(&elem_type) else {
+            return Result::Ok(());
+        };
+
+        let elem_type_sym = self.intern_string(SymbolNamespace::ElemType, type_name);
+        let kind_sym = self.intern_string(SymbolNamespace::ElemType, kind.name());
+        self.data
+            .elem_type_kind_table
+            .insert(elem_type_sym, kind_sym);
+        Result::Ok(())
+    }
+
     fn add_bool(&mut self, elem: ElemId, value: bool) -> Result<()> {
         Self::process_prev_value(elem, self.data.bool_table.insert(elem, value))
     }
 
+    /// Intern number `value` in the number pool, returning its identifier.
+    fn intern_number(&mut self, value: i64) -> NumberId {
+        match self.data.number_pool.get_by_left(&value) {
+            Some(id) => *id,
+            None => {
+                let id = NumberId(self.data.number_pool.len() + 1);
+                self.data.number_pool.insert(value, id);
+                id
+            }
+        }
+    }
+
     fn add_i64(&mut self, elem: ElemId, value: i64) -> Result<()> {
-        Self::process_prev_value(elem, self.data.number_table.insert(elem, value))
+        if self
+            .number_pool
+            .as_ref()
+            .is_some_and(|range| range.contains(&value))
+        {
+            let id = self.intern_number(value);
+            Self::process_prev_value(elem, self.data.pooled_number_table.insert(elem, id))
+        } else {
+            Self::process_prev_value(elem, self.data.number_table.insert(elem, value))
+        }
     }
 
     fn add_u64(&mut self, elem: ElemId, value: u64) -> Result<()> {
         match i64::try_from(value) {
-            Ok(signed_value) => {
-                Self::process_prev_value(elem, self.data.number_table.insert(elem, signed_value))
-            }
-
+            Ok(signed_value) => self.add_i64(elem, signed_value),
             Err(_) => Result::Err(DatalogExtractionError::IntegerCastOverflow(value)),
         }
     }
 
     fn add_str(&mut self, elem: ElemId, value: &str) -> Result<()> {
-        let value_sym = self.intern_string(value);
+        let value_sym = self.intern_string(SymbolNamespace::Value, value);
         Self::process_prev_value(elem, self.data.string_table.insert(elem, value_sym))
     }
 
+    fn add_bytes(&mut self, elem: ElemId, value: &[u8]) -> Result<()> {
+        let Some(policy) = self.bytes_policy else {
+            return Result::Err(DatalogExtractionError::UnextractableData(
+                "byte array".to_string(),
+            ));
+        };
+
+        let value_sym = self.intern_string(SymbolNamespace::Value, &policy.encode(value));
+        let encoding_sym = self.intern_string(SymbolNamespace::Value, policy.name());
+        Self::process_prev_value(elem, self.data.bytes_table.insert(elem, value_sym))?;
+        Self::process_prev_value(
+            elem,
+            self.data
+                .bytes_encoding_table
+                .insert(elem, (encoding_sym, value.len())),
+        )
+    }
+
+    fn add_full_str(&mut self, elem: ElemId, value: &str) -> Result<()> {
+        Self::process_prev_value(
+            elem,
+            self.data.full_string_table.insert(elem, value.to_string()),
+        )
+    }
+
     fn add_struct_type(&mut self, elem: ElemId, struct_name: &str) -> Result<()> {
-        let struct_name_sym = self.intern_string(struct_name);
+        let struct_name_sym = self.intern_string(SymbolNamespace::TypeName, struct_name);
         Self::process_prev_value(
             elem,
             self.data.struct_type_table.insert(elem, struct_name_sym),
@@ -415,10 +1685,15 @@ impl<K: Display + Eq + Hash> AbstractBackend<K> {
     }
 
     fn add_struct_entry(&mut self, elem: ElemId, key: &str, value: ElemId) -> Result<()> {
-        let key_sym = self.intern_string(key);
+        let key_sym = self.intern_string(SymbolNamespace::Field, key);
         Self::process_prev_value(elem, self.data.struct_table.insert((elem, key_sym), value))
     }
 
+    fn add_key(&mut self, elem: ElemId, key: ElemId) -> Result<()> {
+        self.data.key_table.insert((elem, key));
+        Result::Ok(())
+    }
+
     fn add_seq_entry(&mut self, elem: ElemId, pos: usize, value: ElemId) -> Result<()> {
         self.data
             .seq_table
@@ -434,8 +1709,8 @@ impl<K: Display + Eq + Hash> AbstractBackend<K> {
         type_name: &str,
         variant_name: &str,
     ) -> Result<()> {
-        let type_name_sym = self.intern_string(type_name);
-        let variant_name_sym = self.intern_string(variant_name);
+        let type_name_sym = self.intern_string(SymbolNamespace::TypeName, type_name);
+        let variant_name_sym = self.intern_string(SymbolNamespace::VariantName, variant_name);
         Self::process_prev_value(
             elem,
             self.data
@@ -447,6 +1722,131 @@ impl<K: Display + Eq + Hash> AbstractBackend<K> {
     fn add_tuple_entry(&mut self, elem: ElemId, pos: usize, value: ElemId) -> Result<()> {
         Self::process_prev_value(elem, self.data.tuple_table.insert((elem, pos), value))
     }
+
+    fn add_schema_type(&mut self, elem: ElemId, schema_ref: &str) -> Result<()> {
+        let schema_sym = self.intern_string(SymbolNamespace::Value, schema_ref);
+        Self::process_prev_value(elem, self.data.schema_type_table.insert(elem, schema_sym))
+    }
+
+    fn add_datetime(&mut self, elem: ElemId, epoch_micros: i64, tz_offset: i32) -> Result<()> {
+        Self::process_prev_value(
+            elem,
+            self.data
+                .datetime_table
+                .insert(elem, (epoch_micros, tz_offset)),
+        )
+    }
+
+    fn add_uuid(&mut self, elem: ElemId, hi: u64, lo: u64) -> Result<()> {
+        Self::process_prev_value(elem, self.data.uuid_table.insert(elem, (hi, lo)))
+    }
+
+    fn add_ip(&mut self, elem: ElemId, address: IpAddr) -> Result<()> {
+        let version = if address.is_ipv4() { 4 } else { 6 };
+        let address_sym = self.intern_string(SymbolNamespace::Value, &address.to_string());
+        Self::process_prev_value(
+            elem,
+            self.data.ip_table.insert(elem, (version, address_sym)),
+        )
+    }
+
+    fn add_url(&mut self, elem: ElemId, scheme: &str, host: &str) -> Result<()> {
+        let scheme_sym = self.intern_string(SymbolNamespace::Value, scheme);
+        let host_sym = self.intern_string(SymbolNamespace::Value, host);
+        Self::process_prev_value(
+            elem,
+            self.data.url_table.insert(elem, (scheme_sym, host_sym)),
+        )
+    }
+
+    fn add_email(&mut self, elem: ElemId, local: &str, domain: &str) -> Result<()> {
+        let local_sym = self.intern_string(SymbolNamespace::Value, local);
+        let domain_sym = self.intern_string(SymbolNamespace::Value, domain);
+        Self::process_prev_value(
+            elem,
+            self.data.email_table.insert(elem, (local_sym, domain_sym)),
+        )
+    }
+
+    fn add_declared_len(&mut self, elem: ElemId, len: usize) -> Result<()> {
+        Self::process_prev_value(elem, self.data.declared_len_table.insert(elem, len))
+    }
+
+    fn add_absent_field(&mut self, elem: ElemId, field: &str) -> Result<()> {
+        let field_sym = self.intern_string(SymbolNamespace::Field, field);
+        self.data.absent_field_table.insert((elem, field_sym));
+        Result::Ok(())
+    }
+
+    fn add_unit(&mut self, elem: ElemId) -> Result<()> {
+        self.data.unit_table.insert(elem);
+        Result::Ok(())
+    }
+
+    fn add_truncated(&mut self, relation: &str, quota: usize) -> Result<()> {
+        let relation_sym = self.intern_string(SymbolNamespace::Value, relation);
+        self.data.truncated_table.insert(relation_sym, quota);
+        Result::Ok(())
+    }
+
+    fn add_redacted(&mut self, elem: ElemId) -> Result<()> {
+        self.data.redacted_table.insert(elem);
+        Result::Ok(())
+    }
+
+    fn add_sampled(&mut self, elem: ElemId, len: usize, kept: usize) -> Result<()> {
+        Self::process_prev_value(elem, self.data.sampled_table.insert(elem, (len, kept)))
+    }
+
+    fn add_same_string(&mut self, elem: ElemId, other: ElemId) -> Result<()> {
+        self.data.same_string_table.insert((elem, other));
+        Result::Ok(())
+    }
+
+    fn add_shape(&mut self, elem: ElemId, shape: u64) -> Result<()> {
+        Self::process_prev_value(elem, self.data.shape_table.insert(elem, shape))
+    }
+
+    fn add_shape_field(&mut self, shape: u64, pos: usize, field: &str) -> Result<()> {
+        let field_sym = self.intern_string(SymbolNamespace::Field, field);
+        self.data.shape_field_table.insert((shape, pos), field_sym);
+        Result::Ok(())
+    }
+
+    fn add_parse_error(&mut self, file: &str, line: usize, message: &str) -> Result<()> {
+        let file_sym = self.intern_string(SymbolNamespace::Value, file);
+        let message_sym = self.intern_string(SymbolNamespace::Value, message);
+        self.data
+            .parse_error_table
+            .insert((file_sym, line), message_sym);
+        Result::Ok(())
+    }
+
+    fn add_string_hash(&mut self, elem: ElemId, hash: u64, len: usize) -> Result<()> {
+        Self::process_prev_value(elem, self.data.string_hash_table.insert(elem, (hash, len)))
+    }
+
+    fn add_root_meta(&mut self, file: &str, key: &str, value: &str) -> Result<()> {
+        let file_sym = self.intern_string(SymbolNamespace::Value, file);
+        let key_sym = self.intern_string(SymbolNamespace::Value, key);
+        let value_sym = self.intern_string(SymbolNamespace::Value, value);
+        self.data
+            .root_meta_table
+            .insert((file_sym, key_sym, value_sym));
+        Result::Ok(())
+    }
+
+    fn add_depth(&mut self, elem: ElemId, depth: usize) -> Result<()> {
+        Self::process_prev_value(elem, self.data.depth_table.insert(elem, depth))
+    }
+
+    fn add_subtree_size(&mut self, elem: ElemId, size: usize) -> Result<()> {
+        Self::process_prev_value(elem, self.data.subtree_size_table.insert(elem, size))
+    }
+
+    fn add_codepoint(&mut self, elem: ElemId, codepoint: u32) -> Result<()> {
+        Self::process_prev_value(elem, self.data.char_table.insert(elem, codepoint))
+    }
 }
 
 /// DatalogExtractorBackend impl that stores facts as vectors of tuples.
@@ -462,26 +1862,114 @@ impl Backend {
         self.parent.get_data()
     }
 
+    /// Intern strings of each [namespace][SymbolNamespace] separately, so
+    /// that equal strings in different namespaces have distinct identifiers.
+    /// Symbols loaded with [load_symbol][Self::load_symbol] are placed in the
+    /// [Value][SymbolNamespace::Value] namespace.
+    pub fn with_separate_namespaces(mut self) -> Self {
+        self.parent.separate_namespaces();
+        self
+    }
+
+    /// Dump facts in order of their identifiers, so that dumps of the same
+    /// data are identical. Disabling this saves sorting facts before they are
+    /// dumped. Enabled by default.
+    pub fn with_deterministic_order(mut self, enable: bool) -> Self {
+        self.parent.data.deterministic_order = enable;
+        self
+    }
+
+    /// Extract byte arrays as text encoded with `policy`, in the `bytes`
+    /// table, with their encoding and original length in the
+    /// `bytesEncoding` table. Without a policy, byte arrays cannot be
+    /// extracted.
+    pub fn with_bytes_policy(mut self, policy: BytesPolicy) -> Self {
+        self.parent.bytes_policy = Some(policy);
+        self
+    }
+
+    /// Intern numbers in `range` in a number pool, like strings are interned
+    /// in the symbol table, so that number elements with the same value
+    /// share a [NumberId]. Pooled numbers are stored in the
+    /// [number_pool][BackendData::number_pool] and
+    /// [pooled_number_table][BackendData::pooled_number_table] tables instead
+    /// of the number table, which suits inputs dominated by small repeated
+    /// integers such as flags and enum ordinals. Use
+    /// [numbers][BackendData::numbers] to iterate over all numbers.
+    pub fn with_number_pool(mut self, range: RangeInclusive<i64>) -> Self {
+        self.parent.number_pool = Some(range);
+        self
+    }
+
+    /// Add an existing interned string `symbol` with identifier `id` to the
+    /// symbol table. Strings interned afterwards are given fresh identifiers
+    /// greater than `id`.
+    pub fn load_symbol(&mut self, symbol: String, id: SymbolId) {
+        self.parent.load_symbol(symbol, id)
+    }
+
+    /// Report the `top_n` most frequently referenced interned strings.
+    pub fn string_report(&self, top_n: usize) -> StringReport {
+        self.parent.data.string_report_with_fn(top_n, |_| None)
+    }
+
     /// Print generate fact tables to standard output.
     pub fn dump(self) {
         self.parent.get_data().dump()
     }
 }
 
+/// Backend that stores facts in `data`, e.g. data read from a database, so
+/// that more facts can be added to it.
+impl From<BackendData<ElemId>> for Backend {
+    fn from(data: BackendData<ElemId>) -> Self {
+        Backend {
+            parent: AbstractBackend::from_data(data),
+        }
+    }
+}
+
 impl DatalogExtractorBackend for Backend {
     delegate! {
         to self.parent {
             fn add_root_elem(&mut self, file: &str, elem: ElemId) -> Result<()>;
+            fn add_file_format(&mut self, file: &str, format: &str) -> Result<()>;
+            fn add_key_field(&mut self, struct_name: &str, field: &str) -> Result<()>;
             fn add_elem(&mut self, elem: ElemId, elem_type: ElemType) -> Result<()>;
             fn add_bool(&mut self, elem: ElemId, value: bool) -> Result<()>;
             fn add_i64(&mut self, elem: ElemId, value: i64) -> Result<()>;
             fn add_u64(&mut self, elem: ElemId, value: u64) -> Result<()>;
             fn add_str(&mut self, elem: ElemId, value: &str) -> Result<()>;
+            fn add_bytes(&mut self, elem: ElemId, value: &[u8]) -> Result<()>;
+            fn add_full_str(&mut self, elem: ElemId, value: &str) -> Result<()>;
             fn add_struct_type(&mut self, elem: ElemId, struct_name: &str) -> Result<()>;
             fn add_struct_entry(&mut self, elem: ElemId, key: &str, value: ElemId) -> Result<()>;
+            fn add_key(&mut self, elem: ElemId, key: ElemId) -> Result<()>;
             fn add_seq_entry(&mut self, elem: ElemId, pos: usize, value: ElemId) -> Result<()>;
             fn add_variant_type(&mut self, elem: ElemId, type_name: &str, variant_name: &str) -> Result<()>;
             fn add_tuple_entry(&mut self, elem: ElemId, pos: usize, value: ElemId) -> Result<()>;
+            fn add_schema_type(&mut self, elem: ElemId, schema_ref: &str) -> Result<()>;
+            fn add_datetime(&mut self, elem: ElemId, epoch_micros: i64, tz_offset: i32) -> Result<()>;
+            fn add_uuid(&mut self, elem: ElemId, hi: u64, lo: u64) -> Result<()>;
+            fn add_ip(&mut self, elem: ElemId, address: IpAddr) -> Result<()>;
+            fn add_url(&mut self, elem: ElemId, scheme: &str, host: &str) -> Result<()>;
+            fn add_email(&mut self, elem: ElemId, local: &str, domain: &str) -> Result<()>;
+            fn add_declared_len(&mut self, elem: ElemId, len: usize) -> Result<()>;
+            fn add_absent_field(&mut self, elem: ElemId, field: &str) -> Result<()>;
+            fn add_unit(&mut self, elem: ElemId) -> Result<()>;
+            fn add_truncated(&mut self, relation: &str, quota: usize) -> Result<()>;
+            fn add_redacted(&mut self, elem: ElemId) -> Result<()>;
+            fn add_sampled(&mut self, elem: ElemId, len: usize, kept: usize) -> Result<()>;
+            fn add_elem_type_kind(&mut self, elem_type: ElemType, kind: ElemKind) -> Result<()>;
+            fn add_same_string(&mut self, elem: ElemId, other: ElemId) -> Result<()>;
+            fn add_shape(&mut self, elem: ElemId, shape: u64) -> Result<()>;
+            fn add_shape_field(&mut self, shape: u64, pos: usize, field: &str) -> Result<()>;
+            fn add_parse_error(&mut self, file: &str, line: usize, message: &str) -> Result<()>;
+            fn add_string_hash(&mut self, elem: ElemId, hash: u64, len: usize) -> Result<()>;
+            fn add_root_meta(&mut self, file: &str, key: &str, value: &str) -> Result<()>;
+            fn add_depth(&mut self, elem: ElemId, depth: usize) -> Result<()>;
+            fn add_subtree_size(&mut self, elem: ElemId, size: usize) -> Result<()>;
+            fn add_codepoint(&mut self, elem: ElemId, codepoint: u32) -> Result<()>;
         }
     }
 
@@ -506,11 +1994,74 @@ impl StringKeyBackend {
         self.parent.get_data()
     }
 
+    /// Intern strings of each [namespace][SymbolNamespace] separately, so
+    /// that equal strings in different namespaces have distinct identifiers.
+    /// Symbols loaded with [load_symbol][Self::load_symbol] are placed in the
+    /// [Value][SymbolNamespace::Value] namespace.
+    pub fn with_separate_namespaces(mut self) -> Self {
+        self.parent.separate_namespaces();
+        self
+    }
+
+    /// Dump facts in order of their identifiers, so that dumps of the same
+    /// data are identical. Disabling this saves sorting facts before they are
+    /// dumped. Enabled by default.
+    pub fn with_deterministic_order(mut self, enable: bool) -> Self {
+        self.parent.data.deterministic_order = enable;
+        self
+    }
+
+    /// Extract byte arrays as text encoded with `policy`, in the `bytes`
+    /// table, with their encoding and original length in the
+    /// `bytesEncoding` table. Without a policy, byte arrays cannot be
+    /// extracted.
+    pub fn with_bytes_policy(mut self, policy: BytesPolicy) -> Self {
+        self.parent.bytes_policy = Some(policy);
+        self
+    }
+
+    /// Intern numbers in `range` in a number pool, like strings are interned
+    /// in the symbol table, so that number elements with the same value
+    /// share a [NumberId]. Pooled numbers are stored in the
+    /// [number_pool][BackendData::number_pool] and
+    /// [pooled_number_table][BackendData::pooled_number_table] tables instead
+    /// of the number table, which suits inputs dominated by small repeated
+    /// integers such as flags and enum ordinals. Use
+    /// [numbers][BackendData::numbers] to iterate over all numbers.
+    pub fn with_number_pool(mut self, range: RangeInclusive<i64>) -> Self {
+        self.parent.number_pool = Some(range);
+        self
+    }
+
+    /// Add an existing interned string `symbol` with identifier `id` to the
+    /// symbol table. Strings interned afterwards are given fresh identifiers
+    /// greater than `id`.
+    pub fn load_symbol(&mut self, symbol: String, id: SymbolId) {
+        self.parent.load_symbol(symbol, id)
+    }
+
+    /// Report the `top_n` most frequently referenced interned strings,
+    /// including map keys.
+    pub fn string_report(&self, top_n: usize) -> StringReport {
+        self.parent
+            .data
+            .string_report_with_fn(top_n, |key| Some(*key))
+    }
+
     /// Print generate fact tables to standard output.
     pub fn dump(self) {
         self.parent
             .get_data()
-            .dump_with_fn(|data, key| data.symbol_table.get_by_right(key).unwrap())
+            .dump_with_fn(|data, key| data.symbol(key).unwrap())
+    }
+}
+
+/// Backend that stores facts in `data`; see [Backend::from].
+impl From<BackendData<SymbolId>> for StringKeyBackend {
+    fn from(data: BackendData<SymbolId>) -> Self {
+        StringKeyBackend {
+            parent: AbstractBackend::from_data(data),
+        }
     }
 }
 
@@ -518,16 +2069,43 @@ impl DatalogExtractorBackend for StringKeyBackend {
     delegate! {
         to self.parent {
             fn add_root_elem(&mut self, file: &str, elem: ElemId) -> Result<()>;
+            fn add_file_format(&mut self, file: &str, format: &str) -> Result<()>;
+            fn add_key_field(&mut self, struct_name: &str, field: &str) -> Result<()>;
             fn add_elem(&mut self, elem: ElemId, elem_type: ElemType) -> Result<()>;
             fn add_bool(&mut self, elem: ElemId, value: bool) -> Result<()>;
             fn add_i64(&mut self, elem: ElemId, value: i64) -> Result<()>;
             fn add_u64(&mut self, elem: ElemId, value: u64) -> Result<()>;
             fn add_str(&mut self, elem: ElemId, value: &str) -> Result<()>;
+            fn add_bytes(&mut self, elem: ElemId, value: &[u8]) -> Result<()>;
+            fn add_full_str(&mut self, elem: ElemId, value: &str) -> Result<()>;
             fn add_struct_type(&mut self, elem: ElemId, struct_name: &str) -> Result<()>;
             fn add_struct_entry(&mut self, elem: ElemId, key: &str, value: ElemId) -> Result<()>;
+            fn add_key(&mut self, elem: ElemId, key: ElemId) -> Result<()>;
             fn add_seq_entry(&mut self, elem: ElemId, pos: usize, value: ElemId) -> Result<()>;
             fn add_variant_type(&mut self, elem: ElemId, type_name: &str, variant_name: &str) -> Result<()>;
             fn add_tuple_entry(&mut self, elem: ElemId, pos: usize, value: ElemId) -> Result<()>;
+            fn add_schema_type(&mut self, elem: ElemId, schema_ref: &str) -> Result<()>;
+            fn add_datetime(&mut self, elem: ElemId, epoch_micros: i64, tz_offset: i32) -> Result<()>;
+            fn add_uuid(&mut self, elem: ElemId, hi: u64, lo: u64) -> Result<()>;
+            fn add_ip(&mut self, elem: ElemId, address: IpAddr) -> Result<()>;
+            fn add_url(&mut self, elem: ElemId, scheme: &str, host: &str) -> Result<()>;
+            fn add_email(&mut self, elem: ElemId, local: &str, domain: &str) -> Result<()>;
+            fn add_declared_len(&mut self, elem: ElemId, len: usize) -> Result<()>;
+            fn add_absent_field(&mut self, elem: ElemId, field: &str) -> Result<()>;
+            fn add_unit(&mut self, elem: ElemId) -> Result<()>;
+            fn add_truncated(&mut self, relation: &str, quota: usize) -> Result<()>;
+            fn add_redacted(&mut self, elem: ElemId) -> Result<()>;
+            fn add_sampled(&mut self, elem: ElemId, len: usize, kept: usize) -> Result<()>;
+            fn add_elem_type_kind(&mut self, elem_type: ElemType, kind: ElemKind) -> Result<()>;
+            fn add_same_string(&mut self, elem: ElemId, other: ElemId) -> Result<()>;
+            fn add_shape(&mut self, elem: ElemId, shape: u64) -> Result<()>;
+            fn add_shape_field(&mut self, shape: u64, pos: usize, field: &str) -> Result<()>;
+            fn add_parse_error(&mut self, file: &str, line: usize, message: &str) -> Result<()>;
+            fn add_string_hash(&mut self, elem: ElemId, hash: u64, len: usize) -> Result<()>;
+            fn add_root_meta(&mut self, file: &str, key: &str, value: &str) -> Result<()>;
+            fn add_depth(&mut self, elem: ElemId, depth: usize) -> Result<()>;
+            fn add_subtree_size(&mut self, elem: ElemId, size: usize) -> Result<()>;
+            fn add_codepoint(&mut self, elem: ElemId, codepoint: u32) -> Result<()>;
         }
     }
 
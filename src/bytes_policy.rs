@@ -0,0 +1,175 @@
+//! Policies that encode byte arrays as text, so that backends storing
+//! strings as symbols can extract them.
+
+use std::fmt::Write;
+
+/// How backends encode the byte arrays they extract as text. Encoded byte
+/// arrays are stored as symbols, along with the name of their encoding and
+/// their original length, so that rules can tell encodings apart and reason
+/// about the size of payloads whose contents were only hashed.
+///
+/// Hex and base64 encodings keep the contents of byte arrays, while hashing
+/// keeps symbol tables small when byte arrays are large:
+///
+/// ```
+/// # use serde_datalog::bytes_policy::{BytesPolicy, HashAlgorithm};
+/// assert_eq!(BytesPolicy::Hex.encode(b"hi!"), "686921");
+/// assert_eq!(BytesPolicy::Base64.encode(b"hi!"), "aGkh");
+/// assert_eq!(BytesPolicy::Base64.decode("aGkh"), Some(b"hi!".to_vec()));
+/// assert_eq!(
+///     BytesPolicy::Hash(HashAlgorithm::Fnv1a64).encode(b"hi!"),
+///     "3373751930070381"
+/// );
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum BytesPolicy {
+    /// Lowercase hexadecimal digits, two per byte.
+    #[default]
+    Hex,
+
+    /// Standard base64 with padding, as specified by RFC 4648.
+    Base64,
+
+    /// Only the hash of the byte array, in lowercase hexadecimal digits.
+    Hash(HashAlgorithm),
+}
+
+/// Hash algorithms of [BytesPolicy::Hash].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HashAlgorithm {
+    /// 64-bit FNV-1a, which is fast but not collision resistant.
+    Fnv1a64,
+
+    /// SHA-256. Requires the `sha256` feature.
+    #[cfg(feature = "sha256")]
+    Sha256,
+}
+
+impl BytesPolicy {
+    /// Name of the encoding, which backends store with encoded byte arrays:
+    /// `hex`, `base64`, or the name of the hash algorithm (`fnv1a64` or
+    /// `sha256`).
+    pub fn name(&self) -> &'static str {
+        match self {
+            BytesPolicy::Hex => "hex",
+            BytesPolicy::Base64 => "base64",
+            BytesPolicy::Hash(HashAlgorithm::Fnv1a64) => "fnv1a64",
+            #[cfg(feature = "sha256")]
+            BytesPolicy::Hash(HashAlgorithm::Sha256) => "sha256",
+        }
+    }
+
+    /// The policy named `name`, as returned by [name][Self::name].
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "hex" => Some(BytesPolicy::Hex),
+            "base64" => Some(BytesPolicy::Base64),
+            "fnv1a64" => Some(BytesPolicy::Hash(HashAlgorithm::Fnv1a64)),
+            #[cfg(feature = "sha256")]
+            "sha256" => Some(BytesPolicy::Hash(HashAlgorithm::Sha256)),
+            _ => None,
+        }
+    }
+
+    /// Encode `bytes` as text.
+    pub fn encode(&self, bytes: &[u8]) -> String {
+        match self {
+            BytesPolicy::Hex => hex(bytes),
+            BytesPolicy::Base64 => base64(bytes),
+            BytesPolicy::Hash(HashAlgorithm::Fnv1a64) => {
+                format!("{:016x}", crate::string_policy::fnv1a(bytes))
+            }
+
+            #[cfg(feature = "sha256")]
+            BytesPolicy::Hash(HashAlgorithm::Sha256) => {
+                use sha2::Digest;
+                hex(&sha2::Sha256::digest(bytes))
+            }
+        }
+    }
+
+    /// Decode `text` encoded with [encode][Self::encode]. Returns `None` if
+    /// `text` is not a valid encoding, or if the policy only keeps hashes.
+    pub fn decode(&self, text: &str) -> Option<Vec<u8>> {
+        match self {
+            BytesPolicy::Hex => unhex(text),
+            BytesPolicy::Base64 => unbase64(text),
+            BytesPolicy::Hash(_) => None,
+        }
+    }
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .fold(String::with_capacity(bytes.len() * 2), |mut text, byte| {
+            let _ = write!(text, "{:02x}", byte);
+            text
+        })
+}
+
+fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+    let mut text = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let group = chunk.iter().enumerate().fold(0u32, |group, (i, byte)| {
+            group | (*byte as u32) << (16 - 8 * i)
+        });
+
+        for i in 0..4 {
+            if i <= chunk.len() {
+                let index = (group >> (18 - 6 * i)) & 0x3f;
+                text.push(ALPHABET[index as usize] as char);
+            } else {
+                text.push('=');
+            }
+        }
+    }
+
+    text
+}
+
+fn unhex(text: &str) -> Option<Vec<u8>> {
+    if !text.len().is_multiple_of(2) {
+        return None;
+    }
+
+    (0..text.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(text.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+fn unbase64(text: &str) -> Option<Vec<u8>> {
+    if !text.len().is_multiple_of(4) {
+        return None;
+    }
+
+    let mut bytes = Vec::with_capacity(text.len() / 4 * 3);
+    for chunk in text.as_bytes().chunks(4) {
+        let padding = chunk.iter().rev().take_while(|c| **c == b'=').count();
+        if padding > 2 {
+            return None;
+        }
+
+        let mut group = 0u32;
+        for (i, c) in chunk[..4 - padding].iter().enumerate() {
+            let index = match c {
+                b'A'..=b'Z' => c - b'A',
+                b'a'..=b'z' => c - b'a' + 26,
+                b'0'..=b'9' => c - b'0' + 52,
+                b'+' => 62,
+                b'/' => 63,
+                _ => return None,
+            };
+            group |= (index as u32) << (18 - 6 * i);
+        }
+
+        for i in 0..3 - padding {
+            bytes.push((group >> (16 - 8 * i)) as u8);
+        }
+    }
+
+    Some(bytes)
+}
@@ -0,0 +1,88 @@
+//! Cache of the facts extracted from inputs, so that later runs of the
+//! command-line tool do not extract unchanged inputs again.
+//!
+//! The cache is a directory with a trace of the calls the extractor made for
+//! each input (see [record][serde_datalog::backend::record]), stored as JSON
+//! lines in a file named after a SHA-256 hash of the input's contents and of
+//! the settings of the extraction. Cached calls are replayed into the
+//! extractor of the run with [DatalogExtractor::replay], which renumbers
+//! their elements, so the facts of cached and extracted inputs merge into
+//! one output.
+//!
+//! [DatalogExtractor::replay]: serde_datalog::DatalogExtractor::replay
+
+use serde_datalog::backend::record::{self, Call};
+use sha2::{Digest, Sha256};
+use std::{
+    fs,
+    io::{self, BufReader, BufWriter},
+    path::PathBuf,
+};
+
+use crate::error::CliError;
+
+/// A directory of the facts extracted from inputs.
+pub struct ExtractionCache {
+    dir: PathBuf,
+}
+
+impl ExtractionCache {
+    /// Open the cache in directory `dir`, which is created if it does not
+    /// exist.
+    pub fn open(dir: &str) -> Result<Self, CliError> {
+        fs::create_dir_all(dir).map_err(|source| CliError::Io {
+            path: dir.to_string(),
+            source,
+        })?;
+
+        Result::Ok(ExtractionCache { dir: dir.into() })
+    }
+
+    /// The key of the facts of an input, from `parts` that determine the
+    /// facts: its contents, its name, its input format, and the settings of
+    /// the extraction.
+    pub fn key(parts: &[&[u8]]) -> String {
+        let mut hasher = Sha256::new();
+        for part in parts {
+            // parts are prefixed with their length, so that their
+            // boundaries are part of the key
+            hasher.update((part.len() as u64).to_le_bytes());
+            hasher.update(part);
+        }
+
+        format!("{:x}", hasher.finalize())
+    }
+
+    fn path(&self, key: &str) -> PathBuf {
+        self.dir.join(format!("{}.jsonl", key))
+    }
+
+    /// The calls cached under `key`, if any. Cached traces that cannot be
+    /// read, e.g. because they were written by an incompatible version, are
+    /// treated as missing, so that the input is extracted again.
+    pub fn get(&self, key: &str) -> Option<Vec<Call>> {
+        let file = fs::File::open(self.path(key)).ok()?;
+        record::read_trace(BufReader::new(file)).ok()
+    }
+
+    /// Cache `calls` under `key`. The trace is written to a temporary file
+    /// that replaces the cached trace once it is complete, so that runs
+    /// that are interrupted or that share the cache never read partial
+    /// traces.
+    pub fn put(&self, key: &str, calls: &[Call]) -> Result<(), CliError> {
+        let path = self.path(key);
+        let tmp_path = path.with_extension(format!("jsonl.{}.tmp", std::process::id()));
+        let io_error = |source: io::Error| CliError::Io {
+            path: path.display().to_string(),
+            source,
+        };
+
+        let file = fs::File::create(&tmp_path).map_err(io_error)?;
+        record::write_trace(calls, BufWriter::new(file))
+            .and_then(|_| fs::rename(&tmp_path, &path))
+            .map_err(|source| {
+                let _ = fs::remove_file(&tmp_path);
+                io_error(source)
+            })
+    }
+}
@@ -0,0 +1,287 @@
+//! Preset that extracts the dependency graph of a Cargo project, from either
+//! a `Cargo.lock` file or the output of `cargo metadata --format-version 1`,
+//! into relations that are convenient to query:
+//!
+//! - `package(id, name, version, source)`: packages in the graph. Packages
+//!   without a source (e.g. workspace members) have source `""`.
+//! - `dependency(package, dependency, kind)`: dependency edges between
+//!   packages. The kind is `normal`, `dev`, or `build`; since `Cargo.lock`
+//!   does not record dependency kinds, edges from lock files have kind `""`.
+//! - `feature(package, feature)` and `featureEnables(package, feature, enables)`:
+//!   features declared by packages, and what each feature enables.
+//! - `enabledFeature(package, feature)`: features enabled in the resolved graph.
+//! - `workspaceMember(package)`: members of the workspace.
+//!
+//! Feature and workspace relations are only populated from `cargo metadata`
+//! output, which records them.
+
+use rusqlite::Connection;
+use serde_json::Value as JsonValue;
+
+pub struct Package {
+    pub id: String,
+    pub name: String,
+    pub version: String,
+    pub source: String,
+}
+
+#[derive(Default)]
+pub struct CargoGraph {
+    pub packages: Vec<Package>,
+    pub dependencies: Vec<(String, String, String)>,
+    pub features: Vec<(String, String)>,
+    pub feature_enables: Vec<(String, String, String)>,
+    pub enabled_features: Vec<(String, String)>,
+    pub workspace_members: Vec<String>,
+}
+
+fn get_str<'a>(value: &'a JsonValue, key: &str) -> Option<&'a str> {
+    value.get(key).and_then(|v| v.as_str())
+}
+
+impl CargoGraph {
+    /// Build the dependency graph recorded in a `Cargo.lock` file.
+    pub fn from_lock(lock: &toml::Value) -> Result<Self, String> {
+        let entries = match lock.get("package") {
+            Some(packages) => packages
+                .as_array()
+                .ok_or_else(|| "package is not an array".to_string())?
+                .as_slice(),
+            None => &[],
+        };
+
+        let mut graph = CargoGraph::default();
+        for entry in entries.iter() {
+            let field = |key: &str| entry.get(key).and_then(|v| v.as_str());
+            let name = field("name").ok_or_else(|| "package without a name".to_string())?;
+            let version =
+                field("version").ok_or_else(|| format!("package {} without a version", name))?;
+            let source = field("source").unwrap_or_default();
+
+            graph.packages.push(Package {
+                id: Self::lock_id(name, version, source),
+                name: name.to_string(),
+                version: version.to_string(),
+                source: source.to_string(),
+            });
+        }
+
+        for (entry, package) in entries.iter().zip(graph.packages.iter()) {
+            let deps = entry
+                .get("dependencies")
+                .and_then(|deps| deps.as_array())
+                .map(|deps| deps.as_slice())
+                .unwrap_or_default();
+
+            for dep in deps.iter() {
+                let dep = dep
+                    .as_str()
+                    .ok_or_else(|| format!("invalid dependency of {}", package.id))?;
+                let dep_id = graph.resolve_lock_dependency(dep)?;
+                graph
+                    .dependencies
+                    .push((package.id.clone(), dep_id, String::new()));
+            }
+        }
+
+        Result::Ok(graph)
+    }
+
+    /// Packages in lock files are identified by name, version, and source,
+    /// in the format Cargo uses to refer to them.
+    fn lock_id(name: &str, version: &str, source: &str) -> String {
+        if source.is_empty() {
+            format!("{} {}", name, version)
+        } else {
+            format!("{} {} ({})", name, version, source)
+        }
+    }
+
+    /// Resolve a dependency of the form `name`, `name version`, or
+    /// `name version (source)` to a package.
+    fn resolve_lock_dependency(&self, dep: &str) -> Result<String, String> {
+        let mut parts = dep.splitn(3, ' ');
+        let name = parts.next().unwrap_or_default();
+        let version = parts.next();
+        let source = parts
+            .next()
+            .map(|s| s.trim_start_matches('(').trim_end_matches(')'));
+
+        let matches: Vec<&Package> = self
+            .packages
+            .iter()
+            .filter(|p| p.name == name)
+            .filter(|p| version.is_none_or(|v| p.version == v))
+            .filter(|p| source.is_none_or(|s| p.source == s))
+            .collect();
+
+        match matches.as_slice() {
+            [package] => Result::Ok(package.id.clone()),
+            [] => Result::Err(format!("dependency {} does not match any package", dep)),
+            _ => Result::Err(format!("dependency {} matches multiple packages", dep)),
+        }
+    }
+
+    /// Build the dependency graph in the output of
+    /// `cargo metadata --format-version 1`.
+    pub fn from_metadata(metadata: &JsonValue) -> Result<Self, String> {
+        let packages = metadata
+            .get("packages")
+            .and_then(|p| p.as_array())
+            .ok_or_else(|| "metadata is missing packages".to_string())?;
+
+        let mut graph = CargoGraph::default();
+        for package in packages.iter() {
+            let id = get_str(package, "id").ok_or_else(|| "package without an id".to_string())?;
+
+            graph.packages.push(Package {
+                id: id.to_string(),
+                name: get_str(package, "name").unwrap_or_default().to_string(),
+                version: get_str(package, "version").unwrap_or_default().to_string(),
+                source: get_str(package, "source").unwrap_or_default().to_string(),
+            });
+
+            if let Some(features) = package.get("features").and_then(|f| f.as_object()) {
+                for (feature, enables) in features.iter() {
+                    graph.features.push((id.to_string(), feature.clone()));
+
+                    for enabled in enables.as_array().into_iter().flatten() {
+                        if let Some(enabled) = enabled.as_str() {
+                            graph.feature_enables.push((
+                                id.to_string(),
+                                feature.clone(),
+                                enabled.to_string(),
+                            ));
+                        }
+                    }
+                }
+            }
+        }
+
+        if let Some(members) = metadata.get("workspace_members").and_then(|m| m.as_array()) {
+            graph.workspace_members = members
+                .iter()
+                .filter_map(|m| m.as_str().map(|m| m.to_string()))
+                .collect();
+        }
+
+        // the resolved graph is absent when running with --no-deps
+        let nodes = metadata
+            .get("resolve")
+            .and_then(|r| r.get("nodes"))
+            .and_then(|n| n.as_array())
+            .map(|n| n.as_slice())
+            .unwrap_or_default();
+
+        for node in nodes.iter() {
+            let id = get_str(node, "id").ok_or_else(|| "node without an id".to_string())?;
+
+            for dep in node
+                .get("deps")
+                .and_then(|d| d.as_array())
+                .into_iter()
+                .flatten()
+            {
+                let dep_id =
+                    get_str(dep, "pkg").ok_or_else(|| format!("invalid dependency of {}", id))?;
+
+                let mut kinds: Vec<String> = dep
+                    .get("dep_kinds")
+                    .and_then(|k| k.as_array())
+                    .into_iter()
+                    .flatten()
+                    .map(|k| get_str(k, "kind").unwrap_or("normal").to_string())
+                    .collect();
+                kinds.sort();
+                kinds.dedup();
+
+                if kinds.is_empty() {
+                    kinds.push("normal".to_string());
+                }
+
+                for kind in kinds {
+                    graph
+                        .dependencies
+                        .push((id.to_string(), dep_id.to_string(), kind));
+                }
+            }
+
+            for feature in node
+                .get("features")
+                .and_then(|f| f.as_array())
+                .into_iter()
+                .flatten()
+            {
+                if let Some(feature) = feature.as_str() {
+                    graph
+                        .enabled_features
+                        .push((id.to_string(), feature.to_string()));
+                }
+            }
+        }
+
+        Result::Ok(graph)
+    }
+
+    /// Store the graph's relations in tables of `conn`, replacing existing ones.
+    pub fn store(&self, conn: &Connection) -> rusqlite::Result<()> {
+        conn.execute_batch(
+            "BEGIN;
+            DROP TABLE IF EXISTS package;
+            DROP TABLE IF EXISTS dependency;
+            DROP TABLE IF EXISTS feature;
+            DROP TABLE IF EXISTS featureEnables;
+            DROP TABLE IF EXISTS enabledFeature;
+            DROP TABLE IF EXISTS workspaceMember;
+            CREATE TABLE package(id TEXT, name TEXT, version TEXT, source TEXT, PRIMARY KEY (id));
+            CREATE TABLE dependency(package TEXT, dependency TEXT, kind TEXT);
+            CREATE TABLE feature(package TEXT, feature TEXT);
+            CREATE TABLE featureEnables(package TEXT, feature TEXT, enables TEXT);
+            CREATE TABLE enabledFeature(package TEXT, feature TEXT);
+            CREATE TABLE workspaceMember(package TEXT);",
+        )?;
+
+        let res = self.insert_rows(conn);
+        match res {
+            Result::Ok(()) => conn.execute_batch("COMMIT;"),
+            Result::Err(err) => {
+                conn.execute_batch("ROLLBACK;")?;
+                Result::Err(err)
+            }
+        }
+    }
+
+    fn insert_rows(&self, conn: &Connection) -> rusqlite::Result<()> {
+        let mut stmt = conn.prepare("INSERT INTO package VALUES (?1, ?2, ?3, ?4);")?;
+        for p in self.packages.iter() {
+            stmt.execute((&p.id, &p.name, &p.version, &p.source))?;
+        }
+
+        let mut stmt = conn.prepare("INSERT INTO dependency VALUES (?1, ?2, ?3);")?;
+        for (package, dependency, kind) in self.dependencies.iter() {
+            stmt.execute((package, dependency, kind))?;
+        }
+
+        let mut stmt = conn.prepare("INSERT INTO feature VALUES (?1, ?2);")?;
+        for (package, feature) in self.features.iter() {
+            stmt.execute((package, feature))?;
+        }
+
+        let mut stmt = conn.prepare("INSERT INTO featureEnables VALUES (?1, ?2, ?3);")?;
+        for (package, feature, enables) in self.feature_enables.iter() {
+            stmt.execute((package, feature, enables))?;
+        }
+
+        let mut stmt = conn.prepare("INSERT INTO enabledFeature VALUES (?1, ?2);")?;
+        for (package, feature) in self.enabled_features.iter() {
+            stmt.execute((package, feature))?;
+        }
+
+        let mut stmt = conn.prepare("INSERT INTO workspaceMember VALUES (?1);")?;
+        for member in self.workspace_members.iter() {
+            stmt.execute([member])?;
+        }
+
+        Result::Ok(())
+    }
+}
@@ -0,0 +1,701 @@
+// Command-line arguments of the serde_datalog binary. This file is included
+// by main.rs and by the build script, which generates the shell completion
+// scripts and the man page printed by the `completions` and `man` subcommands
+// from them.
+
+#[derive(Parser, Debug)]
+#[command(
+    version = "0.1.0",
+    about,
+    long_about = Some("Converts input in a variety of formats to a database of facts."),
+    args_conflicts_with_subcommands = true
+)]
+struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    #[command(flatten)]
+    extract: ExtractArgs,
+}
+
+/// Options of the `extract` subcommand, which are also accepted without a
+/// subcommand.
+#[derive(clap::Args, Debug)]
+struct ExtractArgs {
+    #[command(flatten)]
+    input: InputArgs,
+
+    #[arg(
+        short = 'o',
+        long = "output",
+        help = "File name of output SQLite database"
+    )]
+    output: Option<String>,
+
+    #[arg(
+        short = 'a',
+        long = "append",
+        help = "Append facts to the output database if it exists, instead of overwriting it"
+    )]
+    append: bool,
+
+    #[arg(
+        long = "replace",
+        requires = "append",
+        help = "When appending, replace facts previously extracted from the same input files"
+    )]
+    replace: bool,
+
+    #[arg(
+        long = "split",
+        value_name = "MODE",
+        requires = "output",
+        conflicts_with = "append",
+        help = "Write one database per input file (file) or per relation (relation) into the output directory, with agreeing symbol identifiers"
+    )]
+    split: Option<SplitMode>,
+
+    #[arg(
+        short = 'l',
+        long = "list-formats",
+        help = "Generate a list of supported file formats"
+    )]
+    list_formats: bool,
+}
+
+#[derive(clap::Args, Clone, Debug)]
+struct InputArgs {
+    #[arg(
+        index = 1,
+        help = "List of input files, directories, or glob patterns; if absent, will read from standard input"
+    )]
+    filenames: Vec<String>,
+
+    #[arg(
+        short = 'r',
+        long = "recursive",
+        help = "Extract all files with a known input format in directories and their subdirectories"
+    )]
+    recursive: bool,
+
+    #[arg(
+        short = 'f',
+        long = "format",
+        help = "Format of all input files; if absent, will guess the format of each file from its extension"
+    )]
+    format: Option<String>,
+
+    #[arg(
+        long = "detect",
+        help = "Detect the format of inputs from their contents if it cannot be guessed from their file extension, including standard input"
+    )]
+    detect: bool,
+
+    #[arg(
+        short = 'c',
+        long = "compression",
+        help = "Compression of input (gzip, zstd, xz, or none); if absent, will guess compression from file extensions"
+    )]
+    compression: Option<String>,
+
+    #[arg(
+        short = 'k',
+        long = "keep-going",
+        help = "Report input files that cannot be processed, discarding their facts, and continue with the remaining files"
+    )]
+    keep_going: bool,
+
+    #[arg(
+        long = "config",
+        value_name = "FILE",
+        conflicts_with = "no_config",
+        help = "Read settings from this configuration file instead of serdedl.toml in the current directory or its ancestors"
+    )]
+    config: Option<String>,
+
+    #[arg(long = "no-config", help = "Do not read settings from serdedl.toml")]
+    no_config: bool,
+
+    #[arg(
+        long = "backend",
+        value_name = "SCHEMA",
+        help = "Generate facts in the schema assuming string map keys (string-key) or in the general schema (general); by default (auto), the former is used when every input format has string keys"
+    )]
+    backend: Option<BackendArg>,
+
+    /// Input formats of file extensions, from the `[formats]` table of the
+    /// configuration file.
+    #[arg(skip)]
+    extension_formats: BTreeMap<String, String>,
+
+    #[arg(
+        long = "json",
+        help = "Print a summary of the extraction to standard output as JSON, and errors to standard error as JSON"
+    )]
+    json: bool,
+
+    #[arg(
+        long = "skip-invalid",
+        help = "Record documents that cannot be read or extracted in the parseError relation and continue with the next document"
+    )]
+    skip_invalid: bool,
+
+    #[arg(
+        long = "progress",
+        help = "Show the bytes read, the elements extracted, and the current input file while extracting"
+    )]
+    progress: bool,
+
+    #[cfg(feature = "cache")]
+    #[arg(
+        long = "cache",
+        value_name = "DIR",
+        conflicts_with_all = ["same_strings", "quota"],
+        help = "Reuse the facts of unchanged input files extracted by earlier runs with the same options from this directory, and store the facts of other input files in it"
+    )]
+    cache: Option<String>,
+
+    #[arg(
+        long = "split-roots",
+        help = "Extract each item of a top-level array, or the value of each entry of a top-level map, in an input file as a separate root element"
+    )]
+    split_roots: bool,
+
+    #[arg(
+        long = "separate-namespaces",
+        help = "Give element type names, field names, type names, variant names, and string values distinct symbol identifiers"
+    )]
+    separate_namespaces: bool,
+
+    #[arg(
+        long = "max-string-len",
+        help = "Truncate strings longer than this many bytes, appending a hash of the full string"
+    )]
+    max_string_len: Option<usize>,
+
+    #[arg(
+        long = "elide-strings",
+        value_name = "MAX_LEN",
+        help = "Replace strings longer than this many bytes with their hash and length in the stringHash relation"
+    )]
+    elide_strings: Option<usize>,
+
+    #[arg(
+        long = "escape-control-chars",
+        help = "Escape control characters such as tabs and newlines in strings"
+    )]
+    escape_control_chars: bool,
+
+    #[arg(
+        long = "keep-full-strings",
+        help = "Store the original text of strings changed by other string options in the fullString relation"
+    )]
+    keep_full_strings: bool,
+
+    #[cfg(feature = "unicode")]
+    #[arg(
+        long = "nfc",
+        help = "Normalize strings to Unicode Normalization Form C"
+    )]
+    nfc: bool,
+
+    #[arg(long = "case-fold", help = "Convert strings to lowercase")]
+    case_fold: bool,
+
+    #[cfg(feature = "string_compression")]
+    #[arg(
+        long = "compress-strings",
+        value_name = "MIN_LEN",
+        help = "Compress strings of at least this many bytes in new databases with zstd"
+    )]
+    compress_strings: Option<usize>,
+
+    #[arg(
+        long = "stats",
+        help = "Create tables with fact counts per relation, a histogram of numbers, and the most frequent strings in the output database"
+    )]
+    stats: bool,
+
+    #[arg(
+        long = "create-indexes",
+        help = "Create indexes on value columns and on the text of strings in the output database"
+    )]
+    create_indexes: bool,
+
+    #[arg(
+        long = "analyze",
+        help = "Run ANALYZE on the output database after loading facts"
+    )]
+    analyze: bool,
+
+    #[arg(
+        long = "provenance",
+        help = "Record the time of extraction and the command line in the metadata of the output database; without it, extracting the same inputs yields identical databases"
+    )]
+    provenance: bool,
+
+    #[arg(
+        long = "number-bits",
+        value_name = "BITS",
+        help = "Store only numbers that fit in BITS-bit signed integers in the number relation of the output database, e.g. 32 for Souffle's default number type"
+    )]
+    number_bits: Option<u32>,
+
+    #[arg(
+        long = "wide-numbers",
+        value_enum,
+        default_value = "split",
+        requires = "number_bits",
+        help = "How numbers wider than --number-bits are stored: split into high and low 32 bits in numberParts, as text in numberText, or refused with an error"
+    )]
+    wide_numbers: WideNumbersArg,
+
+    #[arg(
+        long = "souffle-directives",
+        help = "Write Souffle declarations and input directives for the output database next to it, in a file with extension .dl"
+    )]
+    souffle_directives: bool,
+
+    #[arg(
+        long = "string-report",
+        value_name = "N",
+        help = "Print the N most frequently referenced strings and their share of all string bytes to standard error"
+    )]
+    string_report: Option<usize>,
+
+    #[arg(
+        long = "proto-descriptor",
+        requires = "proto_message",
+        help = "File name of a protobuf descriptor set (from protoc --descriptor_set_out) describing protobuf input"
+    )]
+    proto_descriptor: Option<String>,
+
+    #[arg(
+        long = "proto-message",
+        requires = "proto_descriptor",
+        help = "Fully qualified name of the message type of protobuf input, e.g. my.package.Message"
+    )]
+    proto_message: Option<String>,
+
+    #[arg(
+        long = "schema",
+        help = "File name of a JSON Schema or OpenAPI document whose schemas annotate extracted elements in the schemaType relation"
+    )]
+    schema: Option<String>,
+
+    #[arg(
+        long = "schema-ref",
+        requires = "schema",
+        default_value = "#",
+        help = "JSON pointer to the schema of input documents in the --schema document, e.g. #/components/schemas/Pet"
+    )]
+    schema_ref: String,
+
+    #[arg(
+        long = "recognize",
+        value_name = "KIND",
+        value_delimiter = ',',
+        help = "Recognize scalars of these kinds (uuid, ip, url, email, or all) in strings, extracting them in relations of the same name"
+    )]
+    recognize: Vec<ScalarKind>,
+
+    #[arg(
+        long = "bytes",
+        value_name = "ENCODING",
+        help = "Extract byte arrays as text in this encoding (hex, base64, fnv1a64, or sha256), with their encoding and length in the bytesEncoding relation"
+    )]
+    bytes: Option<String>,
+
+    #[arg(
+        long = "sparse-structs",
+        help = "Record the number of fields structs declare in the declaredLen relation, and the fields they skip in the absentField relation"
+    )]
+    sparse_structs: bool,
+
+    #[arg(
+        long = "units",
+        value_name = "MODE",
+        default_value = "elements",
+        help = "Extract unit values as elements without facts, as elements in the unit relation (facts), or as one shared element per type and file (singletons)"
+    )]
+    units: UnitMode,
+
+    #[arg(
+        long = "quota",
+        value_name = "RELATION=MAX",
+        help = "Extract at most MAX facts of RELATION, recording in the truncated relation that later facts were dropped; can be given for several relations"
+    )]
+    quota: Vec<String>,
+
+    #[arg(
+        long = "redact",
+        value_name = "PATTERN",
+        value_delimiter = ',',
+        help = "Redact the strings and numbers of fields and map entries whose names contain these patterns, ignoring case, recording them in the redacted relation"
+    )]
+    redact: Vec<String>,
+
+    #[arg(
+        long = "redact-mode",
+        value_name = "MODE",
+        help = "Replace redacted values with their hashes (hash, the default) or with *** and 0 (mask)"
+    )]
+    redact_mode: Option<RedactMode>,
+
+    #[arg(
+        long = "redact-salt",
+        value_name = "SALT",
+        help = "Secret prefixed to redacted values before hashing them"
+    )]
+    redact_salt: Option<String>,
+
+    #[arg(
+        long = "sample",
+        value_name = "THRESHOLD",
+        help = "Extract only THRESHOLD elements of sequences with more elements, recording their lengths in the sampled relation"
+    )]
+    sample: Option<usize>,
+
+    #[arg(
+        long = "sample-mode",
+        value_name = "MODE",
+        default_value = "stratified",
+        help = "Sample evenly spread elements (stratified) or elements chosen at random (random)"
+    )]
+    sample_mode: SampleModeArg,
+
+    #[arg(
+        long = "sample-seed",
+        value_name = "SEED",
+        default_value_t = 0,
+        help = "Seed of the random sampling of sequences"
+    )]
+    sample_seed: u64,
+
+    #[arg(
+        long = "same-strings",
+        help = "Relate strings to the strings with the same value in earlier input files in the sameString relation"
+    )]
+    same_strings: bool,
+
+    #[arg(
+        long = "shapes",
+        help = "Identify the shapes of structs and maps, recording them in the shape relation and their fields in the shapeField relation"
+    )]
+    shapes: bool,
+
+    #[arg(
+        long = "measures",
+        help = "Record the depth of every element in the depth relation and the number of elements in its value in the subtreeSize relation"
+    )]
+    measures: bool,
+
+    #[arg(
+        long = "char-codepoints",
+        help = "Record the Unicode code point of every char in the char relation"
+    )]
+    char_codepoints: bool,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    #[command(about = "Extract facts from input files; the default when no subcommand is given")]
+    Extract(Box<ExtractArgs>),
+
+    #[command(about = "Run a query against a database of facts")]
+    Query(QueryArgs),
+
+    #[command(about = "Extract facts from input files and run a Souffle program on them")]
+    Run(Box<RunArgs>),
+
+    #[command(about = "Interactively explore a database of facts")]
+    Repl(Box<ReplArgs>),
+
+    #[command(about = "Merge databases, e.g. databases written with --split, into one database")]
+    Merge(MergeArgs),
+
+    #[command(about = "Check the referential integrity of a database of facts")]
+    Validate(ValidateArgs),
+
+    #[command(about = "Report facts added, removed, or changed between two databases")]
+    Diff(DiffArgs),
+
+    #[command(about = "Infer the fields and value types of the records of a database of facts")]
+    Schema(SchemaArgs),
+
+    #[command(about = "Count the facts of each relation of a database of facts")]
+    Stats(StatsArgs),
+
+    #[cfg(feature = "analyze")]
+    #[command(
+        about = "Derive the reachability, depths, and subtree sizes of the elements of a database of facts, and write them into it"
+    )]
+    Analyze(AnalyzeArgs),
+
+    #[cfg(feature = "cargo")]
+    #[command(about = "Extract the dependency graph of a Cargo project")]
+    Cargo(CargoArgs),
+
+    #[command(about = "Print a completion script for a shell")]
+    Completions(CompletionsArgs),
+
+    #[command(about = "Print the man page of serde_datalog in roff format")]
+    Man,
+}
+
+#[derive(clap::Args, Debug)]
+struct MergeArgs {
+    #[arg(index = 1, required = true, help = "List of databases to merge")]
+    inputs: Vec<String>,
+
+    #[arg(
+        short = 'o',
+        long = "output",
+        help = "File name of output SQLite database; facts are added to it if it exists"
+    )]
+    output: String,
+}
+
+#[derive(clap::Args, Debug)]
+struct ValidateArgs {
+    #[arg(index = 1, help = "File name of SQLite database to check")]
+    db: String,
+}
+
+#[derive(clap::Args, Debug)]
+struct DiffArgs {
+    #[arg(
+        index = 1,
+        help = "File name of the SQLite database to compare against"
+    )]
+    before: String,
+
+    #[arg(index = 2, help = "File name of the SQLite database to compare")]
+    after: String,
+
+    #[arg(
+        long = "structural",
+        help = "Match elements by their contents instead of their paths from root elements"
+    )]
+    structural: bool,
+}
+
+#[derive(clap::Args, Debug)]
+struct SchemaArgs {
+    #[arg(
+        index = 1,
+        help = "File name of SQLite database to infer a schema from"
+    )]
+    db: String,
+
+    #[arg(
+        long = "format",
+        value_name = "FORMAT",
+        default_value = "json",
+        help = "Format of the inferred schema: JSON (json) or Souffle type declarations (souffle)"
+    )]
+    format: SchemaFormat,
+}
+
+#[derive(clap::Args, Debug)]
+struct StatsArgs {
+    #[arg(index = 1, help = "File name of SQLite database to count facts of")]
+    db: String,
+}
+
+#[cfg(feature = "analyze")]
+#[derive(clap::Args, Debug)]
+struct AnalyzeArgs {
+    #[arg(
+        index = 1,
+        help = "File name of SQLite database to derive relations from and write them into"
+    )]
+    db: String,
+}
+
+#[derive(clap::Args, Debug)]
+struct CompletionsArgs {
+    #[arg(
+        index = 1,
+        value_name = "SHELL",
+        help = "Shell to complete commands of"
+    )]
+    shell: clap_complete::Shell,
+}
+
+#[cfg(feature = "cargo")]
+#[derive(clap::Args, Debug)]
+struct CargoArgs {
+    #[arg(
+        index = 1,
+        help = "A Cargo.lock file, or a JSON file with the output of `cargo metadata --format-version 1`; if absent, will run `cargo metadata`"
+    )]
+    input: Option<String>,
+
+    #[arg(
+        long = "manifest-path",
+        conflicts_with = "input",
+        help = "Path to the Cargo.toml of the project passed to `cargo metadata`"
+    )]
+    manifest_path: Option<String>,
+
+    #[arg(
+        short = 'o',
+        long = "output",
+        help = "File name of output SQLite database"
+    )]
+    output: String,
+}
+
+#[derive(clap::Args, Debug)]
+struct ReplArgs {
+    #[command(flatten)]
+    input: InputArgs,
+
+    #[arg(
+        long = "db",
+        help = "File name of SQLite database to explore; if input files are given, facts extracted from them are stored in this database"
+    )]
+    db: Option<String>,
+}
+
+#[derive(clap::Args, Debug)]
+struct RunArgs {
+    #[command(flatten)]
+    input: InputArgs,
+
+    #[arg(
+        long = "program",
+        value_name = "FILE",
+        help = "Souffle program to run on the extracted facts; it uses the relations of the facts without declaring them"
+    )]
+    program: String,
+
+    #[arg(
+        short = 'o',
+        long = "output",
+        help = "File name of SQLite database to collect the output relations of the program into; if absent, output relations are printed to standard output unless --output-dir is given"
+    )]
+    output: Option<String>,
+
+    #[arg(
+        short = 'D',
+        long = "output-dir",
+        value_name = "DIR",
+        help = "Directory to write the output relations of the program to as CSV files"
+    )]
+    output_dir: Option<String>,
+
+    #[arg(
+        long = "facts",
+        value_name = "FILE",
+        help = "File name of the SQLite database of the extracted facts; if absent, the facts are stored in a temporary database"
+    )]
+    facts: Option<String>,
+
+    #[arg(
+        long = "souffle",
+        value_name = "PATH",
+        default_value = "souffle",
+        help = "Souffle executable to run the program with"
+    )]
+    souffle: String,
+
+    #[arg(
+        short = 'j',
+        long = "jobs",
+        value_name = "N",
+        help = "Number of threads Souffle runs the program with"
+    )]
+    jobs: Option<usize>,
+}
+
+#[derive(clap::Args, Debug)]
+struct QueryArgs {
+    #[arg(long = "db", help = "File name of SQLite database to query")]
+    db: String,
+
+    #[arg(
+        long = "sql",
+        conflicts_with = "datalog",
+        required_unless_present = "datalog",
+        help = "SQL query to run against the database"
+    )]
+    sql: Option<String>,
+
+    #[arg(
+        long = "datalog",
+        help = "Datalog rule to run against the database, e.g. 'q(x) :- map(r, \"key\", x).'"
+    )]
+    datalog: Option<String>,
+}
+
+/// How facts are split into several output databases.
+#[derive(clap::ValueEnum, Clone, Copy, PartialEq, Eq, Debug)]
+enum SplitMode {
+    /// One database per input file. Each database continues the symbol table
+    /// of the database of the previous input file.
+    File,
+
+    /// One database per relation, each with a copy of the symbol table.
+    Relation,
+}
+
+/// Kinds of scalars recognized in strings with `--recognize`.
+#[derive(clap::ValueEnum, Clone, Copy, PartialEq, Eq, Debug)]
+enum ScalarKind {
+    Uuid,
+    Ip,
+    Url,
+    Email,
+    All,
+}
+
+/// Treatments of unit values selected with `--units`, see [UnitPolicy].
+#[derive(clap::ValueEnum, Clone, Copy, PartialEq, Eq, Debug)]
+enum UnitMode {
+    Elements,
+    Facts,
+    Singletons,
+}
+
+/// Schemas of the output database selected with `--backend`.
+#[derive(clap::ValueEnum, serde::Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "kebab-case")]
+pub enum BackendArg {
+    Auto,
+    StringKey,
+    General,
+}
+
+/// Replacements of redacted values selected with `--redact-mode`, see
+/// [RedactionMode].
+#[derive(clap::ValueEnum, serde::Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "kebab-case")]
+pub enum RedactMode {
+    Hash,
+    Mask,
+}
+
+/// Storage of wide numbers selected with `--wide-numbers`, see
+/// [WideNumbers].
+#[derive(clap::ValueEnum, Clone, Copy, PartialEq, Eq, Debug)]
+enum WideNumbersArg {
+    Split,
+    Symbol,
+    Error,
+}
+
+/// Samplings of sequences selected with `--sample-mode`, see [Sampling].
+#[derive(clap::ValueEnum, Clone, Copy, PartialEq, Eq, Debug)]
+enum SampleModeArg {
+    Stratified,
+    Random,
+}
+
+/// Formats of schemas inferred by the `schema` subcommand.
+#[derive(clap::ValueEnum, Clone, Copy, PartialEq, Eq, Debug)]
+enum SchemaFormat {
+    Json,
+    Souffle,
+}
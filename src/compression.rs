@@ -0,0 +1,101 @@
+use std::{
+    io::{self, Read},
+    path::{Path, PathBuf},
+};
+
+/// Compression scheme of an input file. Compressed inputs are decompressed
+/// as they are read by an [InputFormat][serde_datalog::input_format::InputFormat].
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Compression {
+    None,
+    Gzip,
+    Zstd,
+    Xz,
+}
+
+impl Compression {
+    /// Returns all supported compression schemes, excluding [Compression::None].
+    pub fn all() -> Vec<Compression> {
+        vec![Compression::Gzip, Compression::Zstd, Compression::Xz]
+    }
+
+    /// The name of the compression scheme. This name can be passed explicitly
+    /// as the compression of the input.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Compression::None => "none",
+            Compression::Gzip => "gzip",
+            Compression::Zstd => "zstd",
+            Compression::Xz => "xz",
+        }
+    }
+
+    /// Returns a list of file extensions associated with the compression scheme.
+    pub fn file_extensions(&self) -> Vec<&'static str> {
+        match self {
+            Compression::None => vec![],
+            Compression::Gzip => vec!["gz", "gzip"],
+            Compression::Zstd => vec!["zst", "zstd"],
+            Compression::Xz => vec!["xz"],
+        }
+    }
+
+    /// Find the compression scheme with name `name`.
+    pub fn from_name(name: &str) -> Option<Compression> {
+        if name == Compression::None.name() {
+            return Some(Compression::None);
+        }
+
+        Compression::all().into_iter().find(|c| c.name() == name)
+    }
+
+    /// Guess the compression scheme of `path` from its extension.
+    /// Returns the compression scheme along with the path stripped of its
+    /// compression extension, so that the input format of the file can be
+    /// guessed from the remaining extension (e.g. `data.json.gz` yields
+    /// [Compression::Gzip] and `data.json`).
+    pub fn from_path(path: &Path) -> (Compression, PathBuf) {
+        let ext_opt = path.extension().and_then(|ext| ext.to_str());
+
+        if let Some(ext) = ext_opt {
+            let compression_opt = Compression::all()
+                .into_iter()
+                .find(|c| c.file_extensions().contains(&ext));
+
+            if let Some(compression) = compression_opt {
+                return (compression, path.with_extension(""));
+            }
+        }
+
+        (Compression::None, path.to_path_buf())
+    }
+
+    /// Returns a reader of the decompressed contents of `input`.
+    pub fn decompress<'r>(&self, input: Box<dyn Read + 'r>) -> io::Result<Box<dyn Read + 'r>> {
+        let reader: Box<dyn Read + 'r> = match self {
+            Compression::None => input,
+
+            #[cfg(feature = "compression")]
+            Compression::Gzip => Box::new(flate2::read::MultiGzDecoder::new(input)),
+
+            #[cfg(feature = "compression")]
+            Compression::Zstd => Box::new(zstd::Decoder::new(input)?),
+
+            #[cfg(feature = "compression")]
+            Compression::Xz => Box::new(xz2::read::XzDecoder::new_multi_decoder(input)),
+
+            #[cfg(not(feature = "compression"))]
+            _ => {
+                return Err(io::Error::new(
+                    io::ErrorKind::Unsupported,
+                    format!(
+                        "cannot decompress {} input; serde_datalog was built without the compression feature",
+                        self.name()
+                    ),
+                ));
+            }
+        };
+
+        io::Result::Ok(reader)
+    }
+}
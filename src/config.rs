@@ -0,0 +1,308 @@
+//! Configuration of extractors in one place, for applications that set many
+//! options of [DatalogExtractor] or that choose them at runtime.
+//!
+//! [ExtractorConfig] gathers the options set by the `with_` methods of
+//! [DatalogExtractor], checks that they are consistent, and creates
+//! extractors with them:
+//!
+//! ```
+//! # use serde::Serialize;
+//! # use serde_datalog::{backend, quota::FactQuotas, DatalogExtractor};
+//! let mut extractor = DatalogExtractor::builder()
+//!     .shapes(true)
+//!     .error_context(true)
+//!     .fact_quotas(FactQuotas::default().limit("string", 10_000))
+//!     .build(backend::vector::Backend::default())
+//!     .unwrap();
+//!
+//! vec!["a", "b"].serialize(&mut extractor).unwrap();
+//! ```
+//!
+//! Options that are not set keep the defaults of [DatalogExtractor::new].
+
+use std::{
+    collections::HashSet,
+    fmt::{self, Display},
+    time::{Duration, Instant},
+};
+
+use crate::{
+    progress::{CancellationToken, Progress, ProgressCallback},
+    quota::{self, FactQuotas},
+    recognize::Recognizers,
+    redact::Redaction,
+    sample::Sampling,
+    schema_guide::SchemaGuide,
+    string_policy::StringPolicy,
+    unit_policy::UnitPolicy,
+    DatalogExtractor, DatalogExtractorBackend, ElemId, StructHints,
+};
+
+/// Inconsistent options of an [ExtractorConfig].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConfigError {
+    /// A fact quota names a relation that is not in [quota::RELATIONS]
+    UnknownQuotaRelation(String),
+
+    /// Hints were given more than once for the struct type
+    DuplicateHints(String),
+
+    /// Sampling keeps no element of long sequences
+    ZeroSampleThreshold,
+}
+
+impl Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::UnknownQuotaRelation(relation) => {
+                write!(f, "unknown relation {} in quota", relation)
+            }
+
+            ConfigError::DuplicateHints(name) => {
+                write!(f, "multiple hints for struct type {}", name)
+            }
+
+            ConfigError::ZeroSampleThreshold => {
+                write!(f, "sampling threshold must be at least 1")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+/// Options of a [DatalogExtractor]; see the [module documentation][self].
+/// Each method sets the option of the `with_` method of [DatalogExtractor]
+/// with the same name.
+#[derive(Default)]
+pub struct ExtractorConfig {
+    first_elem_id: Option<ElemId>,
+    batch_size: usize,
+    hints: Vec<StructHints>,
+    sparse_structs: bool,
+    string_policy: StringPolicy,
+    schema_guide: Option<SchemaGuide>,
+    recognizers: Recognizers,
+    unit_policy: UnitPolicy,
+    fact_quotas: FactQuotas,
+    redaction: Option<Redaction>,
+    sampling: Option<Sampling>,
+    same_strings: bool,
+    shapes: bool,
+    measures: bool,
+    char_codepoints: bool,
+    error_context: bool,
+    atomic_roots: bool,
+    progress: Option<(usize, ProgressCallback)>,
+    deadline: Option<Instant>,
+    cancellation: Option<CancellationToken>,
+}
+
+impl ExtractorConfig {
+    /// Start generated element identifiers at `first_elem_id`, as
+    /// [DatalogExtractor::new_with_first_elem_id] does.
+    pub fn first_elem_id(mut self, first_elem_id: ElemId) -> Self {
+        self.first_elem_id = Some(first_elem_id);
+        self
+    }
+
+    /// See [DatalogExtractor::with_batch_size].
+    pub fn batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size;
+        self
+    }
+
+    /// See [DatalogExtractor::with_hints]. Unlike the extractor, the
+    /// configuration rejects more than one set of hints for a type.
+    pub fn hints(mut self, hints: StructHints) -> Self {
+        self.hints.push(hints);
+        self
+    }
+
+    /// See [DatalogExtractor::with_sparse_structs].
+    pub fn sparse_structs(mut self, enable: bool) -> Self {
+        self.sparse_structs = enable;
+        self
+    }
+
+    /// See [DatalogExtractor::with_string_policy].
+    pub fn string_policy(mut self, policy: StringPolicy) -> Self {
+        self.string_policy = policy;
+        self
+    }
+
+    /// See [DatalogExtractor::with_schema_guide].
+    pub fn schema_guide(mut self, guide: SchemaGuide) -> Self {
+        self.schema_guide = Some(guide);
+        self
+    }
+
+    /// See [DatalogExtractor::with_recognizers].
+    pub fn recognizers(mut self, recognizers: Recognizers) -> Self {
+        self.recognizers = recognizers;
+        self
+    }
+
+    /// See [DatalogExtractor::with_unit_policy].
+    pub fn unit_policy(mut self, policy: UnitPolicy) -> Self {
+        self.unit_policy = policy;
+        self
+    }
+
+    /// See [DatalogExtractor::with_fact_quotas].
+    pub fn fact_quotas(mut self, quotas: FactQuotas) -> Self {
+        self.fact_quotas = quotas;
+        self
+    }
+
+    /// See [DatalogExtractor::with_redaction].
+    pub fn redaction(mut self, redaction: Redaction) -> Self {
+        self.redaction = Some(redaction);
+        self
+    }
+
+    /// See [DatalogExtractor::with_sampling].
+    pub fn sampling(mut self, sampling: Sampling) -> Self {
+        self.sampling = Some(sampling);
+        self
+    }
+
+    /// See [DatalogExtractor::with_same_strings].
+    pub fn same_strings(mut self, enable: bool) -> Self {
+        self.same_strings = enable;
+        self
+    }
+
+    /// See [DatalogExtractor::with_shapes].
+    pub fn shapes(mut self, enable: bool) -> Self {
+        self.shapes = enable;
+        self
+    }
+
+    /// See [DatalogExtractor::with_measures].
+    pub fn measures(mut self, enable: bool) -> Self {
+        self.measures = enable;
+        self
+    }
+
+    /// See [DatalogExtractor::with_atomic_roots].
+    pub fn atomic_roots(mut self, enable: bool) -> Self {
+        self.atomic_roots = enable;
+        self
+    }
+
+    /// See [DatalogExtractor::with_char_codepoints].
+    pub fn char_codepoints(mut self, enable: bool) -> Self {
+        self.char_codepoints = enable;
+        self
+    }
+
+    /// See [DatalogExtractor::with_error_context].
+    pub fn error_context(mut self, enable: bool) -> Self {
+        self.error_context = enable;
+        self
+    }
+
+    /// See [DatalogExtractor::with_progress].
+    pub fn progress<F>(mut self, interval: usize, callback: F) -> Self
+    where
+        F: FnMut(&Progress) -> std::ops::ControlFlow<()> + 'static,
+    {
+        self.progress = Some((interval, Box::new(callback)));
+        self
+    }
+
+    /// See [DatalogExtractor::with_deadline].
+    pub fn deadline(mut self, deadline: Instant) -> Self {
+        self.deadline = Some(deadline);
+        self
+    }
+
+    /// See [DatalogExtractor::with_timeout]. The timeout starts when it is
+    /// set, not when the extractor is built.
+    pub fn timeout(self, timeout: Duration) -> Self {
+        self.deadline(Instant::now() + timeout)
+    }
+
+    /// See [DatalogExtractor::with_cancellation].
+    pub fn cancellation(mut self, token: CancellationToken) -> Self {
+        self.cancellation = Some(token);
+        self
+    }
+
+    /// Check that the options are consistent.
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        if let Some(relation) = self
+            .fact_quotas
+            .relations()
+            .find(|relation| !quota::RELATIONS.contains(relation))
+        {
+            return Result::Err(ConfigError::UnknownQuotaRelation(relation.to_string()));
+        }
+
+        let mut hinted = HashSet::new();
+        for hints in self.hints.iter() {
+            if !hinted.insert(hints.name()) {
+                return Result::Err(ConfigError::DuplicateHints(hints.name().to_string()));
+            }
+        }
+
+        if self
+            .sampling
+            .is_some_and(|sampling| sampling.threshold() == 0)
+        {
+            return Result::Err(ConfigError::ZeroSampleThreshold);
+        }
+
+        Result::Ok(())
+    }
+
+    /// Create an extractor with these options that materializes facts with
+    /// `backend`, if the options are consistent.
+    pub fn build<B: DatalogExtractorBackend>(
+        self,
+        backend: B,
+    ) -> Result<DatalogExtractor<B>, ConfigError> {
+        self.validate()?;
+
+        let mut extractor = DatalogExtractor::new_with_first_elem_id(
+            backend,
+            self.first_elem_id.unwrap_or(ElemId(1)),
+        )
+        .with_batch_size(self.batch_size)
+        .with_sparse_structs(self.sparse_structs)
+        .with_string_policy(self.string_policy)
+        .with_recognizers(self.recognizers)
+        .with_unit_policy(self.unit_policy)
+        .with_fact_quotas(self.fact_quotas)
+        .with_same_strings(self.same_strings)
+        .with_shapes(self.shapes)
+        .with_measures(self.measures)
+        .with_atomic_roots(self.atomic_roots)
+        .with_char_codepoints(self.char_codepoints)
+        .with_error_context(self.error_context);
+        for hints in self.hints {
+            extractor = extractor.with_hints(hints);
+        }
+        if let Some(guide) = self.schema_guide {
+            extractor = extractor.with_schema_guide(guide);
+        }
+        if let Some(redaction) = self.redaction {
+            extractor = extractor.with_redaction(redaction);
+        }
+        if let Some(sampling) = self.sampling {
+            extractor = extractor.with_sampling(sampling);
+        }
+        if let Some((interval, callback)) = self.progress {
+            extractor = extractor.with_progress(interval, callback);
+        }
+        if let Some(deadline) = self.deadline {
+            extractor = extractor.with_deadline(deadline);
+        }
+        if let Some(token) = self.cancellation {
+            extractor = extractor.with_cancellation(token);
+        }
+
+        Result::Ok(extractor)
+    }
+}
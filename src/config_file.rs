@@ -0,0 +1,136 @@
+//! Settings shared by the runs of `serde_datalog` in a project, read from a
+//! `serdedl.toml` file so that they need not be repeated on every command
+//! line:
+//!
+//! ```toml
+//! # schema of the output database: auto, string-key, or general
+//! backend = "general"
+//!
+//! # output database when -o is not given
+//! output = "facts/{stem}.db"
+//!
+//! [redact]
+//! fields = ["password", "token"]
+//! mode = "mask"
+//!
+//! # input formats of file extensions
+//! [formats]
+//! jsonl = "ndjson"
+//! conf = "ini"
+//! ```
+//!
+//! Options given on the command line take precedence over the file.
+
+use serde::Deserialize;
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{error::CliError, BackendArg, RedactMode};
+
+/// File name of the configuration file, which is looked up in the current
+/// directory and its ancestors.
+pub const FILE_NAME: &str = "serdedl.toml";
+
+/// Contents of a configuration file.
+#[derive(Deserialize, Debug, Default)]
+#[serde(deny_unknown_fields)]
+pub struct ConfigFile {
+    /// Schema of the output database, unless given with `--backend`.
+    pub backend: Option<BackendArg>,
+
+    /// Template of the output database, unless given with `--output`.
+    /// See [ConfigFile::output_for].
+    pub output: Option<String>,
+
+    /// Fields to redact, in addition to those given with `--redact`.
+    #[serde(default)]
+    pub redact: RedactConfig,
+
+    /// Names of the input formats of files with these extensions.
+    #[serde(default)]
+    pub formats: BTreeMap<String, String>,
+}
+
+/// The `[redact]` table of a configuration file.
+#[derive(Deserialize, Debug, Default)]
+#[serde(deny_unknown_fields)]
+pub struct RedactConfig {
+    /// Patterns of the names of fields to redact.
+    #[serde(default)]
+    pub fields: Vec<String>,
+
+    /// Replacements of redacted values, unless given with `--redact-mode`.
+    pub mode: Option<RedactMode>,
+
+    /// Secret prefixed to redacted values, unless given with `--redact-salt`.
+    pub salt: Option<String>,
+}
+
+impl ConfigFile {
+    /// Find the configuration file in `dir` or the nearest of its ancestors
+    /// that has one.
+    pub fn find(dir: &Path) -> Option<PathBuf> {
+        dir.ancestors()
+            .map(|dir| dir.join(FILE_NAME))
+            .find(|path| path.is_file())
+    }
+
+    /// Read the configuration file at `path`.
+    pub fn read(path: &Path) -> Result<Self, CliError> {
+        let contents = fs::read_to_string(path).map_err(|source| CliError::Io {
+            path: path.to_string_lossy().to_string(),
+            source,
+        })?;
+
+        toml::from_str(&contents).map_err(|err| CliError::Parse {
+            path: path.to_string_lossy().to_string(),
+            message: err.to_string().trim_end().to_string(),
+        })
+    }
+
+    /// The output database for the inputs `filenames` given on the command
+    /// line, from the output template, if any. In the template, `{name}`,
+    /// `{stem}`, and `{dir}` stand for the file name of the input, its file
+    /// name without extensions, and its directory; they can be used only if
+    /// a single input is given.
+    pub fn output_for(&self, filenames: &[String]) -> Result<Option<String>, CliError> {
+        let Some(template) = &self.output else {
+            return Result::Ok(None);
+        };
+
+        if !template.contains('{') {
+            return Result::Ok(Some(template.clone()));
+        }
+
+        let [filename] = filenames else {
+            return Result::Err(CliError::Usage(format!(
+                "Output template {} requires a single input file",
+                template
+            )));
+        };
+
+        let path = Path::new(filename);
+        let name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let stem = match name.split_once('.') {
+            Some((stem, _)) if !stem.is_empty() => stem.to_string(),
+            _ => name.clone(),
+        };
+        let dir = match path.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir.to_string_lossy().to_string(),
+            _ => ".".to_string(),
+        };
+
+        Result::Ok(Some(
+            template
+                .replace("{name}", &name)
+                .replace("{stem}", &stem)
+                .replace("{dir}", &dir),
+        ))
+    }
+}
@@ -0,0 +1,180 @@
+//! Recognition and normalization of date-times, which are extracted with
+//! [add_datetime][crate::DatalogExtractorBackend::add_datetime] so that
+//! time-window queries can be written in Datalog.
+//!
+//! Most data formats have no serde representation for date-times, so they
+//! reach the extractor as strings. The extractor normalizes a string element
+//! into a date-time if:
+//!
+//! - it is serialized within a newtype struct named [DATETIME_NAME], which
+//!   [DatetimeStr] does for formats that serialize their values themselves;
+//! - it is the value of a map entry with key [TOML_DATETIME_KEY], which is how
+//!   TOML date-times are deserialized by the `toml` crate;
+//! - date-time strings are enabled with
+//!   [set_datetime_strings][crate::DatalogExtractor::set_datetime_strings],
+//!   which is used for formats like YAML whose date-times are plain scalars.
+//!
+//! The string element itself is extracted as usual.
+
+use serde::{Serialize, Serializer};
+
+/// Name of the newtype struct that marks its string value as a date-time.
+/// The newtype struct is transparent to the extractor: only the string
+/// element is extracted.
+pub const DATETIME_NAME: &str = "$serde_datalog::Datetime";
+
+/// Key of the single entry of the maps into which the `toml` crate
+/// deserializes TOML date-times.
+pub const TOML_DATETIME_KEY: &str = "$__toml_private_datetime";
+
+/// A string that is serialized as a date-time, by wrapping it in a newtype
+/// struct named [DATETIME_NAME]. Serializers other than the extractor
+/// serialize it as the string.
+pub struct DatetimeStr<'a>(pub &'a str);
+
+impl Serialize for DatetimeStr<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_newtype_struct(DATETIME_NAME, self.0)
+    }
+}
+
+/// A date-time, normalized to an instant and the offset from UTC it was
+/// written with.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Datetime {
+    /// Microseconds since the Unix epoch.
+    pub epoch_micros: i64,
+
+    /// Offset from UTC in seconds, which is positive east of UTC.
+    pub tz_offset: i32,
+}
+
+impl Datetime {
+    /// Parse an RFC 3339 date-time, such as `2024-05-01T12:30:00.123+02:00`.
+    /// The `T` separating the date and time may also be a `t` or a space, and
+    /// date-times without an offset are taken to be UTC. Fractional seconds
+    /// beyond microseconds are truncated.
+    pub fn parse_rfc3339(text: &str) -> Option<Self> {
+        Self::parse_with(text, false)
+    }
+
+    /// Parse a date or date-time in the ISO 8601 forms used by data formats:
+    /// RFC 3339 date-times, dates such as `2024-05-01`, which are taken to be
+    /// midnight UTC, and YAML timestamps, whose offset may be separated from
+    /// the time by spaces and may omit its minutes, such as
+    /// `2001-12-14 21:59:43.10 -5`. Times without a date are not date-times.
+    pub fn parse(text: &str) -> Option<Self> {
+        Self::parse_with(text, true)
+    }
+
+    /// Seconds since the Unix epoch, rounded down.
+    pub fn epoch_seconds(&self) -> i64 {
+        self.epoch_micros.div_euclid(1_000_000)
+    }
+
+    fn parse_with(text: &str, lenient: bool) -> Option<Self> {
+        let (date, time) = match text.split_at_checked(10) {
+            Some((date, "")) if lenient => (date, None),
+            Some((date, time)) => (date, Some(time.strip_prefix(['T', 't', ' '])?)),
+            None => return None,
+        };
+
+        let mut date_parts = date.split('-');
+        let year = number(date_parts.next()?)?;
+        let month = number(date_parts.next()?)?;
+        let day = number(date_parts.next()?)?;
+        if date_parts.next().is_some() || !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+            return None;
+        }
+
+        let days = days_from_civil(year, month, day);
+        let Some(time) = time else {
+            return Some(Datetime {
+                epoch_micros: days * 86_400_000_000,
+                tz_offset: 0,
+            });
+        };
+
+        let (clock, rest) = time.split_at_checked(8)?;
+        let mut clock_parts = clock.split(':');
+        let hour = number(clock_parts.next()?)?;
+        let minute = number(clock_parts.next()?)?;
+        let second = number(clock_parts.next()?)?;
+        if hour > 23 || minute > 59 || second > 60 {
+            return None;
+        }
+
+        let (micros, offset) = match rest.strip_prefix('.') {
+            Some(fraction) => {
+                let digits = fraction.bytes().take_while(|b| b.is_ascii_digit()).count();
+                if digits == 0 {
+                    return None;
+                }
+
+                let micros = fraction[..digits.min(6)]
+                    .bytes()
+                    .chain(std::iter::repeat(b'0'))
+                    .take(6)
+                    .fold(0, |micros, b| micros * 10 + (b - b'0') as i64);
+                (micros, &fraction[digits..])
+            }
+            None => (0, rest),
+        };
+
+        let offset = if lenient { offset.trim_start() } else { offset };
+        let tz_offset = parse_offset(offset, lenient)?;
+
+        let seconds = days * 86400 + hour * 3600 + minute * 60 + second - tz_offset as i64;
+        Some(Datetime {
+            epoch_micros: seconds * 1_000_000 + micros,
+            tz_offset,
+        })
+    }
+}
+
+/// Parse a non-empty string of ASCII digits.
+fn number(digits: &str) -> Option<i64> {
+    if !digits.is_empty() && digits.bytes().all(|b| b.is_ascii_digit()) {
+        digits.parse().ok()
+    } else {
+        None
+    }
+}
+
+/// Parse an offset from UTC, such as `+02:00` or `Z`, into seconds. Lenient
+/// offsets may also be written as `+02`, `+2`, or `+0200`.
+fn parse_offset(offset: &str, lenient: bool) -> Option<i32> {
+    let (sign, hours_minutes) = match offset {
+        "" | "Z" | "z" => return Some(0),
+        _ => match offset.split_at_checked(1)? {
+            ("+", rest) => (1, rest),
+            ("-", rest) => (-1, rest),
+            _ => return None,
+        },
+    };
+
+    let (hours, minutes) = match hours_minutes.split_once(':') {
+        Some((hours, minutes)) => (hours, minutes),
+        None if lenient && hours_minutes.len() == 4 => hours_minutes.split_at(2),
+        None if lenient && hours_minutes.len() <= 2 => (hours_minutes, "00"),
+        None => return None,
+    };
+
+    let (hours, minutes) = (number(hours)?, number(minutes)?);
+    if hours > 23 || minutes > 59 {
+        return None;
+    }
+
+    Some(sign * (hours * 3600 + minutes * 60) as i32)
+}
+
+/// Number of days from 1970-01-01 to the given date in the proleptic
+/// Gregorian calendar.
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146097 + day_of_era - 719468
+}
@@ -0,0 +1,487 @@
+//! Differences between databases of facts generated by the Souffle SQLite
+//! backends, e.g. from two versions of the same document.
+//!
+//! Element identifiers are assigned in extraction order, so they differ
+//! between extractions even if documents only differ slightly. [diff] instead
+//! labels elements by their path from a root element or by a hash of their
+//! contents (see [MatchMode]), renders the facts of both databases with these
+//! labels and the text of interned strings, e.g. `string(/name, "serde")`,
+//! and reports the facts that were added, removed, or changed.
+
+use std::{
+    collections::{hash_map::Entry, BTreeMap, HashMap, VecDeque},
+    fmt::{self, Display},
+};
+
+use rusqlite::types::Value;
+
+use crate::{backend::souffle_sqlite::SchemaConfig, string_policy::fnv1a, Result};
+
+/// How [diff] matches the elements of two databases.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum MatchMode {
+    /// Match elements by their path from the root element of their file, as
+    /// a JSON pointer of struct fields, map keys, and positions, e.g.
+    /// `/dependencies/0/name`. If both databases have a single root element,
+    /// the roots are matched regardless of their file names; otherwise paths
+    /// are prefixed by file names, e.g. `a.json:/name`. Map keys that are
+    /// elements are labeled by the path of their entry followed by `#key`,
+    /// e.g. `/name#key`, and elements that are not reachable from a root
+    /// element are matched structurally.
+    #[default]
+    Path,
+
+    /// Match elements by a hash of their contents, labeled as `@` followed by
+    /// the hash. Equal subtrees are matched wherever they occur, so moving a
+    /// subtree only changes the facts of its old and new parent.
+    Structural,
+}
+
+/// Fact that is in both databases with different values, i.e. that only
+/// differs in its last argument.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ChangedFact {
+    pub before: String,
+    pub after: String,
+}
+
+/// Facts added, removed, and changed between two databases, as computed by
+/// [diff]. Facts are rendered as Datalog facts with elements replaced by
+/// their labels, and are sorted.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct FactDiff {
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+    pub changed: Vec<ChangedFact>,
+}
+
+impl FactDiff {
+    /// Whether the databases have the same facts.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+impl Display for FactDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_empty() {
+            return writeln!(f, "no differences found");
+        }
+
+        for fact in self.removed.iter() {
+            writeln!(f, "- {}", fact)?;
+        }
+        for fact in self.added.iter() {
+            writeln!(f, "+ {}", fact)?;
+        }
+        for fact in self.changed.iter() {
+            writeln!(f, "~ {} -> {}", fact.before, fact.after)?;
+        }
+        writeln!(
+            f,
+            "{} added, {} removed, {} changed",
+            self.added.len(),
+            self.removed.len(),
+            self.changed.len()
+        )
+    }
+}
+
+/// Compute the facts added, removed, and changed from the database of
+/// `before` to the database of `after`, whose tables are named according to
+/// `schema`, matching elements according to `mode`.
+pub fn diff(
+    before: &rusqlite::Connection,
+    after: &rusqlite::Connection,
+    schema: &SchemaConfig,
+    mode: MatchMode,
+) -> Result<FactDiff> {
+    let before = Extraction::load(before, schema)?;
+    let after = Extraction::load(after, schema)?;
+    let single_root = before.roots.len() == 1 && after.roots.len() == 1;
+
+    // facts are compared as multisets, since structural labels of equal
+    // subtrees are equal
+    let mut counts: HashMap<(String, String), isize> = HashMap::new();
+    let before_facts = before.facts(&before.labels(mode, single_root));
+    let after_facts = after.facts(&after.labels(mode, single_root));
+    for fact in before_facts.iter() {
+        *counts.entry(fact.clone()).or_default() += 1;
+    }
+    for fact in after_facts.iter() {
+        *counts.entry(fact.clone()).or_default() -= 1;
+    }
+
+    // facts removed and added for each key, i.e. all but the last argument
+    let mut by_key: BTreeMap<String, (Vec<String>, Vec<String>)> = BTreeMap::new();
+    for ((key, fact), count) in counts {
+        let (removed, added) = by_key.entry(key).or_default();
+        let facts = if count > 0 { removed } else { added };
+        facts.extend(std::iter::repeat_n(fact, count.unsigned_abs()));
+    }
+
+    let mut result = FactDiff::default();
+    for (_, (mut removed, mut added)) in by_key {
+        removed.sort();
+        added.sort();
+
+        let changed = removed.len().min(added.len());
+        let mut removed = removed.into_iter();
+        let mut added = added.into_iter();
+        result.changed.extend(
+            removed
+                .by_ref()
+                .zip(added.by_ref())
+                .take(changed)
+                .map(|(before, after)| ChangedFact { before, after }),
+        );
+        result.removed.extend(removed);
+        result.added.extend(added);
+    }
+
+    result.added.sort();
+    result.removed.sort();
+    result
+        .changed
+        .sort_by(|fact1, fact2| fact1.before.cmp(&fact2.before));
+    Result::Ok(result)
+}
+
+/// Kinds of columns of tables of relations.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum ColumnKind {
+    Elem,
+    Symbol,
+    Value,
+}
+
+struct Relation {
+    name: String,
+    columns: Vec<ColumnKind>,
+    rows: Vec<Vec<Value>>,
+}
+
+/// Facts of a database, with interned strings resolved.
+struct Extraction {
+    symbols: HashMap<i64, String>,
+    relations: Vec<Relation>,
+
+    /// Files and their root elements, sorted by file.
+    roots: Vec<(String, i64)>,
+
+    /// Facts about each element, i.e. rows whose first column is the element,
+    /// as indices of relations and rows.
+    elem_facts: HashMap<i64, Vec<(usize, usize)>>,
+}
+
+fn as_id(value: &Value) -> i64 {
+    match value {
+        Value::Integer(id) => *id,
+        _ => -1,
+    }
+}
+
+fn render_value(value: &Value) -> String {
+    match value {
+        Value::Null => "null".to_string(),
+        Value::Integer(n) => n.to_string(),
+        Value::Real(x) => x.to_string(),
+        Value::Text(text) => format!("{:?}", text),
+        Value::Blob(bytes) => bytes.iter().map(|byte| format!("{:02x}", byte)).collect(),
+    }
+}
+
+/// Append `step` to JSON pointer `path`, escaping `~` and `/`.
+fn join_path(path: &str, step: &str) -> String {
+    let step = step.replace('~', "~0").replace('/', "~1");
+    if path.ends_with('/') {
+        format!("{}{}", path, step)
+    } else {
+        format!("{}/{}", path, step)
+    }
+}
+
+impl Extraction {
+    fn load(conn: &rusqlite::Connection, schema: &SchemaConfig) -> Result<Self> {
+        let symbol_table = schema.symbol_table_name();
+        let type_table = schema.table_name("type");
+        let root_table = schema.table_name("rootElem");
+
+        let symbols: HashMap<i64, String> = conn
+            .prepare(&format!("SELECT id, symbol FROM {};", symbol_table))?
+            .query_map((), |row| rusqlite::Result::Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<rusqlite::Result<_>>()?;
+
+        let mut tables: Vec<String> = conn
+            .prepare("SELECT name FROM sqlite_master WHERE type = 'table' ORDER BY name;")?
+            .query_map((), |row| row.get(0))?
+            .collect::<rusqlite::Result<_>>()?;
+        let shared_prefix = schema.table_name("_");
+        tables.retain(|table| {
+            table.starts_with(&schema.table_prefix) && !table.starts_with(&shared_prefix)
+        });
+
+        let mut relations = Vec::new();
+        for table in tables.iter() {
+            let columns: Vec<String> = conn
+                .prepare(&format!(
+                    "SELECT name FROM pragma_table_info('{}') ORDER BY cid;",
+                    table
+                ))?
+                .query_map((), |row| row.get(0))?
+                .collect::<rusqlite::Result<_>>()?;
+
+            let foreign_keys: HashMap<String, String> = conn
+                .prepare(&format!(
+                    "SELECT \"from\", \"table\" FROM pragma_foreign_key_list('{}');",
+                    table
+                ))?
+                .query_map((), |row| rusqlite::Result::Ok((row.get(0)?, row.get(1)?)))?
+                .collect::<rusqlite::Result<_>>()?;
+
+            // element types and root elements are not declared as foreign keys
+            let kinds = columns
+                .iter()
+                .map(|column| match foreign_keys.get(column) {
+                    Some(target) if *target == symbol_table => ColumnKind::Symbol,
+                    Some(target) if *target == type_table => ColumnKind::Elem,
+                    _ if *table == type_table && column == "id" => ColumnKind::Elem,
+                    _ if *table == type_table && column == "type" => ColumnKind::Symbol,
+                    _ if *table == root_table && column == "elem" => ColumnKind::Elem,
+                    _ if *table == root_table && column == "file" => ColumnKind::Symbol,
+                    _ => ColumnKind::Value,
+                })
+                .collect();
+
+            let column_list: Vec<String> = columns
+                .iter()
+                .map(|column| format!("\"{}\"", column))
+                .collect();
+            let rows: Vec<Vec<Value>> = conn
+                .prepare(&format!(
+                    "SELECT {} FROM \"{}\";",
+                    column_list.join(", "),
+                    table
+                ))?
+                .query_map((), |row| {
+                    (0..columns.len())
+                        .map(|i| row.get(i))
+                        .collect::<rusqlite::Result<_>>()
+                })?
+                .collect::<rusqlite::Result<_>>()?;
+
+            let name = table.strip_prefix(&schema.table_prefix).unwrap_or(table);
+            relations.push(Relation {
+                name: name.to_string(),
+                columns: kinds,
+                rows,
+            });
+        }
+
+        let mut roots = Vec::new();
+        let mut elem_facts: HashMap<i64, Vec<(usize, usize)>> = HashMap::new();
+        for (r, relation) in relations.iter().enumerate() {
+            for (i, row) in relation.rows.iter().enumerate() {
+                if relation.columns.first() == Some(&ColumnKind::Elem) {
+                    elem_facts.entry(as_id(&row[0])).or_default().push((r, i));
+                }
+
+                if relation.name == "rootElem" {
+                    let file = relation
+                        .columns
+                        .iter()
+                        .position(|kind| *kind == ColumnKind::Symbol);
+                    let elem = relation
+                        .columns
+                        .iter()
+                        .position(|kind| *kind == ColumnKind::Elem);
+                    if let (Some(file), Some(elem)) = (file, elem) {
+                        let file = symbols.get(&as_id(&row[file])).cloned().unwrap_or_default();
+                        roots.push((file, as_id(&row[elem])));
+                    }
+                }
+            }
+        }
+        roots.sort();
+
+        Result::Ok(Extraction {
+            symbols,
+            relations,
+            roots,
+            elem_facts,
+        })
+    }
+
+    fn render_symbol(&self, value: &Value) -> String {
+        let id = as_id(value);
+        match self.symbols.get(&id) {
+            Some(symbol) => format!("{:?}", symbol),
+            None => format!("#{}", id),
+        }
+    }
+
+    /// Hash of the facts about `elem`, with elements replaced by their hashes.
+    fn hash(&self, elem: i64, hashes: &mut HashMap<i64, u64>) -> u64 {
+        if let Some(hash) = hashes.get(&elem) {
+            return *hash;
+        }
+
+        // guard against cycles in invalid databases
+        hashes.insert(elem, 0);
+
+        let mut facts = Vec::new();
+        for (r, i) in self.elem_facts.get(&elem).into_iter().flatten() {
+            let relation = &self.relations[*r];
+            let row = &relation.rows[*i];
+            let mut args = vec![relation.name.clone()];
+            for (kind, value) in relation.columns.iter().zip(row.iter()).skip(1) {
+                args.push(match kind {
+                    ColumnKind::Elem => format!("@{:016x}", self.hash(as_id(value), hashes)),
+                    ColumnKind::Symbol => self.render_symbol(value),
+                    ColumnKind::Value => render_value(value),
+                });
+            }
+            facts.push(args.join(", "));
+        }
+        facts.sort();
+
+        let hash = fnv1a(facts.join("\n").as_bytes());
+        hashes.insert(elem, hash);
+        hash
+    }
+
+    /// Path step of a map key element, i.e. its value if it is a string,
+    /// number, or boolean, and its hash otherwise.
+    fn key_step(&self, elem: i64, hashes: &HashMap<i64, u64>) -> String {
+        for (r, i) in self.elem_facts.get(&elem).into_iter().flatten() {
+            let relation = &self.relations[*r];
+            let row = &relation.rows[*i];
+            if matches!(relation.name.as_str(), "string" | "number" | "bool") && row.len() == 2 {
+                return match (relation.columns[1], &row[1]) {
+                    (ColumnKind::Symbol, value) => {
+                        self.symbols.get(&as_id(value)).cloned().unwrap_or_default()
+                    }
+                    (_, Value::Text(text)) => text.clone(),
+                    (_, value) => render_value(value),
+                };
+            }
+        }
+        format!("@{:016x}", hashes.get(&elem).copied().unwrap_or_default())
+    }
+
+    /// Labels of elements according to `mode`.
+    fn labels(&self, mode: MatchMode, single_root: bool) -> HashMap<i64, String> {
+        let mut hashes = HashMap::new();
+        let mut elems: Vec<i64> = self.elem_facts.keys().copied().collect();
+        elems.sort();
+        for elem in elems.iter() {
+            self.hash(*elem, &mut hashes);
+        }
+
+        let mut labels = HashMap::new();
+        if mode == MatchMode::Path {
+            let mut queue = VecDeque::new();
+            for (file, elem) in self.roots.iter() {
+                let label = if single_root {
+                    "/".to_string()
+                } else {
+                    format!("{}:/", file)
+                };
+                if let Entry::Vacant(entry) = labels.entry(*elem) {
+                    entry.insert(label);
+                    queue.push_back(*elem);
+                }
+            }
+
+            // entries are facts whose first argument is the element, whose
+            // last argument is the value, and whose arguments in between,
+            // such as fields, keys, or positions, are path steps
+            while let Some(elem) = queue.pop_front() {
+                let path = labels[&elem].clone();
+                for (r, i) in self.elem_facts.get(&elem).into_iter().flatten() {
+                    let relation = &self.relations[*r];
+                    let row = &relation.rows[*i];
+                    if row.len() < 3 || relation.columns.last() != Some(&ColumnKind::Elem) {
+                        continue;
+                    }
+
+                    let child = as_id(&row[row.len() - 1]);
+                    if labels.contains_key(&child) {
+                        continue;
+                    }
+
+                    let mut child_path = path.clone();
+                    let mut key_elems = Vec::new();
+                    let steps = relation.columns.iter().zip(row.iter());
+                    for (kind, value) in steps.take(row.len() - 1).skip(1) {
+                        let step = match (kind, value) {
+                            (ColumnKind::Elem, value) => {
+                                key_elems.push(as_id(value));
+                                self.key_step(as_id(value), &hashes)
+                            }
+                            (ColumnKind::Symbol, value) => {
+                                self.symbols.get(&as_id(value)).cloned().unwrap_or_default()
+                            }
+                            (ColumnKind::Value, Value::Text(text)) => text.clone(),
+                            (ColumnKind::Value, value) => render_value(value),
+                        };
+                        child_path = join_path(&child_path, &step);
+                    }
+
+                    // map keys are labeled by the path of their entry
+                    for key_elem in key_elems {
+                        if let Entry::Vacant(entry) = labels.entry(key_elem) {
+                            entry.insert(format!("{}#key", child_path));
+                            queue.push_back(key_elem);
+                        }
+                    }
+
+                    labels.insert(child, child_path);
+                    queue.push_back(child);
+                }
+            }
+        }
+
+        for (elem, hash) in hashes {
+            labels
+                .entry(elem)
+                .or_insert_with(|| format!("@{:016x}", hash));
+        }
+        labels
+    }
+
+    /// Facts of all relations rendered with `labels`, with their keys, i.e.
+    /// the facts without their last argument.
+    fn facts(&self, labels: &HashMap<i64, String>) -> Vec<(String, String)> {
+        let mut facts = Vec::new();
+        for relation in self.relations.iter() {
+            for row in relation.rows.iter() {
+                let args: Vec<String> = relation
+                    .columns
+                    .iter()
+                    .zip(row.iter())
+                    .map(|(kind, value)| match kind {
+                        ColumnKind::Elem => {
+                            let id = as_id(value);
+                            labels
+                                .get(&id)
+                                .cloned()
+                                .unwrap_or_else(|| format!("#{}", id))
+                        }
+                        ColumnKind::Symbol => self.render_symbol(value),
+                        ColumnKind::Value => render_value(value),
+                    })
+                    .collect();
+
+                let key = format!(
+                    "{}({}",
+                    relation.name,
+                    args[..args.len().saturating_sub(1)].join(", ")
+                );
+                let fact = format!("{}({}).", relation.name, args.join(", "));
+                facts.push((key, fact));
+            }
+        }
+        facts
+    }
+}
@@ -0,0 +1,184 @@
+use serde_datalog::{backend::BackendError, DatalogExtractionError, ErrorContext};
+use std::{fmt, io, process::ExitCode};
+
+/// Errors reported by the `serde_datalog` command-line tool.
+#[derive(Debug)]
+pub enum CliError {
+    /// Invalid command-line arguments, e.g. an unknown input format.
+    Usage(String),
+
+    /// An input file could not be read.
+    Io { path: String, source: io::Error },
+
+    /// An input file could not be parsed in its input format.
+    Parse { path: String, message: String },
+
+    /// Facts could not be extracted from an input file.
+    Extraction {
+        path: String,
+        source: DatalogExtractionError,
+    },
+
+    /// A database could not be written or queried.
+    Database { path: String, source: BackendError },
+
+    /// Some input files could not be processed when running with `--keep-going`.
+    PartialFailure { failed: usize, total: usize },
+
+    /// A database violates integrity constraints checked by `validate`.
+    Invalid { path: String, violations: usize },
+}
+
+impl CliError {
+    /// Create an error from an extraction error for input file or database
+    /// `path`. Errors raised by the input format's deserializer surface as
+    /// [DatalogExtractionError::Custom] errors, and are reported as parse
+    /// errors; their messages include the position of the error when the
+    /// input format provides it. Backend errors are reported as database errors.
+    ///
+    /// Parse and extraction errors keep the path to the value whose
+    /// extraction failed, if the extractor attached it; backend errors
+    /// concern the database rather than the input, so they drop it.
+    pub fn from_extraction(path: &str, err: DatalogExtractionError) -> Self {
+        match err {
+            DatalogExtractionError::InValue(context) => {
+                let ErrorContext {
+                    path: value_path,
+                    facts,
+                    error,
+                } = *context;
+
+                match Self::from_extraction(path, error) {
+                    CliError::Parse { path, message } => CliError::Parse {
+                        path,
+                        message: format!("{} at {} after {} fact(s)", message, value_path, facts),
+                    },
+
+                    CliError::Extraction { path, source } => CliError::Extraction {
+                        path,
+                        source: DatalogExtractionError::InValue(Box::new(ErrorContext {
+                            path: value_path,
+                            facts,
+                            error: source,
+                        })),
+                    },
+
+                    err => err,
+                }
+            }
+
+            DatalogExtractionError::Backend(source) => CliError::Database {
+                path: path.to_string(),
+                source,
+            },
+
+            DatalogExtractionError::Custom(message) => CliError::Parse {
+                path: path.to_string(),
+                message,
+            },
+
+            source => CliError::Extraction {
+                path: path.to_string(),
+                source,
+            },
+        }
+    }
+
+    /// The message of this error if it is about an invalid document, which
+    /// can be skipped with `--skip-invalid`: a parse error, or an extraction
+    /// error other than a cancellation.
+    pub fn invalid_document_message(&self) -> Option<String> {
+        match self {
+            CliError::Parse { message, .. } => Some(message.clone()),
+            CliError::Extraction { source, .. } => {
+                match source.context().map_or(source, |context| &context.error) {
+                    DatalogExtractionError::Cancelled => None,
+                    _ => Some(source.to_string()),
+                }
+            }
+            _ => None,
+        }
+    }
+
+    /// The exit status of the tool when it fails with this error.
+    pub fn exit_status(&self) -> u8 {
+        match self {
+            CliError::Usage(_) => 2,
+            CliError::Io { .. } | CliError::Parse { .. } | CliError::Extraction { .. } => 3,
+            CliError::Database { .. } => 4,
+            CliError::PartialFailure { .. } => 5,
+            CliError::Invalid { .. } => 6,
+        }
+    }
+
+    /// The exit code of the tool when it fails with this error.
+    pub fn exit_code(&self) -> ExitCode {
+        ExitCode::from(self.exit_status())
+    }
+
+    /// The kind of this error, as reported with `--json`.
+    fn kind(&self) -> &'static str {
+        match self {
+            CliError::Usage(_) => "usage",
+            CliError::Io { .. } => "io",
+            CliError::Parse { .. } => "parse",
+            CliError::Extraction { .. } => "extraction",
+            CliError::Database { .. } => "database",
+            CliError::PartialFailure { .. } => "partialFailure",
+            CliError::Invalid { .. } => "invalid",
+        }
+    }
+
+    /// This error as a JSON object, as printed to standard error with
+    /// `--json`: its kind, message, exit status, and the file it concerns,
+    /// if any.
+    pub fn to_json(&self) -> serde_json::Value {
+        let path = match self {
+            CliError::Io { path, .. }
+            | CliError::Parse { path, .. }
+            | CliError::Extraction { path, .. }
+            | CliError::Database { path, .. }
+            | CliError::Invalid { path, .. } => Some(path),
+            CliError::Usage(_) | CliError::PartialFailure { .. } => None,
+        };
+
+        serde_json::json!({
+            "error": self.kind(),
+            "message": self.to_string(),
+            "path": path,
+            "exitStatus": self.exit_status(),
+        })
+    }
+}
+
+impl fmt::Display for CliError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CliError::Usage(msg) => write!(f, "{}", msg),
+
+            CliError::Io { path, source } => write!(f, "{}: {}", path, source),
+
+            CliError::Parse { path, message } => {
+                write!(f, "{}: parse error: {}", path, message)
+            }
+
+            CliError::Extraction { path, source } => {
+                write!(f, "{}: extraction error: {}", path, source)
+            }
+
+            CliError::Database { path, source } => {
+                write!(f, "{}: database error: {}", path, source)
+            }
+
+            CliError::PartialFailure { failed, total } => {
+                write!(f, "{} of {} input(s) could not be processed", failed, total)
+            }
+
+            CliError::Invalid { path, violations } => {
+                write!(f, "{}: {} integrity violation(s)", path, violations)
+            }
+        }
+    }
+}
+
+impl std::error::Error for CliError {}
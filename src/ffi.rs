@@ -0,0 +1,238 @@
+//! A C ABI for embedding the extractor in non-Rust programs, which extract
+//! facts from JSON documents and store them in SQLite databases with the same
+//! schema as the [Souffle SQLite backends][crate::backend::souffle_sqlite].
+//! The header `include/serde_datalog.h` declares these functions; it is
+//! generated with [cbindgen](https://github.com/mozilla/cbindgen):
+//!
+//! ```text
+//! cbindgen --config cbindgen.toml --output include/serde_datalog.h
+//! ```
+//!
+//! An extractor is created with [serde_datalog_extractor_new] and must be
+//! released with [serde_datalog_extractor_free]. Functions that can fail
+//! return 0 on success and -1 on failure, after which
+//! [serde_datalog_last_error] describes the failure. Panics are failures
+//! too; they never unwind into the calling program:
+//!
+//! ```c
+//! SerdeDatalogExtractor *extractor = serde_datalog_extractor_new(true);
+//! if (serde_datalog_extract_json(extractor, "data.json", "{\"name\": \"abc\"}") != 0 ||
+//!     serde_datalog_dump_to_sqlite(extractor, "facts.db") != 0) {
+//!     fprintf(stderr, "%s\n", serde_datalog_last_error(extractor));
+//! }
+//! serde_datalog_extractor_free(extractor);
+//! ```
+
+use serde::Serialize;
+use std::{
+    ffi::{c_char, c_int, CStr, CString},
+    panic::{self, AssertUnwindSafe},
+    ptr,
+};
+
+use crate::{
+    backend::{
+        record,
+        souffle_sqlite::{self, AbstractBackend},
+    },
+    DatalogExtractionError, DatalogExtractor, DatalogExtractorBackend, Result,
+};
+
+/// An extractor whose facts have not yet been stored in a database.
+pub struct SerdeDatalogExtractor {
+    /// Extractor recording the facts, so that they are kept if storing them
+    /// fails. `None` once facts are stored.
+    extractor: Option<DatalogExtractor<record::Backend>>,
+
+    /// Whether map keys are stored as strings.
+    string_keys: bool,
+
+    /// Message of the last failure.
+    error: Option<CString>,
+}
+
+impl SerdeDatalogExtractor {
+    fn extract_json(&mut self, file: &str, json: &str) -> Result<()> {
+        let value: serde_json::Value = serde_json::from_str(json)
+            .map_err(|err| DatalogExtractionError::Custom(format!("invalid JSON: {}", err)))?;
+
+        match self.extractor.as_mut() {
+            Some(extractor) => extract(extractor, file, &value),
+            None => Result::Err(dumped()),
+        }
+    }
+
+    fn dump_to_sqlite(&mut self, filename: &str) -> Result<()> {
+        let Some(extractor) = &self.extractor else {
+            return Result::Err(dumped());
+        };
+
+        let calls = extractor.backend().calls();
+        if self.string_keys {
+            dump(souffle_sqlite::StringKeyBackend::default(), calls, filename)?;
+        } else {
+            dump(souffle_sqlite::Backend::default(), calls, filename)?;
+        }
+
+        self.extractor = None;
+        Result::Ok(())
+    }
+
+    /// Run `f` with this extractor, recording its failure, or a panic, which
+    /// must not unwind into C, and convert it to a return code.
+    fn run(&mut self, f: impl FnOnce(&mut Self) -> Result<()>) -> c_int {
+        let res = panic::catch_unwind(AssertUnwindSafe(|| f(self))).unwrap_or_else(|panic| {
+            let message = panic
+                .downcast_ref::<&str>()
+                .map(|message| message.to_string())
+                .or_else(|| panic.downcast_ref::<String>().cloned())
+                .unwrap_or_default();
+            Result::Err(DatalogExtractionError::Custom(format!(
+                "panicked: {}",
+                message
+            )))
+        });
+
+        match res {
+            Result::Ok(()) => 0,
+            Result::Err(err) => {
+                self.error = CString::new(err.to_string().replace('\0', " ")).ok();
+                -1
+            }
+        }
+    }
+}
+
+fn extract<B: DatalogExtractorBackend>(
+    extractor: &mut DatalogExtractor<B>,
+    file: &str,
+    value: &serde_json::Value,
+) -> Result<()> {
+    extractor.set_file(file)?;
+    extractor.set_file_format(file, "json")?;
+    value.serialize(&mut *extractor)?;
+    extractor.flush()
+}
+
+/// Store the facts of `calls` with `backend` in a SQLite file with name
+/// `filename`.
+fn dump<B: AbstractBackend>(mut backend: B, calls: &[record::Call], filename: &str) -> Result<()> {
+    record::replay(calls, &mut backend)?;
+    backend.dump_to_db(filename)
+}
+
+fn dumped() -> DatalogExtractionError {
+    DatalogExtractionError::Custom("facts of extractor were already stored".to_string())
+}
+
+/// Read the C string `s` as UTF-8.
+///
+/// # Safety
+///
+/// `s` must be null or point to a NUL-terminated string.
+unsafe fn utf8<'a>(s: *const c_char, what: &str) -> Result<&'a str> {
+    if s.is_null() {
+        return Result::Err(DatalogExtractionError::Custom(format!("{} is null", what)));
+    }
+
+    CStr::from_ptr(s)
+        .to_str()
+        .map_err(|_| DatalogExtractionError::Custom(format!("{} is not valid UTF-8", what)))
+}
+
+/// Create an extractor that stores facts in a Souffle SQLite database. If
+/// `string_keys` is set, map keys are stored as strings, as in
+/// [souffle_sqlite::StringKeyBackend]; otherwise map keys are elements, as in
+/// [souffle_sqlite::Backend].
+#[no_mangle]
+pub extern "C" fn serde_datalog_extractor_new(string_keys: bool) -> *mut SerdeDatalogExtractor {
+    panic::catch_unwind(|| {
+        Box::into_raw(Box::new(SerdeDatalogExtractor {
+            extractor: Some(DatalogExtractor::new(record::Backend::default())),
+            string_keys,
+            error: None,
+        }))
+    })
+    .unwrap_or(ptr::null_mut())
+}
+
+/// Release `extractor`, discarding facts that were not stored.
+///
+/// # Safety
+///
+/// `extractor` must be null or have been returned by
+/// [serde_datalog_extractor_new], and not released before.
+#[no_mangle]
+pub unsafe extern "C" fn serde_datalog_extractor_free(extractor: *mut SerdeDatalogExtractor) {
+    if !extractor.is_null() {
+        let _ = panic::catch_unwind(AssertUnwindSafe(|| drop(Box::from_raw(extractor))));
+    }
+}
+
+/// Extract facts from the NUL-terminated JSON document `json`, read from the
+/// file named `file`. Returns 0 on success and -1 on failure.
+///
+/// # Safety
+///
+/// `extractor` must have been returned by [serde_datalog_extractor_new], and
+/// `file` and `json` must be NUL-terminated strings.
+#[no_mangle]
+pub unsafe extern "C" fn serde_datalog_extract_json(
+    extractor: *mut SerdeDatalogExtractor,
+    file: *const c_char,
+    json: *const c_char,
+) -> c_int {
+    let Some(extractor) = extractor.as_mut() else {
+        return -1;
+    };
+
+    extractor.run(|extractor| {
+        let file = utf8(file, "file name")?;
+        let json = utf8(json, "JSON document")?;
+        extractor.extract_json(file, json)
+    })
+}
+
+/// Store the facts of `extractor` in a SQLite file with name `filename`,
+/// which must not already contain the tables of the schema. Facts can only
+/// be stored once; if storing them fails, they are kept and can be stored
+/// again. Returns 0 on success and -1 on failure.
+///
+/// # Safety
+///
+/// `extractor` must have been returned by [serde_datalog_extractor_new], and
+/// `filename` must be a NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn serde_datalog_dump_to_sqlite(
+    extractor: *mut SerdeDatalogExtractor,
+    filename: *const c_char,
+) -> c_int {
+    let Some(extractor) = extractor.as_mut() else {
+        return -1;
+    };
+
+    extractor.run(|extractor| extractor.dump_to_sqlite(utf8(filename, "database file name")?))
+}
+
+/// Message describing the last failure of a function called with
+/// `extractor`, or null if no function failed. The message is owned by
+/// `extractor` and valid until the next call with it.
+///
+/// # Safety
+///
+/// `extractor` must have been returned by [serde_datalog_extractor_new].
+#[no_mangle]
+pub unsafe extern "C" fn serde_datalog_last_error(
+    extractor: *const SerdeDatalogExtractor,
+) -> *const c_char {
+    panic::catch_unwind(AssertUnwindSafe(|| {
+        match extractor
+            .as_ref()
+            .and_then(|extractor| extractor.error.as_ref())
+        {
+            Some(error) => error.as_ptr(),
+            None => ptr::null(),
+        }
+    }))
+    .unwrap_or(ptr::null())
+}
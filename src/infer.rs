@@ -0,0 +1,439 @@
+//! Inference of the structure of extracted facts.
+//!
+//! [infer_schema] summarizes a database of facts generated by the Souffle
+//! SQLite backends as records: struct types, and maps found at the same path
+//! from a root element, e.g. the maps at `/dependencies/*` of a JSON file.
+//! For each record, it reports which fields appear, how often, and the types
+//! of their values, so that users exploring unfamiliar data know which facts
+//! to expect before writing rules. Value types are described as follows:
+//!
+//! - element types of scalars, i.e. `Bool`, `Number`, `Str`, or `Unit`;
+//! - names of struct types, unit structs, and variants, e.g. `Option::None`;
+//! - paths of maps, e.g. `/dependencies/*`, where `*` stands for any
+//!   position of a sequence;
+//! - `[T | U]` for sequences whose elements have types `T` or `U`;
+//! - `(T, U)` for tuples, prefixed by the name of tuple structs and variants.
+
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
+    fmt::Write,
+};
+
+use crate::{
+    backend::souffle_sqlite::{db_has_string_keys, SchemaConfig},
+    Result,
+};
+
+/// Kinds of records of an [InferredSchema].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum RecordKind {
+    /// Struct or struct variant, named by its type.
+    Struct,
+
+    /// Map, named by its path from a root element.
+    Map,
+}
+
+/// Field of a record of an [InferredSchema].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FieldSchema {
+    pub name: String,
+
+    /// Number of elements of the record that have the field.
+    pub count: usize,
+
+    /// Whether some elements of the record do not have the field.
+    pub optional: bool,
+
+    /// Types of the values of the field, sorted.
+    pub types: Vec<String>,
+}
+
+/// Struct type, or maps at a path, of an [InferredSchema].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RecordSchema {
+    pub name: String,
+    pub kind: RecordKind,
+
+    /// Number of elements of the record.
+    pub count: usize,
+
+    /// Fields of the record, sorted by name.
+    pub fields: Vec<FieldSchema>,
+}
+
+/// Structure of the facts of a database, as inferred by [infer_schema].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct InferredSchema {
+    /// Types of root elements, sorted.
+    pub roots: Vec<String>,
+
+    /// Records, sorted by name.
+    pub records: Vec<RecordSchema>,
+}
+
+#[derive(Default)]
+struct RecordData {
+    count: usize,
+    fields: BTreeMap<String, (usize, BTreeSet<String>)>,
+}
+
+/// Elements of a database and their entries.
+struct Elems {
+    types: HashMap<i64, String>,
+    names: HashMap<i64, String>,
+    entries: HashMap<i64, Vec<(String, i64)>>,
+}
+
+impl Elems {
+    /// Describe the type of `elem` at `path`, and record the fields of `elem`
+    /// and its descendants in `records`.
+    fn describe(
+        &self,
+        elem: i64,
+        path: &str,
+        records: &mut BTreeMap<(String, bool), RecordData>,
+        visited: &mut HashSet<i64>,
+    ) -> String {
+        let ty = self.types.get(&elem).map(String::as_str).unwrap_or("?");
+        let name = self.names.get(&elem).cloned().unwrap_or_default();
+
+        // guard against cycles in invalid databases
+        if !visited.insert(elem) {
+            return ty.to_string();
+        }
+
+        let entries = self.entries.get(&elem).map(Vec::as_slice).unwrap_or(&[]);
+        match ty {
+            "Map" | "Struct" | "StructVariant" => {
+                let is_map = ty == "Map";
+                let record = if is_map { path.to_string() } else { name };
+
+                let mut fields = Vec::new();
+                for (key, child) in entries.iter() {
+                    let child_path = join_path(path, key);
+                    fields.push((key, self.describe(*child, &child_path, records, visited)));
+                }
+
+                let data = records.entry((record.clone(), is_map)).or_default();
+                data.count += 1;
+                for (key, child_type) in fields {
+                    let (count, types) = data.fields.entry(key.clone()).or_default();
+                    *count += 1;
+                    types.insert(child_type);
+                }
+                record
+            }
+
+            "Seq" => {
+                let child_path = join_path(path, "*");
+                let types: BTreeSet<String> = entries
+                    .iter()
+                    .map(|(_, child)| self.describe(*child, &child_path, records, visited))
+                    .collect();
+                format!("[{}]", types.into_iter().collect::<Vec<_>>().join(" | "))
+            }
+
+            "Tuple" | "TupleStruct" | "TupleVariant" => {
+                let types: Vec<String> = entries
+                    .iter()
+                    .map(|(pos, child)| {
+                        self.describe(*child, &join_path(path, pos), records, visited)
+                    })
+                    .collect();
+                format!("{}({})", name, types.join(", "))
+            }
+
+            "UnitStruct" | "UnitVariant" => name,
+
+            _ => ty.to_string(),
+        }
+    }
+}
+
+/// Append `step` to the path `path`, escaping `~` and `/` as in JSON pointers.
+fn join_path(path: &str, step: &str) -> String {
+    let step = step.replace('~', "~0").replace('/', "~1");
+    if path.ends_with('/') {
+        format!("{}{}", path, step)
+    } else {
+        format!("{}/{}", path, step)
+    }
+}
+
+/// Infer the structure of the facts of the database of `conn`, whose tables
+/// are named according to `schema`, from its root elements.
+pub fn infer_schema(conn: &rusqlite::Connection, schema: &SchemaConfig) -> Result<InferredSchema> {
+    let symbol_table = schema.symbol_table_name();
+    let string_keys = db_has_string_keys(conn, schema)?;
+
+    let query_pairs = |sql: String| -> rusqlite::Result<Vec<(i64, String)>> {
+        conn.prepare(&sql)?
+            .query_map((), |row| rusqlite::Result::Ok((row.get(0)?, row.get(1)?)))?
+            .collect()
+    };
+    let query_entries = |sql: String| -> rusqlite::Result<Vec<(i64, String, i64)>> {
+        conn.prepare(&sql)?
+            .query_map((), |row| {
+                rusqlite::Result::Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+            })?
+            .collect()
+    };
+
+    let symbol_of = |relation: &str, column: &str| {
+        format!(
+            "SELECT r.id, s.symbol FROM {table} AS r
+            INNER JOIN {symbol_table} AS s ON r.{column} = s.id;",
+            table = schema.table_name(relation),
+        )
+    };
+
+    let types: HashMap<i64, String> = query_pairs(symbol_of("type", "type"))?
+        .into_iter()
+        .collect();
+
+    let mut names: HashMap<i64, String> = query_pairs(symbol_of("structType", "type"))?
+        .into_iter()
+        .collect();
+    names.extend(query_pairs(format!(
+        "SELECT r.id, s1.symbol || '::' || s2.symbol FROM {table} AS r
+        INNER JOIN {symbol_table} AS s1 ON r.type = s1.id
+        INNER JOIN {symbol_table} AS s2 ON r.variant = s2.id;",
+        table = schema.table_name("variantType"),
+    ))?);
+
+    // map keys are strings, or elements whose strings are used as keys
+    let map_keys = if string_keys {
+        format!(
+            "SELECT r.id, s.symbol, r.value FROM {map} AS r
+            INNER JOIN {symbol_table} AS s ON r.key = s.id;",
+            map = schema.table_name("map"),
+        )
+    } else {
+        format!(
+            "SELECT r.id, COALESCE(s.symbol, CAST(n.value AS TEXT), '#' || r.key), r.value
+            FROM {map} AS r
+            LEFT JOIN {string} AS k ON r.key = k.id
+            LEFT JOIN {symbol_table} AS s ON k.value = s.id
+            LEFT JOIN {number} AS n ON r.key = n.id;",
+            map = schema.table_name("map"),
+            string = schema.table_name("string"),
+            number = schema.table_name("number"),
+        )
+    };
+
+    let mut entries: HashMap<i64, Vec<(String, i64)>> = HashMap::new();
+    for sql in [
+        format!(
+            "SELECT r.id, s.symbol, r.value FROM {table} AS r
+            INNER JOIN {symbol_table} AS s ON r.field = s.id;",
+            table = schema.table_name("struct"),
+        ),
+        map_keys,
+        format!(
+            "SELECT id, CAST(pos AS TEXT), value FROM {} ORDER BY id, pos;",
+            schema.table_name("seq")
+        ),
+        format!(
+            "SELECT id, CAST(pos AS TEXT), value FROM {} ORDER BY id, pos;",
+            schema.table_name("tuple")
+        ),
+    ] {
+        for (elem, key, value) in query_entries(sql)? {
+            entries.entry(elem).or_default().push((key, value));
+        }
+    }
+
+    let roots: Vec<i64> = conn
+        .prepare(&format!(
+            "SELECT elem FROM {} ORDER BY file;",
+            schema.table_name("rootElem")
+        ))?
+        .query_map((), |row| row.get(0))?
+        .collect::<rusqlite::Result<_>>()?;
+
+    let elems = Elems {
+        types,
+        names,
+        entries,
+    };
+
+    let mut records = BTreeMap::new();
+    let mut visited = HashSet::new();
+    let root_types: BTreeSet<String> = roots
+        .iter()
+        .map(|root| elems.describe(*root, "/", &mut records, &mut visited))
+        .collect();
+
+    let records = records
+        .into_iter()
+        .map(|((name, is_map), data)| RecordSchema {
+            name,
+            kind: if is_map {
+                RecordKind::Map
+            } else {
+                RecordKind::Struct
+            },
+            count: data.count,
+            fields: data
+                .fields
+                .into_iter()
+                .map(|(name, (count, types))| FieldSchema {
+                    name,
+                    count,
+                    optional: count < data.count,
+                    types: types.into_iter().collect(),
+                })
+                .collect(),
+        })
+        .collect();
+
+    Result::Ok(InferredSchema {
+        roots: root_types.into_iter().collect(),
+        records,
+    })
+}
+
+fn json_string(s: &str) -> String {
+    let mut out = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\t' => out.push_str("\\t"),
+            c if c.is_control() => {
+                let _ = write!(out, "\\u{:04x}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+fn json_strings(strings: &[String]) -> String {
+    let strings: Vec<String> = strings.iter().map(|s| json_string(s)).collect();
+    format!("[{}]", strings.join(", "))
+}
+
+/// Identifier for Souffle programs derived from `name`, e.g. `Root_deps_item`
+/// for the path `/deps/*`.
+fn souffle_ident(name: &str) -> String {
+    let name = if name.starts_with('/') {
+        let steps = name.split('/').filter(|step| !step.is_empty()).map(|step| {
+            if step == "*" {
+                "item"
+            } else {
+                step
+            }
+        });
+        std::iter::once("Root")
+            .chain(steps)
+            .collect::<Vec<_>>()
+            .join("_")
+    } else {
+        name.to_string()
+    };
+
+    let mut ident: String = name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    if !ident.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_') {
+        ident.insert(0, '_');
+    }
+    ident
+}
+
+impl InferredSchema {
+    /// Render the schema as JSON.
+    pub fn to_json(&self) -> String {
+        let mut out = String::new();
+        let _ = writeln!(out, "{{");
+        let _ = writeln!(out, "  \"roots\": {},", json_strings(&self.roots));
+        let _ = writeln!(out, "  \"records\": [");
+        for (i, record) in self.records.iter().enumerate() {
+            let kind = match record.kind {
+                RecordKind::Struct => "struct",
+                RecordKind::Map => "map",
+            };
+            let _ = writeln!(out, "    {{");
+            let _ = writeln!(out, "      \"name\": {},", json_string(&record.name));
+            let _ = writeln!(out, "      \"kind\": \"{}\",", kind);
+            let _ = writeln!(out, "      \"count\": {},", record.count);
+            let _ = writeln!(out, "      \"fields\": [");
+            for (j, field) in record.fields.iter().enumerate() {
+                let _ = writeln!(
+                    out,
+                    "        {{\"name\": {}, \"count\": {}, \"optional\": {}, \"types\": {}}}{}",
+                    json_string(&field.name),
+                    field.count,
+                    field.optional,
+                    json_strings(&field.types),
+                    if j + 1 < record.fields.len() { "," } else { "" }
+                );
+            }
+            let _ = writeln!(out, "      ]");
+            let _ = writeln!(
+                out,
+                "    }}{}",
+                if i + 1 < self.records.len() { "," } else { "" }
+            );
+        }
+        let _ = writeln!(out, "  ]");
+        let _ = writeln!(out, "}}");
+        out
+    }
+
+    /// Render the schema as Souffle record type declarations, with one record
+    /// type per record. Fields whose values are strings have type `symbol`,
+    /// fields whose values are records have the type of the record, and other
+    /// fields have type `number`, i.e. they hold element identifiers or
+    /// numbers. Comments describe the types of values of fields.
+    pub fn to_souffle(&self) -> String {
+        let record_names: HashSet<&str> = self
+            .records
+            .iter()
+            .map(|record| record.name.as_str())
+            .collect();
+
+        let mut out = String::new();
+        let _ = writeln!(out, "// root elements: {}\n", self.roots.join(" | "));
+        for record in self.records.iter() {
+            let kind = match record.kind {
+                RecordKind::Struct => "struct",
+                RecordKind::Map => "maps at",
+            };
+            let _ = writeln!(
+                out,
+                "// {} {}: {} element(s)",
+                kind, record.name, record.count
+            );
+            if record.fields.is_empty() {
+                let _ = writeln!(out, ".type {} = []\n", souffle_ident(&record.name));
+                continue;
+            }
+
+            let _ = writeln!(out, ".type {} = [", souffle_ident(&record.name));
+            for (i, field) in record.fields.iter().enumerate() {
+                let ty = match field.types.as_slice() {
+                    [ty] if ty == "Str" => "symbol".to_string(),
+                    [ty] if record_names.contains(ty.as_str()) => souffle_ident(ty),
+                    _ => "number".to_string(),
+                };
+                let _ = writeln!(
+                    out,
+                    "    {}: {}{} // {}{}",
+                    souffle_ident(&field.name),
+                    ty,
+                    if i + 1 < record.fields.len() { "," } else { "" },
+                    field.types.join(" | "),
+                    if field.optional { ", optional" } else { "" }
+                );
+            }
+            let _ = writeln!(out, "]\n");
+        }
+        out
+    }
+}
@@ -1,4 +1,18 @@
+//! Input formats from which data can be read and extracted into facts, as
+//! used by the serde_datalog binary. Formats are collected in a
+//! [FormatRegistry], to which applications can add their own formats.
+
 use erased_serde::Deserializer as ErasedDeserializer;
+use std::{
+    collections::HashMap,
+    fmt::{self, Display},
+    fs::File,
+    io::{self, Read},
+    ops::Range,
+    path::Path,
+};
+
+use crate::{DatalogExtractionError, DatalogExtractor, DatalogExtractorBackend};
 
 /// An input format from which data can be extracted into Datalog facts.
 /// Implementations of this trait can generate
@@ -12,11 +26,266 @@ pub trait InputFormat {
     /// if its format is not explcitly specified
     fn file_extensions(&self) -> Vec<&'static str>;
 
+    /// Returns a list of file names associated with the input format, for
+    /// formats whose files are conventionally named rather than identified
+    /// by their extension (e.g. `.env`).
+    fn file_names(&self) -> Vec<&'static str> {
+        Vec::new()
+    }
+
+    /// Returns a list of media types (MIME types) associated with the input
+    /// format, e.g. `application/json`, for finding the format of inputs
+    /// from their `Content-Type` when they are fetched over HTTP.
+    fn media_types(&self) -> Vec<&'static str> {
+        Vec::new()
+    }
+
     /// Create an [InputFormatData] instance from the contents of an input file.
-    fn create<'input>(&self, contents: &'input str) -> Box<dyn InputFormatData<'input> + 'input>;
+    /// Returns an error message if the contents cannot be read in this format.
+    fn create<'input>(
+        &self,
+        contents: &'input str,
+    ) -> Result<Box<dyn InputFormatData<'input> + 'input>, String>;
+
+    /// Is this a binary input format? Formats that are not binary read input
+    /// as UTF-8 text.
+    fn is_binary(&self) -> bool {
+        false
+    }
+
+    /// Decode the contents of an input file into documents, each of which is
+    /// extracted as a separate root value, for formats that decode values
+    /// themselves instead of providing a deserializer through
+    /// [InputFormat::create]. Returns an error message if the contents cannot
+    /// be read in this format.
+    ///
+    /// The default implementation returns `None`, so that the contents are
+    /// split with [InputFormat::documents] and read with [InputFormat::create].
+    fn decode(&self, _contents: &[u8]) -> Option<Result<Vec<DecodedDocument>, String>> {
+        None
+    }
+
+    /// Split the contents of an input file into documents, each of which is
+    /// extracted as a separate root value.
+    ///
+    /// The default implementation treats the entire file as a single document.
+    fn documents<'input>(&self, contents: &'input str) -> Vec<InputDocument<'input>> {
+        vec![InputDocument {
+            line: None,
+            contents,
+        }]
+    }
 
     /// Are all of the map keys in this format strings?
     fn has_string_keys(&self) -> bool;
+
+    /// Are strings in this format that are dates or date-times date-times?
+    /// This is the case for formats whose date-times cannot be told apart
+    /// from strings once deserialized, such as YAML; see
+    /// [set_datetime_strings][crate::DatalogExtractor::set_datetime_strings].
+    ///
+    /// The default implementation returns false.
+    fn has_datetime_strings(&self) -> bool {
+        false
+    }
+
+    /// The key under which the [positions][Document::position] of documents
+    /// of this format are recorded in the `rootMeta` relation, such as
+    /// `line` for formats with a document on each line of the input; see
+    /// [set_document_meta].
+    ///
+    /// The default implementation returns `document`, for positions that
+    /// count the documents of the input.
+    fn position_key(&self) -> &'static str {
+        "document"
+    }
+
+    /// Do the first bytes of an input, `prefix`, look like the contents of a
+    /// file in this format? This is used to detect the format of inputs whose
+    /// format cannot be found from their file name, such as standard input.
+    ///
+    /// The default implementation returns false, so that the format is never
+    /// detected from the contents of an input.
+    fn detect(&self, _prefix: &[u8]) -> bool {
+        false
+    }
+
+    /// Read the contents of an input file from `reader`, returning the
+    /// documents in the input. `size_hint` is the size of the input in bytes,
+    /// if it is known. Returns an error if the input cannot be read.
+    ///
+    /// Formats whose parsers can read from a reader override this method, so
+    /// that inputs are parsed as they are read instead of being loaded into
+    /// memory first. The default implementation reads the entire input, then
+    /// decodes it with [InputFormat::decode], or splits it with
+    /// [InputFormat::documents] and reads each document with
+    /// [InputFormat::create]. Inputs of formats that are not binary must be
+    /// valid UTF-8.
+    fn read<'r>(
+        &'r self,
+        mut reader: Box<dyn Read + 'r>,
+        size_hint: Option<u64>,
+    ) -> io::Result<Box<dyn InputSource + 'r>> {
+        let mut contents = Vec::with_capacity(size_hint.unwrap_or_default() as usize);
+        reader.read_to_end(&mut contents)?;
+
+        let source = BufferedSource::new(self, contents)?;
+        io::Result::Ok(Box::new(source))
+    }
+}
+
+/// The documents read from an input file, each of which is extracted as a
+/// separate root value.
+pub trait InputSource {
+    /// Read the next document of the input, or return `None` once all of its
+    /// documents have been read. Returns an error message if the document
+    /// cannot be read in the input format.
+    fn next_document(&mut self) -> Option<Result<Document<'_>, String>>;
+
+    /// Whether the documents following a document that cannot be read or
+    /// extracted can still be read, e.g. because each document is on its own
+    /// line. Extraction stops at the first invalid document of sources that
+    /// cannot resume, even when skipping invalid documents.
+    fn resumes_after_errors(&self) -> bool {
+        false
+    }
+}
+
+/// A document read from an input file.
+pub struct Document<'a> {
+    /// The position of the document in the input file (e.g. its line or its
+    /// index in a stream of documents), if the input file contains multiple
+    /// documents. This is used to distinguish the root elements of documents
+    /// from the same file.
+    pub position: Option<usize>,
+
+    /// The contents of the document.
+    pub value: DocumentValue<'a>,
+}
+
+/// The contents of a document, either to be deserialized or already decoded.
+pub enum DocumentValue<'a> {
+    /// Data from which a deserializer for the document is created.
+    Data(Box<dyn InputFormatData<'a> + 'a>),
+
+    /// A value decoded by the input format.
+    Decoded(Box<dyn erased_serde::Serialize + 'a>),
+}
+
+enum BufferedDocuments {
+    Decoded(std::vec::IntoIter<DecodedDocument>),
+    Failed(Option<String>),
+    Text {
+        contents: String,
+        documents: std::vec::IntoIter<(Option<usize>, Range<usize>)>,
+    },
+}
+
+/// Documents of an input that has been read into memory, as read by the
+/// default implementation of [InputFormat::read].
+pub struct BufferedSource<'r, F: InputFormat + ?Sized> {
+    format: &'r F,
+    documents: BufferedDocuments,
+}
+
+impl<'r, F: InputFormat + ?Sized> BufferedSource<'r, F> {
+    /// Create a source for the documents in `contents`. Returns an error
+    /// if the format is not binary and `contents` is not valid UTF-8.
+    pub fn new(format: &'r F, contents: Vec<u8>) -> io::Result<Self> {
+        let documents = match format.decode(&contents) {
+            Some(Result::Ok(decoded)) => BufferedDocuments::Decoded(decoded.into_iter()),
+
+            Some(Result::Err(message)) => BufferedDocuments::Failed(Some(message)),
+
+            None => {
+                let contents = String::from_utf8(contents)
+                    .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+
+                // documents are slices of the contents, so they are stored as
+                // ranges to avoid borrowing the contents
+                let start = contents.as_ptr() as usize;
+                let ranges: Vec<(Option<usize>, Range<usize>)> = format
+                    .documents(&contents)
+                    .into_iter()
+                    .map(|doc| {
+                        let offset = doc.contents.as_ptr() as usize - start;
+                        (doc.line, offset..offset + doc.contents.len())
+                    })
+                    .collect();
+
+                BufferedDocuments::Text {
+                    contents,
+                    documents: ranges.into_iter(),
+                }
+            }
+        };
+
+        io::Result::Ok(BufferedSource { format, documents })
+    }
+}
+
+impl<F: InputFormat + ?Sized> InputSource for BufferedSource<'_, F> {
+    /// Buffered documents are split before they are read, so a document that
+    /// cannot be read does not affect the following ones.
+    fn resumes_after_errors(&self) -> bool {
+        true
+    }
+
+    fn next_document(&mut self) -> Option<Result<Document<'_>, String>> {
+        match &mut self.documents {
+            BufferedDocuments::Decoded(decoded) => decoded.next().map(|doc| {
+                Result::Ok(Document {
+                    position: doc.position,
+                    value: DocumentValue::Decoded(doc.value),
+                })
+            }),
+
+            BufferedDocuments::Failed(message) => message.take().map(Result::Err),
+
+            BufferedDocuments::Text {
+                contents,
+                documents,
+            } => {
+                let (line, range) = documents.next()?;
+                let res = self.format.create(&contents[range]);
+
+                Some(
+                    res.map(|data| Document {
+                        position: line,
+                        value: DocumentValue::Data(data),
+                    })
+                    .map_err(|message| match line {
+                        Some(line) => format!("line {}: {}", line, message),
+                        None => message,
+                    }),
+                )
+            }
+        }
+    }
+}
+
+/// A document within an input file that is extracted as a separate root value.
+pub struct InputDocument<'input> {
+    /// The line on which the document starts, if the input file contains
+    /// multiple documents. This is used to distinguish the root elements
+    /// of documents from the same file.
+    pub line: Option<usize>,
+
+    /// The contents of the document.
+    pub contents: &'input str,
+}
+
+/// A value decoded from an input file that is extracted as a separate root
+/// value. Formats that decode values themselves, instead of providing a
+/// deserializer, can generate values with information that is lost when
+/// transcoding, such as struct names and enum variants.
+pub struct DecodedDocument {
+    /// The position of the value in the input file, starting from 1, if the
+    /// input file contains multiple values.
+    pub position: Option<usize>,
+
+    /// The decoded value.
+    pub value: Box<dyn erased_serde::Serialize>,
 }
 
 /// Data that is used to create a [serde::Deserializer] from the contents
@@ -28,14 +297,523 @@ pub trait InputFormatData<'input> {
     fn deserializer<'de>(&'de mut self) -> Box<dyn ErasedDeserializer<'input> + 'de>;
 }
 
-#[cfg(feature = "ron")]
+/// A collection of input formats, from which the format of an input file is
+/// found by name or by file name. Applications can register their own formats
+/// in addition to, or in place of, the formats provided by this crate.
+#[derive(Default)]
+pub struct FormatRegistry {
+    formats: Vec<Box<dyn InputFormat>>,
+    extension_formats: HashMap<String, String>,
+}
+
+impl FormatRegistry {
+    /// Create an empty registry.
+    pub fn new() -> Self {
+        FormatRegistry::default()
+    }
+
+    /// Create a registry with the formats enabled by this crate's features.
+    /// The protobuf format, if enabled, must be configured with a message type
+    /// before it can read inputs, by registering a format created with
+    /// `InputFormatProtobuf::new` in its place.
+    pub fn with_builtin_formats() -> Self {
+        // formats are listed in the order they are registered
+        let formats: Vec<Box<dyn InputFormat>> = vec![
+            #[cfg(feature = "json")]
+            Box::new(json::InputFormatJSON),
+            #[cfg(feature = "json")]
+            Box::new(ndjson::InputFormatNDJSON),
+            #[cfg(feature = "ron")]
+            Box::new(ron::InputFormatRON),
+            #[cfg(feature = "toml")]
+            Box::new(toml::InputFormatTOML),
+            #[cfg(feature = "yaml")]
+            Box::new(yaml::InputFormatYAML),
+            #[cfg(feature = "ini")]
+            Box::new(ini::InputFormatINI),
+            #[cfg(feature = "env")]
+            Box::new(env::InputFormatEnv),
+            #[cfg(feature = "edn")]
+            Box::new(edn::InputFormatEDN),
+            #[cfg(feature = "avro")]
+            Box::new(avro::InputFormatAvro),
+            #[cfg(feature = "graphql")]
+            Box::new(graphql::InputFormatGraphQL),
+            #[cfg(feature = "graphql")]
+            Box::new(graphql::InputFormatGraphQLResponse),
+            #[cfg(feature = "rust")]
+            Box::new(rust::InputFormatRust),
+            #[cfg(feature = "url")]
+            Box::new(url::InputFormatURL),
+            #[cfg(feature = "url")]
+            Box::new(url::InputFormatURLEncoded),
+            #[cfg(feature = "spreadsheet")]
+            Box::new(spreadsheet::InputFormatSpreadsheet),
+            #[cfg(feature = "logs")]
+            Box::new(logs::InputFormatLogfmt),
+            #[cfg(feature = "logs")]
+            Box::new(logs::InputFormatSyslog),
+            #[cfg(feature = "protobuf")]
+            Box::new(protobuf::InputFormatProtobuf::unconfigured()),
+        ];
+
+        let mut registry = FormatRegistry::new();
+        for format in formats {
+            registry.register(format);
+        }
+
+        registry
+    }
+
+    /// Add `format` to the registry. A format already registered with the
+    /// same name is replaced, keeping its place in the registry.
+    pub fn register(&mut self, format: Box<dyn InputFormat>) {
+        match self
+            .formats
+            .iter_mut()
+            .find(|fmt| fmt.name() == format.name())
+        {
+            Some(fmt) => *fmt = format,
+            None => self.formats.push(format),
+        }
+    }
+
+    /// Add a text format named `name` whose files have extensions
+    /// `file_extensions`, and whose contents are read with `create`.
+    /// See [InputFormat::create] and [InputFormat::has_string_keys].
+    pub fn register_fn<F>(
+        &mut self,
+        name: &'static str,
+        file_extensions: Vec<&'static str>,
+        has_string_keys: bool,
+        create: F,
+    ) where
+        F: for<'input> Fn(&'input str) -> Result<Box<dyn InputFormatData<'input> + 'input>, String>
+            + 'static,
+    {
+        self.register(Box::new(FnInputFormat {
+            name,
+            file_extensions,
+            has_string_keys,
+            create,
+        }));
+    }
+
+    /// Find the format named `name`.
+    pub fn get(&self, name: &str) -> Option<&dyn InputFormat> {
+        self.formats
+            .iter()
+            .find(|fmt| fmt.name() == name)
+            .map(|fmt| fmt.as_ref())
+    }
+
+    /// Read files with extension `ext` in the format named `name`, instead of
+    /// the format whose extensions include `ext`, if any. Returns false if no
+    /// format named `name` is registered.
+    pub fn map_extension(&mut self, ext: &str, name: &str) -> bool {
+        if self.get(name).is_none() {
+            return false;
+        }
+
+        self.extension_formats
+            .insert(ext.to_string(), name.to_string());
+        true
+    }
+
+    /// Find the format of the file at `path`, first by its file name and then
+    /// by its extension.
+    pub fn find_by_path(&self, path: &Path) -> Option<&dyn InputFormat> {
+        let name = path.file_name()?.to_str()?;
+        if let Some(fmt) = self
+            .formats
+            .iter()
+            .find(|fmt| fmt.file_names().contains(&name))
+        {
+            return Some(fmt.as_ref());
+        }
+
+        let ext = path.extension()?.to_str()?;
+        if let Some(name) = self.extension_formats.get(ext) {
+            return self.get(name);
+        }
+
+        self.formats
+            .iter()
+            .find(|fmt| fmt.file_extensions().contains(&ext))
+            .map(|fmt| fmt.as_ref())
+    }
+
+    /// Find the format of an input with media type `media_type`, e.g. the
+    /// `Content-Type` of an HTTP response. Parameters such as `charset` are
+    /// ignored, and types with a structured syntax suffix such as
+    /// `application/ld+json` are read in the format of the suffix if no
+    /// format has the type itself.
+    pub fn find_by_media_type(&self, media_type: &str) -> Option<&dyn InputFormat> {
+        let media_type = media_type
+            .split(';')
+            .next()
+            .unwrap_or_default()
+            .trim()
+            .to_ascii_lowercase();
+        let find = |media_type: &str| {
+            self.formats
+                .iter()
+                .find(|fmt| fmt.media_types().contains(&media_type))
+                .map(|fmt| fmt.as_ref())
+        };
+
+        find(&media_type).or_else(|| {
+            let (_, suffix) = media_type.rsplit_once('+')?;
+            find(&format!("application/{}", suffix))
+        })
+    }
+
+    /// Find the format of an input from its first bytes, `prefix`. If several
+    /// formats detect the input, the format registered first is returned.
+    /// See [InputFormat::detect].
+    pub fn detect(&self, prefix: &[u8]) -> Option<&dyn InputFormat> {
+        self.formats
+            .iter()
+            .find(|fmt| fmt.detect(prefix))
+            .map(|fmt| fmt.as_ref())
+    }
+
+    /// Returns the registered formats, in order of registration.
+    pub fn iter(&self) -> impl Iterator<Item = &dyn InputFormat> {
+        self.formats.iter().map(|fmt| fmt.as_ref())
+    }
+
+    /// Extract facts from the documents of the input file at `path` with
+    /// `extractor`, as the serde_datalog binary does. The input format is the
+    /// format named `format_hint`, if given; otherwise it is found from the
+    /// file name of `path`, or detected from the first
+    /// [DETECT_PREFIX_LEN] bytes of the file. The root elements of documents
+    /// are named with [document_root] after `path` as given.
+    pub fn extract_file<B: DatalogExtractorBackend>(
+        &self,
+        extractor: &mut DatalogExtractor<B>,
+        path: &Path,
+        format_hint: Option<&str>,
+    ) -> Result<(), ExtractFileError> {
+        let file = File::open(path).map_err(ExtractFileError::Io)?;
+        let size_hint = file.metadata().ok().map(|metadata| metadata.len());
+        let mut reader: Box<dyn Read> = Box::new(file);
+
+        let format = match format_hint {
+            Some(name) => self
+                .get(name)
+                .ok_or_else(|| ExtractFileError::UnknownFormat(name.to_string()))?,
+
+            None => match self.find_by_path(path) {
+                Some(format) => format,
+
+                None => {
+                    let mut prefix = Vec::new();
+                    Read::by_ref(&mut reader)
+                        .take(DETECT_PREFIX_LEN)
+                        .read_to_end(&mut prefix)
+                        .map_err(ExtractFileError::Io)?;
+
+                    let format = self.detect(&prefix).ok_or_else(|| {
+                        ExtractFileError::UndetectedFormat(path.display().to_string())
+                    })?;
+                    reader = Box::new(io::Cursor::new(prefix).chain(reader));
+                    format
+                }
+            },
+        };
+
+        let file = path.display().to_string();
+        extractor
+            .set_file_format(&file, format.name())
+            .map_err(|error| ExtractFileError::Extraction {
+                root: file.clone(),
+                error,
+            })?;
+        extractor.set_datetime_strings(format.has_datetime_strings());
+
+        let reader = if format.is_binary() {
+            reader
+        } else {
+            skip_bom(reader).map_err(ExtractFileError::Io)?
+        };
+
+        let mut source = format
+            .read(reader, size_hint)
+            .map_err(ExtractFileError::Io)?;
+        while let Some(document) = source.next_document() {
+            let document = document.map_err(|message| ExtractFileError::Parse {
+                root: file.clone(),
+                message,
+            })?;
+
+            let root = document_root(&file, document.position);
+            extract_document(extractor, &root, document.value)
+                .and_then(|()| set_document_meta(extractor, &root, format, document.position))
+                .map_err(|error| ExtractFileError::Extraction { root, error })?;
+        }
+
+        Result::Ok(())
+    }
+}
+
+/// Number of bytes at the start of an input from which its format is
+/// detected.
+pub const DETECT_PREFIX_LEN: u64 = 4096;
+
+/// Error encountered while extracting facts from an input file with
+/// [FormatRegistry::extract_file].
+#[derive(Debug)]
+pub enum ExtractFileError {
+    /// Input file could not be read
+    Io(io::Error),
+
+    /// No input format has the given name
+    UnknownFormat(String),
+
+    /// Input format of the file could not be found from its name or contents
+    UndetectedFormat(String),
+
+    /// Document could not be read in the input format
+    Parse { root: String, message: String },
+
+    /// Facts could not be extracted from a document
+    Extraction {
+        root: String,
+        error: DatalogExtractionError,
+    },
+}
+
+impl Display for ExtractFileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExtractFileError::Io(err) => write!(f, "{}", err),
+
+            ExtractFileError::UnknownFormat(name) => write!(f, "unknown input format {}", name),
+
+            ExtractFileError::UndetectedFormat(path) => {
+                write!(f, "cannot detect the input format of {}", path)
+            }
+
+            ExtractFileError::Parse { root, message } => write!(f, "{}: {}", root, message),
+
+            ExtractFileError::Extraction { root, error } => write!(f, "{}: {}", root, error),
+        }
+    }
+}
+
+impl std::error::Error for ExtractFileError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ExtractFileError::Io(err) => Some(err),
+            ExtractFileError::Extraction { error, .. } => Some(error),
+            _ => None,
+        }
+    }
+}
+
+/// Extract facts from the input file at `path` into `backend`, with an
+/// extractor with default options and the formats of
+/// [FormatRegistry::with_builtin_formats]; see
+/// [FormatRegistry::extract_file]. Returns the backend, which can also be
+/// passed by mutable reference.
+///
+/// ```ignore
+/// let backend = extract_file(Path::new("Cargo.toml"), None, backend::vector::Backend::default())?;
+/// ```
+pub fn extract_file<B: DatalogExtractorBackend>(
+    path: &Path,
+    format_hint: Option<&str>,
+    backend: B,
+) -> Result<B, ExtractFileError> {
+    let mut extractor = DatalogExtractor::new(backend);
+    FormatRegistry::with_builtin_formats().extract_file(&mut extractor, path, format_hint)?;
+    Result::Ok(extractor.get_backend())
+}
+
+/// The name of the root element of a document of the input file `file`:
+/// `file` itself, or `file:position` for documents of multi-document inputs.
+pub fn document_root(file: &str, position: Option<usize>) -> String {
+    match position {
+        Some(position) => format!("{}:{}", file, position),
+        None => file.to_string(),
+    }
+}
+
+/// Record the position `position` of the document of an input in format
+/// `format` that was extracted as root value `root`, in the `rootMeta`
+/// relation under the format's [position key][InputFormat::position_key],
+/// e.g. `rootMeta("events.ndjson:3", "line", "3")`, and the format of the
+/// root in the `fileFormat` relation, e.g.
+/// `fileFormat("events.ndjson:3", "ndjson")`, so that the facts of the
+/// document join with its format on the name of its root. Documents without
+/// a position, i.e. documents of single-document inputs, are not recorded,
+/// since their roots are named after their input file, whose format is
+/// recorded with [set_file_format][DatalogExtractor::set_file_format].
+pub fn set_document_meta<B: DatalogExtractorBackend>(
+    extractor: &mut DatalogExtractor<B>,
+    root: &str,
+    format: &(impl InputFormat + ?Sized),
+    position: Option<usize>,
+) -> crate::Result<()> {
+    match position {
+        Some(position) => {
+            extractor.set_root_meta(root, format.position_key(), &position.to_string())?;
+            extractor.set_file_format(root, format.name())
+        }
+        None => crate::Result::Ok(()),
+    }
+}
+
+/// Extract facts from the document `value` as a root value named `root`.
+pub fn extract_document<B: DatalogExtractorBackend>(
+    extractor: &mut DatalogExtractor<B>,
+    root: &str,
+    value: DocumentValue<'_>,
+) -> crate::Result<()> {
+    extractor.set_file(root)?;
+    match value {
+        DocumentValue::Decoded(value) => erased_serde::serialize(value.as_ref(), &mut *extractor),
+
+        DocumentValue::Data(mut format_data) => {
+            serde_transcode::transcode(format_data.deserializer().as_mut(), &mut *extractor)
+        }
+    }
+}
+
+/// Skip a byte order mark at the start of `reader`, as text formats read
+/// UTF-8 text without one.
+pub fn skip_bom<'r>(mut reader: Box<dyn Read + 'r>) -> io::Result<Box<dyn Read + 'r>> {
+    let mut start = Vec::with_capacity(UTF8_BOM.len());
+    Read::by_ref(&mut reader)
+        .take(UTF8_BOM.len() as u64)
+        .read_to_end(&mut start)?;
+
+    if start == UTF8_BOM {
+        start.clear();
+    }
+
+    io::Result::Ok(Box::new(io::Cursor::new(start).chain(reader)))
+}
+
+/// The byte order mark that may start UTF-8 text.
+pub const UTF8_BOM: &[u8] = b"\xEF\xBB\xBF";
+
+/// The start of the text in `prefix`, skipping a byte order mark and
+/// leading whitespace, for detecting text formats.
+#[cfg(any(
+    feature = "edn",
+    feature = "json",
+    feature = "logs",
+    feature = "url",
+    feature = "yaml"
+))]
+fn text_start(prefix: &[u8]) -> &[u8] {
+    prefix
+        .strip_prefix(UTF8_BOM)
+        .unwrap_or(prefix)
+        .trim_ascii_start()
+}
+
+/// Return a static copy of `name`, leaking each distinct name once. Serde
+/// requires static names for structs, fields, and variants, so formats whose
+/// type names come from their input intern them here.
+#[cfg(any(feature = "avro", feature = "graphql"))]
+fn static_name(name: &str) -> &'static str {
+    use std::{
+        collections::HashSet,
+        sync::{Mutex, OnceLock},
+    };
+
+    static NAMES: OnceLock<Mutex<HashSet<&'static str>>> = OnceLock::new();
+
+    let mut names = NAMES
+        .get_or_init(Default::default)
+        .lock()
+        .unwrap_or_else(|err| err.into_inner());
+    match names.get(name) {
+        Some(name) => name,
+        None => {
+            let name: &'static str = Box::leak(name.to_string().into_boxed_str());
+            names.insert(name);
+            name
+        }
+    }
+}
+
+/// A format registered with [FormatRegistry::register_fn].
+struct FnInputFormat<F> {
+    name: &'static str,
+    file_extensions: Vec<&'static str>,
+    has_string_keys: bool,
+    create: F,
+}
+
+impl<F> InputFormat for FnInputFormat<F>
+where
+    F: for<'input> Fn(&'input str) -> Result<Box<dyn InputFormatData<'input> + 'input>, String>,
+{
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn file_extensions(&self) -> Vec<&'static str> {
+        self.file_extensions.clone()
+    }
+
+    fn create<'input>(
+        &self,
+        contents: &'input str,
+    ) -> Result<Box<dyn InputFormatData<'input> + 'input>, String> {
+        (self.create)(contents)
+    }
+
+    fn has_string_keys(&self) -> bool {
+        self.has_string_keys
+    }
+}
+
+#[cfg(feature = "avro")]
+pub mod avro;
+
+#[cfg(feature = "edn")]
+pub mod edn;
+
+#[cfg(feature = "env")]
+pub mod env;
+
+#[cfg(feature = "graphql")]
+pub mod graphql;
+
+#[cfg(feature = "ini")]
+pub mod ini;
+
+#[cfg(feature = "json")]
 pub mod json;
 
+#[cfg(feature = "json")]
+pub mod ndjson;
+
+#[cfg(feature = "logs")]
+pub mod logs;
+
+#[cfg(feature = "protobuf")]
+pub mod protobuf;
+
 #[cfg(feature = "ron")]
 pub mod ron;
 
+#[cfg(feature = "rust")]
+pub mod rust;
+
+#[cfg(feature = "spreadsheet")]
+pub mod spreadsheet;
+
 #[cfg(feature = "toml")]
 pub mod toml;
 
+#[cfg(feature = "url")]
+pub mod url;
+
 #[cfg(feature = "yaml")]
 pub mod yaml;
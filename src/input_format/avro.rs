@@ -0,0 +1,674 @@
+use super::{static_name, DecodedDocument, InputFormat, InputFormatData};
+use serde::ser::{Serialize, SerializeMap, SerializeSeq, SerializeStruct, Serializer};
+use serde_json::Value as JsonValue;
+use std::{collections::HashMap, rc::Rc};
+
+/// [Avro](https://avro.apache.org) object container files. The schema is read
+/// from the header of the file, and each record in the file is extracted as a
+/// separate root value.
+///
+/// Records are extracted as structs whose type is the full name of the record
+/// schema, and enum symbols are extracted as unit variants of the enum schema.
+/// Unions are extracted as the value of the selected branch, and logical types
+/// are extracted as their underlying type.
+///
+/// Schemas and values nested more than 128 levels deep are rejected, as are
+/// blocks whose item counts cannot fit in the rest of the block. Items are
+/// assumed to take at least one byte, so arrays of nulls or of empty records
+/// cannot have more items than their block has bytes.
+pub struct InputFormatAvro;
+
+impl InputFormat for InputFormatAvro {
+    fn name(&self) -> &'static str {
+        "avro"
+    }
+
+    fn file_extensions(&self) -> Vec<&'static str> {
+        vec!["avro"]
+    }
+
+    fn media_types(&self) -> Vec<&'static str> {
+        vec!["application/avro", "avro/binary"]
+    }
+
+    fn create<'input>(
+        &self,
+        _contents: &'input str,
+    ) -> Result<Box<dyn InputFormatData<'input> + 'input>, String> {
+        Result::Err("avro is a binary input format".to_string())
+    }
+
+    fn is_binary(&self) -> bool {
+        true
+    }
+
+    fn detect(&self, prefix: &[u8]) -> bool {
+        prefix.starts_with(MAGIC)
+    }
+
+    fn decode(&self, contents: &[u8]) -> Option<Result<Vec<DecodedDocument>, String>> {
+        let documents = read_container(contents).map(|records| {
+            records
+                .into_iter()
+                .enumerate()
+                .map(|(i, record)| DecodedDocument {
+                    position: Some(i + 1),
+                    value: Box::new(record),
+                })
+                .collect()
+        });
+
+        Some(documents)
+    }
+
+    fn has_string_keys(&self) -> bool {
+        true
+    }
+}
+
+const MAGIC: &[u8] = b"Obj\x01";
+const SYNC_SIZE: usize = 16;
+
+/// The maximum nesting depth of schemas and values.
+const MAX_DEPTH: usize = 128;
+
+/// An Avro schema. Named types are stored separately, so that they can be
+/// referenced recursively.
+enum Schema {
+    Null,
+    Boolean,
+    Int,
+    Long,
+    Float,
+    Double,
+    Bytes,
+    String,
+    Array(Box<Schema>),
+    Map(Box<Schema>),
+    Union(Vec<Schema>),
+    Named(usize),
+}
+
+enum NamedSchema {
+    Record {
+        name: Rc<str>,
+        fields: Vec<(Rc<str>, Schema)>,
+    },
+    Enum {
+        name: Rc<str>,
+        symbols: Vec<Rc<str>>,
+    },
+    Fixed {
+        size: usize,
+    },
+}
+
+/// Parses the JSON representation of a schema, resolving references to
+/// named types.
+#[derive(Default)]
+struct SchemaParser {
+    named: Vec<Option<NamedSchema>>,
+    names: HashMap<String, usize>,
+    depth: usize,
+}
+
+impl SchemaParser {
+    fn full_name(name: &str, namespace: Option<&str>) -> String {
+        match namespace {
+            Some(ns) if !name.contains('.') && !ns.is_empty() => format!("{}.{}", ns, name),
+            _ => name.to_string(),
+        }
+    }
+
+    fn parse(&mut self, json: &JsonValue, namespace: Option<&str>) -> Result<Schema, String> {
+        if self.depth >= MAX_DEPTH {
+            return Result::Err(format!("schema is nested more than {} levels", MAX_DEPTH));
+        }
+
+        self.depth += 1;
+        let schema = self.parse_nested(json, namespace);
+        self.depth -= 1;
+        schema
+    }
+
+    fn parse_nested(
+        &mut self,
+        json: &JsonValue,
+        namespace: Option<&str>,
+    ) -> Result<Schema, String> {
+        match json {
+            JsonValue::String(name) => self.parse_name(name, namespace),
+
+            JsonValue::Array(branches) => Result::Ok(Schema::Union(
+                branches
+                    .iter()
+                    .map(|branch| self.parse(branch, namespace))
+                    .collect::<Result<_, _>>()?,
+            )),
+
+            JsonValue::Object(obj) => {
+                let ty = obj
+                    .get("type")
+                    .ok_or_else(|| "schema is missing a type".to_string())?;
+
+                match ty.as_str() {
+                    Some("record") | Some("error") | Some("enum") | Some("fixed") => {
+                        self.parse_named(obj, namespace)
+                    }
+
+                    Some("array") => {
+                        let items = obj
+                            .get("items")
+                            .ok_or_else(|| "array schema is missing items".to_string())?;
+                        Result::Ok(Schema::Array(Box::new(self.parse(items, namespace)?)))
+                    }
+
+                    Some("map") => {
+                        let values = obj
+                            .get("values")
+                            .ok_or_else(|| "map schema is missing values".to_string())?;
+                        Result::Ok(Schema::Map(Box::new(self.parse(values, namespace)?)))
+                    }
+
+                    // primitive types, possibly annotated with a logical type
+                    _ => self.parse(ty, namespace),
+                }
+            }
+
+            _ => Result::Err(format!("invalid schema {}", json)),
+        }
+    }
+
+    fn parse_name(&mut self, name: &str, namespace: Option<&str>) -> Result<Schema, String> {
+        let schema = match name {
+            "null" => Schema::Null,
+            "boolean" => Schema::Boolean,
+            "int" => Schema::Int,
+            "long" => Schema::Long,
+            "float" => Schema::Float,
+            "double" => Schema::Double,
+            "bytes" => Schema::Bytes,
+            "string" => Schema::String,
+            _ => {
+                let index = self
+                    .names
+                    .get(&Self::full_name(name, namespace))
+                    .or_else(|| self.names.get(name))
+                    .ok_or_else(|| format!("unknown type {} in schema", name))?;
+                Schema::Named(*index)
+            }
+        };
+
+        Result::Ok(schema)
+    }
+
+    fn parse_named(
+        &mut self,
+        obj: &serde_json::Map<String, JsonValue>,
+        namespace: Option<&str>,
+    ) -> Result<Schema, String> {
+        let name = obj
+            .get("name")
+            .and_then(|name| name.as_str())
+            .ok_or_else(|| "named schema is missing a name".to_string())?;
+
+        let namespace = obj
+            .get("namespace")
+            .and_then(|ns| ns.as_str())
+            .or(namespace);
+        let full_name = Self::full_name(name, namespace);
+
+        // names within a named type are relative to its namespace
+        let inner_namespace = full_name.rsplit_once('.').map(|(ns, _)| ns.to_string());
+
+        // register the name before parsing fields, which may refer to it
+        let index = self.named.len();
+        self.named.push(None);
+        self.names.insert(full_name.clone(), index);
+
+        let named = match obj.get("type").and_then(|ty| ty.as_str()) {
+            Some("enum") => {
+                let symbols = obj
+                    .get("symbols")
+                    .and_then(|symbols| symbols.as_array())
+                    .ok_or_else(|| format!("enum {} is missing symbols", full_name))?
+                    .iter()
+                    .map(|symbol| {
+                        symbol.as_str().map(Rc::from).ok_or_else(|| {
+                            format!("enum {} has an invalid symbol {}", full_name, symbol)
+                        })
+                    })
+                    .collect::<Result<_, _>>()?;
+
+                NamedSchema::Enum {
+                    name: Rc::from(full_name.as_str()),
+                    symbols,
+                }
+            }
+
+            Some("fixed") => {
+                let size = obj
+                    .get("size")
+                    .and_then(|size| size.as_u64())
+                    .ok_or_else(|| format!("fixed {} is missing a size", full_name))?;
+
+                NamedSchema::Fixed {
+                    size: size as usize,
+                }
+            }
+
+            _ => {
+                let fields = obj
+                    .get("fields")
+                    .and_then(|fields| fields.as_array())
+                    .ok_or_else(|| format!("record {} is missing fields", full_name))?
+                    .iter()
+                    .map(|field| {
+                        let field_name = field
+                            .get("name")
+                            .and_then(|name| name.as_str())
+                            .ok_or_else(|| {
+                                format!("record {} has a field without a name", full_name)
+                            })?;
+                        let field_type = field.get("type").ok_or_else(|| {
+                            format!("field {} of {} is missing a type", field_name, full_name)
+                        })?;
+
+                        Result::Ok((
+                            Rc::from(field_name),
+                            self.parse(field_type, inner_namespace.as_deref())?,
+                        ))
+                    })
+                    .collect::<Result<_, String>>()?;
+
+                NamedSchema::Record {
+                    name: Rc::from(full_name.as_str()),
+                    fields,
+                }
+            }
+        };
+
+        self.named[index] = Some(named);
+        Result::Ok(Schema::Named(index))
+    }
+}
+
+/// A value decoded from an Avro file.
+enum AvroValue {
+    Null,
+    Boolean(bool),
+    Int(i32),
+    Long(i64),
+    Float(f32),
+    Double(f64),
+    Bytes(Vec<u8>),
+    String(String),
+    Array(Vec<AvroValue>),
+    Map(Vec<(String, AvroValue)>),
+    Record {
+        name: Rc<str>,
+        fields: Vec<(Rc<str>, AvroValue)>,
+    },
+    Enum {
+        name: Rc<str>,
+        index: u32,
+        symbol: Rc<str>,
+    },
+}
+
+impl Serialize for AvroValue {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            AvroValue::Null => serializer.serialize_unit(),
+            AvroValue::Boolean(value) => serializer.serialize_bool(*value),
+            AvroValue::Int(value) => serializer.serialize_i32(*value),
+            AvroValue::Long(value) => serializer.serialize_i64(*value),
+            AvroValue::Float(value) => serializer.serialize_f32(*value),
+            AvroValue::Double(value) => serializer.serialize_f64(*value),
+            AvroValue::Bytes(value) => serializer.serialize_bytes(value),
+            AvroValue::String(value) => serializer.serialize_str(value),
+
+            AvroValue::Array(items) => {
+                let mut seq = serializer.serialize_seq(Some(items.len()))?;
+                for item in items.iter() {
+                    seq.serialize_element(item)?;
+                }
+                seq.end()
+            }
+
+            AvroValue::Map(entries) => {
+                let mut map = serializer.serialize_map(Some(entries.len()))?;
+                for (key, value) in entries.iter() {
+                    map.serialize_entry(key, value)?;
+                }
+                map.end()
+            }
+
+            AvroValue::Record { name, fields } => {
+                let mut record = serializer.serialize_struct(static_name(name), fields.len())?;
+                for (key, value) in fields.iter() {
+                    record.serialize_field(static_name(key), value)?;
+                }
+                record.end()
+            }
+
+            AvroValue::Enum {
+                name,
+                index,
+                symbol,
+            } => serializer.serialize_unit_variant(static_name(name), *index, static_name(symbol)),
+        }
+    }
+}
+
+/// Reads values in the Avro binary encoding.
+struct Decoder<'a> {
+    data: &'a [u8],
+    pos: usize,
+    named: &'a [Option<NamedSchema>],
+    depth: usize,
+}
+
+impl<'a> Decoder<'a> {
+    fn is_empty(&self) -> bool {
+        self.pos >= self.data.len()
+    }
+
+    fn read_bytes(&mut self, len: usize) -> Result<&'a [u8], String> {
+        let end = self
+            .pos
+            .checked_add(len)
+            .filter(|end| *end <= self.data.len())
+            .ok_or_else(|| format!("unexpected end of input at byte {}", self.data.len()))?;
+
+        let bytes = &self.data[self.pos..end];
+        self.pos = end;
+        Result::Ok(bytes)
+    }
+
+    /// Read a zigzag-encoded variable-length integer.
+    fn read_long(&mut self) -> Result<i64, String> {
+        let mut value: u64 = 0;
+        for shift in (0..64).step_by(7) {
+            let byte = self.read_bytes(1)?[0];
+            value |= ((byte & 0x7f) as u64) << shift;
+
+            if byte & 0x80 == 0 {
+                return Result::Ok((value >> 1) as i64 ^ -((value & 1) as i64));
+            }
+        }
+
+        Result::Err(format!(
+            "invalid variable-length integer at byte {}",
+            self.pos
+        ))
+    }
+
+    fn read_len(&mut self) -> Result<usize, String> {
+        let len = self.read_long()?;
+        usize::try_from(len).map_err(|_| format!("invalid length {} at byte {}", len, self.pos))
+    }
+
+    fn read_string(&mut self) -> Result<String, String> {
+        let len = self.read_len()?;
+        let bytes = self.read_bytes(len)?;
+        String::from_utf8(bytes.to_vec()).map_err(|err| err.to_string())
+    }
+
+    /// Check that `count` items of at least `item_size` bytes each fit in the
+    /// rest of the input.
+    fn check_count(&self, count: u64, item_size: usize) -> Result<(), String> {
+        let remaining = self.data.len().saturating_sub(self.pos) as u64;
+        if count.saturating_mul(item_size.max(1) as u64) > remaining {
+            return Result::Err(format!(
+                "count {} at byte {} exceeds the remaining {} bytes",
+                count, self.pos, remaining
+            ));
+        }
+
+        Result::Ok(())
+    }
+
+    /// The minimum size in bytes of a value of `schema`, without looking
+    /// into records, which may be empty.
+    fn min_size(&self, schema: &Schema) -> usize {
+        match schema {
+            Schema::Null => 0,
+            Schema::Float => 4,
+            Schema::Double => 8,
+            Schema::Named(index) => match self.named[*index].as_ref() {
+                Some(NamedSchema::Enum { .. }) => 1,
+                Some(NamedSchema::Fixed { size }) => *size,
+                _ => 0,
+            },
+            _ => 1,
+        }
+    }
+
+    /// Read the items of an array or map, which are encoded in blocks. Each
+    /// item takes at least `item_size` bytes.
+    fn read_blocks<T>(
+        &mut self,
+        item_size: usize,
+        mut read_item: impl FnMut(&mut Self) -> Result<T, String>,
+    ) -> Result<Vec<T>, String> {
+        let mut items = Vec::new();
+        loop {
+            let count = self.read_long()?;
+            if count == 0 {
+                return Result::Ok(items);
+            }
+
+            // a negative count is followed by the size of the block in bytes
+            if count < 0 {
+                self.read_long()?;
+            }
+
+            self.check_count(count.unsigned_abs(), item_size)?;
+            for _ in 0..count.unsigned_abs() {
+                items.push(read_item(self)?);
+            }
+        }
+    }
+
+    fn read_value(&mut self, schema: &Schema) -> Result<AvroValue, String> {
+        if self.depth >= MAX_DEPTH {
+            return Result::Err(format!(
+                "value at byte {} is nested more than {} levels",
+                self.pos, MAX_DEPTH
+            ));
+        }
+
+        self.depth += 1;
+        let value = self.read_nested(schema);
+        self.depth -= 1;
+        value
+    }
+
+    fn read_nested(&mut self, schema: &Schema) -> Result<AvroValue, String> {
+        let value = match schema {
+            Schema::Null => AvroValue::Null,
+
+            Schema::Boolean => AvroValue::Boolean(self.read_bytes(1)?[0] != 0),
+
+            Schema::Int => {
+                let value = self.read_long()?;
+                AvroValue::Int(
+                    i32::try_from(value)
+                        .map_err(|_| format!("int {} out of range at byte {}", value, self.pos))?,
+                )
+            }
+
+            Schema::Long => AvroValue::Long(self.read_long()?),
+
+            Schema::Float => {
+                let bytes = self.read_bytes(4)?;
+                AvroValue::Float(f32::from_le_bytes(bytes.try_into().unwrap()))
+            }
+
+            Schema::Double => {
+                let bytes = self.read_bytes(8)?;
+                AvroValue::Double(f64::from_le_bytes(bytes.try_into().unwrap()))
+            }
+
+            Schema::Bytes => {
+                let len = self.read_len()?;
+                AvroValue::Bytes(self.read_bytes(len)?.to_vec())
+            }
+
+            Schema::String => AvroValue::String(self.read_string()?),
+
+            Schema::Array(items) => AvroValue::Array(
+                self.read_blocks(self.min_size(items), |decoder| decoder.read_value(items))?,
+            ),
+
+            Schema::Map(values) => {
+                AvroValue::Map(self.read_blocks(1 + self.min_size(values), |decoder| {
+                    let key = decoder.read_string()?;
+                    Result::Ok((key, decoder.read_value(values)?))
+                })?)
+            }
+
+            Schema::Union(branches) => {
+                let index = self.read_long()?;
+                let branch = usize::try_from(index)
+                    .ok()
+                    .and_then(|i| branches.get(i))
+                    .ok_or_else(|| {
+                        format!("invalid union branch {} at byte {}", index, self.pos)
+                    })?;
+                self.read_value(branch)?
+            }
+
+            Schema::Named(index) => {
+                let named = self.named;
+                match named[*index].as_ref() {
+                    Some(NamedSchema::Record { name, fields }) => AvroValue::Record {
+                        name: name.clone(),
+                        fields: fields
+                            .iter()
+                            .map(|(key, field)| Result::Ok((key.clone(), self.read_value(field)?)))
+                            .collect::<Result<_, String>>()?,
+                    },
+
+                    Some(NamedSchema::Enum { name, symbols }) => {
+                        let index = self.read_long()?;
+                        let symbol = usize::try_from(index)
+                            .ok()
+                            .and_then(|i| symbols.get(i))
+                            .ok_or_else(|| {
+                                format!("invalid enum symbol {} at byte {}", index, self.pos)
+                            })?;
+
+                        AvroValue::Enum {
+                            name: name.clone(),
+                            index: index as u32,
+                            symbol: symbol.clone(),
+                        }
+                    }
+
+                    Some(NamedSchema::Fixed { size }) => {
+                        AvroValue::Bytes(self.read_bytes(*size)?.to_vec())
+                    }
+
+                    None => return Result::Err("invalid recursive schema".to_string()),
+                }
+            }
+        };
+
+        Result::Ok(value)
+    }
+}
+
+/// Decompress a block of an object container file.
+fn decompress_block(codec: &str, block: &[u8]) -> Result<Vec<u8>, String> {
+    match codec {
+        "null" => Result::Ok(block.to_vec()),
+
+        #[cfg(feature = "compression")]
+        "deflate" => {
+            use std::io::Read;
+
+            let mut buf = Vec::new();
+            flate2::read::DeflateDecoder::new(block)
+                .read_to_end(&mut buf)
+                .map_err(|err| err.to_string())?;
+            Result::Ok(buf)
+        }
+
+        #[cfg(feature = "compression")]
+        "zstandard" => zstd::decode_all(block).map_err(|err| err.to_string()),
+
+        _ => Result::Err(format!("unsupported Avro codec {}", codec)),
+    }
+}
+
+/// Read the records of an object container file.
+fn read_container(contents: &[u8]) -> Result<Vec<AvroValue>, String> {
+    if !contents.starts_with(MAGIC) {
+        return Result::Err("not an Avro object container file".to_string());
+    }
+
+    let mut header = Decoder {
+        data: contents,
+        pos: MAGIC.len(),
+        named: &[],
+        depth: 0,
+    };
+
+    // each entry has a key and a value, each with at least a length
+    let metadata: HashMap<String, Vec<u8>> = header
+        .read_blocks(2, |decoder| {
+            let key = decoder.read_string()?;
+            let len = decoder.read_len()?;
+            Result::Ok((key, decoder.read_bytes(len)?.to_vec()))
+        })?
+        .into_iter()
+        .collect();
+
+    let sync = header.read_bytes(SYNC_SIZE)?;
+
+    let schema_json: JsonValue = metadata
+        .get("avro.schema")
+        .ok_or_else(|| "Avro file is missing a schema".to_string())
+        .and_then(|schema| {
+            serde_json::from_slice(schema).map_err(|err| format!("invalid Avro schema: {}", err))
+        })?;
+
+    let codec = match metadata.get("avro.codec") {
+        Some(codec) => String::from_utf8_lossy(codec).to_string(),
+        None => "null".to_string(),
+    };
+
+    let mut parser = SchemaParser::default();
+    let schema = parser.parse(&schema_json, None)?;
+    let named = parser.named;
+
+    let mut records = Vec::new();
+    while !header.is_empty() {
+        let count = header.read_len()?;
+        let size = header.read_len()?;
+        let block = decompress_block(&codec, header.read_bytes(size)?)?;
+
+        let mut decoder = Decoder {
+            data: &block,
+            pos: 0,
+            named: &named,
+            depth: 0,
+        };
+
+        decoder.check_count(count as u64, decoder.min_size(&schema))?;
+        for _ in 0..count {
+            records.push(decoder.read_value(&schema)?);
+        }
+
+        if header.read_bytes(SYNC_SIZE)? != sync {
+            return Result::Err(format!(
+                "invalid sync marker at byte {}",
+                header.pos - SYNC_SIZE
+            ));
+        }
+    }
+
+    Result::Ok(records)
+}
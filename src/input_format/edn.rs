@@ -0,0 +1,433 @@
+use super::{text_start, DecodedDocument, InputFormat, InputFormatData};
+use crate::datetime::DatetimeStr;
+use serde::ser::{Serialize, SerializeMap, SerializeSeq, SerializeTupleVariant, Serializer};
+
+/// Type name of variants that represent EDN values without a Serde
+/// counterpart, such as symbols and keywords.
+pub const EDN_TYPE_NAME: &str = "Edn";
+
+/// [EDN](https://github.com/edn-format/edn) (extensible data notation) files,
+/// as used by Clojure and Datomic. Since s-expressions are valid EDN, this
+/// format can also read lisp-style data.
+///
+/// Lists and vectors are extracted as sequences, and maps as maps (whose keys
+/// can be any value). Values without a Serde counterpart are extracted as
+/// variants of type `Edn`:
+///
+/// - symbols are `Symbol` newtype variants containing the symbol's name,
+/// - keywords are `Keyword` newtype variants containing the keyword's name,
+///   without the leading colon,
+/// - sets are `Set` newtype variants containing a sequence of the elements,
+/// - tagged literals (e.g. `#inst "2024-01-01"`) are `Tagged` tuple variants
+///   containing the tag, without the leading `#`, and the tagged value; the
+///   strings of `#inst` literals are date-times,
+/// - integers with the `N` suffix or that do not fit in 64 bits are `BigInt`
+///   newtype variants, and decimals with the `M` suffix are `BigDecimal`
+///   newtype variants, both containing the number as a string.
+///
+/// If a file contains multiple top-level values, each is extracted as a
+/// separate root value.
+pub struct InputFormatEDN;
+
+impl InputFormat for InputFormatEDN {
+    fn name(&self) -> &'static str {
+        "edn"
+    }
+
+    fn file_extensions(&self) -> Vec<&'static str> {
+        vec!["edn"]
+    }
+
+    fn media_types(&self) -> Vec<&'static str> {
+        vec!["application/edn"]
+    }
+
+    fn create<'input>(
+        &self,
+        _contents: &'input str,
+    ) -> Result<Box<dyn InputFormatData<'input> + 'input>, String> {
+        Result::Err("EDN values are decoded directly".to_string())
+    }
+
+    fn decode(&self, contents: &[u8]) -> Option<Result<Vec<DecodedDocument>, String>> {
+        let documents = std::str::from_utf8(contents)
+            .map_err(|err| err.to_string())
+            .and_then(|text| Parser::new(text).parse_all())
+            .map(|values| {
+                let multiple = values.len() > 1;
+                values
+                    .into_iter()
+                    .enumerate()
+                    .map(|(i, value)| DecodedDocument {
+                        position: if multiple { Some(i + 1) } else { None },
+                        value: Box::new(value),
+                    })
+                    .collect()
+            });
+
+        Some(documents)
+    }
+
+    fn has_string_keys(&self) -> bool {
+        false
+    }
+
+    /// Inputs starting with a map with keyword keys (e.g. `{:name "x"}`) or a
+    /// set are detected as EDN.
+    fn detect(&self, prefix: &[u8]) -> bool {
+        let text = text_start(prefix);
+        text.starts_with(b"#{")
+            || (text.starts_with(b"{") && text[1..].trim_ascii_start().starts_with(b":"))
+    }
+}
+
+/// A value read from an EDN file.
+enum EdnValue {
+    Nil,
+    Bool(bool),
+    Int(i64),
+    BigInt(String),
+    Float(f64),
+    BigDecimal(String),
+    Str(String),
+    Char(char),
+    Symbol(String),
+    Keyword(String),
+    Seq(Vec<EdnValue>),
+    Set(Vec<EdnValue>),
+    Map(Vec<(EdnValue, EdnValue)>),
+    Tagged(String, Box<EdnValue>),
+}
+
+impl Serialize for EdnValue {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            EdnValue::Nil => serializer.serialize_unit(),
+            EdnValue::Bool(value) => serializer.serialize_bool(*value),
+            EdnValue::Int(value) => serializer.serialize_i64(*value),
+            EdnValue::Float(value) => serializer.serialize_f64(*value),
+            EdnValue::Str(value) => serializer.serialize_str(value),
+            EdnValue::Char(value) => serializer.serialize_char(*value),
+
+            EdnValue::Symbol(name) => {
+                serializer.serialize_newtype_variant(EDN_TYPE_NAME, 0, "Symbol", name)
+            }
+
+            EdnValue::Keyword(name) => {
+                serializer.serialize_newtype_variant(EDN_TYPE_NAME, 1, "Keyword", name)
+            }
+
+            EdnValue::Set(items) => {
+                serializer.serialize_newtype_variant(EDN_TYPE_NAME, 2, "Set", items)
+            }
+
+            EdnValue::Tagged(tag, value) => {
+                let mut tagged =
+                    serializer.serialize_tuple_variant(EDN_TYPE_NAME, 3, "Tagged", 2)?;
+                tagged.serialize_field(tag)?;
+                match value.as_ref() {
+                    EdnValue::Str(text) if tag == "inst" => {
+                        tagged.serialize_field(&DatetimeStr(text))?
+                    }
+                    value => tagged.serialize_field(value)?,
+                }
+                tagged.end()
+            }
+
+            EdnValue::BigInt(value) => {
+                serializer.serialize_newtype_variant(EDN_TYPE_NAME, 4, "BigInt", value)
+            }
+
+            EdnValue::BigDecimal(value) => {
+                serializer.serialize_newtype_variant(EDN_TYPE_NAME, 5, "BigDecimal", value)
+            }
+
+            EdnValue::Seq(items) => {
+                let mut seq = serializer.serialize_seq(Some(items.len()))?;
+                for item in items.iter() {
+                    seq.serialize_element(item)?;
+                }
+                seq.end()
+            }
+
+            EdnValue::Map(entries) => {
+                let mut map = serializer.serialize_map(Some(entries.len()))?;
+                for (key, value) in entries.iter() {
+                    map.serialize_entry(key, value)?;
+                }
+                map.end()
+            }
+        }
+    }
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl Parser {
+    fn new(text: &str) -> Self {
+        Parser {
+            chars: text.chars().collect(),
+            pos: 0,
+        }
+    }
+
+    /// Create an error message that includes the current position.
+    fn error(&self, msg: &str) -> String {
+        let consumed = &self.chars[..self.pos.min(self.chars.len())];
+        let line = consumed.iter().filter(|c| **c == '\n').count() + 1;
+        let column = consumed.iter().rev().take_while(|c| **c != '\n').count() + 1;
+        format!("{} at line {} column {}", msg, line, column)
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.peek();
+        self.pos += 1;
+        c
+    }
+
+    fn is_delimiter(c: char) -> bool {
+        c.is_whitespace() || matches!(c, ',' | '(' | ')' | '[' | ']' | '{' | '}' | '"' | ';')
+    }
+
+    /// Skip whitespace, commas, and comments.
+    fn skip_whitespace(&mut self) {
+        while let Some(c) = self.peek() {
+            if c.is_whitespace() || c == ',' {
+                self.pos += 1;
+            } else if c == ';' {
+                while !matches!(self.next(), Some('\n') | None) {}
+            } else {
+                break;
+            }
+        }
+    }
+
+    fn parse_all(&mut self) -> Result<Vec<EdnValue>, String> {
+        let mut values = Vec::new();
+        while let Some(value) = self.parse_value(None)? {
+            values.push(value);
+        }
+
+        Result::Ok(values)
+    }
+
+    /// Parse the next value, or return `None` if the input or the enclosing
+    /// collection (ending with `close`) ends.
+    fn parse_value(&mut self, close: Option<char>) -> Result<Option<EdnValue>, String> {
+        self.skip_whitespace();
+
+        let c = match self.peek() {
+            Some(c) => c,
+            None if close.is_some() => return Result::Err(self.error("unexpected end of input")),
+            None => return Result::Ok(None),
+        };
+
+        if Some(c) == close {
+            self.pos += 1;
+            return Result::Ok(None);
+        }
+
+        let value = match c {
+            '(' => {
+                self.pos += 1;
+                EdnValue::Seq(self.parse_items(')')?)
+            }
+
+            '[' => {
+                self.pos += 1;
+                EdnValue::Seq(self.parse_items(']')?)
+            }
+
+            '{' => {
+                self.pos += 1;
+                let items = self.parse_items('}')?;
+                if items.len() % 2 != 0 {
+                    return Result::Err(self.error("map has a key without a value"));
+                }
+
+                let mut entries = Vec::new();
+                let mut iter = items.into_iter();
+                while let (Some(key), Some(value)) = (iter.next(), iter.next()) {
+                    entries.push((key, value));
+                }
+                EdnValue::Map(entries)
+            }
+
+            '"' => {
+                self.pos += 1;
+                EdnValue::Str(self.parse_string()?)
+            }
+
+            '\\' => {
+                self.pos += 1;
+                EdnValue::Char(self.parse_char()?)
+            }
+
+            '#' => {
+                self.pos += 1;
+                match self.peek() {
+                    Some('{') => {
+                        self.pos += 1;
+                        EdnValue::Set(self.parse_items('}')?)
+                    }
+
+                    // discard the next value
+                    Some('_') => {
+                        self.pos += 1;
+                        if self.parse_value(None)?.is_none() {
+                            return Result::Err(self.error("expected a value to discard"));
+                        }
+                        return self.parse_value(close);
+                    }
+
+                    Some(c) if c.is_alphabetic() => {
+                        let tag = self.parse_token();
+                        let value = self
+                            .parse_value(None)?
+                            .ok_or_else(|| self.error("expected a value after tag"))?;
+                        EdnValue::Tagged(tag, Box::new(value))
+                    }
+
+                    _ => return Result::Err(self.error("invalid dispatch character")),
+                }
+            }
+
+            ')' | ']' | '}' => return Result::Err(self.error("unexpected closing delimiter")),
+
+            ':' => {
+                self.pos += 1;
+                let name = self.parse_token();
+                if name.is_empty() {
+                    return Result::Err(self.error("empty keyword"));
+                }
+                EdnValue::Keyword(name)
+            }
+
+            _ => {
+                let token = self.parse_token();
+                self.parse_atom(&token)?
+            }
+        };
+
+        Result::Ok(Some(value))
+    }
+
+    fn parse_items(&mut self, close: char) -> Result<Vec<EdnValue>, String> {
+        let mut items = Vec::new();
+        while let Some(item) = self.parse_value(Some(close))? {
+            items.push(item);
+        }
+
+        Result::Ok(items)
+    }
+
+    fn parse_token(&mut self) -> String {
+        let mut token = String::new();
+        while let Some(c) = self.peek().filter(|c| !Self::is_delimiter(*c)) {
+            token.push(c);
+            self.pos += 1;
+        }
+
+        token
+    }
+
+    fn parse_atom(&self, token: &str) -> Result<EdnValue, String> {
+        let value = match token {
+            "nil" => EdnValue::Nil,
+            "true" => EdnValue::Bool(true),
+            "false" => EdnValue::Bool(false),
+            _ => {
+                let unsigned = token.strip_prefix(['+', '-']).unwrap_or(token);
+                if !unsigned.starts_with(|c: char| c.is_ascii_digit()) {
+                    return Result::Ok(EdnValue::Symbol(token.to_string()));
+                }
+
+                let invalid = || self.error(&format!("invalid number {}", token));
+
+                if let Some(int) = token.strip_suffix('N') {
+                    int.parse::<i128>().map_err(|_| invalid())?;
+                    EdnValue::BigInt(int.to_string())
+                } else if let Some(decimal) = token.strip_suffix('M') {
+                    decimal.parse::<f64>().map_err(|_| invalid())?;
+                    EdnValue::BigDecimal(decimal.to_string())
+                } else if unsigned.contains(['.', 'e', 'E']) {
+                    EdnValue::Float(token.parse().map_err(|_| invalid())?)
+                } else {
+                    match token.parse::<i64>() {
+                        Result::Ok(int) => EdnValue::Int(int),
+                        Result::Err(_) => {
+                            token.parse::<i128>().map_err(|_| invalid())?;
+                            EdnValue::BigInt(token.trim_start_matches('+').to_string())
+                        }
+                    }
+                }
+            }
+        };
+
+        Result::Ok(value)
+    }
+
+    fn parse_string(&mut self) -> Result<String, String> {
+        let mut string = String::new();
+        loop {
+            match self.next() {
+                Some('"') => return Result::Ok(string),
+                Some('\\') => {
+                    let escaped = match self.next() {
+                        Some('t') => '\t',
+                        Some('r') => '\r',
+                        Some('n') => '\n',
+                        Some('\\') => '\\',
+                        Some('"') => '"',
+                        Some('u') => self.parse_unicode()?,
+                        _ => return Result::Err(self.error("invalid escape sequence")),
+                    };
+                    string.push(escaped);
+                }
+                Some(c) => string.push(c),
+                None => return Result::Err(self.error("unterminated string")),
+            }
+        }
+    }
+
+    fn parse_char(&mut self) -> Result<char, String> {
+        let first = self
+            .next()
+            .ok_or_else(|| self.error("unexpected end of input"))?;
+
+        let mut name = first.to_string();
+        while let Some(c) = self.peek().filter(|c| !Self::is_delimiter(*c)) {
+            name.push(c);
+            self.pos += 1;
+        }
+
+        match name.as_str() {
+            "newline" => Result::Ok('\n'),
+            "return" => Result::Ok('\r'),
+            "space" => Result::Ok(' '),
+            "tab" => Result::Ok('\t'),
+            _ if name.chars().count() == 1 => Result::Ok(first),
+            _ if name.starts_with('u') && name.len() == 5 => {
+                self.pos -= 4;
+                self.parse_unicode()
+            }
+            _ => Result::Err(self.error(&format!("invalid character \\{}", name))),
+        }
+    }
+
+    /// Parse the four hex digits of a unicode escape.
+    fn parse_unicode(&mut self) -> Result<char, String> {
+        let digits: String = (0..4).filter_map(|_| self.next()).collect();
+        u32::from_str_radix(&digits, 16)
+            .ok()
+            .and_then(char::from_u32)
+            .ok_or_else(|| self.error("invalid unicode escape"))
+    }
+}
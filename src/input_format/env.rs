@@ -0,0 +1,123 @@
+use super::{InputFormat, InputFormatData};
+use erased_serde::Deserializer as ErasedDeserializer;
+use serde::de::{value::Error as ValueError, IntoDeserializer};
+use std::{collections::BTreeMap, marker::PhantomData};
+
+/// Environment files (`.env`), extracted as a map from variable names to
+/// values. Lines have the form `KEY=VALUE`, optionally prefixed by `export`;
+/// blank lines and lines starting with `#` are ignored.
+///
+/// Values may be quoted: escape sequences are interpreted in double-quoted
+/// values, and single-quoted values are taken literally. Unquoted values end
+/// at a `#` comment preceded by whitespace. References to other variables
+/// (e.g. `${HOME}`) are not expanded, so values are extracted as written.
+pub struct InputFormatEnv;
+
+impl InputFormat for InputFormatEnv {
+    fn name(&self) -> &'static str {
+        "env"
+    }
+
+    fn file_extensions(&self) -> Vec<&'static str> {
+        vec!["env"]
+    }
+
+    fn file_names(&self) -> Vec<&'static str> {
+        vec![
+            ".env",
+            ".env.local",
+            ".env.development",
+            ".env.production",
+            ".env.test",
+            ".env.example",
+        ]
+    }
+
+    fn create<'input>(
+        &self,
+        contents: &'input str,
+    ) -> Result<Box<dyn InputFormatData<'input> + 'input>, String> {
+        let mut vars: BTreeMap<String, String> = BTreeMap::new();
+
+        for (i, line) in contents.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let (key, value) =
+                parse_line(line).map_err(|msg| format!("line {}: {}", i + 1, msg))?;
+            vars.insert(key, value);
+        }
+
+        Result::Ok(Box::new(InputFormatDataEnv {
+            vars,
+            contents: PhantomData,
+        }))
+    }
+
+    fn has_string_keys(&self) -> bool {
+        true
+    }
+}
+
+/// Parse a non-empty, non-comment line into a variable name and value.
+fn parse_line(line: &str) -> Result<(String, String), String> {
+    let line = line.strip_prefix("export ").unwrap_or(line);
+
+    let (key, value) = line
+        .split_once('=')
+        .ok_or_else(|| "expected KEY=VALUE".to_string())?;
+
+    let key = key.trim();
+    if key.is_empty() || key.contains(char::is_whitespace) {
+        return Result::Err(format!("invalid variable name '{}'", key));
+    }
+
+    let value = value.trim_start();
+    let value = if let Some(quoted) = value.strip_prefix('"') {
+        let mut unescaped = String::new();
+        let mut chars = quoted.chars();
+        loop {
+            match chars.next() {
+                Some('"') => break,
+                Some('\\') => match chars.next() {
+                    Some('n') => unescaped.push('\n'),
+                    Some('t') => unescaped.push('\t'),
+                    Some('r') => unescaped.push('\r'),
+                    Some(c) => unescaped.push(c),
+                    None => return Result::Err("unterminated escape sequence".to_string()),
+                },
+                Some(c) => unescaped.push(c),
+                None => return Result::Err("unterminated double-quoted value".to_string()),
+            }
+        }
+        unescaped
+    } else if let Some(quoted) = value.strip_prefix('\'') {
+        let (literal, _) = quoted
+            .split_once('\'')
+            .ok_or_else(|| "unterminated single-quoted value".to_string())?;
+        literal.to_string()
+    } else {
+        let end = value.find(" #").or_else(|| value.find("\t#"));
+        value[..end.unwrap_or(value.len())].trim_end().to_string()
+    };
+
+    Result::Ok((key.to_string(), value))
+}
+
+pub struct InputFormatDataEnv<'a> {
+    vars: BTreeMap<String, String>,
+    contents: PhantomData<&'a str>,
+}
+
+impl<'input> InputFormatData<'input> for InputFormatDataEnv<'input> {
+    fn deserializer<'de>(&'de mut self) -> Box<dyn ErasedDeserializer<'input> + 'de> {
+        let vars = std::mem::take(&mut self.vars);
+        Box::new(<dyn ErasedDeserializer<'input>>::erase(IntoDeserializer::<
+            ValueError,
+        >::into_deserializer(
+            vars
+        )))
+    }
+}
@@ -0,0 +1,981 @@
+use super::{static_name, DecodedDocument, InputFormat, InputFormatData};
+use serde::ser::{Serialize, SerializeMap, SerializeStruct, SerializeStructVariant, Serializer};
+use serde_json::Value as JsonValue;
+
+/// Type name of the variants of type definitions in GraphQL schemas.
+pub const TYPE_DEFINITION_NAME: &str = "TypeDefinition";
+
+/// [GraphQL](https://spec.graphql.org) schemas in SDL (schema definition
+/// language) files, with extensions `.graphql`, `.graphqls`, and `.gql`.
+///
+/// A schema is extracted as a `GraphQLSchema` struct with the fields:
+///
+/// - `schema`, a sequence of `SchemaDefinition` structs with an `operations`
+///   map from operation types (e.g. `query`) to root type names;
+/// - `types`, a sequence of type definitions, which are struct variants of
+///   type `TypeDefinition` named after the kind of the type (`Scalar`,
+///   `Object`, `Interface`, `Union`, `Enum`, or `InputObject`);
+/// - `directives`, a sequence of `DirectiveDefinition` structs.
+///
+/// Fields are `FieldDefinition` structs and arguments and input fields are
+/// `InputValue` structs, whose `type` field is the type as written (e.g.
+/// `[User!]!`) and whose `named_type` field is the name of the type without
+/// list and non-null wrappers (e.g. `User`). Descriptions are extracted when
+/// present, and default values and directive arguments as their GraphQL text.
+/// Definitions in `extend` blocks have an `extend` field that is true.
+pub struct InputFormatGraphQL;
+
+impl InputFormat for InputFormatGraphQL {
+    fn name(&self) -> &'static str {
+        "graphql"
+    }
+
+    fn file_extensions(&self) -> Vec<&'static str> {
+        vec!["graphql", "graphqls", "gql"]
+    }
+
+    fn media_types(&self) -> Vec<&'static str> {
+        vec!["application/graphql"]
+    }
+
+    fn create<'input>(
+        &self,
+        _contents: &'input str,
+    ) -> Result<Box<dyn InputFormatData<'input> + 'input>, String> {
+        Result::Err("GraphQL schemas are decoded directly".to_string())
+    }
+
+    fn decode(&self, contents: &[u8]) -> Option<Result<Vec<DecodedDocument>, String>> {
+        let documents = std::str::from_utf8(contents)
+            .map_err(|err| err.to_string())
+            .and_then(|text| Parser::new(text)?.parse_document())
+            .map(|schema| {
+                vec![DecodedDocument {
+                    position: None,
+                    value: Box::new(schema),
+                }]
+            });
+
+        Some(documents)
+    }
+
+    fn has_string_keys(&self) -> bool {
+        true
+    }
+}
+
+/// JSON responses of GraphQL servers, i.e. objects with `data`, `errors`,
+/// and `extensions` fields. This format has no file extensions, since
+/// responses are JSON files, and must be selected explicitly.
+///
+/// A response is extracted as a `GraphQLResponse` struct. Objects with a
+/// `__typename` field, which clients request to identify the types of
+/// objects, are extracted as structs whose type is the `__typename` value,
+/// so that data can be joined with the type definitions of the schema on
+/// type and field names, which share symbols. Other objects are extracted as
+/// maps. Since Serde requires static names for structs and fields, type and
+/// field names of objects with a `__typename` field are leaked once per
+/// process.
+pub struct InputFormatGraphQLResponse;
+
+impl InputFormat for InputFormatGraphQLResponse {
+    fn name(&self) -> &'static str {
+        "graphql_response"
+    }
+
+    fn file_extensions(&self) -> Vec<&'static str> {
+        vec![]
+    }
+
+    fn media_types(&self) -> Vec<&'static str> {
+        vec!["application/graphql-response+json"]
+    }
+
+    fn create<'input>(
+        &self,
+        _contents: &'input str,
+    ) -> Result<Box<dyn InputFormatData<'input> + 'input>, String> {
+        Result::Err("GraphQL responses are decoded directly".to_string())
+    }
+
+    fn decode(&self, contents: &[u8]) -> Option<Result<Vec<DecodedDocument>, String>> {
+        let documents = serde_json::from_slice::<JsonValue>(contents)
+            .map_err(|err| err.to_string())
+            .and_then(|json| match json {
+                JsonValue::Object(_) => Result::Ok(vec![DecodedDocument {
+                    position: None,
+                    value: Box::new(Response(json)),
+                }]),
+                _ => Result::Err("GraphQL response must be an object".to_string()),
+            });
+
+        Some(documents)
+    }
+
+    fn has_string_keys(&self) -> bool {
+        true
+    }
+}
+
+struct Response(JsonValue);
+
+impl Serialize for Response {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let fields = match &self.0 {
+            JsonValue::Object(fields) => fields,
+            _ => unreachable!(),
+        };
+
+        let mut s = serializer.serialize_struct("GraphQLResponse", fields.len())?;
+        for (name, value) in fields.iter() {
+            s.serialize_field(static_name(name), &ResponseValue(value))?;
+        }
+        s.end()
+    }
+}
+
+struct ResponseValue<'a>(&'a JsonValue);
+
+impl Serialize for ResponseValue<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self.0 {
+            JsonValue::Array(items) => serializer.collect_seq(items.iter().map(ResponseValue)),
+
+            JsonValue::Object(fields) => match fields.get("__typename") {
+                Some(JsonValue::String(type_name)) => {
+                    let mut s =
+                        serializer.serialize_struct(static_name(type_name), fields.len())?;
+                    for (name, value) in fields.iter() {
+                        s.serialize_field(static_name(name), &ResponseValue(value))?;
+                    }
+                    s.end()
+                }
+
+                _ => {
+                    let mut map = serializer.serialize_map(Some(fields.len()))?;
+                    for (name, value) in fields.iter() {
+                        map.serialize_entry(name, &ResponseValue(value))?;
+                    }
+                    map.end()
+                }
+            },
+
+            value => value.serialize(serializer),
+        }
+    }
+}
+
+/// A GraphQL schema read from an SDL file.
+#[derive(Default)]
+struct Schema {
+    schema: Vec<SchemaDefinition>,
+    types: Vec<TypeDefinition>,
+    directives: Vec<DirectiveDefinition>,
+}
+
+impl Serialize for Schema {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut s = serializer.serialize_struct("GraphQLSchema", 3)?;
+        s.serialize_field("schema", &self.schema)?;
+        s.serialize_field("types", &self.types)?;
+        s.serialize_field("directives", &self.directives)?;
+        s.end()
+    }
+}
+
+struct SchemaDefinition {
+    description: Option<String>,
+    directives: Vec<Directive>,
+    operations: Vec<(String, String)>,
+    extend: bool,
+}
+
+impl Serialize for SchemaDefinition {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut s = serializer.serialize_struct("SchemaDefinition", 4)?;
+        if let Some(description) = &self.description {
+            s.serialize_field("description", description)?;
+        }
+        s.serialize_field("directives", &self.directives)?;
+        s.serialize_field("operations", &Pairs(&self.operations))?;
+        s.serialize_field("extend", &self.extend)?;
+        s.end()
+    }
+}
+
+/// Map of strings, in the order in which they appear in the schema.
+struct Pairs<'a>(&'a [(String, String)]);
+
+impl Serialize for Pairs<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(self.0.len()))?;
+        for (key, value) in self.0.iter() {
+            map.serialize_entry(key, value)?;
+        }
+        map.end()
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum TypeKind {
+    Scalar,
+    Object,
+    Interface,
+    Union,
+    Enum,
+    InputObject,
+}
+
+impl TypeKind {
+    fn variant(&self) -> (u32, &'static str) {
+        match self {
+            TypeKind::Scalar => (0, "Scalar"),
+            TypeKind::Object => (1, "Object"),
+            TypeKind::Interface => (2, "Interface"),
+            TypeKind::Union => (3, "Union"),
+            TypeKind::Enum => (4, "Enum"),
+            TypeKind::InputObject => (5, "InputObject"),
+        }
+    }
+}
+
+/// A type definition. Only the fields relevant to the kind of the type are
+/// extracted, e.g. `members` for unions.
+struct TypeDefinition {
+    kind: TypeKind,
+    name: String,
+    description: Option<String>,
+    directives: Vec<Directive>,
+    extend: bool,
+    interfaces: Vec<String>,
+    fields: Vec<FieldDefinition>,
+    input_fields: Vec<InputValue>,
+    members: Vec<String>,
+    values: Vec<EnumValue>,
+}
+
+impl Serialize for TypeDefinition {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let (index, variant) = self.kind.variant();
+        let mut s = serializer.serialize_struct_variant(TYPE_DEFINITION_NAME, index, variant, 5)?;
+        s.serialize_field("name", &self.name)?;
+        if let Some(description) = &self.description {
+            s.serialize_field("description", description)?;
+        }
+        s.serialize_field("directives", &self.directives)?;
+        s.serialize_field("extend", &self.extend)?;
+
+        match self.kind {
+            TypeKind::Scalar => {}
+
+            TypeKind::Object | TypeKind::Interface => {
+                s.serialize_field("interfaces", &self.interfaces)?;
+                s.serialize_field("fields", &self.fields)?;
+            }
+
+            TypeKind::Union => s.serialize_field("members", &self.members)?,
+
+            TypeKind::Enum => s.serialize_field("values", &self.values)?,
+
+            TypeKind::InputObject => s.serialize_field("fields", &self.input_fields)?,
+        }
+        s.end()
+    }
+}
+
+/// A type reference, e.g. `[User!]!`.
+struct TypeRef {
+    text: String,
+    named_type: String,
+}
+
+struct FieldDefinition {
+    name: String,
+    description: Option<String>,
+    arguments: Vec<InputValue>,
+    ty: TypeRef,
+    directives: Vec<Directive>,
+}
+
+impl Serialize for FieldDefinition {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut s = serializer.serialize_struct("FieldDefinition", 6)?;
+        s.serialize_field("name", &self.name)?;
+        if let Some(description) = &self.description {
+            s.serialize_field("description", description)?;
+        }
+        s.serialize_field("arguments", &self.arguments)?;
+        s.serialize_field("type", &self.ty.text)?;
+        s.serialize_field("named_type", &self.ty.named_type)?;
+        s.serialize_field("directives", &self.directives)?;
+        s.end()
+    }
+}
+
+struct InputValue {
+    name: String,
+    description: Option<String>,
+    ty: TypeRef,
+    default_value: Option<String>,
+    directives: Vec<Directive>,
+}
+
+impl Serialize for InputValue {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut s = serializer.serialize_struct("InputValue", 6)?;
+        s.serialize_field("name", &self.name)?;
+        if let Some(description) = &self.description {
+            s.serialize_field("description", description)?;
+        }
+        s.serialize_field("type", &self.ty.text)?;
+        s.serialize_field("named_type", &self.ty.named_type)?;
+        if let Some(default_value) = &self.default_value {
+            s.serialize_field("default_value", default_value)?;
+        }
+        s.serialize_field("directives", &self.directives)?;
+        s.end()
+    }
+}
+
+struct EnumValue {
+    name: String,
+    description: Option<String>,
+    directives: Vec<Directive>,
+}
+
+impl Serialize for EnumValue {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut s = serializer.serialize_struct("EnumValue", 3)?;
+        s.serialize_field("name", &self.name)?;
+        if let Some(description) = &self.description {
+            s.serialize_field("description", description)?;
+        }
+        s.serialize_field("directives", &self.directives)?;
+        s.end()
+    }
+}
+
+/// A directive applied to a definition, e.g. `@deprecated(reason: "old")`.
+struct Directive {
+    name: String,
+    arguments: Vec<(String, String)>,
+}
+
+impl Serialize for Directive {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut s = serializer.serialize_struct("Directive", 2)?;
+        s.serialize_field("name", &self.name)?;
+        s.serialize_field("arguments", &Pairs(&self.arguments))?;
+        s.end()
+    }
+}
+
+struct DirectiveDefinition {
+    name: String,
+    description: Option<String>,
+    arguments: Vec<InputValue>,
+    repeatable: bool,
+    locations: Vec<String>,
+}
+
+impl Serialize for DirectiveDefinition {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut s = serializer.serialize_struct("DirectiveDefinition", 5)?;
+        s.serialize_field("name", &self.name)?;
+        if let Some(description) = &self.description {
+            s.serialize_field("description", description)?;
+        }
+        s.serialize_field("arguments", &self.arguments)?;
+        s.serialize_field("repeatable", &self.repeatable)?;
+        s.serialize_field("locations", &self.locations)?;
+        s.end()
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Name(String),
+    Punct(char),
+    Str(String),
+    Number(String),
+}
+
+impl Token {
+    fn describe(&self) -> String {
+        match self {
+            Token::Name(name) => format!("`{}`", name),
+            Token::Punct(c) => format!("`{}`", c),
+            Token::Str(_) => "string".to_string(),
+            Token::Number(number) => format!("`{}`", number),
+        }
+    }
+}
+
+/// Tokens of a GraphQL document with the lines they start on.
+struct Parser {
+    tokens: Vec<(Token, usize)>,
+    pos: usize,
+}
+
+impl Parser {
+    fn new(text: &str) -> Result<Self, String> {
+        Result::Ok(Parser {
+            tokens: Lexer::new(text).tokenize()?,
+            pos: 0,
+        })
+    }
+
+    /// Create an error message that includes the line of the current token.
+    fn error(&self, msg: &str) -> String {
+        match self.tokens.get(self.pos) {
+            Some((token, line)) => format!("{}, found {} at line {}", msg, token.describe(), line),
+            None => format!("{}, found end of input", msg),
+        }
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos).map(|(token, _)| token)
+    }
+
+    fn peek_name(&self) -> Option<&str> {
+        match self.peek() {
+            Some(Token::Name(name)) => Some(name),
+            _ => None,
+        }
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).map(|(token, _)| token.clone());
+        self.pos += 1;
+        token
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        if self.peek() == Some(&Token::Punct(c)) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), String> {
+        if self.eat(c) {
+            Result::Ok(())
+        } else {
+            Result::Err(self.error(&format!("expected `{}`", c)))
+        }
+    }
+
+    fn eat_keyword(&mut self, keyword: &str) -> bool {
+        if self.peek_name() == Some(keyword) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn parse_name(&mut self) -> Result<String, String> {
+        match self.peek() {
+            Some(Token::Name(name)) => {
+                let name = name.clone();
+                self.pos += 1;
+                Result::Ok(name)
+            }
+            _ => Result::Err(self.error("expected a name")),
+        }
+    }
+
+    fn parse_description(&mut self) -> Option<String> {
+        match self.peek() {
+            Some(Token::Str(description)) => {
+                let description = description.clone();
+                self.pos += 1;
+                Some(description)
+            }
+            _ => None,
+        }
+    }
+
+    fn parse_document(&mut self) -> Result<Schema, String> {
+        let mut schema = Schema::default();
+        while self.peek().is_some() {
+            let description = self.parse_description();
+            let extend = self.eat_keyword("extend");
+            let keyword = self.parse_name()?;
+
+            let kind = match keyword.as_str() {
+                "schema" => {
+                    schema
+                        .schema
+                        .push(self.parse_schema_definition(description, extend)?);
+                    continue;
+                }
+
+                "directive" if !extend => {
+                    schema
+                        .directives
+                        .push(self.parse_directive_definition(description)?);
+                    continue;
+                }
+
+                "scalar" => TypeKind::Scalar,
+                "type" => TypeKind::Object,
+                "interface" => TypeKind::Interface,
+                "union" => TypeKind::Union,
+                "enum" => TypeKind::Enum,
+                "input" => TypeKind::InputObject,
+
+                "query" | "mutation" | "subscription" | "fragment" => {
+                    self.pos -= 1;
+                    return Result::Err(
+                        self.error("expected a type system definition, not an operation"),
+                    );
+                }
+
+                _ => {
+                    self.pos -= 1;
+                    return Result::Err(self.error("expected a type system definition"));
+                }
+            };
+
+            schema
+                .types
+                .push(self.parse_type_definition(kind, description, extend)?);
+        }
+
+        Result::Ok(schema)
+    }
+
+    fn parse_schema_definition(
+        &mut self,
+        description: Option<String>,
+        extend: bool,
+    ) -> Result<SchemaDefinition, String> {
+        let directives = self.parse_directives()?;
+        let mut operations = Vec::new();
+        if self.eat('{') {
+            while !self.eat('}') {
+                let operation = self.parse_name()?;
+                self.expect(':')?;
+                operations.push((operation, self.parse_name()?));
+            }
+        }
+
+        Result::Ok(SchemaDefinition {
+            description,
+            directives,
+            operations,
+            extend,
+        })
+    }
+
+    fn parse_directive_definition(
+        &mut self,
+        description: Option<String>,
+    ) -> Result<DirectiveDefinition, String> {
+        self.expect('@')?;
+        let name = self.parse_name()?;
+        let arguments = self.parse_arguments_definition()?;
+        let repeatable = self.eat_keyword("repeatable");
+        if !self.eat_keyword("on") {
+            return Result::Err(self.error("expected `on`"));
+        }
+
+        self.eat('|');
+        let mut locations = vec![self.parse_name()?];
+        while self.eat('|') {
+            locations.push(self.parse_name()?);
+        }
+
+        Result::Ok(DirectiveDefinition {
+            name,
+            description,
+            arguments,
+            repeatable,
+            locations,
+        })
+    }
+
+    fn parse_type_definition(
+        &mut self,
+        kind: TypeKind,
+        description: Option<String>,
+        extend: bool,
+    ) -> Result<TypeDefinition, String> {
+        let mut definition = TypeDefinition {
+            kind,
+            name: self.parse_name()?,
+            description,
+            directives: Vec::new(),
+            extend,
+            interfaces: Vec::new(),
+            fields: Vec::new(),
+            input_fields: Vec::new(),
+            members: Vec::new(),
+            values: Vec::new(),
+        };
+
+        if matches!(kind, TypeKind::Object | TypeKind::Interface) && self.eat_keyword("implements")
+        {
+            self.eat('&');
+            definition.interfaces.push(self.parse_name()?);
+            while self.eat('&') {
+                definition.interfaces.push(self.parse_name()?);
+            }
+        }
+
+        definition.directives = self.parse_directives()?;
+
+        match kind {
+            TypeKind::Scalar => {}
+
+            TypeKind::Object | TypeKind::Interface => {
+                if self.eat('{') {
+                    while !self.eat('}') {
+                        definition.fields.push(self.parse_field_definition()?);
+                    }
+                }
+            }
+
+            TypeKind::Union => {
+                if self.eat('=') {
+                    self.eat('|');
+                    definition.members.push(self.parse_name()?);
+                    while self.eat('|') {
+                        definition.members.push(self.parse_name()?);
+                    }
+                }
+            }
+
+            TypeKind::Enum => {
+                if self.eat('{') {
+                    while !self.eat('}') {
+                        let description = self.parse_description();
+                        definition.values.push(EnumValue {
+                            name: self.parse_name()?,
+                            description,
+                            directives: self.parse_directives()?,
+                        });
+                    }
+                }
+            }
+
+            TypeKind::InputObject => {
+                if self.eat('{') {
+                    while !self.eat('}') {
+                        definition.input_fields.push(self.parse_input_value()?);
+                    }
+                }
+            }
+        }
+
+        Result::Ok(definition)
+    }
+
+    fn parse_field_definition(&mut self) -> Result<FieldDefinition, String> {
+        let description = self.parse_description();
+        let name = self.parse_name()?;
+        let arguments = self.parse_arguments_definition()?;
+        self.expect(':')?;
+        let ty = self.parse_type()?;
+        let directives = self.parse_directives()?;
+
+        Result::Ok(FieldDefinition {
+            name,
+            description,
+            arguments,
+            ty,
+            directives,
+        })
+    }
+
+    fn parse_arguments_definition(&mut self) -> Result<Vec<InputValue>, String> {
+        let mut arguments = Vec::new();
+        if self.eat('(') {
+            while !self.eat(')') {
+                arguments.push(self.parse_input_value()?);
+            }
+        }
+
+        Result::Ok(arguments)
+    }
+
+    fn parse_input_value(&mut self) -> Result<InputValue, String> {
+        let description = self.parse_description();
+        let name = self.parse_name()?;
+        self.expect(':')?;
+        let ty = self.parse_type()?;
+        let default_value = if self.eat('=') {
+            Some(self.parse_value()?)
+        } else {
+            None
+        };
+        let directives = self.parse_directives()?;
+
+        Result::Ok(InputValue {
+            name,
+            description,
+            ty,
+            default_value,
+            directives,
+        })
+    }
+
+    fn parse_type(&mut self) -> Result<TypeRef, String> {
+        let mut ty = if self.eat('[') {
+            let item = self.parse_type()?;
+            self.expect(']')?;
+            TypeRef {
+                text: format!("[{}]", item.text),
+                named_type: item.named_type,
+            }
+        } else {
+            let name = self.parse_name()?;
+            TypeRef {
+                text: name.clone(),
+                named_type: name,
+            }
+        };
+
+        if self.eat('!') {
+            ty.text.push('!');
+        }
+        Result::Ok(ty)
+    }
+
+    fn parse_directives(&mut self) -> Result<Vec<Directive>, String> {
+        let mut directives = Vec::new();
+        while self.eat('@') {
+            let name = self.parse_name()?;
+            let mut arguments = Vec::new();
+            if self.eat('(') {
+                while !self.eat(')') {
+                    let argument = self.parse_name()?;
+                    self.expect(':')?;
+                    arguments.push((argument, self.parse_value()?));
+                }
+            }
+            directives.push(Directive { name, arguments });
+        }
+
+        Result::Ok(directives)
+    }
+
+    /// Parse a constant value, returning its GraphQL text.
+    fn parse_value(&mut self) -> Result<String, String> {
+        match self.next() {
+            Some(Token::Name(name)) => Result::Ok(name),
+            Some(Token::Number(number)) => Result::Ok(number),
+            Some(Token::Str(string)) => Result::Ok(format!("{:?}", string)),
+
+            Some(Token::Punct('[')) => {
+                let mut items = Vec::new();
+                while !self.eat(']') {
+                    items.push(self.parse_value()?);
+                }
+                Result::Ok(format!("[{}]", items.join(", ")))
+            }
+
+            Some(Token::Punct('{')) => {
+                let mut fields = Vec::new();
+                while !self.eat('}') {
+                    let name = self.parse_name()?;
+                    self.expect(':')?;
+                    fields.push(format!("{}: {}", name, self.parse_value()?));
+                }
+                Result::Ok(format!("{{{}}}", fields.join(", ")))
+            }
+
+            _ => {
+                self.pos -= 1;
+                Result::Err(self.error("expected a value"))
+            }
+        }
+    }
+}
+
+struct Lexer {
+    chars: Vec<char>,
+    pos: usize,
+    line: usize,
+}
+
+impl Lexer {
+    fn new(text: &str) -> Self {
+        Lexer {
+            chars: text.chars().collect(),
+            pos: 0,
+            line: 1,
+        }
+    }
+
+    fn error(&self, msg: &str) -> String {
+        format!("{} at line {}", msg, self.line)
+    }
+
+    fn peek_at(&self, offset: usize) -> Option<char> {
+        self.chars.get(self.pos + offset).copied()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.peek_at(0);
+        if c == Some('\n') {
+            self.line += 1;
+        }
+        self.pos += 1;
+        c
+    }
+
+    fn tokenize(&mut self) -> Result<Vec<(Token, usize)>, String> {
+        let mut tokens = Vec::new();
+        while let Some(c) = self.peek_at(0) {
+            let line = self.line;
+            let token = match c {
+                // commas are insignificant, like whitespace
+                c if c.is_whitespace() || c == ',' || c == '\u{feff}' => {
+                    self.next();
+                    continue;
+                }
+
+                '#' => {
+                    while !matches!(self.next(), Some('\n') | None) {}
+                    continue;
+                }
+
+                '"' if self.peek_at(1) == Some('"') && self.peek_at(2) == Some('"') => {
+                    self.pos += 3;
+                    Token::Str(self.block_string()?)
+                }
+
+                '"' => {
+                    self.pos += 1;
+                    Token::Str(self.string()?)
+                }
+
+                c if c.is_ascii_alphabetic() || c == '_' => {
+                    let mut name = String::new();
+                    while let Some(c) = self
+                        .peek_at(0)
+                        .filter(|c| c.is_ascii_alphanumeric() || *c == '_')
+                    {
+                        name.push(c);
+                        self.pos += 1;
+                    }
+                    Token::Name(name)
+                }
+
+                c if c.is_ascii_digit() || c == '-' => {
+                    let mut number = String::new();
+                    while let Some(c) = self
+                        .peek_at(0)
+                        .filter(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '+' | '.'))
+                    {
+                        number.push(c);
+                        self.pos += 1;
+                    }
+                    Token::Number(number)
+                }
+
+                '{' | '}' | '(' | ')' | '[' | ']' | ':' | '=' | '!' | '@' | '|' | '&' | '$' => {
+                    self.pos += 1;
+                    Token::Punct(c)
+                }
+
+                c => return Result::Err(self.error(&format!("unexpected character `{}`", c))),
+            };
+
+            tokens.push((token, line));
+        }
+
+        Result::Ok(tokens)
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        let mut string = String::new();
+        loop {
+            match self.next() {
+                Some('"') => return Result::Ok(string),
+                Some('\\') => {
+                    let escaped = match self.next() {
+                        Some('"') => '"',
+                        Some('\\') => '\\',
+                        Some('/') => '/',
+                        Some('b') => '\u{8}',
+                        Some('f') => '\u{c}',
+                        Some('n') => '\n',
+                        Some('r') => '\r',
+                        Some('t') => '\t',
+                        Some('u') => {
+                            let digits: String = (0..4).filter_map(|_| self.next()).collect();
+                            u32::from_str_radix(&digits, 16)
+                                .ok()
+                                .and_then(char::from_u32)
+                                .ok_or_else(|| self.error("invalid unicode escape"))?
+                        }
+                        _ => return Result::Err(self.error("invalid escape sequence")),
+                    };
+                    string.push(escaped);
+                }
+                Some('\n') | None => return Result::Err(self.error("unterminated string")),
+                Some(c) => string.push(c),
+            }
+        }
+    }
+
+    /// Read a block string, removing its common indentation and leading and
+    /// trailing blank lines.
+    fn block_string(&mut self) -> Result<String, String> {
+        let mut raw = String::new();
+        loop {
+            match self.next() {
+                Some('"') if self.peek_at(0) == Some('"') && self.peek_at(1) == Some('"') => {
+                    self.pos += 2;
+                    break;
+                }
+                Some('\\')
+                    if self.peek_at(0) == Some('"')
+                        && self.peek_at(1) == Some('"')
+                        && self.peek_at(2) == Some('"') =>
+                {
+                    self.pos += 3;
+                    raw.push_str("\"\"\"");
+                }
+                Some(c) => raw.push(c),
+                None => return Result::Err(self.error("unterminated block string")),
+            }
+        }
+
+        let lines: Vec<&str> = raw.lines().collect();
+        let indent = lines
+            .iter()
+            .skip(1)
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| line.len() - line.trim_start().len())
+            .min()
+            .unwrap_or(0);
+
+        let lines: Vec<&str> = lines
+            .iter()
+            .enumerate()
+            .map(|(i, line)| {
+                if i == 0 {
+                    line
+                } else {
+                    line.get(indent..).unwrap_or("")
+                }
+            })
+            .collect();
+        let start = lines
+            .iter()
+            .position(|line| !line.trim().is_empty())
+            .unwrap_or(lines.len());
+        let end = lines
+            .iter()
+            .rposition(|line| !line.trim().is_empty())
+            .map_or(start, |end| end + 1);
+
+        Result::Ok(lines[start..end].join("\n"))
+    }
+}
@@ -0,0 +1,64 @@
+use super::{InputFormat, InputFormatData};
+use erased_serde::Deserializer as ErasedDeserializer;
+use ini::Ini;
+use serde::de::{value::Error as ValueError, IntoDeserializer};
+use std::{collections::BTreeMap, marker::PhantomData};
+
+/// INI files, extracted as a map from section names to maps from keys to
+/// values. Keys that precede the first section are in the section named `""`.
+/// All values are strings; if a key is defined multiple times within a
+/// section, its last definition is extracted.
+pub struct InputFormatINI;
+
+impl InputFormat for InputFormatINI {
+    fn name(&self) -> &'static str {
+        "ini"
+    }
+
+    fn file_extensions(&self) -> Vec<&'static str> {
+        vec!["ini", "cfg"]
+    }
+
+    fn create<'input>(
+        &self,
+        contents: &'input str,
+    ) -> Result<Box<dyn InputFormatData<'input> + 'input>, String> {
+        let ini = Ini::load_from_str(contents).map_err(|err| err.to_string())?;
+
+        let mut sections: BTreeMap<String, BTreeMap<String, String>> = BTreeMap::new();
+        for (section, properties) in ini.iter() {
+            let entries = sections
+                .entry(section.unwrap_or_default().to_string())
+                .or_default();
+
+            for (key, value) in properties.iter() {
+                entries.insert(key.to_string(), value.to_string());
+            }
+        }
+
+        Result::Ok(Box::new(InputFormatDataINI {
+            sections,
+            contents: PhantomData,
+        }))
+    }
+
+    fn has_string_keys(&self) -> bool {
+        true
+    }
+}
+
+pub struct InputFormatDataINI<'a> {
+    sections: BTreeMap<String, BTreeMap<String, String>>,
+    contents: PhantomData<&'a str>,
+}
+
+impl<'input> InputFormatData<'input> for InputFormatDataINI<'input> {
+    fn deserializer<'de>(&'de mut self) -> Box<dyn ErasedDeserializer<'input> + 'de> {
+        let sections = std::mem::take(&mut self.sections);
+        Box::new(<dyn ErasedDeserializer<'input>>::erase(IntoDeserializer::<
+            ValueError,
+        >::into_deserializer(
+            sections
+        )))
+    }
+}
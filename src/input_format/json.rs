@@ -1,6 +1,13 @@
-use super::{InputFormat, InputFormatData};
+use super::{
+    ndjson::starts_with_json_lines, text_start, Document, DocumentValue, InputFormat,
+    InputFormatData, InputSource,
+};
 use erased_serde::Deserializer as ErasedDeserializer;
-use serde_json::de::StrRead;
+use serde_json::de::{IoRead, StrRead};
+use std::{
+    io::{self, BufReader, Read},
+    marker::PhantomData,
+};
 
 pub struct InputFormatJSON;
 
@@ -13,25 +20,92 @@ impl InputFormat for InputFormatJSON {
         vec!["json"]
     }
 
-    fn create<'input>(&self, contents: &'input str) -> Box<dyn InputFormatData<'input> + 'input> {
-        Box::new(InputFormatJSONData {
-            deserializer: serde_json::Deserializer::from_str(contents),
-        })
+    fn media_types(&self) -> Vec<&'static str> {
+        vec!["application/json"]
+    }
+
+    fn create<'input>(
+        &self,
+        contents: &'input str,
+    ) -> Result<Box<dyn InputFormatData<'input> + 'input>, String> {
+        Result::Ok(Box::new(InputFormatJSONData::from_str(contents)))
     }
 
     fn has_string_keys(&self) -> bool {
         true
     }
+
+    /// Inputs starting with an object or an array are detected as JSON,
+    /// unless their first lines are separate values (see [InputFormatNDJSON]).
+    ///
+    /// [InputFormatNDJSON]: super::ndjson::InputFormatNDJSON
+    fn detect(&self, prefix: &[u8]) -> bool {
+        let text = text_start(prefix);
+        let starts_value = match text.first() {
+            Some(b'{') => matches!(text[1..].trim_ascii_start().first(), Some(b'"' | b'}')),
+            Some(b'[') => matches!(
+                text[1..].trim_ascii_start().first(),
+                Some(b'{' | b'[' | b'"' | b']' | b'-' | b'0'..=b'9')
+            ),
+            _ => false,
+        };
+
+        starts_value && !starts_with_json_lines(text)
+    }
+
+    /// JSON input is parsed as it is read.
+    fn read<'r>(
+        &'r self,
+        reader: Box<dyn Read + 'r>,
+        _size_hint: Option<u64>,
+    ) -> io::Result<Box<dyn InputSource + 'r>> {
+        io::Result::Ok(Box::new(JSONSource {
+            deserializer: Some(serde_json::Deserializer::from_reader(BufReader::new(
+                reader,
+            ))),
+        }))
+    }
+}
+
+type ReaderDeserializer<'r> = serde_json::Deserializer<IoRead<BufReader<Box<dyn Read + 'r>>>>;
+
+struct JSONSource<'r> {
+    deserializer: Option<ReaderDeserializer<'r>>,
+}
+
+impl InputSource for JSONSource<'_> {
+    fn next_document(&mut self) -> Option<Result<Document<'_>, String>> {
+        let deserializer = self.deserializer.take()?;
+        Some(Result::Ok(Document {
+            position: None,
+            value: DocumentValue::Data(Box::new(InputFormatJSONData {
+                deserializer,
+                contents: PhantomData,
+            })),
+        }))
+    }
 }
 
-struct InputFormatJSONData<'input> {
-    deserializer: serde_json::de::Deserializer<StrRead<'input>>,
+pub(super) struct InputFormatJSONData<'input, R> {
+    deserializer: serde_json::de::Deserializer<R>,
+    contents: PhantomData<&'input str>,
 }
 
-impl<'input> InputFormatData<'input> for InputFormatJSONData<'input> {
+impl<'input, R: serde_json::de::Read<'input>> InputFormatData<'input>
+    for InputFormatJSONData<'input, R>
+{
     fn deserializer<'de>(&'de mut self) -> Box<dyn ErasedDeserializer<'input> + 'de> {
         Box::new(<dyn ErasedDeserializer<'input>>::erase(
             &mut self.deserializer,
         ))
     }
 }
+
+impl<'input> InputFormatJSONData<'input, StrRead<'input>> {
+    pub(super) fn from_str(contents: &'input str) -> Self {
+        InputFormatJSONData {
+            deserializer: serde_json::Deserializer::from_str(contents),
+            contents: PhantomData,
+        }
+    }
+}
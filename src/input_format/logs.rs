@@ -0,0 +1,406 @@
+use super::{text_start, DecodedDocument, InputFormat, InputFormatData};
+use crate::datetime::Datetime;
+use serde::ser::{Serialize, SerializeStruct, Serializer};
+use std::collections::BTreeMap;
+
+/// Decode each non-empty line of `contents` into a separate document with
+/// `parse_line`, reporting errors with their line number.
+fn decode_lines<T, F>(contents: &[u8], parse_line: F) -> Result<Vec<DecodedDocument>, String>
+where
+    T: Serialize + 'static,
+    F: Fn(&str) -> Result<T, String>,
+{
+    let text = std::str::from_utf8(contents).map_err(|err| err.to_string())?;
+
+    text.lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(i, line)| {
+            let record = parse_line(line).map_err(|msg| format!("line {}: {}", i + 1, msg))?;
+
+            Result::Ok(DecodedDocument {
+                position: Some(i + 1),
+                value: Box::new(record),
+            })
+        })
+        .collect()
+}
+
+/// Parse an RFC 3339 timestamp, such as `2024-05-01T12:30:00.123+02:00`,
+/// into seconds since the Unix epoch, as described in
+/// [Datetime::parse_rfc3339].
+fn parse_timestamp(timestamp: &str) -> Option<i64> {
+    Datetime::parse_rfc3339(timestamp).map(|datetime| datetime.epoch_seconds())
+}
+
+/// Keys whose values are taken to be the timestamp of a logfmt record.
+const LOGFMT_TIMESTAMP_KEYS: [&str; 4] = ["time", "ts", "timestamp", "t"];
+
+/// Logs in [logfmt](https://brandur.org/logfmt) format, where each non-empty
+/// line is a record of `key=value` pairs separated by spaces, such as
+/// `level=info msg="request done" duration=12ms`.
+///
+/// Each record is extracted as a separate root element, named `file:line`, as
+/// a `LogfmtRecord` struct. Its `fields` field is a map from keys to values,
+/// which are all strings; keys without a value (e.g. `debug` instead of
+/// `debug=true`) map to `""`. If the value of a `time`, `ts`, `timestamp`,
+/// or `t` key is an RFC 3339 timestamp, the `timestamp` field of the record
+/// contains it as seconds since the Unix epoch.
+pub struct InputFormatLogfmt;
+
+struct LogfmtRecord {
+    timestamp: Option<i64>,
+    fields: BTreeMap<String, String>,
+}
+
+impl Serialize for LogfmtRecord {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut s = serializer.serialize_struct("LogfmtRecord", 2)?;
+        s.serialize_field("timestamp", &self.timestamp)?;
+        s.serialize_field("fields", &self.fields)?;
+        s.end()
+    }
+}
+
+/// Parse a logfmt value starting after a `=`, returning the value and the
+/// rest of the line.
+fn parse_logfmt_value(input: &str) -> Result<(String, &str), String> {
+    let Some(quoted) = input.strip_prefix('"') else {
+        let end = input.find(char::is_whitespace).unwrap_or(input.len());
+        return Result::Ok((input[..end].to_string(), &input[end..]));
+    };
+
+    let mut value = String::new();
+    let mut chars = quoted.char_indices();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '"' => return Result::Ok((value, &quoted[i + 1..])),
+            '\\' => match chars.next() {
+                Some((_, 'n')) => value.push('\n'),
+                Some((_, 't')) => value.push('\t'),
+                Some((_, 'r')) => value.push('\r'),
+                Some((_, c)) => value.push(c),
+                None => break,
+            },
+            c => value.push(c),
+        }
+    }
+
+    Result::Err("unterminated quoted value".to_string())
+}
+
+fn parse_logfmt_line(line: &str) -> Result<LogfmtRecord, String> {
+    let mut fields = BTreeMap::new();
+    let mut rest = line.trim_start();
+
+    while !rest.is_empty() {
+        let end = rest
+            .find(|c: char| c == '=' || c.is_whitespace())
+            .unwrap_or(rest.len());
+        let key = &rest[..end];
+        if key.is_empty() {
+            return Result::Err("expected key before '='".to_string());
+        }
+        rest = &rest[end..];
+
+        let value = match rest.strip_prefix('=') {
+            Some(after) => {
+                let (value, after) = parse_logfmt_value(after)?;
+                rest = after;
+                value
+            }
+            None => String::new(),
+        };
+
+        fields.insert(key.to_string(), value);
+        rest = rest.trim_start();
+    }
+
+    let timestamp = LOGFMT_TIMESTAMP_KEYS
+        .iter()
+        .find_map(|key| fields.get(*key).and_then(|value| parse_timestamp(value)));
+
+    Result::Ok(LogfmtRecord { timestamp, fields })
+}
+
+impl InputFormat for InputFormatLogfmt {
+    fn name(&self) -> &'static str {
+        "logfmt"
+    }
+
+    /// Documents are lines of the input.
+    fn position_key(&self) -> &'static str {
+        "line"
+    }
+
+    fn file_extensions(&self) -> Vec<&'static str> {
+        vec!["logfmt"]
+    }
+
+    fn create<'input>(
+        &self,
+        _contents: &'input str,
+    ) -> Result<Box<dyn InputFormatData<'input> + 'input>, String> {
+        Result::Err("logfmt records are decoded directly".to_string())
+    }
+
+    fn decode(&self, contents: &[u8]) -> Option<Result<Vec<DecodedDocument>, String>> {
+        Some(decode_lines(contents, parse_logfmt_line))
+    }
+
+    fn has_string_keys(&self) -> bool {
+        true
+    }
+
+    /// Inputs whose first line starts with two `key=value` pairs are detected
+    /// as logfmt; a single pair is more likely a `.env` file.
+    fn detect(&self, prefix: &[u8]) -> bool {
+        let is_pair = |token: &[u8]| match token.iter().position(|b| *b == b'=') {
+            Some(end) => {
+                end > 0
+                    && token[..end]
+                        .iter()
+                        .all(|b| b.is_ascii_alphanumeric() || b"_.-".contains(b))
+            }
+            None => false,
+        };
+
+        let line = text_start(prefix)
+            .split(|b| *b == b'\n')
+            .next()
+            .unwrap_or_default();
+        let mut tokens = line
+            .split(|b| b.is_ascii_whitespace())
+            .filter(|token| !token.is_empty());
+
+        tokens.next().is_some_and(is_pair) && tokens.next().is_some_and(is_pair)
+    }
+}
+
+/// Syslog messages in [RFC 5424](https://www.rfc-editor.org/rfc/rfc5424)
+/// format, one on each non-empty line, such as
+/// `<34>1 2024-05-01T12:30:00Z host app 1234 ID47 [origin ip="10.0.0.1"] msg`.
+///
+/// Each message is extracted as a separate root element, named `file:line`,
+/// as a `SyslogRecord` struct with the fields of the message header, the
+/// message, and its structured data as a map from SD-IDs to maps from
+/// parameter names to values. Header fields with the nil value `-` are `None`,
+/// and the `timestamp` field contains the timestamp as seconds since the Unix
+/// epoch.
+pub struct InputFormatSyslog;
+
+struct SyslogRecord {
+    facility: u8,
+    severity: u8,
+    version: u32,
+    timestamp: Option<i64>,
+    hostname: Option<String>,
+    app_name: Option<String>,
+    procid: Option<String>,
+    msgid: Option<String>,
+    structured_data: BTreeMap<String, BTreeMap<String, String>>,
+    message: Option<String>,
+}
+
+impl Serialize for SyslogRecord {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut s = serializer.serialize_struct("SyslogRecord", 10)?;
+        s.serialize_field("facility", &self.facility)?;
+        s.serialize_field("severity", &self.severity)?;
+        s.serialize_field("version", &self.version)?;
+        s.serialize_field("timestamp", &self.timestamp)?;
+        s.serialize_field("hostname", &self.hostname)?;
+        s.serialize_field("app_name", &self.app_name)?;
+        s.serialize_field("procid", &self.procid)?;
+        s.serialize_field("msgid", &self.msgid)?;
+        s.serialize_field("structured_data", &self.structured_data)?;
+        s.serialize_field("message", &self.message)?;
+        s.end()
+    }
+}
+
+/// Split the next space-separated header field from `rest`.
+fn header_field<'a>(rest: &mut &'a str, name: &str) -> Result<&'a str, String> {
+    let (field, after) = rest.split_once(' ').unwrap_or((rest, ""));
+    if field.is_empty() {
+        return Result::Err(format!("missing {}", name));
+    }
+    *rest = after;
+    Result::Ok(field)
+}
+
+/// Header field, or `None` for the nil value.
+fn nil_field(field: &str) -> Option<String> {
+    if field == "-" {
+        None
+    } else {
+        Some(field.to_string())
+    }
+}
+
+fn parse_structured_data(
+    rest: &mut &str,
+) -> Result<BTreeMap<String, BTreeMap<String, String>>, String> {
+    let mut data: BTreeMap<String, BTreeMap<String, String>> = BTreeMap::new();
+
+    if let Some(after) = rest.strip_prefix('-') {
+        *rest = after;
+        return Result::Ok(data);
+    }
+
+    while let Some(after) = rest.strip_prefix('[') {
+        let end = after
+            .find([' ', ']'])
+            .ok_or_else(|| "unterminated structured data element".to_string())?;
+        let params = data.entry(after[..end].to_string()).or_default();
+        let mut input = &after[end..];
+
+        loop {
+            if let Some(after) = input.strip_prefix(']') {
+                input = after;
+                break;
+            }
+
+            let param = input.trim_start_matches(' ');
+            let (name, value) = param
+                .split_once("=\"")
+                .ok_or_else(|| "expected PARAM-NAME=\"PARAM-VALUE\"".to_string())?;
+
+            let mut unescaped = String::new();
+            let mut chars = value.char_indices();
+            let mut end = None;
+            while let Some((i, c)) = chars.next() {
+                match c {
+                    '"' => {
+                        end = Some(i + 1);
+                        break;
+                    }
+                    '\\' => match chars.next() {
+                        Some((_, c @ ('"' | '\\' | ']'))) => unescaped.push(c),
+                        Some((_, c)) => {
+                            unescaped.push('\\');
+                            unescaped.push(c);
+                        }
+                        None => break,
+                    },
+                    c => unescaped.push(c),
+                }
+            }
+
+            let end = end.ok_or_else(|| "unterminated parameter value".to_string())?;
+            params.insert(name.to_string(), unescaped);
+            input = &value[end..];
+        }
+
+        *rest = input;
+    }
+
+    if data.is_empty() {
+        Result::Err("expected structured data".to_string())
+    } else {
+        Result::Ok(data)
+    }
+}
+
+fn parse_syslog_line(line: &str) -> Result<SyslogRecord, String> {
+    let (priority, mut rest) = line
+        .strip_prefix('<')
+        .and_then(|rest| rest.split_once('>'))
+        .ok_or_else(|| "expected <PRI>".to_string())?;
+
+    let priority: u8 = priority
+        .parse()
+        .ok()
+        .filter(|priority| *priority <= 191)
+        .ok_or_else(|| format!("invalid priority {}", priority))?;
+
+    let version = header_field(&mut rest, "version")?;
+    let version = version
+        .parse()
+        .map_err(|_| format!("invalid version {}", version))?;
+
+    let timestamp = match header_field(&mut rest, "timestamp")? {
+        "-" => None,
+        timestamp => Some(
+            parse_timestamp(timestamp).ok_or_else(|| format!("invalid timestamp {}", timestamp))?,
+        ),
+    };
+
+    let hostname = nil_field(header_field(&mut rest, "hostname")?);
+    let app_name = nil_field(header_field(&mut rest, "app name")?);
+    let procid = nil_field(header_field(&mut rest, "process id")?);
+    let msgid = nil_field(header_field(&mut rest, "message id")?);
+
+    let structured_data = parse_structured_data(&mut rest)?;
+
+    let message = match rest.strip_prefix(' ') {
+        Some(message) => Some(message.trim_start_matches('\u{feff}').to_string()),
+        None if rest.is_empty() => None,
+        None => return Result::Err("expected space after structured data".to_string()),
+    };
+
+    Result::Ok(SyslogRecord {
+        facility: priority / 8,
+        severity: priority % 8,
+        version,
+        timestamp,
+        hostname,
+        app_name,
+        procid,
+        msgid,
+        structured_data,
+        message,
+    })
+}
+
+impl InputFormat for InputFormatSyslog {
+    fn name(&self) -> &'static str {
+        "syslog"
+    }
+
+    /// Documents are lines of the input.
+    fn position_key(&self) -> &'static str {
+        "line"
+    }
+
+    fn file_extensions(&self) -> Vec<&'static str> {
+        vec!["syslog"]
+    }
+
+    fn create<'input>(
+        &self,
+        _contents: &'input str,
+    ) -> Result<Box<dyn InputFormatData<'input> + 'input>, String> {
+        Result::Err("syslog messages are decoded directly".to_string())
+    }
+
+    fn decode(&self, contents: &[u8]) -> Option<Result<Vec<DecodedDocument>, String>> {
+        Some(decode_lines(contents, parse_syslog_line))
+    }
+
+    fn has_string_keys(&self) -> bool {
+        true
+    }
+
+    /// Inputs starting with a priority and a version (e.g. `<34>1 `) are
+    /// detected as syslog messages.
+    fn detect(&self, prefix: &[u8]) -> bool {
+        let text = text_start(prefix);
+        let Some(rest) = text.strip_prefix(b"<") else {
+            return false;
+        };
+
+        let priority_len = rest.iter().take_while(|b| b.is_ascii_digit()).count();
+        let rest = &rest[priority_len..];
+        let version_len = rest
+            .iter()
+            .skip(1)
+            .take_while(|b| b.is_ascii_digit())
+            .count();
+
+        (1..=3).contains(&priority_len)
+            && rest.first() == Some(&b'>')
+            && version_len > 0
+            && rest.get(1 + version_len) == Some(&b' ')
+    }
+}
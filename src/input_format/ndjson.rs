@@ -0,0 +1,138 @@
+use super::{
+    json::{InputFormatJSON, InputFormatJSONData},
+    text_start, Document, DocumentValue, InputDocument, InputFormat, InputFormatData, InputSource,
+};
+use std::io::{self, BufRead, BufReader, Read};
+
+/// Newline-delimited JSON, where each non-empty line of the input is a
+/// separate JSON value.
+pub struct InputFormatNDJSON;
+
+impl InputFormat for InputFormatNDJSON {
+    fn name(&self) -> &'static str {
+        "ndjson"
+    }
+
+    /// Documents are lines of the input.
+    fn position_key(&self) -> &'static str {
+        "line"
+    }
+
+    fn file_extensions(&self) -> Vec<&'static str> {
+        vec!["ndjson", "jsonl"]
+    }
+
+    fn media_types(&self) -> Vec<&'static str> {
+        vec!["application/x-ndjson", "application/jsonl"]
+    }
+
+    fn create<'input>(
+        &self,
+        contents: &'input str,
+    ) -> Result<Box<dyn InputFormatData<'input> + 'input>, String> {
+        InputFormatJSON.create(contents)
+    }
+
+    fn documents<'input>(&self, contents: &'input str) -> Vec<InputDocument<'input>> {
+        contents
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .map(|(i, line)| InputDocument {
+                line: Some(i + 1),
+                contents: line,
+            })
+            .collect()
+    }
+
+    fn has_string_keys(&self) -> bool {
+        true
+    }
+
+    fn detect(&self, prefix: &[u8]) -> bool {
+        starts_with_json_lines(text_start(prefix))
+    }
+
+    /// Lines are read one at a time, so that only the current line is held in
+    /// memory.
+    fn read<'r>(
+        &'r self,
+        reader: Box<dyn Read + 'r>,
+        _size_hint: Option<u64>,
+    ) -> io::Result<Box<dyn InputSource + 'r>> {
+        io::Result::Ok(Box::new(NDJSONSource {
+            reader: BufReader::new(reader),
+            line: String::new(),
+            line_number: 0,
+            failed: false,
+        }))
+    }
+}
+
+/// Do the first two non-empty lines of `text` each hold a complete object or
+/// array? Only the first line must be complete, as the second line may be
+/// cut off by the end of the prefix read for detection.
+pub(super) fn starts_with_json_lines(text: &[u8]) -> bool {
+    let mut lines = text
+        .split(|b| *b == b'\n')
+        .map(|line| line.trim_ascii())
+        .filter(|line| !line.is_empty());
+
+    let first_is_value = match lines.next() {
+        Some(line) => matches!(
+            (line.first(), line.last()),
+            (Some(b'{'), Some(b'}')) | (Some(b'['), Some(b']'))
+        ),
+        None => false,
+    };
+
+    first_is_value
+        && matches!(
+            lines.next().and_then(|line| line.first()),
+            Some(b'{' | b'[')
+        )
+}
+
+struct NDJSONSource<'r> {
+    reader: BufReader<Box<dyn Read + 'r>>,
+    line: String,
+    line_number: usize,
+    failed: bool,
+}
+
+impl InputSource for NDJSONSource<'_> {
+    fn next_document(&mut self) -> Option<Result<Document<'_>, String>> {
+        // reading may fail again at the same point, so the input ends at
+        // the first read error
+        if self.failed {
+            return None;
+        }
+
+        loop {
+            self.line.clear();
+            match self.reader.read_line(&mut self.line) {
+                Result::Ok(0) => return None,
+                Result::Ok(_) => self.line_number += 1,
+                Result::Err(err) => {
+                    self.failed = true;
+                    return Some(Result::Err(err.to_string()));
+                }
+            }
+
+            if !self.line.trim().is_empty() {
+                break;
+            }
+        }
+
+        Some(Result::Ok(Document {
+            position: Some(self.line_number),
+            value: DocumentValue::Data(Box::new(InputFormatJSONData::from_str(&self.line))),
+        }))
+    }
+
+    /// Each document is on its own line, so documents following an invalid
+    /// line can still be read.
+    fn resumes_after_errors(&self) -> bool {
+        true
+    }
+}
@@ -12,10 +12,12 @@ impl InputFormat for InputFormatRON {
         vec!["ron"]
     }
 
-    fn create<'input>(&self, contents: &'input str) -> Box<dyn InputFormatData<'input> + 'input> {
-        Box::new(InputFormatDataRON {
-            deserializer: ron::Deserializer::from_str(contents).unwrap(),
-        })
+    fn create<'input>(
+        &self,
+        contents: &'input str,
+    ) -> Result<Box<dyn InputFormatData<'input> + 'input>, String> {
+        let deserializer = ron::Deserializer::from_str(contents).map_err(|err| err.to_string())?;
+        Result::Ok(Box::new(InputFormatDataRON { deserializer }))
     }
 
     fn has_string_keys(&self) -> bool {
@@ -0,0 +1,627 @@
+use super::{DecodedDocument, InputFormat, InputFormatData};
+use quote::ToTokens;
+use serde::ser::{Serialize, SerializeStruct, SerializeStructVariant, Serializer};
+use syn::spanned::Spanned;
+
+/// Rust source files, parsed with [syn](https://crates.io/crates/syn).
+///
+/// A file is extracted as a `File` struct containing its attributes and items.
+/// Items, statements, expressions, and other syntax nodes are extracted as
+/// struct variants of the types `Item`, `ImplItem`, `TraitItem`, `Stmt`,
+/// `Expr`, and `FnArg`, with fields named after the corresponding syn types.
+/// Items, calls, method calls, and macro invocations have a `line` field with
+/// the line on which they start.
+///
+/// Types, patterns, paths, visibilities, attributes, and expressions without
+/// a dedicated variant (which are extracted as `Expr::Other`) are extracted as
+/// strings of their tokens, as rendered by `proc_macro2`
+/// (e.g. `Vec < String >`).
+pub struct InputFormatRust;
+
+impl InputFormat for InputFormatRust {
+    fn name(&self) -> &'static str {
+        "rust"
+    }
+
+    fn file_extensions(&self) -> Vec<&'static str> {
+        vec!["rs"]
+    }
+
+    fn create<'input>(
+        &self,
+        _contents: &'input str,
+    ) -> Result<Box<dyn InputFormatData<'input> + 'input>, String> {
+        Result::Err("Rust syntax trees are decoded directly".to_string())
+    }
+
+    fn decode(&self, contents: &[u8]) -> Option<Result<Vec<DecodedDocument>, String>> {
+        let file = std::str::from_utf8(contents)
+            .map_err(|err| err.to_string())
+            .and_then(|text| {
+                syn::parse_file(text).map_err(|err| {
+                    let start = err.span().start();
+                    format!("{} at line {} column {}", err, start.line, start.column + 1)
+                })
+            });
+
+        Some(file.map(|file| {
+            vec![DecodedDocument {
+                position: None,
+                value: Box::new(SynFile(file)),
+            }]
+        }))
+    }
+
+    fn has_string_keys(&self) -> bool {
+        true
+    }
+}
+
+/// Serialize struct variant `$variant` of type `$ty` with the given fields.
+/// Variant indices are not meaningful, since the extractor ignores them.
+macro_rules! variant {
+    ($serializer:expr, $ty:literal, $variant:literal, {}) => {
+        $serializer.serialize_struct_variant($ty, 0, $variant, 0)?.end()
+    };
+    ($serializer:expr, $ty:literal, $variant:literal, { $($field:literal : $value:expr),* $(,)? }) => {{
+        let mut s = $serializer.serialize_struct_variant($ty, 0, $variant, <[&str]>::len(&[$($field),*]))?;
+        $( s.serialize_field($field, &$value)?; )*
+        s.end()
+    }};
+}
+
+fn tokens<T: ToTokens>(node: &T) -> String {
+    node.to_token_stream().to_string()
+}
+
+fn line<T: Spanned>(node: &T) -> usize {
+    node.span().start().line
+}
+
+fn attrs(attrs: &[syn::Attribute]) -> Vec<String> {
+    attrs.iter().map(|attr| tokens(&attr.meta)).collect()
+}
+
+fn nodes<T>(items: impl IntoIterator<Item = T>) -> Vec<Syn<T>> {
+    items.into_iter().map(Syn).collect()
+}
+
+fn block(block: &syn::Block) -> Vec<Syn<&syn::Stmt>> {
+    nodes(block.stmts.iter())
+}
+
+fn generics(generics: &syn::Generics) -> String {
+    let mut rendered = tokens(generics);
+    if let Some(where_clause) = &generics.where_clause {
+        rendered.push(' ');
+        rendered.push_str(&tokens(where_clause));
+    }
+    rendered
+}
+
+/// A parsed source file.
+struct SynFile(syn::File);
+
+impl Serialize for SynFile {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut s = serializer.serialize_struct("File", 2)?;
+        s.serialize_field("attrs", &attrs(&self.0.attrs))?;
+        s.serialize_field("items", &nodes(self.0.items.iter()))?;
+        s.end()
+    }
+}
+
+/// Wrapper that serializes a syn syntax node.
+struct Syn<T>(T);
+
+impl Serialize for Syn<&syn::Item> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self.0 {
+            syn::Item::Fn(f) => variant!(serializer, "Item", "Fn", {
+                "name": f.sig.ident.to_string(),
+                "vis": tokens(&f.vis),
+                "attrs": attrs(&f.attrs),
+                "line": line(f),
+                "sig": Syn(&f.sig),
+                "body": block(&f.block),
+            }),
+
+            syn::Item::Struct(s) => variant!(serializer, "Item", "Struct", {
+                "name": s.ident.to_string(),
+                "vis": tokens(&s.vis),
+                "attrs": attrs(&s.attrs),
+                "line": line(s),
+                "generics": generics(&s.generics),
+                "fields": nodes(s.fields.iter()),
+            }),
+
+            syn::Item::Enum(e) => variant!(serializer, "Item", "Enum", {
+                "name": e.ident.to_string(),
+                "vis": tokens(&e.vis),
+                "attrs": attrs(&e.attrs),
+                "line": line(e),
+                "generics": generics(&e.generics),
+                "variants": nodes(e.variants.iter()),
+            }),
+
+            syn::Item::Union(u) => variant!(serializer, "Item", "Union", {
+                "name": u.ident.to_string(),
+                "vis": tokens(&u.vis),
+                "attrs": attrs(&u.attrs),
+                "line": line(u),
+                "generics": generics(&u.generics),
+                "fields": nodes(u.fields.named.iter()),
+            }),
+
+            syn::Item::Impl(i) => variant!(serializer, "Item", "Impl", {
+                "attrs": attrs(&i.attrs),
+                "line": line(i),
+                "generics": generics(&i.generics),
+                "trait": i.trait_.as_ref().map(|(_, path, _)| tokens(path)),
+                "self_ty": tokens(&i.self_ty),
+                "items": nodes(i.items.iter()),
+            }),
+
+            syn::Item::Trait(t) => variant!(serializer, "Item", "Trait", {
+                "name": t.ident.to_string(),
+                "vis": tokens(&t.vis),
+                "attrs": attrs(&t.attrs),
+                "line": line(t),
+                "generics": generics(&t.generics),
+                "supertraits": t.supertraits.iter().map(tokens).collect::<Vec<_>>(),
+                "items": nodes(t.items.iter()),
+            }),
+
+            syn::Item::Mod(m) => variant!(serializer, "Item", "Mod", {
+                "name": m.ident.to_string(),
+                "vis": tokens(&m.vis),
+                "attrs": attrs(&m.attrs),
+                "line": line(m),
+                "items": m.content.as_ref().map(|(_, items)| nodes(items.iter())),
+            }),
+
+            syn::Item::Use(u) => {
+                let mut paths = Vec::new();
+                use_paths(&u.tree, String::new(), &mut paths);
+                variant!(serializer, "Item", "Use", {
+                    "vis": tokens(&u.vis),
+                    "attrs": attrs(&u.attrs),
+                    "line": line(u),
+                    "paths": paths,
+                })
+            }
+
+            syn::Item::Const(c) => variant!(serializer, "Item", "Const", {
+                "name": c.ident.to_string(),
+                "vis": tokens(&c.vis),
+                "attrs": attrs(&c.attrs),
+                "line": line(c),
+                "ty": tokens(&c.ty),
+                "expr": Syn(&*c.expr),
+            }),
+
+            syn::Item::Static(s) => variant!(serializer, "Item", "Static", {
+                "name": s.ident.to_string(),
+                "vis": tokens(&s.vis),
+                "attrs": attrs(&s.attrs),
+                "line": line(s),
+                "mutable": !matches!(s.mutability, syn::StaticMutability::None),
+                "ty": tokens(&s.ty),
+                "expr": Syn(&*s.expr),
+            }),
+
+            syn::Item::Type(t) => variant!(serializer, "Item", "Type", {
+                "name": t.ident.to_string(),
+                "vis": tokens(&t.vis),
+                "attrs": attrs(&t.attrs),
+                "line": line(t),
+                "generics": generics(&t.generics),
+                "ty": tokens(&t.ty),
+            }),
+
+            syn::Item::Macro(m) => variant!(serializer, "Item", "Macro", {
+                "name": m.ident.as_ref().map(|ident| ident.to_string()),
+                "attrs": attrs(&m.attrs),
+                "line": line(m),
+                "path": tokens(&m.mac.path),
+                "tokens": m.mac.tokens.to_string(),
+            }),
+
+            syn::Item::ExternCrate(e) => variant!(serializer, "Item", "ExternCrate", {
+                "name": e.ident.to_string(),
+                "vis": tokens(&e.vis),
+                "attrs": attrs(&e.attrs),
+                "line": line(e),
+                "rename": e.rename.as_ref().map(|(_, ident)| ident.to_string()),
+            }),
+
+            other => variant!(serializer, "Item", "Other", {
+                "line": line(other),
+                "tokens": tokens(other),
+            }),
+        }
+    }
+}
+
+/// Flatten a use tree into the paths it imports, e.g. `std::io::{Read, Write}`
+/// into `std::io::Read` and `std::io::Write`.
+fn use_paths(tree: &syn::UseTree, prefix: String, paths: &mut Vec<String>) {
+    match tree {
+        syn::UseTree::Path(path) => {
+            use_paths(&path.tree, format!("{}{}::", prefix, path.ident), paths)
+        }
+        syn::UseTree::Name(name) => paths.push(format!("{}{}", prefix, name.ident)),
+        syn::UseTree::Rename(rename) => {
+            paths.push(format!("{}{} as {}", prefix, rename.ident, rename.rename))
+        }
+        syn::UseTree::Glob(_) => paths.push(format!("{}*", prefix)),
+        syn::UseTree::Group(group) => {
+            for tree in group.items.iter() {
+                use_paths(tree, prefix.clone(), paths);
+            }
+        }
+    }
+}
+
+impl Serialize for Syn<&syn::Signature> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let sig = self.0;
+        let mut s = serializer.serialize_struct("Signature", 7)?;
+        s.serialize_field("const", &sig.constness.is_some())?;
+        s.serialize_field("async", &sig.asyncness.is_some())?;
+        s.serialize_field("unsafe", &sig.unsafety.is_some())?;
+        s.serialize_field("abi", &sig.abi.as_ref().map(tokens))?;
+        s.serialize_field("generics", &generics(&sig.generics))?;
+        s.serialize_field("inputs", &nodes(sig.inputs.iter()))?;
+        s.serialize_field(
+            "output",
+            &match &sig.output {
+                syn::ReturnType::Default => None,
+                syn::ReturnType::Type(_, ty) => Some(tokens(ty)),
+            },
+        )?;
+        s.end()
+    }
+}
+
+impl Serialize for Syn<&syn::FnArg> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self.0 {
+            syn::FnArg::Receiver(r) => variant!(serializer, "FnArg", "Receiver", {
+                "reference": r.reference.is_some(),
+                "mutable": r.mutability.is_some(),
+                "ty": tokens(&r.ty),
+            }),
+
+            syn::FnArg::Typed(t) => variant!(serializer, "FnArg", "Typed", {
+                "pat": tokens(&t.pat),
+                "ty": tokens(&t.ty),
+            }),
+        }
+    }
+}
+
+impl Serialize for Syn<&syn::Field> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let field = self.0;
+        let mut s = serializer.serialize_struct("Field", 4)?;
+        s.serialize_field("name", &field.ident.as_ref().map(|ident| ident.to_string()))?;
+        s.serialize_field("vis", &tokens(&field.vis))?;
+        s.serialize_field("attrs", &attrs(&field.attrs))?;
+        s.serialize_field("ty", &tokens(&field.ty))?;
+        s.end()
+    }
+}
+
+impl Serialize for Syn<&syn::Variant> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let variant = self.0;
+        let mut s = serializer.serialize_struct("Variant", 4)?;
+        s.serialize_field("name", &variant.ident.to_string())?;
+        s.serialize_field("attrs", &attrs(&variant.attrs))?;
+        s.serialize_field("fields", &nodes(variant.fields.iter()))?;
+        s.serialize_field(
+            "discriminant",
+            &variant.discriminant.as_ref().map(|(_, expr)| Syn(expr)),
+        )?;
+        s.end()
+    }
+}
+
+impl Serialize for Syn<&syn::ImplItem> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self.0 {
+            syn::ImplItem::Fn(f) => variant!(serializer, "ImplItem", "Fn", {
+                "name": f.sig.ident.to_string(),
+                "vis": tokens(&f.vis),
+                "attrs": attrs(&f.attrs),
+                "line": line(f),
+                "sig": Syn(&f.sig),
+                "body": block(&f.block),
+            }),
+
+            syn::ImplItem::Const(c) => variant!(serializer, "ImplItem", "Const", {
+                "name": c.ident.to_string(),
+                "vis": tokens(&c.vis),
+                "attrs": attrs(&c.attrs),
+                "line": line(c),
+                "ty": tokens(&c.ty),
+                "expr": Syn(&c.expr),
+            }),
+
+            syn::ImplItem::Type(t) => variant!(serializer, "ImplItem", "Type", {
+                "name": t.ident.to_string(),
+                "vis": tokens(&t.vis),
+                "attrs": attrs(&t.attrs),
+                "line": line(t),
+                "ty": tokens(&t.ty),
+            }),
+
+            other => variant!(serializer, "ImplItem", "Other", {
+                "line": line(other),
+                "tokens": tokens(other),
+            }),
+        }
+    }
+}
+
+impl Serialize for Syn<&syn::TraitItem> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self.0 {
+            syn::TraitItem::Fn(f) => variant!(serializer, "TraitItem", "Fn", {
+                "name": f.sig.ident.to_string(),
+                "attrs": attrs(&f.attrs),
+                "line": line(f),
+                "sig": Syn(&f.sig),
+                "default": f.default.as_ref().map(block),
+            }),
+
+            syn::TraitItem::Const(c) => variant!(serializer, "TraitItem", "Const", {
+                "name": c.ident.to_string(),
+                "attrs": attrs(&c.attrs),
+                "line": line(c),
+                "ty": tokens(&c.ty),
+                "default": c.default.as_ref().map(|(_, expr)| Syn(expr)),
+            }),
+
+            syn::TraitItem::Type(t) => variant!(serializer, "TraitItem", "Type", {
+                "name": t.ident.to_string(),
+                "attrs": attrs(&t.attrs),
+                "line": line(t),
+                "bounds": t.bounds.iter().map(tokens).collect::<Vec<_>>(),
+            }),
+
+            other => variant!(serializer, "TraitItem", "Other", {
+                "line": line(other),
+                "tokens": tokens(other),
+            }),
+        }
+    }
+}
+
+impl Serialize for Syn<&syn::Stmt> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self.0 {
+            syn::Stmt::Local(local) => variant!(serializer, "Stmt", "Local", {
+                "pat": tokens(&local.pat),
+                "init": local.init.as_ref().map(|init| Syn(&*init.expr)),
+                "diverge": local
+                    .init
+                    .as_ref()
+                    .and_then(|init| init.diverge.as_ref())
+                    .map(|(_, expr)| Syn(&**expr)),
+            }),
+
+            syn::Stmt::Item(item) => variant!(serializer, "Stmt", "Item", {
+                "item": Syn(item),
+            }),
+
+            syn::Stmt::Expr(expr, semi) => variant!(serializer, "Stmt", "Expr", {
+                "expr": Syn(expr),
+                "semi": semi.is_some(),
+            }),
+
+            syn::Stmt::Macro(m) => variant!(serializer, "Stmt", "Macro", {
+                "line": line(m),
+                "path": tokens(&m.mac.path),
+                "tokens": m.mac.tokens.to_string(),
+            }),
+        }
+    }
+}
+
+fn exprs<'a>(items: impl IntoIterator<Item = &'a syn::Expr>) -> Vec<Syn<&'a syn::Expr>> {
+    nodes(items)
+}
+
+impl Serialize for Syn<&syn::Expr> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self.0 {
+            syn::Expr::Call(c) => variant!(serializer, "Expr", "Call", {
+                "func": Syn(&*c.func),
+                "args": exprs(c.args.iter()),
+                "line": line(c),
+            }),
+
+            syn::Expr::MethodCall(m) => variant!(serializer, "Expr", "MethodCall", {
+                "receiver": Syn(&*m.receiver),
+                "method": m.method.to_string(),
+                "turbofish": m.turbofish.as_ref().map(tokens),
+                "args": exprs(m.args.iter()),
+                "line": line(m),
+            }),
+
+            syn::Expr::Macro(m) => variant!(serializer, "Expr", "Macro", {
+                "path": tokens(&m.mac.path),
+                "tokens": m.mac.tokens.to_string(),
+                "line": line(m),
+            }),
+
+            syn::Expr::Path(p) => variant!(serializer, "Expr", "Path", {
+                "path": tokens(&p.path),
+            }),
+
+            syn::Expr::Lit(l) => variant!(serializer, "Expr", "Lit", {
+                "lit": tokens(&l.lit),
+            }),
+
+            syn::Expr::Block(b) => variant!(serializer, "Expr", "Block", {
+                "stmts": block(&b.block),
+            }),
+
+            syn::Expr::Unsafe(u) => variant!(serializer, "Expr", "Unsafe", {
+                "stmts": block(&u.block),
+            }),
+
+            syn::Expr::Async(a) => variant!(serializer, "Expr", "Async", {
+                "move": a.capture.is_some(),
+                "stmts": block(&a.block),
+            }),
+
+            syn::Expr::If(i) => variant!(serializer, "Expr", "If", {
+                "cond": Syn(&*i.cond),
+                "then": block(&i.then_branch),
+                "else": i.else_branch.as_ref().map(|(_, expr)| Syn(&**expr)),
+            }),
+
+            syn::Expr::Let(l) => variant!(serializer, "Expr", "Let", {
+                "pat": tokens(&l.pat),
+                "expr": Syn(&*l.expr),
+            }),
+
+            syn::Expr::Match(m) => variant!(serializer, "Expr", "Match", {
+                "expr": Syn(&*m.expr),
+                "arms": nodes(m.arms.iter()),
+            }),
+
+            syn::Expr::While(w) => variant!(serializer, "Expr", "While", {
+                "cond": Syn(&*w.cond),
+                "body": block(&w.body),
+            }),
+
+            syn::Expr::ForLoop(f) => variant!(serializer, "Expr", "ForLoop", {
+                "pat": tokens(&f.pat),
+                "expr": Syn(&*f.expr),
+                "body": block(&f.body),
+            }),
+
+            syn::Expr::Loop(l) => variant!(serializer, "Expr", "Loop", {
+                "body": block(&l.body),
+            }),
+
+            syn::Expr::Closure(c) => variant!(serializer, "Expr", "Closure", {
+                "move": c.capture.is_some(),
+                "async": c.asyncness.is_some(),
+                "inputs": c.inputs.iter().map(tokens).collect::<Vec<_>>(),
+                "output": match &c.output {
+                    syn::ReturnType::Default => None,
+                    syn::ReturnType::Type(_, ty) => Some(tokens(ty)),
+                },
+                "body": Syn(&*c.body),
+            }),
+
+            syn::Expr::Return(r) => variant!(serializer, "Expr", "Return", {
+                "expr": r.expr.as_ref().map(|expr| Syn(&**expr)),
+            }),
+
+            syn::Expr::Break(b) => variant!(serializer, "Expr", "Break", {
+                "expr": b.expr.as_ref().map(|expr| Syn(&**expr)),
+            }),
+
+            syn::Expr::Continue(_) => variant!(serializer, "Expr", "Continue", {}),
+
+            syn::Expr::Try(t) => variant!(serializer, "Expr", "Try", {
+                "expr": Syn(&*t.expr),
+            }),
+
+            syn::Expr::Await(a) => variant!(serializer, "Expr", "Await", {
+                "base": Syn(&*a.base),
+            }),
+
+            syn::Expr::Binary(b) => variant!(serializer, "Expr", "Binary", {
+                "left": Syn(&*b.left),
+                "op": tokens(&b.op),
+                "right": Syn(&*b.right),
+            }),
+
+            syn::Expr::Unary(u) => variant!(serializer, "Expr", "Unary", {
+                "op": tokens(&u.op),
+                "expr": Syn(&*u.expr),
+            }),
+
+            syn::Expr::Assign(a) => variant!(serializer, "Expr", "Assign", {
+                "left": Syn(&*a.left),
+                "right": Syn(&*a.right),
+            }),
+
+            syn::Expr::Field(f) => variant!(serializer, "Expr", "Field", {
+                "base": Syn(&*f.base),
+                "member": tokens(&f.member),
+            }),
+
+            syn::Expr::Index(i) => variant!(serializer, "Expr", "Index", {
+                "expr": Syn(&*i.expr),
+                "index": Syn(&*i.index),
+            }),
+
+            syn::Expr::Reference(r) => variant!(serializer, "Expr", "Reference", {
+                "mutable": r.mutability.is_some(),
+                "expr": Syn(&*r.expr),
+            }),
+
+            syn::Expr::Cast(c) => variant!(serializer, "Expr", "Cast", {
+                "expr": Syn(&*c.expr),
+                "ty": tokens(&c.ty),
+            }),
+
+            syn::Expr::Range(r) => variant!(serializer, "Expr", "Range", {
+                "start": r.start.as_ref().map(|expr| Syn(&**expr)),
+                "limits": tokens(&r.limits),
+                "end": r.end.as_ref().map(|expr| Syn(&**expr)),
+            }),
+
+            syn::Expr::Tuple(t) => variant!(serializer, "Expr", "Tuple", {
+                "elems": exprs(t.elems.iter()),
+            }),
+
+            syn::Expr::Array(a) => variant!(serializer, "Expr", "Array", {
+                "elems": exprs(a.elems.iter()),
+            }),
+
+            syn::Expr::Struct(s) => variant!(serializer, "Expr", "Struct", {
+                "path": tokens(&s.path),
+                "fields": nodes(s.fields.iter()),
+                "rest": s.rest.as_ref().map(|expr| Syn(&**expr)),
+            }),
+
+            // parentheses and invisible groups do not affect the syntax tree
+            syn::Expr::Paren(p) => Syn(&*p.expr).serialize(serializer),
+            syn::Expr::Group(g) => Syn(&*g.expr).serialize(serializer),
+
+            other => variant!(serializer, "Expr", "Other", {
+                "tokens": tokens(other),
+            }),
+        }
+    }
+}
+
+impl Serialize for Syn<&syn::Arm> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let arm = self.0;
+        let mut s = serializer.serialize_struct("Arm", 3)?;
+        s.serialize_field("pat", &tokens(&arm.pat))?;
+        s.serialize_field("guard", &arm.guard.as_ref().map(|(_, expr)| Syn(&**expr)))?;
+        s.serialize_field("body", &Syn(&*arm.body))?;
+        s.end()
+    }
+}
+
+impl Serialize for Syn<&syn::FieldValue> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let field = self.0;
+        let mut s = serializer.serialize_struct("FieldValue", 2)?;
+        s.serialize_field("member", &tokens(&field.member))?;
+        s.serialize_field("expr", &Syn(&field.expr))?;
+        s.end()
+    }
+}
@@ -0,0 +1,208 @@
+use super::{DecodedDocument, InputFormat, InputFormatData};
+use crate::datetime::DatetimeStr;
+use calamine::{open_workbook_auto_from_rs, Data, Range, Reader};
+use serde::ser::{Serialize, SerializeMap, SerializeStruct, Serializer};
+use std::{collections::HashSet, io::Cursor};
+
+/// Spreadsheets in Excel (`.xlsx`, `.xlsm`, `.xlsb`, `.xls`) and OpenDocument
+/// (`.ods`) formats, read with [calamine](https://crates.io/crates/calamine).
+///
+/// Each sheet is extracted as a separate root element, named `file:N` for the
+/// Nth sheet, as a struct whose type is the name of the sheet. The struct has
+/// a `rows` field containing a sequence of maps, one for each row after the
+/// first, from the headers in the first row to the row's cells. Columns with
+/// an empty or repeated header are keyed by their column letter (e.g. `C`).
+///
+/// Empty cells and empty rows are omitted. Whole numbers are extracted as
+/// integers, since spreadsheets store all numbers as floats; note that the
+/// Souffle SQLite backend cannot extract other numbers. Dates and times are
+/// extracted as ISO 8601 strings, which are date-times, durations as numbers
+/// of days, and cells with errors as their error (e.g. `#DIV/0!`).
+pub struct InputFormatSpreadsheet;
+
+impl InputFormat for InputFormatSpreadsheet {
+    fn name(&self) -> &'static str {
+        "spreadsheet"
+    }
+
+    fn file_extensions(&self) -> Vec<&'static str> {
+        vec!["xlsx", "xlsm", "xlsb", "xls", "ods"]
+    }
+
+    fn media_types(&self) -> Vec<&'static str> {
+        vec![
+            "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet",
+            "application/vnd.ms-excel",
+            "application/vnd.oasis.opendocument.spreadsheet",
+        ]
+    }
+
+    fn create<'input>(
+        &self,
+        _contents: &'input str,
+    ) -> Result<Box<dyn InputFormatData<'input> + 'input>, String> {
+        Result::Err("spreadsheets are a binary input format".to_string())
+    }
+
+    fn is_binary(&self) -> bool {
+        true
+    }
+
+    /// Inputs starting with the magic number of ZIP archives (`.xlsx`, `.ods`,
+    /// etc.) or of OLE compound files (`.xls`) are detected as spreadsheets.
+    fn detect(&self, prefix: &[u8]) -> bool {
+        prefix.starts_with(b"PK\x03\x04") || prefix.starts_with(b"\xD0\xCF\x11\xE0\xA1\xB1\x1A\xE1")
+    }
+
+    fn decode(&self, contents: &[u8]) -> Option<Result<Vec<DecodedDocument>, String>> {
+        Some(decode_workbook(contents))
+    }
+
+    fn has_string_keys(&self) -> bool {
+        true
+    }
+}
+
+fn decode_workbook(contents: &[u8]) -> Result<Vec<DecodedDocument>, String> {
+    let mut workbook =
+        open_workbook_auto_from_rs(Cursor::new(contents)).map_err(|err| err.to_string())?;
+
+    let mut docs = Vec::new();
+    for (i, name) in workbook.sheet_names().into_iter().enumerate() {
+        let range = workbook
+            .worksheet_range(&name)
+            .map_err(|err| format!("sheet {}: {}", name, err))?;
+
+        docs.push(DecodedDocument {
+            position: Some(i + 1),
+            value: Box::new(Sheet::new(name, &range)),
+        });
+    }
+
+    Result::Ok(docs)
+}
+
+/// The letters naming a column, e.g. `AB` for the 28th column.
+fn column_letter(mut column: u32) -> String {
+    let mut letters = Vec::new();
+    loop {
+        letters.push(b'A' + (column % 26) as u8);
+        if column < 26 {
+            break;
+        }
+        column = column / 26 - 1;
+    }
+    letters.reverse();
+    String::from_utf8(letters).unwrap_or_default()
+}
+
+struct Sheet {
+    /// Struct names must be static, so sheet names are leaked.
+    name: &'static str,
+    rows: Vec<Vec<(String, Cell)>>,
+}
+
+impl Sheet {
+    fn new(name: String, range: &Range<Data>) -> Self {
+        let start_column = range.start().map(|(_, column)| column).unwrap_or_default();
+        let mut rows = range.rows();
+
+        let mut seen = HashSet::new();
+        let headers: Vec<String> = rows
+            .next()
+            .unwrap_or_default()
+            .iter()
+            .enumerate()
+            .map(|(i, cell)| {
+                let header = cell.to_string();
+                if header.is_empty() || !seen.insert(header.clone()) {
+                    column_letter(start_column + i as u32)
+                } else {
+                    header
+                }
+            })
+            .collect();
+
+        let rows = rows
+            .map(|row| {
+                headers
+                    .iter()
+                    .zip(row.iter())
+                    .filter(|(_, cell)| !matches!(cell, Data::Empty))
+                    .map(|(header, cell)| (header.clone(), Cell::new(cell)))
+                    .collect::<Vec<_>>()
+            })
+            .filter(|row| !row.is_empty())
+            .collect();
+
+        Sheet {
+            name: Box::leak(name.into_boxed_str()),
+            rows,
+        }
+    }
+}
+
+impl Serialize for Sheet {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let rows: Vec<Row> = self.rows.iter().map(|row| Row(row)).collect();
+
+        let mut s = serializer.serialize_struct(self.name, 1)?;
+        s.serialize_field("rows", &rows)?;
+        s.end()
+    }
+}
+
+struct Row<'a>(&'a [(String, Cell)]);
+
+impl Serialize for Row<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(self.0.len()))?;
+        for (header, cell) in self.0.iter() {
+            map.serialize_entry(header, cell)?;
+        }
+        map.end()
+    }
+}
+
+enum Cell {
+    Int(i64),
+    Float(f64),
+    Bool(bool),
+    String(String),
+    Datetime(String),
+}
+
+impl Cell {
+    fn new(data: &Data) -> Self {
+        match data {
+            Data::Int(i) => Cell::Int(*i),
+            // spreadsheets store all numbers as floats, so whole numbers are
+            // extracted as integers
+            Data::Float(f) if f.fract() == 0.0 && f.abs() < i64::MAX as f64 => Cell::Int(*f as i64),
+            Data::Float(f) => Cell::Float(*f),
+            Data::Bool(b) => Cell::Bool(*b),
+
+            // the debug representation of datetimes is in ISO 8601 format
+            Data::DateTime(datetime) if datetime.is_datetime() => match datetime.as_datetime() {
+                Some(datetime) => Cell::Datetime(format!("{:?}", datetime)),
+                None => Cell::Float(datetime.as_f64()),
+            },
+
+            Data::DateTime(duration) => Cell::Float(duration.as_f64()),
+
+            other => Cell::String(other.to_string()),
+        }
+    }
+}
+
+impl Serialize for Cell {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Cell::Int(i) => serializer.serialize_i64(*i),
+            Cell::Float(f) => serializer.serialize_f64(*f),
+            Cell::Bool(b) => serializer.serialize_bool(*b),
+            Cell::String(s) => serializer.serialize_str(s),
+            Cell::Datetime(s) => DatetimeStr(s).serialize(serializer),
+        }
+    }
+}
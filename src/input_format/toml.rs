@@ -1,6 +1,9 @@
 use super::{InputFormat, InputFormatData};
 use erased_serde::Deserializer as ErasedDeserializer;
 
+/// TOML files. Date-times, dates, and times are extracted as maps with a
+/// single `$__toml_private_datetime` entry, whose value is the date-time as
+/// written; the values of dates and date-times are date-times.
 pub struct InputFormatTOML;
 
 impl InputFormat for InputFormatTOML {
@@ -12,8 +15,15 @@ impl InputFormat for InputFormatTOML {
         vec!["toml"]
     }
 
-    fn create<'input>(&self, contents: &'input str) -> Box<dyn InputFormatData<'input> + 'input> {
-        Box::new(InputFormatDataTOML { contents })
+    fn media_types(&self) -> Vec<&'static str> {
+        vec!["application/toml"]
+    }
+
+    fn create<'input>(
+        &self,
+        contents: &'input str,
+    ) -> Result<Box<dyn InputFormatData<'input> + 'input>, String> {
+        Result::Ok(Box::new(InputFormatDataTOML { contents }))
     }
 
     fn has_string_keys(&self) -> bool {
@@ -0,0 +1,186 @@
+use super::{text_start, DecodedDocument, InputFormat, InputFormatData};
+use serde::ser::{Serialize, SerializeStruct, Serializer};
+use std::collections::BTreeMap;
+use url::{form_urlencoded, ParseError, Url};
+
+/// Query parameters, mapping each key to its values in order of occurrence.
+type Query = BTreeMap<String, Vec<String>>;
+
+fn parse_query(query: &[u8]) -> Query {
+    let mut params = Query::new();
+    for (key, value) in form_urlencoded::parse(query) {
+        params
+            .entry(key.into_owned())
+            .or_default()
+            .push(value.into_owned());
+    }
+    params
+}
+
+/// Files with a URL on each non-empty line, such as request paths from web
+/// server logs. Each URL is extracted as a separate root element, named
+/// `file:line`, as a `Url` struct with its components and its query
+/// parameters as a map from keys to sequences of values.
+///
+/// Relative URLs (e.g. `/search?q=datalog`) have no scheme, host, or port.
+pub struct InputFormatURL;
+
+struct UrlComponents {
+    scheme: Option<String>,
+    host: Option<String>,
+    port: Option<u16>,
+    path: String,
+    query: Query,
+    fragment: Option<String>,
+}
+
+impl UrlComponents {
+    fn parse(line: &str) -> Result<Self, ParseError> {
+        match Url::parse(line) {
+            Result::Ok(url) => Result::Ok(UrlComponents {
+                scheme: Some(url.scheme().to_string()),
+                host: url.host_str().map(|host| host.to_string()),
+                port: url.port(),
+                path: url.path().to_string(),
+                query: url
+                    .query()
+                    .map(|query| parse_query(query.as_bytes()))
+                    .unwrap_or_default(),
+                fragment: url.fragment().map(|fragment| fragment.to_string()),
+            }),
+
+            Result::Err(ParseError::RelativeUrlWithoutBase) => {
+                let (rest, fragment) = match line.split_once('#') {
+                    Some((rest, fragment)) => (rest, Some(fragment.to_string())),
+                    None => (line, None),
+                };
+
+                let (path, query) = rest.split_once('?').unwrap_or((rest, ""));
+
+                Result::Ok(UrlComponents {
+                    scheme: None,
+                    host: None,
+                    port: None,
+                    path: path.to_string(),
+                    query: parse_query(query.as_bytes()),
+                    fragment,
+                })
+            }
+
+            Result::Err(err) => Result::Err(err),
+        }
+    }
+}
+
+impl Serialize for UrlComponents {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut s = serializer.serialize_struct("Url", 6)?;
+        s.serialize_field("scheme", &self.scheme)?;
+        s.serialize_field("host", &self.host)?;
+        s.serialize_field("port", &self.port)?;
+        s.serialize_field("path", &self.path)?;
+        s.serialize_field("query", &self.query)?;
+        s.serialize_field("fragment", &self.fragment)?;
+        s.end()
+    }
+}
+
+impl InputFormat for InputFormatURL {
+    fn name(&self) -> &'static str {
+        "url"
+    }
+
+    /// Documents are lines of the input.
+    fn position_key(&self) -> &'static str {
+        "line"
+    }
+
+    fn file_extensions(&self) -> Vec<&'static str> {
+        vec!["urls"]
+    }
+
+    fn create<'input>(
+        &self,
+        _contents: &'input str,
+    ) -> Result<Box<dyn InputFormatData<'input> + 'input>, String> {
+        Result::Err("URLs are decoded directly".to_string())
+    }
+
+    fn decode(&self, contents: &[u8]) -> Option<Result<Vec<DecodedDocument>, String>> {
+        let docs = std::str::from_utf8(contents)
+            .map_err(|err| err.to_string())
+            .and_then(|text| {
+                text.lines()
+                    .enumerate()
+                    .filter(|(_, line)| !line.trim().is_empty())
+                    .map(|(i, line)| {
+                        let url = UrlComponents::parse(line.trim())
+                            .map_err(|err| format!("line {}: {}", i + 1, err))?;
+
+                        Result::Ok(DecodedDocument {
+                            position: Some(i + 1),
+                            value: Box::new(url),
+                        })
+                    })
+                    .collect()
+            });
+
+        Some(docs)
+    }
+
+    fn has_string_keys(&self) -> bool {
+        true
+    }
+
+    /// Inputs starting with an `http` or `https` URL on its own line are
+    /// detected as URLs.
+    fn detect(&self, prefix: &[u8]) -> bool {
+        let line = text_start(prefix)
+            .split(|b| *b == b'\n')
+            .next()
+            .unwrap_or_default();
+        let line = line.trim_ascii_end();
+        (line.starts_with(b"http://") || line.starts_with(b"https://"))
+            && !line.iter().any(|b| b.is_ascii_whitespace())
+    }
+}
+
+/// `application/x-www-form-urlencoded` data, such as HTML form submissions,
+/// extracted as a map from keys to sequences of values.
+pub struct InputFormatURLEncoded;
+
+impl InputFormat for InputFormatURLEncoded {
+    fn name(&self) -> &'static str {
+        "urlencoded"
+    }
+
+    fn file_extensions(&self) -> Vec<&'static str> {
+        vec!["urlencoded", "qs"]
+    }
+
+    fn media_types(&self) -> Vec<&'static str> {
+        vec!["application/x-www-form-urlencoded"]
+    }
+
+    fn create<'input>(
+        &self,
+        _contents: &'input str,
+    ) -> Result<Box<dyn InputFormatData<'input> + 'input>, String> {
+        Result::Err("URL-encoded data is decoded directly".to_string())
+    }
+
+    fn decode(&self, contents: &[u8]) -> Option<Result<Vec<DecodedDocument>, String>> {
+        // a leading `?` is accepted so that bare query strings can be read
+        let contents = contents.trim_ascii();
+        let contents = contents.strip_prefix(b"?").unwrap_or(contents);
+
+        Some(Result::Ok(vec![DecodedDocument {
+            position: None,
+            value: Box::new(parse_query(contents)),
+        }]))
+    }
+
+    fn has_string_keys(&self) -> bool {
+        true
+    }
+}
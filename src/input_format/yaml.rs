@@ -1,6 +1,9 @@
-use super::{InputFormat, InputFormatData};
+use super::{text_start, Document, DocumentValue, InputFormat, InputFormatData, InputSource};
 use erased_serde::Deserializer as ErasedDeserializer;
+use std::io::{self, Read};
 
+/// YAML files. Files containing multiple documents separated by `---` are
+/// extracted with a root value for each document.
 pub struct InputFormatYAML;
 
 impl InputFormat for InputFormatYAML {
@@ -12,23 +15,92 @@ impl InputFormat for InputFormatYAML {
         vec!["yaml", "yml"]
     }
 
-    fn create<'input>(&self, contents: &'input str) -> Box<dyn InputFormatData<'input> + 'input> {
-        Box::new(InputFormatDataYAML { contents })
+    fn media_types(&self) -> Vec<&'static str> {
+        vec!["application/yaml", "application/x-yaml", "text/yaml"]
+    }
+
+    fn create<'input>(
+        &self,
+        contents: &'input str,
+    ) -> Result<Box<dyn InputFormatData<'input> + 'input>, String> {
+        Result::Ok(Box::new(InputFormatDataYAML {
+            document: serde_yaml::Deserializer::from_str(contents),
+        }))
     }
 
     fn has_string_keys(&self) -> bool {
         false
     }
+
+    /// YAML timestamps are plain scalars, which are deserialized as strings.
+    fn has_datetime_strings(&self) -> bool {
+        true
+    }
+
+    /// Inputs starting with a document marker or a `%YAML` directive are
+    /// detected as YAML.
+    fn detect(&self, prefix: &[u8]) -> bool {
+        let text = text_start(prefix);
+        text.starts_with(b"---") || text.starts_with(b"%YAML")
+    }
+
+    fn read<'r>(
+        &'r self,
+        reader: Box<dyn Read + 'r>,
+        _size_hint: Option<u64>,
+    ) -> io::Result<Box<dyn InputSource + 'r>> {
+        let mut documents = serde_yaml::Deserializer::from_reader(reader);
+        let next = documents.next();
+
+        io::Result::Ok(Box::new(YAMLSource {
+            documents,
+            next,
+            index: 0,
+        }))
+    }
+}
+
+/// A stream of YAML documents. The document following the current one is
+/// parsed ahead of time, to determine whether the stream has multiple
+/// documents; documents of single-document streams have no position.
+struct YAMLSource<'r> {
+    documents: serde_yaml::Deserializer<'r>,
+    next: Option<serde_yaml::Deserializer<'r>>,
+    index: usize,
+}
+
+impl InputSource for YAMLSource<'_> {
+    fn next_document(&mut self) -> Option<Result<Document<'_>, String>> {
+        let document = self.next.take()?;
+
+        // after a document fails to parse, the stream yields the same error
+        // indefinitely, so extraction stops at the first failing document
+        self.next = self.documents.next();
+        self.index += 1;
+
+        let position = if self.index > 1 || self.next.is_some() {
+            Some(self.index)
+        } else {
+            None
+        };
+
+        Some(Result::Ok(Document {
+            position,
+            value: DocumentValue::Data(Box::new(InputFormatDataYAML { document })),
+        }))
+    }
 }
 
 pub struct InputFormatDataYAML<'input> {
-    contents: &'input str,
+    document: serde_yaml::Deserializer<'input>,
 }
 
 impl<'input> InputFormatData<'input> for InputFormatDataYAML<'input> {
     fn deserializer<'de>(&'de mut self) -> Box<dyn ErasedDeserializer<'input> + 'de> {
-        Box::new(<dyn ErasedDeserializer<'input>>::erase(
-            serde_yaml::Deserializer::from_str(self.contents),
-        ))
+        // YAML deserializers are consumed by deserialization, so the document
+        // can only be deserialized once
+        let document =
+            std::mem::replace(&mut self.document, serde_yaml::Deserializer::from_str(""));
+        Box::new(<dyn ErasedDeserializer<'input>>::erase(document))
     }
 }
@@ -0,0 +1,351 @@
+use std::{
+    fs,
+    io::{self, Read},
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use crate::error::CliError;
+
+/// Input files collected from the command line.
+#[derive(Default)]
+pub struct InputFiles {
+    /// Files to extract facts from.
+    pub files: Vec<String>,
+
+    /// Files found in directories or by glob patterns that were skipped
+    /// because their input format is unknown.
+    pub skipped: Vec<String>,
+}
+
+fn is_glob_pattern(arg: &str) -> bool {
+    arg.contains(['*', '?', '['])
+}
+
+pub mod archive;
+#[cfg(feature = "s3")]
+mod s3;
+
+/// Is the command-line argument `arg` a URL of a remote source, e.g. an
+/// HTTP(S) URL or an S3 URL, rather than a path?
+pub fn is_url(arg: &str) -> bool {
+    is_http_url(arg) || arg.starts_with("s3://")
+}
+
+fn is_http_url(arg: &str) -> bool {
+    arg.starts_with("http://") || arg.starts_with("https://")
+}
+
+/// The path of URL `arg` without its query and fragment, from which the
+/// input format and compression of the input are found, or `arg` itself if
+/// it is not an HTTP(S) URL. Object keys of S3 URLs are taken as is.
+pub fn url_path(arg: &str) -> &str {
+    if !is_http_url(arg) {
+        return arg;
+    }
+
+    match arg.find(['?', '#']) {
+        Some(end) => &arg[..end],
+        None => arg,
+    }
+}
+
+/// Metadata of an input read from a remote source, which is recorded in the
+/// `rootMeta` relation.
+#[derive(Clone)]
+pub struct RemoteMeta {
+    /// The `Content-Type` of the input, if any.
+    pub content_type: Option<String>,
+
+    /// When the input was opened, as an RFC 3339 date-time in UTC.
+    pub fetched_at: String,
+}
+
+/// An input opened from a remote source. Its contents are streamed as they
+/// are read.
+pub struct RemoteObject {
+    /// Reader of the contents of the input.
+    pub reader: Box<dyn Read + Send>,
+
+    /// The size of the contents, if it is known.
+    pub size: Option<u64>,
+
+    /// Metadata of the input.
+    pub meta: RemoteMeta,
+}
+
+/// The contents of an input read into memory, e.g. an input fetched from a
+/// remote source, so that they can be read more than once to detect their
+/// format, or an entry of an archive.
+pub struct Download {
+    /// The contents of the input.
+    pub body: Arc<[u8]>,
+
+    /// Metadata of the input, if it was read from a remote source.
+    pub meta: Option<RemoteMeta>,
+}
+
+/// A source of inputs named by URLs rather than paths, e.g. web servers or
+/// object stores.
+pub trait RemoteSource {
+    /// URLs of the inputs named by the command-line argument `url`. Sources
+    /// with hierarchical names, such as object stores, list the inputs
+    /// under `url`; by default, `url` names a single input.
+    fn list(&self, url: &str) -> Result<Vec<String>, CliError> {
+        Result::Ok(vec![url.to_string()])
+    }
+
+    /// Open the input at `url` for reading.
+    fn open(&self, url: &str) -> Result<RemoteObject, CliError>;
+}
+
+/// Inputs fetched with HTTP(S) GET requests. Responses with an error status
+/// are errors.
+#[cfg(feature = "http")]
+pub struct Http;
+
+#[cfg(feature = "http")]
+impl RemoteSource for Http {
+    fn open(&self, url: &str) -> Result<RemoteObject, CliError> {
+        let response = call(ureq::get(url), url)?;
+        Result::Ok(remote_object(response))
+    }
+}
+
+/// Send `request` for the input at `url`. Responses with an error status
+/// are errors.
+#[cfg(feature = "http")]
+fn call(request: ureq::Request, url: &str) -> Result<ureq::Response, CliError> {
+    request.call().map_err(|err| {
+        let message = match err {
+            ureq::Error::Status(status, response) => {
+                format!("HTTP status {} {}", status, response.status_text())
+            }
+            ureq::Error::Transport(transport) => transport.to_string(),
+        };
+
+        CliError::Io {
+            path: url.to_string(),
+            source: io::Error::other(message),
+        }
+    })
+}
+
+/// Stream the contents of a successful `response`.
+#[cfg(feature = "http")]
+fn remote_object(response: ureq::Response) -> RemoteObject {
+    let fetched_at = rfc3339_utc(std::time::SystemTime::now());
+    let content_type = response.header("Content-Type").map(str::to_string);
+    let size = response
+        .header("Content-Length")
+        .and_then(|len| len.parse().ok());
+
+    RemoteObject {
+        reader: response.into_reader(),
+        size,
+        meta: RemoteMeta {
+            content_type,
+            fetched_at,
+        },
+    }
+}
+
+/// The source of inputs at `url`. Sources whose feature is disabled are
+/// usage errors.
+fn source(url: &str) -> Result<Box<dyn RemoteSource>, CliError> {
+    let feature = if url.starts_with("s3://") {
+        "s3"
+    } else {
+        "http"
+    };
+
+    match feature {
+        #[cfg(feature = "s3")]
+        "s3" => Result::Ok(Box::new(s3::S3::from_env())),
+
+        #[cfg(feature = "http")]
+        "http" => Result::Ok(Box::new(Http)),
+
+        _ => Result::Err(CliError::Usage(format!(
+            "Cannot read {}: serde_datalog was built without the {} feature",
+            url, feature
+        ))),
+    }
+}
+
+/// URLs of the inputs named by the command-line argument `url`. See
+/// [RemoteSource::list].
+pub fn list(url: &str) -> Result<Vec<String>, CliError> {
+    source(url)?.list(url)
+}
+
+/// Open the input at `url` for reading, streaming its contents.
+pub fn open(url: &str) -> Result<RemoteObject, CliError> {
+    source(url)?.open(url)
+}
+
+/// Fetch the contents of the input at `url` into memory.
+pub fn fetch(url: &str) -> Result<Download, CliError> {
+    let mut object = open(url)?;
+
+    let mut body = Vec::new();
+    object
+        .reader
+        .read_to_end(&mut body)
+        .map_err(|source| CliError::Io {
+            path: url.to_string(),
+            source,
+        })?;
+
+    Result::Ok(Download {
+        body: body.into(),
+        meta: Some(object.meta),
+    })
+}
+
+/// Format `time` as an RFC 3339 date-time in UTC with second precision,
+/// e.g. `2024-01-31T12:00:00Z`.
+#[cfg(feature = "http")]
+fn rfc3339_utc(time: std::time::SystemTime) -> String {
+    let secs = time
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs() as i64)
+        .unwrap_or_default();
+    let (days, secs) = (secs.div_euclid(86_400), secs.rem_euclid(86_400));
+
+    // civil date of days since 1970-01-01, see
+    // http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        secs / 3600,
+        secs % 3600 / 60,
+        secs % 60
+    )
+}
+
+/// Collect the files under `dir`, descending into subdirectories.
+/// Entries are visited in sorted order so that extraction is deterministic.
+fn walk_dir(dir: &Path, files: &mut Vec<PathBuf>) -> io::Result<()> {
+    let mut entries: Vec<PathBuf> = fs::read_dir(dir)?
+        .map(|entry| entry.map(|e| e.path()))
+        .collect::<io::Result<_>>()?;
+    entries.sort();
+
+    for entry in entries {
+        if entry.is_dir() {
+            walk_dir(&entry, files)?;
+        } else {
+            files.push(entry);
+        }
+    }
+
+    io::Result::Ok(())
+}
+
+/// Expand command-line arguments into a list of input files.
+///
+/// Arguments that name existing files, and URLs naming single inputs, are
+/// always included. Directories are walked if `recursive` is set, URLs of
+/// sources with hierarchical names are listed (see [RemoteSource::list]),
+/// and arguments that do not name an existing path but contain glob
+/// metacharacters are expanded as glob patterns.
+/// Files discovered through directories, listings, or glob patterns are only included
+/// if `is_known` returns true for them; otherwise they are reported in
+/// [InputFiles::skipped].
+pub fn expand(
+    args: &[String],
+    recursive: bool,
+    is_known: impl Fn(&Path) -> bool,
+) -> Result<InputFiles, CliError> {
+    let mut inputs = InputFiles::default();
+
+    for arg in args.iter() {
+        if is_url(arg) {
+            let urls = list(arg)?;
+
+            // a URL naming a single input is included even if its format is
+            // unknown, like a path naming an existing file
+            if urls.len() == 1 && urls[0] == *arg {
+                inputs.files.extend(urls);
+                continue;
+            }
+
+            for url in urls {
+                if is_known(Path::new(url_path(&url))) {
+                    inputs.files.push(url);
+                } else {
+                    inputs.skipped.push(url);
+                }
+            }
+            continue;
+        }
+
+        let path = Path::new(arg);
+
+        let discovered: Vec<PathBuf> = if path.is_dir() {
+            if !recursive {
+                return Result::Err(CliError::Usage(format!(
+                    "{} is a directory; use --recursive to extract files in directories",
+                    arg
+                )));
+            }
+
+            let mut files = Vec::new();
+            walk_dir(path, &mut files).map_err(|source| CliError::Io {
+                path: arg.to_string(),
+                source,
+            })?;
+            files
+        } else if !path.exists() && is_glob_pattern(arg) {
+            let paths = glob::glob(arg)
+                .map_err(|err| CliError::Usage(format!("invalid glob pattern {}: {}", arg, err)))?;
+
+            let mut files = Vec::new();
+            for entry in paths {
+                let entry = entry.map_err(|err| CliError::Io {
+                    path: err.path().display().to_string(),
+                    source: io::Error::new(err.error().kind(), err.error().to_string()),
+                })?;
+
+                if entry.is_dir() {
+                    if recursive {
+                        walk_dir(&entry, &mut files).map_err(|source| CliError::Io {
+                            path: entry.display().to_string(),
+                            source,
+                        })?;
+                    }
+                } else {
+                    files.push(entry);
+                }
+            }
+            files
+        } else {
+            inputs.files.push(arg.to_string());
+            continue;
+        };
+
+        for file in discovered {
+            let filename = file.display().to_string();
+            if is_known(&file) {
+                inputs.files.push(filename);
+            } else {
+                inputs.skipped.push(filename);
+            }
+        }
+    }
+
+    Result::Ok(inputs)
+}
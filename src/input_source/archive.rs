@@ -0,0 +1,136 @@
+use std::{
+    fs,
+    io::{self, Read},
+    path::Path,
+};
+
+use crate::{compression::Compression, error::CliError};
+
+/// Kind of an archive of input files, whose entries are extracted as
+/// separate inputs.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Archive {
+    Zip,
+
+    /// A tar archive, which may be compressed as a whole, e.g. `.tar.gz`.
+    Tar(Compression),
+}
+
+/// An entry of an archive, read into memory.
+pub struct ArchiveEntry {
+    /// The path of the entry within the archive.
+    pub name: String,
+
+    /// The contents of the entry.
+    pub body: Vec<u8>,
+}
+
+impl Archive {
+    /// Guess whether `path` is an archive from its extension: `.zip`, or
+    /// `.tar` with an optional compression extension, e.g. `.tar.gz`.
+    /// `.tgz` is a gzip-compressed tar archive.
+    pub fn from_path(path: &Path) -> Option<Archive> {
+        match path.extension().and_then(|ext| ext.to_str())? {
+            "zip" => return Some(Archive::Zip),
+            "tgz" => return Some(Archive::Tar(Compression::Gzip)),
+            _ => {}
+        }
+
+        let (compression, path) = Compression::from_path(path);
+        match path.extension().and_then(|ext| ext.to_str())? {
+            "tar" => Some(Archive::Tar(compression)),
+            _ => None,
+        }
+    }
+
+    /// Read the file entries of the archive at `path` whose names satisfy
+    /// `wanted`, in the order they are stored. Directories and other
+    /// entries that are not files are skipped.
+    pub fn entries(
+        &self,
+        path: &Path,
+        wanted: impl Fn(&str) -> bool,
+    ) -> Result<Vec<ArchiveEntry>, CliError> {
+        let io_error = |source| CliError::Io {
+            path: path.display().to_string(),
+            source,
+        };
+
+        let file = fs::File::open(path).map_err(io_error)?;
+        match self {
+            Archive::Zip => read_zip(file, wanted).map_err(io_error),
+
+            Archive::Tar(compression) => {
+                let reader = compression.decompress(Box::new(file)).map_err(io_error)?;
+                read_tar(reader, wanted).map_err(io_error)
+            }
+        }
+    }
+}
+
+#[cfg(feature = "archive")]
+fn read_zip(file: fs::File, wanted: impl Fn(&str) -> bool) -> io::Result<Vec<ArchiveEntry>> {
+    let mut archive = zip::ZipArchive::new(file)?;
+
+    let mut entries = Vec::new();
+    for i in 0..archive.len() {
+        let mut entry = archive.by_index(i)?;
+        if !entry.is_file() || !wanted(entry.name()) {
+            continue;
+        }
+
+        let mut body = Vec::with_capacity(entry.size() as usize);
+        entry.read_to_end(&mut body)?;
+        entries.push(ArchiveEntry {
+            name: entry.name().to_string(),
+            body,
+        });
+    }
+
+    io::Result::Ok(entries)
+}
+
+#[cfg(feature = "archive")]
+fn read_tar(reader: Box<dyn Read>, wanted: impl Fn(&str) -> bool) -> io::Result<Vec<ArchiveEntry>> {
+    let mut archive = tar::Archive::new(reader);
+
+    let mut entries = Vec::new();
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        if !entry.header().entry_type().is_file() {
+            continue;
+        }
+
+        let name = entry.path()?.to_string_lossy().to_string();
+        if !wanted(&name) {
+            continue;
+        }
+
+        let mut body = Vec::with_capacity(entry.size() as usize);
+        entry.read_to_end(&mut body)?;
+        entries.push(ArchiveEntry { name, body });
+    }
+
+    io::Result::Ok(entries)
+}
+
+#[cfg(not(feature = "archive"))]
+fn read_zip(_file: fs::File, _wanted: impl Fn(&str) -> bool) -> io::Result<Vec<ArchiveEntry>> {
+    Err(without_archive_feature())
+}
+
+#[cfg(not(feature = "archive"))]
+fn read_tar(
+    _reader: Box<dyn Read>,
+    _wanted: impl Fn(&str) -> bool,
+) -> io::Result<Vec<ArchiveEntry>> {
+    Err(without_archive_feature())
+}
+
+#[cfg(not(feature = "archive"))]
+fn without_archive_feature() -> io::Error {
+    io::Error::new(
+        io::ErrorKind::Unsupported,
+        "cannot read archives; serde_datalog was built without the archive feature",
+    )
+}
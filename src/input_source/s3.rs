@@ -0,0 +1,327 @@
+//! Inputs read from S3 and S3-compatible object stores, with `s3://` URLs.
+//!
+//! Requests are signed with AWS Signature Version 4 using credentials from
+//! the `AWS_ACCESS_KEY_ID`, `AWS_SECRET_ACCESS_KEY`, and `AWS_SESSION_TOKEN`
+//! environment variables; without credentials, requests are unsigned, which
+//! suffices for public buckets. The region is read from `AWS_REGION` or
+//! `AWS_DEFAULT_REGION`, and S3-compatible stores are reached through the
+//! endpoint in `AWS_ENDPOINT_URL_S3` or `AWS_ENDPOINT_URL`.
+
+use hmac::{Hmac, Mac};
+use sha2::{Digest, Sha256};
+use std::{env, fmt::Write};
+
+use super::{call, remote_object, rfc3339_utc, RemoteObject, RemoteSource};
+use crate::error::CliError;
+
+/// Region of requests if none is configured.
+const DEFAULT_REGION: &str = "us-east-1";
+
+/// Payload hash of requests; S3 accepts unsigned payloads, and GET requests
+/// have none.
+const UNSIGNED_PAYLOAD: &str = "UNSIGNED-PAYLOAD";
+
+struct Credentials {
+    access_key_id: String,
+    secret_access_key: String,
+    session_token: Option<String>,
+}
+
+/// Objects of an S3 bucket, named by URLs `s3://bucket/key`.
+pub struct S3 {
+    region: String,
+
+    /// Endpoint of an S3-compatible store, which is addressed with
+    /// path-style URLs. AWS is addressed with virtual-hosted-style URLs.
+    endpoint: Option<String>,
+
+    credentials: Option<Credentials>,
+}
+
+impl S3 {
+    /// Configure requests from the `AWS_*` environment variables.
+    pub fn from_env() -> Self {
+        let var = |name: &str| env::var(name).ok().filter(|value| !value.is_empty());
+
+        let credentials = var("AWS_ACCESS_KEY_ID")
+            .zip(var("AWS_SECRET_ACCESS_KEY"))
+            .map(|(access_key_id, secret_access_key)| Credentials {
+                access_key_id,
+                secret_access_key,
+                session_token: var("AWS_SESSION_TOKEN"),
+            });
+
+        S3 {
+            region: var("AWS_REGION")
+                .or_else(|| var("AWS_DEFAULT_REGION"))
+                .unwrap_or_else(|| DEFAULT_REGION.to_string()),
+            endpoint: var("AWS_ENDPOINT_URL_S3")
+                .or_else(|| var("AWS_ENDPOINT_URL"))
+                .map(|endpoint| endpoint.trim_end_matches('/').to_string()),
+            credentials,
+        }
+    }
+
+    /// Send a signed GET request for `key` of `bucket`, with query
+    /// parameters `query`.
+    fn get(
+        &self,
+        url: &str,
+        bucket: &str,
+        key: &str,
+        query: &[(&str, &str)],
+    ) -> Result<ureq::Response, CliError> {
+        let (base, path) = match &self.endpoint {
+            Some(endpoint) if key.is_empty() => (endpoint.clone(), format!("/{}", bucket)),
+            Some(endpoint) => (endpoint.clone(), format!("/{}/{}", bucket, key)),
+            None => (
+                format!("https://{}.s3.{}.amazonaws.com", bucket, self.region),
+                format!("/{}", key),
+            ),
+        };
+        let host = base
+            .split_once("://")
+            .map_or(base.as_str(), |(_, host)| host)
+            .to_string();
+
+        let path = uri_encode(&path, false);
+        let mut query: Vec<(String, String)> = query
+            .iter()
+            .map(|(name, value)| (uri_encode(name, true), uri_encode(value, true)))
+            .collect();
+        query.sort();
+        let query = query
+            .iter()
+            .map(|(name, value)| format!("{}={}", name, value))
+            .collect::<Vec<_>>()
+            .join("&");
+
+        let request_url = match query.as_str() {
+            "" => format!("{}{}", base, path),
+            query => format!("{}{}?{}", base, path, query),
+        };
+        let mut request = ureq::get(&request_url);
+
+        if let Some(credentials) = &self.credentials {
+            // e.g. 20240131T120000Z
+            let timestamp = rfc3339_utc(std::time::SystemTime::now()).replace(['-', ':'], "");
+            let date = &timestamp[..8];
+            let scope = format!("{}/{}/s3/aws4_request", date, self.region);
+
+            let mut headers = vec![
+                ("host", host),
+                ("x-amz-content-sha256", UNSIGNED_PAYLOAD.to_string()),
+                ("x-amz-date", timestamp.clone()),
+            ];
+            if let Some(token) = &credentials.session_token {
+                headers.push(("x-amz-security-token", token.clone()));
+            }
+
+            let signed_headers = headers
+                .iter()
+                .map(|(name, _)| *name)
+                .collect::<Vec<_>>()
+                .join(";");
+            let canonical_request = format!(
+                "GET\n{}\n{}\n{}\n{}\n{}",
+                path,
+                query,
+                headers
+                    .iter()
+                    .map(|(name, value)| format!("{}:{}\n", name, value.trim()))
+                    .collect::<String>(),
+                signed_headers,
+                UNSIGNED_PAYLOAD
+            );
+            let string_to_sign = format!(
+                "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+                timestamp,
+                scope,
+                hex(&Sha256::digest(canonical_request.as_bytes()))
+            );
+
+            let secret = format!("AWS4{}", credentials.secret_access_key);
+            let signing_key = [date, self.region.as_str(), "s3", "aws4_request"]
+                .iter()
+                .fold(secret.into_bytes(), |key, part| hmac(&key, part));
+            let signature = hex(&hmac(&signing_key, &string_to_sign));
+
+            for (name, value) in headers.iter().skip(1) {
+                request = request.set(name, value);
+            }
+            request = request.set(
+                "Authorization",
+                &format!(
+                    "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+                    credentials.access_key_id, scope, signed_headers, signature
+                ),
+            );
+        }
+
+        call(request, url)
+    }
+}
+
+impl RemoteSource for S3 {
+    /// Lists the objects whose keys start with the key of `url`, or only the
+    /// object with that key if there is one, so that `s3://bucket/prefix/`
+    /// names every object under the prefix. Keys ending with `/`, which
+    /// conventionally mark folders, are skipped.
+    fn list(&self, url: &str) -> Result<Vec<String>, CliError> {
+        let (bucket, prefix) = parse_url(url)?;
+
+        let mut keys = Vec::new();
+        let mut continuation_token: Option<String> = None;
+        loop {
+            let mut query = vec![("list-type", "2"), ("prefix", prefix)];
+            if let Some(token) = &continuation_token {
+                query.push(("continuation-token", token.as_str()));
+            }
+
+            let body = self
+                .get(url, bucket, "", &query)?
+                .into_string()
+                .map_err(|source| CliError::Io {
+                    path: url.to_string(),
+                    source,
+                })?;
+
+            keys.extend(elements(&body, "Key"));
+            continuation_token = match elements(&body, "IsTruncated").next().as_deref() {
+                Some("true") => elements(&body, "NextContinuationToken").next(),
+                _ => None,
+            };
+            if continuation_token.is_none() {
+                break;
+            }
+        }
+
+        // a prefix ending with `/` names a folder even if its marker exists
+        if !prefix.is_empty() && !prefix.ends_with('/') && keys.iter().any(|key| key == prefix) {
+            return Result::Ok(vec![url.to_string()]);
+        }
+
+        Result::Ok(
+            keys.into_iter()
+                .filter(|key| !key.ends_with('/'))
+                .map(|key| format!("s3://{}/{}", bucket, key))
+                .collect(),
+        )
+    }
+
+    fn open(&self, url: &str) -> Result<RemoteObject, CliError> {
+        let (bucket, key) = parse_url(url)?;
+        if key.is_empty() {
+            return Result::Err(CliError::Usage(format!(
+                "{} names a bucket, not an object",
+                url
+            )));
+        }
+
+        let response = self.get(url, bucket, key, &[])?;
+        Result::Ok(remote_object(response))
+    }
+}
+
+/// Split URL `s3://bucket/key` into its bucket and key.
+fn parse_url(url: &str) -> Result<(&str, &str), CliError> {
+    let rest = url.strip_prefix("s3://").unwrap_or(url);
+    let (bucket, key) = rest.split_once('/').unwrap_or((rest, ""));
+
+    if bucket.is_empty() {
+        return Result::Err(CliError::Usage(format!("{} has no bucket name", url)));
+    }
+    Result::Ok((bucket, key))
+}
+
+/// Percent-encode `text` as required by Signature Version 4: all bytes
+/// except unreserved characters are encoded, and `/` is encoded unless
+/// `text` is a path.
+fn uri_encode(text: &str, encode_slash: bool) -> String {
+    let mut encoded = String::with_capacity(text.len());
+    for byte in text.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                encoded.push(byte as char)
+            }
+            b'/' if !encode_slash => encoded.push('/'),
+            _ => {
+                let _ = write!(encoded, "%{:02X}", byte);
+            }
+        }
+    }
+    encoded
+}
+
+fn hmac(key: &[u8], data: &str) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts keys of any size");
+    mac.update(data.as_bytes());
+    mac.finalize().into_bytes().to_vec()
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .fold(String::with_capacity(bytes.len() * 2), |mut text, byte| {
+            let _ = write!(text, "{:02x}", byte);
+            text
+        })
+}
+
+/// The unescaped text of the elements named `tag` of XML document `xml`.
+/// Responses of S3 are flat enough that their elements can be found without
+/// parsing the document.
+fn elements<'a>(xml: &'a str, tag: &str) -> impl Iterator<Item = String> + 'a {
+    let open = format!("<{}>", tag);
+    let close = format!("</{}>", tag);
+
+    let mut rest = xml;
+    std::iter::from_fn(move || {
+        let start = rest.find(&open)? + open.len();
+        let len = rest[start..].find(&close)?;
+        let text = &rest[start..start + len];
+        rest = &rest[start + len + close.len()..];
+        Some(unescape(text))
+    })
+}
+
+/// Replace the entity and character references of XML text `text`.
+fn unescape(text: &str) -> String {
+    let mut unescaped = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(amp) = rest.find('&') {
+        unescaped.push_str(&rest[..amp]);
+        rest = &rest[amp..];
+
+        let Some(semi) = rest.find(';') else {
+            break;
+        };
+        let reference = &rest[1..semi];
+        let ch = match reference {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            _ => reference
+                .strip_prefix("#x")
+                .map(|hex| u32::from_str_radix(hex, 16))
+                .or_else(|| reference.strip_prefix('#').map(str::parse))
+                .and_then(Result::ok)
+                .and_then(char::from_u32),
+        };
+
+        match ch {
+            Some(ch) => {
+                unescaped.push(ch);
+                rest = &rest[semi + 1..];
+            }
+            None => {
+                unescaped.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    unescaped.push_str(rest);
+    unescaped
+}
@@ -84,14 +84,92 @@
 //! input.serialize(&mut extractor);
 //! backend.dump_to_db("input.db");
 //! ```
-
-use serde::ser;
+//!
+//! # Flattened Fields
+//!
+//! Serde serializes a struct with `#[serde(flatten)]` fields as a map, whose
+//! entries are the struct's own fields followed by the entries of its
+//! flattened fields. The extractor sees only the map, so such a struct is
+//! extracted as a single map element, without a struct type:
+//!
+//! - every field of the struct and of its flattened structs is a map entry
+//!   whose key is a string element holding the field's name;
+//! - the entries of a flattened map are entries of the same map element;
+//! - a flattened enum variant is an entry whose key is the variant name and
+//!   whose value is the variant's content: the value of a newtype variant, a
+//!   sequence of a tuple variant's fields, or a struct of a struct variant's
+//!   fields, with the variant name as its type name;
+//! - a flattened `None` or `()` has no entries.
+//!
+//! ```ignore
+//! #[derive(Serialize)]
+//! struct User {
+//!     id: u32,
+//!     #[serde(flatten)]
+//!     name: Name, // struct Name { first: String, last: String }
+//! }
+//! ```
+//!
+//! A `User` is extracted as a map element with entries keyed by `"id"`,
+//! `"first"`, and `"last"`. Since flattened structs have no struct elements,
+//! [StructHints] do not apply to their fields. Serde cannot flatten unit
+//! variants, unit structs, and other scalars; see [reset][DatalogExtractor::reset] for
+//! recovering from such errors.
+
+use config::ExtractorConfig;
+use datetime::Datetime;
+use delegate::delegate;
+use progress::{CancellationToken, Progress, ProgressCallback, CANCELLATION_CHECK_INTERVAL};
+use quota::FactQuotas;
+use recognize::{Recognizers, Scalar};
+use redact::Redaction;
+use sample::{Capture, Discard, Sampler, Sampling};
+use schema_guide::{SchemaGuide, SchemaNodeId};
+use serde::{ser, Deserialize, Serialize};
 use std::{
+    borrow::Cow,
+    collections::{HashMap, HashSet},
     fmt::{self, Display},
+    net::IpAddr,
     result,
+    time::{Duration, Instant},
 };
+use string_policy::{fnv1a, StringPolicy};
+use unit_policy::UnitPolicy;
 
+#[cfg(feature = "analyze")]
+pub mod analyze;
 pub mod backend;
+pub mod bytes_policy;
+pub mod config;
+pub mod datetime;
+#[cfg(all(feature = "sqlite", not(target_arch = "wasm32")))]
+pub mod diff;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+#[cfg(all(feature = "sqlite", not(target_arch = "wasm32")))]
+pub mod infer;
+pub mod progress;
+#[cfg(feature = "python")]
+pub mod python;
+pub mod quota;
+pub mod recognize;
+pub mod redact;
+pub mod sample;
+pub mod schema_guide;
+pub mod snapshot;
+pub mod string_policy;
+pub mod tee;
+#[cfg(feature = "testing")]
+pub mod testing;
+pub mod unit_policy;
+#[cfg(all(feature = "sqlite", not(target_arch = "wasm32")))]
+pub mod validate;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+
+#[cfg(feature = "input_format")]
+pub mod input_format;
 
 /// Error encountered during extraction.
 #[derive(Debug)]
@@ -108,7 +186,69 @@ pub enum DatalogExtractionError {
     /// Unsigned int could not be coerced into a signed int
     IntegerCastOverflow(u64),
 
+    /// Backend could not store or load facts
+    Backend(backend::BackendError),
+
+    /// Extraction was cancelled, or its deadline passed
+    Cancelled,
+
     Custom(String),
+
+    /// Extraction of a value nested in a root value failed, with where it
+    /// failed; see [with_error_context][DatalogExtractor::with_error_context]
+    InValue(Box<ErrorContext>),
+}
+
+impl DatalogExtractionError {
+    /// Where extraction failed, if the error has context.
+    pub fn context(&self) -> Option<&ErrorContext> {
+        match self {
+            DatalogExtractionError::InValue(context) => Some(context),
+            _ => None,
+        }
+    }
+
+    /// The error without its context, if any.
+    pub fn without_context(self) -> Self {
+        match self {
+            DatalogExtractionError::InValue(context) => context.error,
+            err => err,
+        }
+    }
+}
+
+/// Where in a root value extraction failed.
+#[derive(Debug)]
+pub struct ErrorContext {
+    /// Path from the root value to the innermost value being extracted,
+    /// e.g. `$.items[3]["name"]` for the value of key `name` of a map at
+    /// position 3 of the field `items` of the root value.
+    pub path: String,
+
+    /// Number of facts generated from the current input file, or since the
+    /// extractor was created if no file was set, before extraction failed.
+    pub facts: usize,
+
+    /// The error that extraction failed with.
+    pub error: DatalogExtractionError,
+}
+
+/// Segment of the path to a value, see [ErrorContext::path].
+enum PathSegment {
+    Field(&'static str),
+    Key(Option<String>),
+    Pos(usize),
+}
+
+impl Display for PathSegment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PathSegment::Field(field) => write!(f, ".{}", field),
+            PathSegment::Key(Some(key)) => write!(f, "[{:?}]", key),
+            PathSegment::Key(None) => write!(f, "[?]"),
+            PathSegment::Pos(pos) => write!(f, "[{}]", pos),
+        }
+    }
 }
 
 impl Display for DatalogExtractionError {
@@ -130,9 +270,25 @@ impl Display for DatalogExtractionError {
                 write!(f, "could not coerce unsigned int {} to signed int", value)
             }
 
+            DatalogExtractionError::Backend(err) => {
+                write!(f, "backend error: {}", err)
+            }
+
+            DatalogExtractionError::Cancelled => {
+                write!(f, "extraction cancelled")
+            }
+
             DatalogExtractionError::Custom(msg) => {
                 write!(f, "{}", msg)
             }
+
+            DatalogExtractionError::InValue(context) => {
+                write!(
+                    f,
+                    "{} at {} after {} fact(s)",
+                    context.error, context.path, context.facts
+                )
+            }
         }
     }
 }
@@ -143,13 +299,34 @@ impl ser::Error for DatalogExtractionError {
     }
 }
 
-impl std::error::Error for DatalogExtractionError {}
+impl std::error::Error for DatalogExtractionError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            DatalogExtractionError::Backend(err) => Some(err),
+            DatalogExtractionError::InValue(context) => Some(&context.error),
+            _ => None,
+        }
+    }
+}
+
+impl From<backend::BackendError> for DatalogExtractionError {
+    fn from(err: backend::BackendError) -> Self {
+        DatalogExtractionError::Backend(err)
+    }
+}
+
+#[cfg(all(feature = "sqlite", not(target_arch = "wasm32")))]
+impl From<rusqlite::Error> for DatalogExtractionError {
+    fn from(err: rusqlite::Error) -> Self {
+        DatalogExtractionError::Backend(backend::BackendError::Sqlite(err))
+    }
+}
 
 pub type Result<T> = std::result::Result<T, DatalogExtractionError>;
 
 /// A unique identifier for data elements.
 /// Identifiers are automatically generated by the [extractor][DatalogExtractor].
-#[derive(PartialEq, Eq, Hash, Clone, Copy, Debug)]
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy, Debug, Serialize, Deserialize)]
 pub struct ElemId(usize);
 
 impl Display for ElemId {
@@ -167,7 +344,7 @@ impl Display for ElemId {
 /// That is, `None` values are treated as unit variants with type name `Option`
 /// and variant name `None`, while `Some` values are treated as newtype variants
 /// with type name `Option` and variant name `Some`.
-#[derive(PartialEq, Eq, Hash, Clone, Debug)]
+#[derive(PartialEq, Eq, Hash, Clone, Debug, Serialize, Deserialize)]
 pub enum ElemType {
     Bool,
     I8,
@@ -197,13 +374,581 @@ pub enum ElemType {
     UnitVariant,
 }
 
+impl ElemType {
+    /// Every element type.
+    pub const ALL: [ElemType; 26] = [
+        ElemType::Bool,
+        ElemType::I8,
+        ElemType::I16,
+        ElemType::I32,
+        ElemType::I64,
+        ElemType::U8,
+        ElemType::U16,
+        ElemType::U32,
+        ElemType::U64,
+        ElemType::F32,
+        ElemType::F64,
+        ElemType::Char,
+        ElemType::Str,
+        ElemType::Bytes,
+        ElemType::Map,
+        ElemType::NewtypeStruct,
+        ElemType::NewtypeVariant,
+        ElemType::Seq,
+        ElemType::Struct,
+        ElemType::StructVariant,
+        ElemType::Tuple,
+        ElemType::TupleStruct,
+        ElemType::TupleVariant,
+        ElemType::Unit,
+        ElemType::UnitStruct,
+        ElemType::UnitVariant,
+    ];
+
+    /// Kind of the element type.
+    pub fn kind(&self) -> ElemKind {
+        match self {
+            ElemType::Bool
+            | ElemType::I8
+            | ElemType::I16
+            | ElemType::I32
+            | ElemType::I64
+            | ElemType::U8
+            | ElemType::U16
+            | ElemType::U32
+            | ElemType::U64
+            | ElemType::F32
+            | ElemType::F64
+            | ElemType::Char
+            | ElemType::Str
+            | ElemType::Bytes
+            | ElemType::Unit => ElemKind::Scalar,
+
+            ElemType::Map | ElemType::Seq | ElemType::Tuple => ElemKind::Container,
+
+            ElemType::NewtypeVariant
+            | ElemType::StructVariant
+            | ElemType::TupleVariant
+            | ElemType::UnitVariant => ElemKind::Variant,
+
+            ElemType::NewtypeStruct
+            | ElemType::Struct
+            | ElemType::TupleStruct
+            | ElemType::UnitStruct => ElemKind::Struct,
+        }
+    }
+}
+
+/// Classification of element types, so that Datalog rules can tell e.g.
+/// scalars from containers without listing every element type. The kind of
+/// each element type is materialized with
+/// [add_elem_type_kind][DatalogExtractorBackend::add_elem_type_kind].
+#[derive(PartialEq, Eq, Hash, Clone, Copy, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ElemKind {
+    /// Booleans, numbers, chars, strings, byte arrays, and units.
+    Scalar,
+
+    /// Maps, sequences, and tuples.
+    Container,
+
+    /// Enum variants, including options.
+    Variant,
+
+    /// Structs, including newtype, tuple, and unit structs.
+    Struct,
+}
+
+impl ElemKind {
+    /// Name of the kind in `elemTypeKind` facts.
+    pub fn name(&self) -> &'static str {
+        match self {
+            ElemKind::Scalar => "scalar",
+            ElemKind::Container => "container",
+            ElemKind::Variant => "variant",
+            ElemKind::Struct => "struct",
+        }
+    }
+
+    /// The kind named `name`, as returned by [name][Self::name].
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "scalar" => Some(ElemKind::Scalar),
+            "container" => Some(ElemKind::Container),
+            "variant" => Some(ElemKind::Variant),
+            "struct" => Some(ElemKind::Struct),
+            _ => None,
+        }
+    }
+}
+
+/// A fact generated by [DatalogExtractor]. Each variant corresponds to a
+/// method of [DatalogExtractorBackend] that materializes the fact, so facts
+/// can be passed between extractors and backends, e.g. buffered, recorded,
+/// or sent over the network, and materialized later with [add_to][Self::add_to].
+///
+/// Facts serialize as externally tagged enums named after their variants,
+/// with fields named as below, e.g. `{"SeqEntry": {"elem": 1, "pos": 0,
+/// "value": 2}}` in JSON. Variants and fields are only ever added, so facts
+/// serialized by one version can be deserialized by later versions. Facts
+/// convert to and from the data of vector backends with
+/// [BackendData::facts][backend::vector::BackendData::facts] and
+/// [BackendData::from_facts][backend::vector::BackendData::from_facts].
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub enum Fact {
+    RootElem {
+        file: String,
+        elem: ElemId,
+    },
+    FileFormat {
+        file: String,
+        format: String,
+    },
+    KeyField {
+        struct_name: String,
+        field: String,
+    },
+    Elem {
+        elem: ElemId,
+        elem_type: ElemType,
+    },
+    Bool {
+        elem: ElemId,
+        value: bool,
+    },
+    I8 {
+        elem: ElemId,
+        value: i8,
+    },
+    I16 {
+        elem: ElemId,
+        value: i16,
+    },
+    I32 {
+        elem: ElemId,
+        value: i32,
+    },
+    I64 {
+        elem: ElemId,
+        value: i64,
+    },
+    U8 {
+        elem: ElemId,
+        value: u8,
+    },
+    U16 {
+        elem: ElemId,
+        value: u16,
+    },
+    U32 {
+        elem: ElemId,
+        value: u32,
+    },
+    U64 {
+        elem: ElemId,
+        value: u64,
+    },
+    F32 {
+        elem: ElemId,
+        value: f32,
+    },
+    F64 {
+        elem: ElemId,
+        value: f64,
+    },
+    Char {
+        elem: ElemId,
+        value: char,
+    },
+    Str {
+        elem: ElemId,
+        value: String,
+    },
+    FullStr {
+        elem: ElemId,
+        value: String,
+    },
+    Bytes {
+        elem: ElemId,
+        value: Vec<u8>,
+    },
+    MapEntry {
+        elem: ElemId,
+        key: ElemId,
+        value: ElemId,
+    },
+    StructType {
+        elem: ElemId,
+        struct_name: String,
+    },
+    StructEntry {
+        elem: ElemId,
+        key: String,
+        value: ElemId,
+    },
+    Key {
+        elem: ElemId,
+        key: ElemId,
+    },
+    SeqEntry {
+        elem: ElemId,
+        pos: usize,
+        value: ElemId,
+    },
+    VariantType {
+        elem: ElemId,
+        type_name: String,
+        variant_name: String,
+    },
+    TupleEntry {
+        elem: ElemId,
+        pos: usize,
+        value: ElemId,
+    },
+    SchemaType {
+        elem: ElemId,
+        schema_ref: String,
+    },
+    Datetime {
+        elem: ElemId,
+        epoch_micros: i64,
+        tz_offset: i32,
+    },
+    Uuid {
+        elem: ElemId,
+        hi: u64,
+        lo: u64,
+    },
+    Ip {
+        elem: ElemId,
+        address: IpAddr,
+    },
+    Url {
+        elem: ElemId,
+        scheme: String,
+        host: String,
+    },
+    Email {
+        elem: ElemId,
+        local: String,
+        domain: String,
+    },
+    DeclaredLen {
+        elem: ElemId,
+        len: usize,
+    },
+    AbsentField {
+        elem: ElemId,
+        field: String,
+    },
+    Unit {
+        elem: ElemId,
+    },
+    Truncated {
+        relation: String,
+        quota: usize,
+    },
+    Redacted {
+        elem: ElemId,
+    },
+    Sampled {
+        elem: ElemId,
+        len: usize,
+        kept: usize,
+    },
+    ElemTypeKind {
+        elem_type: ElemType,
+        kind: ElemKind,
+    },
+    SameString {
+        elem: ElemId,
+        other: ElemId,
+    },
+    Shape {
+        elem: ElemId,
+        shape: u64,
+    },
+    ShapeField {
+        shape: u64,
+        pos: usize,
+        field: String,
+    },
+    ParseError {
+        file: String,
+        line: usize,
+        message: String,
+    },
+    StringHash {
+        elem: ElemId,
+        hash: u64,
+        len: usize,
+    },
+    RootMeta {
+        file: String,
+        key: String,
+        value: String,
+    },
+    Depth {
+        elem: ElemId,
+        depth: usize,
+    },
+    SubtreeSize {
+        elem: ElemId,
+        size: usize,
+    },
+    Codepoint {
+        elem: ElemId,
+        codepoint: u32,
+    },
+}
+
+impl Fact {
+    /// Materialize the fact by calling the corresponding method of `backend`.
+    pub fn add_to<B: DatalogExtractorBackend + ?Sized>(&self, backend: &mut B) -> Result<()> {
+        match self {
+            Fact::RootElem { file, elem } => backend.add_root_elem(file, *elem),
+            Fact::FileFormat { file, format } => backend.add_file_format(file, format),
+            Fact::KeyField { struct_name, field } => backend.add_key_field(struct_name, field),
+            Fact::Elem { elem, elem_type } => backend.add_elem(*elem, elem_type.clone()),
+            Fact::Bool { elem, value } => backend.add_bool(*elem, *value),
+            Fact::I8 { elem, value } => backend.add_i8(*elem, *value),
+            Fact::I16 { elem, value } => backend.add_i16(*elem, *value),
+            Fact::I32 { elem, value } => backend.add_i32(*elem, *value),
+            Fact::I64 { elem, value } => backend.add_i64(*elem, *value),
+            Fact::U8 { elem, value } => backend.add_u8(*elem, *value),
+            Fact::U16 { elem, value } => backend.add_u16(*elem, *value),
+            Fact::U32 { elem, value } => backend.add_u32(*elem, *value),
+            Fact::U64 { elem, value } => backend.add_u64(*elem, *value),
+            Fact::F32 { elem, value } => backend.add_f32(*elem, *value),
+            Fact::F64 { elem, value } => backend.add_f64(*elem, *value),
+            Fact::Char { elem, value } => backend.add_char(*elem, *value),
+            Fact::FullStr { elem, value } => backend.add_full_str(*elem, value),
+            Fact::Str { elem, value } => backend.add_str(*elem, value),
+            Fact::Bytes { elem, value } => backend.add_bytes(*elem, value),
+            Fact::MapEntry { elem, key, value } => backend.add_map_entry(*elem, *key, *value),
+            Fact::StructType { elem, struct_name } => backend.add_struct_type(*elem, struct_name),
+            Fact::StructEntry { elem, key, value } => backend.add_struct_entry(*elem, key, *value),
+            Fact::Key { elem, key } => backend.add_key(*elem, *key),
+            Fact::SeqEntry { elem, pos, value } => backend.add_seq_entry(*elem, *pos, *value),
+            Fact::VariantType {
+                elem,
+                type_name,
+                variant_name,
+            } => backend.add_variant_type(*elem, type_name, variant_name),
+            Fact::TupleEntry { elem, pos, value } => backend.add_tuple_entry(*elem, *pos, *value),
+            Fact::SchemaType { elem, schema_ref } => backend.add_schema_type(*elem, schema_ref),
+            Fact::Datetime {
+                elem,
+                epoch_micros,
+                tz_offset,
+            } => backend.add_datetime(*elem, *epoch_micros, *tz_offset),
+            Fact::Uuid { elem, hi, lo } => backend.add_uuid(*elem, *hi, *lo),
+            Fact::Ip { elem, address } => backend.add_ip(*elem, *address),
+            Fact::Url { elem, scheme, host } => backend.add_url(*elem, scheme, host),
+            Fact::Email {
+                elem,
+                local,
+                domain,
+            } => backend.add_email(*elem, local, domain),
+            Fact::DeclaredLen { elem, len } => backend.add_declared_len(*elem, *len),
+            Fact::AbsentField { elem, field } => backend.add_absent_field(*elem, field),
+            Fact::Unit { elem } => backend.add_unit(*elem),
+            Fact::Truncated { relation, quota } => backend.add_truncated(relation, *quota),
+            Fact::Redacted { elem } => backend.add_redacted(*elem),
+            Fact::Sampled { elem, len, kept } => backend.add_sampled(*elem, *len, *kept),
+            Fact::ElemTypeKind { elem_type, kind } => {
+                backend.add_elem_type_kind(elem_type.clone(), *kind)
+            }
+            Fact::SameString { elem, other } => backend.add_same_string(*elem, *other),
+            Fact::Shape { elem, shape } => backend.add_shape(*elem, *shape),
+            Fact::ShapeField { shape, pos, field } => backend.add_shape_field(*shape, *pos, field),
+            Fact::ParseError {
+                file,
+                line,
+                message,
+            } => backend.add_parse_error(file, *line, message),
+            Fact::StringHash { elem, hash, len } => backend.add_string_hash(*elem, *hash, *len),
+            Fact::RootMeta { file, key, value } => backend.add_root_meta(file, key, value),
+            Fact::Depth { elem, depth } => backend.add_depth(*elem, *depth),
+            Fact::SubtreeSize { elem, size } => backend.add_subtree_size(*elem, *size),
+            Fact::Codepoint { elem, codepoint } => backend.add_codepoint(*elem, *codepoint),
+        }
+    }
+
+    /// Name of the relation of the fact in the Souffle schema. Numbers of
+    /// every type are `number` facts, and chars are `string` facts.
+    pub fn relation(&self) -> &'static str {
+        match self {
+            Fact::RootElem { .. } => "rootElem",
+            Fact::FileFormat { .. } => "fileFormat",
+            Fact::KeyField { .. } => "keyField",
+            Fact::Elem { .. } => "type",
+            Fact::Bool { .. } => "bool",
+            Fact::I8 { .. }
+            | Fact::I16 { .. }
+            | Fact::I32 { .. }
+            | Fact::I64 { .. }
+            | Fact::U8 { .. }
+            | Fact::U16 { .. }
+            | Fact::U32 { .. }
+            | Fact::U64 { .. }
+            | Fact::F32 { .. }
+            | Fact::F64 { .. } => "number",
+            Fact::Char { .. } | Fact::Str { .. } => "string",
+            Fact::FullStr { .. } => "fullString",
+            Fact::Bytes { .. } => "bytes",
+            Fact::MapEntry { .. } => "map",
+            Fact::StructType { .. } => "structType",
+            Fact::StructEntry { .. } => "struct",
+            Fact::Key { .. } => "key",
+            Fact::SeqEntry { .. } => "seq",
+            Fact::VariantType { .. } => "variantType",
+            Fact::TupleEntry { .. } => "tuple",
+            Fact::SchemaType { .. } => "schemaType",
+            Fact::Datetime { .. } => "datetime",
+            Fact::Uuid { .. } => "uuid",
+            Fact::Ip { .. } => "ip",
+            Fact::Url { .. } => "url",
+            Fact::Email { .. } => "email",
+            Fact::DeclaredLen { .. } => "declaredLen",
+            Fact::AbsentField { .. } => "absentField",
+            Fact::Unit { .. } => "unit",
+            Fact::Truncated { .. } => "truncated",
+            Fact::Redacted { .. } => "redacted",
+            Fact::Sampled { .. } => "sampled",
+            Fact::ElemTypeKind { .. } => "elemTypeKind",
+            Fact::SameString { .. } => "sameString",
+            Fact::Shape { .. } => "shape",
+            Fact::ShapeField { .. } => "shapeField",
+            Fact::ParseError { .. } => "parseError",
+            Fact::StringHash { .. } => "stringHash",
+            Fact::RootMeta { .. } => "rootMeta",
+            Fact::Depth { .. } => "depth",
+            Fact::SubtreeSize { .. } => "subtreeSize",
+            Fact::Codepoint { .. } => "char",
+        }
+    }
+
+    /// The element identifiers of the fact, e.g. to renumber the elements of
+    /// facts generated by another extractor.
+    pub fn elems_mut(&mut self) -> Vec<&mut ElemId> {
+        match self {
+            Fact::RootElem { elem, .. }
+            | Fact::Elem { elem, .. }
+            | Fact::Bool { elem, .. }
+            | Fact::I8 { elem, .. }
+            | Fact::I16 { elem, .. }
+            | Fact::I32 { elem, .. }
+            | Fact::I64 { elem, .. }
+            | Fact::U8 { elem, .. }
+            | Fact::U16 { elem, .. }
+            | Fact::U32 { elem, .. }
+            | Fact::U64 { elem, .. }
+            | Fact::F32 { elem, .. }
+            | Fact::F64 { elem, .. }
+            | Fact::Char { elem, .. }
+            | Fact::Str { elem, .. }
+            | Fact::FullStr { elem, .. }
+            | Fact::Bytes { elem, .. }
+            | Fact::StructType { elem, .. }
+            | Fact::VariantType { elem, .. }
+            | Fact::SchemaType { elem, .. }
+            | Fact::Datetime { elem, .. }
+            | Fact::Uuid { elem, .. }
+            | Fact::Ip { elem, .. }
+            | Fact::Url { elem, .. }
+            | Fact::Email { elem, .. }
+            | Fact::DeclaredLen { elem, .. }
+            | Fact::AbsentField { elem, .. }
+            | Fact::Unit { elem }
+            | Fact::Redacted { elem }
+            | Fact::Sampled { elem, .. }
+            | Fact::Shape { elem, .. }
+            | Fact::StringHash { elem, .. }
+            | Fact::Depth { elem, .. }
+            | Fact::SubtreeSize { elem, .. }
+            | Fact::Codepoint { elem, .. } => vec![elem],
+
+            Fact::StructEntry { elem, value, .. }
+            | Fact::SeqEntry { elem, value, .. }
+            | Fact::TupleEntry { elem, value, .. } => vec![elem, value],
+
+            Fact::Key { elem, key } => vec![elem, key],
+            Fact::SameString { elem, other } => vec![elem, other],
+            Fact::MapEntry { elem, key, value } => vec![elem, key, value],
+
+            Fact::FileFormat { .. }
+            | Fact::KeyField { .. }
+            | Fact::Truncated { .. }
+            | Fact::ElemTypeKind { .. }
+            | Fact::ShapeField { .. }
+            | Fact::ParseError { .. }
+            | Fact::RootMeta { .. } => Vec::new(),
+        }
+    }
+}
+
 /// An implementation of `DatalogExtractorBackend` materializes facts generated
 /// by [DatalogExtractor]. These facts can be represented in whatever format
 /// the backend chooses, e.g. a SQLite database, a set of vectors, etc.
+///
+/// Mutable references to backends and boxed backends are backends too, so an
+/// extractor can borrow a backend that is used after extraction. The trait is
+/// object safe, so a backend chosen at runtime can be boxed and used as a
+/// `DatalogExtractor<Box<dyn DatalogExtractorBackend>>`.
 pub trait DatalogExtractorBackend {
+    /// Called by the extractor before it generates facts about a root value,
+    /// i.e. a value directly serialized with the extractor.
+    /// Backends can use this hook to e.g. start a transaction.
+    ///
+    /// The default implementation does nothing.
+    fn begin(&mut self) -> Result<()> {
+        Result::Ok(())
+    }
+
+    /// Called by the extractor after it has generated all facts about a root
+    /// value. Backends can use this hook to e.g. commit a transaction or
+    /// flush buffered writes. This is not called if extraction fails.
+    ///
+    /// The default implementation does nothing.
+    fn finish(&mut self) -> Result<()> {
+        Result::Ok(())
+    }
+
+    /// Materialize a batch of facts. The extractor calls this method instead
+    /// of the methods for individual facts if it buffers facts
+    /// (see [DatalogExtractor::with_batch_size]). Backends can override it to
+    /// amortize per-fact overhead, e.g. by inserting facts in bulk.
+    ///
+    /// The default implementation materializes each fact in order with
+    /// [Fact::add_to].
+    fn add_facts(&mut self, batch: &[Fact]) -> Result<()> {
+        for fact in batch {
+            fact.add_to(self)?;
+        }
+
+        Result::Ok(())
+    }
+
     /// Set `elem` as the root element of `file`.
     fn add_root_elem(&mut self, file: &str, elem: ElemId) -> Result<()>;
 
+    /// Materialize fact that input file `file` has input format `format`.
+    ///
+    /// The default implementation ignores the fact.
+    fn add_file_format(&mut self, _file: &str, _format: &str) -> Result<()> {
+        Result::Ok(())
+    }
+
+    /// Materialize fact that field `field` identifies structs of type
+    /// `struct_name`, as declared with [StructHints::key].
+    ///
+    /// The default implementation ignores the fact.
+    fn add_key_field(&mut self, _struct_name: &str, _field: &str) -> Result<()> {
+        Result::Ok(())
+    }
+
     /// Materialize fact that element with ID `elem` has element type `elem_type`.
     fn add_elem(&mut self, elem: ElemId, elem_type: ElemType) -> Result<()>;
 
@@ -300,6 +1045,14 @@ pub trait DatalogExtractorBackend {
         Result::Err(DatalogExtractionError::UnextractableData("str".to_string()))
     }
 
+    /// Materialize fact that the string element with ID `elem`, whose value
+    /// was changed by the extractor's [StringPolicy], has original text `value`.
+    ///
+    /// The default implementation ignores the fact.
+    fn add_full_str(&mut self, _elem: ElemId, _value: &str) -> Result<()> {
+        Result::Ok(())
+    }
+
     /// Materialize fact that element with ID `elem` is a byte array with value `value`.
     ///
     /// The default implementation returns an
@@ -334,129 +1087,1858 @@ pub trait DatalogExtractorBackend {
         ))
     }
 
-    /// Materialize fact that element with ID `elem` is a struct,
-    /// with value `value` at key `key`.
-    /// The element can have element type [ElemType::Struct] or
-    /// [ElemType::StructVariant].
-    ///
-    /// The default implementation returns an
-    /// [UnextractableData][DatalogExtractionError::UnextractableData] error.
-    fn add_struct_entry(&mut self, _elem: ElemId, _key: &str, _value: ElemId) -> Result<()> {
-        Result::Err(DatalogExtractionError::UnextractableData(
-            "struct entry".to_string(),
-        ))
+    /// Materialize fact that element with ID `elem` is a struct,
+    /// with value `value` at key `key`.
+    /// The element can have element type [ElemType::Struct] or
+    /// [ElemType::StructVariant].
+    ///
+    /// The default implementation returns an
+    /// [UnextractableData][DatalogExtractionError::UnextractableData] error.
+    fn add_struct_entry(&mut self, _elem: ElemId, _key: &str, _value: ElemId) -> Result<()> {
+        Result::Err(DatalogExtractionError::UnextractableData(
+            "struct entry".to_string(),
+        ))
+    }
+
+    /// Materialize fact that element with ID `key` is the value of a key field
+    /// of struct element with ID `elem`, as declared with [StructHints::key].
+    ///
+    /// The default implementation ignores the fact.
+    fn add_key(&mut self, _elem: ElemId, _key: ElemId) -> Result<()> {
+        Result::Ok(())
+    }
+
+    /// Materialize fact that element with ID `elem` is a sequence
+    /// with value `value` at position `pos`.
+    ///
+    /// The default implementation returns an
+    /// [UnextractableData][DatalogExtractionError::UnextractableData] error.
+    fn add_seq_entry(&mut self, _elem: ElemId, _pos: usize, _value: ElemId) -> Result<()> {
+        Result::Err(DatalogExtractionError::UnextractableData(
+            "seq entry".to_string(),
+        ))
+    }
+
+    /// Materialize fact that element with ID `elem` is an enum variant
+    /// with type name `type_name` and variant name `variant_name`.
+    /// The element can have element type
+    /// [ElemType::NewtypeVariant], [ElemType::StructVariant],
+    /// [ElemType::TupleVariant], or [ElemType::UnitVariant].
+    ///
+    /// The default implementation returns an
+    /// [UnextractableData][DatalogExtractionError::UnextractableData] error.
+    fn add_variant_type(
+        &mut self,
+        _elem: ElemId,
+        _type_name: &str,
+        _variant_name: &str,
+    ) -> Result<()> {
+        Result::Err(DatalogExtractionError::UnextractableData(
+            "variant type".to_string(),
+        ))
+    }
+
+    /// Materialize fact that element with ID `elem` is a tuple with value
+    /// `value` at position `pos`.
+    /// The element can have element type [ElemType::NewtypeStruct],
+    /// [ElemType::NewtypeVariant], [ElemType::Tuple], [ElemType::TupleStruct],
+    /// or [ElemType::TupleVariant].
+    ///
+    /// The default implementation returns an
+    /// [UnextractableData][DatalogExtractionError::UnextractableData] error.
+    fn add_tuple_entry(&mut self, _elem: ElemId, _pos: usize, _value: ElemId) -> Result<()> {
+        Result::Err(DatalogExtractionError::UnextractableData(
+            "tuple entry".to_string(),
+        ))
+    }
+
+    /// Materialize fact that element with ID `elem` matches the schema with
+    /// reference `schema_ref` of the extractor's [SchemaGuide].
+    ///
+    /// The default implementation ignores the fact.
+    fn add_schema_type(&mut self, _elem: ElemId, _schema_ref: &str) -> Result<()> {
+        Result::Ok(())
+    }
+
+    /// Materialize fact that the string element with ID `elem` is a
+    /// date-time at `epoch_micros` microseconds since the Unix epoch, written
+    /// with an offset of `tz_offset` seconds east of UTC. See [datetime] for
+    /// the strings that are recognized as date-times.
+    ///
+    /// The default implementation ignores the fact.
+    fn add_datetime(&mut self, _elem: ElemId, _epoch_micros: i64, _tz_offset: i32) -> Result<()> {
+        Result::Ok(())
+    }
+
+    /// Materialize fact that the string element with ID `elem` is a UUID
+    /// whose high and low 64 bits are `hi` and `lo`. See [recognize].
+    ///
+    /// The default implementation ignores the fact.
+    fn add_uuid(&mut self, _elem: ElemId, _hi: u64, _lo: u64) -> Result<()> {
+        Result::Ok(())
+    }
+
+    /// Materialize fact that the string element with ID `elem` is the IP
+    /// address `address`. See [recognize].
+    ///
+    /// The default implementation ignores the fact.
+    fn add_ip(&mut self, _elem: ElemId, _address: IpAddr) -> Result<()> {
+        Result::Ok(())
+    }
+
+    /// Materialize fact that the string element with ID `elem` is a URL
+    /// with scheme `scheme` and host `host`. See [recognize].
+    ///
+    /// The default implementation ignores the fact.
+    fn add_url(&mut self, _elem: ElemId, _scheme: &str, _host: &str) -> Result<()> {
+        Result::Ok(())
+    }
+
+    /// Materialize fact that the string element with ID `elem` is an email
+    /// address with local part `local` and domain `domain`. See [recognize].
+    ///
+    /// The default implementation ignores the fact.
+    fn add_email(&mut self, _elem: ElemId, _local: &str, _domain: &str) -> Result<()> {
+        Result::Ok(())
+    }
+
+    /// Materialize fact that the [serde::Serialize] implementation of the
+    /// struct element with ID `elem` declared that it serializes `len`
+    /// fields. See [with_sparse_structs][DatalogExtractor::with_sparse_structs].
+    ///
+    /// The default implementation ignores the fact.
+    fn add_declared_len(&mut self, _elem: ElemId, _len: usize) -> Result<()> {
+        Result::Ok(())
+    }
+
+    /// Materialize fact that the struct element with ID `elem` has no field
+    /// `field`, because its [serde::Serialize] implementation skipped it or
+    /// because it is missing from the fields declared with
+    /// [StructHints::fields]. See
+    /// [with_sparse_structs][DatalogExtractor::with_sparse_structs].
+    ///
+    /// The default implementation ignores the fact.
+    fn add_absent_field(&mut self, _elem: ElemId, _field: &str) -> Result<()> {
+        Result::Ok(())
+    }
+
+    /// Materialize fact that the element with ID `elem` is a unit or unit
+    /// struct value. This is only called if the extractor's [UnitPolicy] is
+    /// [Facts][UnitPolicy::Facts].
+    ///
+    /// The default implementation ignores the fact.
+    fn add_unit(&mut self, _elem: ElemId) -> Result<()> {
+        Result::Ok(())
+    }
+
+    /// Materialize fact that facts of the relation named `relation` were
+    /// dropped after `quota` of them were extracted. This is only called if
+    /// the extractor has [FactQuotas], once for each relation that exceeds
+    /// its quota.
+    ///
+    /// The default implementation ignores the fact.
+    fn add_truncated(&mut self, _relation: &str, _quota: usize) -> Result<()> {
+        Result::Ok(())
+    }
+
+    /// Materialize fact that the value of the element with ID `elem` was
+    /// redacted. This is only called if the extractor has a [Redaction],
+    /// after the redacted value of the element is materialized.
+    ///
+    /// The default implementation ignores the fact.
+    fn add_redacted(&mut self, _elem: ElemId) -> Result<()> {
+        Result::Ok(())
+    }
+
+    /// Materialize fact that only `kept` of the `len` elements of the
+    /// sequence with ID `elem` were extracted. This is only called if the
+    /// extractor has a [Sampling], after the sampled entries of the sequence
+    /// are materialized.
+    ///
+    /// The default implementation ignores the fact.
+    fn add_sampled(&mut self, _elem: ElemId, _len: usize, _kept: usize) -> Result<()> {
+        Result::Ok(())
+    }
+
+    /// Materialize fact that element type `elem_type` has kind `kind`. This
+    /// is called for every element type before the first element is
+    /// materialized, so that the facts are static.
+    ///
+    /// The default implementation ignores the fact.
+    fn add_elem_type_kind(&mut self, _elem_type: ElemType, _kind: ElemKind) -> Result<()> {
+        Result::Ok(())
+    }
+
+    /// Materialize fact that the string element with ID `elem` has the same
+    /// value as the string element with ID `other`, the first element with
+    /// the value in an earlier input file. This is only called if the
+    /// extractor [correlates strings][DatalogExtractor::with_same_strings],
+    /// after the value of `elem` is materialized.
+    ///
+    /// The default implementation ignores the fact.
+    fn add_same_string(&mut self, _elem: ElemId, _other: ElemId) -> Result<()> {
+        Result::Ok(())
+    }
+
+    /// Materialize fact that the struct or map element with ID `elem` has
+    /// the shape `shape`, which identifies its struct type and the names of
+    /// its fields. See [with_shapes][DatalogExtractor::with_shapes].
+    ///
+    /// The default implementation ignores the fact.
+    fn add_shape(&mut self, _elem: ElemId, _shape: u64) -> Result<()> {
+        Result::Ok(())
+    }
+
+    /// Materialize fact that `field` is the field at position `pos` of the
+    /// fields of shape `shape`, sorted by their names. This is called for
+    /// every field of a shape before the first element with the shape is
+    /// materialized. See [with_shapes][DatalogExtractor::with_shapes].
+    ///
+    /// The default implementation ignores the fact.
+    fn add_shape_field(&mut self, _shape: u64, _pos: usize, _field: &str) -> Result<()> {
+        Result::Ok(())
+    }
+
+    /// Materialize fact that the document at line or position `line` of
+    /// input file `file`, or at an unknown position if `line` is 0, could
+    /// not be read or extracted, failing with error message `message`. See
+    /// [record_parse_error][DatalogExtractor::record_parse_error].
+    ///
+    /// The default implementation ignores the fact.
+    fn add_parse_error(&mut self, _file: &str, _line: usize, _message: &str) -> Result<()> {
+        Result::Ok(())
+    }
+
+    /// Materialize fact that the string element with ID `elem` has a value
+    /// of `len` bytes whose hash is `hash`, which was
+    /// [elided][StringPolicy::elide] instead of materialized with
+    /// [add_str][Self::add_str]. Equal strings have equal hashes.
+    ///
+    /// The default implementation ignores the fact.
+    fn add_string_hash(&mut self, _elem: ElemId, _hash: u64, _len: usize) -> Result<()> {
+        Result::Ok(())
+    }
+
+    /// Materialize fact that the root named `file` has metadata `value` for
+    /// `key`, e.g. a logical name or the URI the input was fetched from. See
+    /// [set_root_meta][DatalogExtractor::set_root_meta].
+    ///
+    /// The default implementation ignores the fact.
+    fn add_root_meta(&mut self, _file: &str, _key: &str, _value: &str) -> Result<()> {
+        Result::Ok(())
+    }
+
+    /// Materialize fact that the element with ID `elem` is nested `depth`
+    /// levels below its root element, whose depth is 0. See
+    /// [with_measures][DatalogExtractor::with_measures].
+    ///
+    /// The default implementation ignores the fact.
+    fn add_depth(&mut self, _elem: ElemId, _depth: usize) -> Result<()> {
+        Result::Ok(())
+    }
+
+    /// Materialize fact that the value of the element with ID `elem` has
+    /// `size` elements, including the element itself. See
+    /// [with_measures][DatalogExtractor::with_measures].
+    ///
+    /// The default implementation ignores the fact.
+    fn add_subtree_size(&mut self, _elem: ElemId, _size: usize) -> Result<()> {
+        Result::Ok(())
+    }
+
+    /// Materialize fact that the char element with ID `elem` has Unicode
+    /// code point `codepoint`. See
+    /// [with_char_codepoints][DatalogExtractor::with_char_codepoints].
+    ///
+    /// The default implementation ignores the fact.
+    fn add_codepoint(&mut self, _elem: ElemId, _codepoint: u32) -> Result<()> {
+        Result::Ok(())
+    }
+}
+
+impl<B: DatalogExtractorBackend + ?Sized> DatalogExtractorBackend for &mut B {
+    delegate! {
+        to (**self) {
+            fn begin(&mut self) -> Result<()>;
+            fn finish(&mut self) -> Result<()>;
+            fn add_facts(&mut self, batch: &[Fact]) -> Result<()>;
+            fn add_root_elem(&mut self, file: &str, elem: ElemId) -> Result<()>;
+            fn add_file_format(&mut self, file: &str, format: &str) -> Result<()>;
+            fn add_key_field(&mut self, struct_name: &str, field: &str) -> Result<()>;
+            fn add_elem(&mut self, elem: ElemId, elem_type: ElemType) -> Result<()>;
+            fn add_bool(&mut self, elem: ElemId, value: bool) -> Result<()>;
+            fn add_i8(&mut self, elem: ElemId, value: i8) -> Result<()>;
+            fn add_i16(&mut self, elem: ElemId, value: i16) -> Result<()>;
+            fn add_i32(&mut self, elem: ElemId, value: i32) -> Result<()>;
+            fn add_i64(&mut self, elem: ElemId, value: i64) -> Result<()>;
+            fn add_u8(&mut self, elem: ElemId, value: u8) -> Result<()>;
+            fn add_u16(&mut self, elem: ElemId, value: u16) -> Result<()>;
+            fn add_u32(&mut self, elem: ElemId, value: u32) -> Result<()>;
+            fn add_u64(&mut self, elem: ElemId, value: u64) -> Result<()>;
+            fn add_f32(&mut self, elem: ElemId, value: f32) -> Result<()>;
+            fn add_f64(&mut self, elem: ElemId, value: f64) -> Result<()>;
+            fn add_char(&mut self, elem: ElemId, value: char) -> Result<()>;
+            fn add_str(&mut self, elem: ElemId, value: &str) -> Result<()>;
+            fn add_full_str(&mut self, elem: ElemId, value: &str) -> Result<()>;
+            fn add_bytes(&mut self, elem: ElemId, value: &[u8]) -> Result<()>;
+            fn add_map_entry(&mut self, elem: ElemId, key: ElemId, value: ElemId) -> Result<()>;
+            fn add_struct_type(&mut self, elem: ElemId, struct_name: &str) -> Result<()>;
+            fn add_struct_entry(&mut self, elem: ElemId, key: &str, value: ElemId) -> Result<()>;
+            fn add_key(&mut self, elem: ElemId, key: ElemId) -> Result<()>;
+            fn add_seq_entry(&mut self, elem: ElemId, pos: usize, value: ElemId) -> Result<()>;
+            fn add_variant_type(&mut self, elem: ElemId, type_name: &str, variant_name: &str) -> Result<()>;
+            fn add_tuple_entry(&mut self, elem: ElemId, pos: usize, value: ElemId) -> Result<()>;
+            fn add_schema_type(&mut self, elem: ElemId, schema_ref: &str) -> Result<()>;
+            fn add_datetime(&mut self, elem: ElemId, epoch_micros: i64, tz_offset: i32) -> Result<()>;
+            fn add_uuid(&mut self, elem: ElemId, hi: u64, lo: u64) -> Result<()>;
+            fn add_ip(&mut self, elem: ElemId, address: IpAddr) -> Result<()>;
+            fn add_url(&mut self, elem: ElemId, scheme: &str, host: &str) -> Result<()>;
+            fn add_email(&mut self, elem: ElemId, local: &str, domain: &str) -> Result<()>;
+            fn add_declared_len(&mut self, elem: ElemId, len: usize) -> Result<()>;
+            fn add_absent_field(&mut self, elem: ElemId, field: &str) -> Result<()>;
+            fn add_unit(&mut self, elem: ElemId) -> Result<()>;
+            fn add_truncated(&mut self, relation: &str, quota: usize) -> Result<()>;
+            fn add_redacted(&mut self, elem: ElemId) -> Result<()>;
+            fn add_sampled(&mut self, elem: ElemId, len: usize, kept: usize) -> Result<()>;
+            fn add_elem_type_kind(&mut self, elem_type: ElemType, kind: ElemKind) -> Result<()>;
+            fn add_same_string(&mut self, elem: ElemId, other: ElemId) -> Result<()>;
+            fn add_shape(&mut self, elem: ElemId, shape: u64) -> Result<()>;
+            fn add_shape_field(&mut self, shape: u64, pos: usize, field: &str) -> Result<()>;
+            fn add_parse_error(&mut self, file: &str, line: usize, message: &str) -> Result<()>;
+            fn add_string_hash(&mut self, elem: ElemId, hash: u64, len: usize) -> Result<()>;
+            fn add_root_meta(&mut self, file: &str, key: &str, value: &str) -> Result<()>;
+            fn add_depth(&mut self, elem: ElemId, depth: usize) -> Result<()>;
+            fn add_subtree_size(&mut self, elem: ElemId, size: usize) -> Result<()>;
+            fn add_codepoint(&mut self, elem: ElemId, codepoint: u32) -> Result<()>;
+        }
+    }
+}
+
+impl<B: DatalogExtractorBackend + ?Sized> DatalogExtractorBackend for Box<B> {
+    delegate! {
+        to (**self) {
+            fn begin(&mut self) -> Result<()>;
+            fn finish(&mut self) -> Result<()>;
+            fn add_facts(&mut self, batch: &[Fact]) -> Result<()>;
+            fn add_root_elem(&mut self, file: &str, elem: ElemId) -> Result<()>;
+            fn add_file_format(&mut self, file: &str, format: &str) -> Result<()>;
+            fn add_key_field(&mut self, struct_name: &str, field: &str) -> Result<()>;
+            fn add_elem(&mut self, elem: ElemId, elem_type: ElemType) -> Result<()>;
+            fn add_bool(&mut self, elem: ElemId, value: bool) -> Result<()>;
+            fn add_i8(&mut self, elem: ElemId, value: i8) -> Result<()>;
+            fn add_i16(&mut self, elem: ElemId, value: i16) -> Result<()>;
+            fn add_i32(&mut self, elem: ElemId, value: i32) -> Result<()>;
+            fn add_i64(&mut self, elem: ElemId, value: i64) -> Result<()>;
+            fn add_u8(&mut self, elem: ElemId, value: u8) -> Result<()>;
+            fn add_u16(&mut self, elem: ElemId, value: u16) -> Result<()>;
+            fn add_u32(&mut self, elem: ElemId, value: u32) -> Result<()>;
+            fn add_u64(&mut self, elem: ElemId, value: u64) -> Result<()>;
+            fn add_f32(&mut self, elem: ElemId, value: f32) -> Result<()>;
+            fn add_f64(&mut self, elem: ElemId, value: f64) -> Result<()>;
+            fn add_char(&mut self, elem: ElemId, value: char) -> Result<()>;
+            fn add_str(&mut self, elem: ElemId, value: &str) -> Result<()>;
+            fn add_full_str(&mut self, elem: ElemId, value: &str) -> Result<()>;
+            fn add_bytes(&mut self, elem: ElemId, value: &[u8]) -> Result<()>;
+            fn add_map_entry(&mut self, elem: ElemId, key: ElemId, value: ElemId) -> Result<()>;
+            fn add_struct_type(&mut self, elem: ElemId, struct_name: &str) -> Result<()>;
+            fn add_struct_entry(&mut self, elem: ElemId, key: &str, value: ElemId) -> Result<()>;
+            fn add_key(&mut self, elem: ElemId, key: ElemId) -> Result<()>;
+            fn add_seq_entry(&mut self, elem: ElemId, pos: usize, value: ElemId) -> Result<()>;
+            fn add_variant_type(&mut self, elem: ElemId, type_name: &str, variant_name: &str) -> Result<()>;
+            fn add_tuple_entry(&mut self, elem: ElemId, pos: usize, value: ElemId) -> Result<()>;
+            fn add_schema_type(&mut self, elem: ElemId, schema_ref: &str) -> Result<()>;
+            fn add_datetime(&mut self, elem: ElemId, epoch_micros: i64, tz_offset: i32) -> Result<()>;
+            fn add_uuid(&mut self, elem: ElemId, hi: u64, lo: u64) -> Result<()>;
+            fn add_ip(&mut self, elem: ElemId, address: IpAddr) -> Result<()>;
+            fn add_url(&mut self, elem: ElemId, scheme: &str, host: &str) -> Result<()>;
+            fn add_email(&mut self, elem: ElemId, local: &str, domain: &str) -> Result<()>;
+            fn add_declared_len(&mut self, elem: ElemId, len: usize) -> Result<()>;
+            fn add_absent_field(&mut self, elem: ElemId, field: &str) -> Result<()>;
+            fn add_unit(&mut self, elem: ElemId) -> Result<()>;
+            fn add_truncated(&mut self, relation: &str, quota: usize) -> Result<()>;
+            fn add_redacted(&mut self, elem: ElemId) -> Result<()>;
+            fn add_sampled(&mut self, elem: ElemId, len: usize, kept: usize) -> Result<()>;
+            fn add_elem_type_kind(&mut self, elem_type: ElemType, kind: ElemKind) -> Result<()>;
+            fn add_same_string(&mut self, elem: ElemId, other: ElemId) -> Result<()>;
+            fn add_shape(&mut self, elem: ElemId, shape: u64) -> Result<()>;
+            fn add_shape_field(&mut self, shape: u64, pos: usize, field: &str) -> Result<()>;
+            fn add_parse_error(&mut self, file: &str, line: usize, message: &str) -> Result<()>;
+            fn add_string_hash(&mut self, elem: ElemId, hash: u64, len: usize) -> Result<()>;
+            fn add_root_meta(&mut self, file: &str, key: &str, value: &str) -> Result<()>;
+            fn add_depth(&mut self, elem: ElemId, depth: usize) -> Result<()>;
+            fn add_subtree_size(&mut self, elem: ElemId, size: usize) -> Result<()>;
+            fn add_codepoint(&mut self, elem: ElemId, codepoint: u32) -> Result<()>;
+        }
+    }
+}
+
+/// Extraction hints for the fields of a struct type, which give control over
+/// the facts extracted from structs without changing their
+/// [serde::Serialize] implementation. Hints play the role of derive attributes
+/// on the struct's fields:
+///
+/// | Attribute                        | Hint                         |
+/// |----------------------------------|------------------------------|
+/// | `#[datalog(skip)]`               | [skip][StructHints::skip]     |
+/// | `#[datalog(rename = "name")]`    | [rename][StructHints::rename] |
+/// | `#[datalog(key)]`                | [key][StructHints::key]       |
+///
+/// Hints apply to the structs whose type name is the name given to
+/// [StructHints::new], and to the struct variants of enums with that name.
+/// Field names are the names of fields as serialized, e.g. after renaming
+/// with `#[serde(rename)]`.
+///
+/// ```ignore
+/// let hints = StructHints::new("User").key("id").skip("password");
+/// let mut extractor = DatalogExtractor::new(backend).with_hints(hints);
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct StructHints {
+    name: String,
+    skip: HashSet<String>,
+    rename: HashMap<String, String>,
+    keys: Vec<String>,
+    fields: Vec<String>,
+}
+
+impl StructHints {
+    /// Create hints for structs with type name `name`.
+    pub fn new(name: &str) -> Self {
+        StructHints {
+            name: name.to_string(),
+            ..StructHints::default()
+        }
+    }
+
+    /// The type name of the structs the hints apply to.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Do not extract field `field`.
+    pub fn skip(mut self, field: &str) -> Self {
+        self.skip.insert(field.to_string());
+        self
+    }
+
+    /// Extract field `field` with name `name`.
+    pub fn rename(mut self, field: &str, name: &str) -> Self {
+        self.rename.insert(field.to_string(), name.to_string());
+        self
+    }
+
+    /// Mark field `field` as identifying its struct, e.g. a natural key such as
+    /// an `id` or a `name`. This is materialized with
+    /// [add_key_field][DatalogExtractorBackend::add_key_field] the first time a
+    /// struct of the type is extracted.
+    pub fn key(mut self, field: &str) -> Self {
+        self.keys.push(field.to_string());
+        self
+    }
+
+    /// Declare the fields that structs of the type have, e.g. the fields of
+    /// a sample struct. When [sparse structs][DatalogExtractor::with_sparse_structs]
+    /// are enabled, every declared field that a struct does not serialize is
+    /// materialized with
+    /// [add_absent_field][DatalogExtractorBackend::add_absent_field].
+    pub fn fields(mut self, fields: &[&str]) -> Self {
+        self.fields = fields.iter().map(|field| field.to_string()).collect();
+        self
+    }
+
+    /// The name with which field `field` is extracted.
+    fn field_name(&self, field: &str) -> String {
+        self.rename
+            .get(field)
+            .cloned()
+            .unwrap_or_else(|| field.to_string())
+    }
+}
+
+/// State of an extractor with [atomic roots][DatalogExtractor::with_atomic_roots]
+/// before it started generating facts about the current root value, which
+/// is restored if extraction of the value fails.
+struct Checkpoint {
+    batch_len: usize,
+    cur_elem_id: ElemId,
+    facts_emitted: usize,
+    total_facts: usize,
+    fact_counts: HashMap<&'static str, usize>,
+    declared_elem_type_kinds: bool,
+    /// Structs whose keys were declared by facts about the current root value.
+    declared_keys: Vec<String>,
+    /// Shapes declared by facts about the current root value.
+    declared_shapes: Vec<u64>,
+}
+
+/// State of an extractor before it started a
+/// [checkpoint][DatalogExtractor::begin_checkpoint], and the ranges of the
+/// buffered facts about each root value finished since, so that the backend's
+/// [begin][DatalogExtractorBackend::begin] and
+/// [finish][DatalogExtractorBackend::finish] are called around them once the
+/// checkpoint is committed.
+struct FileCheckpoint {
+    start: Checkpoint,
+    root_start: usize,
+    roots: Vec<std::ops::Range<usize>>,
+}
+
+/// Implementation of [serde::Serializer] that extracts facts from a data structure.
+/// Note that the extractor does *not* contain an explicit representation of
+/// the facts that it generates from a data structure. Instead, it calls out
+/// to a [DatalogExtractorBackend] to materialize facts.
+pub struct DatalogExtractor<B: DatalogExtractorBackend> {
+    cur_file: Option<String>,
+    cur_elem_id: ElemId,
+    elem_stack: Vec<ElemId>,
+    parent_stack: Vec<(ElemId, usize)>,
+    open_values: usize,
+    batch_size: usize,
+    batch: Vec<Fact>,
+    hints: HashMap<String, StructHints>,
+    declared_keys: HashSet<String>,
+    declared_elem_type_kinds: bool,
+    struct_stack: Vec<&'static str>,
+    sparse_structs: bool,
+    seen_fields: Vec<HashSet<&'static str>>,
+    string_policy: StringPolicy,
+    schema_guide: Option<SchemaGuide>,
+    schema_stack: Vec<Option<SchemaNodeId>>,
+    capture_map_key: bool,
+    map_key: Option<String>,
+    datetime_key: bool,
+    datetime_strings: bool,
+    mark_datetime: bool,
+    recognizers: Recognizers,
+    unit_policy: UnitPolicy,
+    unit_singletons: HashMap<Option<&'static str>, ElemId>,
+    elements: usize,
+    progress_interval: usize,
+    progress: Option<ProgressCallback>,
+    progress_file: Option<String>,
+    deadline: Option<Instant>,
+    cancellation: Option<CancellationToken>,
+    fact_quotas: FactQuotas,
+    fact_counts: HashMap<&'static str, usize>,
+    redaction: Option<Redaction>,
+    redact_depth: usize,
+    sampling: Option<Sampling>,
+    sample_stack: Vec<Option<Sampler>>,
+    same_strings: bool,
+    file_index: usize,
+    first_strings: HashMap<String, Vec<(usize, ElemId)>>,
+    shapes: bool,
+    shape_stack: Vec<Option<Vec<String>>>,
+    declared_shapes: HashSet<u64>,
+    measures: bool,
+    subtree_sizes: Vec<usize>,
+    char_codepoints: bool,
+    error_context: bool,
+    path: Vec<PathSegment>,
+    context_attached: bool,
+    facts_emitted: usize,
+    total_facts: usize,
+    atomic_roots: bool,
+    checkpoint: Option<Checkpoint>,
+    file_checkpoint: Option<FileCheckpoint>,
+    #[cfg(feature = "tracing")]
+    lossy_strings: usize,
+    #[cfg(feature = "tracing")]
+    file_span: tracing::Span,
+    backend: B,
+}
+
+impl DatalogExtractor<Box<dyn DatalogExtractorBackend>> {
+    /// Start configuring an extractor with an [ExtractorConfig], whose
+    /// [build][ExtractorConfig::build] creates the extractor with any
+    /// backend.
+    pub fn builder() -> ExtractorConfig {
+        ExtractorConfig::default()
+    }
+}
+
+impl<B: DatalogExtractorBackend> DatalogExtractor<B> {
+    pub fn new(backend: B) -> Self {
+        Self::new_with_first_elem_id(backend, ElemId(1))
+    }
+
+    /// Create an extractor whose generated element identifiers start at
+    /// `first_elem_id`. This is useful when appending facts to an existing
+    /// database whose elements already use smaller identifiers.
+    pub fn new_with_first_elem_id(backend: B, first_elem_id: ElemId) -> Self {
+        DatalogExtractor {
+            backend,
+            cur_elem_id: first_elem_id,
+            cur_file: None,
+            elem_stack: Vec::new(),
+            parent_stack: Vec::new(),
+            open_values: 0,
+            batch_size: 0,
+            batch: Vec::new(),
+            hints: HashMap::new(),
+            declared_keys: HashSet::new(),
+            declared_elem_type_kinds: false,
+            struct_stack: Vec::new(),
+            sparse_structs: false,
+            seen_fields: Vec::new(),
+            string_policy: StringPolicy::default(),
+            schema_guide: None,
+            schema_stack: Vec::new(),
+            capture_map_key: false,
+            map_key: None,
+            datetime_key: false,
+            datetime_strings: false,
+            mark_datetime: false,
+            recognizers: Recognizers::default(),
+            unit_policy: UnitPolicy::default(),
+            unit_singletons: HashMap::new(),
+            elements: 0,
+            progress_interval: 0,
+            progress: None,
+            progress_file: None,
+            deadline: None,
+            cancellation: None,
+            fact_quotas: FactQuotas::default(),
+            fact_counts: HashMap::new(),
+            redaction: None,
+            redact_depth: 0,
+            sampling: None,
+            sample_stack: Vec::new(),
+            same_strings: false,
+            file_index: 0,
+            first_strings: HashMap::new(),
+            shapes: false,
+            shape_stack: Vec::new(),
+            declared_shapes: HashSet::new(),
+            measures: false,
+            subtree_sizes: Vec::new(),
+            char_codepoints: false,
+            error_context: false,
+            path: Vec::new(),
+            context_attached: false,
+            facts_emitted: 0,
+            total_facts: 0,
+            atomic_roots: false,
+            checkpoint: None,
+            file_checkpoint: None,
+            #[cfg(feature = "tracing")]
+            lossy_strings: 0,
+            #[cfg(feature = "tracing")]
+            file_span: tracing::Span::none(),
+        }
+    }
+
+    /// Buffer up to `batch_size` facts and pass them to the backend in
+    /// batches with [add_facts][DatalogExtractorBackend::add_facts].
+    /// Buffered facts are also passed to the backend once all facts about a
+    /// root value have been generated. A batch size of 0 (the default)
+    /// disables buffering.
+    ///
+    /// Note that with buffering, errors returned by the backend for a fact
+    /// surface when the fact's batch is passed to the backend.
+    pub fn with_batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size;
+        self
+    }
+
+    /// Apply `hints` to the fields of structs with the hints' type name,
+    /// replacing any hints previously given for the type.
+    pub fn with_hints(mut self, hints: StructHints) -> Self {
+        self.hints.insert(hints.name.clone(), hints);
+        self
+    }
+
+    /// Record which fields structs leave out, so that queries can tell
+    /// absent fields from fields that never existed. For every struct, the
+    /// number of fields that its [serde::Serialize] implementation declares
+    /// is materialized with
+    /// [add_declared_len][DatalogExtractorBackend::add_declared_len], and
+    /// every field that it skips, such as fields with
+    /// `#[serde(skip_serializing_if)]`, or that is declared with
+    /// [StructHints::fields] but not serialized, is materialized with
+    /// [add_absent_field][DatalogExtractorBackend::add_absent_field].
+    /// Fields skipped with [StructHints::skip] are never absent.
+    pub fn with_sparse_structs(mut self, enable: bool) -> Self {
+        self.sparse_structs = enable;
+        self
+    }
+
+    /// Apply `policy` to string and char values before passing them to the
+    /// backend.
+    pub fn with_string_policy(mut self, policy: StringPolicy) -> Self {
+        self.string_policy = policy;
+        self
+    }
+
+    /// Annotate elements with the schemas of `guide` they match, as
+    /// described in [schema_guide].
+    pub fn with_schema_guide(mut self, guide: SchemaGuide) -> Self {
+        self.schema_guide = Some(guide);
+        self
+    }
+
+    /// Recognize the scalars of `recognizers` in string values, as
+    /// described in [recognize].
+    pub fn with_recognizers(mut self, recognizers: Recognizers) -> Self {
+        self.recognizers = recognizers;
+        self
+    }
+
+    /// Extract unit and unit struct values as described by `policy`.
+    pub fn with_unit_policy(mut self, policy: UnitPolicy) -> Self {
+        self.unit_policy = policy;
+        self
+    }
+
+    /// Limit the number of facts extracted for relations as described by
+    /// `quotas`. Facts of a relation beyond its quota are dropped, and the
+    /// first dropped fact is replaced with a truncation fact materialized
+    /// with [add_truncated][DatalogExtractorBackend::add_truncated]. Facts
+    /// about map keys are neither limited nor counted.
+    pub fn with_fact_quotas(mut self, quotas: FactQuotas) -> Self {
+        self.fact_quotas = quotas;
+        self
+    }
+
+    /// Redact the values of struct fields and map entries matching
+    /// `redaction`, as described in [redact].
+    pub fn with_redaction(mut self, redaction: Redaction) -> Self {
+        self.redaction = Some(redaction);
+        self
+    }
+
+    /// Extract only a sample of the elements of long sequences, as described
+    /// in [sample].
+    pub fn with_sampling(mut self, sampling: Sampling) -> Self {
+        self.sampling = Some(sampling);
+        self
+    }
+
+    /// Correlate the string values of different files, so that joins across
+    /// files are a single rule. Every string element with the same value as
+    /// a string element of an earlier file, as named with
+    /// [set_file][Self::set_file], is related to the first such element of
+    /// each earlier file with
+    /// [add_same_string][DatalogExtractorBackend::add_same_string]:
+    ///
+    /// ```text
+    /// sharedUser(A, B) :- sameString(A, B), struct(_, "user", A), struct(_, "user", B).
+    /// ```
+    ///
+    /// The extractor keeps the first element of every string value of every
+    /// file, so memory grows with the number of distinct strings. Map keys
+    /// are not correlated.
+    pub fn with_same_strings(mut self, enable: bool) -> Self {
+        self.same_strings = enable;
+        self
+    }
+
+    /// Dictionary-encode the fields of structs and maps, so that queries
+    /// about the shapes of values are cheap. Each distinct combination of a
+    /// struct type and a set of field names is a shape, identified by a hash
+    /// of the type and names, so that identifiers are the same across runs.
+    /// The shape of every struct and of every map whose keys are strings is
+    /// materialized with [add_shape][DatalogExtractorBackend::add_shape], and
+    /// the fields of each shape, sorted by name, with
+    /// [add_shape_field][DatalogExtractorBackend::add_shape_field]:
+    ///
+    /// ```text
+    /// lacksEmail(Id) :- shape(Id, S), structType(Id, "User"), !shapeField(S, _, "email").
+    /// ```
+    pub fn with_shapes(mut self, enable: bool) -> Self {
+        self.shapes = enable;
+        self
+    }
+
+    /// Record basic measures of the tree of every root value, so that
+    /// queries need not recompute them with recursive rules. The depth of
+    /// every element, which is 0 for the element of a root value and one more
+    /// than its parent's for nested values, is materialized with
+    /// [add_depth][DatalogExtractorBackend::add_depth], and the number of
+    /// elements in its value, including itself, with
+    /// [add_subtree_size][DatalogExtractorBackend::add_subtree_size]:
+    ///
+    /// ```text
+    /// deepLeaf(Id) :- depth(Id, D), D > 10, subtreeSize(Id, 1).
+    /// ```
+    ///
+    /// Map keys are nested in their maps. A [shared][UnitPolicy::Singletons]
+    /// unit element is measured where it first occurs, and is not counted
+    /// in the values it is shared with.
+    pub fn with_measures(mut self, enable: bool) -> Self {
+        self.measures = enable;
+        self
+    }
+
+    /// Buffer the facts about every root value until the value is finished,
+    /// so that a value whose extraction fails leaves no facts behind once
+    /// the extractor is [reset][Self::reset]. The backend's
+    /// [begin][DatalogExtractorBackend::begin] is then called once the value
+    /// is finished, right before its facts are passed to the backend. This
+    /// holds all facts about a root value in memory.
+    pub fn with_atomic_roots(mut self, enable: bool) -> Self {
+        self.atomic_roots = enable;
+        self
+    }
+
+    /// Record the Unicode code point of every char value as a number, so
+    /// that rules can compare chars by range without parsing strings. Chars
+    /// are still materialized as strings with
+    /// [add_char][DatalogExtractorBackend::add_char]; their code points are
+    /// materialized with
+    /// [add_codepoint][DatalogExtractorBackend::add_codepoint], before any
+    /// [StringPolicy] is applied:
+    ///
+    /// ```text
+    /// asciiChar(Id) :- char(Id, C), C < 128.
+    /// ```
+    ///
+    /// The code points of [redacted][Self::with_redaction] chars are not
+    /// recorded.
+    pub fn with_char_codepoints(mut self, enable: bool) -> Self {
+        self.char_codepoints = enable;
+        self
+    }
+
+    /// Attach where extraction failed to errors raised while extracting
+    /// values nested in root values, so that failures on malformed inputs
+    /// can be diagnosed. Such errors are wrapped in an
+    /// [InValue][DatalogExtractionError::InValue] error with the path to the
+    /// innermost value being extracted and the number of facts generated so
+    /// far, e.g. `unextractable data f64 at $.points[2].x after 17 fact(s)`.
+    /// [without_context][DatalogExtractionError::without_context] unwraps
+    /// them.
+    pub fn with_error_context(mut self, enable: bool) -> Self {
+        self.error_context = enable;
+        self
+    }
+
+    /// Report the progress of extraction to `callback` every `interval`
+    /// extracted elements, and whenever an input file starts with
+    /// [set_file][Self::set_file]. If `callback` returns
+    /// [ControlFlow::Break][std::ops::ControlFlow::Break], extraction fails
+    /// with [DatalogExtractionError::Cancelled]; see [progress].
+    pub fn with_progress<F>(mut self, interval: usize, callback: F) -> Self
+    where
+        F: FnMut(&Progress) -> std::ops::ControlFlow<()> + 'static,
+    {
+        self.progress_interval = interval;
+        self.progress = Some(Box::new(callback));
+        self
+    }
+
+    /// Fail with [DatalogExtractionError::Cancelled] once `deadline` has
+    /// passed. The deadline is checked periodically, so extraction can run
+    /// slightly past it; see [progress].
+    pub fn with_deadline(mut self, deadline: Instant) -> Self {
+        self.deadline = Some(deadline);
+        self
+    }
+
+    /// Fail with [DatalogExtractionError::Cancelled] once `timeout` has
+    /// elapsed from now; see [with_deadline][Self::with_deadline].
+    pub fn with_timeout(self, timeout: Duration) -> Self {
+        self.with_deadline(Instant::now() + timeout)
+    }
+
+    /// Fail with [DatalogExtractionError::Cancelled] once `token` is
+    /// cancelled. The token is checked periodically; see [progress].
+    pub fn with_cancellation(mut self, token: CancellationToken) -> Self {
+        self.cancellation = Some(token);
+        self
+    }
+
+    /// Recognize all strings that are dates or date-times, as parsed by
+    /// [Datetime::parse], as date-times, instead of only the strings marked as
+    /// date-times described in [datetime]. This is meant for formats whose
+    /// date-times are not told apart from strings, such as YAML, and can be
+    /// changed between input files.
+    pub fn set_datetime_strings(&mut self, enabled: bool) {
+        self.datetime_strings = enabled;
+    }
+
+    /// The schema expected of the value being serialized, if any. Root
+    /// values are expected to match the root schema of the guide.
+    fn cur_schema(&self) -> Option<SchemaNodeId> {
+        let guide = self.schema_guide.as_ref()?;
+        match self.schema_stack.last() {
+            Some(node) => *node,
+            None => Some(guide.root()),
+        }
+    }
+
+    /// The schema expected of an entry of the value being serialized,
+    /// found with `child` from the schema of the value.
+    fn child_schema(
+        &self,
+        child: impl FnOnce(&SchemaGuide, SchemaNodeId) -> Option<SchemaNodeId>,
+    ) -> Option<SchemaNodeId> {
+        let guide = self.schema_guide.as_ref()?;
+        child(guide, self.cur_schema()?)
+    }
+
+    /// Serialize `value`, an entry of the value being serialized whose
+    /// schema is `schema`.
+    fn serialize_with_schema<T: ?Sized + serde::Serialize>(
+        &mut self,
+        schema: Option<SchemaNodeId>,
+        value: &T,
+    ) -> Result<()> {
+        if self.schema_guide.is_none() {
+            return value.serialize(&mut *self);
+        }
+
+        self.schema_stack.push(schema);
+        let res = value.serialize(&mut *self);
+        self.schema_stack.pop();
+        res
+    }
+
+    /// Serialize `value`, the value of the struct field or map entry named
+    /// `name`, like [serialize_with_schema][Self::serialize_with_schema],
+    /// redacting it if `name` matches the extractor's [Redaction].
+    fn serialize_entry_value<T: ?Sized + serde::Serialize>(
+        &mut self,
+        name: Option<&str>,
+        schema: Option<SchemaNodeId>,
+        value: &T,
+    ) -> Result<()> {
+        let redacts = match (&self.redaction, name) {
+            (Some(redaction), Some(name)) => redaction.matches(name),
+            _ => false,
+        };
+        if !redacts {
+            return self.serialize_with_schema(schema, value);
+        }
+
+        self.redact_depth += 1;
+        let res = self.serialize_with_schema(schema, value);
+        self.redact_depth -= 1;
+        res
+    }
+
+    /// Generate facts about the value of string element `elem`, applying the
+    /// string policy to it.
+    fn emit_str(&mut self, elem: ElemId, value: &str) -> Result<()> {
+        // map keys are names, and redacted strings are replaced anyway
+        let elides = !self.capture_map_key && self.redact_depth == 0;
+        if elides && self.string_policy.elides(value) {
+            return self.emit(Fact::StringHash {
+                elem,
+                hash: fnv1a(value.as_bytes()),
+                len: value.len(),
+            });
+        }
+
+        let applied = self.string_policy.apply(value);
+        let changed = matches!(applied, Cow::Owned(_));
+        self.emit(Fact::Str {
+            elem,
+            value: applied.into_owned(),
+        })?;
+
+        if changed && self.string_policy.keeps_full_text() {
+            self.emit(Fact::FullStr {
+                elem,
+                value: value.to_string(),
+            })?;
+        } else if changed {
+            #[cfg(feature = "tracing")]
+            {
+                self.lossy_strings += 1;
+            }
+        }
+
+        Result::Ok(())
+    }
+
+    /// The hints for the fields of the struct being serialized.
+    fn cur_hints(&self) -> Option<&StructHints> {
+        if self.hints.is_empty() {
+            return None;
+        }
+
+        self.struct_stack
+            .last()
+            .and_then(|name| self.hints.get(*name))
+    }
+
+    /// Is field `key` of the struct being serialized skipped?
+    fn skips_field(&self, key: &str) -> bool {
+        self.cur_hints()
+            .is_some_and(|hints| hints.skip.contains(key))
+    }
+
+    /// Start serializing the fields of a struct with type name `name` that
+    /// declares `len` fields, declaring its key fields if it is the first
+    /// struct of its type.
+    fn begin_struct(&mut self, name: &'static str, len: usize) -> Result<()> {
+        self.struct_stack.push(name);
+        if self.shapes {
+            self.shape_stack.push(Some(Vec::new()));
+        }
+
+        if self.sparse_structs {
+            self.seen_fields.push(HashSet::new());
+            let (elem, _) = self.parent_stack.last().unwrap();
+            self.emit(Fact::DeclaredLen { elem: *elem, len })?;
+        }
+
+        if let Some(hints) = self.hints.get(name) {
+            if !hints.keys.is_empty() && self.declared_keys.insert(name.to_string()) {
+                let facts: Vec<Fact> = hints
+                    .keys
+                    .iter()
+                    .map(|key| Fact::KeyField {
+                        struct_name: name.to_string(),
+                        field: hints.field_name(key),
+                    })
+                    .collect();
+                for checkpoint in self.checkpoints_mut() {
+                    checkpoint.declared_keys.push(name.to_string());
+                }
+
+                for fact in facts {
+                    self.emit(fact)?;
+                }
+            }
+        }
+
+        Result::Ok(())
+    }
+
+    /// Finish serializing the fields of the current struct, generating facts
+    /// about its declared fields that were not serialized.
+    fn end_struct(&mut self) -> Result<()> {
+        let name = self.struct_stack.pop().unwrap();
+        self.end_shape(name)?;
+        if !self.sparse_structs {
+            return Result::Ok(());
+        }
+
+        let seen = self.seen_fields.pop().unwrap();
+        let Some(hints) = self.hints.get(name) else {
+            return Result::Ok(());
+        };
+
+        let (elem, _) = self.parent_stack.last().unwrap();
+        let facts: Vec<Fact> = hints
+            .fields
+            .iter()
+            .filter(|field| !seen.contains(field.as_str()) && !hints.skip.contains(*field))
+            .map(|field| Fact::AbsentField {
+                elem: *elem,
+                field: hints.field_name(field),
+            })
+            .collect();
+
+        for fact in facts {
+            self.emit(fact)?;
+        }
+
+        Result::Ok(())
+    }
+
+    /// Generate facts about the shape of the current struct with type name
+    /// `struct_name`, or of the current map if it is empty, whose fields have
+    /// all been serialized.
+    fn end_shape(&mut self, struct_name: &str) -> Result<()> {
+        if !self.shapes {
+            return Result::Ok(());
+        }
+
+        // maps with keys that are not strings have no shape
+        let Some(mut fields) = self.shape_stack.pop().flatten() else {
+            return Result::Ok(());
+        };
+        fields.sort();
+        fields.dedup();
+
+        let shape = fnv1a(format!("{}\0{}", struct_name, fields.join("\0")).as_bytes());
+        if self.declared_shapes.insert(shape) {
+            for checkpoint in self.checkpoints_mut() {
+                checkpoint.declared_shapes.push(shape);
+            }
+            for (pos, field) in fields.into_iter().enumerate() {
+                self.emit(Fact::ShapeField { shape, pos, field })?;
+            }
+        }
+
+        let (elem, _) = self.parent_stack.last().unwrap();
+        self.emit(Fact::Shape { elem: *elem, shape })
+    }
+
+    /// Record that the current struct or map has a field named `field`, or a
+    /// key that is not a string if `field` is `None`.
+    fn see_shape_field(&mut self, field: Option<&str>) {
+        if let Some(shape) = self.shape_stack.last_mut() {
+            match field {
+                Some(field) => {
+                    if let Some(fields) = shape {
+                        fields.push(field.to_string());
+                    }
+                }
+                None => *shape = None,
+            }
+        }
+    }
+
+    /// Pass buffered facts to the backend.
+    /// Facts generated from a root value whose extraction failed remain
+    /// buffered until the next call to this method or to [set_file][Self::set_file].
+    /// Facts buffered by a [checkpoint][Self::begin_checkpoint] are only
+    /// passed once it is committed.
+    pub fn flush(&mut self) -> Result<()> {
+        if self.file_checkpoint.is_some() {
+            return Result::Ok(());
+        }
+
+        if !self.batch.is_empty() {
+            let res = self.backend.add_facts(&self.batch);
+            self.batch.clear();
+            res?;
+        }
+
+        Result::Ok(())
+    }
+
+    /// Materialize `fact` with the backend, or buffer it if batching is enabled.
+    fn emit(&mut self, fact: Fact) -> Result<()> {
+        // map keys are names rather than values, so they are not redacted
+        if self.redact_depth > 0 && !self.capture_map_key {
+            if let Some(redaction) = &self.redaction {
+                for fact in redaction.redact_fact(fact) {
+                    self.emit_unredacted(fact)?;
+                }
+                return Result::Ok(());
+            }
+        }
+
+        self.emit_unredacted(fact)
+    }
+
+    /// Materialize `fact` like [emit][Self::emit], without redacting it.
+    fn emit_unredacted(&mut self, fact: Fact) -> Result<()> {
+        if !self.fact_quotas.is_empty() && !self.within_quota(&fact)? {
+            return Result::Ok(());
+        }
+
+        let same_strings = match &fact {
+            Fact::Str { elem, value } if self.same_strings && !self.capture_map_key => {
+                self.correlate_string(*elem, value)
+            }
+            _ => Vec::new(),
+        };
+
+        self.materialize(fact)?;
+
+        for fact in same_strings {
+            self.emit_unredacted(fact)?;
+        }
+
+        Result::Ok(())
+    }
+
+    /// Pass `fact` to the backend, or buffer it if batching is enabled.
+    fn materialize(&mut self, fact: Fact) -> Result<()> {
+        self.facts_emitted += 1;
+        self.total_facts += 1;
+        // facts about a root value with atomic roots are buffered until the
+        // value is finished, and facts of a checkpoint until it is committed
+        let buffering = self.checkpoint.is_some() || self.file_checkpoint.is_some();
+        if self.batch_size == 0 && !buffering {
+            fact.add_to(&mut self.backend)?;
+        } else {
+            self.batch.push(fact);
+            if !buffering && self.batch.len() >= self.batch_size {
+                self.flush()?;
+            }
+        }
+
+        Result::Ok(())
+    }
+
+    /// Facts relating string element `elem` to the first elements of earlier
+    /// files with the same value `value`. The element is kept if it is the
+    /// first element of the current file with the value.
+    fn correlate_string(&mut self, elem: ElemId, value: &str) -> Vec<Fact> {
+        let file_index = self.file_index;
+        let firsts = match self.first_strings.get_mut(value) {
+            Some(firsts) => firsts,
+            None => self.first_strings.entry(value.to_string()).or_default(),
+        };
+
+        let facts = firsts
+            .iter()
+            .filter(|(file, _)| *file != file_index)
+            .map(|(_, other)| Fact::SameString {
+                elem,
+                other: *other,
+            })
+            .collect();
+
+        // elements of the current file are the last ones kept
+        if firsts.last().is_none_or(|(file, _)| *file != file_index) {
+            firsts.push((file_index, elem));
+        }
+
+        facts
+    }
+
+    /// Count `fact` towards the quota of its relation, and return whether
+    /// it is within the quota. The first fact beyond the quota is replaced
+    /// with a truncation fact.
+    fn within_quota(&mut self, fact: &Fact) -> Result<bool> {
+        // backends that store map keys as strings take them from the facts
+        // of keys, so these are never dropped
+        if self.capture_map_key {
+            return Result::Ok(true);
+        }
+
+        let relation = fact.relation();
+        let Some(quota) = self.fact_quotas.get(relation) else {
+            return Result::Ok(true);
+        };
+
+        let count = self.fact_counts.entry(relation).or_default();
+        *count += 1;
+        if *count <= quota {
+            return Result::Ok(true);
+        }
+
+        if *count == quota + 1 {
+            self.emit(Fact::Truncated {
+                relation: relation.to_string(),
+                quota,
+            })?;
+        }
+
+        Result::Ok(false)
+    }
+
+    pub fn set_file(&mut self, file: &str) -> Result<()> {
+        self.cur_file = Some(file.to_string());
+        self.file_index += 1;
+        self.reset();
+
+        #[cfg(feature = "tracing")]
+        self.trace_file_summary();
+        self.facts_emitted = 0;
+
+        #[cfg(feature = "tracing")]
+        {
+            self.lossy_strings = 0;
+            self.file_span = tracing::info_span!("extract_file", file);
+            tracing::debug!(parent: &self.file_span, "extracting file");
+        }
+
+        self.check_cancelled()?;
+        if self.progress.is_some() {
+            self.progress_file = Some(file.to_string());
+            self.report_progress()?;
+        }
+
+        // unit values are only shared within a file, so that removing a file
+        // from a database does not remove elements of other files
+        self.unit_singletons.clear();
+        self.flush()
+    }
+
+    /// Discard the state of a root value whose extraction failed, so that
+    /// the next root value starts from a clean slate. A value whose
+    /// [serde::Serialize] implementation fails midway, such as a struct with
+    /// a `#[serde(flatten)]` field that serde cannot flatten, is never
+    /// finished, leaving its unfinished compound values on the extractor's
+    /// stacks. [set_file][Self::set_file] resets the extractor, so this only
+    /// needs to be called between root values of the same file.
+    ///
+    /// Facts already generated from the failed value are kept; buffered facts
+    /// are passed to the backend by the next call to [flush][Self::flush].
+    /// With [atomic roots][Self::with_atomic_roots], the facts of the failed
+    /// value are discarded instead, as if it had never been serialized.
+    pub fn reset(&mut self) {
+        if let Some(checkpoint) = self.checkpoint.take() {
+            self.discard_root(checkpoint);
+        }
+
+        self.open_values = 0;
+        self.elem_stack.clear();
+        self.parent_stack.clear();
+        self.struct_stack.clear();
+        self.seen_fields.clear();
+        self.schema_stack.clear();
+        self.capture_map_key = false;
+        self.map_key = None;
+        self.datetime_key = false;
+        self.mark_datetime = false;
+        self.redact_depth = 0;
+        self.sample_stack.clear();
+        self.shape_stack.clear();
+        self.subtree_sizes.clear();
+        self.path.clear();
+        self.context_attached = false;
+    }
+
+    /// Start buffering all facts generated from now on, such as the facts
+    /// about the root values of an input file and their metadata, until
+    /// [commit_checkpoint][Self::commit_checkpoint] passes them to the
+    /// backend, or [discard_checkpoint][Self::discard_checkpoint] discards
+    /// them, e.g. because extraction of a later value of the file failed.
+    /// Facts buffered before the checkpoint are passed to the backend first.
+    /// This holds all facts of the checkpoint in memory.
+    pub fn begin_checkpoint(&mut self) -> Result<()> {
+        self.commit_checkpoint()?;
+        self.flush()?;
+        self.file_checkpoint = Some(FileCheckpoint {
+            start: self.checkpoint_state(),
+            root_start: 0,
+            roots: Vec::new(),
+        });
+
+        Result::Ok(())
+    }
+
+    /// Pass the facts buffered since [begin_checkpoint][Self::begin_checkpoint]
+    /// to the backend, calling its [begin][DatalogExtractorBackend::begin]
+    /// and [finish][DatalogExtractorBackend::finish] around the facts about
+    /// each root value as if they had not been buffered.
+    pub fn commit_checkpoint(&mut self) -> Result<()> {
+        let Some(checkpoint) = self.file_checkpoint.take() else {
+            return Result::Ok(());
+        };
+
+        let batch = std::mem::take(&mut self.batch);
+        let mut pos = 0;
+        for root in checkpoint.roots.iter().cloned() {
+            if pos < root.start {
+                self.backend.add_facts(&batch[pos..root.start])?;
+            }
+            self.backend.begin()?;
+            if !root.is_empty() {
+                self.backend.add_facts(&batch[root.clone()])?;
+            }
+            self.backend.finish()?;
+            pos = root.end;
+        }
+        if pos < batch.len() {
+            self.backend.add_facts(&batch[pos..])?;
+        }
+
+        self.batch = batch;
+        self.batch.clear();
+        Result::Ok(())
+    }
+
+    /// Discard the facts generated since [begin_checkpoint][Self::begin_checkpoint],
+    /// restoring the state of the extractor from before the checkpoint, as
+    /// if no values had been serialized since. This also
+    /// [resets][Self::reset] the extractor.
+    pub fn discard_checkpoint(&mut self) {
+        self.reset();
+        if let Some(checkpoint) = self.file_checkpoint.take() {
+            self.discard_root(checkpoint.start);
+        }
+    }
+
+    /// The state of the extractor to restore if the facts generated from now
+    /// on are discarded.
+    fn checkpoint_state(&self) -> Checkpoint {
+        Checkpoint {
+            batch_len: self.batch.len(),
+            cur_elem_id: self.cur_elem_id,
+            facts_emitted: self.facts_emitted,
+            total_facts: self.total_facts,
+            fact_counts: self.fact_counts.clone(),
+            declared_elem_type_kinds: self.declared_elem_type_kinds,
+            declared_keys: Vec::new(),
+            declared_shapes: Vec::new(),
+        }
     }
 
-    /// Materialize fact that element with ID `elem` is a sequence
-    /// with value `value` at position `pos`.
-    ///
-    /// The default implementation returns an
-    /// [UnextractableData][DatalogExtractionError::UnextractableData] error.
-    fn add_seq_entry(&mut self, _elem: ElemId, _pos: usize, _value: ElemId) -> Result<()> {
-        Result::Err(DatalogExtractionError::UnextractableData(
-            "seq entry".to_string(),
-        ))
+    /// The checkpoints of the current root value and of the current
+    /// [checkpoint][Self::begin_checkpoint], which record the keys and
+    /// shapes declared since they started.
+    fn checkpoints_mut(&mut self) -> impl Iterator<Item = &mut Checkpoint> {
+        self.checkpoint.iter_mut().chain(
+            self.file_checkpoint
+                .iter_mut()
+                .map(|checkpoint| &mut checkpoint.start),
+        )
+    }
+
+    /// Call the backend's [begin][DatalogExtractorBackend::begin] before
+    /// passing it the facts about a root value, whose buffered facts start
+    /// at `batch_start`. Within a [checkpoint][Self::begin_checkpoint], this
+    /// is deferred until the checkpoint is committed.
+    fn begin_root(&mut self, batch_start: usize) -> Result<()> {
+        match &mut self.file_checkpoint {
+            Some(checkpoint) => {
+                checkpoint.root_start = batch_start;
+                Result::Ok(())
+            }
+            None => self.backend.begin(),
+        }
     }
 
-    /// Materialize fact that element with ID `elem` is an enum variant
-    /// with type name `type_name` and variant name `variant_name`.
-    /// The element can have element type
-    /// [ElemType::NewtypeVariant], [ElemType::StructVariant],
-    /// [ElemType::TupleVariant], or [ElemType::UnitVariant].
-    ///
-    /// The default implementation returns an
-    /// [UnextractableData][DatalogExtractionError::UnextractableData] error.
-    fn add_variant_type(
+    /// Pass the facts about a finished root value to the backend and call
+    /// its [finish][DatalogExtractorBackend::finish], or record their range
+    /// within a [checkpoint][Self::begin_checkpoint].
+    fn finish_root(&mut self) -> Result<()> {
+        match &mut self.file_checkpoint {
+            Some(checkpoint) => {
+                checkpoint
+                    .roots
+                    .push(checkpoint.root_start..self.batch.len());
+                Result::Ok(())
+            }
+            None => {
+                self.flush()?;
+                self.backend.finish()
+            }
+        }
+    }
+
+    /// Discard the facts generated since `checkpoint`, such as the facts
+    /// about the root value whose extraction failed, restoring the state of
+    /// the extractor from before the value was serialized.
+    fn discard_root(&mut self, checkpoint: Checkpoint) {
+        let first = checkpoint.cur_elem_id;
+        self.batch.truncate(checkpoint.batch_len);
+        self.cur_elem_id = first;
+        self.facts_emitted = checkpoint.facts_emitted;
+        self.total_facts = checkpoint.total_facts;
+        self.fact_counts = checkpoint.fact_counts;
+        self.declared_elem_type_kinds = checkpoint.declared_elem_type_kinds;
+        for name in &checkpoint.declared_keys {
+            self.declared_keys.remove(name);
+        }
+        for shape in &checkpoint.declared_shapes {
+            self.declared_shapes.remove(shape);
+        }
+
+        self.unit_singletons.retain(|_, elem| *elem < first);
+        self.first_strings.retain(|_, firsts| {
+            firsts.retain(|(_, elem)| *elem < first);
+            !firsts.is_empty()
+        });
+    }
+
+    /// Generate facts about the child value at path segment `segment` of
+    /// the current value with `f`, attaching the path to the child to errors
+    /// if the extractor has [error context][Self::with_error_context].
+    fn in_child(
         &mut self,
-        _elem: ElemId,
-        _type_name: &str,
-        _variant_name: &str,
+        segment: PathSegment,
+        f: impl FnOnce(&mut Self) -> Result<()>,
     ) -> Result<()> {
-        Result::Err(DatalogExtractionError::UnextractableData(
-            "variant type".to_string(),
-        ))
+        if !self.error_context {
+            return f(self);
+        }
+
+        self.path.push(segment);
+        let res = f(self).map_err(|error| {
+            // the innermost value knows best where extraction failed; the
+            // error may have lost its context, e.g. if a deserializer being
+            // transcoded turned it into a message of its own
+            if std::mem::replace(&mut self.context_attached, true) {
+                return error;
+            }
+
+            let path = self.path.iter().map(|segment| segment.to_string());
+            DatalogExtractionError::InValue(Box::new(ErrorContext {
+                path: std::iter::once("$".to_string()).chain(path).collect(),
+                facts: self.facts_emitted,
+                error,
+            }))
+        });
+        self.path.pop();
+        res
+    }
+
+    /// Record that the document at line or position `line` of input file
+    /// `file`, or at an unknown position if `line` is 0, could not be read or
+    /// extracted, failing with error message `message`, so that extraction
+    /// can skip invalid documents and continue with the next one. The state
+    /// of the failed document is discarded like with [reset][Self::reset];
+    /// facts already generated from it are kept, unless the extractor has
+    /// [atomic roots][Self::with_atomic_roots].
+    pub fn record_parse_error(&mut self, file: &str, line: usize, message: &str) -> Result<()> {
+        self.reset();
+        self.emit(Fact::ParseError {
+            file: file.to_string(),
+            line,
+            message: message.to_string(),
+        })?;
+        self.flush()
+    }
+
+    /// Record that input file `file` has input format `format`.
+    pub fn set_file_format(&mut self, file: &str, format: &str) -> Result<()> {
+        self.emit(Fact::FileFormat {
+            file: file.to_string(),
+            format: format.to_string(),
+        })?;
+
+        if self.open_values == 0 {
+            self.flush()?;
+        }
+
+        Result::Ok(())
     }
 
-    /// Materialize fact that element with ID `elem` is a tuple with value
-    /// `value` at position `pos`.
-    /// The element can have element type [ElemType::NewtypeStruct],
-    /// [ElemType::NewtypeVariant], [ElemType::Tuple], [ElemType::TupleStruct],
-    /// or [ElemType::TupleVariant].
+    /// Record metadata `value` for `key` of the root named `file`, as given
+    /// to [set_file][Self::set_file], in the `rootMeta` relation. Roots need
+    /// not be named after input files: values extracted in memory or fetched
+    /// over the network can be named freely and described with metadata.
+    /// By convention, the key `name` holds a logical name of the root and
+    /// the key `uri` the location it was read from; other keys are tags.
+    /// A root can have several values for a key.
+    pub fn set_root_meta(&mut self, file: &str, key: &str, value: &str) -> Result<()> {
+        self.emit(Fact::RootMeta {
+            file: file.to_string(),
+            key: key.to_string(),
+            value: value.to_string(),
+        })?;
+
+        if self.open_values == 0 {
+            self.flush()?;
+        }
+
+        Result::Ok(())
+    }
+
+    /// Replay `calls` recorded by a [record backend][backend::record::Backend]
+    /// of another extractor as if this extractor made them, e.g. to reuse the
+    /// facts of an input extracted by an earlier run. The element identifiers
+    /// of the recording extractor must start at 1, as with [new][Self::new];
+    /// they are shifted past the elements generated so far. Key fields, shape
+    /// fields, and element type kinds, which extractors declare once, are
+    /// skipped if this extractor declared them already.
     ///
-    /// The default implementation returns an
-    /// [UnextractableData][DatalogExtractionError::UnextractableData] error.
-    fn add_tuple_entry(&mut self, _elem: ElemId, _pos: usize, _value: ElemId) -> Result<()> {
-        Result::Err(DatalogExtractionError::UnextractableData(
-            "tuple entry".to_string(),
-        ))
+    /// The facts were redacted, sampled, and counted towards quotas by the
+    /// recording extractor, so this extractor does not do so again, nor does
+    /// it [correlate][Self::with_same_strings] their strings.
+    pub fn replay(&mut self, calls: &[backend::record::Call]) -> Result<()> {
+        use backend::record::Call;
+
+        let offset = self.cur_elem_id.0 - 1;
+        let mut next_elem_id = self.cur_elem_id;
+        let declared_elem_type_kinds = self.declared_elem_type_kinds;
+        let mut declared_keys = HashSet::new();
+        let mut declared_shapes = HashSet::new();
+
+        for call in calls {
+            let facts = match call {
+                Call::Begin => {
+                    self.begin_root(self.batch.len())?;
+                    continue;
+                }
+
+                Call::Finish => {
+                    self.finish_root()?;
+                    continue;
+                }
+
+                Call::Fact(fact) => std::slice::from_ref(fact),
+                Call::Batch(batch) => batch.as_slice(),
+            };
+
+            for fact in facts {
+                let declared = match fact {
+                    Fact::KeyField { struct_name, .. } => {
+                        self.declared_keys.contains(struct_name)
+                            && !declared_keys.contains(struct_name)
+                    }
+                    Fact::ShapeField { shape, .. } => {
+                        self.declared_shapes.contains(shape) && !declared_shapes.contains(shape)
+                    }
+                    Fact::ElemTypeKind { .. } => declared_elem_type_kinds,
+                    _ => false,
+                };
+                if declared {
+                    continue;
+                }
+
+                match fact {
+                    Fact::KeyField { struct_name, .. } => {
+                        declared_keys.insert(struct_name.clone());
+                    }
+                    Fact::ShapeField { shape, .. } => {
+                        declared_shapes.insert(*shape);
+                    }
+                    Fact::ElemTypeKind { .. } => self.declared_elem_type_kinds = true,
+                    _ => {}
+                }
+
+                let mut fact = fact.clone();
+                for elem in fact.elems_mut() {
+                    elem.0 += offset;
+                    next_elem_id = next_elem_id.max(ElemId(elem.0 + 1));
+                }
+                self.materialize(fact)?;
+            }
+        }
+
+        for name in declared_keys {
+            if self.declared_keys.insert(name.clone()) {
+                for checkpoint in self.checkpoints_mut() {
+                    checkpoint.declared_keys.push(name.clone());
+                }
+            }
+        }
+        for shape in declared_shapes {
+            if self.declared_shapes.insert(shape) {
+                for checkpoint in self.checkpoints_mut() {
+                    checkpoint.declared_shapes.push(shape);
+                }
+            }
+        }
+        self.cur_elem_id = next_elem_id;
+        self.flush()
+    }
+
+    /// Fail if the deadline has passed or the cancellation token has been
+    /// cancelled.
+    fn check_cancelled(&self) -> Result<()> {
+        let cancelled = self
+            .cancellation
+            .as_ref()
+            .is_some_and(|token| token.is_cancelled())
+            || self
+                .deadline
+                .is_some_and(|deadline| Instant::now() >= deadline);
+
+        if cancelled {
+            Result::Err(DatalogExtractionError::Cancelled)
+        } else {
+            Result::Ok(())
+        }
     }
-}
 
-/// Implementation of [serde::Serializer] that extracts facts from a data structure.
-/// Note that the extractor does *not* contain an explicit representation of
-/// the facts that it generates from a data structure. Instead, it calls out
-/// to a [DatalogExtractorBackend] to materialize facts.
-pub struct DatalogExtractor<B: DatalogExtractorBackend> {
-    cur_file: Option<String>,
-    cur_elem_id: ElemId,
-    elem_stack: Vec<ElemId>,
-    parent_stack: Vec<(ElemId, usize)>,
-    backend: B,
-}
+    /// Log the facts emitted and strings changed lossily since the current
+    /// file started.
+    #[cfg(feature = "tracing")]
+    fn trace_file_summary(&self) {
+        if self.facts_emitted > 0 {
+            tracing::info!(
+                parent: &self.file_span,
+                monotonic_counter.facts_emitted = self.facts_emitted,
+                elements = self.elements,
+                "extracted file"
+            );
+        }
 
-impl<B: DatalogExtractorBackend> DatalogExtractor<B> {
-    pub fn new(backend: B) -> Self {
-        DatalogExtractor {
-            backend,
-            cur_elem_id: ElemId(1),
-            cur_file: None,
-            elem_stack: Vec::new(),
-            parent_stack: Vec::new(),
+        if self.lossy_strings > 0 {
+            tracing::warn!(
+                parent: &self.file_span,
+                lossy_strings = self.lossy_strings,
+                "string policy changed string values without keeping their full text"
+            );
         }
     }
 
-    pub fn set_file(&mut self, file: &str) -> Result<()> {
-        self.cur_file = Some(file.to_string());
+    /// Report progress to the progress callback, if any.
+    fn report_progress(&mut self) -> Result<()> {
+        if let Some(callback) = self.progress.as_mut() {
+            let progress = Progress {
+                elements: self.elements,
+                file: self.progress_file.as_deref(),
+            };
+            if callback(&progress).is_break() {
+                return Result::Err(DatalogExtractionError::Cancelled);
+            }
+        }
+
         Result::Ok(())
     }
 
     fn get_fresh_elem_id(&mut self, elem_type: ElemType) -> Result<ElemId> {
+        if !self.declared_elem_type_kinds {
+            self.declared_elem_type_kinds = true;
+            for elem_type in ElemType::ALL {
+                let kind = elem_type.kind();
+                self.emit(Fact::ElemTypeKind { elem_type, kind })?;
+            }
+        }
+
         let id = self.cur_elem_id;
-        self.backend.add_elem(id, elem_type)?;
+        self.emit(Fact::Elem {
+            elem: id,
+            elem_type,
+        })?;
         self.elem_stack.push(id);
         self.cur_elem_id.0 += 1;
 
-        if let Some(file) = &self.cur_file {
-            self.backend.add_root_elem(file, id)?;
-            self.cur_file = None;
+        self.elements += 1;
+        if self.elements.is_multiple_of(CANCELLATION_CHECK_INTERVAL) {
+            self.check_cancelled()?;
+        }
+        if self.progress_interval > 0 && self.elements.is_multiple_of(self.progress_interval) {
+            self.report_progress()?;
+        }
+
+        if let Some(file) = self.cur_file.take() {
+            self.emit(Fact::RootElem { file, elem: id })?;
+        }
+
+        if let Some(schema) = self.cur_schema() {
+            let schema_ref = self.schema_guide.as_ref().unwrap().schema_ref(schema);
+            self.emit(Fact::SchemaType {
+                elem: id,
+                schema_ref: schema_ref.to_string(),
+            })?;
+        }
+
+        if self.measures {
+            // every open value has one element, so the values enclosing the
+            // element's own value are its ancestors
+            *self.subtree_sizes.last_mut().unwrap() += 1;
+            self.emit(Fact::Depth {
+                elem: id,
+                depth: self.open_values - 1,
+            })?;
         }
 
         Result::Ok(id)
     }
 
+    /// Get the element of a unit value with element type `elem_type`, and
+    /// `name` if it is a unit struct. If units are
+    /// [singletons][UnitPolicy::Singletons], the element of a unit value that
+    /// is not a root value is shared with the other unit values of its type.
+    /// Returns the element and whether it is fresh.
+    fn get_unit_elem_id(
+        &mut self,
+        elem_type: ElemType,
+        name: Option<&'static str>,
+    ) -> Result<(ElemId, bool)> {
+        // the unit value is already open, so it is a root value if it is the
+        // only open value
+        let shared = self.unit_policy == UnitPolicy::Singletons && self.open_values > 1;
+        if shared {
+            if let Some(id) = self.unit_singletons.get(&name) {
+                self.elem_stack.push(*id);
+                return Result::Ok((*id, false));
+            }
+        }
+
+        let id = self.get_fresh_elem_id(elem_type)?;
+        if shared {
+            self.unit_singletons.insert(name, id);
+        }
+
+        if self.unit_policy == UnitPolicy::Facts {
+            self.emit(Fact::Unit { elem: id })?;
+        }
+
+        Result::Ok((id, true))
+    }
+
     fn serialize_tuple_or_seq_element<T: ?Sized + serde::Serialize>(
         &mut self,
         value: &T,
         elem_type: ElemType,
     ) -> Result<()> {
-        value.serialize(&mut *self)?;
+        let pos = self.parent_stack.last().unwrap().1;
+        self.in_child(PathSegment::Pos(pos), |this| {
+            let schema = this.child_schema(|guide, node| guide.item(node, pos));
+            this.serialize_with_schema(schema, value)?;
+            this.end_tuple_or_seq_entry(elem_type)
+        })
+    }
+
+    /// Generate facts about the entry of the current sequence or tuple whose
+    /// value was just serialized.
+    fn end_tuple_or_seq_entry(&mut self, elem_type: ElemType) -> Result<()> {
         let child_id = self.elem_stack.pop().unwrap();
         let (parent_id, pos) = self.parent_stack.last_mut().unwrap();
+        let (elem, entry_pos) = (*parent_id, *pos);
+        *pos += 1;
 
         match elem_type {
-            ElemType::Seq => self.backend.add_seq_entry(*parent_id, *pos, child_id),
+            ElemType::Seq => self.emit(Fact::SeqEntry {
+                elem,
+                pos: entry_pos,
+                value: child_id,
+            }),
 
             ElemType::Tuple | ElemType::TupleStruct | ElemType::TupleVariant => {
-                self.backend.add_tuple_entry(*parent_id, *pos, child_id)
+                self.emit(Fact::TupleEntry {
+                    elem,
+                    pos: entry_pos,
+                    value: child_id,
+                })
             }
 
             _ => unreachable!(),
-        }?;
+        }
+    }
+
+    /// Start generating facts about a value, calling
+    /// [begin][DatalogExtractorBackend::begin] if it is a root value.
+    fn begin_value(&mut self) -> Result<()> {
+        if self.open_values == 0 {
+            self.context_attached = false;
+            if self.atomic_roots {
+                self.checkpoint = Some(self.checkpoint_state());
+            } else {
+                self.begin_root(self.batch.len())?;
+            }
+        }
+
+        self.open_values += 1;
+        if self.measures {
+            self.subtree_sizes.push(0);
+        }
+        Result::Ok(())
+    }
+
+    /// Finish generating facts about a value, calling
+    /// [finish][DatalogExtractorBackend::finish] if it is a root value.
+    fn end_value(&mut self) -> Result<()> {
+        if self.measures {
+            self.end_subtree()?;
+        }
+
+        self.open_values -= 1;
+        if self.open_values == 0 {
+            // only the root element is left, which has no parent to pop it
+            self.elem_stack.clear();
+            if let Some(checkpoint) = self.checkpoint.take() {
+                self.begin_root(checkpoint.batch_len)?;
+            }
+            self.finish_root()?;
+
+            #[cfg(feature = "tracing")]
+            tracing::trace!(
+                parent: &self.file_span,
+                elements = self.elements,
+                "extracted root value"
+            );
+        }
 
-        *pos += 1;
         Result::Ok(())
     }
 
+    /// Generate facts about the size of the value being finished, whose
+    /// nested values have all been finished, and count its elements in the
+    /// value enclosing it.
+    fn end_subtree(&mut self) -> Result<()> {
+        let size = self.subtree_sizes.pop().unwrap();
+        if let Some(parent_size) = self.subtree_sizes.last_mut() {
+            *parent_size += size;
+        }
+
+        // shared unit elements are counted where they first occur
+        if size == 0 {
+            return Result::Ok(());
+        }
+
+        // the elements of nested values have been popped by their parents
+        let elem = *self.elem_stack.last().unwrap();
+        self.emit(Fact::SubtreeSize { elem, size })
+    }
+
     fn end_parent(&mut self) -> Result<ElemId> {
         let (parent_id, _) = self.parent_stack.pop().unwrap();
         Result::Ok(parent_id)
@@ -467,18 +2949,136 @@ impl<B: DatalogExtractorBackend> DatalogExtractor<B> {
         key: &'static str,
         value: &T,
     ) -> Result<()> {
-        value.serialize(&mut *self)?;
-        let (parent_id, _) = self.parent_stack.last_mut().unwrap();
+        self.see_field(key);
+        if self.skips_field(key) {
+            return Result::Ok(());
+        }
+
+        self.in_child(PathSegment::Field(key), |this| {
+            let schema = this.child_schema(|guide, node| guide.property(node, Some(key)));
+            this.serialize_entry_value(Some(key), schema, value)?;
+            this.end_struct_entry(key)
+        })
+    }
+
+    /// Record that the current struct serialized or skipped field `key`, so
+    /// that it is not materialized as absent when the struct ends.
+    fn see_field(&mut self, key: &'static str) {
+        if let Some(seen) = self.seen_fields.last_mut() {
+            seen.insert(key);
+        }
+    }
+
+    /// Generate facts about the field `key` of the current struct, which its
+    /// [serde::Serialize] implementation skipped.
+    fn skip_struct_element(&mut self, key: &'static str) -> Result<()> {
+        if !self.sparse_structs || self.skips_field(key) {
+            return Result::Ok(());
+        }
+
+        self.see_field(key);
+        let field = match self.cur_hints() {
+            Some(hints) => hints.field_name(key),
+            None => key.to_string(),
+        };
+
+        let (elem, _) = self.parent_stack.last().unwrap();
+        self.emit(Fact::AbsentField { elem: *elem, field })
+    }
+
+    /// Generate facts about the field `key` of the current struct whose value
+    /// was just serialized.
+    fn end_struct_entry(&mut self, key: &'static str) -> Result<()> {
+        let (key, is_key) = match self.cur_hints() {
+            Some(hints) => (hints.field_name(key), hints.keys.iter().any(|k| k == key)),
+            None => (key.to_string(), false),
+        };
+
+        self.see_shape_field(Some(&key));
+        let (parent_id, _) = self.parent_stack.last().unwrap();
+        let elem = *parent_id;
+        let val_id = self.elem_stack.pop().unwrap();
+        self.emit(Fact::StructEntry {
+            elem,
+            key,
+            value: val_id,
+        })?;
+
+        if is_key {
+            self.emit(Fact::Key { elem, key: val_id })?;
+        }
+
+        Result::Ok(())
+    }
+
+    /// Generate facts about the entry of the current map whose key and value
+    /// were just serialized.
+    fn end_map_entry(&mut self) -> Result<()> {
+        let (parent_id, _) = self.parent_stack.last().unwrap();
+        let elem = *parent_id;
         let val_id = self.elem_stack.pop().unwrap();
-        self.backend.add_struct_entry(*parent_id, key, val_id)
+        let key_id = self.elem_stack.pop().unwrap();
+        self.emit(Fact::MapEntry {
+            elem,
+            key: key_id,
+            value: val_id,
+        })
+    }
+
+    /// Generate facts about a newtype struct whose value was just serialized.
+    fn end_newtype_struct(&mut self, name: &'static str) -> Result<()> {
+        let child_id = self.elem_stack.pop().unwrap();
+        let id = self.get_fresh_elem_id(ElemType::NewtypeStruct)?;
+        self.emit(Fact::StructType {
+            elem: id,
+            struct_name: name.to_string(),
+        })?;
+        self.emit(Fact::TupleEntry {
+            elem: id,
+            pos: 0,
+            value: child_id,
+        })?;
+        self.end_value()
+    }
+
+    /// Generate facts about a newtype variant whose value was just serialized.
+    fn end_newtype_variant(&mut self, name: &'static str, variant: &'static str) -> Result<()> {
+        let child_id = self.elem_stack.pop().unwrap();
+        let id = self.get_fresh_elem_id(ElemType::NewtypeVariant)?;
+        self.emit(Fact::VariantType {
+            elem: id,
+            type_name: name.to_string(),
+            variant_name: variant.to_string(),
+        })?;
+        self.emit(Fact::TupleEntry {
+            elem: id,
+            pos: 0,
+            value: child_id,
+        })?;
+        self.end_value()
+    }
+
+    /// The number of facts generated since the extractor was created, over
+    /// all root values. Facts dropped by [quotas][Self::with_fact_quotas] are
+    /// not counted.
+    pub fn facts(&self) -> usize {
+        self.total_facts
+    }
+
+    /// The backend, e.g. to inspect the facts it received so far without
+    /// finishing extraction.
+    pub fn backend(&self) -> &B {
+        &self.backend
     }
 
     pub fn get_backend(self) -> B {
+        #[cfg(feature = "tracing")]
+        self.trace_file_summary();
         self.backend
     }
 }
 
-impl<'a, B: DatalogExtractorBackend> ser::Serializer for &'a mut DatalogExtractor<B> {
+impl<B: DatalogExtractorBackend> ser::Serializer for &mut DatalogExtractor<B> {
     type Ok = ();
     type Error = DatalogExtractionError;
 
@@ -499,8 +3099,10 @@ impl<'a, B: DatalogExtractorBackend> ser::Serializer for &'a mut DatalogExtracto
     /// add_bool(id, value)
     /// ```
     fn serialize_bool(self, value: bool) -> Result<Self::Ok> {
+        self.begin_value()?;
         let id = self.get_fresh_elem_id(ElemType::Bool)?;
-        self.backend.add_bool(id, value)
+        self.emit(Fact::Bool { elem: id, value })?;
+        self.end_value()
     }
 
     /// Generate facts about an i8 value.
@@ -512,8 +3114,10 @@ impl<'a, B: DatalogExtractorBackend> ser::Serializer for &'a mut DatalogExtracto
     /// add_i8(id, value)
     /// ```
     fn serialize_i8(self, value: i8) -> Result<Self::Ok> {
+        self.begin_value()?;
         let id = self.get_fresh_elem_id(ElemType::I8)?;
-        self.backend.add_i8(id, value)
+        self.emit(Fact::I8 { elem: id, value })?;
+        self.end_value()
     }
 
     /// Generate facts about an i16 value.
@@ -525,8 +3129,10 @@ impl<'a, B: DatalogExtractorBackend> ser::Serializer for &'a mut DatalogExtracto
     /// add_i16(id, value)
     /// ```
     fn serialize_i16(self, value: i16) -> Result<Self::Ok> {
+        self.begin_value()?;
         let id = self.get_fresh_elem_id(ElemType::I16)?;
-        self.backend.add_i16(id, value)
+        self.emit(Fact::I16 { elem: id, value })?;
+        self.end_value()
     }
 
     /// Generate facts about an i32 value.
@@ -538,8 +3144,10 @@ impl<'a, B: DatalogExtractorBackend> ser::Serializer for &'a mut DatalogExtracto
     /// add_i32(id, value)
     /// ```
     fn serialize_i32(self, value: i32) -> Result<Self::Ok> {
+        self.begin_value()?;
         let id = self.get_fresh_elem_id(ElemType::I32)?;
-        self.backend.add_i32(id, value)
+        self.emit(Fact::I32 { elem: id, value })?;
+        self.end_value()
     }
 
     /// Generate facts about an i64 value.
@@ -551,8 +3159,10 @@ impl<'a, B: DatalogExtractorBackend> ser::Serializer for &'a mut DatalogExtracto
     /// add_i64(id, value)
     /// ```
     fn serialize_i64(self, value: i64) -> Result<Self::Ok> {
+        self.begin_value()?;
         let id = self.get_fresh_elem_id(ElemType::I64)?;
-        self.backend.add_i64(id, value)
+        self.emit(Fact::I64 { elem: id, value })?;
+        self.end_value()
     }
 
     /// Generate facts about an u8 value.
@@ -564,8 +3174,10 @@ impl<'a, B: DatalogExtractorBackend> ser::Serializer for &'a mut DatalogExtracto
     /// add_u8(id, value)
     /// ```
     fn serialize_u8(self, value: u8) -> Result<Self::Ok> {
+        self.begin_value()?;
         let id = self.get_fresh_elem_id(ElemType::U8)?;
-        self.backend.add_u8(id, value)
+        self.emit(Fact::U8 { elem: id, value })?;
+        self.end_value()
     }
 
     /// Generate facts about an u16 value.
@@ -577,8 +3189,10 @@ impl<'a, B: DatalogExtractorBackend> ser::Serializer for &'a mut DatalogExtracto
     /// add_u16(id, value)
     /// ```
     fn serialize_u16(self, value: u16) -> Result<Self::Ok> {
+        self.begin_value()?;
         let id = self.get_fresh_elem_id(ElemType::U16)?;
-        self.backend.add_u16(id, value)
+        self.emit(Fact::U16 { elem: id, value })?;
+        self.end_value()
     }
 
     /// Generate facts about an u32 value.
@@ -590,8 +3204,10 @@ impl<'a, B: DatalogExtractorBackend> ser::Serializer for &'a mut DatalogExtracto
     /// add_u32(id, value)
     /// ```
     fn serialize_u32(self, value: u32) -> Result<Self::Ok> {
+        self.begin_value()?;
         let id = self.get_fresh_elem_id(ElemType::U32)?;
-        self.backend.add_u32(id, value)
+        self.emit(Fact::U32 { elem: id, value })?;
+        self.end_value()
     }
 
     /// Generate facts about an u64 value.
@@ -603,8 +3219,10 @@ impl<'a, B: DatalogExtractorBackend> ser::Serializer for &'a mut DatalogExtracto
     /// add_u64(id, value)
     /// ```
     fn serialize_u64(self, value: u64) -> Result<Self::Ok> {
+        self.begin_value()?;
         let id = self.get_fresh_elem_id(ElemType::U64)?;
-        self.backend.add_u64(id, value)
+        self.emit(Fact::U64 { elem: id, value })?;
+        self.end_value()
     }
 
     /// Generate facts about an f32 value.
@@ -616,8 +3234,10 @@ impl<'a, B: DatalogExtractorBackend> ser::Serializer for &'a mut DatalogExtracto
     /// add_f32(id, value)
     /// ```
     fn serialize_f32(self, value: f32) -> Result<Self::Ok> {
+        self.begin_value()?;
         let id = self.get_fresh_elem_id(ElemType::F32)?;
-        self.backend.add_f32(id, value)
+        self.emit(Fact::F32 { elem: id, value })?;
+        self.end_value()
     }
 
     /// Generate facts about an f64 value.
@@ -629,8 +3249,10 @@ impl<'a, B: DatalogExtractorBackend> ser::Serializer for &'a mut DatalogExtracto
     /// add_f64(id, value)
     /// ```
     fn serialize_f64(self, value: f64) -> Result<Self::Ok> {
+        self.begin_value()?;
         let id = self.get_fresh_elem_id(ElemType::F64)?;
-        self.backend.add_f64(id, value)
+        self.emit(Fact::F64 { elem: id, value })?;
+        self.end_value()
     }
 
     /// Generate facts about a char value.
@@ -641,9 +3263,30 @@ impl<'a, B: DatalogExtractorBackend> ser::Serializer for &'a mut DatalogExtracto
     /// add_elem(id, ElemType::Char)
     /// add_char(id, value)
     /// ```
+    ///
+    /// If the extractor records [code points][DatalogExtractor::with_char_codepoints],
+    /// this will also call:
+    ///
+    /// ```ignore
+    /// add_codepoint(id, value as u32)
+    /// ```
     fn serialize_char(self, value: char) -> Result<Self::Ok> {
+        self.begin_value()?;
         let id = self.get_fresh_elem_id(ElemType::Char)?;
-        self.backend.add_char(id, value)
+        let mut buf = [0; 4];
+        let text: &str = value.encode_utf8(&mut buf);
+        if matches!(self.string_policy.apply(text), Cow::Borrowed(_)) {
+            self.emit(Fact::Char { elem: id, value })?;
+        } else {
+            self.emit_str(id, text)?;
+        }
+        if self.char_codepoints {
+            self.emit(Fact::Codepoint {
+                elem: id,
+                codepoint: value.into(),
+            })?;
+        }
+        self.end_value()
     }
 
     /// Generate facts about a str value.
@@ -654,9 +3297,62 @@ impl<'a, B: DatalogExtractorBackend> ser::Serializer for &'a mut DatalogExtracto
     /// add_elem(id, ElemType::Str)
     /// add_str(id, value)
     /// ```
+    ///
+    /// If the string is a date-time, this will also call:
+    ///
+    /// ```ignore
+    /// add_datetime(id, epoch_micros, tz_offset)
+    /// ```
+    ///
+    /// If the string is a scalar recognized by the extractor's
+    /// [Recognizers], this will also call the method for the scalar, e.g.
+    /// `add_uuid(id, hi, lo)`.
     fn serialize_str(self, value: &str) -> Result<Self::Ok> {
+        if self.capture_map_key {
+            self.datetime_key = value == datetime::TOML_DATETIME_KEY;
+            if self.schema_guide.is_some()
+                || self.redaction.is_some()
+                || self.shapes
+                || self.error_context
+            {
+                self.map_key = Some(value.to_string());
+            }
+        }
+
+        let datetime = if std::mem::take(&mut self.mark_datetime) || self.datetime_strings {
+            Datetime::parse(value)
+        } else {
+            None
+        };
+
+        self.begin_value()?;
         let id = self.get_fresh_elem_id(ElemType::Str)?;
-        self.backend.add_str(id, value)
+        self.emit_str(id, value)?;
+        if let Some(datetime) = datetime {
+            self.emit(Fact::Datetime {
+                elem: id,
+                epoch_micros: datetime.epoch_micros,
+                tz_offset: datetime.tz_offset,
+            })?;
+        }
+
+        if let Some(scalar) = self.recognizers.recognize(value) {
+            self.emit(match scalar {
+                Scalar::Uuid { hi, lo } => Fact::Uuid { elem: id, hi, lo },
+                Scalar::Ip(address) => Fact::Ip { elem: id, address },
+                Scalar::Url { scheme, host } => Fact::Url {
+                    elem: id,
+                    scheme,
+                    host,
+                },
+                Scalar::Email { local, domain } => Fact::Email {
+                    elem: id,
+                    local,
+                    domain,
+                },
+            })?;
+        }
+        self.end_value()
     }
 
     /// Generate facts about a byte array value.
@@ -668,8 +3364,13 @@ impl<'a, B: DatalogExtractorBackend> ser::Serializer for &'a mut DatalogExtracto
     /// add_bytes(id, value)
     /// ```
     fn serialize_bytes(self, value: &[u8]) -> Result<Self::Ok> {
+        self.begin_value()?;
         let id = self.get_fresh_elem_id(ElemType::Bytes)?;
-        self.backend.add_bytes(id, value)
+        self.emit(Fact::Bytes {
+            elem: id,
+            value: value.to_vec(),
+        })?;
+        self.end_value()
     }
 
     /// Generate facts about a None value.
@@ -705,9 +3406,16 @@ impl<'a, B: DatalogExtractorBackend> ser::Serializer for &'a mut DatalogExtracto
     /// ```ignore
     /// add_elem(id, ElemType::Unit)
     /// ```
+    ///
+    /// If the extractor's [UnitPolicy] is [Facts][UnitPolicy::Facts], this
+    /// will also call `add_unit(id)`. If it is
+    /// [Singletons][UnitPolicy::Singletons], only the first unit value of an
+    /// input file that is not a root value gets a fresh element; later ones
+    /// generate no facts and share its element.
     fn serialize_unit(self) -> Result<Self::Ok> {
-        self.get_fresh_elem_id(ElemType::Unit)?;
-        Result::Ok(())
+        self.begin_value()?;
+        self.get_unit_elem_id(ElemType::Unit, None)?;
+        self.end_value()
     }
 
     /// Generate facts about a unit struct value.
@@ -718,9 +3426,20 @@ impl<'a, B: DatalogExtractorBackend> ser::Serializer for &'a mut DatalogExtracto
     /// add_elem(id, ElemType::UnitStruct)
     /// add_struct_type(id, name)
     /// ```
+    ///
+    /// Unit struct values are affected by the extractor's [UnitPolicy] like
+    /// unit values, except that singletons are shared by unit structs of the
+    /// same type name.
     fn serialize_unit_struct(self, name: &'static str) -> Result<Self::Ok> {
-        let id = self.get_fresh_elem_id(ElemType::UnitStruct)?;
-        self.backend.add_struct_type(id, name)
+        self.begin_value()?;
+        let (id, fresh) = self.get_unit_elem_id(ElemType::UnitStruct, Some(name))?;
+        if fresh {
+            self.emit(Fact::StructType {
+                elem: id,
+                struct_name: name.to_string(),
+            })?;
+        }
+        self.end_value()
     }
 
     /// Generate facts about a unit variant value.
@@ -737,9 +3456,14 @@ impl<'a, B: DatalogExtractorBackend> ser::Serializer for &'a mut DatalogExtracto
         _variant_index: u32,
         variant: &'static str,
     ) -> std::result::Result<Self::Ok, Self::Error> {
+        self.begin_value()?;
         let id = self.get_fresh_elem_id(ElemType::UnitVariant)?;
-        self.elem_stack.push(id);
-        self.backend.add_variant_type(id, name, variant)
+        self.emit(Fact::VariantType {
+            elem: id,
+            type_name: name.to_string(),
+            variant_name: variant.to_string(),
+        })?;
+        self.end_value()
     }
 
     /// Generate facts about a newtype struct value.
@@ -752,16 +3476,24 @@ impl<'a, B: DatalogExtractorBackend> ser::Serializer for &'a mut DatalogExtracto
     /// add_struct_type(id, name)
     /// add_tuple_entry(id, 0, value_id)
     /// ```
+    ///
+    /// Newtype structs named [DATETIME_NAME][datetime::DATETIME_NAME] are
+    /// not extracted; their value is extracted as a date-time instead.
     fn serialize_newtype_struct<T: ?Sized + serde::Serialize>(
         self,
         name: &'static str,
         value: &T,
     ) -> Result<Self::Ok> {
+        if name == datetime::DATETIME_NAME {
+            self.mark_datetime = true;
+            let res = value.serialize(&mut *self);
+            self.mark_datetime = false;
+            return res;
+        }
+
+        self.begin_value()?;
         value.serialize(&mut *self)?;
-        let child_id = self.elem_stack.pop().unwrap();
-        let id = self.get_fresh_elem_id(ElemType::NewtypeStruct)?;
-        self.backend.add_struct_type(id, name)?;
-        self.backend.add_tuple_entry(id, 0, child_id)
+        self.end_newtype_struct(name)
     }
 
     /// Generate facts about a newtype variant value.
@@ -781,12 +3513,9 @@ impl<'a, B: DatalogExtractorBackend> ser::Serializer for &'a mut DatalogExtracto
         variant: &'static str,
         value: &T,
     ) -> Result<Self::Ok> {
+        self.begin_value()?;
         value.serialize(&mut *self)?;
-        let child_id = self.elem_stack.pop().unwrap();
-
-        let id = self.get_fresh_elem_id(ElemType::NewtypeVariant)?;
-        self.backend.add_variant_type(id, name, variant)?;
-        self.backend.add_tuple_entry(id, 0, child_id)
+        self.end_newtype_variant(name, variant)
     }
 
     /// Generate facts about a sequence value.
@@ -797,9 +3526,17 @@ impl<'a, B: DatalogExtractorBackend> ser::Serializer for &'a mut DatalogExtracto
     /// ```ignore
     /// add_elem(id, ElemType::Seq)
     /// ```
-    fn serialize_seq(self, _len_opt: Option<usize>) -> Result<Self::SerializeSeq> {
+    ///
+    /// If the extractor has a [Sampling], only the sampled elements of the
+    /// sequence generate facts, and `add_sampled(id, len, kept)` is called
+    /// if elements were left out.
+    fn serialize_seq(self, len_opt: Option<usize>) -> Result<Self::SerializeSeq> {
+        self.begin_value()?;
         let id = self.get_fresh_elem_id(ElemType::Seq)?;
         self.parent_stack.push((id, 0));
+        if let Some(sampling) = &self.sampling {
+            self.sample_stack.push(sampling.sampler(id.0, len_opt));
+        }
         Result::Ok(self)
     }
 
@@ -812,6 +3549,7 @@ impl<'a, B: DatalogExtractorBackend> ser::Serializer for &'a mut DatalogExtracto
     /// add_elem(id, ElemType::Tuple)
     /// ```
     fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple> {
+        self.begin_value()?;
         let id = self.get_fresh_elem_id(ElemType::Tuple)?;
         self.parent_stack.push((id, 0));
         Result::Ok(self)
@@ -831,9 +3569,13 @@ impl<'a, B: DatalogExtractorBackend> ser::Serializer for &'a mut DatalogExtracto
         name: &'static str,
         _len: usize,
     ) -> Result<Self::SerializeTupleStruct> {
+        self.begin_value()?;
         let id = self.get_fresh_elem_id(ElemType::TupleStruct)?;
         self.parent_stack.push((id, 0));
-        self.backend.add_struct_type(id, name)?;
+        self.emit(Fact::StructType {
+            elem: id,
+            struct_name: name.to_string(),
+        })?;
         Result::Ok(self)
     }
 
@@ -853,9 +3595,14 @@ impl<'a, B: DatalogExtractorBackend> ser::Serializer for &'a mut DatalogExtracto
         variant: &'static str,
         _len: usize,
     ) -> Result<Self::SerializeTupleVariant> {
+        self.begin_value()?;
         let id = self.get_fresh_elem_id(ElemType::TupleVariant)?;
         self.parent_stack.push((id, 0));
-        self.backend.add_variant_type(id, name, variant)?;
+        self.emit(Fact::VariantType {
+            elem: id,
+            type_name: name.to_string(),
+            variant_name: variant.to_string(),
+        })?;
         Result::Ok(self)
     }
 
@@ -868,8 +3615,12 @@ impl<'a, B: DatalogExtractorBackend> ser::Serializer for &'a mut DatalogExtracto
     /// add_elem(id, ElemType::Map)
     /// ```
     fn serialize_map(self, _len_opt: Option<usize>) -> Result<Self::SerializeMap> {
+        self.begin_value()?;
         let id = self.get_fresh_elem_id(ElemType::Map)?;
         self.parent_stack.push((id, 0));
+        if self.shapes {
+            self.shape_stack.push(Some(Vec::new()));
+        }
         Result::Ok(self)
     }
 
@@ -882,10 +3633,18 @@ impl<'a, B: DatalogExtractorBackend> ser::Serializer for &'a mut DatalogExtracto
     /// add_elem(id, ElemType::Struct)
     /// add_struct_type(id, name)
     /// ```
-    fn serialize_struct(self, name: &'static str, _len: usize) -> Result<Self::SerializeStruct> {
+    ///
+    /// If sparse structs are enabled, this will also call
+    /// `add_declared_len(id, len)`.
+    fn serialize_struct(self, name: &'static str, len: usize) -> Result<Self::SerializeStruct> {
+        self.begin_value()?;
         let id = self.get_fresh_elem_id(ElemType::Struct)?;
         self.parent_stack.push((id, 0));
-        self.backend.add_struct_type(id, name)?;
+        self.emit(Fact::StructType {
+            elem: id,
+            struct_name: name.to_string(),
+        })?;
+        self.begin_struct(name, len)?;
         Result::Ok(self)
     }
 
@@ -898,21 +3657,30 @@ impl<'a, B: DatalogExtractorBackend> ser::Serializer for &'a mut DatalogExtracto
     /// add_elem(id, ElemType::StructVariant)
     /// add_variant_type(id, name, variant)
     /// ```
+    ///
+    /// If sparse structs are enabled, this will also call
+    /// `add_declared_len(id, len)`.
     fn serialize_struct_variant(
         self,
         name: &'static str,
         _variant_index: u32,
         variant: &'static str,
-        _len: usize,
+        len: usize,
     ) -> Result<Self::SerializeStructVariant> {
+        self.begin_value()?;
         let id = self.get_fresh_elem_id(ElemType::StructVariant)?;
         self.parent_stack.push((id, 0));
-        self.backend.add_variant_type(id, name, variant)?;
+        self.emit(Fact::VariantType {
+            elem: id,
+            type_name: name.to_string(),
+            variant_name: variant.to_string(),
+        })?;
+        self.begin_struct(name, len)?;
         Result::Ok(self)
     }
 }
 
-impl<'a, B: DatalogExtractorBackend> ser::SerializeSeq for &'a mut DatalogExtractor<B> {
+impl<B: DatalogExtractorBackend> ser::SerializeSeq for &mut DatalogExtractor<B> {
     type Ok = ();
     type Error = DatalogExtractionError;
 
@@ -927,16 +3695,45 @@ impl<'a, B: DatalogExtractorBackend> ser::SerializeSeq for &'a mut DatalogExtrac
     /// add_seq_entry(parent_id, pos, value_id)
     /// ```
     fn serialize_element<T: ?Sized + serde::Serialize>(&mut self, value: &T) -> Result<Self::Ok> {
+        if let Some(Some(sampler)) = self.sample_stack.last_mut() {
+            if sampler.holds() {
+                sampler.hold(value.serialize(Capture)?);
+                return Result::Ok(());
+            }
+
+            if !sampler.keep() {
+                self.parent_stack.last_mut().unwrap().1 += 1;
+                return value.serialize(Discard);
+            }
+        }
+
         self.serialize_tuple_or_seq_element(value, ElemType::Seq)
     }
 
     fn end(self) -> Result<()> {
-        self.end_parent()?;
-        Result::Ok(())
+        let mut sampler = self.sample_stack.pop().flatten();
+
+        // elements of sequences of unknown length are held until they end
+        if let Some(sampler) = &mut sampler {
+            for (pos, value) in sampler.take_held() {
+                self.parent_stack.last_mut().unwrap().1 = pos;
+                self.serialize_tuple_or_seq_element(&value, ElemType::Seq)?;
+            }
+        }
+
+        let id = self.end_parent()?;
+        if let Some(sampler) = sampler.filter(|sampler| sampler.kept() < sampler.seen()) {
+            self.emit(Fact::Sampled {
+                elem: id,
+                len: sampler.seen(),
+                kept: sampler.kept(),
+            })?;
+        }
+        self.end_value()
     }
 }
 
-impl<'a, B: DatalogExtractorBackend> ser::SerializeTuple for &'a mut DatalogExtractor<B> {
+impl<B: DatalogExtractorBackend> ser::SerializeTuple for &mut DatalogExtractor<B> {
     type Ok = ();
     type Error = DatalogExtractionError;
 
@@ -956,11 +3753,11 @@ impl<'a, B: DatalogExtractorBackend> ser::SerializeTuple for &'a mut DatalogExtr
 
     fn end(self) -> Result<()> {
         self.end_parent()?;
-        Result::Ok(())
+        self.end_value()
     }
 }
 
-impl<'a, B: DatalogExtractorBackend> ser::SerializeTupleVariant for &'a mut DatalogExtractor<B> {
+impl<B: DatalogExtractorBackend> ser::SerializeTupleVariant for &mut DatalogExtractor<B> {
     type Ok = ();
     type Error = DatalogExtractionError;
 
@@ -980,11 +3777,11 @@ impl<'a, B: DatalogExtractorBackend> ser::SerializeTupleVariant for &'a mut Data
 
     fn end(self) -> Result<Self::Ok> {
         self.end_parent()?;
-        Result::Ok(())
+        self.end_value()
     }
 }
 
-impl<'a, B: DatalogExtractorBackend> ser::SerializeTupleStruct for &'a mut DatalogExtractor<B> {
+impl<B: DatalogExtractorBackend> ser::SerializeTupleStruct for &mut DatalogExtractor<B> {
     type Ok = ();
     type Error = DatalogExtractionError;
 
@@ -1004,17 +3801,25 @@ impl<'a, B: DatalogExtractorBackend> ser::SerializeTupleStruct for &'a mut Datal
 
     fn end(self) -> Result<Self::Ok> {
         self.end_parent()?;
-        Result::Ok(())
+        self.end_value()
     }
 }
 
-impl<'a, B: DatalogExtractorBackend> ser::SerializeMap for &'a mut DatalogExtractor<B> {
+impl<B: DatalogExtractorBackend> ser::SerializeMap for &mut DatalogExtractor<B> {
     type Ok = ();
     type Error = DatalogExtractionError;
 
+    /// Generate facts about a map key. String keys are captured to find
+    /// whether the entry's value is a TOML date-time, and, if the extractor
+    /// has a [SchemaGuide] or a [Redaction], the schema of the entry's value
+    /// and whether it is redacted; keys are not annotated with schemas.
     fn serialize_key<T: ?Sized + serde::Serialize>(&mut self, key: &T) -> Result<Self::Ok> {
-        key.serialize(&mut **self)?;
-        Result::Ok(())
+        self.map_key = None;
+        self.datetime_key = false;
+        self.capture_map_key = true;
+        let res = self.serialize_with_schema(None, key);
+        self.capture_map_key = false;
+        res
     }
 
     /// Generate facts about a map entry.
@@ -1028,20 +3833,27 @@ impl<'a, B: DatalogExtractorBackend> ser::SerializeMap for &'a mut DatalogExtrac
     /// add_map_entry(parent_id, key_id, value_id)
     /// ```
     fn serialize_value<T: ?Sized + serde::Serialize>(&mut self, value: &T) -> Result<Self::Ok> {
-        value.serialize(&mut **self)?;
-        let (parent_id, _) = self.parent_stack.last().unwrap();
-        let val_id = self.elem_stack.pop().unwrap();
-        let key_id = self.elem_stack.pop().unwrap();
-        self.backend.add_map_entry(*parent_id, key_id, val_id)
+        let key = self.map_key.take();
+        self.see_shape_field(key.as_deref());
+        let segment = PathSegment::Key(key.clone().filter(|_| self.error_context));
+        self.in_child(segment, |this| {
+            let schema = this.child_schema(|guide, node| guide.property(node, key.as_deref()));
+            this.mark_datetime = std::mem::take(&mut this.datetime_key);
+            let res = this.serialize_entry_value(key.as_deref(), schema, value);
+            this.mark_datetime = false;
+            res?;
+            this.end_map_entry()
+        })
     }
 
     fn end(self) -> result::Result<Self::Ok, Self::Error> {
+        self.end_shape("")?;
         self.end_parent()?;
-        Result::Ok(())
+        self.end_value()
     }
 }
 
-impl<'a, B: DatalogExtractorBackend> ser::SerializeStruct for &'a mut DatalogExtractor<B> {
+impl<B: DatalogExtractorBackend> ser::SerializeStruct for &mut DatalogExtractor<B> {
     type Ok = ();
     type Error = DatalogExtractionError;
 
@@ -1054,6 +3866,9 @@ impl<'a, B: DatalogExtractorBackend> ser::SerializeStruct for &'a mut DatalogExt
     /// ```ignore
     /// add_struct_entry(parent_id, key, value_id)
     /// ```
+    ///
+    /// If `key` is a [key field][StructHints::key] of the struct, this will
+    /// also call `add_key(parent_id, value_id)`.
     fn serialize_field<T: ?Sized + serde::Serialize>(
         &mut self,
         key: &'static str,
@@ -1062,13 +3877,27 @@ impl<'a, B: DatalogExtractorBackend> ser::SerializeStruct for &'a mut DatalogExt
         self.serialize_struct_element(key, value)
     }
 
+    /// Generate facts about a field skipped by the struct's
+    /// [serde::Serialize] implementation, e.g. with
+    /// `#[serde(skip_serializing_if)]`. If sparse structs are enabled, given
+    /// that the parent struct has element ID `parent_id`, this will make the
+    /// following call to an implementation of [DatalogExtractorBackend]:
+    ///
+    /// ```ignore
+    /// add_absent_field(parent_id, key)
+    /// ```
+    fn skip_field(&mut self, key: &'static str) -> Result<Self::Ok> {
+        self.skip_struct_element(key)
+    }
+
     fn end(self) -> Result<Self::Ok> {
+        self.end_struct()?;
         self.end_parent()?;
-        Result::Ok(())
+        self.end_value()
     }
 }
 
-impl<'a, B: DatalogExtractorBackend> ser::SerializeStructVariant for &'a mut DatalogExtractor<B> {
+impl<B: DatalogExtractorBackend> ser::SerializeStructVariant for &mut DatalogExtractor<B> {
     type Ok = ();
     type Error = DatalogExtractionError;
 
@@ -1081,6 +3910,9 @@ impl<'a, B: DatalogExtractorBackend> ser::SerializeStructVariant for &'a mut Dat
     /// ```ignore
     /// add_struct_entry(parent_id, key, value_id)
     /// ```
+    ///
+    /// If `key` is a [key field][StructHints::key] of the struct, this will
+    /// also call `add_key(parent_id, value_id)`.
     fn serialize_field<T: ?Sized + serde::Serialize>(
         &mut self,
         key: &'static str,
@@ -1089,8 +3921,22 @@ impl<'a, B: DatalogExtractorBackend> ser::SerializeStructVariant for &'a mut Dat
         self.serialize_struct_element(key, value)
     }
 
+    /// Generate facts about a field skipped by the struct's
+    /// [serde::Serialize] implementation, e.g. with
+    /// `#[serde(skip_serializing_if)]`. If sparse structs are enabled, given
+    /// that the parent struct has element ID `parent_id`, this will make the
+    /// following call to an implementation of [DatalogExtractorBackend]:
+    ///
+    /// ```ignore
+    /// add_absent_field(parent_id, key)
+    /// ```
+    fn skip_field(&mut self, key: &'static str) -> Result<Self::Ok> {
+        self.skip_struct_element(key)
+    }
+
     fn end(self) -> result::Result<Self::Ok, Self::Error> {
+        self.end_struct()?;
         self.end_parent()?;
-        Result::Ok(())
+        self.end_value()
     }
 }
@@ -2,82 +2,461 @@
 //! converts from a variety of common data formats into an input EDB for a
 //! Datalog program.
 
-pub mod input_format;
+#[cfg(feature = "cache")]
+pub mod cache;
+#[cfg(feature = "cargo")]
+pub mod cargo;
+pub mod compression;
+pub mod config_file;
+pub mod error;
+pub mod input_source;
+pub mod query;
+pub mod repl;
+pub mod souffle;
+pub mod split_roots;
+pub mod summary;
 
-use clap::Parser;
+use clap::{Parser, Subcommand};
+use indicatif::{ProgressBar, ProgressStyle};
+use serde::de::DeserializeSeed;
 use std::{
+    collections::{BTreeMap, HashSet},
     fs,
     io::{self, Read},
+    ops::ControlFlow,
     path::{Path, PathBuf},
-    str::FromStr,
+    process::ExitCode,
 };
 
-use serde_datalog::{backend, DatalogExtractor, DatalogExtractorBackend};
-
-use crate::input_format::InputFormat;
-
-#[derive(Parser, Debug)]
-#[command(
-    version = "0.1.0",
-    about,
-    long_about = Some("Converts input in a variety of formats to a database of facts.")
-)]
-struct Args {
-    #[arg(
-        index = 1,
-        help = "List of input files; if absent, will read from standard input.\nFiles must all have the same input format."
-    )]
-    filenames: Vec<String>,
-
-    #[arg(
-        short = 'f',
-        long = "format",
-        help = "Format of input file; if absent, will guess format from file extensions"
-    )]
-    format: Option<String>,
-
-    #[arg(
-        short = 'o',
-        long = "output",
-        help = "File name of output SQLite database"
-    )]
-    output: Option<String>,
-
-    #[arg(
-        short = 'l',
-        long = "list-formats",
-        help = "Generate a list of supported file formats"
-    )]
-    list_formats: bool,
-}
-
-fn get_input_formats() -> Vec<Box<dyn InputFormat>> {
-    let mut formats: Vec<Box<dyn InputFormat>> = Vec::new();
-
-    #[cfg(feature = "json")]
-    {
-        formats.push(Box::new(input_format::json::InputFormatJSON));
+use serde_datalog::{
+    backend::{
+        self,
+        souffle_sqlite::{LoadOptions, WideNumbers},
+    },
+    bytes_policy::BytesPolicy,
+    config::ExtractorConfig,
+    diff, infer,
+    quota::FactQuotas,
+    recognize::Recognizers,
+    redact::{Redaction, RedactionMode},
+    sample::Sampling,
+    schema_guide::SchemaGuide,
+    string_policy::StringPolicy,
+    unit_policy::UnitPolicy,
+    validate, DatalogExtractionError, DatalogExtractor, DatalogExtractorBackend,
+};
+
+use serde_datalog::input_format::{
+    self, skip_bom, Document, DocumentValue, FormatRegistry, InputFormat, DETECT_PREFIX_LEN,
+};
+
+use crate::{
+    compression::Compression,
+    config_file::ConfigFile,
+    error::CliError,
+    input_source::{archive::Archive, Download, RemoteMeta},
+    split_roots::SplitRoots,
+    summary::Summary,
+};
+
+include!("cli.rs");
+
+impl InputArgs {
+    /// The policy applied to strings extracted from inputs.
+    fn string_policy(&self) -> StringPolicy {
+        let policy = StringPolicy::default()
+            .case_fold(self.case_fold)
+            .escape_control_chars(self.escape_control_chars)
+            .keep_full_text(self.keep_full_strings);
+
+        #[cfg(feature = "unicode")]
+        let policy = policy.nfc(self.nfc);
+
+        let policy = match self.elide_strings {
+            Some(max_len) => policy.elide(max_len),
+            None => policy,
+        };
+
+        match self.max_string_len {
+            Some(max_len) => policy.truncate(max_len),
+            None => policy,
+        }
     }
 
-    #[cfg(feature = "ron")]
-    {
-        formats.push(Box::new(crate::input_format::ron::InputFormatRON));
+    /// The scalars recognized in strings extracted from inputs.
+    fn recognizers(&self) -> Recognizers {
+        self.recognize
+            .iter()
+            .fold(Recognizers::default(), |recognizers, kind| match kind {
+                ScalarKind::Uuid => recognizers.uuid(true),
+                ScalarKind::Ip => recognizers.ip(true),
+                ScalarKind::Url => recognizers.url(true),
+                ScalarKind::Email => recognizers.email(true),
+                ScalarKind::All => Recognizers::all(),
+            })
     }
 
-    #[cfg(feature = "toml")]
-    {
-        formats.push(Box::new(crate::input_format::toml::InputFormatTOML));
+    /// The policy that encodes byte arrays extracted from inputs, if any.
+    fn bytes_policy(&self) -> Result<Option<BytesPolicy>, CliError> {
+        let Some(name) = &self.bytes else {
+            return Result::Ok(None);
+        };
+
+        BytesPolicy::from_name(name)
+            .map(Some)
+            .ok_or_else(|| CliError::Usage(format!("Unknown bytes encoding {}", name)))
+    }
+
+    /// The quotas on the facts of relations given with `--quota`.
+    fn fact_quotas(&self) -> Result<FactQuotas, CliError> {
+        let mut quotas = FactQuotas::default();
+        for quota in self.quota.iter() {
+            let (relation, max_facts) = quota
+                .split_once('=')
+                .and_then(|(relation, max)| Some((relation, max.parse::<usize>().ok()?)))
+                .ok_or_else(|| {
+                    CliError::Usage(format!("Quota {} is not of the form RELATION=MAX", quota))
+                })?;
+
+            quotas = quotas.limit(relation, max_facts);
+        }
+
+        Result::Ok(quotas)
+    }
+
+    /// Fill in the options that are not given on the command line from the
+    /// configuration file given with `--config` or found from the current
+    /// directory, if any. Returns the configuration that was read.
+    fn apply_config_file(&mut self) -> Result<ConfigFile, CliError> {
+        let path = match &self.config {
+            Some(path) => Some(Path::new(path).to_path_buf()),
+            None if self.no_config => None,
+            None => std::env::current_dir()
+                .ok()
+                .and_then(|dir| ConfigFile::find(&dir)),
+        };
+        let Some(path) = path else {
+            return Result::Ok(ConfigFile::default());
+        };
+
+        let mut config = ConfigFile::read(&path)?;
+        self.backend = self.backend.or(config.backend);
+        self.redact_mode = self.redact_mode.or(config.redact.mode);
+        if self.redact_salt.is_none() {
+            self.redact_salt = config.redact.salt.take();
+        }
+        self.redact
+            .splice(0..0, std::mem::take(&mut config.redact.fields));
+        self.extension_formats = std::mem::take(&mut config.formats);
+
+        Result::Ok(config)
+    }
+
+    /// The redaction of fields given with `--redact`, if any.
+    fn redaction(&self) -> Option<Redaction> {
+        if self.redact.is_empty() {
+            return None;
+        }
+
+        let redaction = self.redact.iter().fold(
+            Redaction::new(self.redact_mode.unwrap_or(RedactMode::Hash).into()),
+            |redaction, pattern| redaction.field(pattern),
+        );
+        Some(match &self.redact_salt {
+            Some(salt) => redaction.salt(salt),
+            None => redaction,
+        })
+    }
+
+    /// The sampling of sequences given with `--sample`, if any.
+    fn sampling(&self) -> Option<Sampling> {
+        let threshold = self.sample?;
+        Some(match self.sample_mode {
+            SampleModeArg::Stratified => Sampling::stratified(threshold),
+            SampleModeArg::Random => Sampling::random(threshold, self.sample_seed),
+        })
+    }
+
+    /// The schema guide compiled from the `--schema` document, if any.
+    fn schema_guide(&self) -> Result<Option<SchemaGuide>, CliError> {
+        let Some(path) = &self.schema else {
+            return Result::Ok(None);
+        };
+
+        #[cfg(feature = "json_schema")]
+        {
+            let contents = fs::read_to_string(path).map_err(|source| CliError::Io {
+                path: path.to_string(),
+                source,
+            })?;
+
+            let parse_error = |message: String| CliError::Parse {
+                path: path.to_string(),
+                message,
+            };
+
+            // OpenAPI documents are often written in YAML
+            let document: serde_json::Value =
+                match Path::new(path).extension().and_then(|ext| ext.to_str()) {
+                    #[cfg(feature = "yaml")]
+                    Some("yaml" | "yml") => serde_yaml::from_str(&contents)
+                        .map_err(|err| parse_error(err.to_string()))?,
+                    _ => serde_json::from_str(&contents)
+                        .map_err(|err| parse_error(err.to_string()))?,
+                };
+
+            SchemaGuide::from_json_schema(&document, &self.schema_ref)
+                .map(Some)
+                .map_err(parse_error)
+        }
+
+        #[cfg(not(feature = "json_schema"))]
+        Result::Err(CliError::Usage(format!(
+            "Cannot read schema {}: serde_datalog was built without the json_schema feature",
+            path
+        )))
+    }
+
+    /// Options of the extractor given with extraction flags.
+    fn extractor_config(&self) -> Result<ExtractorConfig, CliError> {
+        let config = DatalogExtractor::builder()
+            .string_policy(self.string_policy())
+            .recognizers(self.recognizers())
+            .sparse_structs(self.sparse_structs)
+            .same_strings(self.same_strings)
+            .shapes(self.shapes)
+            .measures(self.measures)
+            .atomic_roots(self.skip_invalid)
+            .char_codepoints(self.char_codepoints)
+            .error_context(true)
+            .unit_policy(self.units.into())
+            .fact_quotas(self.fact_quotas()?);
+        let config = match self.redaction() {
+            Some(redaction) => config.redaction(redaction),
+            None => config,
+        };
+        let config = match self.sampling() {
+            Some(sampling) => config.sampling(sampling),
+            None => config,
+        };
+        let config = match self.schema_guide()? {
+            Some(guide) => config.schema_guide(guide),
+            None => config,
+        };
+
+        Result::Ok(config)
+    }
+
+    /// The options that determine the facts extracted from inputs, which are
+    /// part of the keys of cached facts: the extraction flags, without the
+    /// flags naming inputs or reporting progress, and the contents of the
+    /// schemas they name.
+    #[cfg(feature = "cache")]
+    fn cache_settings(&self) -> Result<Vec<u8>, CliError> {
+        let args = InputArgs {
+            filenames: Vec::new(),
+            recursive: false,
+            keep_going: false,
+            config: None,
+            no_config: false,
+            json: false,
+            progress: false,
+            cache: None,
+            ..self.clone()
+        };
+
+        let mut settings = format!("{} {:?}", env!("CARGO_PKG_VERSION"), args).into_bytes();
+        for path in [&self.schema, &self.proto_descriptor].into_iter().flatten() {
+            let contents = fs::read(path).map_err(|source| CliError::Io {
+                path: path.to_string(),
+                source,
+            })?;
+            settings.extend(contents);
+        }
+
+        Result::Ok(settings)
+    }
+
+    /// Options for loading facts into the output database.
+    fn load_options(&self) -> LoadOptions {
+        let options = LoadOptions::default()
+            .statistics(self.stats)
+            .create_indexes(self.create_indexes)
+            .analyze(self.analyze)
+            .provenance(self.provenance);
+
+        let options = match self.number_bits {
+            Some(bits) => options.number_domain(bits, self.wide_numbers.into()),
+            None => options,
+        };
+
+        #[cfg(feature = "string_compression")]
+        let options = match self.compress_strings {
+            Some(min_len) => options.compress_strings(min_len),
+            None => options,
+        };
+
+        options
+    }
+}
+
+fn run_query(args: &QueryArgs) -> Result<(), CliError> {
+    let db_error = |source: rusqlite::Error| CliError::Database {
+        path: args.db.clone(),
+        source: source.into(),
+    };
+
+    let conn =
+        rusqlite::Connection::open_with_flags(&args.db, rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY)
+            .map_err(db_error)?;
+
+    #[cfg(feature = "string_compression")]
+    backend::souffle_sqlite::register_functions(&conn)
+        .map_err(|err| CliError::from_extraction(&args.db, err))?;
+
+    let sql = match (&args.sql, &args.datalog) {
+        (Some(sql), _) => sql.clone(),
+        (None, Some(rule)) => query::compile_rule(&conn, rule).map_err(CliError::Usage)?,
+        (None, None) => unreachable!(),
+    };
+
+    let result = query::run_sql(&conn, &sql).map_err(db_error)?;
+    result.dump();
+    Result::Ok(())
+}
+
+/// Extract the dependency graph of a Cargo project, along with facts about the
+/// lock file or metadata it was read from.
+#[cfg(feature = "cargo")]
+fn run_cargo(args: &CargoArgs) -> Result<(), CliError> {
+    use backend::souffle_sqlite::AbstractBackend;
+    use serde::Serialize;
+
+    let (file, contents) = match &args.input {
+        Some(file) => {
+            let contents = fs::read_to_string(file).map_err(|source| CliError::Io {
+                path: file.clone(),
+                source,
+            })?;
+            (file.clone(), contents)
+        }
+
+        None => {
+            let mut command = std::process::Command::new("cargo");
+            command.args(["metadata", "--format-version", "1"]);
+            if let Some(manifest_path) = &args.manifest_path {
+                command.args(["--manifest-path", manifest_path]);
+            }
+
+            let cargo_error = |source| CliError::Io {
+                path: "cargo metadata".to_string(),
+                source,
+            };
+            let output = command.output().map_err(cargo_error)?;
+            if !output.status.success() {
+                return Result::Err(cargo_error(io::Error::other(
+                    String::from_utf8_lossy(&output.stderr).trim().to_string(),
+                )));
+            }
+
+            let contents = String::from_utf8(output.stdout)
+                .map_err(|err| cargo_error(io::Error::new(io::ErrorKind::InvalidData, err)))?;
+            ("cargo metadata".to_string(), contents)
+        }
+    };
+
+    let parse_error = |message: String| CliError::Parse {
+        path: file.clone(),
+        message,
+    };
+
+    let mut extractor = DatalogExtractor::new(backend::souffle_sqlite::StringKeyBackend::default());
+    let extraction_error = |err| CliError::from_extraction(&file, err);
+
+    // lock files are TOML, and metadata is JSON
+    let graph = if file.ends_with(".lock") || file.ends_with(".toml") {
+        let lock: toml::Value =
+            toml::from_str(&contents).map_err(|err| parse_error(err.to_string()))?;
+        extractor
+            .set_file_format(&file, "toml")
+            .and_then(|_| extractor.set_file(&file))
+            .and_then(|_| lock.serialize(&mut extractor))
+            .map_err(extraction_error)?;
+        cargo::CargoGraph::from_lock(&lock).map_err(parse_error)?
+    } else {
+        let metadata: serde_json::Value =
+            serde_json::from_str(&contents).map_err(|err| parse_error(err.to_string()))?;
+        extractor
+            .set_file_format(&file, "json")
+            .and_then(|_| extractor.set_file(&file))
+            .and_then(|_| metadata.serialize(&mut extractor))
+            .map_err(extraction_error)?;
+        cargo::CargoGraph::from_metadata(&metadata).map_err(parse_error)?
+    };
+
+    let output = Path::new(&args.output);
+    if output.is_file() {
+        fs::remove_file(output).map_err(|source| CliError::Io {
+            path: args.output.clone(),
+            source,
+        })?;
     }
 
-    #[cfg(feature = "yaml")]
+    let db_error = |err| CliError::from_extraction(&args.output, err);
+    extractor
+        .get_backend()
+        .dump_to_db(&args.output)
+        .map_err(db_error)?;
+
+    rusqlite::Connection::open(&args.output)
+        .and_then(|conn| graph.store(&conn))
+        .map_err(|err| db_error(err.into()))
+}
+
+fn get_input_formats(args: &InputArgs) -> Result<FormatRegistry, CliError> {
+    let mut formats = FormatRegistry::with_builtin_formats();
+
+    #[cfg(feature = "protobuf")]
     {
-        formats.push(Box::new(crate::input_format::yaml::InputFormatYAML));
+        use serde_datalog::input_format::protobuf::InputFormatProtobuf;
+
+        // the built-in protobuf format is unconfigured, so it is replaced by
+        // one reading the message type given on the command line
+        if let (Some(descriptor_file), Some(message)) =
+            (&args.proto_descriptor, &args.proto_message)
+        {
+            let descriptor_set = fs::read(descriptor_file).map_err(|source| CliError::Io {
+                path: descriptor_file.to_string(),
+                source,
+            })?;
+            let protobuf =
+                InputFormatProtobuf::new(&descriptor_set, message).map_err(CliError::Usage)?;
+            formats.register(Box::new(protobuf));
+        }
+    }
+
+    for (ext, name) in args.extension_formats.iter() {
+        if !formats.map_extension(ext, name) {
+            return Result::Err(CliError::Usage(format!(
+                "Unknown input format {} for extension {}",
+                name, ext
+            )));
+        }
     }
 
-    formats
+    Result::Ok(formats)
 }
 
-fn print_formats(formats: &Vec<Box<dyn InputFormat>>) {
+/// Find the input format of `path` from its file name or extension,
+/// looking past compression extensions (e.g. `data.json.gz`).
+fn find_format_by_path<'a>(
+    formats: &'a FormatRegistry,
+    path: &Path,
+) -> Option<&'a dyn InputFormat> {
+    formats.find_by_path(&Compression::from_path(path).1)
+}
+
+fn print_formats(formats: &FormatRegistry) {
     println!("Supported input formats:");
     for fmt in formats.iter() {
         print!("- {} (extensions: ", fmt.name());
@@ -92,160 +471,1790 @@ fn print_formats(formats: &Vec<Box<dyn InputFormat>>) {
             }
         }
 
+        let names = fmt.file_names();
+        if !names.is_empty() {
+            print!("; file names: {}", names.join(", "));
+        }
+
         println!(")");
     }
+
+    println!("Supported compression formats:");
+    for compression in Compression::all() {
+        println!(
+            "- {} (extensions: .{})",
+            compression.name(),
+            compression.file_extensions().join(", .")
+        );
+    }
 }
 
+/// Extract facts from the documents read from `input` in input format `format`,
+/// whose root is named `path`, see [Input::root_name].
+/// `size_hint` is the size of the input in bytes, if it is known. With
+/// `skip_invalid`, documents that cannot be read or extracted are recorded as
+/// parse errors instead of failing, and extraction continues with the next
+/// document if the input can resume after them. Returns a warning for each
+/// document recorded as a parse error.
 fn process_file<B: DatalogExtractorBackend>(
     extractor: &mut DatalogExtractor<B>,
-    format: &Box<dyn InputFormat>,
-    filename_opt: Option<String>,
-    input: String,
-) -> Result<(), String> {
-    let mut format_data = format.create(&input);
+    format: &dyn InputFormat,
+    path: &str,
+    input: Box<dyn Read + '_>,
+    size_hint: Option<u64>,
+    split_roots: bool,
+    skip_invalid: bool,
+) -> Result<Vec<String>, CliError> {
+    extractor
+        .set_file_format(path, format.name())
+        .map_err(|err| CliError::from_extraction(path, err))?;
+    extractor.set_datetime_strings(format.has_datetime_strings());
+
+    let input = if format.is_binary() {
+        io::Result::Ok(input)
+    } else {
+        skip_bom(input)
+    };
+
+    let mut source = input
+        .and_then(|input| format.read(input, size_hint))
+        .map_err(|source| CliError::Io {
+            path: path.to_string(),
+            source,
+        })?;
+
+    let resumes_after_errors = source.resumes_after_errors();
+    let mut warnings = Vec::new();
+    while let Some(document) = source.next_document() {
+        let (line, res) = match document {
+            Result::Ok(document) => (
+                document.position.unwrap_or(0),
+                extract_document(extractor, path, format, document, split_roots),
+            ),
+
+            Result::Err(message) => (
+                0,
+                Result::Err(CliError::Parse {
+                    path: path.to_string(),
+                    message,
+                }),
+            ),
+        };
+
+        match res.map_err(|err| (err.invalid_document_message(), err)) {
+            Result::Err((Some(message), _)) if skip_invalid => {
+                extractor
+                    .record_parse_error(path, line, &message)
+                    .map_err(|err| CliError::from_extraction(path, err))?;
+                warnings.push(match line {
+                    0 => format!("{}: skipped invalid document: {}", path, message),
+                    line => format!("{}:{}: skipped invalid document: {}", path, line, message),
+                });
+
+                if !resumes_after_errors {
+                    break;
+                }
+            }
+
+            res => res.map_err(|(_, err)| err)?,
+        }
+    }
+
+    Result::Ok(warnings)
+}
+
+/// Extract facts from `document`, read from the input file with path `path`
+/// in input format `format`, and record its position in `rootMeta`.
+fn extract_document<B: DatalogExtractorBackend>(
+    extractor: &mut DatalogExtractor<B>,
+    path: &str,
+    format: &dyn InputFormat,
+    document: Document<'_>,
+    split_roots: bool,
+) -> Result<(), CliError> {
+    // documents from multi-document inputs are distinguished by position
+    let root = input_format::document_root(path, document.position);
+
+    let mut format_data = match document.value {
+        DocumentValue::Data(format_data) if split_roots => format_data,
+
+        value => {
+            return input_format::extract_document(extractor, &root, value)
+                .and_then(|()| {
+                    input_format::set_document_meta(extractor, &root, format, document.position)
+                })
+                .map_err(|err| CliError::from_extraction(&root, err));
+        }
+    };
+
     let mut deserializer = format_data.deserializer();
-    let path: String = match &filename_opt {
-        Some(file) => PathBuf::from_str(file)
-            .unwrap()
-            .canonicalize()
-            .unwrap()
-            .display()
-            .to_string(),
-        None => "stdin".to_string(),
-    };
-    extractor.set_file(&path).unwrap();
-    serde_transcode::transcode(deserializer.as_mut(), extractor).unwrap();
+    let mut error = None;
+    let res = SplitRoots {
+        extractor: &mut *extractor,
+        root: &root,
+        position: document
+            .position
+            .map(|position| (format.position_key(), position)),
+        format: format.name(),
+        error: &mut error,
+    }
+    .deserialize(deserializer.as_mut());
+
+    if let Some((item_root, err)) = error {
+        return Result::Err(CliError::from_extraction(&item_root, err));
+    }
+
+    res.map_err(|err| CliError::Parse {
+        path: root.clone(),
+        message: err.to_string(),
+    })
+}
+
+/// Number of extracted elements between updates of the progress bar.
+const PROGRESS_INTERVAL: usize = 10_000;
+
+/// Create a progress bar for reading `inputs`, whose length is the total size
+/// of the input files, or a spinner if the size is unknown.
+fn progress_bar(inputs: &[Input]) -> ProgressBar {
+    let sizes: Option<u64> = inputs
+        .iter()
+        .map(|input| {
+            if let Some(download) = &input.download {
+                return Some(download.body.len() as u64);
+            }
+
+            // the size of streamed remote inputs is only known once they are opened
+            let filename = input
+                .filename
+                .as_ref()
+                .filter(|name| !input_source::is_url(name))?;
+            fs::metadata(filename).ok().map(|metadata| metadata.len())
+        })
+        .sum();
+
+    let (bar, template) = match sizes {
+        Some(total) => (
+            ProgressBar::new(total),
+            "[{elapsed_precise}] {bar:30} {bytes}/{total_bytes} {msg}",
+        ),
+        None => (
+            ProgressBar::new_spinner(),
+            "[{elapsed_precise}] {spinner} {bytes} {msg}",
+        ),
+    };
+
+    if let Ok(style) = ProgressStyle::with_template(template) {
+        bar.set_style(style);
+    }
+    bar
+}
+
+/// An input opened for reading by [open_input].
+struct OpenInput {
+    /// Reader of the decompressed contents of the input.
+    reader: Box<dyn Read>,
+
+    /// The size of the contents, if it is known.
+    size_hint: Option<u64>,
+
+    /// Metadata of inputs read from remote sources.
+    meta: Option<RemoteMeta>,
+}
+
+/// Open `input` for reading, decompressing its contents if necessary.
+/// Bytes read from the input, before decompression, advance `progress`.
+fn open_input(
+    input: &Input,
+    compression_opt: Option<Compression>,
+    progress: Option<&ProgressBar>,
+) -> Result<OpenInput, CliError> {
+    let path = input.filename.as_deref().unwrap_or("stdin");
+    let io_error = |source| CliError::Io {
+        path: path.to_string(),
+        source,
+    };
+
+    let mut meta = None;
+    let (reader, size_hint, compression): (Box<dyn Read>, _, _) =
+        match (&input.filename, &input.download) {
+            (Some(url), Some(download)) => {
+                let body = download.body.clone();
+                let compression = compression_opt.unwrap_or_else(|| {
+                    Compression::from_path(Path::new(input_source::url_path(url))).0
+                });
+                let size_hint = match compression {
+                    Compression::None => Some(body.len() as u64),
+                    _ => None,
+                };
+
+                meta = download.meta.clone();
+                (Box::new(io::Cursor::new(body)), size_hint, compression)
+            }
+
+            // inputs of remote sources whose format is known are streamed
+            (Some(url), None) if input_source::is_url(url) => {
+                let object = input_source::open(url)?;
+                let compression = compression_opt.unwrap_or_else(|| {
+                    Compression::from_path(Path::new(input_source::url_path(url))).0
+                });
+                let size_hint = match compression {
+                    Compression::None => object.size,
+                    _ => None,
+                };
+
+                meta = Some(object.meta);
+                (object.reader, size_hint, compression)
+            }
+
+            (Some(filename), None) => {
+                let path = Path::new(filename);
+                let compression = compression_opt.unwrap_or_else(|| Compression::from_path(path).0);
+                let file = fs::File::open(path).map_err(io_error)?;
+
+                // the size of compressed files is not the size of their contents
+                let size_hint = match compression {
+                    Compression::None => file.metadata().ok().map(|metadata| metadata.len()),
+                    _ => None,
+                };
+
+                (Box::new(file), size_hint, compression)
+            }
+
+            (None, _) => (
+                Box::new(io::stdin()),
+                None,
+                compression_opt.unwrap_or(Compression::None),
+            ),
+        };
+
+    let reader: Box<dyn Read> = match progress {
+        Some(bar) => Box::new(bar.wrap_read(reader)),
+        None => reader,
+    };
+    let reader = compression.decompress(reader).map_err(io_error)?;
+    Result::Ok(OpenInput {
+        reader,
+        size_hint,
+        meta,
+    })
+}
+
+impl From<UnitMode> for UnitPolicy {
+    fn from(mode: UnitMode) -> Self {
+        match mode {
+            UnitMode::Elements => UnitPolicy::Elements,
+            UnitMode::Facts => UnitPolicy::Facts,
+            UnitMode::Singletons => UnitPolicy::Singletons,
+        }
+    }
+}
+
+impl From<RedactMode> for RedactionMode {
+    fn from(mode: RedactMode) -> Self {
+        match mode {
+            RedactMode::Hash => RedactionMode::Hash,
+            RedactMode::Mask => RedactionMode::Mask,
+        }
+    }
+}
+
+impl From<WideNumbersArg> for WideNumbers {
+    fn from(wide: WideNumbersArg) -> Self {
+        match wide {
+            WideNumbersArg::Split => WideNumbers::Split,
+            WideNumbersArg::Symbol => WideNumbers::Symbol,
+            WideNumbersArg::Error => WideNumbers::Error,
+        }
+    }
+}
+
+/// How facts are written to an output database.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum WriteMode {
+    /// Overwrite an existing database.
+    Overwrite,
+
+    /// Append facts to an existing database.
+    Append,
+
+    /// Append facts to an existing database, replacing facts previously
+    /// extracted from the same input files.
+    Replace,
+}
+
+/// An input to extract facts from, along with its resolved input format.
+/// Inputs without a file name are read from standard input. Inputs of
+/// remote sources are read from their downloaded contents if they were
+/// fetched to find their format, and are streamed otherwise. Entries of
+/// archives are read from their contents, extracted in memory.
+struct Input<'a> {
+    filename: Option<String>,
+    format: InputFormatChoice<'a>,
+    download: Option<Download>,
+
+    /// Metadata of the input's root recorded in `rootMeta`, in addition to
+    /// the metadata of inputs read from remote sources.
+    root_meta: Vec<(&'static str, String)>,
+}
+
+impl Input<'_> {
+    /// The name of the input's root in the `file` relation: the canonical
+    /// path of input files, and the name of other inputs as is.
+    fn root_name(&self) -> Result<String, CliError> {
+        match &self.filename {
+            Some(name) if self.download.is_some() || input_source::is_url(name) => {
+                Result::Ok(name.to_string())
+            }
+
+            Some(file) => canonical_path(file),
+            None => Result::Ok("stdin".to_string()),
+        }
+    }
+
+    /// Can the facts of the input be cached? Inputs are cached by their
+    /// contents, so only input files and inputs read into memory are, while
+    /// standard input and streamed inputs are extracted as they are read.
+    #[cfg(feature = "cache")]
+    fn is_cacheable(&self) -> bool {
+        match (&self.filename, &self.download) {
+            (Some(_), Some(_)) => true,
+            (Some(filename), None) => !input_source::is_url(filename),
+            (None, _) => false,
+        }
+    }
+
+    /// Are all of the map keys in the input strings? The format of inputs
+    /// detected as they are read is not known in advance, so they are assumed
+    /// to allow non-string keys.
+    fn has_string_keys(&self) -> bool {
+        match self.format {
+            InputFormatChoice::Known(format) => format.has_string_keys(),
+            InputFormatChoice::Detect(_) => false,
+        }
+    }
+}
+
+/// How the input format of an input is determined.
+#[derive(Clone, Copy)]
+enum InputFormatChoice<'a> {
+    /// The input has a known format.
+    Known(&'a dyn InputFormat),
+
+    /// The format is detected from the contents of the input as it is read,
+    /// for standard input, which cannot be read more than once.
+    Detect(&'a FormatRegistry),
+}
+
+/// Detect the input format of `input` from the first bytes read from `reader`,
+/// returning the format and a reader of the entire input.
+fn detect_format<'a, 'r>(
+    formats: &'a FormatRegistry,
+    input: &Input,
+    mut reader: Box<dyn Read + 'r>,
+) -> Result<(&'a dyn InputFormat, Box<dyn Read + 'r>), CliError> {
+    let path = input.filename.as_deref().unwrap_or("stdin");
+
+    let mut prefix = Vec::new();
+    Read::by_ref(&mut reader)
+        .take(DETECT_PREFIX_LEN)
+        .read_to_end(&mut prefix)
+        .map_err(|source| CliError::Io {
+            path: path.to_string(),
+            source,
+        })?;
+
+    let format = formats.detect(&prefix).ok_or_else(|| {
+        CliError::Usage(format!(
+            "Cannot detect the input format of {}; use --format to specify it",
+            path
+        ))
+    })?;
+
+    Result::Ok((format, Box::new(io::Cursor::new(prefix).chain(reader))))
+}
+
+/// Extract facts from `input` into `extractor`, recording the metadata of its
+/// root. Bytes read from the input advance `progress`. Returns a warning for
+/// each document recorded as a parse error.
+fn extract_input<B: DatalogExtractorBackend>(
+    extractor: &mut DatalogExtractor<B>,
+    input: &Input,
+    compression_opt: Option<Compression>,
+    progress: Option<&ProgressBar>,
+    args: &InputArgs,
+) -> Result<Vec<String>, CliError> {
+    let OpenInput {
+        reader,
+        size_hint,
+        meta,
+    } = open_input(input, compression_opt, progress)?;
+    let (format, reader) = match input.format {
+        InputFormatChoice::Known(format) => (format, reader),
+        InputFormatChoice::Detect(formats) => detect_format(formats, input, reader)?,
+    };
+
+    let root = input.root_name()?;
+    set_root_meta(extractor, input, &root, meta.as_ref())?;
+
+    process_file(
+        extractor,
+        format,
+        &root,
+        reader,
+        size_hint,
+        args.split_roots,
+        args.skip_invalid,
+    )
+}
+
+/// Extract facts from `input` into `extractor` like [extract_input], reusing
+/// the facts cached in `cache` for an input with the same name, input format,
+/// compression, and contents, extracted with the same `settings`, see
+/// [InputArgs::cache_settings]. The facts of other inputs are extracted by a
+/// separate extractor, cached, and replayed into `extractor`. Inputs whose
+/// facts were reused count as cached in `summary`.
+///
+/// The metadata of roots is recorded by `extractor` rather than cached, since
+/// inputs read from remote sources record when they were read.
+#[cfg(feature = "cache")]
+#[allow(clippy::too_many_arguments)]
+fn extract_cached<B: DatalogExtractorBackend>(
+    extractor: &mut DatalogExtractor<B>,
+    cache: &cache::ExtractionCache,
+    settings: &[u8],
+    input: &Input,
+    compression_opt: Option<Compression>,
+    progress: Option<&ProgressBar>,
+    args: &InputArgs,
+    summary: &mut Summary,
+) -> Result<Vec<String>, CliError> {
+    let (InputFormatChoice::Known(format), Some(filename)) = (input.format, &input.filename) else {
+        return extract_input(extractor, input, compression_opt, progress, args);
+    };
+
+    let contents: std::sync::Arc<[u8]> = match &input.download {
+        Some(download) => download.body.clone(),
+        None => fs::read(filename)
+            .map_err(|source| CliError::Io {
+                path: filename.to_string(),
+                source,
+            })?
+            .into(),
+    };
+
+    let root = input.root_name()?;
+    let meta = input
+        .download
+        .as_ref()
+        .and_then(|download| download.meta.as_ref());
+    set_root_meta(extractor, input, &root, meta)?;
+
+    let key = cache::ExtractionCache::key(&[
+        settings,
+        root.as_bytes(),
+        format.name().as_bytes(),
+        format!("{:?}", compression_opt).as_bytes(),
+        &contents,
+    ]);
+    let (calls, warnings) = match cache.get(&key) {
+        Some(calls) => {
+            if let Some(bar) = progress {
+                bar.inc(contents.len() as u64);
+            }
+            summary.files_cached += 1;
+            (calls, Vec::new())
+        }
+
+        None => {
+            let mut recorder = args
+                .extractor_config()?
+                .build(backend::record::Backend::default())
+                .map_err(|err| CliError::Usage(err.to_string()))?;
+            let OpenInput {
+                reader, size_hint, ..
+            } = open_input(input, compression_opt, progress)?;
+            let warnings = process_file(
+                &mut recorder,
+                format,
+                &root,
+                reader,
+                size_hint,
+                args.split_roots,
+                args.skip_invalid,
+            )?;
+
+            let calls = recorder.get_backend().into_calls();
+            cache.put(&key, &calls)?;
+            (calls, warnings)
+        }
+    };
+
+    extractor
+        .replay(&calls)
+        .map_err(|err| CliError::from_extraction(&root, err))?;
+    Result::Ok(warnings)
+}
+
+/// Record the metadata of the root of `input`, named `root`, in `rootMeta`:
+/// the metadata `input` was resolved with, and where and when inputs read
+/// from remote sources were read, from their `meta`.
+fn set_root_meta<B: DatalogExtractorBackend>(
+    extractor: &mut DatalogExtractor<B>,
+    input: &Input,
+    root: &str,
+    meta: Option<&RemoteMeta>,
+) -> Result<(), CliError> {
+    let mut root_meta: Vec<(&str, &str)> = input
+        .root_meta
+        .iter()
+        .map(|(key, value)| (*key, value.as_str()))
+        .collect();
+
+    if let Some(meta) = meta {
+        root_meta.push(("uri", root));
+        root_meta.push(("fetchedAt", &meta.fetched_at));
+        if let Some(content_type) = &meta.content_type {
+            root_meta.push(("contentType", content_type));
+        }
+    }
+
+    for (key, value) in root_meta {
+        extractor
+            .set_root_meta(root, key, value)
+            .map_err(|err| CliError::from_extraction(root, err))?;
+    }
+
     Result::Ok(())
 }
 
+/// Extract facts from `inputs` and store them in a SQLite database `output`,
+/// or print them to standard output if `output` is absent.
+///
+/// If `--keep-going` is set, inputs that cannot be processed are reported and
+/// skipped; facts extracted from such an input before its error occurred
+/// remain in the output.
+///
+/// If `mode` is not [WriteMode::Overwrite] and `output` already exists,
+/// facts are appended to it.
+///
+/// If `--split-roots` is set, the items of top-level sequences in inputs are
+/// extracted as separate root elements.
+///
+/// If `seed` is given, extraction continues the symbol table and element
+/// numbering of that database, as when appending to it.
 fn process_files<B: backend::souffle_sqlite::AbstractBackend>(
-    backend: B,
-    format: &Box<dyn InputFormat>,
-    filenames: &Vec<String>,
+    mut backend: B,
+    inputs: &[Input],
+    compression_opt: Option<Compression>,
     output: &Option<String>,
-) {
-    let mut extractor: DatalogExtractor<B> = DatalogExtractor::new(backend);
-    if filenames.len() > 0 {
-        for filename in filenames.iter() {
-            let path = Path::new(filename);
-            let buf = fs::read_to_string(path).unwrap();
-            process_file(&mut extractor, format, Some(filename.to_string()), buf).unwrap();
+    args: &InputArgs,
+    mode: WriteMode,
+    seed: Option<&str>,
+) -> Result<Summary, CliError> {
+    if args.souffle_directives && output.is_none() {
+        return Result::Err(CliError::Usage(
+            "--souffle-directives requires an output database".to_string(),
+        ));
+    }
+
+    let append_file: Option<&str> = output
+        .as_deref()
+        .filter(|output_file| mode != WriteMode::Overwrite && Path::new(output_file).is_file());
+
+    let config = args.extractor_config()?;
+    let config = match append_file.or(seed) {
+        Some(seed_file) => {
+            let first_elem_id = backend
+                .load_db(seed_file)
+                .map_err(|err| CliError::from_extraction(seed_file, err))?;
+            config.first_elem_id(first_elem_id)
         }
-    } else {
-        let mut buf = String::new();
-        io::stdin().read_to_string(&mut buf).unwrap();
 
-        process_file(&mut extractor, format, None, buf).unwrap();
+        None => config,
+    };
+    let progress = args.progress.then(|| progress_bar(inputs));
+    let config = match &progress {
+        Some(bar) => {
+            let bar = bar.clone();
+            config.progress(PROGRESS_INTERVAL, move |progress| {
+                bar.set_message(format!(
+                    "{} elements {}",
+                    progress.elements,
+                    progress.file.unwrap_or_default()
+                ));
+                ControlFlow::Continue(())
+            })
+        }
+        None => config,
+    };
+    let mut extractor: DatalogExtractor<B> = config
+        .build(backend)
+        .map_err(|err| CliError::Usage(err.to_string()))?;
+    let mut summary = Summary::default();
+
+    #[cfg(feature = "cache")]
+    let cache = match &args.cache {
+        Some(dir) => Some((cache::ExtractionCache::open(dir)?, args.cache_settings()?)),
+        None => None,
     };
 
-    if let Some(output_file) = output {
-        let outpath = Path::new(&output_file);
-        if outpath.is_file() {
-            fs::remove_file(output_file).unwrap();
+    for input in inputs.iter() {
+        let path = input.filename.as_deref().unwrap_or("stdin");
+
+        // with --keep-going, the facts of an input are buffered until it has
+        // been extracted, so that a failing input leaves no partial facts
+        if args.keep_going {
+            extractor
+                .begin_checkpoint()
+                .map_err(|err| CliError::from_extraction(path, err))?;
         }
+
+        #[cfg(feature = "cache")]
+        let res = match &cache {
+            Some((cache, settings)) if input.is_cacheable() => extract_cached(
+                &mut extractor,
+                cache,
+                settings,
+                input,
+                compression_opt,
+                progress.as_ref(),
+                args,
+                &mut summary,
+            ),
+            _ => extract_input(
+                &mut extractor,
+                input,
+                compression_opt,
+                progress.as_ref(),
+                args,
+            ),
+        };
+
+        #[cfg(not(feature = "cache"))]
+        let res = extract_input(
+            &mut extractor,
+            input,
+            compression_opt,
+            progress.as_ref(),
+            args,
+        );
+
+        let res = match res {
+            Result::Ok(warnings) => extractor
+                .commit_checkpoint()
+                .map(|()| warnings)
+                .map_err(|err| CliError::from_extraction(path, err)),
+
+            Result::Err(err) => {
+                extractor.discard_checkpoint();
+                Result::Err(err)
+            }
+        };
+
+        match res {
+            Result::Ok(warnings) => {
+                summary.files_processed += 1;
+                summary.warnings.extend(warnings);
+            }
+
+            Result::Err(err) if args.keep_going => {
+                if !args.json {
+                    match &progress {
+                        Some(bar) => bar.suspend(|| eprintln!("error: {}", err)),
+                        None => eprintln!("error: {}", err),
+                    }
+                }
+                summary.warnings.push(err.to_string());
+                summary.files_failed += 1;
+            }
+
+            Result::Err(err) => return Result::Err(err),
+        }
+    }
+
+    if let Some(bar) = progress {
+        bar.finish_and_clear();
     }
 
+    summary.facts_emitted = extractor.facts();
     let souffle_sqlite = extractor.get_backend();
+    if let Some(top_n) = args.string_report {
+        eprint!("{}", souffle_sqlite.string_report(top_n));
+    }
+
+    if let (true, Some(output_file)) = (args.souffle_directives, output) {
+        let dbname = Path::new(output_file)
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_else(|| output_file.clone());
+        let directives_file = Path::new(output_file).with_extension("dl");
+        fs::write(&directives_file, souffle_sqlite.input_directives(&dbname)).map_err(
+            |source| CliError::Io {
+                path: directives_file.to_string_lossy().to_string(),
+                source,
+            },
+        )?;
+        summary
+            .outputs
+            .push(directives_file.to_string_lossy().to_string());
+    }
+
     match output {
+        Some(output_file) if append_file.is_some() => souffle_sqlite
+            .append_to_db(output_file, mode == WriteMode::Replace)
+            .map_err(|err| CliError::from_extraction(output_file, err))?,
+
         Some(output_file) => {
-            souffle_sqlite.dump_to_db(output_file).unwrap();
+            let outpath = Path::new(&output_file);
+            if outpath.is_file() {
+                fs::remove_file(outpath).map_err(|source| CliError::Io {
+                    path: output_file.to_string(),
+                    source,
+                })?;
+            }
+
+            souffle_sqlite
+                .dump_to_db(output_file)
+                .map_err(|err| CliError::from_extraction(output_file, err))?;
         }
 
         None => souffle_sqlite.dump(),
     }
+
+    if let Some(output_file) = output {
+        summary.outputs.push(output_file.clone());
+    }
+
+    Result::Ok(summary)
 }
 
-fn main() {
-    let args = Args::parse();
-    let formats: Vec<Box<dyn InputFormat>> = get_input_formats();
+/// Resolve the input format of the input at `url` of a remote source: the
+/// format named on the command line, if any; otherwise the format of the
+/// extension of the URL's path. Inputs of known format are streamed when
+/// they are extracted. Otherwise the input is fetched, and has the format
+/// of its `Content-Type`, or, if `detect` is set, the format detected from
+/// its contents.
+fn resolve_url<'a>(
+    formats: &'a FormatRegistry,
+    explicit_format: Option<&'a dyn InputFormat>,
+    url: &str,
+    detect: bool,
+    compression_opt: Option<Compression>,
+) -> Result<Input<'a>, CliError> {
+    let mut input = Input {
+        filename: Some(url.to_string()),
+        format: InputFormatChoice::Detect(formats),
+        download: None,
+        root_meta: Vec::new(),
+    };
 
-    if args.list_formats {
-        print_formats(&formats);
-        return;
+    let format = explicit_format
+        .or_else(|| find_format_by_path(formats, Path::new(input_source::url_path(url))));
+    if let Some(format) = format {
+        input.format = InputFormatChoice::Known(format);
+        return Result::Ok(input);
     }
 
-    // assume that all input files are the same format
-    let format_res: Result<&Box<dyn InputFormat>, String> = match args.format {
-        Some(name) => formats.iter().find(|fmt| fmt.name() == &name).map_or(
-            Result::Err(format!("Unknown input format {}", &name)),
-            |fmt| Result::Ok(fmt),
-        ),
+    let download = input_source::fetch(url)?;
+    let content_type = download
+        .meta
+        .as_ref()
+        .and_then(|meta| meta.content_type.clone());
+    input.download = Some(download);
+
+    input.format = match content_type
+        .as_deref()
+        .and_then(|content_type| formats.find_by_media_type(content_type))
+    {
+        Some(format) => InputFormatChoice::Known(format),
+
+        None if detect => {
+            let reader = open_input(&input, compression_opt, None)?.reader;
+            InputFormatChoice::Known(detect_format(formats, &input, reader)?.0)
+        }
 
         None => {
-            let exts: Vec<Option<String>> = args
-                .filenames
-                .iter()
-                .map(|filename| {
-                    Path::new(filename)
-                        .extension()
-                        .and_then(|ext| ext.to_str())
-                        .map(|s| s.to_string())
+            return Result::Err(CliError::Usage(format!(
+                "Unknown input format of {} ({}); use --format or --detect",
+                url,
+                content_type.as_deref().unwrap_or("no Content-Type")
+            )))
+        }
+    };
+
+    Result::Ok(input)
+}
+
+/// Resolve the input format of each input file. If `format_name` is given,
+/// all inputs have that format; otherwise the format of each file is guessed
+/// from its extension. If `detect` is set, the format of files with an unknown
+/// extension, and of standard input, is detected from their contents.
+fn resolve_inputs<'a>(
+    formats: &'a FormatRegistry,
+    format_name: &Option<String>,
+    filenames: &[String],
+    detect: bool,
+    compression_opt: Option<Compression>,
+) -> Result<Vec<Input<'a>>, CliError> {
+    let explicit_format: Option<&dyn InputFormat> = match format_name {
+        Some(name) => Some(
+            formats
+                .get(name)
+                .ok_or_else(|| CliError::Usage(format!("Unknown input format {}", name)))?,
+        ),
+
+        None => None,
+    };
+
+    if filenames.is_empty() {
+        let format = match explicit_format {
+            Some(format) => InputFormatChoice::Known(format),
+
+            None if detect => InputFormatChoice::Detect(formats),
+
+            None => {
+                return Result::Err(CliError::Usage(
+                    "Input format must be specified with --format or detected with --detect when reading from standard input"
+                        .to_string(),
+                ))
+            }
+        };
+
+        return Result::Ok(vec![Input {
+            filename: None,
+            format,
+            download: None,
+            root_meta: Vec::new(),
+        }]);
+    }
+
+    let mut inputs = Vec::new();
+    for filename in filenames.iter() {
+        if input_source::is_url(filename) {
+            inputs.push(resolve_url(
+                formats,
+                explicit_format,
+                filename,
+                detect,
+                compression_opt,
+            )?);
+        } else if let Some(archive) = Archive::from_path(Path::new(filename)) {
+            inputs.extend(resolve_archive(
+                formats,
+                explicit_format,
+                filename,
+                archive,
+                detect,
+                compression_opt,
+            )?);
+        } else {
+            inputs.push(resolve_file(
+                formats,
+                explicit_format,
+                filename,
+                detect,
+                compression_opt,
+            )?);
+        }
+    }
+
+    Result::Ok(inputs)
+}
+
+/// Resolve the input format of input file `filename`: the format named on
+/// the command line, if any; otherwise the format of its extension, or, if
+/// `detect` is set, the format detected from its contents.
+fn resolve_file<'a>(
+    formats: &'a FormatRegistry,
+    explicit_format: Option<&'a dyn InputFormat>,
+    filename: &str,
+    detect: bool,
+    compression_opt: Option<Compression>,
+) -> Result<Input<'a>, CliError> {
+    let format = match explicit_format {
+        Some(format) => format,
+
+        None => match find_format_by_path(formats, Path::new(filename)) {
+            Some(format) => format,
+
+            // files can be read more than once, so their format is
+            // detected before extraction
+            None if detect => {
+                let input = Input {
+                    filename: Some(filename.to_string()),
+                    format: InputFormatChoice::Detect(formats),
+                    download: None,
+                    root_meta: Vec::new(),
+                };
+                let reader = open_input(&input, compression_opt, None)?.reader;
+                detect_format(formats, &input, reader)?.0
+            }
+
+            None => {
+                return Result::Err(CliError::Usage(format!(
+                    "Unknown or missing file extension for {}; use --format or --detect",
+                    filename
+                )))
+            }
+        },
+    };
+
+    Result::Ok(Input {
+        filename: Some(filename.to_string()),
+        format: InputFormatChoice::Known(format),
+        download: None,
+        root_meta: Vec::new(),
+    })
+}
+
+/// Resolve the inputs of the entries of archive `filename`, which are
+/// extracted in memory: every entry if an input format is named on the
+/// command line, and otherwise the entries whose extension has a known
+/// format, or, if `detect` is set, whose format is detected from their
+/// contents. Entries are named `ARCHIVE!/ENTRY` after the canonical path
+/// of the archive, and record both in `rootMeta`.
+fn resolve_archive<'a>(
+    formats: &'a FormatRegistry,
+    explicit_format: Option<&'a dyn InputFormat>,
+    filename: &str,
+    archive: Archive,
+    detect: bool,
+    compression_opt: Option<Compression>,
+) -> Result<Vec<Input<'a>>, CliError> {
+    let archive_path = canonical_path(filename)?;
+    let entries = archive.entries(Path::new(filename), |name| {
+        explicit_format.is_some()
+            || detect
+            || find_format_by_path(formats, Path::new(name)).is_some()
+    })?;
+
+    let mut inputs = Vec::new();
+    for entry in entries {
+        let mut input = Input {
+            filename: Some(format!("{}!/{}", archive_path, entry.name)),
+            format: InputFormatChoice::Detect(formats),
+            download: Some(Download {
+                body: entry.body.into(),
+                meta: None,
+            }),
+            root_meta: vec![("archive", archive_path.clone())],
+        };
+
+        let format =
+            explicit_format.or_else(|| find_format_by_path(formats, Path::new(&entry.name)));
+        input.format = match format {
+            Some(format) => InputFormatChoice::Known(format),
+
+            None => {
+                let reader = open_input(&input, compression_opt, None)?.reader;
+                InputFormatChoice::Known(detect_format(formats, &input, reader)?.0)
+            }
+        };
+
+        input.root_meta.push(("entry", entry.name));
+        inputs.push(input);
+    }
+
+    Result::Ok(inputs)
+}
+
+/// The canonical path of input file `file`.
+fn canonical_path(file: &str) -> Result<String, CliError> {
+    let path = Path::new(file)
+        .canonicalize()
+        .map_err(|source| CliError::Io {
+            path: file.to_string(),
+            source,
+        })?;
+
+    Result::Ok(path.display().to_string())
+}
+
+/// Extract facts from the inputs described by `args`, and store them in
+/// a SQLite database `output` or print them to standard output.
+fn extract(
+    formats: &FormatRegistry,
+    args: &InputArgs,
+    output: &Option<String>,
+    mode: WriteMode,
+    split: Option<SplitMode>,
+) -> Result<(), CliError> {
+    if args.json && output.is_none() {
+        return Result::Err(CliError::Usage(
+            "--json requires an output database".to_string(),
+        ));
+    }
+
+    let compression_opt: Option<Compression> = match &args.compression {
+        Some(name) => Some(
+            Compression::from_name(name)
+                .ok_or_else(|| CliError::Usage(format!("Unknown compression format {}", name)))?,
+        ),
+
+        None => None,
+    };
+
+    let input_files = input_source::expand(&args.filenames, args.recursive, |path| {
+        find_format_by_path(formats, path).is_some() || Archive::from_path(path).is_some()
+    })?;
+
+    if !input_files.skipped.is_empty() && !args.json {
+        eprintln!(
+            "Skipped {} file(s) with unknown input format:",
+            input_files.skipped.len()
+        );
+        for file in input_files.skipped.iter() {
+            eprintln!("- {}", file);
+        }
+    }
+
+    if !args.filenames.is_empty() && input_files.files.is_empty() {
+        return Result::Err(CliError::Usage("No input files found".to_string()));
+    }
+
+    let inputs = resolve_inputs(
+        formats,
+        &args.format,
+        &input_files.files,
+        args.detect,
+        compression_opt,
+    )?;
+
+    let has_string_keys = inputs.iter().all(Input::has_string_keys);
+
+    // when appending, facts must conform to the schema of the existing database
+    let use_string_keys = match output {
+        Some(output_file) if mode != WriteMode::Overwrite && Path::new(output_file).is_file() => {
+            let db_has_string_keys = rusqlite::Connection::open(output_file)
+                .map_err(DatalogExtractionError::from)
+                .and_then(|conn| {
+                    backend::souffle_sqlite::db_has_string_keys(
+                        &conn,
+                        &backend::souffle_sqlite::SchemaConfig::default(),
+                    )
                 })
-                .collect();
-
-            let mut ext_opt: Result<String, String> =
-                Result::Err("Unknown or missing file extension".to_string());
-
-            for file_ext_opt in exts {
-                match file_ext_opt {
-                    Some(file_ext) => {
-                        if let Result::Ok(ext) = &ext_opt {
-                            if ext != &file_ext {
-                                let err: String =
-                                    format!("Input format must be unique; found extensions {} and {} for different formats",
-                                        ext, &file_ext);
-
-                                ext_opt = Result::Err(err);
-                                break;
-                            }
-                        } else {
-                            ext_opt = Result::Ok(file_ext);
-                        }
-                    }
+                .map_err(|err| CliError::from_extraction(output_file, err))?;
 
-                    None => {
-                        ext_opt = Result::Err("Missing file extension".to_string());
-                        break;
-                    }
+            if db_has_string_keys && !has_string_keys {
+                return Result::Err(CliError::Usage(format!(
+                    "Cannot append to {}: it assumes map keys are strings, but some input formats allow non-string keys",
+                    output_file
+                )));
+            }
+
+            match args.backend {
+                Some(BackendArg::StringKey) if !db_has_string_keys => {
+                    return Result::Err(CliError::Usage(format!(
+                        "Cannot append to {} with --backend string-key: it has the general schema",
+                        output_file
+                    )))
+                }
+
+                Some(BackendArg::General) if db_has_string_keys => {
+                    return Result::Err(CliError::Usage(format!(
+                        "Cannot append to {} with --backend general: it assumes map keys are strings",
+                        output_file
+                    )))
                 }
+
+                _ => db_has_string_keys,
+            }
+        }
+
+        _ => match args.backend {
+            Some(BackendArg::StringKey) if !has_string_keys => {
+                return Result::Err(CliError::Usage(
+                    "Cannot use --backend string-key: some input formats allow non-string keys"
+                        .to_string(),
+                ))
+            }
+
+            Some(BackendArg::StringKey) => true,
+
+            Some(BackendArg::General) => false,
+
+            // the simpler string key schema can only be used if
+            // every input format has string keys
+            Some(BackendArg::Auto) | None => has_string_keys,
+        },
+    };
+
+    let bytes_policy = args.bytes_policy()?;
+    let command_line: Vec<String> = std::env::args().collect();
+    let command_line = command_line.join(" ");
+
+    let mut summary = Summary::default();
+    summary.warnings.extend(
+        input_files
+            .skipped
+            .iter()
+            .map(|file| format!("{}: skipped file with unknown input format", file)),
+    );
+
+    // a database has a single schema, so mixing formats with and without
+    // string keys puts the facts of every input in the general schema
+    if !use_string_keys && inputs.iter().any(Input::has_string_keys) {
+        for input in inputs.iter().filter(|input| !input.has_string_keys()) {
+            let warning = format!(
+                "{}: input format allows non-string map keys, so facts of all inputs conform to the general schema",
+                input.filename.as_deref().unwrap_or("stdin")
+            );
+            if !args.json {
+                eprintln!("warning: {}", warning);
+            }
+            summary.warnings.push(warning);
+        }
+    }
+
+    let res = if use_string_keys {
+        let make_backend = || {
+            let mut backend = backend::souffle_sqlite::StringKeyBackend::default();
+            if args.separate_namespaces {
+                backend = backend.with_separate_namespaces();
+            }
+            if let Some(policy) = bytes_policy {
+                backend = backend.with_bytes_policy(policy);
+            }
+            backend = backend.with_load_options(args.load_options());
+            if args.provenance {
+                backend = backend.with_metadata("commandLine", &command_line);
+            }
+            backend
+        };
+
+        write_output(
+            make_backend,
+            &inputs,
+            compression_opt,
+            output,
+            args,
+            mode,
+            split,
+        )
+    } else {
+        let make_backend = || {
+            let mut backend = backend::souffle_sqlite::Backend::default();
+            if args.separate_namespaces {
+                backend = backend.with_separate_namespaces();
+            }
+            if let Some(policy) = bytes_policy {
+                backend = backend.with_bytes_policy(policy);
+            }
+            backend = backend.with_load_options(args.load_options());
+            if args.provenance {
+                backend = backend.with_metadata("commandLine", &command_line);
             }
+            backend
+        };
+
+        write_output(
+            make_backend,
+            &inputs,
+            compression_opt,
+            output,
+            args,
+            mode,
+            split,
+        )
+    };
+    summary.merge(res?);
+
+    if args.json {
+        println!("{}", serde_json::to_string(&summary).unwrap());
+    }
 
-            ext_opt.map_or_else(
-                |err| Result::Err(err),
-                |ext| {
-                    formats
-                        .iter()
-                        .find(|fmt| fmt.file_extensions().contains(&ext.as_str()))
-                        .map_or(
-                            Result::Err(format!("Unknown file extension {}", &ext)),
-                            |fmt| Result::Ok(fmt),
-                        )
-                },
+    if summary.files_failed > 0 {
+        Result::Err(CliError::PartialFailure {
+            failed: summary.files_failed,
+            total: inputs.len(),
+        })
+    } else {
+        Result::Ok(())
+    }
+}
+
+/// Extract facts from `inputs` with backends created by `make_backend`, and
+/// write them to `output` as described by `split` (see [process_files]).
+/// When splitting, `output` is a directory, which is created if it does not
+/// exist.
+fn write_output<B: backend::souffle_sqlite::AbstractBackend>(
+    make_backend: impl Fn() -> B,
+    inputs: &[Input],
+    compression_opt: Option<Compression>,
+    output: &Option<String>,
+    args: &InputArgs,
+    mode: WriteMode,
+    split: Option<SplitMode>,
+) -> Result<Summary, CliError> {
+    let dir = match (split, output) {
+        (Some(_), Some(dir)) => Path::new(dir),
+        _ => {
+            return process_files(
+                make_backend(),
+                inputs,
+                compression_opt,
+                output,
+                args,
+                mode,
+                None,
             )
         }
     };
 
-    match format_res {
-        Err(err) => {
-            println!("{}", err);
-        }
-
-        Ok(format) => {
-            if format.has_string_keys() {
-                process_files(
-                    backend::souffle_sqlite::StringKeyBackend::default(),
-                    format,
-                    &args.filenames,
-                    &args.output,
-                );
-            } else {
-                process_files(
-                    backend::souffle_sqlite::Backend::default(),
-                    format,
-                    &args.filenames,
-                    &args.output,
-                );
+    let io_error = |path: &Path, source| CliError::Io {
+        path: path.to_string_lossy().to_string(),
+        source,
+    };
+    fs::create_dir_all(dir).map_err(|source| io_error(dir, source))?;
+
+    match split {
+        Some(SplitMode::File) => {
+            let mut used_names = HashSet::new();
+            let mut prev_file: Option<String> = None;
+            let mut summary = Summary::default();
+
+            for (i, input) in inputs.iter().enumerate() {
+                let name = input
+                    .filename
+                    .as_deref()
+                    .and_then(|filename| Path::new(input_source::url_path(filename)).file_name())
+                    .map(|name| name.to_string_lossy().to_string())
+                    .unwrap_or_else(|| "stdin".to_string());
+
+                // input files in different directories can share a name
+                let name = if used_names.insert(name.clone()) {
+                    name
+                } else {
+                    format!("{}-{}", i, name)
+                };
+                let split_file = dir
+                    .join(format!("{}.db", name))
+                    .to_string_lossy()
+                    .to_string();
+
+                summary.merge(process_files(
+                    make_backend(),
+                    std::slice::from_ref(input),
+                    compression_opt,
+                    &Some(split_file.clone()),
+                    args,
+                    WriteMode::Overwrite,
+                    prev_file.as_deref(),
+                )?);
+
+                prev_file = Some(split_file);
+            }
+
+            Result::Ok(summary)
+        }
+
+        Some(SplitMode::Relation) => {
+            let all_file = dir.join(".all.db");
+            let all_filename = all_file.to_string_lossy().to_string();
+            let res = process_files(
+                make_backend(),
+                inputs,
+                compression_opt,
+                &Some(all_filename.clone()),
+                args,
+                WriteMode::Overwrite,
+                None,
+            );
+
+            // facts are split even if some inputs failed with --keep-going
+            let res = match res {
+                Result::Ok(mut summary) => backend::souffle_sqlite::split_db_by_relation(
+                    &all_filename,
+                    &backend::souffle_sqlite::SchemaConfig::default(),
+                    dir,
+                )
+                .map(|files| {
+                    summary.outputs = files;
+                    summary
+                })
+                .map_err(|err| CliError::from_extraction(&all_filename, err)),
+
+                Result::Err(err) => Result::Err(err),
+            };
+
+            if all_file.is_file() {
+                fs::remove_file(&all_file).map_err(|source| io_error(&all_file, source))?;
             }
+            res
+        }
+
+        None => unreachable!(),
+    }
+}
+
+/// Merge the databases given by `args`.
+fn run_merge(args: &MergeArgs) -> Result<(), CliError> {
+    backend::souffle_sqlite::merge_dbs(&args.output, &args.inputs)
+        .map_err(|err| CliError::from_extraction(&args.output, err))
+}
+
+/// Check the database given by `args` and print the violations found.
+fn run_validate(args: &ValidateArgs) -> Result<(), CliError> {
+    let conn =
+        rusqlite::Connection::open_with_flags(&args.db, rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY)
+            .map_err(|source| CliError::Database {
+                path: args.db.clone(),
+                source: source.into(),
+            })?;
+
+    #[cfg(feature = "string_compression")]
+    backend::souffle_sqlite::register_functions(&conn)
+        .map_err(|err| CliError::from_extraction(&args.db, err))?;
+
+    let schema = backend::souffle_sqlite::SchemaConfig::default();
+    let report = validate::validate(&conn, &schema)
+        .map_err(|err| CliError::from_extraction(&args.db, err))?;
+
+    print!("{}", report);
+    if report.is_valid() {
+        Result::Ok(())
+    } else {
+        Result::Err(CliError::Invalid {
+            path: args.db.clone(),
+            violations: report.violations.len(),
+        })
+    }
+}
+
+/// Print the facts that differ between the databases given by `args`.
+fn run_diff(args: &DiffArgs) -> Result<(), CliError> {
+    let open = |path: &String| -> Result<rusqlite::Connection, CliError> {
+        let conn =
+            rusqlite::Connection::open_with_flags(path, rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY)
+                .map_err(|source| CliError::Database {
+                    path: path.clone(),
+                    source: source.into(),
+                })?;
+
+        #[cfg(feature = "string_compression")]
+        backend::souffle_sqlite::register_functions(&conn)
+            .map_err(|err| CliError::from_extraction(path, err))?;
+
+        Result::Ok(conn)
+    };
+
+    let before = open(&args.before)?;
+    let after = open(&args.after)?;
+    let mode = if args.structural {
+        diff::MatchMode::Structural
+    } else {
+        diff::MatchMode::Path
+    };
+
+    let schema = backend::souffle_sqlite::SchemaConfig::default();
+    let result = diff::diff(&before, &after, &schema, mode)
+        .map_err(|err| CliError::from_extraction(&args.after, err))?;
+
+    print!("{}", result);
+    Result::Ok(())
+}
+
+/// Print the number of facts of each relation of the database given by
+/// `args`.
+fn run_stats(args: &StatsArgs) -> Result<(), CliError> {
+    let conn =
+        rusqlite::Connection::open_with_flags(&args.db, rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY)
+            .map_err(|source| CliError::Database {
+                path: args.db.clone(),
+                source: source.into(),
+            })?;
+
+    let schema = backend::souffle_sqlite::SchemaConfig::default();
+    let counts = backend::souffle_sqlite::relation_fact_counts(&conn, &schema)
+        .map_err(|err| CliError::from_extraction(&args.db, err))?;
+
+    let width = counts
+        .iter()
+        .map(|(relation, _)| relation.len())
+        .max()
+        .unwrap_or_default()
+        .max("total".len());
+    for (relation, facts) in counts.iter() {
+        println!("{:width$}  {}", relation, facts);
+    }
+    println!(
+        "{:width$}  {}",
+        "total",
+        counts.iter().map(|(_, facts)| facts).sum::<usize>()
+    );
+
+    Result::Ok(())
+}
+
+/// Derive relations from the database given by `args` and write them into it,
+/// printing the number of facts of each derived relation.
+#[cfg(feature = "analyze")]
+fn run_analyze(args: &AnalyzeArgs) -> Result<(), CliError> {
+    let mut conn = rusqlite::Connection::open(&args.db).map_err(|source| CliError::Database {
+        path: args.db.clone(),
+        source: source.into(),
+    })?;
+
+    let schema = backend::souffle_sqlite::SchemaConfig::default();
+    let analysis = serde_datalog::analyze::analyze(&conn, &schema)
+        .and_then(|analysis| analysis.write_to(&mut conn, &schema).map(|_| analysis))
+        .map_err(|err| CliError::from_extraction(&args.db, err))?;
+
+    println!("reachable    {}", analysis.reachable.len());
+    println!("depth        {}", analysis.depth.len());
+    println!("subtreeSize  {}", analysis.subtree_size.len());
+
+    Result::Ok(())
+}
+
+/// Print the schema inferred from the database given by `args`.
+fn run_schema(args: &SchemaArgs) -> Result<(), CliError> {
+    let conn =
+        rusqlite::Connection::open_with_flags(&args.db, rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY)
+            .map_err(|source| CliError::Database {
+                path: args.db.clone(),
+                source: source.into(),
+            })?;
+
+    #[cfg(feature = "string_compression")]
+    backend::souffle_sqlite::register_functions(&conn)
+        .map_err(|err| CliError::from_extraction(&args.db, err))?;
+
+    let schema = backend::souffle_sqlite::SchemaConfig::default();
+    let inferred = infer::infer_schema(&conn, &schema)
+        .map_err(|err| CliError::from_extraction(&args.db, err))?;
+
+    match args.format {
+        SchemaFormat::Json => print!("{}", inferred.to_json()),
+        SchemaFormat::Souffle => print!("{}", inferred.to_souffle()),
+    }
+    Result::Ok(())
+}
+
+fn run_repl(formats: &FormatRegistry, args: &ReplArgs) -> Result<(), CliError> {
+    // without an explicit database, facts extracted from input files are
+    // stored in a fresh temporary directory that is removed when the REPL
+    // exits
+    let mut temp_dir = None;
+    let db = match (&args.db, args.input.filenames.is_empty()) {
+        (Some(db), true) => db.clone(),
+
+        (None, true) => {
+            return Result::Err(CliError::Usage(
+                "Either a database or input files must be given".to_string(),
+            ))
+        }
+
+        (db_opt, false) => {
+            let db = match db_opt {
+                Some(db) => db.clone(),
+                None => {
+                    let dir = create_temp_dir()?;
+                    let db = dir.path().join("facts.db").display().to_string();
+                    temp_dir = Some(dir);
+                    db
+                }
+            };
+            extract(
+                formats,
+                &args.input,
+                &Some(db.clone()),
+                WriteMode::Overwrite,
+                None,
+            )?;
+            db
+        }
+    };
+
+    let conn = rusqlite::Connection::open(&db).map_err(|source| CliError::Database {
+        path: db.clone(),
+        source: source.into(),
+    })?;
+
+    #[cfg(feature = "string_compression")]
+    backend::souffle_sqlite::register_functions(&conn)
+        .map_err(|err| CliError::from_extraction(&db, err))?;
+    let res = repl::run(&conn).map_err(|source| CliError::Io {
+        path: "stdin".to_string(),
+        source,
+    });
+
+    drop(conn);
+    drop(temp_dir);
+    res
+}
+
+/// Create a fresh, private temporary directory, which is removed with its
+/// contents when it is dropped.
+fn create_temp_dir() -> Result<tempfile::TempDir, CliError> {
+    tempfile::Builder::new()
+        .prefix("serde_datalog_")
+        .tempdir()
+        .map_err(|source| CliError::Io {
+            path: std::env::temp_dir().display().to_string(),
+            source,
+        })
+}
+
+/// Extract facts from the inputs given by `args` and run a Souffle program on
+/// them, collecting its output relations into a database if one is given.
+/// Files the facts and output relations are written to when `args` does not
+/// name them are kept in a temporary directory that is removed afterwards.
+fn run_program(formats: &FormatRegistry, args: &RunArgs) -> Result<(), CliError> {
+    let work_dir = create_temp_dir()?;
+    run_program_in(formats, args, work_dir.path())
+}
+
+/// Run the steps of [run_program], keeping intermediate files in `work_dir`.
+fn run_program_in(
+    formats: &FormatRegistry,
+    args: &RunArgs,
+    work_dir: &Path,
+) -> Result<(), CliError> {
+    let facts = args
+        .facts
+        .clone()
+        .unwrap_or_else(|| work_dir.join("facts.db").display().to_string());
+    // the directives are written to the work directory rather than next to
+    // the facts, where they could replace the program
+    let mut input = args.input.clone();
+    input.souffle_directives = false;
+    extract(
+        formats,
+        &input,
+        &Some(facts.clone()),
+        WriteMode::Overwrite,
+        None,
+    )?;
+
+    let facts_path = Path::new(&facts);
+    let schema = backend::souffle_sqlite::SchemaConfig::default();
+    let conn = rusqlite::Connection::open_with_flags(
+        facts_path,
+        rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY,
+    )
+    .map_err(|source| CliError::Database {
+        path: facts.clone(),
+        source: source.into(),
+    })?;
+    let string_keys = backend::souffle_sqlite::db_has_string_keys(&conn, &schema)
+        .map_err(|err| CliError::from_extraction(&facts, err))?;
+    drop(conn);
+
+    let dbname = facts_path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_else(|| facts.clone());
+    let directives = work_dir.join("facts.dl");
+    fs::write(
+        &directives,
+        backend::souffle_sqlite::input_directives(&schema, string_keys, &dbname),
+    )
+    .map_err(|source| CliError::Io {
+        path: directives.display().to_string(),
+        source,
+    })?;
+
+    // output relations collected into a database are written to the work
+    // directory unless they are kept
+    let output_dir = match (&args.output_dir, &args.output) {
+        (Some(dir), _) => {
+            fs::create_dir_all(dir).map_err(|source| CliError::Io {
+                path: dir.to_string(),
+                source,
+            })?;
+            Some(PathBuf::from(dir))
+        }
+        (None, Some(_)) => {
+            let dir = work_dir.join("output");
+            fs::create_dir_all(&dir).map_err(|source| CliError::Io {
+                path: dir.display().to_string(),
+                source,
+            })?;
+            Some(dir)
+        }
+        (None, None) => None,
+    };
+
+    let fact_dir = match facts_path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    souffle::Run {
+        souffle: &args.souffle,
+        program: Path::new(&args.program),
+        directives: &directives,
+        fact_dir,
+        output_dir: output_dir.as_deref(),
+        jobs: args.jobs,
+    }
+    .run(work_dir)?;
+
+    if let (Some(output), Some(output_dir)) = (&args.output, &output_dir) {
+        let read = |path: &Path| {
+            fs::read_to_string(path).map_err(|source| CliError::Io {
+                path: path.display().to_string(),
+                source,
+            })
+        };
+        let program = read(&directives)? + &read(Path::new(&args.program))?;
+        let relations = souffle::declarations(&program);
+        let collected = souffle::collect_outputs(output_dir, &relations, output)?;
+        if !args.input.json {
+            eprintln!(
+                "Collected {} output relation(s) into {}",
+                collected.len(),
+                output
+            );
+        }
+    }
+
+    Result::Ok(())
+}
+
+/// Extract facts from the inputs given by `args`, or list the input formats.
+/// Options not given in `args` are filled in from the configuration file.
+fn run_extract(args: &mut ExtractArgs) -> Result<(), CliError> {
+    let config = args.input.apply_config_file()?;
+    let formats = get_input_formats(&args.input)?;
+    if args.list_formats {
+        print_formats(&formats);
+        return Result::Ok(());
+    }
+
+    if args.output.is_none() {
+        args.output = config.output_for(&args.input.filenames)?;
+
+        // output templates can place databases in directories of their own
+        if let Some(dir) = args.output.as_ref().and_then(|output| {
+            Path::new(output)
+                .parent()
+                .filter(|dir| !dir.as_os_str().is_empty())
+        }) {
+            fs::create_dir_all(dir).map_err(|source| CliError::Io {
+                path: dir.to_string_lossy().to_string(),
+                source,
+            })?;
+        }
+    }
+
+    let mode = if args.replace {
+        WriteMode::Replace
+    } else if args.append {
+        WriteMode::Append
+    } else {
+        WriteMode::Overwrite
+    };
+
+    extract(&formats, &args.input, &args.output, mode, args.split)
+}
+
+/// Completion scripts of each shell, generated by the build script.
+const COMPLETIONS: [(clap_complete::Shell, &str); 5] = [
+    (
+        clap_complete::Shell::Bash,
+        include_str!(concat!(env!("OUT_DIR"), "/completions.bash")),
+    ),
+    (
+        clap_complete::Shell::Elvish,
+        include_str!(concat!(env!("OUT_DIR"), "/completions.elvish")),
+    ),
+    (
+        clap_complete::Shell::Fish,
+        include_str!(concat!(env!("OUT_DIR"), "/completions.fish")),
+    ),
+    (
+        clap_complete::Shell::PowerShell,
+        include_str!(concat!(env!("OUT_DIR"), "/completions.powershell")),
+    ),
+    (
+        clap_complete::Shell::Zsh,
+        include_str!(concat!(env!("OUT_DIR"), "/completions.zsh")),
+    ),
+];
+
+/// The man page of serde_datalog in roff format, generated by the build
+/// script.
+const MAN_PAGE: &str = include_str!(concat!(env!("OUT_DIR"), "/serde_datalog.1"));
+
+fn print_completions(shell: clap_complete::Shell) -> Result<(), CliError> {
+    match COMPLETIONS.iter().find(|(other, _)| *other == shell) {
+        Some((_, script)) => {
+            print!("{}", script);
+            Result::Ok(())
+        }
+
+        None => Result::Err(CliError::Usage(format!(
+            "no completion script was generated for {}",
+            shell
+        ))),
+    }
+}
+
+fn main() -> ExitCode {
+    let mut args = Args::parse();
+
+    let res = match &mut args.command {
+        Some(Command::Query(query_args)) => run_query(query_args),
+
+        Some(Command::Run(run_args)) => run_args
+            .input
+            .apply_config_file()
+            .and_then(|_| get_input_formats(&run_args.input))
+            .and_then(|formats| run_program(&formats, run_args)),
+
+        Some(Command::Merge(merge_args)) => run_merge(merge_args),
+
+        Some(Command::Validate(validate_args)) => run_validate(validate_args),
+
+        Some(Command::Diff(diff_args)) => run_diff(diff_args),
+
+        Some(Command::Schema(schema_args)) => run_schema(schema_args),
+
+        Some(Command::Stats(stats_args)) => run_stats(stats_args),
+
+        #[cfg(feature = "analyze")]
+        Some(Command::Analyze(analyze_args)) => run_analyze(analyze_args),
+
+        #[cfg(feature = "cargo")]
+        Some(Command::Cargo(cargo_args)) => run_cargo(cargo_args),
+
+        Some(Command::Repl(repl_args)) => repl_args
+            .input
+            .apply_config_file()
+            .and_then(|_| get_input_formats(&repl_args.input))
+            .and_then(|formats| run_repl(&formats, repl_args)),
+
+        Some(Command::Completions(completions_args)) => print_completions(completions_args.shell),
+
+        Some(Command::Man) => {
+            print!("{}", MAN_PAGE);
+            Result::Ok(())
+        }
+
+        Some(Command::Extract(extract_args)) => run_extract(extract_args),
+
+        None => run_extract(&mut args.extract),
+    };
+
+    let json = match &args.command {
+        Some(Command::Extract(extract_args)) => extract_args.input.json,
+        Some(Command::Run(run_args)) => run_args.input.json,
+        Some(_) => false,
+        None => args.extract.input.json,
+    };
+
+    match res {
+        Ok(()) => ExitCode::SUCCESS,
+
+        Err(err) if json => {
+            eprintln!("{}", err.to_json());
+            err.exit_code()
+        }
+
+        Err(err) => {
+            eprintln!("error: {}", err);
+            err.exit_code()
         }
     }
 }
@@ -0,0 +1,77 @@
+//! Progress reporting and cancellation for long-running extractions.
+//!
+//! [DatalogExtractor::with_progress][crate::DatalogExtractor::with_progress]
+//! calls a callback with the [Progress] of the extraction whenever a number
+//! of elements have been extracted, and whenever an input file starts. The
+//! callback can cancel extraction cooperatively by returning
+//! [ControlFlow::Break], after which the extractor fails with
+//! [Cancelled][crate::DatalogExtractionError::Cancelled]:
+//!
+//! ```ignore
+//! let extractor = DatalogExtractor::new(backend).with_progress(10_000, move |progress| {
+//!     eprintln!("{} elements extracted", progress.elements);
+//!     if stop_requested.load(Ordering::Relaxed) {
+//!         ControlFlow::Break(())
+//!     } else {
+//!         ControlFlow::Continue(())
+//!     }
+//! });
+//! ```
+//!
+//! Extraction can also be bounded by a deadline with
+//! [with_deadline][crate::DatalogExtractor::with_deadline], or cancelled from
+//! another thread with a [CancellationToken] given to
+//! [with_cancellation][crate::DatalogExtractor::with_cancellation]. Both are
+//! checked every [CANCELLATION_CHECK_INTERVAL] extracted elements, so that
+//! services embedding the extractor can bound its latency on hostile inputs.
+
+use std::{
+    ops::ControlFlow,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
+
+/// Number of extracted elements between checks of deadlines and
+/// cancellation tokens.
+pub const CANCELLATION_CHECK_INTERVAL: usize = 1024;
+
+/// Progress of an extraction.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Progress<'a> {
+    /// Number of elements extracted so far.
+    pub elements: usize,
+
+    /// Input file being extracted, as last given to
+    /// [set_file][crate::DatalogExtractor::set_file].
+    pub file: Option<&'a str>,
+}
+
+/// Callback receiving the progress of an extraction, which continues
+/// extraction with [ControlFlow::Continue] and cancels it with
+/// [ControlFlow::Break].
+pub type ProgressCallback = Box<dyn FnMut(&Progress) -> ControlFlow<()>>;
+
+/// Token that cancels the extractions it is given to. Clones of a token share
+/// its state, so a token can be cancelled from another thread.
+#[derive(Clone, Debug, Default)]
+pub struct CancellationToken {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Cancel the extractions of this token.
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    /// Whether the token has been cancelled.
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+}
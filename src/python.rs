@@ -0,0 +1,163 @@
+//! Python bindings with [PyO3](https://pyo3.rs), so that Python pipelines can
+//! extract dicts, lists, and other Python values into SQLite databases with
+//! the schema of the [Souffle SQLite backends][crate::backend::souffle_sqlite].
+//! Build the extension module with [maturin](https://www.maturin.rs):
+//!
+//! ```text
+//! maturin build --release -F python,pyo3/extension-module
+//! ```
+//!
+//! ```python
+//! import serde_datalog
+//!
+//! extractor = serde_datalog.Extractor()
+//! extractor.extract({"name": "abc", "tags": ["a", "b"]}, file="record.json")
+//! extractor.dump_to_sqlite("facts.db")
+//! ```
+//!
+//! Python values are extracted through serde like the values of input files:
+//! `None` is unit, dicts are maps, lists and tuples are sequences, and
+//! booleans, integers, floats, strings, and bytes are serialized as the
+//! corresponding serde primitives, so floats and bytes are unextractable as
+//! in input files. Values of other types cannot be extracted.
+
+use pyo3::{
+    exceptions::PyValueError,
+    prelude::*,
+    types::{PyBool, PyBytes, PyDict, PyFloat, PyInt, PyList, PyString, PyTuple},
+};
+use serde::{
+    ser::{self, SerializeMap, SerializeSeq},
+    Serialize,
+};
+
+use crate::{
+    backend::souffle_sqlite::{self, AbstractBackend},
+    DatalogExtractor,
+};
+
+/// A Python value serialized with serde.
+pub struct PyValue<'a, 'py>(pub &'a Bound<'py, PyAny>);
+
+impl Serialize for PyValue<'_, '_> {
+    fn serialize<S: ser::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let value = self.0;
+        let custom = |err: PyErr| ser::Error::custom(err.to_string());
+
+        if value.is_none() {
+            serializer.serialize_unit()
+        } else if let Ok(value) = value.cast::<PyBool>() {
+            // checked before integers, since booleans are integers in Python
+            serializer.serialize_bool(value.is_true())
+        } else if value.is_instance_of::<PyInt>() {
+            match value.extract::<i64>() {
+                Ok(value) => serializer.serialize_i64(value),
+                Err(_) => serializer.serialize_u64(value.extract::<u64>().map_err(custom)?),
+            }
+        } else if let Ok(value) = value.cast::<PyFloat>() {
+            serializer.serialize_f64(value.value())
+        } else if let Ok(value) = value.cast::<PyString>() {
+            serializer.serialize_str(&value.to_cow().map_err(custom)?)
+        } else if let Ok(value) = value.cast::<PyBytes>() {
+            serializer.serialize_bytes(value.as_bytes())
+        } else if let Ok(dict) = value.cast::<PyDict>() {
+            let mut map = serializer.serialize_map(Some(dict.len()))?;
+            for (key, value) in dict.iter() {
+                map.serialize_entry(&PyValue(&key), &PyValue(&value))?;
+            }
+            map.end()
+        } else if let Ok(list) = value.cast::<PyList>() {
+            let mut seq = serializer.serialize_seq(Some(list.len()))?;
+            for item in list.iter() {
+                seq.serialize_element(&PyValue(&item))?;
+            }
+            seq.end()
+        } else if let Ok(tuple) = value.cast::<PyTuple>() {
+            let mut seq = serializer.serialize_seq(Some(tuple.len()))?;
+            for item in tuple.iter() {
+                seq.serialize_element(&PyValue(&item))?;
+            }
+            seq.end()
+        } else {
+            let type_name = value
+                .get_type()
+                .name()
+                .map(|name| name.to_string())
+                .unwrap_or_default();
+            Err(ser::Error::custom(format!(
+                "cannot extract Python value of type {}",
+                type_name
+            )))
+        }
+    }
+}
+
+/// Extracts Python values and stores their facts in a Souffle SQLite
+/// database. Map keys are stored as strings, as in
+/// [souffle_sqlite::StringKeyBackend].
+#[pyclass(name = "Extractor", unsendable)]
+pub struct PyExtractor {
+    /// `None` once facts are stored.
+    extractor: Option<DatalogExtractor<souffle_sqlite::StringKeyBackend>>,
+}
+
+#[pymethods]
+impl PyExtractor {
+    #[new]
+    pub fn new() -> Self {
+        PyExtractor {
+            extractor: Some(DatalogExtractor::new(
+                souffle_sqlite::StringKeyBackend::default(),
+            )),
+        }
+    }
+
+    /// Extract facts from `obj`. If `file` is given, `obj` is recorded as the
+    /// root value of the file with that name. If extraction fails, facts
+    /// already extracted from `obj` are kept, as with
+    /// [DatalogExtractor::reset].
+    #[pyo3(signature = (obj, file = None))]
+    pub fn extract(&mut self, obj: &Bound<'_, PyAny>, file: Option<&str>) -> PyResult<()> {
+        let extractor = self.extractor.as_mut().ok_or_else(dumped)?;
+        let res = (|| {
+            if let Some(file) = file {
+                extractor.set_file(file)?;
+                extractor.set_file_format(file, "python")?;
+            }
+            PyValue(obj).serialize(&mut *extractor)?;
+            extractor.flush()
+        })();
+        if res.is_err() {
+            extractor.reset();
+        }
+        res.map_err(|err| PyValueError::new_err(err.to_string()))
+    }
+
+    /// Store the extracted facts in a SQLite file with name `filename`, which
+    /// must not already contain the tables of the schema. Facts can only be
+    /// stored once.
+    pub fn dump_to_sqlite(&mut self, filename: &str) -> PyResult<()> {
+        let extractor = self.extractor.take().ok_or_else(dumped)?;
+        extractor
+            .get_backend()
+            .dump_to_db(filename)
+            .map_err(|err| PyValueError::new_err(err.to_string()))
+    }
+}
+
+impl Default for PyExtractor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn dumped() -> PyErr {
+    PyValueError::new_err("facts of extractor were already stored")
+}
+
+/// The `serde_datalog` Python module.
+#[pymodule]
+fn serde_datalog(module: &Bound<'_, PyModule>) -> PyResult<()> {
+    module.add_class::<PyExtractor>()?;
+    PyResult::Ok(())
+}
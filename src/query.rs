@@ -0,0 +1,308 @@
+use rusqlite::{types::ValueRef, Connection};
+use std::collections::HashMap;
+
+/// Rows returned by a query, along with the names of their columns.
+pub struct QueryResult {
+    pub columns: Vec<String>,
+    pub rows: Vec<Vec<String>>,
+}
+
+impl QueryResult {
+    /// Print the rows of the query result to standard output.
+    pub fn dump(&self) {
+        let header: Vec<String> = self
+            .columns
+            .iter()
+            .map(|col| format!("{:<15}", col))
+            .collect();
+        let header = header.join(" | ");
+
+        println!("{}", header);
+        println!("{}", "-".repeat(header.len()));
+        for row in self.rows.iter() {
+            let row: Vec<String> = row.iter().map(|val| format!("{:<15}", val)).collect();
+            println!("{}", row.join(" | "));
+        }
+        println!();
+        println!("{} row(s)", self.rows.len());
+    }
+}
+
+/// Run a SQL query against a database of facts.
+pub fn run_sql(conn: &Connection, sql: &str) -> rusqlite::Result<QueryResult> {
+    let mut stmt = conn.prepare(sql)?;
+    let columns: Vec<String> = stmt.column_names().iter().map(|c| c.to_string()).collect();
+    let ncols = columns.len();
+
+    let rows = stmt
+        .query_map((), |row| {
+            (0..ncols)
+                .map(|i| {
+                    row.get_ref(i).map(|val| match val {
+                        ValueRef::Null => "NULL".to_string(),
+                        ValueRef::Integer(i) => i.to_string(),
+                        ValueRef::Real(f) => f.to_string(),
+                        ValueRef::Text(t) => String::from_utf8_lossy(t).to_string(),
+                        ValueRef::Blob(b) => format!("<{} bytes>", b.len()),
+                    })
+                })
+                .collect::<rusqlite::Result<Vec<String>>>()
+        })?
+        .collect::<rusqlite::Result<Vec<Vec<String>>>>()?;
+
+    rusqlite::Result::Ok(QueryResult { columns, rows })
+}
+
+/// A term in an atom of a Datalog rule.
+#[derive(Debug)]
+enum Term {
+    Var(String),
+    Wildcard,
+    Str(String),
+    Number(i64),
+}
+
+/// An atom in a Datalog rule, e.g. `map(root, "key", value)`.
+#[derive(Debug)]
+struct Atom {
+    relation: String,
+    args: Vec<Term>,
+}
+
+/// A Datalog rule with a single head atom and a conjunction of body atoms.
+#[derive(Debug)]
+struct Rule {
+    head: Atom,
+    body: Vec<Atom>,
+}
+
+struct RuleParser<'a> {
+    input: &'a str,
+    pos: usize,
+}
+
+impl<'a> RuleParser<'a> {
+    fn skip_whitespace(&mut self) {
+        let rest = &self.input[self.pos..];
+        self.pos += rest.len() - rest.trim_start().len();
+    }
+
+    fn peek(&mut self) -> Option<char> {
+        self.skip_whitespace();
+        self.input[self.pos..].chars().next()
+    }
+
+    fn expect(&mut self, token: &str) -> Result<(), String> {
+        self.skip_whitespace();
+        if self.input[self.pos..].starts_with(token) {
+            self.pos += token.len();
+            Result::Ok(())
+        } else {
+            Result::Err(format!("expected `{}` at position {}", token, self.pos))
+        }
+    }
+
+    fn ident(&mut self) -> Result<String, String> {
+        self.skip_whitespace();
+        let rest = &self.input[self.pos..];
+        let len = rest
+            .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+            .unwrap_or(rest.len());
+
+        if len == 0 {
+            Result::Err(format!("expected identifier at position {}", self.pos))
+        } else {
+            self.pos += len;
+            Result::Ok(rest[..len].to_string())
+        }
+    }
+
+    fn term(&mut self) -> Result<Term, String> {
+        match self.peek() {
+            Some('"') => {
+                self.pos += 1;
+                let rest = &self.input[self.pos..];
+                let len = rest
+                    .find('"')
+                    .ok_or_else(|| format!("unterminated string at position {}", self.pos))?;
+                self.pos += len + 1;
+                Result::Ok(Term::Str(rest[..len].to_string()))
+            }
+
+            Some(c) if c.is_ascii_digit() || c == '-' => {
+                let start = self.pos;
+                self.pos += 1;
+                let rest = &self.input[self.pos..];
+                self.pos += rest
+                    .find(|c: char| !c.is_ascii_digit())
+                    .unwrap_or(rest.len());
+                self.input[start..self.pos]
+                    .parse::<i64>()
+                    .map(Term::Number)
+                    .map_err(|_| format!("invalid number at position {}", start))
+            }
+
+            _ => {
+                let name = self.ident()?;
+                if name == "_" {
+                    Result::Ok(Term::Wildcard)
+                } else {
+                    Result::Ok(Term::Var(name))
+                }
+            }
+        }
+    }
+
+    fn atom(&mut self) -> Result<Atom, String> {
+        let relation = self.ident()?;
+        self.expect("(")?;
+
+        let mut args = Vec::new();
+        if self.peek() != Some(')') {
+            loop {
+                args.push(self.term()?);
+                if self.peek() == Some(',') {
+                    self.pos += 1;
+                } else {
+                    break;
+                }
+            }
+        }
+
+        self.expect(")")?;
+        Result::Ok(Atom { relation, args })
+    }
+
+    fn rule(&mut self) -> Result<Rule, String> {
+        let head = self.atom()?;
+        self.expect(":-")?;
+
+        let mut body = vec![self.atom()?];
+        while self.peek() == Some(',') {
+            self.pos += 1;
+            body.push(self.atom()?);
+        }
+
+        if self.peek() == Some('.') {
+            self.pos += 1;
+        }
+
+        if self.peek().is_some() {
+            return Result::Err(format!("unexpected input at position {}", self.pos));
+        }
+
+        Result::Ok(Rule { head, body })
+    }
+}
+
+fn sql_literal(term: &Term) -> String {
+    match term {
+        Term::Str(s) => format!("'{}'", s.replace('\'', "''")),
+        Term::Number(n) => n.to_string(),
+        Term::Var(_) | Term::Wildcard => unreachable!(),
+    }
+}
+
+/// Compile a Datalog rule into an equivalent SQL query over the relations
+/// of a database of facts. The rule must have the form
+/// `head(x, ...) :- rel1(...), rel2(...), ...` where the arguments of body
+/// atoms are variables, wildcards (`_`), strings, or numbers, and every
+/// variable in the head appears in the body. Column names of relations are
+/// looked up from `conn`.
+pub fn compile_rule(conn: &Connection, rule_str: &str) -> Result<String, String> {
+    let rule = RuleParser {
+        input: rule_str,
+        pos: 0,
+    }
+    .rule()?;
+
+    let mut tables: Vec<String> = Vec::new();
+    let mut conditions: Vec<String> = Vec::new();
+    let mut bindings: HashMap<&str, String> = HashMap::new();
+
+    for (i, atom) in rule.body.iter().enumerate() {
+        let columns = relation_columns(conn, &atom.relation)?;
+        if columns.len() != atom.args.len() {
+            return Result::Err(format!(
+                "relation {} has {} column(s) but was given {} argument(s)",
+                atom.relation,
+                columns.len(),
+                atom.args.len()
+            ));
+        }
+
+        let alias = format!("t{}", i);
+        tables.push(format!("\"{}\" AS {}", atom.relation, alias));
+
+        for (arg, column) in atom.args.iter().zip(columns.iter()) {
+            let col = format!("{}.\"{}\"", alias, column);
+            match arg {
+                Term::Wildcard => {}
+
+                Term::Var(var) => match bindings.get(var.as_str()) {
+                    Some(bound) => conditions.push(format!("{} = {}", col, bound)),
+                    None => {
+                        bindings.insert(var, col);
+                    }
+                },
+
+                _ => conditions.push(format!("{} = {}", col, sql_literal(arg))),
+            }
+        }
+    }
+
+    let mut selected: Vec<String> = Vec::new();
+    for arg in rule.head.args.iter() {
+        match arg {
+            Term::Var(var) => match bindings.get(var.as_str()) {
+                Some(col) => selected.push(format!("{} AS \"{}\"", col, var)),
+                None => {
+                    return Result::Err(format!(
+                        "variable {} in the head of {} does not appear in the body",
+                        var, rule.head.relation
+                    ));
+                }
+            },
+
+            Term::Wildcard => {
+                return Result::Err("wildcards cannot appear in the head of a rule".to_string());
+            }
+
+            _ => selected.push(sql_literal(arg)),
+        }
+    }
+
+    if selected.is_empty() {
+        selected.push("1".to_string());
+    }
+
+    let mut sql = format!(
+        "SELECT DISTINCT {} FROM {}",
+        selected.join(", "),
+        tables.join(", ")
+    );
+
+    if !conditions.is_empty() {
+        sql.push_str(" WHERE ");
+        sql.push_str(&conditions.join(" AND "));
+    }
+
+    Result::Ok(sql)
+}
+
+fn relation_columns(conn: &Connection, relation: &str) -> Result<Vec<String>, String> {
+    let mut stmt = conn
+        .prepare("SELECT name FROM pragma_table_info(?1);")
+        .map_err(|err| err.to_string())?;
+
+    let columns = stmt
+        .query_map([relation], |row| row.get::<_, String>(0))
+        .and_then(|rows| rows.collect::<rusqlite::Result<Vec<String>>>())
+        .map_err(|err| err.to_string())?;
+
+    if columns.is_empty() {
+        Result::Err(format!("unknown relation {}", relation))
+    } else {
+        Result::Ok(columns)
+    }
+}
@@ -0,0 +1,92 @@
+//! Quotas on the number of facts extracted for each relation, so that
+//! extracting untrusted inputs cannot grow a database without bound.
+//!
+//! [DatalogExtractor::with_fact_quotas][crate::DatalogExtractor::with_fact_quotas]
+//! drops the facts of a relation once its quota is reached, and materializes
+//! that the relation was truncated with
+//! [add_truncated][crate::DatalogExtractorBackend::add_truncated]:
+//!
+//! ```ignore
+//! let quotas = FactQuotas::default().limit("string", 10_000).limit("map", 50_000);
+//! let extractor = DatalogExtractor::new(backend).with_fact_quotas(quotas);
+//! ```
+//!
+//! Quotas count the facts of all input files extracted by an extractor,
+//! except facts about map keys, which backends that store map keys as strings
+//! need to store map entries. Facts about elements are dropped without their
+//! elements, so queries over truncated relations should check the `truncated`
+//! relation before relying on them.
+
+use std::collections::HashMap;
+
+/// Relations of the Souffle schema whose facts can be limited by quotas.
+pub const RELATIONS: &[&str] = &[
+    "rootElem",
+    "fileFormat",
+    "keyField",
+    "type",
+    "bool",
+    "number",
+    "string",
+    "fullString",
+    "map",
+    "struct",
+    "key",
+    "seq",
+    "tuple",
+    "structType",
+    "variantType",
+    "schemaType",
+    "datetime",
+    "uuid",
+    "ip",
+    "url",
+    "email",
+    "bytes",
+    "declaredLen",
+    "absentField",
+    "unit",
+    "redacted",
+    "sampled",
+    "sameString",
+    "shape",
+    "shapeField",
+    "parseError",
+    "stringHash",
+    "rootMeta",
+    "depth",
+    "subtreeSize",
+    "char",
+];
+
+/// Maximum number of facts extracted for each relation, named as in the
+/// Souffle schema. Relations without a quota are not limited.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct FactQuotas {
+    quotas: HashMap<String, usize>,
+}
+
+impl FactQuotas {
+    /// Extract at most `max_facts` facts of the relation named `relation`,
+    /// replacing any quota previously given for it. Byte arrays count
+    /// towards the quota of `bytes` only.
+    pub fn limit(mut self, relation: &str, max_facts: usize) -> Self {
+        self.quotas.insert(relation.to_string(), max_facts);
+        self
+    }
+
+    /// The quota of the relation named `relation`, if any.
+    pub fn get(&self, relation: &str) -> Option<usize> {
+        self.quotas.get(relation).copied()
+    }
+
+    /// The relations that have a quota, in no particular order.
+    pub fn relations(&self) -> impl Iterator<Item = &str> {
+        self.quotas.keys().map(|relation| relation.as_str())
+    }
+
+    /// Whether no relation has a quota.
+    pub fn is_empty(&self) -> bool {
+        self.quotas.is_empty()
+    }
+}
@@ -0,0 +1,206 @@
+//! Recognition of well-known scalars, such as UUIDs and IP addresses, in
+//! string values, so that rules can join on their meaning instead of their
+//! spelling.
+//!
+//! An extractor given [Recognizers] with
+//! [with_recognizers][crate::DatalogExtractor::with_recognizers] checks every
+//! string value against the enabled recognizers and, for a string that is a
+//! scalar, generates a typed fact about the string element alongside its
+//! string fact:
+//!
+//! - UUIDs with [add_uuid][crate::DatalogExtractorBackend::add_uuid], as the
+//!   high and low 64 bits of the UUID,
+//! - IP addresses with [add_ip][crate::DatalogExtractorBackend::add_ip], in
+//!   their canonical text form, e.g. `::1` for `0:0:0:0:0:0:0:1`,
+//! - URLs with [add_url][crate::DatalogExtractorBackend::add_url], as their
+//!   scheme and host, both lowercased,
+//! - email addresses with [add_email][crate::DatalogExtractorBackend::add_email],
+//!   as their local part and lowercased domain.
+//!
+//! Recognition is syntactic: a string is a UUID if it is spelled like one,
+//! whether or not it was meant as one.
+
+use std::net::IpAddr;
+
+/// The kinds of scalars that are recognized in strings. Build recognizers by
+/// chaining methods on [Recognizers::default], which recognizes nothing, or
+/// start from [Recognizers::all]:
+///
+/// ```
+/// # use serde_datalog::recognize::{Recognizers, Scalar};
+/// let recognizers = Recognizers::default().uuid(true).email(true);
+///
+/// assert_eq!(
+///     recognizers.recognize("Ada@Example.com"),
+///     Some(Scalar::Email {
+///         local: "Ada".to_string(),
+///         domain: "example.com".to_string(),
+///     })
+/// );
+/// assert_eq!(recognizers.recognize("127.0.0.1"), None);
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Recognizers {
+    uuid: bool,
+    ip: bool,
+    url: bool,
+    email: bool,
+}
+
+/// A scalar recognized in a string.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Scalar {
+    Uuid { hi: u64, lo: u64 },
+    Ip(IpAddr),
+    Url { scheme: String, host: String },
+    Email { local: String, domain: String },
+}
+
+impl Recognizers {
+    /// Recognizers for every kind of scalar.
+    pub fn all() -> Self {
+        Recognizers {
+            uuid: true,
+            ip: true,
+            url: true,
+            email: true,
+        }
+    }
+
+    /// Recognize UUIDs in their hyphenated form, such as
+    /// `f81d4fae-7dec-11d0-a765-00a0c91e6bf6`, in either case.
+    pub fn uuid(mut self, enable: bool) -> Self {
+        self.uuid = enable;
+        self
+    }
+
+    /// Recognize IPv4 addresses in dotted decimal form and IPv6 addresses.
+    pub fn ip(mut self, enable: bool) -> Self {
+        self.ip = enable;
+        self
+    }
+
+    /// Recognize URLs with an authority, such as `https://example.com/a`.
+    pub fn url(mut self, enable: bool) -> Self {
+        self.url = enable;
+        self
+    }
+
+    /// Recognize email addresses, such as `ada@example.com`.
+    pub fn email(mut self, enable: bool) -> Self {
+        self.email = enable;
+        self
+    }
+
+    /// Whether no scalars are recognized.
+    pub fn is_empty(&self) -> bool {
+        *self == Recognizers::default()
+    }
+
+    /// The scalar that `text` is, if it is one of the recognized kinds.
+    pub fn recognize(&self, text: &str) -> Option<Scalar> {
+        if self.uuid {
+            if let Some(uuid) = parse_uuid(text) {
+                return Some(Scalar::Uuid {
+                    hi: (uuid >> 64) as u64,
+                    lo: uuid as u64,
+                });
+            }
+        }
+
+        if self.ip {
+            if let Ok(address) = text.parse() {
+                return Some(Scalar::Ip(address));
+            }
+        }
+
+        if self.url {
+            if let Some((scheme, host)) = parse_url(text) {
+                return Some(Scalar::Url { scheme, host });
+            }
+        }
+
+        if self.email {
+            if let Some((local, domain)) = parse_email(text) {
+                return Some(Scalar::Email { local, domain });
+            }
+        }
+
+        None
+    }
+}
+
+/// Parse a hyphenated UUID.
+fn parse_uuid(text: &str) -> Option<u128> {
+    let groups: Vec<&str> = text.split('-').collect();
+    let lengths: Vec<usize> = groups.iter().map(|group| group.len()).collect();
+    if lengths != [8, 4, 4, 4, 12] {
+        return None;
+    }
+
+    groups.iter().try_fold(0u128, |uuid, group| {
+        if !group.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return None;
+        }
+        let value = u64::from_str_radix(group, 16).ok()?;
+        Some((uuid << (group.len() * 4)) | value as u128)
+    })
+}
+
+/// Parse a URL of the form `scheme://authority...` into its lowercased
+/// scheme and host. The host of URLs such as `file:///tmp` is empty.
+fn parse_url(text: &str) -> Option<(String, String)> {
+    if text.contains(char::is_whitespace) {
+        return None;
+    }
+
+    let (scheme, rest) = text.split_once("://")?;
+    let mut scheme_chars = scheme.chars();
+    if !scheme_chars.next()?.is_ascii_alphabetic()
+        || !scheme_chars.all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'))
+    {
+        return None;
+    }
+
+    let authority = rest.split(['/', '?', '#']).next().unwrap_or_default();
+    let host_port = match authority.rsplit_once('@') {
+        Some((_, host_port)) => host_port,
+        None => authority,
+    };
+
+    let host = if host_port.starts_with('[') {
+        let end = host_port.find(']')?;
+        &host_port[..=end]
+    } else {
+        host_port.split(':').next().unwrap_or_default()
+    };
+
+    Some((scheme.to_ascii_lowercase(), host.to_ascii_lowercase()))
+}
+
+/// Parse an email address into its local part and lowercased domain.
+fn parse_email(text: &str) -> Option<(String, String)> {
+    let (local, domain) = text.split_once('@')?;
+
+    let local_ok = !local.is_empty()
+        && !local.starts_with('.')
+        && !local.ends_with('.')
+        && local
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "!#$%&'*+/=?^_`{|}~.-".contains(c));
+
+    let labels: Vec<&str> = domain.split('.').collect();
+    let domain_ok = labels.len() >= 2
+        && labels.iter().all(|label| {
+            !label.is_empty()
+                && !label.starts_with('-')
+                && !label.ends_with('-')
+                && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+        });
+
+    if local_ok && domain_ok {
+        Some((local.to_string(), domain.to_ascii_lowercase()))
+    } else {
+        None
+    }
+}
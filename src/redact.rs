@@ -0,0 +1,151 @@
+//! Redaction of sensitive values, so that facts extracted from personal data
+//! can be shared with analysts.
+//!
+//! [DatalogExtractor::with_redaction][crate::DatalogExtractor::with_redaction]
+//! redacts the string, char, and number values of struct fields and map
+//! entries whose names match a [Redaction]'s patterns, including the values
+//! nested in them. Redacted values are hashed or masked, and their elements
+//! are materialized with
+//! [add_redacted][crate::DatalogExtractorBackend::add_redacted]:
+//!
+//! ```ignore
+//! let redaction = Redaction::new(RedactionMode::Hash).field("ssn").field("email");
+//! let extractor = DatalogExtractor::new(backend).with_redaction(redaction);
+//! ```
+//!
+//! Hashes are stable, so that queries can still join redacted values that
+//! are equal. Values with few possible values, such as social security
+//! numbers, can be recovered from unsalted hashes by hashing every possible
+//! value, so hashes should be [salted][Redaction::salt] with a secret.
+//! Date-times and scalars recognized in redacted strings are not extracted.
+
+use crate::{string_policy::fnv1a, ElemId, Fact};
+
+/// How redacted values are replaced.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum RedactionMode {
+    /// Replace strings with the hexadecimal hash of their text, and numbers
+    /// with the hash of their decimal text, so that equal values have equal
+    /// hashes.
+    #[default]
+    Hash,
+
+    /// Replace strings with [MASK] and numbers with 0.
+    Mask,
+}
+
+/// Text of masked strings.
+pub const MASK: &str = "***";
+
+/// Patterns of the names of fields whose values are redacted, and how they
+/// are redacted.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Redaction {
+    patterns: Vec<String>,
+    mode: RedactionMode,
+    salt: String,
+}
+
+impl Redaction {
+    pub fn new(mode: RedactionMode) -> Self {
+        Redaction {
+            mode,
+            ..Default::default()
+        }
+    }
+
+    /// Redact the values of fields and map entries whose names contain
+    /// `pattern`, ignoring case, e.g. `email` for both `email` and
+    /// `backupEmail`.
+    pub fn field(mut self, pattern: &str) -> Self {
+        self.patterns.push(pattern.to_lowercase());
+        self
+    }
+
+    /// Prefix hashed values with `salt`, so that hashes cannot be matched
+    /// against the hashes of guessed values without knowing the salt.
+    pub fn salt(mut self, salt: &str) -> Self {
+        self.salt = salt.to_string();
+        self
+    }
+
+    /// Whether the values of the field or map entry named `name` are
+    /// redacted.
+    pub fn matches(&self, name: &str) -> bool {
+        let name = name.to_lowercase();
+        self.patterns
+            .iter()
+            .any(|pattern| name.contains(pattern.as_str()))
+    }
+
+    /// The redacted text of string `value`.
+    pub fn redact_str(&self, value: &str) -> String {
+        match self.mode {
+            RedactionMode::Hash => format!("{:016x}", self.hash(value)),
+            RedactionMode::Mask => MASK.to_string(),
+        }
+    }
+
+    /// The redacted value of a number whose decimal text is `value`.
+    pub fn redact_number(&self, value: &str) -> i64 {
+        match self.mode {
+            RedactionMode::Hash => self.hash(value) as i64,
+            RedactionMode::Mask => 0,
+        }
+    }
+
+    fn hash(&self, value: &str) -> u64 {
+        fnv1a(format!("{}{}", self.salt, value).as_bytes())
+    }
+
+    /// The facts that replace `fact`, a fact about a value nested in a
+    /// redacted field: facts about string, char, and number values are
+    /// redacted and followed by a redaction fact, full strings, byte arrays,
+    /// and recognized scalars are dropped, and other facts are kept.
+    pub(crate) fn redact_fact(&self, fact: Fact) -> Vec<Fact> {
+        let (elem, value) = match fact {
+            Fact::Str { elem, value } => (
+                elem,
+                Fact::Str {
+                    elem,
+                    value: self.redact_str(&value),
+                },
+            ),
+            Fact::Char { elem, value } => (
+                elem,
+                Fact::Str {
+                    elem,
+                    value: self.redact_str(&value.to_string()),
+                },
+            ),
+            Fact::I8 { elem, value } => (elem, self.redact_i64(elem, value)),
+            Fact::I16 { elem, value } => (elem, self.redact_i64(elem, value)),
+            Fact::I32 { elem, value } => (elem, self.redact_i64(elem, value)),
+            Fact::I64 { elem, value } => (elem, self.redact_i64(elem, value)),
+            Fact::U8 { elem, value } => (elem, self.redact_i64(elem, value)),
+            Fact::U16 { elem, value } => (elem, self.redact_i64(elem, value)),
+            Fact::U32 { elem, value } => (elem, self.redact_i64(elem, value)),
+            Fact::U64 { elem, value } => (elem, self.redact_i64(elem, value)),
+            Fact::F32 { elem, value } => (elem, self.redact_i64(elem, value)),
+            Fact::F64 { elem, value } => (elem, self.redact_i64(elem, value)),
+            Fact::FullStr { .. }
+            | Fact::Bytes { .. }
+            | Fact::Datetime { .. }
+            | Fact::Uuid { .. }
+            | Fact::Ip { .. }
+            | Fact::Url { .. }
+            | Fact::Email { .. }
+            | Fact::Codepoint { .. } => return vec![],
+            fact => return vec![fact],
+        };
+
+        vec![value, Fact::Redacted { elem }]
+    }
+
+    fn redact_i64(&self, elem: ElemId, value: impl ToString) -> Fact {
+        Fact::I64 {
+            elem,
+            value: self.redact_number(&value.to_string()),
+        }
+    }
+}
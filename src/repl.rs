@@ -0,0 +1,268 @@
+use rusqlite::{types::Value, Connection, OptionalExtension};
+use std::io::{self, BufRead, Write};
+
+use crate::query;
+
+const HELP: &str = "Commands:
+  relations          list relations and their number of facts
+  roots              list root elements of input files
+  elem <id>          show the subtree rooted at element <id>
+  symbol <id>        resolve symbol <id> to its string
+  symbol \"<str>\"     find the symbol id of string <str>
+  sql <query>        run a SQL query
+  datalog <rule>     run a Datalog rule, e.g. q(x) :- rootElem(_, x).
+  help               show this message
+  quit               exit the REPL";
+
+fn display_value(value: &Value) -> String {
+    match value {
+        Value::Null => "NULL".to_string(),
+        Value::Integer(i) => i.to_string(),
+        Value::Real(f) => f.to_string(),
+        Value::Text(t) => format!("{:?}", t),
+        Value::Blob(b) => format!("<{} bytes>", b.len()),
+    }
+}
+
+fn list_relations(conn: &Connection) -> rusqlite::Result<()> {
+    let mut stmt = conn.prepare(
+        "SELECT name FROM sqlite_master
+        WHERE type IN ('table', 'view') AND name NOT LIKE '\\_%' ESCAPE '\\'
+        AND name NOT LIKE 'sqlite_%'
+        ORDER BY name;",
+    )?;
+
+    let relations = stmt
+        .query_map((), |row| row.get::<_, String>(0))?
+        .collect::<rusqlite::Result<Vec<String>>>()?;
+
+    for relation in relations {
+        let count: i64 = conn.query_row(
+            &format!("SELECT COUNT(*) FROM \"{}\";", relation),
+            (),
+            |row| row.get(0),
+        )?;
+        println!("{:<15} | {:<15}", relation, count);
+    }
+
+    rusqlite::Result::Ok(())
+}
+
+fn resolve_symbol(conn: &Connection, arg: &str) -> rusqlite::Result<()> {
+    if let Some(text) = arg.strip_prefix('"').and_then(|a| a.strip_suffix('"')) {
+        let id: Option<i64> = conn
+            .query_row(
+                "SELECT id FROM __SymbolTable WHERE symbol = ?1;",
+                [text],
+                |row| row.get(0),
+            )
+            .optional()?;
+
+        match id {
+            Some(id) => println!("{}", id),
+            None => println!("no symbol for {:?}", text),
+        }
+    } else {
+        let symbol: Option<String> = conn
+            .query_row(
+                "SELECT symbol FROM __SymbolTable WHERE id = ?1;",
+                [arg],
+                |row| row.get(0),
+            )
+            .optional()?;
+
+        match symbol {
+            Some(symbol) => println!("{:?}", symbol),
+            None => println!("no symbol with id {}", arg),
+        }
+    }
+
+    rusqlite::Result::Ok(())
+}
+
+/// Describe an element by its type, its value (for scalar elements),
+/// and its type name (for structs and enum variants).
+fn describe_elem(conn: &Connection, id: i64) -> rusqlite::Result<Option<String>> {
+    let elem_type: Option<String> = conn
+        .query_row("SELECT type FROM type WHERE id = ?1;", [id], |row| {
+            row.get(0)
+        })
+        .optional()?;
+
+    let mut desc = match elem_type {
+        Some(elem_type) => elem_type,
+        None => return rusqlite::Result::Ok(None),
+    };
+
+    for relation in ["bool", "number", "string"] {
+        let value: Option<Value> = conn
+            .query_row(
+                &format!("SELECT value FROM \"{}\" WHERE id = ?1;", relation),
+                [id],
+                |row| row.get(0),
+            )
+            .optional()?;
+
+        if let Some(value) = value {
+            desc.push_str(&format!(" {}", display_value(&value)));
+        }
+    }
+
+    let struct_type: Option<String> = conn
+        .query_row("SELECT type FROM structType WHERE id = ?1;", [id], |row| {
+            row.get(0)
+        })
+        .optional()?;
+
+    if let Some(struct_type) = struct_type {
+        desc.push_str(&format!(" {}", struct_type));
+    }
+
+    let variant_type: Option<(String, String)> = conn
+        .query_row(
+            "SELECT type, variant FROM variantType WHERE id = ?1;",
+            [id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .optional()?;
+
+    if let Some((type_name, variant)) = variant_type {
+        desc.push_str(&format!(" {}::{}", type_name, variant));
+    }
+
+    rusqlite::Result::Ok(Some(desc))
+}
+
+fn show_elem(conn: &Connection, id: i64, label: &str, depth: usize) -> rusqlite::Result<()> {
+    let indent = "  ".repeat(depth);
+    let desc = match describe_elem(conn, id)? {
+        Some(desc) => desc,
+        None => {
+            println!("{}{}#{} (unknown element)", indent, label, id);
+            return rusqlite::Result::Ok(());
+        }
+    };
+
+    println!("{}{}#{} {}", indent, label, id, desc);
+
+    let children_queries = [
+        (
+            "SELECT field, value FROM struct WHERE id = ?1 ORDER BY field;",
+            ".",
+        ),
+        (
+            "SELECT key, value FROM map WHERE id = ?1 ORDER BY key;",
+            "key ",
+        ),
+        (
+            "SELECT pos, value FROM seq WHERE id = ?1 ORDER BY pos;",
+            "pos ",
+        ),
+        (
+            "SELECT pos, value FROM tuple WHERE id = ?1 ORDER BY pos;",
+            "pos ",
+        ),
+    ];
+
+    for (sql, prefix) in children_queries {
+        let mut stmt = conn.prepare(sql)?;
+        let children = stmt
+            .query_map([id], |row| {
+                Ok((row.get::<_, Value>(0)?, row.get::<_, i64>(1)?))
+            })?
+            .collect::<rusqlite::Result<Vec<(Value, i64)>>>()?;
+
+        for (key, child) in children {
+            let key = match key {
+                Value::Text(t) if prefix == "." => t,
+
+                // in the general schema, map keys are elements themselves
+                Value::Integer(key_id) if prefix == "key " => match describe_elem(conn, key_id)? {
+                    Some(key_desc) => format!("#{} {}", key_id, key_desc),
+                    None => key_id.to_string(),
+                },
+
+                key => display_value(&key),
+            };
+            show_elem(conn, child, &format!("{}{}: ", prefix, key), depth + 1)?;
+        }
+    }
+
+    rusqlite::Result::Ok(())
+}
+
+fn run_command(conn: &Connection, line: &str) -> Result<bool, String> {
+    let (command, arg) = match line.split_once(char::is_whitespace) {
+        Some((command, arg)) => (command, arg.trim()),
+        None => (line, ""),
+    };
+
+    match command {
+        "" => {}
+
+        "help" => println!("{}", HELP),
+
+        "quit" | "exit" => return Result::Ok(false),
+
+        "relations" => list_relations(conn).map_err(|err| err.to_string())?,
+
+        "roots" => query::run_sql(conn, "SELECT file, elem FROM rootElem ORDER BY file;")
+            .map_err(|err| err.to_string())?
+            .dump(),
+
+        "elem" => {
+            let id: i64 = arg
+                .parse()
+                .map_err(|_| format!("invalid element id {}", arg))?;
+            show_elem(conn, id, "", 0).map_err(|err| err.to_string())?;
+        }
+
+        "symbol" => resolve_symbol(conn, arg).map_err(|err| err.to_string())?,
+
+        "sql" => query::run_sql(conn, arg)
+            .map_err(|err| err.to_string())?
+            .dump(),
+
+        "datalog" => {
+            let sql = query::compile_rule(conn, arg)?;
+            query::run_sql(conn, &sql)
+                .map_err(|err| err.to_string())?
+                .dump();
+        }
+
+        _ => {
+            return Result::Err(format!(
+                "unknown command {}; type `help` for a list of commands",
+                command
+            ))
+        }
+    }
+
+    Result::Ok(true)
+}
+
+/// Run a read-eval-print loop for exploring the database of facts in `conn`.
+pub fn run(conn: &Connection) -> io::Result<()> {
+    println!("Type `help` for a list of commands.");
+
+    let stdin = io::stdin();
+    let mut lines = stdin.lock().lines();
+
+    loop {
+        print!("> ");
+        io::stdout().flush()?;
+
+        let line = match lines.next() {
+            Some(line) => line?,
+            None => break,
+        };
+
+        match run_command(conn, line.trim()) {
+            Ok(true) => {}
+            Ok(false) => break,
+            Err(err) => println!("error: {}", err),
+        }
+    }
+
+    io::Result::Ok(())
+}
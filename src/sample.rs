@@ -0,0 +1,967 @@
+//! Sampling of long sequences, so that rules can be prototyped on huge
+//! arrays without extracting every element.
+//!
+//! [DatalogExtractor::with_sampling][crate::DatalogExtractor::with_sampling]
+//! extracts a sample of `threshold` elements from each sequence with more
+//! than `threshold` elements. Sampled elements keep their positions, and the
+//! length of each sampled sequence is materialized with
+//! [add_sampled][crate::DatalogExtractorBackend::add_sampled], so queries can
+//! tell sampled sequences from short ones:
+//!
+//! ```ignore
+//! let extractor = DatalogExtractor::new(backend).with_sampling(Sampling::stratified(100));
+//! ```
+//!
+//! Sequences whose length is known in advance, such as Rust vectors, are
+//! sampled as their elements are extracted. Most input formats, such as JSON
+//! arrays read with deserializers, do not know the length of a sequence until
+//! it ends. The sampled elements of these sequences are held in memory and
+//! extracted when the sequence ends: random samples are kept in a reservoir
+//! of `threshold` elements, and stratified samples keep every `k`-th element,
+//! doubling `k` whenever more than `threshold` elements are held, so that
+//! between `threshold / 2` and `threshold` evenly spread elements remain.
+
+use crate::DatalogExtractionError;
+use serde::{ser, Serialize};
+
+/// How the elements of long sequences are sampled.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SampleMode {
+    /// Sample elements uniformly at random. The same seed samples the same
+    /// elements from the same sequence.
+    Random { seed: u64 },
+
+    /// Divide sequences into `threshold` strata of consecutive elements of
+    /// equal length, and sample the first element of each stratum, so that
+    /// samples are spread evenly across sequences.
+    Stratified,
+}
+
+/// Which elements of long sequences are extracted.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Sampling {
+    threshold: usize,
+    mode: SampleMode,
+}
+
+impl Sampling {
+    /// Sample `threshold` elements uniformly at random from sequences with
+    /// more than `threshold` elements, using a generator seeded with `seed`.
+    pub fn random(threshold: usize, seed: u64) -> Self {
+        Sampling {
+            threshold,
+            mode: SampleMode::Random { seed },
+        }
+    }
+
+    /// Sample `threshold` evenly spread elements from sequences with more
+    /// than `threshold` elements.
+    pub fn stratified(threshold: usize) -> Self {
+        Sampling {
+            threshold,
+            mode: SampleMode::Stratified,
+        }
+    }
+
+    pub fn threshold(&self) -> usize {
+        self.threshold
+    }
+
+    pub fn mode(&self) -> SampleMode {
+        self.mode
+    }
+
+    /// Sampler of a sequence with `len` elements, if known, that has
+    /// element identifier `id`. Returns `None` if every element is kept.
+    pub(crate) fn sampler(&self, id: usize, len: Option<usize>) -> Option<Sampler> {
+        if len.is_some_and(|len| len <= self.threshold) {
+            return None;
+        }
+
+        Some(Sampler {
+            threshold: self.threshold,
+            mode: self.mode,
+            len,
+            seen: 0,
+            kept: 0,
+            stride: 1,
+            held: Vec::new(),
+            state: match self.mode {
+                SampleMode::Random { seed } => seed ^ (id as u64).wrapping_mul(GOLDEN_GAMMA),
+                SampleMode::Stratified => 0,
+            },
+        })
+    }
+}
+
+const GOLDEN_GAMMA: u64 = 0x9e3779b97f4a7c15;
+
+/// Chooses the elements of a sequence that are extracted, in order if the
+/// length of the sequence is known, or at its end otherwise.
+pub(crate) struct Sampler {
+    threshold: usize,
+    mode: SampleMode,
+    len: Option<usize>,
+    seen: usize,
+    kept: usize,
+
+    /// Distance between the positions of stratified samples of a sequence of
+    /// unknown length.
+    stride: usize,
+
+    /// Positions and values of the sampled elements of a sequence of unknown
+    /// length.
+    held: Vec<(usize, Captured)>,
+
+    state: u64,
+}
+
+impl Sampler {
+    /// Whether the elements of the sequence are held until it ends, rather
+    /// than chosen with [keep][Self::keep].
+    pub(crate) fn holds(&self) -> bool {
+        self.len.is_none()
+    }
+
+    /// Whether the next element of a sequence of known length is extracted.
+    pub(crate) fn keep(&mut self) -> bool {
+        let pos = self.seen;
+        self.seen += 1;
+
+        let keep = match (self.len, self.mode) {
+            (None, _) => unreachable!("elements of sequences of unknown length are held"),
+
+            // selection sampling (Knuth's Algorithm S) keeps exactly
+            // `threshold` elements, each with the same probability
+            (Some(len), SampleMode::Random { .. }) => {
+                let remaining = len.saturating_sub(pos).max(1) as u64;
+                let needed = self.threshold.saturating_sub(self.kept) as u64;
+                self.next_u64() % remaining < needed
+            }
+
+            // keep the first element of each stratum
+            (Some(len), SampleMode::Stratified) => {
+                (pos * self.threshold).div_ceil(len) < ((pos + 1) * self.threshold).div_ceil(len)
+            }
+        };
+
+        if keep {
+            self.kept += 1;
+        }
+        keep
+    }
+
+    /// Offer the next element of a sequence of unknown length, which is held
+    /// if it is sampled, possibly in place of an element held before.
+    pub(crate) fn hold(&mut self, value: Captured) {
+        let pos = self.seen;
+        self.seen += 1;
+
+        match self.mode {
+            // reservoir sampling (Algorithm R) keeps `threshold` elements,
+            // each with the same probability
+            SampleMode::Random { .. } => {
+                if self.held.len() < self.threshold {
+                    self.held.push((pos, value));
+                } else {
+                    let slot = (self.next_u64() % (pos as u64 + 1)) as usize;
+                    if slot < self.threshold {
+                        self.held[slot] = (pos, value);
+                    }
+                }
+            }
+
+            SampleMode::Stratified => {
+                if self.threshold > 0 && pos.is_multiple_of(self.stride) {
+                    self.held.push((pos, value));
+                }
+                if self.held.len() > self.threshold {
+                    self.stride *= 2;
+                    let stride = self.stride;
+                    self.held.retain(|(pos, _)| pos.is_multiple_of(stride));
+                }
+            }
+        }
+
+        self.kept = self.held.len();
+    }
+
+    /// Take the held elements of a sequence of unknown length, in order of
+    /// their positions.
+    pub(crate) fn take_held(&mut self) -> Vec<(usize, Captured)> {
+        let mut held = std::mem::take(&mut self.held);
+        held.sort_by_key(|(pos, _)| *pos);
+        held
+    }
+
+    /// Number of elements of the sequence so far.
+    pub(crate) fn seen(&self) -> usize {
+        self.seen
+    }
+
+    /// Number of elements of the sequence extracted so far.
+    pub(crate) fn kept(&self) -> usize {
+        self.kept
+    }
+
+    /// SplitMix64, which is enough for sampling and keeps samples stable
+    /// across releases of random number crates.
+    fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(GOLDEN_GAMMA);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        z ^ (z >> 31)
+    }
+}
+
+/// Serializer that discards values, so that the values of elements that are
+/// not sampled are still read from deserializers being transcoded.
+pub(crate) struct Discard;
+
+type DiscardResult = Result<(), DatalogExtractionError>;
+
+impl ser::Serializer for Discard {
+    type Ok = ();
+    type Error = DatalogExtractionError;
+    type SerializeSeq = Self;
+    type SerializeTuple = Self;
+    type SerializeTupleStruct = Self;
+    type SerializeTupleVariant = Self;
+    type SerializeMap = Self;
+    type SerializeStruct = Self;
+    type SerializeStructVariant = Self;
+
+    fn serialize_bool(self, _v: bool) -> DiscardResult {
+        Result::Ok(())
+    }
+
+    fn serialize_i8(self, _v: i8) -> DiscardResult {
+        Result::Ok(())
+    }
+
+    fn serialize_i16(self, _v: i16) -> DiscardResult {
+        Result::Ok(())
+    }
+
+    fn serialize_i32(self, _v: i32) -> DiscardResult {
+        Result::Ok(())
+    }
+
+    fn serialize_i64(self, _v: i64) -> DiscardResult {
+        Result::Ok(())
+    }
+
+    fn serialize_u8(self, _v: u8) -> DiscardResult {
+        Result::Ok(())
+    }
+
+    fn serialize_u16(self, _v: u16) -> DiscardResult {
+        Result::Ok(())
+    }
+
+    fn serialize_u32(self, _v: u32) -> DiscardResult {
+        Result::Ok(())
+    }
+
+    fn serialize_u64(self, _v: u64) -> DiscardResult {
+        Result::Ok(())
+    }
+
+    fn serialize_f32(self, _v: f32) -> DiscardResult {
+        Result::Ok(())
+    }
+
+    fn serialize_f64(self, _v: f64) -> DiscardResult {
+        Result::Ok(())
+    }
+
+    fn serialize_char(self, _v: char) -> DiscardResult {
+        Result::Ok(())
+    }
+
+    fn serialize_str(self, _v: &str) -> DiscardResult {
+        Result::Ok(())
+    }
+
+    fn serialize_bytes(self, _v: &[u8]) -> DiscardResult {
+        Result::Ok(())
+    }
+
+    fn serialize_none(self) -> DiscardResult {
+        Result::Ok(())
+    }
+
+    fn serialize_some<T: ?Sized + Serialize>(self, value: &T) -> DiscardResult {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> DiscardResult {
+        Result::Ok(())
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> DiscardResult {
+        Result::Ok(())
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+    ) -> DiscardResult {
+        Result::Ok(())
+    }
+
+    fn serialize_newtype_struct<T: ?Sized + Serialize>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> DiscardResult {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: ?Sized + Serialize>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        value: &T,
+    ) -> DiscardResult {
+        value.serialize(self)
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self, DatalogExtractionError> {
+        Result::Ok(self)
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<Self, DatalogExtractionError> {
+        Result::Ok(self)
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self, DatalogExtractionError> {
+        Result::Ok(self)
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self, DatalogExtractionError> {
+        Result::Ok(self)
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self, DatalogExtractionError> {
+        Result::Ok(self)
+    }
+
+    fn serialize_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self, DatalogExtractionError> {
+        Result::Ok(self)
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self, DatalogExtractionError> {
+        Result::Ok(self)
+    }
+}
+
+impl ser::SerializeSeq for Discard {
+    type Ok = ();
+    type Error = DatalogExtractionError;
+
+    fn serialize_element<T: ?Sized + Serialize>(&mut self, value: &T) -> DiscardResult {
+        value.serialize(Discard)
+    }
+
+    fn end(self) -> DiscardResult {
+        Result::Ok(())
+    }
+}
+
+impl ser::SerializeTuple for Discard {
+    type Ok = ();
+    type Error = DatalogExtractionError;
+
+    fn serialize_element<T: ?Sized + Serialize>(&mut self, value: &T) -> DiscardResult {
+        value.serialize(Discard)
+    }
+
+    fn end(self) -> DiscardResult {
+        Result::Ok(())
+    }
+}
+
+impl ser::SerializeTupleStruct for Discard {
+    type Ok = ();
+    type Error = DatalogExtractionError;
+
+    fn serialize_field<T: ?Sized + Serialize>(&mut self, value: &T) -> DiscardResult {
+        value.serialize(Discard)
+    }
+
+    fn end(self) -> DiscardResult {
+        Result::Ok(())
+    }
+}
+
+impl ser::SerializeTupleVariant for Discard {
+    type Ok = ();
+    type Error = DatalogExtractionError;
+
+    fn serialize_field<T: ?Sized + Serialize>(&mut self, value: &T) -> DiscardResult {
+        value.serialize(Discard)
+    }
+
+    fn end(self) -> DiscardResult {
+        Result::Ok(())
+    }
+}
+
+impl ser::SerializeMap for Discard {
+    type Ok = ();
+    type Error = DatalogExtractionError;
+
+    fn serialize_key<T: ?Sized + Serialize>(&mut self, key: &T) -> DiscardResult {
+        key.serialize(Discard)
+    }
+
+    fn serialize_value<T: ?Sized + Serialize>(&mut self, value: &T) -> DiscardResult {
+        value.serialize(Discard)
+    }
+
+    fn end(self) -> DiscardResult {
+        Result::Ok(())
+    }
+}
+
+impl ser::SerializeStruct for Discard {
+    type Ok = ();
+    type Error = DatalogExtractionError;
+
+    fn serialize_field<T: ?Sized + Serialize>(
+        &mut self,
+        _key: &'static str,
+        value: &T,
+    ) -> DiscardResult {
+        value.serialize(Discard)
+    }
+
+    fn end(self) -> DiscardResult {
+        Result::Ok(())
+    }
+}
+
+impl ser::SerializeStructVariant for Discard {
+    type Ok = ();
+    type Error = DatalogExtractionError;
+
+    fn serialize_field<T: ?Sized + Serialize>(
+        &mut self,
+        _key: &'static str,
+        value: &T,
+    ) -> DiscardResult {
+        value.serialize(Discard)
+    }
+
+    fn end(self) -> DiscardResult {
+        Result::Ok(())
+    }
+}
+
+/// A value held by a [Sampler], recorded by the [Capture] serializer so that
+/// it can be serialized again once the sequence ends.
+pub(crate) enum Captured {
+    Bool(bool),
+    I8(i8),
+    I16(i16),
+    I32(i32),
+    I64(i64),
+    U8(u8),
+    U16(u16),
+    U32(u32),
+    U64(u64),
+    F32(f32),
+    F64(f64),
+    Char(char),
+    Str(String),
+    Bytes(Vec<u8>),
+    None,
+    Some(Box<Captured>),
+    Unit,
+    UnitStruct(&'static str),
+    UnitVariant(&'static str, u32, &'static str),
+    NewtypeStruct(&'static str, Box<Captured>),
+    NewtypeVariant(&'static str, u32, &'static str, Box<Captured>),
+    Seq(Vec<Captured>),
+    Tuple(Vec<Captured>),
+    TupleStruct(&'static str, Vec<Captured>),
+    TupleVariant(&'static str, u32, &'static str, Vec<Captured>),
+    Map(Option<usize>, Vec<(Captured, Captured)>),
+
+    /// Fields of a struct with its declared number of fields; fields left
+    /// out with `skip_field` have no value.
+    Struct(&'static str, usize, CapturedFields),
+    StructVariant(&'static str, u32, &'static str, usize, CapturedFields),
+}
+
+type CapturedFields = Vec<(&'static str, Option<Captured>)>;
+
+impl Serialize for Captured {
+    fn serialize<S: ser::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use ser::{
+            SerializeMap, SerializeSeq, SerializeStruct, SerializeStructVariant, SerializeTuple,
+            SerializeTupleStruct, SerializeTupleVariant,
+        };
+
+        match self {
+            Captured::Bool(v) => serializer.serialize_bool(*v),
+            Captured::I8(v) => serializer.serialize_i8(*v),
+            Captured::I16(v) => serializer.serialize_i16(*v),
+            Captured::I32(v) => serializer.serialize_i32(*v),
+            Captured::I64(v) => serializer.serialize_i64(*v),
+            Captured::U8(v) => serializer.serialize_u8(*v),
+            Captured::U16(v) => serializer.serialize_u16(*v),
+            Captured::U32(v) => serializer.serialize_u32(*v),
+            Captured::U64(v) => serializer.serialize_u64(*v),
+            Captured::F32(v) => serializer.serialize_f32(*v),
+            Captured::F64(v) => serializer.serialize_f64(*v),
+            Captured::Char(v) => serializer.serialize_char(*v),
+            Captured::Str(v) => serializer.serialize_str(v),
+            Captured::Bytes(v) => serializer.serialize_bytes(v),
+            Captured::None => serializer.serialize_none(),
+            Captured::Some(value) => serializer.serialize_some(value),
+            Captured::Unit => serializer.serialize_unit(),
+            Captured::UnitStruct(name) => serializer.serialize_unit_struct(name),
+            Captured::UnitVariant(name, index, variant) => {
+                serializer.serialize_unit_variant(name, *index, variant)
+            }
+            Captured::NewtypeStruct(name, value) => {
+                serializer.serialize_newtype_struct(name, value)
+            }
+            Captured::NewtypeVariant(name, index, variant, value) => {
+                serializer.serialize_newtype_variant(name, *index, variant, value)
+            }
+            Captured::Seq(items) => {
+                let mut seq = serializer.serialize_seq(Some(items.len()))?;
+                for item in items {
+                    seq.serialize_element(item)?;
+                }
+                seq.end()
+            }
+            Captured::Tuple(items) => {
+                let mut tuple = serializer.serialize_tuple(items.len())?;
+                for item in items {
+                    tuple.serialize_element(item)?;
+                }
+                tuple.end()
+            }
+            Captured::TupleStruct(name, items) => {
+                let mut tuple = serializer.serialize_tuple_struct(name, items.len())?;
+                for item in items {
+                    tuple.serialize_field(item)?;
+                }
+                tuple.end()
+            }
+            Captured::TupleVariant(name, index, variant, items) => {
+                let mut tuple =
+                    serializer.serialize_tuple_variant(name, *index, variant, items.len())?;
+                for item in items {
+                    tuple.serialize_field(item)?;
+                }
+                tuple.end()
+            }
+            Captured::Map(len, entries) => {
+                let mut map = serializer.serialize_map(*len)?;
+                for (key, value) in entries {
+                    map.serialize_key(key)?;
+                    map.serialize_value(value)?;
+                }
+                map.end()
+            }
+            Captured::Struct(name, len, fields) => {
+                let mut strct = serializer.serialize_struct(name, *len)?;
+                for (key, value) in fields {
+                    match value {
+                        Some(value) => strct.serialize_field(key, value)?,
+                        None => strct.skip_field(key)?,
+                    }
+                }
+                strct.end()
+            }
+            Captured::StructVariant(name, index, variant, len, fields) => {
+                let mut strct = serializer.serialize_struct_variant(name, *index, variant, *len)?;
+                for (key, value) in fields {
+                    match value {
+                        Some(value) => strct.serialize_field(key, value)?,
+                        None => strct.skip_field(key)?,
+                    }
+                }
+                strct.end()
+            }
+        }
+    }
+}
+
+/// Serializer that records values as [Captured] values.
+pub(crate) struct Capture;
+
+type CaptureResult = Result<Captured, DatalogExtractionError>;
+
+/// Compound value being recorded by [Capture].
+pub(crate) struct CaptureCompound {
+    value: Captured,
+}
+
+impl CaptureCompound {
+    fn items(&mut self) -> &mut Vec<Captured> {
+        match &mut self.value {
+            Captured::Seq(items)
+            | Captured::Tuple(items)
+            | Captured::TupleStruct(_, items)
+            | Captured::TupleVariant(_, _, _, items) => items,
+            _ => unreachable!("not a sequence or tuple"),
+        }
+    }
+
+    fn fields(&mut self) -> &mut CapturedFields {
+        match &mut self.value {
+            Captured::Struct(_, _, fields) | Captured::StructVariant(_, _, _, _, fields) => fields,
+            _ => unreachable!("not a struct"),
+        }
+    }
+
+    fn entries(&mut self) -> &mut Vec<(Captured, Captured)> {
+        match &mut self.value {
+            Captured::Map(_, entries) => entries,
+            _ => unreachable!("not a map"),
+        }
+    }
+}
+
+impl ser::Serializer for Capture {
+    type Ok = Captured;
+    type Error = DatalogExtractionError;
+    type SerializeSeq = CaptureCompound;
+    type SerializeTuple = CaptureCompound;
+    type SerializeTupleStruct = CaptureCompound;
+    type SerializeTupleVariant = CaptureCompound;
+    type SerializeMap = CaptureCompound;
+    type SerializeStruct = CaptureCompound;
+    type SerializeStructVariant = CaptureCompound;
+
+    fn serialize_bool(self, v: bool) -> CaptureResult {
+        Result::Ok(Captured::Bool(v))
+    }
+
+    fn serialize_i8(self, v: i8) -> CaptureResult {
+        Result::Ok(Captured::I8(v))
+    }
+
+    fn serialize_i16(self, v: i16) -> CaptureResult {
+        Result::Ok(Captured::I16(v))
+    }
+
+    fn serialize_i32(self, v: i32) -> CaptureResult {
+        Result::Ok(Captured::I32(v))
+    }
+
+    fn serialize_i64(self, v: i64) -> CaptureResult {
+        Result::Ok(Captured::I64(v))
+    }
+
+    fn serialize_u8(self, v: u8) -> CaptureResult {
+        Result::Ok(Captured::U8(v))
+    }
+
+    fn serialize_u16(self, v: u16) -> CaptureResult {
+        Result::Ok(Captured::U16(v))
+    }
+
+    fn serialize_u32(self, v: u32) -> CaptureResult {
+        Result::Ok(Captured::U32(v))
+    }
+
+    fn serialize_u64(self, v: u64) -> CaptureResult {
+        Result::Ok(Captured::U64(v))
+    }
+
+    fn serialize_f32(self, v: f32) -> CaptureResult {
+        Result::Ok(Captured::F32(v))
+    }
+
+    fn serialize_f64(self, v: f64) -> CaptureResult {
+        Result::Ok(Captured::F64(v))
+    }
+
+    fn serialize_char(self, v: char) -> CaptureResult {
+        Result::Ok(Captured::Char(v))
+    }
+
+    fn serialize_str(self, v: &str) -> CaptureResult {
+        Result::Ok(Captured::Str(v.to_string()))
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> CaptureResult {
+        Result::Ok(Captured::Bytes(v.to_vec()))
+    }
+
+    fn serialize_none(self) -> CaptureResult {
+        Result::Ok(Captured::None)
+    }
+
+    fn serialize_some<T: ?Sized + Serialize>(self, value: &T) -> CaptureResult {
+        Result::Ok(Captured::Some(Box::new(value.serialize(self)?)))
+    }
+
+    fn serialize_unit(self) -> CaptureResult {
+        Result::Ok(Captured::Unit)
+    }
+
+    fn serialize_unit_struct(self, name: &'static str) -> CaptureResult {
+        Result::Ok(Captured::UnitStruct(name))
+    }
+
+    fn serialize_unit_variant(
+        self,
+        name: &'static str,
+        variant_index: u32,
+        variant: &'static str,
+    ) -> CaptureResult {
+        Result::Ok(Captured::UnitVariant(name, variant_index, variant))
+    }
+
+    fn serialize_newtype_struct<T: ?Sized + Serialize>(
+        self,
+        name: &'static str,
+        value: &T,
+    ) -> CaptureResult {
+        Result::Ok(Captured::NewtypeStruct(
+            name,
+            Box::new(value.serialize(self)?),
+        ))
+    }
+
+    fn serialize_newtype_variant<T: ?Sized + Serialize>(
+        self,
+        name: &'static str,
+        variant_index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> CaptureResult {
+        Result::Ok(Captured::NewtypeVariant(
+            name,
+            variant_index,
+            variant,
+            Box::new(value.serialize(self)?),
+        ))
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<CaptureCompound, DatalogExtractionError> {
+        Result::Ok(CaptureCompound {
+            value: Captured::Seq(Vec::with_capacity(len.unwrap_or(0))),
+        })
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<CaptureCompound, DatalogExtractionError> {
+        Result::Ok(CaptureCompound {
+            value: Captured::Tuple(Vec::with_capacity(len)),
+        })
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        name: &'static str,
+        len: usize,
+    ) -> Result<CaptureCompound, DatalogExtractionError> {
+        Result::Ok(CaptureCompound {
+            value: Captured::TupleStruct(name, Vec::with_capacity(len)),
+        })
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        name: &'static str,
+        variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<CaptureCompound, DatalogExtractionError> {
+        Result::Ok(CaptureCompound {
+            value: Captured::TupleVariant(name, variant_index, variant, Vec::with_capacity(len)),
+        })
+    }
+
+    fn serialize_map(self, len: Option<usize>) -> Result<CaptureCompound, DatalogExtractionError> {
+        Result::Ok(CaptureCompound {
+            value: Captured::Map(len, Vec::with_capacity(len.unwrap_or(0))),
+        })
+    }
+
+    fn serialize_struct(
+        self,
+        name: &'static str,
+        len: usize,
+    ) -> Result<CaptureCompound, DatalogExtractionError> {
+        Result::Ok(CaptureCompound {
+            value: Captured::Struct(name, len, Vec::with_capacity(len)),
+        })
+    }
+
+    fn serialize_struct_variant(
+        self,
+        name: &'static str,
+        variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<CaptureCompound, DatalogExtractionError> {
+        Result::Ok(CaptureCompound {
+            value: Captured::StructVariant(
+                name,
+                variant_index,
+                variant,
+                len,
+                Vec::with_capacity(len),
+            ),
+        })
+    }
+}
+
+impl ser::SerializeSeq for CaptureCompound {
+    type Ok = Captured;
+    type Error = DatalogExtractionError;
+
+    fn serialize_element<T: ?Sized + Serialize>(&mut self, value: &T) -> DiscardResult {
+        self.items().push(value.serialize(Capture)?);
+        Result::Ok(())
+    }
+
+    fn end(self) -> CaptureResult {
+        Result::Ok(self.value)
+    }
+}
+
+impl ser::SerializeTuple for CaptureCompound {
+    type Ok = Captured;
+    type Error = DatalogExtractionError;
+
+    fn serialize_element<T: ?Sized + Serialize>(&mut self, value: &T) -> DiscardResult {
+        self.items().push(value.serialize(Capture)?);
+        Result::Ok(())
+    }
+
+    fn end(self) -> CaptureResult {
+        Result::Ok(self.value)
+    }
+}
+
+impl ser::SerializeTupleStruct for CaptureCompound {
+    type Ok = Captured;
+    type Error = DatalogExtractionError;
+
+    fn serialize_field<T: ?Sized + Serialize>(&mut self, value: &T) -> DiscardResult {
+        self.items().push(value.serialize(Capture)?);
+        Result::Ok(())
+    }
+
+    fn end(self) -> CaptureResult {
+        Result::Ok(self.value)
+    }
+}
+
+impl ser::SerializeTupleVariant for CaptureCompound {
+    type Ok = Captured;
+    type Error = DatalogExtractionError;
+
+    fn serialize_field<T: ?Sized + Serialize>(&mut self, value: &T) -> DiscardResult {
+        self.items().push(value.serialize(Capture)?);
+        Result::Ok(())
+    }
+
+    fn end(self) -> CaptureResult {
+        Result::Ok(self.value)
+    }
+}
+
+impl ser::SerializeMap for CaptureCompound {
+    type Ok = Captured;
+    type Error = DatalogExtractionError;
+
+    fn serialize_key<T: ?Sized + Serialize>(&mut self, key: &T) -> DiscardResult {
+        // the value is filled in by serialize_value
+        self.entries()
+            .push((key.serialize(Capture)?, Captured::Unit));
+        Result::Ok(())
+    }
+
+    fn serialize_value<T: ?Sized + Serialize>(&mut self, value: &T) -> DiscardResult {
+        let value = value.serialize(Capture)?;
+        self.entries().last_mut().unwrap().1 = value;
+        Result::Ok(())
+    }
+
+    fn end(self) -> CaptureResult {
+        Result::Ok(self.value)
+    }
+}
+
+impl ser::SerializeStruct for CaptureCompound {
+    type Ok = Captured;
+    type Error = DatalogExtractionError;
+
+    fn serialize_field<T: ?Sized + Serialize>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> DiscardResult {
+        let value = value.serialize(Capture)?;
+        self.fields().push((key, Some(value)));
+        Result::Ok(())
+    }
+
+    fn skip_field(&mut self, key: &'static str) -> DiscardResult {
+        self.fields().push((key, None));
+        Result::Ok(())
+    }
+
+    fn end(self) -> CaptureResult {
+        Result::Ok(self.value)
+    }
+}
+
+impl ser::SerializeStructVariant for CaptureCompound {
+    type Ok = Captured;
+    type Error = DatalogExtractionError;
+
+    fn serialize_field<T: ?Sized + Serialize>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> DiscardResult {
+        let value = value.serialize(Capture)?;
+        self.fields().push((key, Some(value)));
+        Result::Ok(())
+    }
+
+    fn skip_field(&mut self, key: &'static str) -> DiscardResult {
+        self.fields().push((key, None));
+        Result::Ok(())
+    }
+
+    fn end(self) -> CaptureResult {
+        Result::Ok(self.value)
+    }
+}
@@ -0,0 +1,274 @@
+//! Schema-guided extraction, which annotates extracted elements with the
+//! schema they match.
+//!
+//! A [SchemaGuide] describes the schemas expected at each position of a
+//! value: the schemas of the properties of objects and of the items of arrays.
+//! When an extractor is given a guide with
+//! [with_schema_guide][crate::DatalogExtractor::with_schema_guide], it follows
+//! the guide alongside the extracted value and materializes the schema of each
+//! element with
+//! [add_schema_type][crate::DatalogExtractorBackend::add_schema_type], so that
+//! rules can be written against schema concepts (e.g. every element matching
+//! `#/components/schemas/User`) instead of the structure of documents.
+//!
+//! Guides are usually compiled from a JSON Schema or OpenAPI document with
+//! [SchemaGuide::from_json_schema], which requires the `json_schema` feature:
+//!
+//! ```ignore
+//! let openapi: serde_json::Value = serde_json::from_str(&spec)?;
+//! let guide = SchemaGuide::from_json_schema(&openapi, "#/components/schemas/Pet")?;
+//! let mut extractor = DatalogExtractor::new(backend).with_schema_guide(guide);
+//! ```
+//!
+//! Guides do not validate values: an element whose position is described by
+//! the guide is annotated with the schema of that position, whether or not it
+//! is valid, and elements at positions the guide does not describe are not
+//! annotated.
+
+use std::collections::HashMap;
+
+/// Identifier of a schema of a [SchemaGuide].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct SchemaNodeId(usize);
+
+#[derive(Clone, Debug, Default)]
+struct SchemaNode {
+    schema_ref: String,
+    properties: HashMap<String, SchemaNodeId>,
+    additional_properties: Option<SchemaNodeId>,
+    prefix_items: Vec<SchemaNodeId>,
+    items: Option<SchemaNodeId>,
+}
+
+/// The schemas expected at each position of extracted values.
+///
+/// Each schema has a reference, which is materialized for the elements that
+/// match it; guides compiled from JSON Schema documents use the JSON pointer
+/// of the schema in the document, e.g. `#/components/schemas/Pet`, with
+/// `$ref`s resolved to the schema they refer to.
+#[derive(Clone, Debug)]
+pub struct SchemaGuide {
+    nodes: Vec<SchemaNode>,
+}
+
+impl SchemaGuide {
+    /// Create a guide whose root schema, which root values match, has
+    /// reference `root_ref`.
+    pub fn new(root_ref: &str) -> Self {
+        let mut guide = SchemaGuide { nodes: Vec::new() };
+        guide.add_schema(root_ref);
+        guide
+    }
+
+    /// The schema matched by root values.
+    pub fn root(&self) -> SchemaNodeId {
+        SchemaNodeId(0)
+    }
+
+    /// Add a schema with reference `schema_ref`.
+    pub fn add_schema(&mut self, schema_ref: &str) -> SchemaNodeId {
+        self.nodes.push(SchemaNode {
+            schema_ref: schema_ref.to_string(),
+            ..SchemaNode::default()
+        });
+        SchemaNodeId(self.nodes.len() - 1)
+    }
+
+    /// Set `child` as the schema of property `name` of objects matching `node`.
+    /// Struct fields and map entries with string keys are properties.
+    pub fn set_property(&mut self, node: SchemaNodeId, name: &str, child: SchemaNodeId) {
+        self.nodes[node.0]
+            .properties
+            .insert(name.to_string(), child);
+    }
+
+    /// Set `child` as the schema of properties of objects matching `node`
+    /// that have no schema of their own.
+    pub fn set_additional_properties(&mut self, node: SchemaNodeId, child: SchemaNodeId) {
+        self.nodes[node.0].additional_properties = Some(child);
+    }
+
+    /// Set `children` as the schemas of the first items of arrays matching
+    /// `node`, by position. Sequence and tuple entries are items.
+    pub fn set_prefix_items(&mut self, node: SchemaNodeId, children: Vec<SchemaNodeId>) {
+        self.nodes[node.0].prefix_items = children;
+    }
+
+    /// Set `child` as the schema of the items of arrays matching `node`
+    /// that have no schema of their own.
+    pub fn set_items(&mut self, node: SchemaNodeId, child: SchemaNodeId) {
+        self.nodes[node.0].items = Some(child);
+    }
+
+    /// The reference of schema `node`.
+    pub fn schema_ref(&self, node: SchemaNodeId) -> &str {
+        &self.nodes[node.0].schema_ref
+    }
+
+    /// The schema of property `name` of objects matching `node`, or of
+    /// properties without a schema of their own if `name` is absent.
+    pub fn property(&self, node: SchemaNodeId, name: Option<&str>) -> Option<SchemaNodeId> {
+        let node = &self.nodes[node.0];
+        name.and_then(|name| node.properties.get(name))
+            .copied()
+            .or(node.additional_properties)
+    }
+
+    /// The schema of the item at position `pos` of arrays matching `node`.
+    pub fn item(&self, node: SchemaNodeId, pos: usize) -> Option<SchemaNodeId> {
+        let node = &self.nodes[node.0];
+        node.prefix_items.get(pos).copied().or(node.items)
+    }
+
+    /// Compile the schema at JSON pointer `pointer` (e.g. `#` or
+    /// `#/components/schemas/Pet`) of `document`, a JSON Schema or an
+    /// OpenAPI document, into a guide.
+    ///
+    /// The guide follows the `properties`, `additionalProperties`, `items`,
+    /// and `prefixItems` keywords, including the array form of `items` of
+    /// older drafts, and merges the properties and items of the subschemas of
+    /// `allOf`, `anyOf`, and `oneOf`, which are not told apart. References
+    /// (`$ref`) within the document are resolved; references to other
+    /// documents are used as the reference of a schema without properties or
+    /// items.
+    #[cfg(feature = "json_schema")]
+    pub fn from_json_schema(document: &serde_json::Value, pointer: &str) -> Result<Self, String> {
+        let mut compiler = Compiler {
+            document,
+            guide: SchemaGuide { nodes: Vec::new() },
+            compiled: HashMap::new(),
+        };
+
+        compiler.compile(pointer, 0)?;
+        Result::Ok(compiler.guide)
+    }
+}
+
+/// The maximum number of `$ref`s followed to find a schema, which bounds
+/// cycles of references.
+#[cfg(feature = "json_schema")]
+const MAX_REF_DEPTH: usize = 64;
+
+#[cfg(feature = "json_schema")]
+struct Compiler<'a> {
+    document: &'a serde_json::Value,
+    guide: SchemaGuide,
+    compiled: HashMap<String, SchemaNodeId>,
+}
+
+#[cfg(feature = "json_schema")]
+impl Compiler<'_> {
+    /// Compile the schema at `pointer`, which was reached by following
+    /// `ref_depth` references.
+    fn compile(&mut self, pointer: &str, ref_depth: usize) -> Result<SchemaNodeId, String> {
+        use serde_json::Value;
+
+        if let Some(node) = self.compiled.get(pointer) {
+            return Result::Ok(*node);
+        }
+
+        let document = self.document;
+        let schema = resolve_pointer(document, pointer)
+            .ok_or_else(|| format!("cannot resolve schema {}", pointer))?;
+
+        if let Some(Value::String(target)) = schema.get("$ref") {
+            let node = if !target.starts_with('#') {
+                self.guide.add_schema(target)
+            } else if ref_depth >= MAX_REF_DEPTH {
+                return Result::Err(format!("cyclic $ref at {}", pointer));
+            } else {
+                self.compile(target, ref_depth + 1)?
+            };
+
+            self.compiled.insert(pointer.to_string(), node);
+            return Result::Ok(node);
+        }
+
+        let node = self.guide.add_schema(pointer);
+        self.compiled.insert(pointer.to_string(), node);
+
+        if let Some(Value::Object(properties)) = schema.get("properties") {
+            for name in properties.keys() {
+                let child = self.compile(
+                    &format!("{}/properties/{}", pointer, escape_pointer(name)),
+                    0,
+                )?;
+                self.guide.set_property(node, name, child);
+            }
+        }
+
+        if let Some(Value::Object(_) | Value::Bool(true)) = schema.get("additionalProperties") {
+            let child = self.compile(&format!("{}/additionalProperties", pointer), 0)?;
+            self.guide.set_additional_properties(node, child);
+        }
+
+        let (prefix_items_keyword, items_keyword) = match schema.get("items") {
+            Some(Value::Array(_)) => ("items", "additionalItems"),
+            _ => ("prefixItems", "items"),
+        };
+
+        if let Some(Value::Array(prefix_items)) = schema.get(prefix_items_keyword) {
+            let children = (0..prefix_items.len())
+                .map(|i| self.compile(&format!("{}/{}/{}", pointer, prefix_items_keyword, i), 0))
+                .collect::<Result<Vec<_>, String>>()?;
+            self.guide.set_prefix_items(node, children);
+        }
+
+        if let Some(Value::Object(_) | Value::Bool(true)) = schema.get(items_keyword) {
+            let child = self.compile(&format!("{}/{}", pointer, items_keyword), 0)?;
+            self.guide.set_items(node, child);
+        }
+
+        for keyword in ["allOf", "anyOf", "oneOf"] {
+            if let Some(Value::Array(subschemas)) = schema.get(keyword) {
+                for i in 0..subschemas.len() {
+                    let subschema = self.compile(&format!("{}/{}/{}", pointer, keyword, i), 0)?;
+                    self.merge(node, subschema);
+                }
+            }
+        }
+
+        Result::Ok(node)
+    }
+
+    /// Add the properties and items of `subschema` that `node` does not
+    /// have to `node`.
+    fn merge(&mut self, node: SchemaNodeId, subschema: SchemaNodeId) {
+        if node == subschema {
+            return;
+        }
+
+        let sub = self.guide.nodes[subschema.0].clone();
+        let target = &mut self.guide.nodes[node.0];
+        for (name, child) in sub.properties {
+            target.properties.entry(name).or_insert(child);
+        }
+
+        target.additional_properties = target.additional_properties.or(sub.additional_properties);
+        if target.prefix_items.is_empty() {
+            target.prefix_items = sub.prefix_items;
+        }
+        target.items = target.items.or(sub.items);
+    }
+}
+
+/// Escape `name` as a JSON pointer segment.
+#[cfg(feature = "json_schema")]
+fn escape_pointer(name: &str) -> String {
+    name.replace('~', "~0").replace('/', "~1")
+}
+
+/// The value at JSON pointer `pointer`, written as a URI fragment
+/// (e.g. `#/definitions/User`), in `document`.
+#[cfg(feature = "json_schema")]
+fn resolve_pointer<'a>(
+    document: &'a serde_json::Value,
+    pointer: &str,
+) -> Option<&'a serde_json::Value> {
+    let path = pointer.strip_prefix('#').unwrap_or(pointer);
+    if path.is_empty() {
+        return Some(document);
+    }
+
+    document.pointer(path)
+}
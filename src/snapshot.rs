@@ -0,0 +1,455 @@
+//! Golden-file snapshot testing for backends.
+//!
+//! [Snapshot::snapshot] renders the facts of a vector backend as canonical
+//! text, with one Datalog fact per line, e.g. `string(3, "hello").`, so that
+//! regression tests can compare extracted facts with checked-in snapshots
+//! using [assert_snapshot]:
+//!
+//! ```no_run
+//! # use serde::Serialize;
+//! # use serde_datalog::{backend, snapshot::{assert_snapshot, Snapshot}, DatalogExtractor};
+//! let mut extractor = DatalogExtractor::new(backend::vector::Backend::default());
+//! vec![1, 2, 3].serialize(&mut extractor).unwrap();
+//! let data = extractor.get_backend().get_data();
+//!
+//! assert_snapshot("tests/snapshots/vec.snap", &data.snapshot());
+//! ```
+//!
+//! Facts are listed by relation, in the order of [crate::Fact], and within
+//! each relation in order of element identifiers. Interned strings are
+//! resolved and printed as quoted strings, so snapshots do not depend on
+//! symbol identifiers.
+
+use std::{collections::HashMap, fmt::Write, hash::Hash, path::Path};
+
+use crate::{
+    backend::vector::{BackendData, SymbolId},
+    ElemId,
+};
+
+/// Environment variable that makes [assert_snapshot] write snapshots instead
+/// of comparing them, e.g. after an intended change in extracted facts.
+pub const UPDATE_SNAPSHOTS_VAR: &str = "SERDE_DATALOG_UPDATE_SNAPSHOTS";
+
+/// Types whose facts can be rendered as snapshots.
+pub trait Snapshot {
+    /// Render facts as canonical text, with one fact per line.
+    fn snapshot(&self) -> String;
+}
+
+impl Snapshot for BackendData<ElemId> {
+    fn snapshot(&self) -> String {
+        snapshot_with_fn(self, |_, key| key.to_string())
+    }
+}
+
+impl Snapshot for BackendData<SymbolId> {
+    fn snapshot(&self) -> String {
+        snapshot_with_fn(self, quoted_symbol)
+    }
+}
+
+fn quoted_symbol<K: Eq + Hash>(data: &BackendData<K>, sym: &SymbolId) -> String {
+    match data.symbol(sym) {
+        Some(symbol) => format!("{:?}", symbol),
+        None => format!("#{}", sym.0),
+    }
+}
+
+/// Render the facts of `data` as canonical text, with one fact per line.
+/// `map_key_fn` renders map keys.
+pub fn snapshot_with_fn<K: Eq + Hash>(
+    data: &BackendData<K>,
+    map_key_fn: impl Fn(&BackendData<K>, &K) -> String,
+) -> String {
+    let sym = |sym: &SymbolId| quoted_symbol(data, sym);
+
+    let mut out = String::new();
+
+    // facts about files and struct types are sorted by their strings
+    let mut string_facts: Vec<(usize, &str, String)> = data
+        .root_elem_table
+        .iter()
+        .map(|(file, elem)| (0, "rootElem", format!("{}, {}", sym(file), elem)))
+        .chain(
+            data.file_format_table
+                .iter()
+                .map(|(file, format)| (1, "fileFormat", format!("{}, {}", sym(file), sym(format)))),
+        )
+        .chain(
+            data.key_field_table
+                .iter()
+                .map(|(name, field)| (2, "keyField", format!("{}, {}", sym(name), sym(field)))),
+        )
+        .chain(data.elem_type_kind_table.iter().map(|(elem_type, kind)| {
+            (
+                3,
+                "elemTypeKind",
+                format!("{}, {}", sym(elem_type), sym(kind)),
+            )
+        }))
+        .collect();
+    string_facts.sort();
+    for (_, relation, args) in string_facts {
+        let _ = writeln!(out, "{}({}).", relation, args);
+    }
+
+    // facts are always sorted, regardless of how the data is dumped;
+    // each fact is sorted by its element, index, and rendered arguments
+    let mut emit = |relation: &str, mut facts: Vec<(ElemId, usize, String)>| {
+        facts.sort();
+        for (_, _, args) in facts {
+            let _ = writeln!(out, "{}({}).", relation, args);
+        }
+    };
+
+    let with_elem = |elem: &ElemId, value: String| (*elem, 0, format!("{}, {}", elem, value));
+    let with_index = |elem: &ElemId, index: usize, value: String| {
+        (*elem, index, format!("{}, {}, {}", elem, index, value))
+    };
+
+    emit(
+        "type",
+        data.type_table
+            .iter()
+            .map(|(elem, ty)| with_elem(elem, sym(ty)))
+            .collect(),
+    );
+    emit(
+        "bool",
+        data.bool_table
+            .iter()
+            .map(|(elem, value)| with_elem(elem, value.to_string()))
+            .collect(),
+    );
+    emit(
+        "number",
+        data.numbers()
+            .map(|(elem, value)| with_elem(elem, value.to_string()))
+            .collect(),
+    );
+    emit(
+        "string",
+        data.string_table
+            .iter()
+            .map(|(elem, value)| with_elem(elem, sym(value)))
+            .collect(),
+    );
+    emit(
+        "fullString",
+        data.full_string_table
+            .iter()
+            .map(|(elem, text)| with_elem(elem, format!("{:?}", text)))
+            .collect(),
+    );
+    emit(
+        "map",
+        data.map_table
+            .iter()
+            .map(|((elem, key), value)| {
+                with_elem(elem, format!("{}, {}", map_key_fn(data, key), value))
+            })
+            .collect(),
+    );
+    emit(
+        "structType",
+        data.struct_type_table
+            .iter()
+            .map(|(elem, ty)| with_elem(elem, sym(ty)))
+            .collect(),
+    );
+    emit(
+        "struct",
+        data.struct_table
+            .iter()
+            .map(|((elem, field), value)| with_elem(elem, format!("{}, {}", sym(field), value)))
+            .collect(),
+    );
+    emit(
+        "key",
+        data.key_table
+            .iter()
+            .map(|(elem, key)| with_elem(elem, key.to_string()))
+            .collect(),
+    );
+    emit(
+        "seq",
+        data.seq_table
+            .iter()
+            .map(|((elem, index), value)| with_index(elem, *index, value.to_string()))
+            .collect(),
+    );
+    emit(
+        "variantType",
+        data.variant_type_table
+            .iter()
+            .map(|(elem, (type_name, variant))| {
+                with_elem(elem, format!("{}, {}", sym(type_name), sym(variant)))
+            })
+            .collect(),
+    );
+    emit(
+        "tuple",
+        data.tuple_table
+            .iter()
+            .map(|((elem, index), value)| with_index(elem, *index, value.to_string()))
+            .collect(),
+    );
+    emit(
+        "schemaType",
+        data.schema_type_table
+            .iter()
+            .map(|(elem, schema_ref)| with_elem(elem, sym(schema_ref)))
+            .collect(),
+    );
+    emit(
+        "datetime",
+        data.datetime_table
+            .iter()
+            .map(|(elem, (micros, offset))| with_elem(elem, format!("{}, {}", micros, offset)))
+            .collect(),
+    );
+    emit(
+        "uuid",
+        data.uuid_table
+            .iter()
+            .map(|(elem, (hi, lo))| with_elem(elem, format!("{}, {}", *hi as i64, *lo as i64)))
+            .collect(),
+    );
+    emit(
+        "ip",
+        data.ip_table
+            .iter()
+            .map(|(elem, (version, address))| {
+                with_elem(elem, format!("{}, {}", version, sym(address)))
+            })
+            .collect(),
+    );
+    emit(
+        "url",
+        data.url_table
+            .iter()
+            .map(|(elem, (scheme, host))| {
+                with_elem(elem, format!("{}, {}", sym(scheme), sym(host)))
+            })
+            .collect(),
+    );
+    emit(
+        "email",
+        data.email_table
+            .iter()
+            .map(|(elem, (local, domain))| {
+                with_elem(elem, format!("{}, {}", sym(local), sym(domain)))
+            })
+            .collect(),
+    );
+    emit(
+        "bytes",
+        data.bytes_table
+            .iter()
+            .map(|(elem, value)| with_elem(elem, sym(value)))
+            .collect(),
+    );
+    emit(
+        "bytesEncoding",
+        data.bytes_encoding_table
+            .iter()
+            .map(|(elem, (encoding, length))| {
+                with_elem(elem, format!("{}, {}", sym(encoding), length))
+            })
+            .collect(),
+    );
+    emit(
+        "declaredLen",
+        data.declared_len_table
+            .iter()
+            .map(|(elem, len)| with_elem(elem, len.to_string()))
+            .collect(),
+    );
+    emit(
+        "absentField",
+        data.absent_field_table
+            .iter()
+            .map(|(elem, field)| with_elem(elem, sym(field)))
+            .collect(),
+    );
+    emit(
+        "unit",
+        data.unit_table
+            .iter()
+            .map(|elem| (*elem, 0, elem.to_string()))
+            .collect(),
+    );
+
+    // truncation facts are about no element, so they are sorted by their
+    // relations
+    emit(
+        "truncated",
+        data.truncated_table
+            .iter()
+            .map(|(relation, quota)| (ElemId(0), 0, format!("{}, {}", sym(relation), quota)))
+            .collect(),
+    );
+    emit(
+        "redacted",
+        data.redacted_table
+            .iter()
+            .map(|elem| (*elem, 0, elem.to_string()))
+            .collect(),
+    );
+    emit(
+        "sampled",
+        data.sampled_table
+            .iter()
+            .map(|(elem, (len, kept))| with_elem(elem, format!("{}, {}", len, kept)))
+            .collect(),
+    );
+    emit(
+        "sameString",
+        data.same_string_table
+            .iter()
+            .map(|(elem, other)| (*elem, other.0, format!("{}, {}", elem, other)))
+            .collect(),
+    );
+    emit(
+        "shape",
+        data.shape_table
+            .iter()
+            .map(|(elem, shape)| with_elem(elem, (*shape as i64).to_string()))
+            .collect(),
+    );
+    emit(
+        "stringHash",
+        data.string_hash_table
+            .iter()
+            .map(|(elem, (hash, len))| with_elem(elem, format!("{}, {}", *hash as i64, len)))
+            .collect(),
+    );
+    emit(
+        "depth",
+        data.depth_table
+            .iter()
+            .map(|(elem, depth)| with_elem(elem, depth.to_string()))
+            .collect(),
+    );
+    emit(
+        "subtreeSize",
+        data.subtree_size_table
+            .iter()
+            .map(|(elem, size)| with_elem(elem, size.to_string()))
+            .collect(),
+    );
+    emit(
+        "char",
+        data.char_table
+            .iter()
+            .map(|(elem, codepoint)| with_elem(elem, codepoint.to_string()))
+            .collect(),
+    );
+
+    // shape fields are about no element, so they are sorted by their
+    // positions and arguments
+    emit(
+        "shapeField",
+        data.shape_field_table
+            .iter()
+            .map(|((shape, pos), field)| {
+                let args = format!("{}, {}, {}", *shape as i64, pos, sym(field));
+                (ElemId(0), *pos, args)
+            })
+            .collect(),
+    );
+
+    // parse errors are about no element, so they are sorted by their lines
+    // and arguments
+    emit(
+        "parseError",
+        data.parse_error_table
+            .iter()
+            .map(|((file, line), message)| {
+                let args = format!("{}, {}, {}", sym(file), line, sym(message));
+                (ElemId(0), *line, args)
+            })
+            .collect(),
+    );
+
+    // root metadata is about no element, so it is sorted by its arguments
+    emit(
+        "rootMeta",
+        data.root_meta_table
+            .iter()
+            .map(|(file, key, value)| {
+                let args = format!("{}, {}, {}", sym(file), sym(key), sym(value));
+                (ElemId(0), 0, args)
+            })
+            .collect(),
+    );
+
+    out
+}
+
+/// Compare `actual` with the snapshot stored in the file `path`, and panic
+/// with the lines that differ if they are not equal. If the environment
+/// variable [UPDATE_SNAPSHOTS_VAR] is set, the snapshot is written instead,
+/// creating the directories of `path` if needed.
+pub fn assert_snapshot(path: impl AsRef<Path>, actual: &str) {
+    let path = path.as_ref();
+
+    if std::env::var_os(UPDATE_SNAPSHOTS_VAR).is_some() {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).unwrap_or_else(|err| {
+                panic!(
+                    "cannot create snapshot directory {}: {}",
+                    dir.display(),
+                    err
+                )
+            });
+        }
+        std::fs::write(path, actual)
+            .unwrap_or_else(|err| panic!("cannot write snapshot {}: {}", path.display(), err));
+        return;
+    }
+
+    let expected = std::fs::read_to_string(path).unwrap_or_else(|err| {
+        panic!(
+            "cannot read snapshot {}: {}; set {} to create it",
+            path.display(),
+            err,
+            UPDATE_SNAPSHOTS_VAR
+        )
+    });
+
+    if expected != actual {
+        panic!(
+            "snapshot {} does not match; set {} to update it\n{}",
+            path.display(),
+            UPDATE_SNAPSHOTS_VAR,
+            diff_lines(&expected, actual)
+        );
+    }
+}
+
+/// Lines only in `expected`, prefixed with `-`, and lines only in `actual`,
+/// prefixed with `+`. Since snapshots are sorted, comparing them as
+/// multisets of lines is enough to show what changed.
+fn diff_lines(expected: &str, actual: &str) -> String {
+    let mut counts: HashMap<&str, isize> = HashMap::new();
+    for line in expected.lines() {
+        *counts.entry(line).or_default() += 1;
+    }
+    for line in actual.lines() {
+        *counts.entry(line).or_default() -= 1;
+    }
+
+    let mut diff = String::new();
+    for (prefix, text, sign) in [("-", expected, 1), ("+", actual, -1)] {
+        for line in text.lines() {
+            let count = counts.get_mut(line).unwrap();
+            if *count * sign > 0 {
+                *count -= sign;
+                let _ = writeln!(diff, "{}{}", prefix, line);
+            }
+        }
+    }
+    diff
+}
@@ -0,0 +1,248 @@
+//! Running Souffle programs on extracted facts with the `run` subcommand, and
+//! collecting the output relations of the programs into SQLite databases.
+
+use rusqlite::Connection;
+use std::{
+    collections::HashMap,
+    fs, io,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use crate::error::CliError;
+
+/// A Souffle program run on the facts of a database.
+pub struct Run<'a> {
+    /// The Souffle executable.
+    pub souffle: &'a str,
+
+    /// The program, which uses the relations of the facts without declaring
+    /// them.
+    pub program: &'a Path,
+
+    /// Declarations and input directives of the relations of the facts.
+    pub directives: &'a Path,
+
+    /// Directory of the database named by the input directives.
+    pub fact_dir: &'a Path,
+
+    /// Directory the output relations are written to as CSV files, or
+    /// standard output if absent.
+    pub output_dir: Option<&'a Path>,
+
+    /// Number of threads Souffle evaluates the program with.
+    pub jobs: Option<usize>,
+}
+
+impl Run<'_> {
+    /// Write a program that includes the directives and then the program to
+    /// directory `work_dir`, and run Souffle on it. Fails if Souffle cannot be
+    /// started or reports an error, which it prints to standard error.
+    pub fn run(&self, work_dir: &Path) -> Result<(), CliError> {
+        let io_error = |path: &Path| {
+            let path = path.display().to_string();
+            move |source| CliError::Io { path, source }
+        };
+
+        let program = fs::canonicalize(self.program).map_err(io_error(self.program))?;
+        let directives = fs::canonicalize(self.directives).map_err(io_error(self.directives))?;
+
+        let main = work_dir.join("main.dl");
+        fs::write(
+            &main,
+            format!(
+                "// Souffle program generated by serde_datalog run\n#include {:?}\n#include {:?}\n",
+                directives, program
+            ),
+        )
+        .map_err(io_error(&main))?;
+
+        let mut command = Command::new(self.souffle);
+        command.arg("-F").arg(self.fact_dir);
+        match self.output_dir {
+            Some(dir) => command.arg("-D").arg(dir),
+            None => command.args(["-D", "-"]),
+        };
+        if let Some(jobs) = self.jobs {
+            command.arg("-j").arg(jobs.to_string());
+        }
+        command.arg(&main);
+
+        let status = command.status().map_err(|source| match source.kind() {
+            io::ErrorKind::NotFound => CliError::Usage(format!(
+                "Cannot run {}: not found; install Souffle or give its path with --souffle",
+                self.souffle
+            )),
+            _ => io_error(Path::new(self.souffle))(source),
+        })?;
+
+        if !status.success() {
+            return Result::Err(CliError::Io {
+                path: self.program.display().to_string(),
+                source: io::Error::other(format!("{} failed with {}", self.souffle, status)),
+            });
+        }
+
+        Result::Ok(())
+    }
+}
+
+/// The columns of a relation, with the SQLite types of their values.
+type Columns = Vec<(String, &'static str)>;
+
+/// The relations declared in Souffle program `program`, with their columns.
+/// Attributes of number types, including subtypes such as `ElemId`, are
+/// stored as integers, of float types as reals, and of other types as text.
+pub fn declarations(program: &str) -> HashMap<String, Columns> {
+    let mut base_types: HashMap<&str, &str> = HashMap::new();
+    for line in program.lines() {
+        let Some(decl) = line.trim().strip_prefix(".type ") else {
+            continue;
+        };
+
+        // subtypes `.type A <: B` and equivalent types `.type A = B`
+        if let Some((name, base)) = decl.split_once("<:").or_else(|| decl.split_once('=')) {
+            base_types.insert(name.trim(), base.trim());
+        }
+    }
+
+    let mut relations = HashMap::new();
+    let mut rest = program;
+    while let Some(start) = rest.find(".decl ") {
+        rest = &rest[start + ".decl ".len()..];
+        let Some((name, attributes)) = rest.split_once('(') else {
+            break;
+        };
+        let Some((attributes, _)) = attributes.split_once(')') else {
+            break;
+        };
+
+        let columns = attributes
+            .split(',')
+            .filter_map(|attribute| {
+                let (column, ty) = attribute.split_once(':')?;
+                Some((column.trim().to_string(), sql_type(&base_types, ty.trim())))
+            })
+            .collect();
+        relations.insert(name.trim().to_string(), columns);
+    }
+
+    relations
+}
+
+/// The SQLite type of the values of Souffle type `ty`, following the
+/// `base_types` of subtypes and equivalent types.
+fn sql_type<'a>(base_types: &HashMap<&'a str, &'a str>, mut ty: &'a str) -> &'static str {
+    // bounded, in case of cyclic declarations
+    for _ in 0..base_types.len() {
+        match base_types.get(ty) {
+            Some(base) => ty = base,
+            None => break,
+        }
+    }
+
+    match ty {
+        "number" | "unsigned" => "INTEGER",
+        "float" => "REAL",
+        _ => "TEXT",
+    }
+}
+
+/// Load the CSV files written by Souffle to `output_dir` into tables of the
+/// SQLite database `db`, one table for each relation named after the file,
+/// replacing tables of the same name. The columns of relations declared in
+/// `relations` are named and typed after their attributes; columns of other
+/// relations are named `c0`, `c1`, etc. Returns the names of the relations.
+pub fn collect_outputs(
+    output_dir: &Path,
+    relations: &HashMap<String, Columns>,
+    db: &str,
+) -> Result<Vec<String>, CliError> {
+    let io_error = |path: &Path| {
+        let path = path.display().to_string();
+        move |source| CliError::Io { path, source }
+    };
+    let db_error = |source: rusqlite::Error| CliError::Database {
+        path: db.to_string(),
+        source: source.into(),
+    };
+
+    let mut files: Vec<PathBuf> = fs::read_dir(output_dir)
+        .map_err(io_error(output_dir))?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<io::Result<_>>()
+        .map_err(io_error(output_dir))?;
+    files.retain(|path| path.extension().is_some_and(|ext| ext == "csv"));
+    files.sort();
+
+    let mut conn = Connection::open(db).map_err(db_error)?;
+    let tx = conn.transaction().map_err(db_error)?;
+
+    let mut collected = Vec::new();
+    for file in files {
+        let Some(relation) = file
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().to_string())
+        else {
+            continue;
+        };
+
+        // Souffle writes tab-separated values without a header
+        let contents = fs::read_to_string(&file).map_err(io_error(&file))?;
+        let rows: Vec<Vec<&str>> = contents
+            .lines()
+            .map(|line| line.split('\t').collect())
+            .collect();
+
+        let columns = match relations.get(&relation) {
+            Some(columns) => columns.clone(),
+            None => {
+                let arity = rows.first().map_or(0, Vec::len);
+                (0..arity).map(|i| (format!("c{}", i), "TEXT")).collect()
+            }
+        };
+        if columns.is_empty() {
+            continue;
+        }
+
+        let column_defs: Vec<String> = columns
+            .iter()
+            .map(|(column, ty)| format!("\"{}\" {}", column, ty))
+            .collect();
+        tx.execute_batch(&format!(
+            "DROP TABLE IF EXISTS \"{0}\"; CREATE TABLE \"{0}\" ({1});",
+            relation,
+            column_defs.join(", ")
+        ))
+        .map_err(db_error)?;
+
+        let placeholders = vec!["?"; columns.len()].join(", ");
+        let mut insert = tx
+            .prepare(&format!(
+                "INSERT INTO \"{}\" VALUES ({})",
+                relation, placeholders
+            ))
+            .map_err(db_error)?;
+        for row in rows.iter() {
+            if row.len() != columns.len() {
+                return Result::Err(CliError::Parse {
+                    path: file.display().to_string(),
+                    message: format!(
+                        "expected {} column(s) for relation {}, found {}",
+                        columns.len(),
+                        relation,
+                        row.len()
+                    ),
+                });
+            }
+            insert
+                .execute(rusqlite::params_from_iter(row.iter()))
+                .map_err(db_error)?;
+        }
+
+        collected.push(relation);
+    }
+
+    tx.commit().map_err(db_error)?;
+    Result::Ok(collected)
+}
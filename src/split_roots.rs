@@ -0,0 +1,160 @@
+//! Extraction of the items of a top-level sequence, or the entries of a
+//! top-level map, as separate root elements, as requested with `--split-roots`.
+
+use serde::de::{self, Deserialize, DeserializeSeed, Deserializer, MapAccess, SeqAccess, Visitor};
+use serde_datalog::{DatalogExtractionError, DatalogExtractor, DatalogExtractorBackend};
+use std::fmt;
+
+/// Deserializes a top-level sequence, extracting each of its items as a
+/// separate root element named `root:N` for the Nth item, with `N` recorded
+/// in `rootMeta` under the key `item`. The values of a top-level map are
+/// extracted likewise as root elements named `root:K` for key `K`, with `K`
+/// recorded in `rootMeta` under the key `key`. Items are transcoded into the
+/// extractor as they are read, so the sequence is never held in memory as a
+/// whole. The `position` of the sequence's document in its input, if any,
+/// is recorded for every item under its key, and the `format` of the input
+/// is recorded for every item in `fileFormat`, so that the facts of items
+/// join with their format on the names of their roots.
+///
+/// If extracting an item fails, the name of its root element and the
+/// extraction error are stored in `error`, so that the error can be reported
+/// as is instead of through the deserializer's error type.
+pub struct SplitRoots<'a, B: DatalogExtractorBackend> {
+    pub extractor: &'a mut DatalogExtractor<B>,
+    pub root: &'a str,
+    pub position: Option<(&'static str, usize)>,
+    pub format: &'static str,
+    pub error: &'a mut Option<(String, DatalogExtractionError)>,
+}
+
+impl<'de, B: DatalogExtractorBackend> DeserializeSeed<'de> for SplitRoots<'_, B> {
+    type Value = ();
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<(), D::Error> {
+        deserializer.deserialize_any(self)
+    }
+}
+
+impl<'de, B: DatalogExtractorBackend> Visitor<'de> for SplitRoots<'_, B> {
+    type Value = ();
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a sequence or map of values to extract as separate roots")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<(), A::Error> {
+        let mut index = 0;
+        loop {
+            index += 1;
+            let item = SplitRoot {
+                extractor: &mut *self.extractor,
+                root: format!("{}:{}", self.root, index),
+                meta: ("item", index.to_string()),
+                position: self.position,
+                format: self.format,
+                error: &mut *self.error,
+            };
+
+            if seq.next_element_seed(item)?.is_none() {
+                return Result::Ok(());
+            }
+        }
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<(), A::Error> {
+        while let Some(RootKey(key)) = map.next_key()? {
+            let entry = SplitRoot {
+                extractor: &mut *self.extractor,
+                root: format!("{}:{}", self.root, key),
+                meta: ("key", key),
+                position: self.position,
+                format: self.format,
+                error: &mut *self.error,
+            };
+            map.next_value_seed(entry)?;
+        }
+
+        Result::Ok(())
+    }
+}
+
+/// A key of a top-level map, which names the root element of its value.
+/// Keys that are numbers or booleans, as in YAML, are named as written.
+struct RootKey(String);
+
+impl<'de> Deserialize<'de> for RootKey {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(RootKeyVisitor)
+    }
+}
+
+struct RootKeyVisitor;
+
+impl Visitor<'_> for RootKeyVisitor {
+    type Value = RootKey;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a string, number, or boolean map key")
+    }
+
+    fn visit_str<E: de::Error>(self, value: &str) -> Result<RootKey, E> {
+        Result::Ok(RootKey(value.to_string()))
+    }
+
+    fn visit_string<E: de::Error>(self, value: String) -> Result<RootKey, E> {
+        Result::Ok(RootKey(value))
+    }
+
+    fn visit_bool<E: de::Error>(self, value: bool) -> Result<RootKey, E> {
+        Result::Ok(RootKey(value.to_string()))
+    }
+
+    fn visit_i64<E: de::Error>(self, value: i64) -> Result<RootKey, E> {
+        Result::Ok(RootKey(value.to_string()))
+    }
+
+    fn visit_u64<E: de::Error>(self, value: u64) -> Result<RootKey, E> {
+        Result::Ok(RootKey(value.to_string()))
+    }
+}
+
+/// Extracts an item of a top-level sequence or the value of an entry of a
+/// top-level map as root element `root`, recording its index or key `meta`
+/// in `rootMeta`.
+struct SplitRoot<'a, B: DatalogExtractorBackend> {
+    extractor: &'a mut DatalogExtractor<B>,
+    root: String,
+    meta: (&'static str, String),
+    position: Option<(&'static str, usize)>,
+    format: &'static str,
+    error: &'a mut Option<(String, DatalogExtractionError)>,
+}
+
+impl<'de, B: DatalogExtractorBackend> DeserializeSeed<'de> for SplitRoot<'_, B> {
+    type Value = ();
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<(), D::Error> {
+        let res = self
+            .extractor
+            .set_file(&self.root)
+            .and_then(|()| serde_transcode::transcode(deserializer, &mut *self.extractor))
+            .and_then(|()| {
+                let (key, value) = &self.meta;
+                self.extractor.set_file_format(&self.root, self.format)?;
+                self.extractor.set_root_meta(&self.root, key, value)?;
+                match self.position {
+                    Some((key, position)) => {
+                        self.extractor
+                            .set_root_meta(&self.root, key, &position.to_string())
+                    }
+                    None => Result::Ok(()),
+                }
+            });
+
+        res.map_err(|err| {
+            let message = err.to_string();
+            *self.error = Some((self.root, err));
+            de::Error::custom(message)
+        })
+    }
+}
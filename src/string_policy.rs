@@ -0,0 +1,153 @@
+//! Policies that adjust string values before they are extracted, so that
+//! they can be stored as Souffle symbols and joined robustly.
+
+use std::{borrow::Cow, fmt::Write};
+
+/// Policy applied by [DatalogExtractor][crate::DatalogExtractor] to string
+/// and char values before they are passed to the backend, so that every
+/// backend stores the same strings. Souffle symbols cannot contain tabs or
+/// newlines when read from facts files, and huge strings blow up symbol
+/// tables. Strings from different input files may also differ only in their
+/// Unicode encoding or case, which breaks joins on them. Build policies by chaining methods on [StringPolicy::default],
+/// which leaves strings unchanged:
+///
+/// ```
+/// # use serde_datalog::string_policy::StringPolicy;
+/// let policy = StringPolicy::default()
+///     .escape_control_chars(true)
+///     .truncate(8)
+///     .keep_full_text(true);
+///
+/// assert_eq!(policy.apply("a\tb"), "a\\tb");
+/// assert_eq!(policy.apply("a long string"), "a long s#40ae5f8ccc96a7a7");
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct StringPolicy {
+    #[cfg(feature = "unicode")]
+    nfc: bool,
+    case_fold: bool,
+    max_len: Option<usize>,
+    escape_control_chars: bool,
+    keep_full_text: bool,
+    elide_len: Option<usize>,
+}
+
+impl StringPolicy {
+    /// Normalize strings to Unicode Normalization Form C, so that strings
+    /// that only differ in how their characters are encoded (e.g. `é` as one
+    /// code point or as `e` followed by a combining accent) are equal.
+    #[cfg(feature = "unicode")]
+    pub fn nfc(mut self, enable: bool) -> Self {
+        self.nfc = enable;
+        self
+    }
+
+    /// Fold the case of strings by converting them to lowercase, so that
+    /// strings that only differ in case are equal. Case is folded after
+    /// normalizing strings.
+    pub fn case_fold(mut self, enable: bool) -> Self {
+        self.case_fold = enable;
+        self
+    }
+
+    /// Truncate strings longer than `max_len` bytes to their first `max_len`
+    /// bytes, followed by `#` and a hash of the full string so that distinct
+    /// strings with the same prefix remain distinct. Strings are truncated
+    /// after all other changes.
+    pub fn truncate(mut self, max_len: usize) -> Self {
+        self.max_len = Some(max_len);
+        self
+    }
+
+    /// Escape control characters, such as tabs and newlines, as Rust escape
+    /// sequences (e.g. `\t`, `\n`, or `\u{1b}`). Control characters are
+    /// escaped after normalizing strings and folding their case.
+    pub fn escape_control_chars(mut self, enable: bool) -> Self {
+        self.escape_control_chars = enable;
+        self
+    }
+
+    /// Store the original text of strings changed by the policy with
+    /// [add_full_str][crate::DatalogExtractorBackend::add_full_str], so that
+    /// both the original and the changed strings are extracted.
+    pub fn keep_full_text(mut self, enable: bool) -> Self {
+        self.keep_full_text = enable;
+        self
+    }
+
+    /// Elide strings longer than `max_len` bytes, such as minified scripts or
+    /// base64-encoded images, so that they do not dominate symbol tables.
+    /// Instead of its text, a hash of an elided string and its length are
+    /// extracted with
+    /// [add_string_hash][crate::DatalogExtractorBackend::add_string_hash],
+    /// so that equal strings can still be joined. Strings are elided before
+    /// any other change, and their text is not stored even if the full text
+    /// of changed strings is kept.
+    pub fn elide(mut self, max_len: usize) -> Self {
+        self.elide_len = Some(max_len);
+        self
+    }
+
+    /// Whether `value` is elided.
+    pub fn elides(&self, value: &str) -> bool {
+        self.elide_len.is_some_and(|max_len| value.len() > max_len)
+    }
+
+    /// Whether the original text of changed strings is stored.
+    pub fn keeps_full_text(&self) -> bool {
+        self.keep_full_text
+    }
+
+    /// Apply the policy to `value`.
+    pub fn apply<'a>(&self, value: &'a str) -> Cow<'a, str> {
+        let mut result = Cow::Borrowed(value);
+
+        #[cfg(feature = "unicode")]
+        if self.nfc && !unicode_normalization::is_nfc(value) {
+            use unicode_normalization::UnicodeNormalization;
+            result = Cow::Owned(value.nfc().collect());
+        }
+
+        if self.case_fold {
+            let folded = result.to_lowercase();
+            if folded != result {
+                result = Cow::Owned(folded);
+            }
+        }
+
+        if self.escape_control_chars && result.chars().any(char::is_control) {
+            let mut escaped = String::with_capacity(result.len());
+            for c in result.chars() {
+                if c.is_control() {
+                    escaped.extend(c.escape_default());
+                } else {
+                    escaped.push(c);
+                }
+            }
+            result = Cow::Owned(escaped);
+        }
+
+        match self.max_len {
+            Some(max_len) if result.len() > max_len => {
+                let mut end = max_len;
+                while !result.is_char_boundary(end) {
+                    end -= 1;
+                }
+
+                let mut truncated = result[..end].to_string();
+                let _ = write!(truncated, "#{:016x}", fnv1a(result.as_bytes()));
+                Cow::Owned(truncated)
+            }
+
+            _ => result,
+        }
+    }
+}
+
+/// 64-bit FNV-1a hash, which unlike the hashers of the standard library is
+/// stable across Rust releases.
+pub(crate) fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ *byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
@@ -0,0 +1,42 @@
+//! Summary of an extraction, printed to standard output as JSON with `--json`
+//! so that scripts and CI jobs need not parse human-readable output.
+
+use serde::Serialize;
+
+/// What an extraction did, accumulated over the runs of
+/// [process_files][crate::process_files] that write its outputs.
+#[derive(Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Summary {
+    /// Number of input files from which facts were extracted.
+    pub files_processed: usize,
+
+    /// Number of input files that could not be processed, with `--keep-going`.
+    pub files_failed: usize,
+
+    /// Number of the processed input files whose facts were reused from the
+    /// extraction cache, with `--cache`.
+    pub files_cached: usize,
+
+    /// Number of facts generated from the input files.
+    pub facts_emitted: usize,
+
+    /// Problems that did not stop the extraction, e.g. input files that were
+    /// skipped or failed, and documents recorded as parse errors.
+    pub warnings: Vec<String>,
+
+    /// Files written by the extraction, e.g. output databases.
+    pub outputs: Vec<String>,
+}
+
+impl Summary {
+    /// Add what `other` did to this summary.
+    pub fn merge(&mut self, other: Summary) {
+        self.files_processed += other.files_processed;
+        self.files_failed += other.files_failed;
+        self.files_cached += other.files_cached;
+        self.facts_emitted += other.facts_emitted;
+        self.warnings.extend(other.warnings);
+        self.outputs.extend(other.outputs);
+    }
+}
@@ -0,0 +1,488 @@
+//! A [serde::Serializer] adapter that extracts facts from values as they are
+//! serialized by another serializer.
+//!
+//! [TeeSerializer] wraps any serializer, such as the serializer of a wire
+//! format, and forwards values to it while extracting facts about them into a
+//! [DatalogExtractor]. Services can then record an EDB of everything they
+//! serialize without serializing each value a second time:
+//!
+//! ```ignore
+//! let mut extractor = DatalogExtractor::new(backend::vector::Backend::default());
+//! let mut json = serde_json::Serializer::new(Vec::new());
+//!
+//! extractor.set_file("response")?;
+//! response.serialize(TeeSerializer::new(&mut json, &mut extractor))?;
+//! ```
+//!
+//! Each value is traversed once: every call made by the value's
+//! [Serialize] implementation is made on both the wrapped serializer and the
+//! extractor. Errors from the extractor are converted into errors of the
+//! wrapped serializer with [ser::Error::custom].
+
+use crate::{DatalogExtractor, DatalogExtractorBackend, ElemType};
+use serde::ser::{self, Serialize, Serializer};
+use std::cell::RefCell;
+
+/// Implementation of [serde::Serializer] that forwards values to a wrapped
+/// serializer while extracting facts about them with a [DatalogExtractor].
+/// The result of serialization is the result of the wrapped serializer.
+pub struct TeeSerializer<'a, S, B: DatalogExtractorBackend> {
+    inner: S,
+    extractor: &'a mut DatalogExtractor<B>,
+}
+
+impl<'a, S: Serializer, B: DatalogExtractorBackend> TeeSerializer<'a, S, B> {
+    pub fn new(inner: S, extractor: &'a mut DatalogExtractor<B>) -> Self {
+        TeeSerializer { inner, extractor }
+    }
+}
+
+/// A value passed to the wrapped serializer, which extracts facts about the
+/// value the first time the wrapped serializer serializes it.
+struct TeeValue<'a, T: ?Sized, B: DatalogExtractorBackend> {
+    value: &'a T,
+    extractor: RefCell<Option<&'a mut DatalogExtractor<B>>>,
+}
+
+impl<T: ?Sized + Serialize, B: DatalogExtractorBackend> Serialize for TeeValue<'_, T, B> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        // serializers may serialize a value more than once (e.g. to compute
+        // its size first), but facts are only extracted once
+        let extractor = self.extractor.borrow_mut().take();
+        match extractor {
+            Some(extractor) => self
+                .value
+                .serialize(TeeSerializer::new(serializer, extractor)),
+            None => self.value.serialize(serializer),
+        }
+    }
+}
+
+/// Pass `value` to the wrapped serializer with `forward`, extracting facts
+/// about it along the way. If the wrapped serializer does not serialize the
+/// value, facts are extracted from the value directly.
+fn tee<T, B, R, E>(
+    extractor: &mut DatalogExtractor<B>,
+    value: &T,
+    forward: impl FnOnce(&TeeValue<'_, T, B>) -> Result<R, E>,
+) -> Result<R, E>
+where
+    T: ?Sized + Serialize,
+    B: DatalogExtractorBackend,
+    E: ser::Error,
+{
+    let tee_value = TeeValue {
+        value,
+        extractor: RefCell::new(Some(extractor)),
+    };
+    let res = forward(&tee_value)?;
+
+    if let Some(extractor) = tee_value.extractor.into_inner() {
+        value.serialize(extractor).map_err(E::custom)?;
+    }
+
+    Result::Ok(res)
+}
+
+macro_rules! tee_primitive {
+    ($method:ident, $ty:ty) => {
+        fn $method(self, value: $ty) -> Result<S::Ok, S::Error> {
+            self.extractor.$method(value).map_err(ser::Error::custom)?;
+            self.inner.$method(value)
+        }
+    };
+}
+
+impl<'a, S: Serializer, B: DatalogExtractorBackend> Serializer for TeeSerializer<'a, S, B> {
+    type Ok = S::Ok;
+    type Error = S::Error;
+
+    type SerializeSeq = TeeCompound<'a, S::SerializeSeq, B>;
+    type SerializeTuple = TeeCompound<'a, S::SerializeTuple, B>;
+    type SerializeTupleStruct = TeeCompound<'a, S::SerializeTupleStruct, B>;
+    type SerializeTupleVariant = TeeCompound<'a, S::SerializeTupleVariant, B>;
+    type SerializeMap = TeeCompound<'a, S::SerializeMap, B>;
+    type SerializeStruct = TeeCompound<'a, S::SerializeStruct, B>;
+    type SerializeStructVariant = TeeCompound<'a, S::SerializeStructVariant, B>;
+
+    tee_primitive!(serialize_bool, bool);
+    tee_primitive!(serialize_i8, i8);
+    tee_primitive!(serialize_i16, i16);
+    tee_primitive!(serialize_i32, i32);
+    tee_primitive!(serialize_i64, i64);
+    tee_primitive!(serialize_u8, u8);
+    tee_primitive!(serialize_u16, u16);
+    tee_primitive!(serialize_u32, u32);
+    tee_primitive!(serialize_u64, u64);
+    tee_primitive!(serialize_f32, f32);
+    tee_primitive!(serialize_f64, f64);
+    tee_primitive!(serialize_char, char);
+    tee_primitive!(serialize_str, &str);
+    tee_primitive!(serialize_bytes, &[u8]);
+
+    fn serialize_none(self) -> Result<S::Ok, S::Error> {
+        self.extractor
+            .serialize_none()
+            .map_err(ser::Error::custom)?;
+        self.inner.serialize_none()
+    }
+
+    fn serialize_some<T: ?Sized + Serialize>(self, value: &T) -> Result<S::Ok, S::Error> {
+        let inner = self.inner;
+        self.extractor.begin_value().map_err(ser::Error::custom)?;
+        let res = tee(self.extractor, value, |value| inner.serialize_some(value))?;
+        self.extractor
+            .end_newtype_variant("Option", "Some")
+            .map_err(ser::Error::custom)?;
+        Result::Ok(res)
+    }
+
+    fn serialize_unit(self) -> Result<S::Ok, S::Error> {
+        self.extractor
+            .serialize_unit()
+            .map_err(ser::Error::custom)?;
+        self.inner.serialize_unit()
+    }
+
+    fn serialize_unit_struct(self, name: &'static str) -> Result<S::Ok, S::Error> {
+        self.extractor
+            .serialize_unit_struct(name)
+            .map_err(ser::Error::custom)?;
+        self.inner.serialize_unit_struct(name)
+    }
+
+    fn serialize_unit_variant(
+        self,
+        name: &'static str,
+        variant_index: u32,
+        variant: &'static str,
+    ) -> Result<S::Ok, S::Error> {
+        self.extractor
+            .serialize_unit_variant(name, variant_index, variant)
+            .map_err(ser::Error::custom)?;
+        self.inner
+            .serialize_unit_variant(name, variant_index, variant)
+    }
+
+    fn serialize_newtype_struct<T: ?Sized + Serialize>(
+        self,
+        name: &'static str,
+        value: &T,
+    ) -> Result<S::Ok, S::Error> {
+        let inner = self.inner;
+        self.extractor.begin_value().map_err(ser::Error::custom)?;
+        let res = tee(self.extractor, value, |value| {
+            inner.serialize_newtype_struct(name, value)
+        })?;
+        self.extractor
+            .end_newtype_struct(name)
+            .map_err(ser::Error::custom)?;
+        Result::Ok(res)
+    }
+
+    fn serialize_newtype_variant<T: ?Sized + Serialize>(
+        self,
+        name: &'static str,
+        variant_index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<S::Ok, S::Error> {
+        let inner = self.inner;
+        self.extractor.begin_value().map_err(ser::Error::custom)?;
+        let res = tee(self.extractor, value, |value| {
+            inner.serialize_newtype_variant(name, variant_index, variant, value)
+        })?;
+        self.extractor
+            .end_newtype_variant(name, variant)
+            .map_err(ser::Error::custom)?;
+        Result::Ok(res)
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<Self::SerializeSeq, S::Error> {
+        Result::Ok(TeeCompound {
+            extractor: self
+                .extractor
+                .serialize_seq(len)
+                .map_err(ser::Error::custom)?,
+            inner: self.inner.serialize_seq(len)?,
+        })
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<Self::SerializeTuple, S::Error> {
+        Result::Ok(TeeCompound {
+            extractor: self
+                .extractor
+                .serialize_tuple(len)
+                .map_err(ser::Error::custom)?,
+            inner: self.inner.serialize_tuple(len)?,
+        })
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        name: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeTupleStruct, S::Error> {
+        Result::Ok(TeeCompound {
+            extractor: self
+                .extractor
+                .serialize_tuple_struct(name, len)
+                .map_err(ser::Error::custom)?,
+            inner: self.inner.serialize_tuple_struct(name, len)?,
+        })
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        name: &'static str,
+        variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeTupleVariant, S::Error> {
+        Result::Ok(TeeCompound {
+            extractor: self
+                .extractor
+                .serialize_tuple_variant(name, variant_index, variant, len)
+                .map_err(ser::Error::custom)?,
+            inner: self
+                .inner
+                .serialize_tuple_variant(name, variant_index, variant, len)?,
+        })
+    }
+
+    fn serialize_map(self, len: Option<usize>) -> Result<Self::SerializeMap, S::Error> {
+        Result::Ok(TeeCompound {
+            extractor: self
+                .extractor
+                .serialize_map(len)
+                .map_err(ser::Error::custom)?,
+            inner: self.inner.serialize_map(len)?,
+        })
+    }
+
+    fn serialize_struct(
+        self,
+        name: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeStruct, S::Error> {
+        Result::Ok(TeeCompound {
+            extractor: self
+                .extractor
+                .serialize_struct(name, len)
+                .map_err(ser::Error::custom)?,
+            inner: self.inner.serialize_struct(name, len)?,
+        })
+    }
+
+    fn serialize_struct_variant(
+        self,
+        name: &'static str,
+        variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeStructVariant, S::Error> {
+        Result::Ok(TeeCompound {
+            extractor: self
+                .extractor
+                .serialize_struct_variant(name, variant_index, variant, len)
+                .map_err(ser::Error::custom)?,
+            inner: self
+                .inner
+                .serialize_struct_variant(name, variant_index, variant, len)?,
+        })
+    }
+
+    /// Values are serialized once for both the wrapped serializer and the
+    /// extractor, so they are serialized in the wrapped serializer's format.
+    fn is_human_readable(&self) -> bool {
+        self.inner.is_human_readable()
+    }
+}
+
+/// Serializer of the entries of a compound value, such as a sequence or
+/// struct, for [TeeSerializer].
+pub struct TeeCompound<'a, S, B: DatalogExtractorBackend> {
+    inner: S,
+    extractor: &'a mut DatalogExtractor<B>,
+}
+
+impl<S: ser::SerializeSeq, B: DatalogExtractorBackend> ser::SerializeSeq for TeeCompound<'_, S, B> {
+    type Ok = S::Ok;
+    type Error = S::Error;
+
+    fn serialize_element<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), S::Error> {
+        let inner = &mut self.inner;
+        tee(self.extractor, value, |value| {
+            inner.serialize_element(value)
+        })?;
+        self.extractor
+            .end_tuple_or_seq_entry(ElemType::Seq)
+            .map_err(ser::Error::custom)
+    }
+
+    fn end(self) -> Result<S::Ok, S::Error> {
+        ser::SerializeSeq::end(self.extractor).map_err(ser::Error::custom)?;
+        self.inner.end()
+    }
+}
+
+impl<S: ser::SerializeTuple, B: DatalogExtractorBackend> ser::SerializeTuple
+    for TeeCompound<'_, S, B>
+{
+    type Ok = S::Ok;
+    type Error = S::Error;
+
+    fn serialize_element<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), S::Error> {
+        let inner = &mut self.inner;
+        tee(self.extractor, value, |value| {
+            inner.serialize_element(value)
+        })?;
+        self.extractor
+            .end_tuple_or_seq_entry(ElemType::Tuple)
+            .map_err(ser::Error::custom)
+    }
+
+    fn end(self) -> Result<S::Ok, S::Error> {
+        ser::SerializeTuple::end(self.extractor).map_err(ser::Error::custom)?;
+        self.inner.end()
+    }
+}
+
+impl<S: ser::SerializeTupleStruct, B: DatalogExtractorBackend> ser::SerializeTupleStruct
+    for TeeCompound<'_, S, B>
+{
+    type Ok = S::Ok;
+    type Error = S::Error;
+
+    fn serialize_field<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), S::Error> {
+        let inner = &mut self.inner;
+        tee(self.extractor, value, |value| inner.serialize_field(value))?;
+        self.extractor
+            .end_tuple_or_seq_entry(ElemType::TupleStruct)
+            .map_err(ser::Error::custom)
+    }
+
+    fn end(self) -> Result<S::Ok, S::Error> {
+        ser::SerializeTupleStruct::end(self.extractor).map_err(ser::Error::custom)?;
+        self.inner.end()
+    }
+}
+
+impl<S: ser::SerializeTupleVariant, B: DatalogExtractorBackend> ser::SerializeTupleVariant
+    for TeeCompound<'_, S, B>
+{
+    type Ok = S::Ok;
+    type Error = S::Error;
+
+    fn serialize_field<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), S::Error> {
+        let inner = &mut self.inner;
+        tee(self.extractor, value, |value| inner.serialize_field(value))?;
+        self.extractor
+            .end_tuple_or_seq_entry(ElemType::TupleVariant)
+            .map_err(ser::Error::custom)
+    }
+
+    fn end(self) -> Result<S::Ok, S::Error> {
+        ser::SerializeTupleVariant::end(self.extractor).map_err(ser::Error::custom)?;
+        self.inner.end()
+    }
+}
+
+impl<S: ser::SerializeMap, B: DatalogExtractorBackend> ser::SerializeMap for TeeCompound<'_, S, B> {
+    type Ok = S::Ok;
+    type Error = S::Error;
+
+    fn serialize_key<T: ?Sized + Serialize>(&mut self, key: &T) -> Result<(), S::Error> {
+        let inner = &mut self.inner;
+        tee(self.extractor, key, |key| inner.serialize_key(key))
+    }
+
+    fn serialize_value<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), S::Error> {
+        let inner = &mut self.inner;
+        tee(self.extractor, value, |value| inner.serialize_value(value))?;
+        self.extractor.end_map_entry().map_err(ser::Error::custom)
+    }
+
+    fn end(self) -> Result<S::Ok, S::Error> {
+        ser::SerializeMap::end(self.extractor).map_err(ser::Error::custom)?;
+        self.inner.end()
+    }
+}
+
+impl<S: ser::SerializeStruct, B: DatalogExtractorBackend> ser::SerializeStruct
+    for TeeCompound<'_, S, B>
+{
+    type Ok = S::Ok;
+    type Error = S::Error;
+
+    fn serialize_field<T: ?Sized + Serialize>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), S::Error> {
+        // skipped fields are only serialized by the wrapped serializer
+        self.extractor.see_field(key);
+        if self.extractor.skips_field(key) {
+            return self.inner.serialize_field(key, value);
+        }
+
+        let inner = &mut self.inner;
+        tee(self.extractor, value, |value| {
+            inner.serialize_field(key, value)
+        })?;
+        self.extractor
+            .end_struct_entry(key)
+            .map_err(ser::Error::custom)
+    }
+
+    fn skip_field(&mut self, key: &'static str) -> Result<(), S::Error> {
+        self.extractor
+            .skip_struct_element(key)
+            .map_err(ser::Error::custom)?;
+        self.inner.skip_field(key)
+    }
+
+    fn end(self) -> Result<S::Ok, S::Error> {
+        ser::SerializeStruct::end(self.extractor).map_err(ser::Error::custom)?;
+        self.inner.end()
+    }
+}
+
+impl<S: ser::SerializeStructVariant, B: DatalogExtractorBackend> ser::SerializeStructVariant
+    for TeeCompound<'_, S, B>
+{
+    type Ok = S::Ok;
+    type Error = S::Error;
+
+    fn serialize_field<T: ?Sized + Serialize>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), S::Error> {
+        // skipped fields are only serialized by the wrapped serializer
+        self.extractor.see_field(key);
+        if self.extractor.skips_field(key) {
+            return self.inner.serialize_field(key, value);
+        }
+
+        let inner = &mut self.inner;
+        tee(self.extractor, value, |value| {
+            inner.serialize_field(key, value)
+        })?;
+        self.extractor
+            .end_struct_entry(key)
+            .map_err(ser::Error::custom)
+    }
+
+    fn skip_field(&mut self, key: &'static str) -> Result<(), S::Error> {
+        self.extractor
+            .skip_struct_element(key)
+            .map_err(ser::Error::custom)?;
+        self.inner.skip_field(key)
+    }
+
+    fn end(self) -> Result<S::Ok, S::Error> {
+        ser::SerializeStructVariant::end(self.extractor).map_err(ser::Error::custom)?;
+        self.inner.end()
+    }
+}
@@ -0,0 +1,538 @@
+//! Property-based testing support for backends and data models.
+//!
+//! [FactRecorder] wraps a backend and records every fact the extractor
+//! generates, and [check_invariants] checks structural invariants of the
+//! recorded facts: every referenced element exists, every element has at most
+//! one parent, and the elements form trees rooted at the root elements.
+//! [check_arbitrary] extracts arbitrary values of a type and checks these
+//! invariants for each value, so backend implementers can validate their
+//! backends against many generated inputs:
+//!
+//! ```
+//! # use serde_datalog::{backend, testing};
+//! let data = [7u8; 4096];
+//! let mut u = arbitrary::Unstructured::new(&data);
+//!
+//! let summary =
+//!     testing::check_arbitrary::<Vec<(String, Option<bool>)>, _>(&mut u, 100, || {
+//!         backend::vector::Backend::default()
+//!     })
+//!     .unwrap();
+//! assert!(summary.extracted > 0);
+//! ```
+
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::{self, Debug, Display},
+    net::IpAddr,
+    ops::ControlFlow,
+};
+
+use arbitrary::{Arbitrary, Unstructured};
+use serde::Serialize;
+
+use crate::{
+    DatalogExtractionError, DatalogExtractor, DatalogExtractorBackend, ElemId, ElemKind, ElemType,
+    Fact, Result,
+};
+
+/// A backend that records every fact it receives before passing it on to
+/// the wrapped backend.
+pub struct FactRecorder<B> {
+    backend: B,
+    facts: Vec<Fact>,
+}
+
+impl<B: DatalogExtractorBackend> FactRecorder<B> {
+    pub fn new(backend: B) -> Self {
+        FactRecorder {
+            backend,
+            facts: Vec::new(),
+        }
+    }
+
+    /// Return the facts recorded so far, in the order they were received.
+    pub fn facts(&self) -> &[Fact] {
+        &self.facts
+    }
+
+    /// Return the wrapped backend.
+    pub fn get_backend(&self) -> &B {
+        &self.backend
+    }
+
+    /// Return the wrapped backend and the recorded facts.
+    pub fn into_parts(self) -> (B, Vec<Fact>) {
+        (self.backend, self.facts)
+    }
+}
+
+macro_rules! record_facts {
+    ($($method:ident($($arg:ident: $ty:ty),*) => $fact:expr;)*) => {
+        $(
+            fn $method(&mut self, $($arg: $ty),*) -> Result<()> {
+                self.facts.push($fact);
+                self.backend.$method($($arg),*)
+            }
+        )*
+    };
+}
+
+impl<B: DatalogExtractorBackend> DatalogExtractorBackend for FactRecorder<B> {
+    fn begin(&mut self) -> Result<()> {
+        self.backend.begin()
+    }
+
+    fn finish(&mut self) -> Result<()> {
+        self.backend.finish()
+    }
+
+    fn add_facts(&mut self, batch: &[Fact]) -> Result<()> {
+        self.facts.extend_from_slice(batch);
+        self.backend.add_facts(batch)
+    }
+
+    fn add_elem(&mut self, elem: ElemId, elem_type: ElemType) -> Result<()> {
+        self.facts.push(Fact::Elem {
+            elem,
+            elem_type: elem_type.clone(),
+        });
+        self.backend.add_elem(elem, elem_type)
+    }
+
+    record_facts! {
+        add_root_elem(file: &str, elem: ElemId) => Fact::RootElem { file: file.to_string(), elem };
+        add_file_format(file: &str, format: &str) => Fact::FileFormat {
+            file: file.to_string(),
+            format: format.to_string(),
+        };
+        add_key_field(struct_name: &str, field: &str) => Fact::KeyField {
+            struct_name: struct_name.to_string(),
+            field: field.to_string(),
+        };
+        add_bool(elem: ElemId, value: bool) => Fact::Bool { elem, value };
+        add_i8(elem: ElemId, value: i8) => Fact::I8 { elem, value };
+        add_i16(elem: ElemId, value: i16) => Fact::I16 { elem, value };
+        add_i32(elem: ElemId, value: i32) => Fact::I32 { elem, value };
+        add_i64(elem: ElemId, value: i64) => Fact::I64 { elem, value };
+        add_u8(elem: ElemId, value: u8) => Fact::U8 { elem, value };
+        add_u16(elem: ElemId, value: u16) => Fact::U16 { elem, value };
+        add_u32(elem: ElemId, value: u32) => Fact::U32 { elem, value };
+        add_u64(elem: ElemId, value: u64) => Fact::U64 { elem, value };
+        add_f32(elem: ElemId, value: f32) => Fact::F32 { elem, value };
+        add_f64(elem: ElemId, value: f64) => Fact::F64 { elem, value };
+        add_char(elem: ElemId, value: char) => Fact::Char { elem, value };
+        add_str(elem: ElemId, value: &str) => Fact::Str { elem, value: value.to_string() };
+        add_full_str(elem: ElemId, value: &str) => Fact::FullStr { elem, value: value.to_string() };
+        add_bytes(elem: ElemId, value: &[u8]) => Fact::Bytes { elem, value: value.to_vec() };
+        add_map_entry(elem: ElemId, key: ElemId, value: ElemId) => Fact::MapEntry { elem, key, value };
+        add_struct_type(elem: ElemId, struct_name: &str) => Fact::StructType {
+            elem,
+            struct_name: struct_name.to_string(),
+        };
+        add_struct_entry(elem: ElemId, key: &str, value: ElemId) => Fact::StructEntry {
+            elem,
+            key: key.to_string(),
+            value,
+        };
+        add_key(elem: ElemId, key: ElemId) => Fact::Key { elem, key };
+        add_seq_entry(elem: ElemId, pos: usize, value: ElemId) => Fact::SeqEntry { elem, pos, value };
+        add_variant_type(elem: ElemId, type_name: &str, variant_name: &str) => Fact::VariantType {
+            elem,
+            type_name: type_name.to_string(),
+            variant_name: variant_name.to_string(),
+        };
+        add_tuple_entry(elem: ElemId, pos: usize, value: ElemId) => Fact::TupleEntry { elem, pos, value };
+        add_schema_type(elem: ElemId, schema_ref: &str) => Fact::SchemaType {
+            elem,
+            schema_ref: schema_ref.to_string(),
+        };
+        add_datetime(elem: ElemId, epoch_micros: i64, tz_offset: i32) => Fact::Datetime {
+            elem,
+            epoch_micros,
+            tz_offset,
+        };
+        add_uuid(elem: ElemId, hi: u64, lo: u64) => Fact::Uuid { elem, hi, lo };
+        add_ip(elem: ElemId, address: IpAddr) => Fact::Ip { elem, address };
+        add_url(elem: ElemId, scheme: &str, host: &str) => Fact::Url {
+            elem,
+            scheme: scheme.to_string(),
+            host: host.to_string(),
+        };
+        add_email(elem: ElemId, local: &str, domain: &str) => Fact::Email {
+            elem,
+            local: local.to_string(),
+            domain: domain.to_string(),
+        };
+        add_declared_len(elem: ElemId, len: usize) => Fact::DeclaredLen { elem, len };
+        add_absent_field(elem: ElemId, field: &str) => Fact::AbsentField {
+            elem,
+            field: field.to_string(),
+        };
+        add_unit(elem: ElemId) => Fact::Unit { elem };
+        add_truncated(relation: &str, quota: usize) => Fact::Truncated {
+            relation: relation.to_string(),
+            quota,
+        };
+        add_redacted(elem: ElemId) => Fact::Redacted { elem };
+        add_sampled(elem: ElemId, len: usize, kept: usize) => Fact::Sampled { elem, len, kept };
+        add_elem_type_kind(elem_type: ElemType, kind: ElemKind) => Fact::ElemTypeKind {
+            elem_type: elem_type.clone(),
+            kind,
+        };
+        add_same_string(elem: ElemId, other: ElemId) => Fact::SameString { elem, other };
+        add_shape(elem: ElemId, shape: u64) => Fact::Shape { elem, shape };
+        add_shape_field(shape: u64, pos: usize, field: &str) => Fact::ShapeField {
+            shape,
+            pos,
+            field: field.to_string(),
+        };
+        add_parse_error(file: &str, line: usize, message: &str) => Fact::ParseError {
+            file: file.to_string(),
+            line,
+            message: message.to_string(),
+        };
+        add_string_hash(elem: ElemId, hash: u64, len: usize) => Fact::StringHash { elem, hash, len };
+        add_root_meta(file: &str, key: &str, value: &str) => Fact::RootMeta {
+            file: file.to_string(),
+            key: key.to_string(),
+            value: value.to_string(),
+        };
+        add_depth(elem: ElemId, depth: usize) => Fact::Depth { elem, depth };
+        add_subtree_size(elem: ElemId, size: usize) => Fact::SubtreeSize { elem, size };
+        add_codepoint(elem: ElemId, codepoint: u32) => Fact::Codepoint { elem, codepoint };
+    }
+}
+
+/// A structural invariant violated by extracted facts.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum InvariantViolation {
+    /// A fact references an element without an element type.
+    MissingElem {
+        relation: &'static str,
+        elem: ElemId,
+    },
+
+    /// An element was given more than one element type.
+    DuplicateElem(ElemId),
+
+    /// An element is an entry of more than one element.
+    MultipleParents(ElemId),
+
+    /// The number of elements that are not entries of another element
+    /// differs from the number of root elements.
+    RootCount { expected: usize, found: usize },
+
+    /// An element is not reachable from a root element, so it is part of
+    /// a cycle of entries.
+    Cycle(ElemId),
+}
+
+impl Display for InvariantViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InvariantViolation::MissingElem { relation, elem } => {
+                write!(f, "{} fact references missing element {}", relation, elem)
+            }
+
+            InvariantViolation::DuplicateElem(elem) => {
+                write!(f, "element {} has more than one type", elem)
+            }
+
+            InvariantViolation::MultipleParents(elem) => {
+                write!(f, "element {} has more than one parent", elem)
+            }
+
+            InvariantViolation::RootCount { expected, found } => {
+                write!(f, "expected {} root element(s), found {}", expected, found)
+            }
+
+            InvariantViolation::Cycle(elem) => {
+                write!(f, "element {} is not reachable from a root element", elem)
+            }
+        }
+    }
+}
+
+/// The relation of a fact and the elements it references, along with the
+/// element it makes a child of the fact's element, if any.
+fn referenced_elems(fact: &Fact) -> (&'static str, Vec<ElemId>, Option<ElemId>) {
+    match fact {
+        Fact::RootElem { elem, .. } => ("rootElem", vec![*elem], None),
+        Fact::FileFormat { .. } | Fact::KeyField { .. } => ("file", vec![], None),
+        Fact::Elem { elem, .. } => ("type", vec![*elem], None),
+        Fact::Bool { elem, .. } => ("bool", vec![*elem], None),
+        Fact::I8 { elem, .. }
+        | Fact::I16 { elem, .. }
+        | Fact::I32 { elem, .. }
+        | Fact::I64 { elem, .. }
+        | Fact::U8 { elem, .. }
+        | Fact::U16 { elem, .. }
+        | Fact::U32 { elem, .. }
+        | Fact::U64 { elem, .. }
+        | Fact::F32 { elem, .. }
+        | Fact::F64 { elem, .. } => ("number", vec![*elem], None),
+        Fact::Char { elem, .. } | Fact::Str { elem, .. } => ("string", vec![*elem], None),
+        Fact::FullStr { elem, .. } => ("fullString", vec![*elem], None),
+        Fact::Bytes { elem, .. } => ("bytes", vec![*elem], None),
+        Fact::MapEntry { elem, key, value } => ("map", vec![*elem, *key, *value], None),
+        Fact::StructType { elem, .. } => ("structType", vec![*elem], None),
+        Fact::StructEntry { elem, value, .. } => ("struct", vec![*elem, *value], Some(*value)),
+        Fact::Key { elem, key } => ("key", vec![*elem, *key], None),
+        Fact::SeqEntry { elem, value, .. } => ("seq", vec![*elem, *value], Some(*value)),
+        Fact::VariantType { elem, .. } => ("variantType", vec![*elem], None),
+        Fact::TupleEntry { elem, value, .. } => ("tuple", vec![*elem, *value], Some(*value)),
+        Fact::SchemaType { elem, .. } => ("schemaType", vec![*elem], None),
+        Fact::Datetime { elem, .. } => ("datetime", vec![*elem], None),
+        Fact::Uuid { elem, .. } => ("uuid", vec![*elem], None),
+        Fact::Ip { elem, .. } => ("ip", vec![*elem], None),
+        Fact::Url { elem, .. } => ("url", vec![*elem], None),
+        Fact::Email { elem, .. } => ("email", vec![*elem], None),
+        Fact::DeclaredLen { elem, .. } => ("declaredLen", vec![*elem], None),
+        Fact::AbsentField { elem, .. } => ("absentField", vec![*elem], None),
+        Fact::Unit { elem } => ("unit", vec![*elem], None),
+        Fact::Truncated { .. } => ("truncated", vec![], None),
+        Fact::Redacted { elem } => ("redacted", vec![*elem], None),
+        Fact::Sampled { elem, .. } => ("sampled", vec![*elem], None),
+        Fact::ElemTypeKind { .. } => ("elemTypeKind", vec![], None),
+        Fact::SameString { elem, other } => ("sameString", vec![*elem, *other], None),
+        Fact::Shape { elem, .. } => ("shape", vec![*elem], None),
+        Fact::ShapeField { .. } => ("shapeField", vec![], None),
+        Fact::ParseError { .. } => ("parseError", vec![], None),
+        Fact::StringHash { elem, .. } => ("stringHash", vec![*elem], None),
+        Fact::RootMeta { .. } => ("rootMeta", vec![], None),
+        Fact::Depth { elem, .. } => ("depth", vec![*elem], None),
+        Fact::SubtreeSize { elem, .. } => ("subtreeSize", vec![*elem], None),
+        Fact::Codepoint { elem, .. } => ("char", vec![*elem], None),
+    }
+}
+
+/// Check structural invariants of `facts`, which are facts generated by the
+/// extractor, e.g. recorded with [FactRecorder]:
+///
+/// - every element referenced by a fact has exactly one element type;
+/// - every element is an entry (a map key or value, a struct field, or a
+///   sequence or tuple item) of at most one other element, except unit and
+///   unit struct elements, which are shared with
+///   [UnitPolicy::Singletons][crate::unit_policy::UnitPolicy::Singletons];
+/// - the elements that are not entries of other elements are the root
+///   elements, i.e. one for each [root element fact][Fact::RootElem], or
+///   exactly one if there are no root element facts;
+/// - every element is reachable from a root element, so entries are acyclic.
+///
+/// Returns all violated invariants.
+pub fn check_invariants(facts: &[Fact]) -> std::result::Result<(), Vec<InvariantViolation>> {
+    let mut violations = Vec::new();
+
+    let mut elems = HashSet::new();
+    let mut unit_elems = HashSet::new();
+    for fact in facts.iter() {
+        if let Fact::Elem { elem, elem_type } = fact {
+            if !elems.insert(*elem) {
+                violations.push(InvariantViolation::DuplicateElem(*elem));
+            }
+
+            if matches!(elem_type, ElemType::Unit | ElemType::UnitStruct) {
+                unit_elems.insert(*elem);
+            }
+        }
+    }
+
+    let mut parents: HashMap<ElemId, ElemId> = HashMap::new();
+    let mut children: HashMap<ElemId, Vec<ElemId>> = HashMap::new();
+    let mut root_facts = 0;
+    for fact in facts.iter() {
+        let (relation, referenced, child) = referenced_elems(fact);
+        for elem in referenced.iter() {
+            if !elems.contains(elem) {
+                violations.push(InvariantViolation::MissingElem {
+                    relation,
+                    elem: *elem,
+                });
+            }
+        }
+
+        if let Fact::RootElem { .. } = fact {
+            root_facts += 1;
+        }
+
+        // map keys are entries of their map, along with map values
+        let entries = match fact {
+            Fact::MapEntry { elem, key, value } => vec![(*elem, *key), (*elem, *value)],
+            _ => child
+                .map(|child| (referenced[0], child))
+                .into_iter()
+                .collect(),
+        };
+        for (parent, child) in entries {
+            if parents.insert(child, parent).is_some() && !unit_elems.contains(&child) {
+                violations.push(InvariantViolation::MultipleParents(child));
+            }
+            children.entry(parent).or_default().push(child);
+        }
+    }
+
+    let mut roots: Vec<ElemId> = elems
+        .iter()
+        .filter(|elem| !parents.contains_key(elem))
+        .copied()
+        .collect();
+    roots.sort();
+
+    let expected_roots = root_facts.max(1);
+    if !elems.is_empty() && roots.len() != expected_roots {
+        violations.push(InvariantViolation::RootCount {
+            expected: expected_roots,
+            found: roots.len(),
+        });
+    }
+
+    let mut reachable = HashSet::new();
+    let mut stack = roots;
+    while let Some(elem) = stack.pop() {
+        if reachable.insert(elem) {
+            stack.extend(children.get(&elem).into_iter().flatten());
+        }
+    }
+
+    let mut unreachable: Vec<ElemId> = elems.difference(&reachable).copied().collect();
+    unreachable.sort();
+    violations.extend(unreachable.into_iter().map(InvariantViolation::Cycle));
+
+    if violations.is_empty() {
+        std::result::Result::Ok(())
+    } else {
+        std::result::Result::Err(violations)
+    }
+}
+
+/// Number of values generated and extracted by [check_arbitrary].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CheckSummary {
+    /// Number of generated values.
+    pub generated: usize,
+
+    /// Number of generated values that could be extracted. The others were
+    /// rejected by the extractor or backend, e.g. because they contain data
+    /// the backend cannot extract.
+    pub extracted: usize,
+}
+
+/// A generated value for which [check_arbitrary] failed.
+#[derive(Debug)]
+pub enum CheckFailure {
+    /// The facts extracted from the value violate structural invariants.
+    Violations {
+        value: String,
+        violations: Vec<InvariantViolation>,
+    },
+
+    /// Extracting the value failed with an unexpected error.
+    Error {
+        value: String,
+        error: DatalogExtractionError,
+    },
+}
+
+impl Display for CheckFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CheckFailure::Violations { value, violations } => {
+                writeln!(f, "facts extracted from {} violate invariants:", value)?;
+                for violation in violations.iter() {
+                    writeln!(f, "- {}", violation)?;
+                }
+                fmt::Result::Ok(())
+            }
+
+            CheckFailure::Error { value, error } => {
+                write!(f, "extracting {} failed: {}", value, error)
+            }
+        }
+    }
+}
+
+impl std::error::Error for CheckFailure {}
+
+/// Generate up to `max_values` arbitrary values of type `T` from `u`, extract
+/// each with a fresh backend created by `make_backend`, and check the
+/// invariants of the extracted facts with [check_invariants].
+///
+/// Values rejected by the extractor or backend as unextractable, or because
+/// of nonunique or overflowing identifiers, are skipped; other extraction
+/// errors are failures.
+pub fn check_arbitrary<T, B>(
+    u: &mut Unstructured,
+    max_values: usize,
+    make_backend: impl FnMut() -> B,
+) -> std::result::Result<CheckSummary, CheckFailure>
+where
+    T: for<'a> Arbitrary<'a> + Serialize + Debug,
+    B: DatalogExtractorBackend,
+{
+    check_arbitrary_with(u, max_values, make_backend, |_: &T, _: B| {})
+}
+
+/// Like [check_arbitrary], but also calls `on_extracted` with each value
+/// whose facts satisfy the invariants and the backend it was extracted with,
+/// e.g. to check the facts stored by the backend.
+pub fn check_arbitrary_with<T, B>(
+    u: &mut Unstructured,
+    max_values: usize,
+    mut make_backend: impl FnMut() -> B,
+    mut on_extracted: impl FnMut(&T, B),
+) -> std::result::Result<CheckSummary, CheckFailure>
+where
+    T: for<'a> Arbitrary<'a> + Serialize + Debug,
+    B: DatalogExtractorBackend,
+{
+    let mut summary = CheckSummary::default();
+    let mut failure = None;
+
+    let max_values = u32::try_from(max_values).unwrap_or(u32::MAX);
+    let _ = u.arbitrary_loop(None, Some(max_values), |u| {
+        let value = match T::arbitrary(u) {
+            arbitrary::Result::Ok(value) => value,
+            arbitrary::Result::Err(_) => return arbitrary::Result::Ok(ControlFlow::Continue(())),
+        };
+        summary.generated += 1;
+
+        let mut extractor = DatalogExtractor::new(FactRecorder::new(make_backend()));
+        match value.serialize(&mut extractor) {
+            Result::Ok(()) => {}
+
+            Result::Err(DatalogExtractionError::UnextractableData(_))
+            | Result::Err(DatalogExtractionError::NonuniqueRootElement(_))
+            | Result::Err(DatalogExtractionError::NonuniqueIdentifier(_))
+            | Result::Err(DatalogExtractionError::IntegerCastOverflow(_)) => {
+                return arbitrary::Result::Ok(ControlFlow::Continue(()));
+            }
+
+            Result::Err(error) => {
+                failure = Some(CheckFailure::Error {
+                    value: format!("{:?}", value),
+                    error,
+                });
+                return arbitrary::Result::Ok(ControlFlow::Break(()));
+            }
+        }
+
+        let (backend, facts) = extractor.get_backend().into_parts();
+        if let std::result::Result::Err(violations) = check_invariants(&facts) {
+            failure = Some(CheckFailure::Violations {
+                value: format!("{:?}", value),
+                violations,
+            });
+            return arbitrary::Result::Ok(ControlFlow::Break(()));
+        }
+
+        summary.extracted += 1;
+        on_extracted(&value, backend);
+        arbitrary::Result::Ok(ControlFlow::Continue(()))
+    });
+
+    match failure {
+        Some(failure) => std::result::Result::Err(failure),
+        None => std::result::Result::Ok(summary),
+    }
+}
@@ -0,0 +1,32 @@
+//! Policies for extracting unit values, i.e. `()`, JSON nulls, and unit
+//! structs, which have no value of their own.
+
+/// How [DatalogExtractor][crate::DatalogExtractor] extracts unit and unit
+/// struct values. By default, each unit value is an element that has an
+/// element type but no other facts, so queries can only tell that a value is
+/// unit by its element type, e.g. whether a JSON field is `null`:
+///
+/// ```text
+/// isNull(id) :- struct(_, "email", id), type(id, "Unit").
+/// ```
+///
+/// Other policies materialize unit values explicitly, or share one element
+/// between unit values of the same type to save space.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum UnitPolicy {
+    /// Extract each unit value as an element with no facts other than its
+    /// element type and, for unit structs, its struct type.
+    #[default]
+    Elements,
+
+    /// Extract each unit value as an element, and also materialize it with
+    /// [add_unit][crate::DatalogExtractorBackend::add_unit], e.g. in the
+    /// `unit(id)` relation, so that queries can match unit values directly.
+    Facts,
+
+    /// Extract all unit values of the same type in an input file as one
+    /// shared element: one for `()` and one for each unit struct type. Shared
+    /// elements are entries of more than one element, so facts are no longer
+    /// a tree. Root values that are unit are not shared.
+    Singletons,
+}
@@ -0,0 +1,286 @@
+//! Integrity checks for databases of facts generated by the Souffle SQLite
+//! backends.
+//!
+//! [validate] checks that a database is internally consistent, e.g. after it
+//! was appended to or edited by other tools:
+//!
+//! - every element and symbol referenced by a fact exists, according to the
+//!   foreign keys of the tables, and root elements and element types refer to
+//!   existing elements and symbols;
+//! - every root element is the root of one file, and is not an entry of
+//!   another element;
+//! - every symbol is referenced by some fact, except the names of element
+//!   types, which are always interned;
+//! - the positions of the entries of each sequence and tuple are dense, i.e.
+//!   they are `0` to `n - 1` for `n` entries.
+
+use std::fmt::{self, Display};
+
+use crate::{
+    backend::{souffle_sqlite::SchemaConfig, vector::ELEM_TYPE_NAMES},
+    Result,
+};
+
+/// Maximum number of offending identifiers recorded for each violation.
+const MAX_EXAMPLES: usize = 10;
+
+/// Kinds of integrity violations found by [validate].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ViolationKind {
+    /// A fact references an element that has no element type.
+    MissingElem,
+
+    /// A fact references a symbol that is not in the symbol table.
+    MissingSymbol,
+
+    /// An element is the root element of more than one file.
+    DuplicateRoot,
+
+    /// A root element is an entry of another element.
+    NestedRoot,
+
+    /// A symbol is not referenced by any fact.
+    OrphanSymbol,
+
+    /// The positions of the entries of a sequence or tuple have gaps.
+    SparsePositions,
+}
+
+impl Display for ViolationKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            ViolationKind::MissingElem => "missing element",
+            ViolationKind::MissingSymbol => "missing symbol",
+            ViolationKind::DuplicateRoot => "duplicate root",
+            ViolationKind::NestedRoot => "nested root",
+            ViolationKind::OrphanSymbol => "orphan symbol",
+            ViolationKind::SparsePositions => "sparse positions",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// Violation of an integrity constraint by the rows of a table.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Violation {
+    pub kind: ViolationKind,
+
+    /// Table whose rows violate the constraint.
+    pub table: String,
+
+    /// Column of the table whose values violate the constraint.
+    pub column: String,
+
+    /// Number of offending values.
+    pub count: usize,
+
+    /// Up to 10 offending values, i.e. element or symbol identifiers.
+    pub examples: Vec<i64>,
+}
+
+impl Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let examples: Vec<String> = self.examples.iter().map(|id| id.to_string()).collect();
+        write!(
+            f,
+            "{}: {} value(s) in {}.{}, e.g. {}",
+            self.kind,
+            self.count,
+            self.table,
+            self.column,
+            examples.join(", ")
+        )
+    }
+}
+
+/// Violations found by [validate].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ValidationReport {
+    pub violations: Vec<Violation>,
+}
+
+impl ValidationReport {
+    /// Whether the database has no violations.
+    pub fn is_valid(&self) -> bool {
+        self.violations.is_empty()
+    }
+}
+
+impl Display for ValidationReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_valid() {
+            return writeln!(f, "no violations found");
+        }
+
+        for violation in self.violations.iter() {
+            writeln!(f, "{}", violation)?;
+        }
+        writeln!(f, "{} violation(s) found", self.violations.len())
+    }
+}
+
+/// Check the integrity of the database of `conn`, whose tables are named
+/// according to `schema`, and report all violations.
+pub fn validate(conn: &rusqlite::Connection, schema: &SchemaConfig) -> Result<ValidationReport> {
+    let symbol_table = schema.symbol_table_name();
+    let type_table = schema.table_name("type");
+    let root_table = schema.table_name("rootElem");
+
+    let mut tables: Vec<String> = conn
+        .prepare("SELECT name FROM sqlite_master WHERE type = 'table' ORDER BY name;")?
+        .query_map((), |row| row.get(0))?
+        .collect::<rusqlite::Result<_>>()?;
+
+    // only tables of relations are checked, e.g. not the symbol table or
+    // tables of other applications
+    let shared_prefix = schema.table_name("_");
+    tables.retain(|table| {
+        table.starts_with(&schema.table_prefix) && !table.starts_with(&shared_prefix)
+    });
+
+    let mut report = ValidationReport::default();
+    let mut check =
+        |kind: ViolationKind, table: &str, column: &str, sql: String| -> rusqlite::Result<()> {
+            let values: Vec<i64> = conn
+                .prepare(&sql)?
+                .query_map((), |row| row.get(0))?
+                .collect::<rusqlite::Result<_>>()?;
+
+            if !values.is_empty() {
+                report.violations.push(Violation {
+                    kind,
+                    table: table.to_string(),
+                    column: column.to_string(),
+                    count: values.len(),
+                    examples: values.into_iter().take(MAX_EXAMPLES).collect(),
+                });
+            }
+            rusqlite::Result::Ok(())
+        };
+
+    // references to elements and symbols, declared as foreign keys, and the
+    // columns of symbols that are referenced
+    let mut symbol_columns = vec![(root_table.clone(), "file".to_string())];
+    let mut entry_columns: Vec<(String, String)> = Vec::new();
+    for table in tables.iter() {
+        let foreign_keys: Vec<(String, String, String)> = conn
+            .prepare(&format!(
+                "SELECT \"table\", \"from\", \"to\" FROM pragma_foreign_key_list('{}');",
+                table
+            ))?
+            .query_map((), |row| {
+                rusqlite::Result::Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+            })?
+            .collect::<rusqlite::Result<_>>()?;
+
+        for (target, column, target_column) in foreign_keys {
+            let kind = if target == symbol_table {
+                symbol_columns.push((table.clone(), column.clone()));
+                ViolationKind::MissingSymbol
+            } else {
+                if target == type_table && column != "id" {
+                    entry_columns.push((table.clone(), column.clone()));
+                }
+                ViolationKind::MissingElem
+            };
+
+            check(
+                kind,
+                table,
+                &column,
+                format!(
+                    "SELECT DISTINCT \"{column}\" FROM \"{table}\"
+                    WHERE \"{column}\" NOT IN (SELECT \"{target_column}\" FROM \"{target}\")
+                    ORDER BY 1;"
+                ),
+            )?;
+        }
+    }
+
+    // columns that reference elements and symbols without foreign keys
+    if tables.contains(&root_table) {
+        check(
+            ViolationKind::MissingElem,
+            &root_table,
+            "elem",
+            format!("SELECT DISTINCT elem FROM {root_table} WHERE elem NOT IN (SELECT id FROM {type_table}) ORDER BY 1;"),
+        )?;
+        check(
+            ViolationKind::MissingSymbol,
+            &root_table,
+            "file",
+            format!("SELECT DISTINCT file FROM {root_table} WHERE file NOT IN (SELECT id FROM {symbol_table}) ORDER BY 1;"),
+        )?;
+        check(
+            ViolationKind::DuplicateRoot,
+            &root_table,
+            "elem",
+            format!("SELECT elem FROM {root_table} GROUP BY elem HAVING COUNT(*) > 1 ORDER BY 1;"),
+        )?;
+
+        let entries: Vec<String> = entry_columns
+            .iter()
+            .map(|(table, column)| format!("SELECT \"{column}\" FROM \"{table}\""))
+            .collect();
+        if !entries.is_empty() {
+            check(
+                ViolationKind::NestedRoot,
+                &root_table,
+                "elem",
+                format!(
+                    "SELECT DISTINCT elem FROM {root_table} WHERE elem IN ({}) ORDER BY 1;",
+                    entries.join(" UNION ")
+                ),
+            )?;
+        }
+    }
+
+    if tables.contains(&type_table) {
+        symbol_columns.push((type_table.clone(), "type".to_string()));
+        check(
+            ViolationKind::MissingSymbol,
+            &type_table,
+            "type",
+            format!("SELECT DISTINCT type FROM {type_table} WHERE type NOT IN (SELECT id FROM {symbol_table}) ORDER BY 1;"),
+        )?;
+    }
+
+    let referenced: Vec<String> = symbol_columns
+        .iter()
+        .filter(|(table, _)| tables.contains(table))
+        .map(|(table, column)| format!("SELECT \"{column}\" FROM \"{table}\""))
+        .collect();
+    if !referenced.is_empty() {
+        let elem_type_names: Vec<String> = ELEM_TYPE_NAMES
+            .iter()
+            .map(|name| format!("'{}'", name))
+            .collect();
+        let elem_type_names = elem_type_names.join(", ");
+        check(
+            ViolationKind::OrphanSymbol,
+            &symbol_table,
+            "id",
+            format!(
+                "SELECT id FROM {symbol_table}
+                WHERE id NOT IN ({}) AND symbol NOT IN ({})
+                ORDER BY 1;",
+                referenced.join(" UNION "),
+                elem_type_names
+            ),
+        )?;
+    }
+
+    for relation in ["seq", "tuple"] {
+        let table = schema.table_name(relation);
+        if tables.contains(&table) {
+            check(
+                ViolationKind::SparsePositions,
+                &table,
+                "pos",
+                format!("SELECT id FROM {table} GROUP BY id HAVING MIN(pos) != 0 OR MAX(pos) != COUNT(*) - 1 ORDER BY 1;"),
+            )?;
+        }
+    }
+
+    Result::Ok(report)
+}
@@ -0,0 +1,71 @@
+//! Bindings for WebAssembly with [wasm-bindgen](https://rustwasm.github.io/wasm-bindgen/),
+//! so that browser tools can extract facts from JSON documents and visualize
+//! them. The bindings only use the in-memory
+//! [vector backend][crate::backend::vector], so they can be built without the
+//! SQLite backends:
+//!
+//! ```text
+//! cargo build --target wasm32-unknown-unknown --no-default-features -F wasm
+//! ```
+//!
+//! From JavaScript, `extractJson(file, input)` returns the facts of the JSON
+//! document `input` as a JSON object with an array of facts for each
+//! relation of the Souffle schema, e.g.
+//! `{"type": [[1, "Map"], [2, "Str"]], "map": [[1, "name", 2]], ...}`.
+
+use serde::Serialize;
+use serde_json::{Map, Value};
+use wasm_bindgen::prelude::*;
+
+use crate::{
+    backend::{
+        tuples::{self, Term},
+        vector,
+    },
+    DatalogExtractionError, DatalogExtractor, Result,
+};
+
+/// Extract facts from the JSON document `input`, read from the file named
+/// `file`, as a JSON object with an array of facts for each relation. Map
+/// keys are strings, as in [vector::StringKeyBackend].
+pub fn extract_json_facts(file: &str, input: &str) -> Result<Value> {
+    let value: Value = serde_json::from_str(input)
+        .map_err(|err| DatalogExtractionError::Custom(format!("invalid JSON: {}", err)))?;
+
+    let mut extractor = DatalogExtractor::new(vector::StringKeyBackend::default());
+    extractor.set_file(file)?;
+    extractor.set_file_format(file, "json")?;
+    value.serialize(&mut extractor)?;
+    extractor.flush()?;
+
+    let data = extractor.get_backend().get_data();
+    let mut facts = Map::new();
+    for relation in tuples::relations(&data) {
+        let rows: Vec<Value> = relation
+            .facts
+            .iter()
+            .map(|fact| Value::Array(fact.iter().map(term).collect()))
+            .collect();
+        facts.insert(relation.name.to_string(), Value::Array(rows));
+    }
+
+    Result::Ok(Value::Object(facts))
+}
+
+fn term(term: &Term) -> Value {
+    match term {
+        Term::Elem(elem) => Value::from(elem.0),
+        Term::Number(value) => Value::from(*value),
+        Term::Bool(value) => Value::from(*value),
+        Term::Name(text) | Term::Text(text) => Value::from(*text),
+        Term::Unresolved(sym) => Value::from(format!("#{}", sym)),
+    }
+}
+
+/// Extract facts from the JSON document `input`, read from the file named
+/// `file`, and return them serialized as JSON; see [extract_json_facts].
+#[wasm_bindgen(js_name = extractJson)]
+pub fn extract_json(file: &str, input: &str) -> std::result::Result<String, JsError> {
+    let facts = extract_json_facts(file, input).map_err(|err| JsError::new(&err.to_string()))?;
+    std::result::Result::Ok(facts.to_string())
+}
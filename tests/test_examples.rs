@@ -1,5 +1,12 @@
+#![cfg(feature = "sqlite")]
+// the example helpers are only used by tests of text formats
+#![cfg_attr(not(feature = "json"), allow(dead_code, unused_imports))]
+
 use serde::Serialize;
-use serde_datalog::{backend::souffle_sqlite, DatalogExtractor};
+use serde_datalog::{
+    backend::souffle_sqlite::{self, AbstractBackend},
+    DatalogExtractor,
+};
 use std::{fs, path::PathBuf};
 
 fn get_example_files(extension: &str) -> Vec<PathBuf> {
@@ -18,6 +25,14 @@ fn run_example<T: Serialize>(value: T) {
     let mut extractor = DatalogExtractor::new(souffle_sqlite);
     let res = value.serialize(&mut extractor);
     assert!(res.is_ok());
+
+    let conn = rusqlite::Connection::open_in_memory().unwrap();
+    extractor.get_backend().dump_into(&conn).unwrap();
+
+    let num_elems: usize = conn
+        .query_row("SELECT COUNT(*) FROM type;", (), |row| row.get(0))
+        .unwrap();
+    assert!(num_elems > 0);
 }
 
 fn run_examples<T: Serialize>(extension: &str, value_builder: fn(String) -> T) {
@@ -67,3 +82,1384 @@ fn run_yaml_examples() {
         serde_yaml::from_str(&input).unwrap()
     });
 }
+
+#[test]
+#[cfg(all(feature = "json", feature = "stream"))]
+fn run_json_examples_stream() {
+    use serde_datalog::backend::stream::{Backend, Granularity, WriterSink};
+
+    for file in get_example_files("json") {
+        let input = fs::read_to_string(&file).unwrap();
+        let value: serde_json::Value = serde_json::from_str(&input).unwrap();
+
+        let backend = Backend::new(WriterSink::new(Vec::new()), "facts", Granularity::Root);
+        let mut extractor = DatalogExtractor::new(backend);
+        extractor.set_file(&file.to_string_lossy()).unwrap();
+        value.serialize(&mut extractor).unwrap();
+
+        let output = extractor.get_backend().into_sink().unwrap().into_inner();
+        let messages: Vec<serde_json::Value> = output
+            .split(|b| *b == b'\n')
+            .filter(|line| !line.is_empty())
+            .map(|line| serde_json::from_slice(line).unwrap())
+            .collect();
+
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0]["file"], file.to_string_lossy().as_ref());
+        assert!(!messages[0]["facts"].as_array().unwrap().is_empty());
+    }
+}
+
+#[test]
+#[cfg(all(feature = "json", feature = "stream"))]
+fn publish_facts_between_roots() {
+    use serde_datalog::backend::stream::{Backend, Granularity, MessageSink};
+
+    /// Sink that keeps the relations of the facts of each message, and
+    /// whether it was flushed.
+    #[derive(Default)]
+    struct RecordingSink {
+        messages: Vec<(serde_json::Value, Vec<String>)>,
+        flushed: bool,
+    }
+
+    impl MessageSink for RecordingSink {
+        fn publish(&mut self, _topic: &str, payload: &[u8]) -> std::io::Result<()> {
+            assert!(!self.flushed);
+            let envelope: serde_json::Value = serde_json::from_slice(payload).unwrap();
+            let mut relations: Vec<String> = envelope["facts"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|fact| fact["relation"].as_str().unwrap().to_string())
+                .collect();
+            relations.sort();
+            relations.dedup();
+            self.messages.push((envelope["file"].clone(), relations));
+            std::io::Result::Ok(())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            self.flushed = true;
+            std::io::Result::Ok(())
+        }
+    }
+
+    let backend = Backend::new(RecordingSink::default(), "facts", Granularity::Root);
+    let mut extractor = DatalogExtractor::new(backend);
+    for (file, line) in [("a", "1"), ("b", "2")] {
+        extractor.set_file(file).unwrap();
+        true.serialize(&mut extractor).unwrap();
+        extractor.set_root_meta(file, "line", line).unwrap();
+    }
+    let sink = extractor.get_backend().into_sink().unwrap();
+
+    // metadata recorded after a root is published on its own, including that
+    // of the last root, before the sink is flushed
+    let message = |file: serde_json::Value, relations: &[&str]| {
+        (
+            file,
+            relations
+                .iter()
+                .map(|relation| relation.to_string())
+                .collect(),
+        )
+    };
+    assert!(sink.flushed);
+    assert_eq!(
+        sink.messages,
+        vec![
+            message("a".into(), &["bool", "elemTypeKind", "rootElem", "type"]),
+            message(serde_json::Value::Null, &["rootMeta"]),
+            message("b".into(), &["bool", "rootElem", "type"]),
+            message(serde_json::Value::Null, &["rootMeta"]),
+        ]
+    );
+}
+
+#[test]
+#[cfg(all(feature = "json", feature = "stream"))]
+fn run_json_examples_nats() {
+    use serde_datalog::backend::stream::{Backend, Granularity, NatsSink};
+    use std::{
+        io::{BufRead, BufReader, Read, Write},
+        net::TcpListener,
+        sync::mpsc,
+    };
+
+    let files = get_example_files("json");
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let (pinged, wait_for_ping) = mpsc::channel();
+
+    // a fake NATS server that pings the client as soon as it connects, and
+    // records the lines it receives until the client's final ping
+    let server = std::thread::spawn(move || {
+        let (mut stream, _) = listener.accept().unwrap();
+        let mut reader = BufReader::new(stream.try_clone().unwrap());
+        let mut read_line = || {
+            let mut line = String::new();
+            reader.read_line(&mut line).unwrap();
+            let line = line.trim_end().to_string();
+            if let Some(len) = line.strip_prefix("PUB facts ") {
+                let mut payload = vec![0; len.parse::<usize>().unwrap() + 2];
+                reader.read_exact(&mut payload).unwrap();
+            }
+            line
+        };
+
+        stream.write_all(b"INFO {}\r\n").unwrap();
+        assert!(read_line().starts_with("CONNECT"));
+        stream.write_all(b"PING\r\n").unwrap();
+        pinged.send(()).unwrap();
+
+        let mut lines = Vec::new();
+        loop {
+            let line = read_line();
+            if line == "PING" {
+                stream.write_all(b"PONG\r\n").unwrap();
+                return lines;
+            }
+            lines.push(line);
+        }
+    });
+
+    let sink = NatsSink::connect(addr).unwrap();
+    wait_for_ping.recv().unwrap();
+    let mut extractor = DatalogExtractor::new(Backend::new(sink, "facts", Granularity::Root));
+    for file in &files {
+        let input = fs::read_to_string(file).unwrap();
+        let value: serde_json::Value = serde_json::from_str(&input).unwrap();
+        extractor.set_file(&file.to_string_lossy()).unwrap();
+        value.serialize(&mut extractor).unwrap();
+    }
+    extractor.get_backend().into_sink().unwrap();
+
+    // the server's ping is answered before the first message is published
+    let lines = server.join().unwrap();
+    assert_eq!(lines[0], "PONG");
+    assert_eq!(lines.len(), files.len() + 1);
+    assert!(lines[1..].iter().all(|line| line.starts_with("PUB facts ")));
+}
+
+#[test]
+#[cfg(all(feature = "input_format", feature = "json"))]
+fn run_json_examples_registered_format() {
+    use serde_datalog::input_format::{DocumentValue, FormatRegistry, InputFormatData};
+
+    struct JSONData<'a>(serde_json::Deserializer<serde_json::de::StrRead<'a>>);
+
+    impl<'a> InputFormatData<'a> for JSONData<'a> {
+        fn deserializer<'de>(&'de mut self) -> Box<dyn erased_serde::Deserializer<'a> + 'de> {
+            Box::new(<dyn erased_serde::Deserializer>::erase(&mut self.0))
+        }
+    }
+
+    fn create(contents: &str) -> Result<Box<dyn InputFormatData<'_> + '_>, String> {
+        Result::Ok(Box::new(JSONData(serde_json::Deserializer::from_str(
+            contents,
+        ))))
+    }
+
+    let mut registry = FormatRegistry::with_builtin_formats();
+    registry.register_fn("custom_json", vec!["json"], true, create);
+    registry.register_fn("custom_json", vec!["json"], true, create);
+    assert_eq!(
+        registry
+            .iter()
+            .filter(|fmt| fmt.name() == "custom_json")
+            .count(),
+        1
+    );
+
+    for file in get_example_files("json") {
+        let format = registry.find_by_path(&file).unwrap();
+        assert_eq!(format.name(), "json");
+
+        let format = registry.get("custom_json").unwrap();
+        let mut source = format
+            .read(Box::new(fs::File::open(&file).unwrap()), None)
+            .unwrap();
+        let mut values = Vec::new();
+        while let Some(doc) = source.next_document() {
+            match doc.unwrap().value {
+                DocumentValue::Data(mut data) => {
+                    let value: serde_json::Value =
+                        erased_serde::deserialize(&mut data.deserializer()).unwrap();
+                    values.push(value);
+                }
+                DocumentValue::Decoded(_) => panic!("expected data to deserialize"),
+            }
+        }
+
+        assert_eq!(values.len(), 1);
+        run_example(values.pop().unwrap());
+    }
+}
+
+#[test]
+#[cfg(all(feature = "input_format", feature = "json"))]
+fn detect_json_formats() {
+    use serde_datalog::input_format::FormatRegistry;
+
+    let registry = FormatRegistry::with_builtin_formats();
+    let detect = |prefix: &[u8]| registry.detect(prefix).map(|fmt| fmt.name());
+
+    assert_eq!(detect(b"{\"a\": 1}"), Some("json"));
+    assert_eq!(detect(b"\xEF\xBB\xBF\n  [1, 2]"), Some("json"));
+    assert_eq!(detect(b"{\n  \"a\": 1\n}\n"), Some("json"));
+    assert_eq!(detect(b"{\"a\": 1}\n{\"a\": 2}\n{\"a\""), Some("ndjson"));
+    assert_eq!(detect(b"[section]\nkey = 1"), None);
+    assert_eq!(detect(b""), None);
+}
+
+#[test]
+#[cfg(all(feature = "input_format", feature = "json"))]
+fn map_extensions_to_formats() {
+    use serde_datalog::input_format::FormatRegistry;
+    use std::path::Path;
+
+    let mut registry = FormatRegistry::with_builtin_formats();
+    assert!(registry.map_extension("log", "ndjson"));
+    assert!(registry.map_extension("json", "ndjson"));
+    assert!(!registry.map_extension("conf", "no-such-format"));
+
+    let find = |path: &str| registry.find_by_path(Path::new(path)).map(|fmt| fmt.name());
+    assert_eq!(find("events.log"), Some("ndjson"));
+    assert_eq!(find("data.json"), Some("ndjson"));
+    assert_eq!(find("settings.conf"), None);
+}
+
+#[test]
+#[cfg(all(feature = "input_format", feature = "json"))]
+fn find_formats_by_media_type() {
+    use serde_datalog::input_format::FormatRegistry;
+
+    let registry = FormatRegistry::with_builtin_formats();
+    let find = |media_type: &str| {
+        registry
+            .find_by_media_type(media_type)
+            .map(|fmt| fmt.name())
+    };
+
+    assert_eq!(find("application/json"), Some("json"));
+    assert_eq!(find("Application/JSON; charset=utf-8"), Some("json"));
+    assert_eq!(find("application/ld+json"), Some("json"));
+    assert_eq!(find("application/x-ndjson"), Some("ndjson"));
+    assert_eq!(find("text/html"), None);
+}
+
+#[test]
+#[cfg(all(feature = "json", feature = "analyze"))]
+fn analyze_json_example() {
+    use serde_datalog::analyze;
+
+    let value: serde_json::Value =
+        serde_json::from_str(r#"{"boroughs": [{"name": "Bronx"}, {"name": "Queens"}]}"#).unwrap();
+    let mut extractor = DatalogExtractor::new(souffle_sqlite::StringKeyBackend::default());
+    extractor.set_file("census.json").unwrap();
+    value.serialize(&mut extractor).unwrap();
+
+    let mut conn = rusqlite::Connection::open_in_memory().unwrap();
+    extractor.get_backend().dump_into(&conn).unwrap();
+
+    let schema = souffle_sqlite::SchemaConfig::default();
+    let analysis = analyze::analyze(&conn, &schema).unwrap();
+    analysis.write_to(&mut conn, &schema).unwrap();
+
+    // the root, the list, two boroughs, and their names
+    let root: i64 = conn
+        .query_row("SELECT elem FROM rootElem;", (), |row| row.get(0))
+        .unwrap();
+    assert_eq!(analysis.reachable.len(), 6);
+    assert!(analysis.reachable.iter().all(|(r, _)| *r == root));
+    assert!(analysis.subtree_size.contains(&(root, 6)));
+
+    let depths: Vec<i64> = conn
+        .prepare("SELECT depth FROM depth ORDER BY depth;")
+        .unwrap()
+        .query_map((), |row| row.get(0))
+        .unwrap()
+        .collect::<rusqlite::Result<_>>()
+        .unwrap();
+    assert_eq!(depths, vec![0, 1, 2, 2, 3, 3]);
+}
+
+#[test]
+#[cfg(all(feature = "input_format", feature = "json"))]
+fn run_json_examples_extract_file() {
+    use serde_datalog::input_format::{extract_file, ExtractFileError};
+
+    for file in get_example_files("json") {
+        let backend =
+            extract_file(&file, None, souffle_sqlite::StringKeyBackend::default()).unwrap();
+
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        backend.dump_into(&conn).unwrap();
+
+        let root: String = conn
+            .query_row("SELECT file FROM rootElem;", (), |row| row.get(0))
+            .unwrap();
+        assert_eq!(root, file.display().to_string());
+
+        let format: String = conn
+            .query_row("SELECT format FROM fileFormat;", (), |row| row.get(0))
+            .unwrap();
+        assert_eq!(format, "json");
+    }
+
+    let mut backend = souffle_sqlite::StringKeyBackend::default();
+    let err = extract_file(&get_example_files("json")[0], Some("jsonc"), &mut backend)
+        .err()
+        .unwrap();
+    assert!(matches!(err, ExtractFileError::UnknownFormat(name) if name == "jsonc"));
+}
+
+#[test]
+#[cfg(all(feature = "json", feature = "stream"))]
+fn run_json_examples_tee() {
+    use serde_datalog::{
+        backend::stream::{Backend, Granularity, WriterSink},
+        tee::TeeSerializer,
+    };
+
+    fn extract<T: Serialize>(value: &T, tee: bool) -> (Vec<u8>, Vec<u8>) {
+        let backend = Backend::new(WriterSink::new(Vec::new()), "facts", Granularity::Root);
+        let mut extractor = DatalogExtractor::new(backend);
+        extractor.set_file("value").unwrap();
+
+        let json = if tee {
+            let mut json = serde_json::Serializer::new(Vec::new());
+            value
+                .serialize(TeeSerializer::new(&mut json, &mut extractor))
+                .unwrap();
+            json.into_inner()
+        } else {
+            value.serialize(&mut extractor).unwrap();
+            serde_json::to_vec(value).unwrap()
+        };
+
+        let facts = extractor.get_backend().into_sink().unwrap().into_inner();
+        (json, facts)
+    }
+
+    for file in get_example_files("json") {
+        let input = fs::read_to_string(&file).unwrap();
+        let value: serde_json::Value = serde_json::from_str(&input).unwrap();
+        assert_eq!(extract(&value, true), extract(&value, false));
+    }
+
+    let value = (Some(1), None::<i64>, vec![("a", 'b')], [Some(vec![2])]);
+    assert_eq!(extract(&value, true), extract(&value, false));
+}
+
+#[test]
+#[cfg(feature = "json")]
+fn run_json_examples_snapshots() {
+    use serde_datalog::{
+        backend::vector::StringKeyBackend,
+        snapshot::{assert_snapshot, Snapshot},
+    };
+
+    for file in get_example_files("json") {
+        let input = fs::read_to_string(&file).unwrap();
+        let value: serde_json::Value = serde_json::from_str(&input).unwrap();
+
+        let mut extractor = DatalogExtractor::new(StringKeyBackend::default());
+        extractor.set_file(&file.to_string_lossy()).unwrap();
+        value.serialize(&mut extractor).unwrap();
+        let data = extractor.get_backend().get_data();
+
+        let file_name = file.file_name().unwrap().to_string_lossy();
+        assert_snapshot(
+            format!("tests/snapshots/{}.snap", file_name),
+            &data.snapshot(),
+        );
+    }
+}
+
+#[test]
+#[cfg(all(unix, feature = "bin_only", feature = "json"))]
+fn run_keeps_program_next_to_facts() {
+    use std::{os::unix::fs::PermissionsExt, process::Command};
+
+    let dir = std::env::temp_dir().join(format!("serde_datalog_run_test_{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+
+    // a stand-in for Souffle that records its arguments
+    let souffle = dir.join("souffle");
+    fs::write(
+        &souffle,
+        format!("#!/bin/sh\necho \"$@\" > {:?}\n", dir.join("args")),
+    )
+    .unwrap();
+    fs::set_permissions(&souffle, fs::Permissions::from_mode(0o755)).unwrap();
+
+    let program = "out(x) :- rootElem(_, x).\n";
+    fs::write(dir.join("prog.dl"), program).unwrap();
+
+    let status = Command::new(env!("CARGO_BIN_EXE_serde_datalog"))
+        .arg("run")
+        .arg("--program")
+        .arg(dir.join("prog.dl"))
+        .arg("--facts")
+        .arg(dir.join("prog.db"))
+        .arg("--souffle")
+        .arg(&souffle)
+        .arg("examples/test1.json")
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    assert_eq!(fs::read_to_string(dir.join("prog.dl")).unwrap(), program);
+    assert!(dir.join("prog.db").is_file());
+
+    let args = fs::read_to_string(dir.join("args")).unwrap();
+    assert!(args.starts_with(&format!("-F {} ", dir.display())));
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+#[cfg(all(feature = "bin_only", feature = "json"))]
+fn sample_json_arrays() {
+    use std::process::Command;
+
+    let dir = std::env::temp_dir().join(format!("serde_datalog_sample_{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let values: Vec<usize> = (0..40).collect();
+    fs::write(
+        dir.join("values.json"),
+        serde_json::to_string(&values).unwrap(),
+    )
+    .unwrap();
+
+    // JSON arrays have no length until they end
+    let sample = |mode: &str| -> (Vec<usize>, (usize, usize)) {
+        let db = dir.join(format!("{}.db", mode));
+        let status = Command::new(env!("CARGO_BIN_EXE_serde_datalog"))
+            .arg(dir.join("values.json"))
+            .arg("-o")
+            .arg(&db)
+            .args(["--sample", "5", "--sample-mode", mode])
+            .status()
+            .unwrap();
+        assert!(status.success());
+
+        let conn = rusqlite::Connection::open(&db).unwrap();
+        let positions = conn
+            .prepare("SELECT pos FROM seq ORDER BY pos;")
+            .unwrap()
+            .query_map((), |row| row.get(0))
+            .unwrap()
+            .collect::<rusqlite::Result<_>>()
+            .unwrap();
+        let sampled = conn
+            .query_row("SELECT len, kept FROM sampled;", (), |row| {
+                rusqlite::Result::Ok((row.get(0)?, row.get(1)?))
+            })
+            .unwrap();
+        (positions, sampled)
+    };
+
+    let (positions, sampled) = sample("random");
+    assert_eq!(positions.len(), 5);
+    assert_ne!(positions, vec![0, 1, 2, 3, 4]);
+    assert_eq!(sampled, (40, 5));
+
+    let (positions, sampled) = sample("stratified");
+    assert_eq!(positions, vec![0, 8, 16, 24, 32]);
+    assert_eq!(sampled, (40, 5));
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+#[cfg(all(feature = "bin_only", feature = "json"))]
+fn extract_ndjson_lines() {
+    use std::process::Command;
+
+    let dir = std::env::temp_dir().join(format!("serde_datalog_ndjson_{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let input = dir.join("events.ndjson");
+    fs::write(&input, "[1]\n\n[2,\n[3, 4]\n").unwrap();
+
+    let db = dir.join("events.db");
+    let status = Command::new(env!("CARGO_BIN_EXE_serde_datalog"))
+        .arg(&input)
+        .arg("-o")
+        .arg(&db)
+        .args(["--split-roots", "--skip-invalid"])
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    // items are named after their line and index, which are both recorded;
+    // the items of an invalid line that were read before the error are kept
+    let conn = rusqlite::Connection::open(&db).unwrap();
+    let meta: Vec<(String, String, String)> = conn
+        .prepare("SELECT file, key, value FROM rootMeta ORDER BY file, key;")
+        .unwrap()
+        .query_map((), |row| {
+            rusqlite::Result::Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+        })
+        .unwrap()
+        .collect::<rusqlite::Result<_>>()
+        .unwrap();
+    let root = |name: &str| format!("{}:{}", input.display(), name);
+    let expected: Vec<(String, String, String)> = [
+        ("1:1", "item", "1"),
+        ("1:1", "line", "1"),
+        ("3:1", "item", "1"),
+        ("3:1", "line", "3"),
+        ("4:1", "item", "1"),
+        ("4:1", "line", "4"),
+        ("4:2", "item", "2"),
+        ("4:2", "line", "4"),
+    ]
+    .into_iter()
+    .map(|(name, key, value)| (root(name), key.to_string(), value.to_string()))
+    .collect();
+    assert_eq!(meta, expected);
+
+    let roots: usize = conn
+        .query_row("SELECT COUNT(*) FROM rootElem;", (), |row| row.get(0))
+        .unwrap();
+    assert_eq!(roots, 4);
+    let line: usize = conn
+        .query_row("SELECT line FROM parseError;", (), |row| row.get(0))
+        .unwrap();
+    assert_eq!(line, 3);
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+#[cfg(all(feature = "bin_only", feature = "json"))]
+fn extract_json_summary() {
+    use std::process::Command;
+
+    let dir = std::env::temp_dir().join(format!("serde_datalog_summary_{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("good.json"), r#"{"a": [1, 2]}"#).unwrap();
+    fs::write(dir.join("bad.json"), "{oops").unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_serde_datalog"))
+        .args([
+            "good.json",
+            "bad.json",
+            "--json",
+            "--keep-going",
+            "-o",
+            "out.db",
+        ])
+        .current_dir(&dir)
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(5));
+
+    // the summary is the only output on stdout, and the error of the run is
+    // a JSON object on stderr
+    let summary: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(summary["filesProcessed"], 1);
+    assert_eq!(summary["filesFailed"], 1);
+    assert_eq!(summary["filesCached"], 0);
+    assert!(summary["factsEmitted"].as_u64().unwrap() > 0);
+    let warnings = summary["warnings"].as_array().unwrap();
+    assert_eq!(warnings.len(), 1);
+    assert!(warnings[0]
+        .as_str()
+        .unwrap()
+        .contains("bad.json: parse error"));
+    assert_eq!(summary["outputs"], serde_json::json!(["out.db"]));
+
+    let error: serde_json::Value = serde_json::from_slice(&output.stderr).unwrap();
+    assert_eq!(error["error"], "partialFailure");
+    assert_eq!(error["exitStatus"], 5);
+
+    // only the input that was processed has facts in the output
+    let conn = rusqlite::Connection::open(dir.join("out.db")).unwrap();
+    let roots: usize = conn
+        .query_row("SELECT COUNT(*) FROM rootElem;", (), |row| row.get(0))
+        .unwrap();
+    assert_eq!(roots, 1);
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+#[cfg(all(feature = "bin_only", feature = "json"))]
+fn extract_with_config_file() {
+    use serde_datalog::backend::souffle_sqlite::{db_has_string_keys, SchemaConfig};
+    use std::process::{Command, Output};
+
+    let dir = std::env::temp_dir().join(format!("serde_datalog_config_{}", std::process::id()));
+    let sub = dir.join("sub");
+    fs::create_dir_all(&sub).unwrap();
+    fs::write(
+        dir.join("serdedl.toml"),
+        "backend = \"general\"\noutput = \"facts/{stem}.db\"\n\n[formats]\ndata = \"json\"\n",
+    )
+    .unwrap();
+    fs::write(dir.join("input.data"), r#"{"a": 1}"#).unwrap();
+
+    // runs in a subdirectory, so that the file is found in its parent
+    let run = |args: &[&str]| -> Output {
+        Command::new(env!("CARGO_BIN_EXE_serde_datalog"))
+            .arg("../input.data")
+            .args(args)
+            .current_dir(&sub)
+            .output()
+            .unwrap()
+    };
+    let string_keys = |db: &std::path::Path| {
+        let conn = rusqlite::Connection::open(db).unwrap();
+        db_has_string_keys(&conn, &SchemaConfig::default()).unwrap()
+    };
+
+    // the file gives the output, the schema, and the format of the input
+    assert!(run(&[]).status.success());
+    let db = sub.join("facts").join("input.db");
+    assert!(!string_keys(&db));
+    let conn = rusqlite::Connection::open(&db).unwrap();
+    let format: String = conn
+        .query_row("SELECT format FROM fileFormat;", (), |row| row.get(0))
+        .unwrap();
+    assert_eq!(format, "json");
+    drop(conn);
+
+    // options on the command line take precedence over the file
+    let output = run(&["-o", "cli.db", "--backend", "string-key"]);
+    assert!(output.status.success());
+    assert!(string_keys(&sub.join("cli.db")));
+
+    // unknown keys are rejected, unless the file is ignored
+    fs::write(dir.join("serdedl.toml"), "bakend = \"general\"\n").unwrap();
+    let output = run(&["-o", "unknown.db"]);
+    assert_eq!(output.status.code(), Some(3));
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("serdedl.toml"));
+    assert!(stderr.contains("unknown field `bakend`"));
+    assert!(
+        run(&["-o", "unknown.db", "--no-config", "--format", "json"])
+            .status
+            .success()
+    );
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+#[cfg(all(feature = "bin_only", feature = "json", feature = "cache"))]
+fn extract_cached_inputs() {
+    use std::process::Command;
+
+    let dir = std::env::temp_dir().join(format!("serde_datalog_cache_{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("a.json"), r#"{"a": [1, "x"]}"#).unwrap();
+    fs::write(dir.join("b.json"), r#"{"b": true}"#).unwrap();
+
+    // runs with the cache in `cache`, returning the number of inputs whose
+    // facts were reused and the output database
+    let run = |db: &str, options: &[&str]| -> (u64, Vec<u8>) {
+        let output = Command::new(env!("CARGO_BIN_EXE_serde_datalog"))
+            .args(["a.json", "b.json", "--json", "--cache", "cache", "-o", db])
+            .args(options)
+            .current_dir(&dir)
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        let summary: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+        assert_eq!(summary["filesProcessed"], 2);
+        let cached = summary["filesCached"].as_u64().unwrap();
+        (cached, fs::read(dir.join(db)).unwrap())
+    };
+
+    let (cached, extracted) = run("first.db", &[]);
+    assert_eq!(cached, 0);
+    assert_eq!(fs::read_dir(dir.join("cache")).unwrap().count(), 2);
+
+    // a cache hit reproduces the database of the run that filled the cache
+    let (cached, replayed) = run("second.db", &[]);
+    assert_eq!(cached, 2);
+    assert!(replayed == extracted);
+
+    // changing an input invalidates its entry only
+    fs::write(dir.join("b.json"), r#"{"b": false}"#).unwrap();
+    let (cached, _) = run("third.db", &[]);
+    assert_eq!(cached, 1);
+
+    // changing the options of the extraction invalidates all entries
+    let (cached, _) = run("fourth.db", &["--shapes"]);
+    assert_eq!(cached, 0);
+    let (cached, _) = run("fifth.db", &["--shapes"]);
+    assert_eq!(cached, 2);
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+#[cfg(all(feature = "bin_only", feature = "json"))]
+fn extract_directory_tree() {
+    use std::process::{Command, Output};
+
+    let dir = std::env::temp_dir().join(format!("serde_datalog_tree_{}", std::process::id()));
+    let tree = dir.join("tree");
+    fs::create_dir_all(tree.join("sub").join("deeper")).unwrap();
+    fs::create_dir_all(tree.join("other")).unwrap();
+    for (name, contents) in [
+        ("b.json", "[2]"),
+        ("a.json", "[1]"),
+        ("notes.txt", "not data"),
+        ("other/e.json", "[5]"),
+        ("sub/c.json", "[3]"),
+        ("sub/readme.md", "# not data"),
+        ("sub/deeper/d.json", "[4]"),
+    ] {
+        fs::write(tree.join(name), contents).unwrap();
+    }
+
+    let run = |args: &[&str]| -> (Output, Vec<String>) {
+        let db = dir.join("tree.db");
+        let _ = fs::remove_file(&db);
+        let output = Command::new(env!("CARGO_BIN_EXE_serde_datalog"))
+            .args(args)
+            .arg("-o")
+            .arg(&db)
+            .current_dir(&dir)
+            .output()
+            .unwrap();
+        if !output.status.success() {
+            return (output, Vec::new());
+        }
+
+        // roots in the order in which the files were extracted, relative to
+        // the tree
+        let conn = rusqlite::Connection::open(&db).unwrap();
+        let root = fs::canonicalize(&tree).unwrap();
+        let files = conn
+            .prepare("SELECT file FROM rootElem ORDER BY elem;")
+            .unwrap()
+            .query_map((), |row| row.get::<_, String>(0))
+            .unwrap()
+            .map(|file| {
+                let file = file.unwrap();
+                let file = std::path::Path::new(&file)
+                    .strip_prefix(&root)
+                    .unwrap()
+                    .to_owned();
+                file.to_string_lossy().replace('\\', "/")
+            })
+            .collect();
+        (output, files)
+    };
+
+    // directories are only walked with --recursive
+    let (output, _) = run(&["tree"]);
+    assert_eq!(output.status.code(), Some(2));
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("use --recursive"));
+
+    // files are walked in order of their paths, and files with unknown
+    // formats are skipped
+    let (output, files) = run(&["--recursive", "tree"]);
+    assert!(output.status.success());
+    assert_eq!(
+        files,
+        vec![
+            "a.json",
+            "b.json",
+            "other/e.json",
+            "sub/c.json",
+            "sub/deeper/d.json"
+        ]
+    );
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("Skipped 2 file(s) with unknown input format"));
+    assert!(stderr.contains("notes.txt"));
+    assert!(stderr.contains("readme.md"));
+
+    // glob patterns match files in order, and directories they match are
+    // only walked with --recursive
+    let (output, files) = run(&["tree/*.json"]);
+    assert!(output.status.success());
+    assert_eq!(files, vec!["a.json", "b.json"]);
+
+    let (output, files) = run(&["--recursive", "tree/s*"]);
+    assert!(output.status.success());
+    assert_eq!(files, vec!["sub/c.json", "sub/deeper/d.json"]);
+
+    let (output, _) = run(&["tree/s*"]);
+    assert_eq!(output.status.code(), Some(2));
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("No input files found"));
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+#[cfg(all(feature = "bin_only", feature = "json", feature = "yaml"))]
+fn extract_split_roots() {
+    use std::process::Command;
+
+    let dir = std::env::temp_dir().join(format!("serde_datalog_split_{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("orders.json"), r#"[{"id": 1}, [2], "three"]"#).unwrap();
+    fs::write(dir.join("config.yaml"), "name: app\n8080: [web]\n").unwrap();
+
+    let db = dir.join("split.db");
+    let status = Command::new(env!("CARGO_BIN_EXE_serde_datalog"))
+        .arg(dir.join("orders.json"))
+        .arg(dir.join("config.yaml"))
+        .arg("-o")
+        .arg(&db)
+        .arg("--split-roots")
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    // the items of the top-level array and the entries of the top-level map
+    // are separate roots, named and recorded by their index or key
+    let conn = rusqlite::Connection::open(&db).unwrap();
+    let roots: Vec<(String, String, String, String)> = conn
+        .prepare(
+            "SELECT rootElem.file, key, value, type FROM rootElem
+            INNER JOIN rootMeta ON rootElem.file = rootMeta.file
+            INNER JOIN type ON rootElem.elem = type.id
+            ORDER BY rootElem.elem;",
+        )
+        .unwrap()
+        .query_map((), |row| {
+            rusqlite::Result::Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?))
+        })
+        .unwrap()
+        .collect::<rusqlite::Result<_>>()
+        .unwrap();
+    let root = |name: &str, key: &str, value: &str, ty: &str| {
+        let file = fs::canonicalize(dir.join(name)).unwrap();
+        (
+            format!("{}:{}", file.display(), value),
+            key.to_string(),
+            value.to_string(),
+            ty.to_string(),
+        )
+    };
+    assert_eq!(
+        roots,
+        vec![
+            root("orders.json", "item", "1", "Map"),
+            root("orders.json", "item", "2", "Seq"),
+            root("orders.json", "item", "3", "Str"),
+            root("config.yaml", "key", "name", "Str"),
+            root("config.yaml", "key", "8080", "Seq"),
+        ]
+    );
+
+    // top-level values that are neither arrays nor maps cannot be split
+    fs::write(dir.join("scalar.json"), "42").unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_serde_datalog"))
+        .arg(dir.join("scalar.json"))
+        .arg("-o")
+        .arg(dir.join("scalar.db"))
+        .arg("--split-roots")
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(3));
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("expected a sequence or map of values"));
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+#[cfg(all(feature = "bin_only", feature = "json"))]
+fn extract_keep_going() {
+    use std::process::Command;
+
+    let dir = std::env::temp_dir().join(format!("serde_datalog_keep_going_{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("first.json"), r#"{"a": 1}"#).unwrap();
+    fs::write(dir.join("bad.ndjson"), "[2]\n[3]\n{oops\n").unwrap();
+    fs::write(dir.join("last.json"), "[true]").unwrap();
+
+    let db = dir.join("keep_going.db");
+    let output = Command::new(env!("CARGO_BIN_EXE_serde_datalog"))
+        .arg(dir.join("first.json"))
+        .arg(dir.join("bad.ndjson"))
+        .arg(dir.join("last.json"))
+        .arg("-o")
+        .arg(&db)
+        .arg("--keep-going")
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(5));
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("bad.ndjson"));
+
+    // the lines of the bad input read before the error leave no facts
+    let conn = rusqlite::Connection::open(&db).unwrap();
+    let strings = |query: &str| -> Vec<String> {
+        conn.prepare(query)
+            .unwrap()
+            .query_map((), |row| row.get(0))
+            .unwrap()
+            .collect::<rusqlite::Result<_>>()
+            .unwrap()
+    };
+    let file = |name: &str| {
+        fs::canonicalize(dir.join(name))
+            .unwrap()
+            .to_string_lossy()
+            .to_string()
+    };
+    assert_eq!(
+        strings("SELECT file FROM rootElem ORDER BY elem;"),
+        vec![file("first.json"), file("last.json")]
+    );
+    assert_eq!(
+        strings("SELECT format FROM fileFormat ORDER BY format;"),
+        vec!["json", "json"]
+    );
+    let numbers: Vec<i64> = conn
+        .prepare("SELECT value FROM number;")
+        .unwrap()
+        .query_map((), |row| row.get(0))
+        .unwrap()
+        .collect::<rusqlite::Result<_>>()
+        .unwrap();
+    assert_eq!(numbers, vec![1]);
+
+    // element identifiers of the bad input are reused by the next one
+    let elems: i64 = conn
+        .query_row("SELECT MAX(elem) FROM rootElem;", (), |row| row.get(0))
+        .unwrap();
+    assert_eq!(elems, 4);
+    drop(conn);
+
+    // without --keep-going, extraction stops at the bad input
+    let status = Command::new(env!("CARGO_BIN_EXE_serde_datalog"))
+        .arg(dir.join("bad.ndjson"))
+        .arg(dir.join("last.json"))
+        .arg("-o")
+        .arg(dir.join("stopped.db"))
+        .status()
+        .unwrap();
+    assert_eq!(status.code(), Some(3));
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+#[cfg(all(feature = "bin_only", feature = "json", feature = "yaml"))]
+fn extract_mixed_formats() {
+    use std::process::Command;
+
+    let dir = std::env::temp_dir().join(format!("serde_datalog_mixed_{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("data.json"), r#"{"a": 1}"#).unwrap();
+    fs::write(dir.join("config.yaml"), "b: 2\n").unwrap();
+
+    let db = dir.join("mixed.db");
+    let output = Command::new(env!("CARGO_BIN_EXE_serde_datalog"))
+        .arg(dir.join("data.json"))
+        .arg(dir.join("config.yaml"))
+        .arg("-o")
+        .arg(&db)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    // the YAML file puts the facts of both files in the general schema
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("config.yaml: input format allows non-string map keys"));
+    assert!(!stderr.contains("data.json"));
+
+    let conn = rusqlite::Connection::open(&db).unwrap();
+    let formats: Vec<String> = conn
+        .prepare("SELECT format FROM fileFormat ORDER BY format;")
+        .unwrap()
+        .query_map((), |row| row.get(0))
+        .unwrap()
+        .collect::<rusqlite::Result<_>>()
+        .unwrap();
+    assert_eq!(formats, vec!["json", "yaml"]);
+    let string_keys = serde_datalog::backend::souffle_sqlite::db_has_string_keys(
+        &conn,
+        &serde_datalog::backend::souffle_sqlite::SchemaConfig::default(),
+    )
+    .unwrap();
+    assert!(!string_keys);
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+#[cfg(all(feature = "bin_only", feature = "json", feature = "yaml"))]
+fn extract_document_formats() {
+    use std::process::Command;
+
+    let dir = std::env::temp_dir().join(format!("serde_datalog_documents_{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("events.ndjson"), "[1]\n{\"a\": 2}\n").unwrap();
+    fs::write(dir.join("stream.yaml"), "a: 1\n---\n- b\n").unwrap();
+    fs::write(dir.join("data.json"), "[true]").unwrap();
+
+    let db = dir.join("documents.db");
+    let status = Command::new(env!("CARGO_BIN_EXE_serde_datalog"))
+        .arg(dir.join("events.ndjson"))
+        .arg(dir.join("stream.yaml"))
+        .arg(dir.join("data.json"))
+        .arg("-o")
+        .arg(&db)
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    // every root, including the documents of multi-document inputs, joins
+    // with the format of its input
+    let conn = rusqlite::Connection::open(&db).unwrap();
+    let formats: Vec<(String, String)> = conn
+        .prepare(
+            "SELECT rootElem.file, format FROM rootElem
+            INNER JOIN fileFormat ON rootElem.file = fileFormat.file
+            ORDER BY rootElem.elem;",
+        )
+        .unwrap()
+        .query_map((), |row| rusqlite::Result::Ok((row.get(0)?, row.get(1)?)))
+        .unwrap()
+        .collect::<rusqlite::Result<_>>()
+        .unwrap();
+    let root = |name: &str, position: &str, format: &str| {
+        let file = fs::canonicalize(dir.join(name)).unwrap();
+        (
+            format!("{}{}", file.display(), position),
+            format.to_string(),
+        )
+    };
+    assert_eq!(
+        formats,
+        vec![
+            root("events.ndjson", ":1", "ndjson"),
+            root("events.ndjson", ":2", "ndjson"),
+            root("stream.yaml", ":1", "yaml"),
+            root("stream.yaml", ":2", "yaml"),
+            root("data.json", "", "json"),
+        ]
+    );
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+#[cfg(all(feature = "bin_only", feature = "json"))]
+fn extract_identical_databases() {
+    use std::process::Command;
+
+    let dir = std::env::temp_dir().join(format!("serde_datalog_identical_{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+
+    let extract = |db: &str| -> Vec<u8> {
+        let status = Command::new(env!("CARGO_BIN_EXE_serde_datalog"))
+            .args(get_example_files("json"))
+            .arg("-o")
+            .arg(dir.join(db))
+            .status()
+            .unwrap();
+        assert!(status.success());
+        fs::read(dir.join(db)).unwrap()
+    };
+    assert!(extract("a.db") == extract("b.db"));
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+#[cfg(all(feature = "bin_only", feature = "json", feature = "s3"))]
+fn extract_s3_prefix() {
+    use std::{
+        io::{BufRead, BufReader, Write},
+        net::TcpListener,
+        process::Command,
+        sync::{Arc, Mutex},
+    };
+
+    // a fake S3-compatible store that lists the objects under `data/` in two
+    // pages, and records the requests it receives
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let endpoint = format!("http://{}", listener.local_addr().unwrap());
+    let requests: Arc<Mutex<Vec<(String, bool)>>> = Arc::default();
+    let recorded = requests.clone();
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let mut stream = stream.unwrap();
+            let requests = recorded.clone();
+            std::thread::spawn(move || {
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                loop {
+                    let mut request_line = String::new();
+                    if reader.read_line(&mut request_line).unwrap() == 0 {
+                        return;
+                    }
+                    let mut signed = false;
+                    loop {
+                        let mut header = String::new();
+                        reader.read_line(&mut header).unwrap();
+                        if header.trim_end().is_empty() {
+                            break;
+                        }
+                        signed |= header.starts_with("Authorization: AWS4-HMAC-SHA256 ");
+                    }
+
+                    let target = request_line.split(' ').nth(1).unwrap().to_string();
+                    let body = match target.as_str() {
+                        "/bucket?list-type=2&prefix=data%2F" => {
+                            "<ListBucketResult><IsTruncated>true</IsTruncated>\
+                             <Contents><Key>data/</Key></Contents>\
+                             <Contents><Key>data/a.json</Key></Contents>\
+                             <NextContinuationToken>t&amp;1</NextContinuationToken>\
+                             </ListBucketResult>"
+                        }
+                        "/bucket?continuation-token=t%261&list-type=2&prefix=data%2F" => {
+                            "<ListBucketResult><IsTruncated>false</IsTruncated>\
+                             <Contents><Key>data/sub/b&amp;c.json</Key></Contents>\
+                             <Contents><Key>data/notes.bin</Key></Contents>\
+                             </ListBucketResult>"
+                        }
+                        "/bucket?list-type=2&prefix=data%2Fa.json" => {
+                            "<ListBucketResult><IsTruncated>false</IsTruncated>\
+                             <Contents><Key>data/a.json</Key></Contents>\
+                             <Contents><Key>data/a.json.bak</Key></Contents>\
+                             </ListBucketResult>"
+                        }
+                        "/bucket/data/a.json" => r#"{"a": 1}"#,
+                        "/bucket/data/sub/b%26c.json" => "[2]",
+                        _ => "",
+                    };
+                    requests.lock().unwrap().push((target, signed));
+
+                    let status = if body.is_empty() {
+                        "404 Not Found"
+                    } else {
+                        "200 OK"
+                    };
+                    write!(
+                        stream,
+                        "HTTP/1.1 {}\r\nContent-Length: {}\r\n\r\n{}",
+                        status,
+                        body.len(),
+                        body
+                    )
+                    .unwrap();
+                }
+            });
+        }
+    });
+
+    let dir = std::env::temp_dir().join(format!("serde_datalog_s3_{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let extract = |url: &str, db: &str, credentials: bool| {
+        let mut command = Command::new(env!("CARGO_BIN_EXE_serde_datalog"));
+        command
+            .arg(url)
+            .arg("-o")
+            .arg(dir.join(db))
+            .env("AWS_ENDPOINT_URL_S3", &endpoint)
+            .env("AWS_REGION", "eu-west-1")
+            .env_remove("AWS_SESSION_TOKEN");
+        if credentials {
+            command
+                .env("AWS_ACCESS_KEY_ID", "AKID")
+                .env("AWS_SECRET_ACCESS_KEY", "secret");
+        } else {
+            command
+                .env_remove("AWS_ACCESS_KEY_ID")
+                .env_remove("AWS_SECRET_ACCESS_KEY");
+        }
+        command.output().unwrap()
+    };
+    let files = |db: &str| -> Vec<String> {
+        let conn = rusqlite::Connection::open(dir.join(db)).unwrap();
+        let files = conn
+            .prepare("SELECT file FROM rootElem ORDER BY file;")
+            .unwrap()
+            .query_map((), |row| row.get(0))
+            .unwrap()
+            .collect::<rusqlite::Result<_>>()
+            .unwrap();
+        files
+    };
+
+    // a prefix names the objects under it, except for folders and objects
+    // whose input format is unknown; keys are unescaped and re-encoded
+    let output = extract("s3://bucket/data/", "prefix.db", false);
+    assert!(output.status.success());
+    assert!(String::from_utf8(output.stderr)
+        .unwrap()
+        .contains("- s3://bucket/data/notes.bin"));
+    assert_eq!(
+        files("prefix.db"),
+        vec!["s3://bucket/data/a.json", "s3://bucket/data/sub/b&c.json"]
+    );
+    assert_eq!(
+        requests.lock().unwrap().drain(..).collect::<Vec<_>>(),
+        vec![
+            ("/bucket?list-type=2&prefix=data%2F".to_string(), false),
+            (
+                "/bucket?continuation-token=t%261&list-type=2&prefix=data%2F".to_string(),
+                false
+            ),
+            ("/bucket/data/a.json".to_string(), false),
+            ("/bucket/data/sub/b%26c.json".to_string(), false),
+        ]
+    );
+
+    // a key of an object names only that object, and requests are signed
+    // when there are credentials
+    let output = extract("s3://bucket/data/a.json", "object.db", true);
+    assert!(output.status.success());
+    assert_eq!(files("object.db"), vec!["s3://bucket/data/a.json"]);
+    assert_eq!(
+        requests.lock().unwrap().drain(..).collect::<Vec<_>>(),
+        vec![
+            ("/bucket?list-type=2&prefix=data%2Fa.json".to_string(), true),
+            ("/bucket/data/a.json".to_string(), true),
+        ]
+    );
+
+    // URLs without a bucket are rejected before any request is sent
+    let output = extract("s3:///data/a.json", "none.db", false);
+    assert!(!output.status.success());
+    assert!(String::from_utf8(output.stderr)
+        .unwrap()
+        .contains("s3:///data/a.json has no bucket name"));
+    assert!(requests.lock().unwrap().is_empty());
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+#[cfg(all(feature = "bin_only", feature = "json", feature = "archive"))]
+fn extract_archive_entries() {
+    use std::{io::Write, process::Command};
+
+    let dir = std::env::temp_dir().join(format!("serde_datalog_archive_{}", std::process::id()));
+    fs::create_dir_all(dir.join("nested")).unwrap();
+
+    // a zip archive with a directory, a nested entry, and an entry whose
+    // input format is unknown
+    let mut zip = zip::ZipWriter::new(fs::File::create(dir.join("configs.zip")).unwrap());
+    let options = zip::write::SimpleFileOptions::default();
+    zip.add_directory("app/", options).unwrap();
+    zip.start_file("app/settings.json", options).unwrap();
+    zip.write_all(br#"{"debug": true}"#).unwrap();
+    zip.start_file("notes.bin", options).unwrap();
+    zip.write_all(b"\x00\x01").unwrap();
+    zip.finish().unwrap();
+
+    // a tar archive in a subdirectory, found by walking the directory
+    let mut tar = tar::Builder::new(fs::File::create(dir.join("nested/dump.tar")).unwrap());
+    for (name, contents) in [("a.json", "[1]"), ("deep/b/c.json", "[2, 3]")] {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(contents.len() as u64);
+        header.set_mode(0o644);
+        header.set_cksum();
+        tar.append_data(&mut header, name, contents.as_bytes())
+            .unwrap();
+    }
+    tar.finish().unwrap();
+    drop(tar);
+
+    let db = dir.join("archives.db");
+    let status = Command::new(env!("CARGO_BIN_EXE_serde_datalog"))
+        .arg(&dir)
+        .arg("--recursive")
+        .arg("-o")
+        .arg(&db)
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    // entries are named after the canonical path of their archive and their
+    // path within it, both of which are recorded
+    let zip_path = dir.join("configs.zip").canonicalize().unwrap();
+    let tar_path = dir.join("nested/dump.tar").canonicalize().unwrap();
+    let entry = |archive: &std::path::Path, name: &str| format!("{}!/{}", archive.display(), name);
+    let conn = rusqlite::Connection::open(&db).unwrap();
+    let meta: Vec<(String, String, String)> = conn
+        .prepare("SELECT file, key, value FROM rootMeta ORDER BY file, key;")
+        .unwrap()
+        .query_map((), |row| {
+            rusqlite::Result::Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+        })
+        .unwrap()
+        .collect::<rusqlite::Result<_>>()
+        .unwrap();
+    let mut expected: Vec<(String, String, String)> = [
+        (&zip_path, "app/settings.json"),
+        (&tar_path, "a.json"),
+        (&tar_path, "deep/b/c.json"),
+    ]
+    .into_iter()
+    .flat_map(|(archive, name)| {
+        [
+            (
+                entry(archive, name),
+                "archive".to_string(),
+                archive.display().to_string(),
+            ),
+            (entry(archive, name), "entry".to_string(), name.to_string()),
+        ]
+    })
+    .collect();
+    expected.sort();
+    assert_eq!(meta, expected);
+
+    let numbers: Vec<(String, usize)> = conn
+        .prepare(
+            "SELECT r.file, n.value FROM rootElem r, seq s, number n \
+             WHERE s.id = r.elem AND n.id = s.value ORDER BY n.value;",
+        )
+        .unwrap()
+        .query_map((), |row| rusqlite::Result::Ok((row.get(0)?, row.get(1)?)))
+        .unwrap()
+        .collect::<rusqlite::Result<_>>()
+        .unwrap();
+    assert_eq!(
+        numbers,
+        vec![
+            (entry(&tar_path, "a.json"), 1),
+            (entry(&tar_path, "deep/b/c.json"), 2),
+            (entry(&tar_path, "deep/b/c.json"), 3),
+        ]
+    );
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+#[cfg(feature = "bin_only")]
+fn print_completions_and_man_page() {
+    use std::process::Command;
+
+    let run = |args: &[&str]| {
+        let output = Command::new(env!("CARGO_BIN_EXE_serde_datalog"))
+            .args(args)
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "{:?} failed", args);
+        String::from_utf8(output.stdout).unwrap()
+    };
+
+    // the scripts and the man page generated by the build script cover the
+    // subcommands and options of the binary
+    for shell in ["bash", "elvish", "fish", "powershell", "zsh"] {
+        let script = run(&["completions", shell]);
+        assert!(script.contains("serde_datalog"), "{}", shell);
+        assert!(script.contains("split-roots"), "{}", shell);
+    }
+
+    let man = run(&["man"]);
+    assert!(man.starts_with(".ie"));
+    assert!(man.contains(".TH serde_datalog 1"));
+    assert!(man.contains("completions"));
+}
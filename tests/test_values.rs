@@ -1,12 +1,15 @@
-#[cfg(feature = "json")]
+#[cfg(all(feature = "json", feature = "sqlite"))]
 mod test {
-    use std::ops::ControlFlow;
-
     use arbitrary::{Arbitrary, Unstructured};
     use arbitrary_json::ArbitraryValue;
     use rand::RngCore;
     use serde::Serialize;
-    use serde_datalog::{backend, DatalogExtractionError, DatalogExtractor, ElemId};
+    use serde_datalog::{
+        backend::{self, vector::SymbolNamespace},
+        string_policy::StringPolicy,
+        tee::TeeSerializer,
+        testing, DatalogExtractionError, DatalogExtractor, ElemId, StructHints,
+    };
     use serde_json::Value;
 
     struct ValueCount {
@@ -38,6 +41,7 @@ mod test {
     }
 
     impl ValueCount {
+        #[allow(clippy::too_many_arguments)]
         fn new(
             null: usize,
             bool: usize,
@@ -100,31 +104,41 @@ mod test {
         }
     }
 
+    /// Check that the facts in `data` match the number of each kind of value
+    /// in `value`.
+    fn check_counts(value: &Value, data: &backend::vector::BackendData<ElemId>) {
+        let map_sym = data.symbol_table.get_by_left("Map").unwrap();
+        let seq_sym = data.symbol_table.get_by_left("Seq").unwrap();
+
+        let (map_count, seq_count) = data.type_table.iter().fold((0, 0), |acc, row| {
+            let map_inc = if row.1 == map_sym { 1 } else { 0 };
+            let seq_inc = if row.1 == seq_sym { 1 } else { 0 };
+            (acc.0 + map_inc, acc.1 + seq_inc)
+        });
+
+        let c = ValueCount::get(value);
+        assert!(map_count == c.object);
+        assert!(seq_count == c.array);
+        assert!(data.map_table.len() == c.object_fields);
+        assert!(data.seq_table.len() == c.array_elements);
+        assert!(data.bool_table.len() == c.bool);
+        assert!(data.number_table.len() == c.number);
+        assert!(data.string_table.len() == c.string);
+        assert!(data.type_table.len() == c.total());
+    }
+
     fn extract(value: &Value) -> Option<backend::vector::BackendData<ElemId>> {
-        let mut extractor = DatalogExtractor::new(backend::vector::Backend::default());
+        let mut extractor = DatalogExtractor::new(testing::FactRecorder::new(
+            backend::vector::Backend::default(),
+        ));
         let res = value.serialize(&mut extractor);
-        let data = extractor.get_backend().get_data();
+        let (backend, facts) = extractor.get_backend().into_parts();
 
-        return match res {
+        match res {
             Ok(_) => {
-                let map_sym = data.symbol_table.get_by_left("Map").unwrap();
-                let seq_sym = data.symbol_table.get_by_left("Seq").unwrap();
-
-                let (map_count, seq_count) = data.type_table.iter().fold((0, 0), |acc, row| {
-                    let map_inc = if row.1 == map_sym { 1 } else { 0 };
-                    let seq_inc = if row.1 == seq_sym { 1 } else { 0 };
-                    (acc.0 + map_inc, acc.1 + seq_inc)
-                });
-
-                let c = ValueCount::get(value);
-                assert!(map_count == c.object);
-                assert!(seq_count == c.array);
-                assert!(data.map_table.len() == c.object_fields);
-                assert!(data.seq_table.len() == c.array_elements);
-                assert!(data.bool_table.len() == c.bool);
-                assert!(data.number_table.len() == c.number);
-                assert!(data.string_table.len() == c.string);
-                assert!(data.type_table.len() == c.total());
+                testing::check_invariants(&facts).unwrap();
+                let data = backend.get_data();
+                check_counts(value, &data);
                 Some(data)
             }
 
@@ -134,10 +148,224 @@ mod test {
             | Err(DatalogExtractionError::IntegerCastOverflow(_)) => None,
 
             Err(DatalogExtractionError::Custom(msg)) => {
-                assert!(false, "{}", msg);
-                None
+                panic!("{}", msg);
+            }
+
+            Err(err @ DatalogExtractionError::Backend(_))
+            | Err(err @ DatalogExtractionError::Cancelled)
+            | Err(err @ DatalogExtractionError::InValue(_)) => {
+                panic!("{}", err);
+            }
+        }
+    }
+
+    /// The values of `data`, one per line, as the path from their root to
+    /// every string, number, and boolean, e.g. `input.items[0].name = "abc"`,
+    /// along with the type of every struct, e.g. `input.items[0]: Item`, and
+    /// enum variant, e.g. `input.kind: Kind::A`, sorted. Unlike snapshots,
+    /// paths do not depend on the order in which elements are generated, so
+    /// tests of input formats can check single values.
+    #[cfg(all(
+        feature = "input_format",
+        any(
+            feature = "avro",
+            feature = "graphql",
+            feature = "protobuf",
+            feature = "ini",
+            feature = "env",
+            feature = "edn",
+            feature = "rust",
+            feature = "url",
+            feature = "spreadsheet",
+            feature = "logs"
+        )
+    ))]
+    fn value_paths<K: backend::tuples::MapKey>(
+        data: &backend::vector::BackendData<K>,
+    ) -> Vec<String> {
+        use backend::tuples::Term;
+        use std::collections::HashMap;
+
+        let relations = backend::tuples::relations(data);
+        let facts = |name: &'static str| {
+            relations
+                .iter()
+                .filter(move |relation| relation.name == name)
+                .flat_map(|relation| relation.facts.iter())
+        };
+        let render = |term: &Term| match term {
+            Term::Number(value) => value.to_string(),
+            Term::Bool(value) => value.to_string(),
+            Term::Name(text) | Term::Text(text) => format!("{:?}", text),
+            term => format!("{:?}", term),
+        };
+
+        let mut leaves: HashMap<ElemId, String> = HashMap::new();
+        for relation in ["string", "number", "bool", "bytes"] {
+            for fact in facts(relation) {
+                if let Term::Elem(elem) = fact[0] {
+                    leaves.insert(elem, render(&fact[1]));
+                }
+            }
+        }
+
+        // map keys that are not strings, numbers, or booleans are described
+        // by their variants and the values they contain, e.g. `Edn::Keyword("a")`
+        // or `["a", 1]`
+        let variants: HashMap<ElemId, String> = facts("variantType")
+            .filter_map(|fact| match fact.as_slice() {
+                [Term::Elem(elem), Term::Name(ty), Term::Name(variant)] => {
+                    Some((*elem, format!("{}::{}", ty, variant)))
+                }
+                _ => None,
+            })
+            .collect();
+        let mut contents: HashMap<ElemId, Vec<(String, ElemId)>> = HashMap::new();
+        for fact in facts("seq").chain(facts("tuple")) {
+            if let (Term::Elem(parent), Term::Elem(child)) = (&fact[0], &fact[2]) {
+                contents
+                    .entry(*parent)
+                    .or_default()
+                    .push((render(&fact[1]), *child));
+            }
+        }
+        fn describe(
+            elem: ElemId,
+            leaves: &HashMap<ElemId, String>,
+            variants: &HashMap<ElemId, String>,
+            contents: &HashMap<ElemId, Vec<(String, ElemId)>>,
+        ) -> String {
+            if let Some(leaf) = leaves.get(&elem) {
+                return leaf.clone();
+            }
+            let mut values: Vec<&(String, ElemId)> =
+                contents.get(&elem).into_iter().flatten().collect();
+            values.sort();
+            let values: Vec<String> = values
+                .into_iter()
+                .map(|(_, value)| describe(*value, leaves, variants, contents))
+                .collect();
+            match variants.get(&elem) {
+                Some(variant) => format!("{}({})", variant, values.join(", ")),
+                None if values.is_empty() => "()".to_string(),
+                None => format!("[{}]", values.join(", ")),
+            }
+        }
+
+        let mut parents: HashMap<ElemId, (ElemId, String)> = HashMap::new();
+        for relation in ["struct", "map", "seq", "tuple"] {
+            for fact in facts(relation) {
+                let (Term::Elem(parent), Term::Elem(child)) = (&fact[0], &fact[2]) else {
+                    continue;
+                };
+                let segment = match (relation, &fact[1]) {
+                    ("struct", field) => format!(".{}", render(field).trim_matches('"')),
+                    ("map", Term::Elem(key)) => {
+                        format!("[{}]", describe(*key, &leaves, &variants, &contents))
+                    }
+                    (_, key) => format!("[{}]", render(key)),
+                };
+                parents.insert(*child, (*parent, segment));
+            }
+        }
+        let roots: HashMap<ElemId, String> = facts("rootElem")
+            .filter_map(|fact| match (&fact[0], &fact[1]) {
+                (Term::Text(root), Term::Elem(elem)) => Some((*elem, root.to_string())),
+                _ => None,
+            })
+            .collect();
+
+        // elements of map keys have no path, since they are described by the
+        // segments of the map's values instead
+        let path = |mut elem: ElemId| {
+            let mut segments = Vec::new();
+            while let Some((parent, segment)) = parents.get(&elem) {
+                segments.push(segment.as_str());
+                elem = *parent;
             }
+            segments.reverse();
+            roots
+                .get(&elem)
+                .map(|root| format!("{}{}", root, segments.concat()))
         };
+
+        let mut paths: Vec<String> = leaves
+            .iter()
+            .filter_map(|(elem, value)| Some(format!("{} = {}", path(*elem)?, value)))
+            .collect();
+        paths.extend(
+            facts("structType").filter_map(|fact| match fact.as_slice() {
+                [Term::Elem(elem), Term::Name(ty)] => Some(format!("{}: {}", path(*elem)?, ty)),
+                _ => None,
+            }),
+        );
+        paths.extend(
+            facts("variantType").filter_map(|fact| match fact.as_slice() {
+                [Term::Elem(elem), Term::Name(ty), Term::Name(variant)] => {
+                    Some(format!("{}: {}::{}", path(*elem)?, ty, variant))
+                }
+                _ => None,
+            }),
+        );
+        paths.sort();
+        paths
+    }
+
+    /// The [value paths][value_paths] extracted from `contents` read
+    /// in input format `format`, with a root named `input`, or `input:N` for
+    /// the Nth document of multi-document inputs, or the error message of the
+    /// first document that cannot be read or extracted.
+    #[cfg(all(
+        feature = "input_format",
+        any(
+            feature = "avro",
+            feature = "graphql",
+            feature = "protobuf",
+            feature = "ini",
+            feature = "env",
+            feature = "edn",
+            feature = "rust",
+            feature = "url",
+            feature = "spreadsheet",
+            feature = "logs"
+        )
+    ))]
+    fn extract_input(
+        format: &dyn serde_datalog::input_format::InputFormat,
+        contents: &[u8],
+    ) -> Result<Vec<String>, String> {
+        use serde_datalog::{bytes_policy::BytesPolicy, input_format, DatalogExtractorBackend};
+
+        fn extract<B: DatalogExtractorBackend>(
+            extractor: &mut DatalogExtractor<B>,
+            format: &dyn input_format::InputFormat,
+            contents: &[u8],
+        ) -> Result<(), String> {
+            let mut source = format
+                .read(Box::new(contents), None)
+                .map_err(|err| err.to_string())?;
+            while let Some(document) = source.next_document() {
+                let document = document?;
+                let root = input_format::document_root("input", document.position);
+                input_format::extract_document(extractor, &root, document.value)
+                    .map_err(|err| err.to_string())?;
+            }
+            Result::Ok(())
+        }
+
+        if format.has_string_keys() {
+            let mut extractor = DatalogExtractor::new(
+                backend::vector::StringKeyBackend::default().with_bytes_policy(BytesPolicy::Hex),
+            );
+            extract(&mut extractor, format, contents)?;
+            Result::Ok(value_paths(&extractor.get_backend().get_data()))
+        } else {
+            let mut extractor = DatalogExtractor::new(
+                backend::vector::Backend::default().with_bytes_policy(BytesPolicy::Hex),
+            );
+            extract(&mut extractor, format, contents)?;
+            Result::Ok(value_paths(&extractor.get_backend().get_data()))
+        }
     }
 
     #[test]
@@ -204,32 +432,3840 @@ mod test {
         assert!(seq_second_elem == b_id);
     }
 
+    struct UnitVariant;
+
+    impl Serialize for UnitVariant {
+        fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            serializer.serialize_unit_variant("Role", 0, "Admin")
+        }
+    }
+
     #[test]
-    fn run_fuzzer() {
-        let mut data = [0u8; 16384];
-        rand::thread_rng().fill_bytes(&mut data);
-        let mut u = Unstructured::new(&data);
-        let mut total = 0;
-        let mut extracted = 0;
-
-        u.arbitrary_loop(Some(10000), Some(10000), |u| {
-            match ArbitraryValue::arbitrary(u) {
-                Ok(value) => {
-                    if extract(&value).is_some() {
-                        extracted += 1;
-                    }
-                    total += 1;
-                }
+    fn run_unit_variant() {
+        let value = vec![(UnitVariant, 1), (UnitVariant, 2)];
+        let mut extractor = DatalogExtractor::new(backend::vector::Backend::default());
+        value.serialize(&mut extractor).unwrap();
+        let data = extractor.get_backend().get_data();
 
-                Err(_) => {}
-            };
-            Ok(ControlFlow::Continue(()))
-        })
-        .unwrap();
+        let tuple_sym = data.symbol_table.get_by_left("Tuple").unwrap();
+        let variant_sym = data.symbol_table.get_by_left("UnitVariant").unwrap();
 
-        println!(
-            "generated {} arbitrary JSON values in total, tested {}",
-            total, extracted
+        // each sequence entry is a tuple whose first entry is the unit variant
+        assert_eq!(data.seq_table.len(), 2);
+        for seq_val in data.seq_table.values() {
+            assert_eq!(data.type_table.get(seq_val), Some(tuple_sym));
+
+            let variant = data.tuple_table.get(&(*seq_val, 0)).unwrap();
+            assert_eq!(data.type_table.get(variant), Some(variant_sym));
+        }
+    }
+
+    struct User {
+        id: i64,
+        name: &'static str,
+        password: &'static str,
+    }
+
+    impl Serialize for User {
+        fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            use serde::ser::SerializeStruct;
+
+            let mut s = serializer.serialize_struct("User", 3)?;
+            s.serialize_field("id", &self.id)?;
+            s.serialize_field("name", &self.name)?;
+            s.serialize_field("password", &self.password)?;
+            s.end()
+        }
+    }
+
+    #[test]
+    fn run_struct_hints() {
+        let value = vec![
+            User {
+                id: 1,
+                name: "alice",
+                password: "hunter2",
+            },
+            User {
+                id: 2,
+                name: "bob",
+                password: "swordfish",
+            },
+        ];
+
+        let hints = StructHints::new("User")
+            .key("id")
+            .rename("name", "login")
+            .skip("password");
+        let mut extractor =
+            DatalogExtractor::new(backend::vector::Backend::default()).with_hints(hints);
+
+        // skipped fields are still serialized by serializers wrapped with tee
+        let mut json = serde_json::Serializer::new(Vec::new());
+        value
+            .serialize(TeeSerializer::new(&mut json, &mut extractor))
+            .unwrap();
+        let json = String::from_utf8(json.into_inner()).unwrap();
+        assert!(json.contains("hunter2"));
+
+        let data = extractor.get_backend().get_data();
+        let sym = |s: &str| *data.symbol_table.get_by_left(s).unwrap();
+
+        assert!(data.symbol_table.get_by_left("hunter2").is_none());
+        assert!(data.symbol_table.get_by_left("name").is_none());
+        assert_eq!(data.struct_table.len(), 4);
+        assert!(data
+            .struct_table
+            .keys()
+            .all(|(_, field)| *field == sym("id") || *field == sym("login")));
+
+        // key fields are declared once per struct type
+        assert_eq!(data.key_field_table.len(), 1);
+        assert!(data.key_field_table.contains(&(sym("User"), sym("id"))));
+
+        // the key of each user is the value of its id field
+        assert_eq!(data.key_table.len(), 2);
+        assert!(data
+            .key_table
+            .iter()
+            .all(|(elem, key)| data.struct_table.get(&(*elem, sym("id"))) == Some(key)));
+    }
+
+    #[test]
+    fn run_separate_namespaces() {
+        let value = User {
+            id: 1,
+            name: "name",
+            password: "User",
+        };
+
+        let mut extractor =
+            DatalogExtractor::new(backend::vector::Backend::default().with_separate_namespaces());
+        value.serialize(&mut extractor).unwrap();
+        let data = extractor.get_backend().get_data();
+
+        let field_sym = data.symbol_id(SymbolNamespace::Field, "name").unwrap();
+        let value_sym = data.symbol_id(SymbolNamespace::Value, "name").unwrap();
+        assert_ne!(field_sym, value_sym);
+        assert_eq!(data.symbol(&field_sym).unwrap(), "name");
+        assert_eq!(data.symbol(&value_sym).unwrap(), "name");
+
+        let type_sym = data.symbol_id(SymbolNamespace::TypeName, "User").unwrap();
+        let string_sym = data.symbol_id(SymbolNamespace::Value, "User").unwrap();
+        assert_ne!(type_sym, string_sym);
+        assert!(data.struct_type_table.values().all(|sym| *sym == type_sym));
+
+        let struct_sym = data.symbol_id(SymbolNamespace::ElemType, "Struct").unwrap();
+        assert!(data.type_table.values().any(|sym| *sym == struct_sym));
+        assert!(data.symbol_id(SymbolNamespace::Value, "Struct").is_none());
+    }
+
+    #[test]
+    fn run_string_policy() {
+        let value = ("line\nbreak", '\t', "a very long string", "short");
+
+        let policy = StringPolicy::default()
+            .escape_control_chars(true)
+            .truncate(6)
+            .keep_full_text(true);
+        let mut extractor =
+            DatalogExtractor::new(backend::vector::Backend::default()).with_string_policy(policy);
+        value.serialize(&mut extractor).unwrap();
+        let data = extractor.get_backend().get_data();
+
+        let strings: Vec<&String> = data
+            .string_table
+            .values()
+            .map(|sym| data.symbol(sym).unwrap())
+            .collect();
+
+        assert_eq!(strings.len(), 4);
+        assert!(strings.iter().all(|s| !s.contains(['\n', '\t'])));
+        assert!(strings.contains(&&"\\t".to_string()));
+        assert!(strings.contains(&&"short".to_string()));
+        assert!(strings.iter().any(|s| s.starts_with("a very#")));
+
+        // only changed strings keep their full text
+        let mut full_strings: Vec<&String> = data.full_string_table.values().collect();
+        full_strings.sort();
+        assert_eq!(
+            full_strings,
+            vec!["\t", "a very long string", "line\nbreak"]
+        );
+    }
+
+    #[cfg(feature = "unicode")]
+    #[test]
+    fn run_string_normalization() {
+        // "Café" with a precomposed é, and with e followed by a combining accent
+        use std::collections::HashSet;
+
+        let value = ("Caf\u{e9}", "cafe\u{301}");
+
+        let policy = StringPolicy::default()
+            .nfc(true)
+            .case_fold(true)
+            .keep_full_text(true);
+        let mut extractor =
+            DatalogExtractor::new(backend::vector::Backend::default()).with_string_policy(policy);
+        value.serialize(&mut extractor).unwrap();
+        let data = extractor.get_backend().get_data();
+
+        // both strings are interned as the same symbol
+        let syms: HashSet<_> = data.string_table.values().collect();
+        assert_eq!(syms.len(), 1);
+        assert_eq!(
+            data.symbol(syms.into_iter().next().unwrap()).unwrap(),
+            "caf\u{e9}"
+        );
+
+        let full_strings: HashSet<&str> = data
+            .full_string_table
+            .values()
+            .map(|s| s.as_str())
+            .collect();
+        assert_eq!(full_strings, HashSet::from(["Caf\u{e9}", "cafe\u{301}"]));
+    }
+
+    #[test]
+    fn run_string_report() {
+        let value = vec!["tag", "tag", "tag", "a much longer string"];
+
+        let mut extractor = DatalogExtractor::new(backend::vector::Backend::default());
+        value.serialize(&mut extractor).unwrap();
+        let report = extractor.get_backend().string_report(2);
+
+        // element types are interned strings too
+        assert_eq!(report.top.len(), 2);
+        assert_eq!(report.top[0].symbol, "Str");
+        assert_eq!(report.top[0].count, 4);
+        assert_eq!(report.top[1].symbol, "tag");
+        assert_eq!(report.top[1].bytes, 9);
+
+        assert_eq!(report.distinct, 4);
+        assert_eq!(report.total_count, 9);
+        assert_eq!(report.total_bytes, 12 + 9 + 20 + 3);
+        assert!((report.byte_share(&report.top[1]) - 9.0 / 44.0).abs() < 1e-9);
+    }
+
+    #[cfg(feature = "string_compression")]
+    #[test]
+    fn run_string_compression() {
+        use backend::souffle_sqlite::{AbstractBackend, LoadOptions};
+
+        let long = "compressible ".repeat(100);
+        let value = vec!["short", long.as_str()];
+
+        let backend = backend::souffle_sqlite::Backend::default()
+            .with_load_options(LoadOptions::default().compress_strings(64));
+        let mut extractor = DatalogExtractor::new(backend);
+        value.serialize(&mut extractor).unwrap();
+
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        extractor.get_backend().dump_into(&conn).unwrap();
+
+        let (compressed, size): (usize, usize) = conn
+            .query_row(
+                "SELECT COUNT(*), SUM(LENGTH(data)) FROM __CompressedSymbolTable;",
+                (),
+                |row| Result::Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap();
+        assert_eq!(compressed, 1);
+        assert!(size < long.len());
+
+        // views decompress strings once the decompression function is registered
+        backend::souffle_sqlite::register_functions(&conn).unwrap();
+        let mut strings: Vec<String> = conn
+            .prepare("SELECT value FROM string;")
+            .unwrap()
+            .query_map((), |row| row.get(0))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        strings.sort();
+        assert_eq!(strings, vec![long.clone(), "short".to_string()]);
+    }
+
+    #[test]
+    fn run_statistics() {
+        use backend::souffle_sqlite::{AbstractBackend, LoadOptions};
+
+        let value = serde_json::json!({ "a": [1, 2, 2, 100], "b": ["x", "y", "x"] });
+
+        let backend = backend::souffle_sqlite::Backend::default()
+            .with_load_options(LoadOptions::default().statistics(true));
+        let mut extractor = DatalogExtractor::new(backend);
+        value.serialize(&mut extractor).unwrap();
+
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        extractor.get_backend().dump_into(&conn).unwrap();
+
+        let count_facts = |relation: &str| -> i64 {
+            conn.query_row(
+                "SELECT facts FROM __RelationStats WHERE relation = ?1;",
+                [relation],
+                |row| row.get(0),
+            )
+            .unwrap()
+        };
+        assert_eq!(count_facts("number"), 4);
+        assert_eq!(count_facts("seq"), 7);
+        assert_eq!(count_facts("type"), 12);
+
+        let histogram: Vec<(i64, i64, i64, i64)> = conn
+            .prepare("SELECT bucket, low, high, count FROM __NumberHistogram ORDER BY bucket;")
+            .unwrap()
+            .query_map((), |row| {
+                Result::Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?))
+            })
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(histogram, vec![(0, 1, 2, 3), (15, 100, 100, 1)]);
+
+        let (symbol, count): (String, i64) = conn
+            .query_row(
+                "SELECT symbol, count FROM __TopStrings ORDER BY count DESC LIMIT 1;",
+                (),
+                |row| Result::Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap();
+        assert_eq!((symbol.as_str(), count), ("x", 2));
+    }
+
+    #[test]
+    fn run_indexes_and_analyze() {
+        use backend::souffle_sqlite::{AbstractBackend, LoadOptions};
+
+        let value = serde_json::json!({ "a": [1, 2], "b": "x" });
+
+        let backend = backend::souffle_sqlite::Backend::default()
+            .with_load_options(LoadOptions::default().create_indexes(true).analyze(true));
+        let mut extractor = DatalogExtractor::new(backend);
+        value.serialize(&mut extractor).unwrap();
+
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        extractor.get_backend().dump_into(&conn).unwrap();
+
+        let has_index = |name: &str| -> bool {
+            conn.query_row(
+                "SELECT EXISTS (SELECT 1 FROM sqlite_master WHERE type = 'index' AND name = ?1);",
+                [name],
+                |row| row.get(0),
+            )
+            .unwrap()
+        };
+        assert!(has_index("_seq_value"));
+        assert!(has_index("__SymbolTable_symbol"));
+
+        // ANALYZE stores its statistics in sqlite_stat1
+        let analyzed: i64 = conn
+            .query_row("SELECT COUNT(*) FROM sqlite_stat1;", (), |row| row.get(0))
+            .unwrap();
+        assert!(analyzed > 0);
+    }
+
+    #[test]
+    fn run_deterministic_order() {
+        use backend::souffle_sqlite::AbstractBackend;
+
+        let value: Vec<Vec<usize>> = (0..20).map(|i| (0..i).collect()).collect();
+
+        let mut extractor = DatalogExtractor::new(backend::souffle_sqlite::Backend::default());
+        value.serialize(&mut extractor).unwrap();
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        extractor.get_backend().dump_into(&conn).unwrap();
+
+        // facts are inserted in order of their identifiers
+        for sql in [
+            "SELECT id FROM _type ORDER BY rowid;",
+            "SELECT id FROM __SymbolTable ORDER BY rowid;",
+        ] {
+            let ids: Vec<usize> = conn
+                .prepare(sql)
+                .unwrap()
+                .query_map((), |row| row.get(0))
+                .unwrap()
+                .collect::<Result<_, _>>()
+                .unwrap();
+            assert!(ids.windows(2).all(|pair| pair[0] < pair[1]));
+        }
+    }
+
+    #[test]
+    fn run_split_and_merge() {
+        use backend::souffle_sqlite::{self, AbstractBackend, SchemaConfig};
+
+        let dir = std::env::temp_dir().join(format!("serde_datalog_split_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let db = dir.join("all.db").to_string_lossy().to_string();
+        let merged = dir.join("merged.db").to_string_lossy().to_string();
+
+        let value = (vec!["a", "b"], true);
+        let mut extractor = DatalogExtractor::new(souffle_sqlite::Backend::default());
+        value.serialize(&mut extractor).unwrap();
+        extractor.get_backend().dump_to_db(&db).unwrap();
+
+        let split_dir = dir.join("split");
+        std::fs::create_dir_all(&split_dir).unwrap();
+        let split_files =
+            souffle_sqlite::split_db_by_relation(&db, &SchemaConfig::default(), &split_dir)
+                .unwrap();
+        assert!(split_files.iter().any(|file| file.ends_with("string.db")));
+
+        souffle_sqlite::merge_dbs(&merged, &split_files).unwrap();
+
+        let count_facts = |filename: &str| -> (usize, usize) {
+            let conn = rusqlite::Connection::open(filename).unwrap();
+            conn.query_row(
+                "SELECT (SELECT COUNT(*) FROM string), (SELECT COUNT(*) FROM bool);",
+                (),
+                |row| Result::Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap()
+        };
+        assert_eq!(count_facts(&merged), (2, 1));
+        assert_eq!(count_facts(&merged), count_facts(&db));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn run_schema_versions() {
+        use backend::souffle_sqlite::{self, AbstractBackend, SchemaConfig, SCHEMA_VERSION};
+        use serde_datalog::unit_policy::UnitPolicy;
+
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        let schema = SchemaConfig::default();
+
+        let backend = souffle_sqlite::Backend::default().with_metadata("extractor", "test");
+        let mut extractor = DatalogExtractor::new(backend);
+        extractor.set_file("a.json").unwrap();
+        vec![1, 2].serialize(&mut extractor).unwrap();
+        extractor.get_backend().dump_into(&conn).unwrap();
+
+        let metadata = |key: &str| -> Vec<String> {
+            conn.prepare("SELECT value FROM __Metadata WHERE key = ?1 ORDER BY value;")
+                .unwrap()
+                .query_map([key], |row| row.get(0))
+                .unwrap()
+                .collect::<Result<_, _>>()
+                .unwrap()
+        };
+        assert_eq!(
+            souffle_sqlite::db_schema_version(&conn, &schema).unwrap(),
+            SCHEMA_VERSION
+        );
+        assert_eq!(metadata("sourceFile"), vec!["a.json"]);
+        assert_eq!(metadata("extractor"), vec!["test"]);
+
+        // the time of extraction is only recorded with provenance
+        assert!(metadata("extractedAt").is_empty());
+
+        // databases without metadata are migrated when facts are appended
+        conn.execute_batch("DROP TABLE __Metadata; DROP VIEW unit; DROP TABLE _unit;")
+            .unwrap();
+        assert_eq!(
+            souffle_sqlite::db_schema_version(&conn, &schema).unwrap(),
+            0
+        );
+
+        let append = |file: &str| {
+            let mut backend = souffle_sqlite::Backend::default()
+                .with_load_options(souffle_sqlite::LoadOptions::default().provenance(true));
+            let first_elem_id = backend.load_from(&conn).unwrap();
+            let mut extractor = DatalogExtractor::new_with_first_elem_id(backend, first_elem_id)
+                .with_unit_policy(UnitPolicy::Facts);
+            extractor.set_file(file).unwrap();
+            ().serialize(&mut extractor).unwrap();
+            extractor.get_backend().append_into(&conn, false)
+        };
+        append("b.json").unwrap();
+        assert_eq!(
+            souffle_sqlite::db_schema_version(&conn, &schema).unwrap(),
+            SCHEMA_VERSION
+        );
+        assert_eq!(metadata("sourceFile"), vec!["b.json"]);
+        assert_eq!(metadata("extractedAt").len(), 1);
+        let units: usize = conn
+            .query_row("SELECT COUNT(*) FROM unit;", (), |row| row.get(0))
+            .unwrap();
+        assert_eq!(units, 1);
+
+        // databases of newer schema versions are left alone
+        conn.execute_batch("UPDATE __Metadata SET value = '99' WHERE key = 'schemaVersion';")
+            .unwrap();
+        assert!(matches!(
+            append("c.json"),
+            Err(DatalogExtractionError::Backend(
+                backend::BackendError::Schema(_)
+            ))
+        ));
+    }
+
+    #[test]
+    fn run_input_directives() {
+        use backend::souffle_sqlite::{AbstractBackend, SchemaConfig, StringKeyBackend};
+
+        let program = StringKeyBackend::default().input_directives("facts.db");
+        assert!(program.contains(".decl map(id: ElemId, key: symbol, value: ElemId)"));
+        assert!(program.contains(".input type(IO=sqlite, dbname=\"facts.db\")"));
+
+        let decls = program.matches(".decl ").count();
+        let inputs = program.matches(".input ").count();
+        assert_eq!(decls, inputs);
+
+        // views with a prefix are read by name
+        let schema = SchemaConfig {
+            view_prefix: "serde_".to_string(),
+            ..SchemaConfig::default()
+        };
+        let program = backend::souffle_sqlite::Backend::default()
+            .with_schema(schema)
+            .input_directives("facts.db");
+        assert!(program.contains(".decl map(id: ElemId, key: ElemId, value: ElemId)"));
+        assert!(program.contains(".input seq(IO=sqlite, dbname=\"facts.db\", name=\"serde_seq\")"));
+    }
+
+    #[test]
+    fn run_prolog() {
+        let value = serde_json::json!({ "name": "O'Neil \"\\\n", "tags": [true] });
+
+        let mut extractor = DatalogExtractor::new(backend::prolog::StringKeyBackend::default());
+        value.serialize(&mut extractor).unwrap();
+        let mut out = Vec::new();
+        extractor.get_backend().write_to(&mut out).unwrap();
+        let clauses = String::from_utf8(out).unwrap();
+
+        assert!(clauses.contains(":- dynamic(unit/1)."));
+        assert!(clauses.contains("string(3, \"O'Neil \\\"\\\\\\n\")."));
+        assert!(clauses.contains("bool(6, true)."));
+        assert!(clauses.contains("map(1, \"tags\", 5)."));
+        assert!(clauses.contains("type(5, 'Seq')."));
+    }
+
+    #[test]
+    fn run_clingo() {
+        let value = serde_json::json!({ "big": 5000000000u64, "name": "a\"b\n" });
+
+        let mut extractor = DatalogExtractor::new(backend::clingo::Backend::default());
+        value.serialize(&mut extractor).unwrap();
+        let mut out = Vec::new();
+        extractor.get_backend().write_to(&mut out).unwrap();
+        let facts = String::from_utf8(out).unwrap();
+
+        assert!(facts.contains("#defined unit/1."));
+        assert!(facts.contains("type(1,\"Map\")."));
+        assert!(facts.contains("map(1,2,3)."));
+        // numbers beyond 32 bits are strings
+        assert!(facts.contains("number(3,\"5000000000\")."));
+        assert!(facts.contains("string(5,\"a\\\"b\\n\")."));
+    }
+
+    #[test]
+    fn run_mangle() {
+        let value = serde_json::json!({ "ok": false, "name": "tab\t" });
+
+        let mut extractor = DatalogExtractor::new(backend::mangle::StringKeyBackend::default());
+        value.serialize(&mut extractor).unwrap();
+        let mut out = Vec::new();
+        extractor.get_backend().write_to(&mut out).unwrap();
+        let facts = String::from_utf8(out).unwrap();
+
+        assert!(facts.contains("Decl seq(Id, Pos, Value)."));
+        assert!(facts.contains("type(1, /Map)."));
+        assert!(facts.contains("bool(5, /false)."));
+        assert!(facts.contains("map(1, \"name\", 3)."));
+        assert!(facts.contains("string(3, \"tab\\t\")."));
+    }
+
+    #[test]
+    fn run_nemo() {
+        let dir = std::env::temp_dir().join(format!("serde_datalog_nemo_{}", std::process::id()));
+        let value = serde_json::json!({ "name": "a, \"b\"" });
+
+        let mut extractor = DatalogExtractor::new(backend::nemo::StringKeyBackend::default());
+        value.serialize(&mut extractor).unwrap();
+        extractor.get_backend().dump_to_dir(&dir).unwrap();
+
+        let read = |file: &str| std::fs::read_to_string(dir.join(file)).unwrap();
+        assert_eq!(read("string.csv"), "3,\"a, \"\"b\"\"\"\n");
+        assert_eq!(read("map.csv"), "1,name,3\n");
+        assert_eq!(read("unit.csv"), "");
+
+        let ruleset = read(backend::nemo::RULESET_FILE);
+        assert!(ruleset
+            .contains("@import map :- csv{resource=\"map.csv\", format=(int, string, int)}."));
+        assert!(ruleset.contains("@import unit :- csv{resource=\"unit.csv\", format=(any)}."));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn run_inline_scalars() {
+        use backend::tuples::{self, Term};
+
+        let value = serde_json::json!({ "name": "a", "tags": [true, null, 3] });
+
+        let mut extractor = DatalogExtractor::new(backend::vector::Backend::default());
+        value.serialize(&mut extractor).unwrap();
+        let data = extractor.get_backend().get_data();
+        let relations = tuples::relations(&data);
+        let fact_count = |relations: &[tuples::Relation]| -> usize {
+            relations.iter().map(|relation| relation.facts.len()).sum()
+        };
+        let full_count = fact_count(&relations);
+
+        let relations = tuples::inline_scalars(relations);
+        let facts = |name: &str| -> Vec<Vec<Term>> {
+            let relation = relations.iter().find(|relation| relation.name == name);
+            relation.unwrap().facts.clone()
+        };
+
+        // map keys and units are not inlined
+        let seq_inline: Vec<Vec<Term>> = facts("seqInline")
+            .into_iter()
+            .map(|fact| fact[1..].to_vec())
+            .collect();
+        assert_eq!(
+            seq_inline,
+            vec![
+                vec![
+                    Term::Number(0),
+                    Term::Name("Bool"),
+                    Term::Number(1),
+                    Term::Text(""),
+                ],
+                vec![
+                    Term::Number(2),
+                    Term::Name("Number"),
+                    Term::Number(3),
+                    Term::Text(""),
+                ],
+            ]
+        );
+        assert_eq!(facts("seq").len(), 1);
+        assert_eq!(facts("mapInline").len(), 1);
+        assert_eq!(facts("mapInline")[0][4], Term::Text("a"));
+        assert_eq!(facts("map").len(), 1);
+        assert_eq!(facts("string").len(), 2);
+        assert_eq!(fact_count(&relations), full_count - 6);
+
+        let dir = std::env::temp_dir().join(format!("serde_datalog_inline_{}", std::process::id()));
+        let mut extractor =
+            DatalogExtractor::new(backend::nemo::StringKeyBackend::default().with_inline_scalars());
+        value.serialize(&mut extractor).unwrap();
+        extractor.get_backend().dump_to_dir(&dir).unwrap();
+
+        let read = |file: &str| std::fs::read_to_string(dir.join(file)).unwrap();
+        assert_eq!(read("mapInline.csv"), "1,name,Str,0,a\n");
+        assert_eq!(read("map.csv"), "1,tags,5\n");
+        let ruleset = read(backend::nemo::RULESET_FILE);
+        assert!(ruleset.contains(
+            "@import mapInline :- csv{resource=\"mapInline.csv\", format=(int, string, string, int, string)}."
+        ));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn run_surreal() {
+        let value = serde_json::json!({ "name": "a\"b", "tags": [true] });
+
+        let mut extractor = DatalogExtractor::new(backend::surrealql::Backend::default());
+        value.serialize(&mut extractor).unwrap();
+        let mut out = Vec::new();
+        extractor.get_backend().write_to(&mut out).unwrap();
+        let script = String::from_utf8(out).unwrap();
+
+        assert!(script.starts_with("BEGIN TRANSACTION;\n"));
+        assert!(
+            script.contains("CREATE elem:3 CONTENT {\"type\": \"Str\", \"string\": \"a\\\"b\"};")
         );
+        assert!(script.contains("CREATE elem:6 CONTENT {\"type\": \"Bool\", \"bool\": true};"));
+        assert!(script.contains("RELATE elem:1->map_entry->elem:5 CONTENT {\"key\": elem:4};"));
+        assert!(script.contains("RELATE elem:5->seq_entry->elem:6 CONTENT {\"pos\": 0};"));
+    }
+
+    #[cfg(feature = "input_format")]
+    #[test]
+    fn run_ndjson_document_formats() {
+        use serde_datalog::{backend::record, input_format, Fact};
+        use std::collections::HashMap;
+
+        let dir = std::env::temp_dir().join(format!(
+            "serde_datalog_ndjson_formats_{}",
+            std::process::id()
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("events.ndjson");
+        std::fs::write(&path, "{\"a\": 1}\n\n[2]\n").unwrap();
+
+        let backend = input_format::extract_file(&path, None, record::Backend::default()).unwrap();
+        let mut formats = HashMap::new();
+        let mut roots = Vec::new();
+        for fact in backend.facts() {
+            match fact {
+                Fact::FileFormat { file, format } => {
+                    formats.insert(file.clone(), format.clone());
+                }
+                Fact::RootElem { file, .. } => roots.push(file.clone()),
+                _ => {}
+            }
+        }
+
+        // each line is a root that joins with the format of the file, which
+        // is also recorded under the name of the file
+        let file = path.display().to_string();
+        assert_eq!(roots, vec![format!("{}:1", file), format!("{}:3", file)]);
+        for root in roots.iter().chain([&file]) {
+            assert_eq!(formats[root], "ndjson");
+        }
+        assert_eq!(formats.len(), 3);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[cfg(feature = "arrow")]
+    #[test]
+    fn run_arrow() {
+        use arrow_array::{Array, Int64Array, StringArray};
+
+        let value = serde_json::json!({ "name": "abc", "n": 5 });
+
+        let mut extractor = DatalogExtractor::new(backend::arrow::StringKeyBackend::default());
+        value.serialize(&mut extractor).unwrap();
+        let batches = extractor.get_backend().record_batches().unwrap();
+        assert_eq!(batches.len(), 39);
+
+        let (_, map) = batches.iter().find(|(name, _)| *name == "map").unwrap();
+        assert_eq!(map.num_rows(), 2);
+        assert_eq!(map.schema().field(1).name(), "key");
+        let keys = map
+            .column(1)
+            .as_any()
+            .downcast_ref::<StringArray>()
+            .unwrap();
+        assert_eq!(keys.value(0), "n");
+
+        let (_, number) = batches.iter().find(|(name, _)| *name == "number").unwrap();
+        let values = number
+            .column(1)
+            .as_any()
+            .downcast_ref::<Int64Array>()
+            .unwrap();
+        assert_eq!(values.value(0), 5);
+
+        let (_, unit) = batches.iter().find(|(name, _)| *name == "unit").unwrap();
+        assert_eq!(unit.num_rows(), 0);
+
+        // the join of the README, SELECT s.value FROM map m JOIN string s ON
+        // m.value = s.id, evaluated over the batches
+        let map_values = map.column(2).as_any().downcast_ref::<Int64Array>().unwrap();
+        let (_, string) = batches.iter().find(|(name, _)| *name == "string").unwrap();
+        assert_eq!(string.schema().field(0).name(), "id");
+        let ids = string
+            .column(0)
+            .as_any()
+            .downcast_ref::<Int64Array>()
+            .unwrap();
+        let strings = string
+            .column(1)
+            .as_any()
+            .downcast_ref::<StringArray>()
+            .unwrap();
+        let joined: Vec<&str> = (0..map.num_rows())
+            .flat_map(|i| {
+                (0..string.num_rows())
+                    .filter(move |&j| ids.value(j) == map_values.value(i))
+                    .map(|j| strings.value(j))
+            })
+            .collect();
+        assert_eq!(joined, vec!["abc"]);
+    }
+
+    #[cfg(feature = "wasm")]
+    #[test]
+    fn run_wasm_extract_json() {
+        use serde_datalog::wasm::extract_json_facts;
+
+        let facts = extract_json_facts("data.json", r#"{"name": "abc"}"#).unwrap();
+        assert_eq!(facts["rootElem"], serde_json::json!([["data.json", 1]]));
+        assert_eq!(
+            facts["fileFormat"],
+            serde_json::json!([["data.json", "json"]])
+        );
+        assert_eq!(facts["map"], serde_json::json!([[1, "name", 3]]));
+        assert!(facts["string"]
+            .as_array()
+            .unwrap()
+            .contains(&serde_json::json!([3, "abc"])));
+        assert_eq!(facts["unit"], serde_json::json!([]));
+
+        assert!(extract_json_facts("data.json", "{").is_err());
+    }
+
+    #[cfg(feature = "ffi")]
+    #[test]
+    fn run_ffi() {
+        use serde_datalog::ffi::*;
+        use std::ffi::{CStr, CString};
+
+        let db = std::env::temp_dir().join(format!("serde_datalog_ffi_{}.db", std::process::id()));
+        let _ = std::fs::remove_file(&db);
+        let file = CString::new("data.json").unwrap();
+        let filename = CString::new(db.to_str().unwrap()).unwrap();
+
+        unsafe {
+            let extractor = serde_datalog_extractor_new(true);
+            assert!(serde_datalog_last_error(extractor).is_null());
+
+            let invalid = CString::new("{").unwrap();
+            assert_eq!(
+                serde_datalog_extract_json(extractor, file.as_ptr(), invalid.as_ptr()),
+                -1
+            );
+            let error = CStr::from_ptr(serde_datalog_last_error(extractor));
+            assert!(error.to_str().unwrap().starts_with("invalid JSON"));
+
+            let json = CString::new(r#"{"name": "abc"}"#).unwrap();
+            assert_eq!(
+                serde_datalog_extract_json(extractor, file.as_ptr(), json.as_ptr()),
+                0
+            );
+
+            // facts are kept if storing them fails
+            let missing = CString::new(db.join("missing.db").to_str().unwrap()).unwrap();
+            assert_eq!(
+                serde_datalog_dump_to_sqlite(extractor, missing.as_ptr()),
+                -1
+            );
+            assert_eq!(
+                serde_datalog_dump_to_sqlite(extractor, filename.as_ptr()),
+                0
+            );
+            assert_eq!(
+                serde_datalog_dump_to_sqlite(extractor, filename.as_ptr()),
+                -1
+            );
+            serde_datalog_extractor_free(extractor);
+        }
+
+        let conn = rusqlite::Connection::open(&db).unwrap();
+        let root: i64 = conn
+            .query_row("SELECT elem FROM rootElem", (), |row| row.get(0))
+            .unwrap();
+        assert_eq!(root, 1);
+        std::fs::remove_file(&db).unwrap();
+    }
+
+    #[cfg(feature = "python")]
+    #[test]
+    fn run_python() {
+        use pyo3::{
+            prelude::*,
+            types::{PyDict, PyList},
+        };
+        use serde_datalog::python::PyExtractor;
+
+        let db = std::env::temp_dir().join(format!("serde_datalog_py_{}.db", std::process::id()));
+        let _ = std::fs::remove_file(&db);
+
+        Python::initialize();
+        Python::attach(|py| {
+            let obj = PyDict::new(py);
+            obj.set_item("name", "abc").unwrap();
+            obj.set_item("ok", true).unwrap();
+            obj.set_item("tags", PyList::new(py, [1, 2]).unwrap())
+                .unwrap();
+            obj.set_item("none", py.None()).unwrap();
+
+            let mut extractor = PyExtractor::new();
+            extractor.extract(&obj, Some("record.py")).unwrap();
+            assert!(extractor.extract(py.Ellipsis().bind(py), None).is_err());
+            extractor.dump_to_sqlite(db.to_str().unwrap()).unwrap();
+            assert!(extractor.dump_to_sqlite(db.to_str().unwrap()).is_err());
+        });
+
+        let conn = rusqlite::Connection::open(&db).unwrap();
+        let query = |sql: &str| -> i64 { conn.query_row(sql, (), |row| row.get(0)).unwrap() };
+        assert_eq!(query("SELECT COUNT(*) FROM map WHERE id = 1"), 4);
+        assert_eq!(query("SELECT COUNT(*) FROM bool WHERE value = 1"), 1);
+        assert_eq!(query("SELECT COUNT(*) FROM seq"), 2);
+        std::fs::remove_file(&db).unwrap();
+    }
+
+    #[test]
+    fn run_progress() {
+        use serde_datalog::DatalogExtractionError;
+        use std::{cell::RefCell, ops::ControlFlow, rc::Rc};
+
+        let reports = Rc::new(RefCell::new(Vec::new()));
+        let recorded = reports.clone();
+        let mut extractor = DatalogExtractor::new(backend::vector::Backend::default())
+            .with_progress(2, move |progress| {
+                recorded.borrow_mut().push((
+                    progress.elements,
+                    progress.file.map(|file| file.to_string()),
+                ));
+                ControlFlow::Continue(())
+            });
+        extractor.set_file("a.json").unwrap();
+        vec![1, 2, 3].serialize(&mut extractor).unwrap();
+        assert_eq!(
+            *reports.borrow(),
+            vec![
+                (0, Some("a.json".to_string())),
+                (2, Some("a.json".to_string())),
+                (4, Some("a.json".to_string())),
+            ]
+        );
+
+        let mut extractor = DatalogExtractor::new(backend::vector::Backend::default())
+            .with_progress(1, |progress| {
+                if progress.elements > 3 {
+                    ControlFlow::Break(())
+                } else {
+                    ControlFlow::Continue(())
+                }
+            });
+        vec![1, 2].serialize(&mut extractor).unwrap();
+        assert!(matches!(
+            vec![3, 4].serialize(&mut extractor),
+            Err(DatalogExtractionError::Cancelled)
+        ));
+    }
+
+    #[test]
+    fn run_cancellation() {
+        use serde_datalog::{progress::CancellationToken, DatalogExtractionError};
+        use std::time::{Duration, Instant};
+
+        let values: Vec<u32> = (0..5000).collect();
+
+        let token = CancellationToken::new();
+        let mut extractor = DatalogExtractor::new(backend::vector::Backend::default())
+            .with_cancellation(token.clone());
+        values.serialize(&mut extractor).unwrap();
+        token.cancel();
+        assert!(matches!(
+            values.serialize(&mut extractor),
+            Err(DatalogExtractionError::Cancelled)
+        ));
+        assert!(matches!(
+            extractor.set_file("a.json"),
+            Err(DatalogExtractionError::Cancelled)
+        ));
+
+        let mut extractor = DatalogExtractor::new(backend::vector::Backend::default())
+            .with_deadline(Instant::now());
+        assert!(matches!(
+            values.serialize(&mut extractor),
+            Err(DatalogExtractionError::Cancelled)
+        ));
+
+        let mut extractor = DatalogExtractor::new(backend::vector::Backend::default())
+            .with_timeout(Duration::from_secs(3600));
+        values.serialize(&mut extractor).unwrap();
+    }
+
+    #[cfg(feature = "tracing")]
+    #[test]
+    fn run_tracing() {
+        use serde_datalog::string_policy::StringPolicy;
+        use std::sync::{Arc, Mutex};
+        use tracing::{
+            field::{Field, Visit},
+            span, Event, Metadata, Subscriber,
+        };
+
+        /// Subscriber that records the messages of events.
+        struct Messages(Arc<Mutex<Vec<String>>>);
+
+        struct MessageVisitor<'a>(&'a mut Vec<String>);
+
+        impl Visit for MessageVisitor<'_> {
+            fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+                if field.name() == "message" {
+                    self.0.push(format!("{:?}", value));
+                }
+            }
+        }
+
+        impl Subscriber for Messages {
+            fn enabled(&self, _: &Metadata<'_>) -> bool {
+                true
+            }
+            fn new_span(&self, _: &span::Attributes<'_>) -> span::Id {
+                span::Id::from_u64(1)
+            }
+            fn record(&self, _: &span::Id, _: &span::Record<'_>) {}
+            fn record_follows_from(&self, _: &span::Id, _: &span::Id) {}
+            fn event(&self, event: &Event<'_>) {
+                event.record(&mut MessageVisitor(&mut self.0.lock().unwrap()));
+            }
+            fn enter(&self, _: &span::Id) {}
+            fn exit(&self, _: &span::Id) {}
+        }
+
+        let messages = Arc::new(Mutex::new(Vec::new()));
+        tracing::subscriber::with_default(Messages(messages.clone()), || {
+            let mut extractor = DatalogExtractor::new(backend::vector::Backend::default())
+                .with_string_policy(StringPolicy::default().truncate(2));
+            extractor.set_file("a.json").unwrap();
+            vec!["abc", "de"].serialize(&mut extractor).unwrap();
+            extractor.get_backend();
+        });
+
+        let messages = messages.lock().unwrap();
+        assert!(messages.contains(&"extracting file".to_string()));
+        assert!(messages.contains(&"extracted root value".to_string()));
+        assert!(messages.contains(&"extracted file".to_string()));
+        assert!(messages.contains(
+            &"string policy changed string values without keeping their full text".to_string()
+        ));
+    }
+
+    #[test]
+    fn run_fact_quotas() {
+        use backend::souffle_sqlite::AbstractBackend;
+        use serde_datalog::quota::FactQuotas;
+        use std::collections::HashMap;
+
+        let value = vec!["a", "b", "c", "d", "e"];
+        let quotas = FactQuotas::default().limit("string", 2).limit("seq", 3);
+
+        let mut extractor = DatalogExtractor::new(backend::vector::Backend::default())
+            .with_fact_quotas(quotas.clone());
+        value.serialize(&mut extractor).unwrap();
+        let data = extractor.get_backend().get_data();
+        assert_eq!(data.type_table.len(), 6);
+        assert_eq!(data.string_table.len(), 2);
+        assert_eq!(data.seq_table.len(), 3);
+
+        let truncated: HashMap<&str, usize> = data
+            .truncated_table
+            .iter()
+            .map(|(relation, quota)| (data.symbol(relation).unwrap().as_str(), *quota))
+            .collect();
+        assert_eq!(truncated, HashMap::from([("string", 2), ("seq", 3)]));
+
+        // facts about map keys are not limited
+        let mut extractor = DatalogExtractor::new(backend::vector::StringKeyBackend::default())
+            .with_fact_quotas(FactQuotas::default().limit("string", 0));
+        serde_json::json!({ "a": "x" })
+            .serialize(&mut extractor)
+            .unwrap();
+        let data = extractor.get_backend().get_data();
+        assert_eq!(data.map_table.len(), 1);
+        assert_eq!(data.truncated_table.len(), 1);
+
+        // quotas count facts of every input file
+        let mut extractor = DatalogExtractor::new(backend::souffle_sqlite::Backend::default())
+            .with_fact_quotas(quotas);
+        extractor.set_file("a.json").unwrap();
+        vec!["a"].serialize(&mut extractor).unwrap();
+        extractor.set_file("b.json").unwrap();
+        vec!["b", "c"].serialize(&mut extractor).unwrap();
+
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        extractor.get_backend().dump_into(&conn).unwrap();
+        let strings: i64 = conn
+            .query_row("SELECT COUNT(*) FROM string;", (), |row| row.get(0))
+            .unwrap();
+        assert_eq!(strings, 2);
+        let truncated: (String, i64) = conn
+            .query_row("SELECT relation, quota FROM truncated;", (), |row| {
+                Ok((row.get(0)?, row.get(1)?))
+            })
+            .unwrap();
+        assert_eq!(truncated, ("string".to_string(), 2));
+    }
+
+    #[test]
+    fn run_redaction() {
+        use serde_datalog::redact::{Redaction, RedactionMode, MASK};
+
+        let value = serde_json::json!({
+            "name": "abc",
+            "ssn": "123-45-6789",
+            "contact": { "Email": ["a@example.com", 5], "phone": "555" },
+        });
+        let extract = |redaction: Redaction| {
+            let mut extractor = DatalogExtractor::new(testing::FactRecorder::new(
+                backend::vector::StringKeyBackend::default(),
+            ))
+            .with_redaction(redaction.field("ssn").field("email"));
+            value.serialize(&mut extractor).unwrap();
+            let (backend, facts) = extractor.get_backend().into_parts();
+            testing::check_invariants(&facts).unwrap();
+            backend.get_data()
+        };
+
+        let data = extract(Redaction::new(RedactionMode::Hash));
+        assert_eq!(data.redacted_table.len(), 3);
+        let strings: Vec<&String> = data
+            .string_table
+            .iter()
+            .filter(|(elem, _)| data.redacted_table.contains(elem))
+            .map(|(_, value)| data.symbol(value).unwrap())
+            .collect();
+        assert_eq!(strings.len(), 2);
+        assert!(strings.iter().all(|value| value.len() == 16));
+        assert!(data.symbol_id(SymbolNamespace::Value, "abc").is_some());
+        assert!(data.symbol_id(SymbolNamespace::Value, "555").is_some());
+        assert!(data.symbol_id(SymbolNamespace::Value, "Email").is_some());
+        assert!(data
+            .symbol_id(SymbolNamespace::Value, "123-45-6789")
+            .is_none());
+
+        // hashes are stable, and depend on the salt
+        let hash = Redaction::new(RedactionMode::Hash).redact_str("123-45-6789");
+        assert!(data.symbol_id(SymbolNamespace::Value, &hash).is_some());
+        let data = extract(Redaction::new(RedactionMode::Hash).salt("secret"));
+        assert!(data.symbol_id(SymbolNamespace::Value, &hash).is_none());
+
+        let data = extract(Redaction::new(RedactionMode::Mask));
+        assert_eq!(data.redacted_table.len(), 3);
+        assert!(data.symbol_id(SymbolNamespace::Value, MASK).is_some());
+        assert!(data
+            .numbers()
+            .all(|(elem, value)| !data.redacted_table.contains(elem) || value == 0));
+    }
+
+    #[test]
+    fn run_sampling() {
+        use serde_datalog::sample::Sampling;
+        use std::collections::BTreeSet;
+
+        let values: Vec<u32> = (0..100).collect();
+        fn extract<T: Serialize>(
+            sampling: Sampling,
+            value: &T,
+        ) -> backend::vector::BackendData<ElemId> {
+            let mut extractor = DatalogExtractor::new(testing::FactRecorder::new(
+                backend::vector::Backend::default(),
+            ))
+            .with_sampling(sampling);
+            value.serialize(&mut extractor).unwrap();
+            let (backend, facts) = extractor.get_backend().into_parts();
+            testing::check_invariants(&facts).unwrap();
+            backend.get_data()
+        }
+        let positions = |data: &backend::vector::BackendData<ElemId>| -> BTreeSet<usize> {
+            data.seq_table.keys().map(|(_, pos)| *pos).collect()
+        };
+
+        let data = extract(Sampling::stratified(10), &values);
+        assert_eq!(positions(&data), (0..100).step_by(10).collect());
+        assert_eq!(
+            data.sampled_table.values().collect::<Vec<_>>(),
+            vec![&(100, 10)]
+        );
+
+        // short sequences are not sampled
+        let data = extract(Sampling::stratified(10), &vec![1, 2, 3]);
+        assert_eq!(data.seq_table.len(), 3);
+        assert!(data.sampled_table.is_empty());
+
+        let data = extract(Sampling::random(10, 7), &values);
+        assert_eq!(data.seq_table.len(), 10);
+        assert_eq!(data.type_table.len(), 11);
+        assert_eq!(
+            positions(&data),
+            positions(&extract(Sampling::random(10, 7), &values))
+        );
+
+        // sequences of unknown length are sampled when they end
+        struct Unsized;
+
+        impl Serialize for Unsized {
+            fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                serializer.collect_seq((0..50).filter(|_| true))
+            }
+        }
+
+        let data = extract(Sampling::random(10, 7), &Unsized);
+        assert_eq!(data.seq_table.len(), 10);
+        assert_eq!(data.type_table.len(), 11);
+        assert_ne!(positions(&data), (0..10).collect());
+        assert_eq!(
+            data.sampled_table.values().collect::<Vec<_>>(),
+            vec![&(50, 10)]
+        );
+
+        let data = extract(Sampling::stratified(10), &Unsized);
+        assert_eq!(positions(&data), (0..50).step_by(8).collect());
+        assert_eq!(
+            data.sampled_table.values().collect::<Vec<_>>(),
+            vec![&(50, 7)]
+        );
+    }
+
+    #[test]
+    fn run_elem_type_kinds() {
+        use serde_datalog::{ElemKind, ElemType, Fact};
+
+        let mut extractor = DatalogExtractor::new(testing::FactRecorder::new(
+            backend::vector::Backend::default(),
+        ));
+        extractor.set_file("a.json").unwrap();
+        vec![1, 2].serialize(&mut extractor).unwrap();
+        extractor.set_file("b.json").unwrap();
+        vec![3].serialize(&mut extractor).unwrap();
+        let (backend, facts) = extractor.get_backend().into_parts();
+        testing::check_invariants(&facts).unwrap();
+
+        // kinds are materialized once, before the first element
+        let kinds: Vec<&Fact> = facts
+            .iter()
+            .filter(|fact| matches!(fact, Fact::ElemTypeKind { .. }))
+            .collect();
+        assert_eq!(kinds.len(), ElemType::ALL.len());
+        assert!(matches!(facts[ElemType::ALL.len()], Fact::Elem { .. }));
+        assert_eq!(ElemType::NewtypeVariant.kind(), ElemKind::Variant);
+
+        let data = backend.get_data();
+        let kind = |elem_type: &str| {
+            let sym = data
+                .symbol_id(SymbolNamespace::ElemType, elem_type)
+                .unwrap();
+            data.symbol(&data.elem_type_kind_table[&sym])
+                .unwrap()
+                .clone()
+        };
+        assert_eq!(data.elem_type_kind_table.len(), 13);
+        assert_eq!(kind("Number"), "scalar");
+        assert_eq!(kind("Seq"), "container");
+        assert_eq!(kind("TupleStruct"), "struct");
+        assert_eq!(kind("UnitVariant"), "variant");
+    }
+
+    #[test]
+    fn run_fact_interchange() {
+        use serde_datalog::{
+            backend::vector::BackendData, bytes_policy::BytesPolicy, snapshot::Snapshot, Fact,
+        };
+
+        struct Payload;
+
+        impl Serialize for Payload {
+            fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                serializer.serialize_bytes(b"hi!")
+            }
+        }
+
+        let value: Value = serde_json::from_str(
+            r#"{"name": "a", "tags": ["x", "y"], "size": 3, "nested": {"ok": true, "none": null}}"#,
+        )
+        .unwrap();
+        let mut extractor = DatalogExtractor::new(testing::FactRecorder::new(
+            backend::vector::Backend::default().with_bytes_policy(BytesPolicy::Hex),
+        ));
+        extractor.set_file("a.json").unwrap();
+        value.serialize(&mut extractor).unwrap();
+        extractor.set_file("b.bin").unwrap();
+        Payload.serialize(&mut extractor).unwrap();
+        let (backend, facts) = extractor.get_backend().into_parts();
+
+        // facts survive a round trip through their serialized form
+        let json = serde_json::to_string(&facts).unwrap();
+        let deserialized: Vec<Fact> = serde_json::from_str(&json).unwrap();
+        assert_eq!(deserialized, facts);
+        assert!(json.contains(r#"{"SeqEntry":{"elem":"#));
+        assert!(json.contains(r#"{"ElemTypeKind":{"elem_type":"Seq","kind":"container"}}"#));
+
+        // backend data converts to facts that materialize the same data
+        let data = backend.get_data();
+        let converted: Vec<Fact> = (&data).into();
+        let rebuilt = BackendData::try_from(converted.as_slice()).unwrap();
+        assert_eq!(rebuilt.snapshot(), data.snapshot());
+        assert_eq!(
+            BackendData::from_facts(facts.iter()).unwrap().snapshot(),
+            data.snapshot()
+        );
+    }
+
+    #[test]
+    fn run_record_replay() {
+        use serde_datalog::{
+            backend::record::{self, Call},
+            snapshot::Snapshot,
+        };
+
+        let value: Value =
+            serde_json::from_str(r#"[{"id": 1, "tags": ["a", "b"]}, {"id": 2, "tags": []}]"#)
+                .unwrap();
+
+        let mut extractor = DatalogExtractor::new(backend::vector::Backend::default());
+        value.serialize(&mut extractor).unwrap();
+        let expected = extractor.get_backend().get_data().snapshot();
+
+        for batch_size in [0, 4] {
+            let mut extractor =
+                DatalogExtractor::new(record::Backend::default()).with_batch_size(batch_size);
+            value.serialize(&mut extractor).unwrap();
+            let calls = extractor.get_backend().into_calls();
+            assert_eq!(calls.first(), Some(&Call::Begin));
+            assert_eq!(calls.last(), Some(&Call::Finish));
+            assert_eq!(
+                calls.iter().any(|call| matches!(call, Call::Batch(_))),
+                batch_size > 0
+            );
+
+            // traces survive a round trip through JSON lines
+            let mut trace = Vec::new();
+            record::write_trace(&calls, &mut trace).unwrap();
+            let calls = record::read_trace(trace.as_slice()).unwrap();
+
+            let mut backend = backend::vector::Backend::default();
+            record::replay(&calls, &mut backend).unwrap();
+            assert_eq!(backend.get_data().snapshot(), expected);
+        }
+    }
+
+    #[test]
+    fn run_extractor_replay() {
+        use serde_datalog::{backend::record, snapshot::Snapshot};
+
+        let values: Vec<Value> = [
+            r#"{"id": 1, "tags": ["a", "b"]}"#,
+            r#"[{"id": 2, "tags": []}, null]"#,
+        ]
+        .iter()
+        .map(|json| serde_json::from_str(json).unwrap())
+        .collect();
+
+        let mut extractor = DatalogExtractor::new(backend::vector::Backend::default());
+        for value in values.iter() {
+            value.serialize(&mut extractor).unwrap();
+        }
+        let expected = extractor.get_backend().get_data().snapshot();
+
+        // values recorded by separate extractors are renumbered when replayed
+        let mut extractor = DatalogExtractor::new(backend::vector::Backend::default());
+        for value in values.iter() {
+            let mut recorder = DatalogExtractor::new(record::Backend::default());
+            value.serialize(&mut recorder).unwrap();
+            extractor
+                .replay(&recorder.get_backend().into_calls())
+                .unwrap();
+        }
+        assert_eq!(extractor.get_backend().get_data().snapshot(), expected);
+    }
+
+    #[test]
+    fn run_backend_conversions() {
+        use serde_datalog::{
+            backend::{
+                souffle_sqlite::AbstractBackend,
+                vector::{BackendData, SymbolId},
+            },
+            snapshot::Snapshot,
+        };
+
+        let value: Value = serde_json::from_str(
+            r#"{"kind": "Map", "tags": ["a, \"b\"", " c"], "size": -3, "ok": true}"#,
+        )
+        .unwrap();
+        let mut extractor =
+            DatalogExtractor::new(backend::vector::Backend::default().with_separate_namespaces());
+        extractor.set_file("a.json").unwrap();
+        value.serialize(&mut extractor).unwrap();
+        let data = extractor.get_backend().get_data();
+        let expected = data.snapshot();
+
+        // data stored in a database is read back with its symbols resolved
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        backend::souffle_sqlite::Backend::from(data)
+            .dump_into(&conn)
+            .unwrap();
+        let read: BackendData<ElemId> = BackendData::try_from(&conn).unwrap();
+        assert_eq!(read.snapshot(), expected);
+        assert!(BackendData::<SymbolId>::try_from(&conn).is_err());
+
+        // and so is data written to a directory of facts
+        let dir =
+            std::env::temp_dir().join(format!("serde_datalog_conversions_{}", std::process::id()));
+        backend::nemo::write_dir(&read, &dir).unwrap();
+        let read = backend::nemo::read_dir(&dir).unwrap();
+        assert_eq!(read.snapshot(), expected);
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let mut extractor = DatalogExtractor::new(backend::vector::StringKeyBackend::default());
+        value.serialize(&mut extractor).unwrap();
+        let data = extractor.get_backend().get_data();
+        let expected = data.snapshot();
+
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        backend::souffle_sqlite::StringKeyBackend::from(data)
+            .dump_into(&conn)
+            .unwrap();
+        let read: BackendData<SymbolId> = BackendData::try_from(&conn).unwrap();
+        assert_eq!(read.snapshot(), expected);
+        assert!(BackendData::<ElemId>::try_from(&conn).is_err());
+    }
+
+    #[test]
+    fn run_read_db() {
+        use serde_datalog::{backend::souffle_sqlite, snapshot::Snapshot};
+
+        let db = std::env::temp_dir().join(format!("serde_datalog_read_{}.db", std::process::id()));
+        let value: Value =
+            serde_json::from_str(r#"{"name": "a", "tags": ["x", "y"], "size": 3}"#).unwrap();
+
+        let mut extractor = DatalogExtractor::new(backend::vector::Backend::default());
+        extractor.set_file("a.json").unwrap();
+        value.serialize(&mut extractor).unwrap();
+        let expected = extractor.get_backend().get_data().snapshot();
+
+        let mut extractor = DatalogExtractor::new(souffle_sqlite::Backend::default());
+        extractor.set_file("a.json").unwrap();
+        value.serialize(&mut extractor).unwrap();
+        {
+            use souffle_sqlite::AbstractBackend;
+            extractor
+                .get_backend()
+                .dump_to_db(db.to_str().unwrap())
+                .unwrap();
+        }
+
+        let data = souffle_sqlite::read_db(&db).unwrap();
+        assert_eq!(data.snapshot(), expected);
+        assert!(souffle_sqlite::read_string_key_db(&db).is_err());
+
+        // databases created by newer versions are refused
+        let conn = rusqlite::Connection::open(&db).unwrap();
+        conn.execute(
+            "UPDATE __Metadata SET value = '999' WHERE key = 'schemaVersion';",
+            (),
+        )
+        .unwrap();
+        drop(conn);
+        assert!(souffle_sqlite::read_db(&db).is_err());
+
+        std::fs::remove_file(&db).unwrap();
+        assert!(souffle_sqlite::read_db(&db).is_err());
+    }
+
+    #[test]
+    fn run_same_strings() {
+        use serde_datalog::{backend::souffle_sqlite, snapshot::Snapshot};
+
+        let files = [
+            ("a.json", r#"{"user": "bob", "tags": ["x", "bob"]}"#),
+            ("b.json", r#"{"owner": "bob", "tag": "x"}"#),
+            ("c.json", r#"{"x": "bob"}"#),
+        ];
+        let values: Vec<(&str, Value)> = files
+            .iter()
+            .map(|(file, text)| (*file, serde_json::from_str(text).unwrap()))
+            .collect();
+
+        let mut extractor = DatalogExtractor::new(testing::FactRecorder::new(
+            backend::vector::Backend::default(),
+        ))
+        .with_same_strings(true);
+        for (file, value) in values.iter() {
+            extractor.set_file(file).unwrap();
+            value.serialize(&mut extractor).unwrap();
+        }
+        let (backend, facts) = extractor.get_backend().into_parts();
+        testing::check_invariants(&facts).unwrap();
+        let data = backend.get_data();
+
+        let value = |elem: &ElemId| -> &str {
+            let symbol = data.string_table[elem];
+            data.symbol_table.get_by_right(&symbol).unwrap()
+        };
+        let root = |elem: &ElemId| -> &str {
+            let mut elem = *elem;
+            loop {
+                let parent = data
+                    .map_table
+                    .iter()
+                    .find(|(_, value)| **value == elem)
+                    .map(|((parent, _), _)| *parent)
+                    .or_else(|| {
+                        data.seq_table
+                            .iter()
+                            .find(|(_, value)| **value == elem)
+                            .map(|((parent, _), _)| *parent)
+                    });
+                match parent {
+                    Some(parent) => elem = parent,
+                    None => break,
+                }
+            }
+            let (file, _) = data
+                .root_elem_table
+                .iter()
+                .find(|(_, root)| **root == elem)
+                .unwrap();
+            data.symbol_table.get_by_right(file).unwrap()
+        };
+
+        let mut same: Vec<(&str, &str, &str)> = data
+            .same_string_table
+            .iter()
+            .map(|(elem, other)| {
+                assert_eq!(value(elem), value(other));
+                (value(elem), root(elem), root(other))
+            })
+            .collect();
+        same.sort();
+
+        // strings are related to the first string of each earlier file, and
+        // map keys are not related
+        assert_eq!(
+            same,
+            vec![
+                ("bob", "b.json", "a.json"),
+                ("bob", "c.json", "a.json"),
+                ("bob", "c.json", "b.json"),
+                ("x", "b.json", "a.json"),
+            ]
+        );
+
+        // strings are not related by default
+        let mut extractor = DatalogExtractor::new(backend::vector::Backend::default());
+        for (file, value) in values.iter() {
+            extractor.set_file(file).unwrap();
+            value.serialize(&mut extractor).unwrap();
+        }
+        assert!(extractor
+            .get_backend()
+            .get_data()
+            .same_string_table
+            .is_empty());
+
+        let db = std::env::temp_dir().join(format!("serde_datalog_same_{}.db", std::process::id()));
+        let mut extractor =
+            DatalogExtractor::new(souffle_sqlite::Backend::default()).with_same_strings(true);
+        for (file, value) in values.iter() {
+            extractor.set_file(file).unwrap();
+            value.serialize(&mut extractor).unwrap();
+        }
+        {
+            use souffle_sqlite::AbstractBackend;
+            extractor
+                .get_backend()
+                .dump_to_db(db.to_str().unwrap())
+                .unwrap();
+        }
+
+        let read = souffle_sqlite::read_db(&db).unwrap();
+        assert_eq!(read.same_string_table, data.same_string_table);
+        assert_eq!(read.snapshot(), data.snapshot());
+        std::fs::remove_file(&db).unwrap();
+    }
+
+    #[test]
+    fn run_shapes() {
+        use serde_datalog::{backend::souffle_sqlite, snapshot::Snapshot};
+
+        #[derive(Serialize)]
+        struct User {
+            name: &'static str,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            email: Option<&'static str>,
+        }
+
+        let users = vec![
+            User {
+                name: "a",
+                email: Some("a@example.com"),
+            },
+            User {
+                name: "b",
+                email: None,
+            },
+            User {
+                name: "c",
+                email: Some("c@example.com"),
+            },
+        ];
+        let objects: Value =
+            serde_json::from_str(r#"[{"b": 1, "a": 2}, {"a": 3, "b": 4}, {"a": 5}]"#).unwrap();
+
+        let mut extractor = DatalogExtractor::new(testing::FactRecorder::new(
+            backend::vector::Backend::default(),
+        ))
+        .with_shapes(true);
+        extractor.set_file("users.json").unwrap();
+        users.serialize(&mut extractor).unwrap();
+        extractor.set_file("objects.json").unwrap();
+        objects.serialize(&mut extractor).unwrap();
+        let (backend, facts) = extractor.get_backend().into_parts();
+        testing::check_invariants(&facts).unwrap();
+        let data = backend.get_data();
+
+        let fields = |shape: &u64| -> Vec<&str> {
+            (0..)
+                .map_while(|pos| data.shape_field_table.get(&(*shape, pos)))
+                .map(|field| data.symbol(field).unwrap().as_str())
+                .collect()
+        };
+        let mut shapes: Vec<(usize, Vec<&str>)> = data
+            .shape_table
+            .values()
+            .map(|shape| {
+                let count = data.shape_table.values().filter(|s| *s == shape).count();
+                (count, fields(shape))
+            })
+            .collect();
+        shapes.sort();
+        shapes.dedup();
+
+        // structs and maps with the same fields in any order share shapes
+        assert_eq!(
+            shapes,
+            vec![
+                (1, vec!["a"]),
+                (1, vec!["name"]),
+                (2, vec!["a", "b"]),
+                (2, vec!["email", "name"]),
+            ]
+        );
+        assert_eq!(data.shape_field_table.len(), 6);
+
+        // shapes are not identified by default
+        let mut extractor = DatalogExtractor::new(backend::vector::Backend::default());
+        users.serialize(&mut extractor).unwrap();
+        assert!(extractor.get_backend().get_data().shape_table.is_empty());
+
+        let db =
+            std::env::temp_dir().join(format!("serde_datalog_shapes_{}.db", std::process::id()));
+        let mut extractor =
+            DatalogExtractor::new(souffle_sqlite::Backend::default()).with_shapes(true);
+        extractor.set_file("users.json").unwrap();
+        users.serialize(&mut extractor).unwrap();
+        extractor.set_file("objects.json").unwrap();
+        objects.serialize(&mut extractor).unwrap();
+        {
+            use souffle_sqlite::AbstractBackend;
+            extractor
+                .get_backend()
+                .dump_to_db(db.to_str().unwrap())
+                .unwrap();
+        }
+
+        let read = souffle_sqlite::read_db(&db).unwrap();
+        assert_eq!(read.shape_table, data.shape_table);
+        assert_eq!(read.snapshot(), data.snapshot());
+        std::fs::remove_file(&db).unwrap();
+    }
+
+    #[test]
+    fn run_number_domain() {
+        use serde_datalog::{
+            backend::souffle_sqlite::{self, AbstractBackend, LoadOptions, WideNumbers},
+            snapshot::Snapshot,
+        };
+
+        let value = serde_json::json!([3, 5_000_000_000i64, -5_000_000_000i64]);
+
+        let mut extractor = DatalogExtractor::new(backend::vector::Backend::default());
+        value.serialize(&mut extractor).unwrap();
+        let expected = extractor.get_backend().get_data().snapshot();
+
+        for (wide, parts, text) in [(WideNumbers::Split, 2, 0), (WideNumbers::Symbol, 0, 2)] {
+            let db = std::env::temp_dir().join(format!(
+                "serde_datalog_numbers_{:?}_{}.db",
+                wide,
+                std::process::id()
+            ));
+            let backend = souffle_sqlite::Backend::default()
+                .with_load_options(LoadOptions::default().number_domain(32, wide));
+            let mut extractor = DatalogExtractor::new(backend);
+            value.serialize(&mut extractor).unwrap();
+            extractor
+                .get_backend()
+                .dump_to_db(db.to_str().unwrap())
+                .unwrap();
+
+            let conn = rusqlite::Connection::open(&db).unwrap();
+            let count = |relation: &str| -> i64 {
+                conn.query_row(&format!("SELECT COUNT(*) FROM {};", relation), (), |row| {
+                    row.get(0)
+                })
+                .unwrap()
+            };
+            assert_eq!(count("number"), 1);
+            assert_eq!(count("numberParts"), parts);
+            assert_eq!(count("numberText"), text);
+            drop(conn);
+
+            // wide numbers are read back as numbers
+            let data = souffle_sqlite::read_db(&db).unwrap();
+            assert_eq!(data.snapshot(), expected);
+            std::fs::remove_file(&db).unwrap();
+        }
+
+        let backend = souffle_sqlite::Backend::default()
+            .with_load_options(LoadOptions::default().number_domain(32, WideNumbers::Error));
+        let mut extractor = DatalogExtractor::new(backend);
+        assert!(value.serialize(&mut extractor).is_err());
+    }
+
+    #[test]
+    fn run_error_context() {
+        use serde::ser::Error;
+
+        struct Malformed;
+
+        impl Serialize for Malformed {
+            fn serialize<S: serde::Serializer>(&self, _serializer: S) -> Result<S::Ok, S::Error> {
+                Result::Err(S::Error::custom("malformed value"))
+            }
+        }
+
+        #[derive(Serialize)]
+        struct Item {
+            name: Malformed,
+        }
+
+        let value = std::collections::BTreeMap::from([(
+            "items",
+            vec![None, Some(Item { name: Malformed })],
+        )]);
+
+        let mut extractor =
+            DatalogExtractor::new(backend::vector::Backend::default()).with_error_context(true);
+        let err = value.serialize(&mut extractor).unwrap_err();
+        let context = err.context().unwrap();
+        assert_eq!(context.path, r#"$["items"][1].name"#);
+        assert!(context.facts > 0);
+        assert_eq!(
+            err.to_string(),
+            format!(
+                r#"malformed value at $["items"][1].name after {} fact(s)"#,
+                context.facts
+            )
+        );
+        assert!(matches!(
+            err.without_context(),
+            DatalogExtractionError::Custom(msg) if msg == "malformed value"
+        ));
+
+        // errors have no context by default
+        let mut extractor = DatalogExtractor::new(backend::vector::Backend::default());
+        let err = value.serialize(&mut extractor).unwrap_err();
+        assert!(err.context().is_none());
+    }
+
+    #[test]
+    fn run_parse_errors() {
+        use serde_datalog::{
+            backend::souffle_sqlite::{self, AbstractBackend},
+            snapshot::Snapshot,
+        };
+
+        let db = std::env::temp_dir().join(format!(
+            "serde_datalog_parse_errors_{}.db",
+            std::process::id()
+        ));
+        let mut extractor = DatalogExtractor::new(souffle_sqlite::Backend::default());
+        extractor.set_file_format("a.ndjson", "ndjson").unwrap();
+        for (line, value) in [(1, Some(1)), (2, None), (3, Some(3))] {
+            extractor.set_file(&format!("a.ndjson:{}", line)).unwrap();
+            match value {
+                Some(value) => serde_json::json!({ "a": value })
+                    .serialize(&mut extractor)
+                    .unwrap(),
+                None => extractor
+                    .record_parse_error("a.ndjson", line, "EOF while parsing a value")
+                    .unwrap(),
+            }
+        }
+        extractor
+            .get_backend()
+            .dump_to_db(db.to_str().unwrap())
+            .unwrap();
+
+        let conn = rusqlite::Connection::open(&db).unwrap();
+        let errors: Vec<(String, i64, String)> = conn
+            .prepare("SELECT file, line, message FROM parseError;")
+            .unwrap()
+            .query_map((), |row| {
+                Result::Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+            })
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(
+            errors,
+            vec![(
+                "a.ndjson".to_string(),
+                2,
+                "EOF while parsing a value".to_string()
+            )]
+        );
+        drop(conn);
+
+        // parse errors are read back, and snapshots include them
+        let data = souffle_sqlite::read_db(&db).unwrap();
+        assert_eq!(data.parse_error_table.len(), 1);
+        assert!(data
+            .snapshot()
+            .contains(r#"parseError("a.ndjson", 2, "EOF while parsing a value")."#));
+        std::fs::remove_file(&db).unwrap();
+    }
+
+    #[test]
+    fn run_atomic_roots() {
+        use serde::ser::Error;
+        use serde_datalog::{unit_policy::UnitPolicy, Fact};
+
+        struct Malformed;
+
+        impl Serialize for Malformed {
+            fn serialize<S: serde::Serializer>(&self, _serializer: S) -> Result<S::Ok, S::Error> {
+                Result::Err(S::Error::custom("malformed value"))
+            }
+        }
+
+        #[derive(Serialize)]
+        struct Item {
+            id: u32,
+            name: Malformed,
+        }
+
+        let extract = |skip: bool| {
+            let mut extractor = DatalogExtractor::new(backend::vector::Backend::default())
+                .with_atomic_roots(true)
+                .with_shapes(true)
+                .with_unit_policy(UnitPolicy::Singletons)
+                .with_batch_size(2);
+            extractor.set_file("a.ndjson:1").unwrap();
+            serde_json::json!({ "a": 1 })
+                .serialize(&mut extractor)
+                .unwrap();
+            if skip {
+                extractor.set_file("a.ndjson:2").unwrap();
+                let value = vec![Some(((), "b")), None];
+                let item = Item {
+                    id: 2,
+                    name: Malformed,
+                };
+                assert!((value, item).serialize(&mut extractor).is_err());
+                extractor
+                    .record_parse_error("a.ndjson", 2, "malformed value")
+                    .unwrap();
+            }
+            extractor.set_file("a.ndjson:3").unwrap();
+            (vec![Some(((), "b"))], serde_json::json!({ "c": 3 }))
+                .serialize(&mut extractor)
+                .unwrap();
+            extractor.get_backend().get_data().facts()
+        };
+
+        // the facts of the skipped document are discarded, and the next
+        // document is extracted as if it had never been serialized
+        let facts = extract(true);
+        let (errors, facts): (Vec<Fact>, Vec<Fact>) = facts
+            .into_iter()
+            .partition(|fact| matches!(fact, Fact::ParseError { .. }));
+        assert_eq!(errors.len(), 1);
+        assert_eq!(facts, extract(false));
+        let roots = facts
+            .iter()
+            .filter(|fact| matches!(fact, Fact::RootElem { .. }))
+            .count();
+        assert_eq!(roots, 2);
+    }
+
+    #[test]
+    fn run_checkpoint() {
+        use serde_datalog::backend::record::{self, Call};
+
+        // the calls received by the backend when the values of the second
+        // file are extracted directly, in a checkpoint that is committed, in
+        // a checkpoint that is discarded, or not at all; batches are split
+        // into their facts
+        let extract = |checkpoint: Option<bool>, second: bool| {
+            let mut extractor = DatalogExtractor::new(record::Backend::default()).with_shapes(true);
+            extractor.set_file("a.json").unwrap();
+            serde_json::json!({ "a": 1 })
+                .serialize(&mut extractor)
+                .unwrap();
+            if second {
+                if checkpoint.is_some() {
+                    extractor.begin_checkpoint().unwrap();
+                }
+                extractor.set_file_format("b.ndjson", "ndjson").unwrap();
+                extractor.set_file("b.ndjson:1").unwrap();
+                serde_json::json!({ "b": [2] })
+                    .serialize(&mut extractor)
+                    .unwrap();
+                extractor.set_root_meta("b.ndjson:1", "line", "1").unwrap();
+                extractor.set_file("b.ndjson:2").unwrap();
+                serde_json::json!({ "a": 3 })
+                    .serialize(&mut extractor)
+                    .unwrap();
+                match checkpoint {
+                    Some(true) => extractor.commit_checkpoint().unwrap(),
+                    Some(false) => extractor.discard_checkpoint(),
+                    None => {}
+                }
+            }
+            extractor.set_file("c.json").unwrap();
+            serde_json::json!({ "b": [4] })
+                .serialize(&mut extractor)
+                .unwrap();
+
+            let mut calls = Vec::new();
+            for call in extractor.get_backend().into_calls() {
+                match call {
+                    Call::Batch(facts) => calls.extend(facts.into_iter().map(Call::Fact)),
+                    call => calls.push(call),
+                }
+            }
+            calls
+        };
+
+        // a committed checkpoint passes the same calls to the backend as if
+        // there had been none, and a discarded one as if its values had
+        // never been serialized
+        assert_eq!(extract(Some(true), true), extract(None, true));
+        assert_eq!(extract(Some(false), true), extract(None, false));
+        assert_ne!(extract(None, true), extract(None, false));
+    }
+
+    #[test]
+    fn run_elided_strings() {
+        use serde_datalog::string_policy::StringPolicy;
+
+        let blob = "x".repeat(100);
+        let value = serde_json::json!({ "a": blob, "b": blob, "c": "short" });
+
+        let mut extractor = DatalogExtractor::new(backend::vector::Backend::default())
+            .with_string_policy(StringPolicy::default().elide(64));
+        value.serialize(&mut extractor).unwrap();
+        let data = extractor.get_backend().get_data();
+
+        // map keys and short strings are kept
+        let mut strings: Vec<&String> = data
+            .string_table
+            .values()
+            .map(|sym| data.symbol(sym).unwrap())
+            .collect();
+        strings.sort();
+        assert_eq!(strings, vec!["a", "b", "c", "short"]);
+        assert!(!data.symbol_table.contains_left(&blob));
+
+        // equal elided strings have equal hashes
+        let hashes: Vec<&(u64, usize)> = data.string_hash_table.values().collect();
+        assert_eq!(hashes.len(), 2);
+        assert_eq!(hashes[0], hashes[1]);
+        assert_eq!(hashes[0].1, 100);
+    }
+
+    #[test]
+    fn run_boxed_backend() {
+        use serde_datalog::{DatalogExtractorBackend, ElemType};
+
+        // backend that only records the files of root elements
+        struct RootFiles<'a>(&'a mut Vec<String>);
+
+        impl DatalogExtractorBackend for RootFiles<'_> {
+            fn add_root_elem(&mut self, file: &str, _elem: ElemId) -> serde_datalog::Result<()> {
+                self.0.push(file.to_string());
+                Ok(())
+            }
+
+            fn add_elem(
+                &mut self,
+                _elem: ElemId,
+                _elem_type: ElemType,
+            ) -> serde_datalog::Result<()> {
+                Ok(())
+            }
+
+            fn add_i64(&mut self, _elem: ElemId, _value: i64) -> serde_datalog::Result<()> {
+                Ok(())
+            }
+        }
+
+        let mut files = Vec::new();
+        for record_files in [false, true] {
+            let backend: Box<dyn DatalogExtractorBackend> = if record_files {
+                Box::new(RootFiles(&mut files))
+            } else {
+                Box::new(backend::vector::Backend::default())
+            };
+
+            let mut extractor = DatalogExtractor::new(backend);
+            for file in ["a.json", "b.json"] {
+                extractor.set_file(file).unwrap();
+                1.serialize(&mut extractor).unwrap();
+            }
+        }
+
+        assert_eq!(files, vec!["a.json", "b.json"]);
+    }
+
+    #[test]
+    fn run_borrowed_backend() {
+        let mut backend = backend::vector::Backend::default();
+        let mut extractor = DatalogExtractor::new(&mut backend);
+        vec![1, 2].serialize(&mut extractor).unwrap();
+        assert_eq!(backend.get_data().number_table.len(), 2);
+
+        let recorder = Box::new(testing::FactRecorder::new(
+            backend::vector::Backend::default(),
+        ));
+        let mut extractor = DatalogExtractor::new(recorder);
+        vec![1, 2].serialize(&mut extractor).unwrap();
+        let (backend, facts) = extractor.get_backend().into_parts();
+        assert_eq!(backend.get_data().number_table.len(), 2);
+        assert!(testing::check_invariants(&facts).is_ok());
+    }
+
+    #[test]
+    fn run_extractor_config() {
+        use serde_datalog::{config::ConfigError, quota::FactQuotas, sample::Sampling};
+
+        let mut extractor = DatalogExtractor::builder()
+            .fact_quotas(FactQuotas::default().limit("number", 2))
+            .build(backend::vector::Backend::default())
+            .unwrap();
+        vec![1, 2, 3].serialize(&mut extractor).unwrap();
+        let data = extractor.get_backend().get_data();
+        assert_eq!(data.number_table.len(), 2);
+        assert_eq!(data.truncated_table.len(), 1);
+
+        let err = DatalogExtractor::builder()
+            .fact_quotas(FactQuotas::default().limit("numbers", 2))
+            .validate()
+            .unwrap_err();
+        assert_eq!(
+            err,
+            ConfigError::UnknownQuotaRelation("numbers".to_string())
+        );
+
+        let err = DatalogExtractor::builder()
+            .hints(StructHints::new("User").skip("password"))
+            .hints(StructHints::new("User").key("id"))
+            .build(backend::vector::Backend::default())
+            .err()
+            .unwrap();
+        assert_eq!(err, ConfigError::DuplicateHints("User".to_string()));
+
+        let err = DatalogExtractor::builder()
+            .sampling(Sampling::stratified(0))
+            .validate()
+            .unwrap_err();
+        assert_eq!(err, ConfigError::ZeroSampleThreshold);
+    }
+
+    #[test]
+    fn run_root_meta() {
+        use serde_datalog::{
+            backend::souffle_sqlite::{self, AbstractBackend},
+            snapshot::Snapshot,
+        };
+
+        let db =
+            std::env::temp_dir().join(format!("serde_datalog_root_meta_{}.db", std::process::id()));
+        let mut extractor = DatalogExtractor::new(souffle_sqlite::Backend::default());
+        extractor.set_file("request-1").unwrap();
+        extractor
+            .set_root_meta("request-1", "uri", "https://example.com/config.json")
+            .unwrap();
+        extractor
+            .set_root_meta("request-1", "name", "config")
+            .unwrap();
+        extractor
+            .set_root_meta("request-1", "env", "staging")
+            .unwrap();
+        serde_json::json!({ "a": 1 })
+            .serialize(&mut extractor)
+            .unwrap();
+        extractor
+            .get_backend()
+            .dump_to_db(db.to_str().unwrap())
+            .unwrap();
+
+        let conn = rusqlite::Connection::open(&db).unwrap();
+        let meta: Vec<(String, String)> = conn
+            .prepare(
+                "SELECT key, value FROM rootMeta INNER JOIN rootElem
+                ON rootMeta.file = rootElem.file ORDER BY key;",
+            )
+            .unwrap()
+            .query_map((), |row| Result::Ok((row.get(0)?, row.get(1)?)))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(
+            meta,
+            vec![
+                ("env".to_string(), "staging".to_string()),
+                ("name".to_string(), "config".to_string()),
+                (
+                    "uri".to_string(),
+                    "https://example.com/config.json".to_string()
+                ),
+            ]
+        );
+        drop(conn);
+
+        // root metadata is read back, and snapshots include it
+        let data = souffle_sqlite::read_db(&db).unwrap();
+        assert_eq!(data.root_meta_table.len(), 3);
+        assert!(data
+            .snapshot()
+            .contains(r#"rootMeta("request-1", "name", "config")."#));
+        std::fs::remove_file(&db).unwrap();
+    }
+
+    #[test]
+    fn run_measures() {
+        use serde_datalog::backend::souffle_sqlite::{self, AbstractBackend};
+
+        #[derive(Serialize)]
+        struct Meters(u32);
+
+        #[derive(Serialize)]
+        struct Point {
+            x: Meters,
+            label: Option<&'static str>,
+            tags: Vec<u8>,
+        }
+
+        let points = vec![
+            Point {
+                x: Meters(1),
+                label: Some("a"),
+                tags: vec![1, 2],
+            },
+            Point {
+                x: Meters(2),
+                label: None,
+                tags: vec![],
+            },
+        ];
+
+        let mut extractor = DatalogExtractor::new(testing::FactRecorder::new(
+            backend::vector::Backend::default(),
+        ))
+        .with_measures(true);
+        points.serialize(&mut extractor).unwrap();
+        let (backend, facts) = extractor.get_backend().into_parts();
+        testing::check_invariants(&facts).unwrap();
+        let data = backend.get_data();
+
+        // every element is measured; newtypes are one level above their values
+        assert_eq!(data.depth_table.len(), data.type_table.len());
+        let mut depths: Vec<usize> = data.depth_table.values().copied().collect();
+        depths.sort();
+        assert_eq!(depths, vec![0, 1, 1, 2, 2, 2, 2, 2, 2, 3, 3, 3, 3, 3]);
+        let mut sizes: Vec<usize> = data.subtree_size_table.values().copied().collect();
+        sizes.sort();
+        assert_eq!(sizes, vec![1, 1, 1, 1, 1, 1, 1, 2, 2, 2, 3, 5, 8, 14]);
+
+        // elements are not measured by default
+        let mut extractor = DatalogExtractor::new(backend::vector::Backend::default());
+        points.serialize(&mut extractor).unwrap();
+        let data = extractor.get_backend().get_data();
+        assert!(data.depth_table.is_empty());
+        assert!(data.subtree_size_table.is_empty());
+
+        let db =
+            std::env::temp_dir().join(format!("serde_datalog_measures_{}.db", std::process::id()));
+        let mut extractor =
+            DatalogExtractor::new(souffle_sqlite::Backend::default()).with_measures(true);
+        extractor.set_file("points.json").unwrap();
+        points.serialize(&mut extractor).unwrap();
+        extractor
+            .get_backend()
+            .dump_to_db(db.to_str().unwrap())
+            .unwrap();
+
+        let conn = rusqlite::Connection::open(&db).unwrap();
+        let root: (usize, usize) = conn
+            .query_row(
+                "SELECT depth, size FROM rootElem
+                INNER JOIN depth ON rootElem.elem = depth.id
+                INNER JOIN subtreeSize ON rootElem.elem = subtreeSize.id;",
+                (),
+                |row| Result::Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap();
+        assert_eq!(root, (0, 14));
+        drop(conn);
+
+        let read = souffle_sqlite::read_db(&db).unwrap();
+        assert_eq!(read.depth_table.len(), 14);
+        assert_eq!(read.subtree_size_table.len(), 14);
+        std::fs::remove_file(&db).unwrap();
+    }
+
+    #[test]
+    fn run_char_codepoints() {
+        use serde_datalog::backend::souffle_sqlite::{self, AbstractBackend};
+
+        let chars = vec!['a', 'é', '€', '🦀'];
+
+        let mut extractor = DatalogExtractor::new(testing::FactRecorder::new(
+            backend::vector::Backend::default(),
+        ))
+        .with_char_codepoints(true);
+        chars.serialize(&mut extractor).unwrap();
+        let (backend, facts) = extractor.get_backend().into_parts();
+        testing::check_invariants(&facts).unwrap();
+        let data = backend.get_data();
+
+        // chars keep their string values
+        let mut codepoints: Vec<u32> = data.char_table.values().copied().collect();
+        codepoints.sort();
+        assert_eq!(codepoints, vec![0x61, 0xe9, 0x20ac, 0x1f980]);
+        for (elem, codepoint) in data.char_table.iter() {
+            let value = data.symbol(data.string_table.get(elem).unwrap()).unwrap();
+            assert_eq!(
+                value.chars().collect::<Vec<_>>(),
+                vec![char::from_u32(*codepoint).unwrap()]
+            );
+        }
+
+        // code points are not recorded by default
+        let mut extractor = DatalogExtractor::new(backend::vector::Backend::default());
+        chars.serialize(&mut extractor).unwrap();
+        assert!(extractor.get_backend().get_data().char_table.is_empty());
+
+        let db = std::env::temp_dir().join(format!(
+            "serde_datalog_char_codepoints_{}.db",
+            std::process::id()
+        ));
+        let mut extractor =
+            DatalogExtractor::new(souffle_sqlite::Backend::default()).with_char_codepoints(true);
+        chars.serialize(&mut extractor).unwrap();
+        extractor
+            .get_backend()
+            .dump_to_db(db.to_str().unwrap())
+            .unwrap();
+
+        let conn = rusqlite::Connection::open(&db).unwrap();
+        let ascii: Vec<String> = conn
+            .prepare(
+                "SELECT value FROM char INNER JOIN string ON char.id = string.id
+                WHERE codepoint < 128;",
+            )
+            .unwrap()
+            .query_map((), |row| row.get(0))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(ascii, vec!["a".to_string()]);
+        drop(conn);
+
+        let read = souffle_sqlite::read_db(&db).unwrap();
+        assert_eq!(read.char_table.len(), 4);
+        std::fs::remove_file(&db).unwrap();
+    }
+
+    #[test]
+    fn run_invariant_violations() {
+        use serde_datalog::{testing::InvariantViolation, Fact};
+
+        let mut extractor = DatalogExtractor::new(testing::FactRecorder::new(
+            backend::vector::Backend::default(),
+        ));
+        vec![1, 2].serialize(&mut extractor).unwrap();
+        let (_, mut facts) = extractor.get_backend().into_parts();
+        assert_eq!(testing::check_invariants(&facts), Ok(()));
+
+        let entries: Vec<(ElemId, ElemId)> = facts
+            .iter()
+            .filter_map(|fact| match fact {
+                Fact::SeqEntry { elem, value, .. } => Some((*elem, *value)),
+                _ => None,
+            })
+            .collect();
+        let (root, first) = entries[0];
+        let (_, second) = entries[1];
+
+        // make the root an entry of its first item, and the second item an
+        // entry of both
+        facts.push(Fact::SeqEntry {
+            elem: first,
+            pos: 0,
+            value: root,
+        });
+        facts.push(Fact::TupleEntry {
+            elem: first,
+            pos: 0,
+            value: second,
+        });
+
+        let violations = testing::check_invariants(&facts).unwrap_err();
+        assert!(violations.contains(&InvariantViolation::MultipleParents(second)));
+        assert!(violations.contains(&InvariantViolation::RootCount {
+            expected: 1,
+            found: 0
+        }));
+        assert!(violations.contains(&InvariantViolation::Cycle(root)));
+    }
+
+    #[test]
+    fn run_validate() {
+        use backend::souffle_sqlite::{AbstractBackend, SchemaConfig};
+        use serde_datalog::validate::{validate, ViolationKind};
+
+        let value = (vec!["a", "b"], vec![1, 2, 3]);
+        let mut extractor = DatalogExtractor::new(backend::souffle_sqlite::Backend::default());
+        value.serialize(&mut extractor).unwrap();
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        extractor.get_backend().dump_into(&conn).unwrap();
+
+        let schema = SchemaConfig::default();
+        let report = validate(&conn, &schema).unwrap();
+        assert!(report.is_valid(), "{}", report);
+
+        // remove the type of the string "a", which orphans its symbol, and
+        // leave a gap in the positions of the second sequence
+        conn.execute_batch(
+            "PRAGMA foreign_keys = OFF;
+            DELETE FROM _type WHERE id = (SELECT id FROM _string WHERE value =
+                (SELECT id FROM __SymbolTable WHERE symbol = 'a'));
+            DELETE FROM _string WHERE value = (SELECT id FROM __SymbolTable WHERE symbol = 'a');
+            DELETE FROM _seq WHERE pos = 1 AND id = (SELECT MAX(id) FROM _seq);",
+        )
+        .unwrap();
+
+        let report = validate(&conn, &schema).unwrap();
+        let kinds: Vec<ViolationKind> = report
+            .violations
+            .iter()
+            .map(|violation| violation.kind)
+            .collect();
+        assert_eq!(
+            kinds,
+            vec![
+                ViolationKind::MissingElem,
+                ViolationKind::OrphanSymbol,
+                ViolationKind::SparsePositions
+            ]
+        );
+        assert_eq!(report.violations[0].table, "_seq");
+        assert_eq!(report.violations[0].column, "value");
+    }
+
+    #[test]
+    fn run_validate_replaced() {
+        use backend::souffle_sqlite::{AbstractBackend, SchemaConfig, StringKeyBackend};
+        use serde_datalog::{validate::validate, DatalogExtractorBackend};
+        use std::collections::BTreeMap;
+
+        fn replace<B: AbstractBackend + DatalogExtractorBackend + Default>() {
+            let conn = rusqlite::Connection::open_in_memory().unwrap();
+            let extract = |files: &[(&str, BTreeMap<&str, Vec<&str>>)], append: bool| {
+                let mut backend = B::default();
+                let mut extractor = if append {
+                    let first_elem_id = backend.load_from(&conn).unwrap();
+                    DatalogExtractor::new_with_first_elem_id(backend, first_elem_id)
+                } else {
+                    DatalogExtractor::new(backend)
+                };
+                for (file, value) in files {
+                    extractor.set_file(file).unwrap();
+                    value.serialize(&mut extractor).unwrap();
+                }
+                if append {
+                    extractor.get_backend().append_into(&conn, true).unwrap();
+                } else {
+                    extractor.get_backend().dump_into(&conn).unwrap();
+                }
+            };
+
+            extract(
+                &[
+                    ("a.json", BTreeMap::from([("old", vec!["x", "y"])])),
+                    ("b.json", BTreeMap::from([("kept", vec!["z"])])),
+                ],
+                false,
+            );
+            extract(&[("a.json", BTreeMap::from([("new", vec![])]))], true);
+
+            let schema = SchemaConfig::default();
+            let report = validate(&conn, &schema).unwrap();
+            assert!(report.is_valid(), "{}", report);
+
+            let symbols: Vec<String> = conn
+                .prepare("SELECT symbol FROM __SymbolTable WHERE symbol IN ('old', 'x', 'y', 'new', 'kept', 'z') ORDER BY symbol;")
+                .unwrap()
+                .query_map((), |row| row.get(0))
+                .unwrap()
+                .collect::<Result<_, _>>()
+                .unwrap();
+            assert_eq!(symbols, vec!["kept", "new", "z"]);
+
+            // the elements of b.json and the new a.json, i.e. two maps and
+            // sequences, the string "z", and with string keys, the two keys
+            let elems: usize = conn
+                .query_row("SELECT COUNT(*) FROM _type;", (), |row| row.get(0))
+                .unwrap();
+            assert_eq!(elems, 7);
+        }
+
+        replace::<backend::souffle_sqlite::Backend>();
+        replace::<StringKeyBackend>();
+    }
+
+    #[test]
+    fn run_diff() {
+        use backend::souffle_sqlite::{AbstractBackend, SchemaConfig};
+        use serde_datalog::diff::{diff, ChangedFact, MatchMode};
+
+        let dump = |value: Value| -> rusqlite::Connection {
+            let mut extractor = DatalogExtractor::new(backend::souffle_sqlite::Backend::default());
+            extractor.set_file("config.json").unwrap();
+            value.serialize(&mut extractor).unwrap();
+            let conn = rusqlite::Connection::open_in_memory().unwrap();
+            extractor.get_backend().dump_into(&conn).unwrap();
+            conn
+        };
+
+        let before = dump(serde_json::json!({"name": "app", "tags": ["a", "b"]}));
+        let after = dump(serde_json::json!({"tags": ["a", "c"], "name": "app", "debug": true}));
+        let schema = SchemaConfig::default();
+
+        let result = diff(&before, &before, &schema, MatchMode::Path).unwrap();
+        assert!(result.is_empty(), "{}", result);
+
+        let result = diff(&before, &after, &schema, MatchMode::Path).unwrap();
+        assert!(result.removed.is_empty());
+        assert_eq!(
+            result.added,
+            vec![
+                "bool(/debug, 1).",
+                "map(/, /debug#key, /debug).",
+                "string(/debug#key, \"debug\").",
+                "type(/debug#key, \"Str\").",
+                "type(/debug, \"Bool\")."
+            ]
+        );
+        assert_eq!(
+            result.changed,
+            vec![ChangedFact {
+                before: "string(/tags/1, \"b\").".to_string(),
+                after: "string(/tags/1, \"c\").".to_string(),
+            }]
+        );
+
+        // equal subtrees are matched wherever they occur
+        let before = dump(serde_json::json!([{"id": 1}, {"id": 2}]));
+        let after = dump(serde_json::json!([{"id": 2}, {"id": 1}]));
+        let result = diff(&before, &after, &schema, MatchMode::Structural).unwrap();
+        assert!(result
+            .removed
+            .iter()
+            .chain(result.added.iter())
+            .all(|fact| !fact.starts_with("map(") && !fact.starts_with("number(")));
+    }
+
+    #[test]
+    fn run_infer_schema() {
+        use backend::souffle_sqlite::{AbstractBackend, SchemaConfig};
+        use serde_datalog::infer::{infer_schema, FieldSchema, RecordKind};
+
+        let value = serde_json::json!({
+            "name": "app",
+            "deps": [
+                {"name": "serde", "features": ["derive"]},
+                {"name": "rand", "optional": true},
+            ],
+        });
+        let mut extractor = DatalogExtractor::new(backend::souffle_sqlite::Backend::default());
+        extractor.set_file("Cargo.json").unwrap();
+        value.serialize(&mut extractor).unwrap();
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        extractor.get_backend().dump_into(&conn).unwrap();
+
+        let inferred = infer_schema(&conn, &SchemaConfig::default()).unwrap();
+        assert_eq!(inferred.roots, vec!["/"]);
+        assert_eq!(inferred.records.len(), 2);
+
+        let deps = &inferred.records[1];
+        assert_eq!(deps.name, "/deps/*");
+        assert_eq!(deps.kind, RecordKind::Map);
+        assert_eq!(deps.count, 2);
+        assert_eq!(
+            deps.fields,
+            vec![
+                FieldSchema {
+                    name: "features".to_string(),
+                    count: 1,
+                    optional: true,
+                    types: vec!["[Str]".to_string()],
+                },
+                FieldSchema {
+                    name: "name".to_string(),
+                    count: 2,
+                    optional: false,
+                    types: vec!["Str".to_string()],
+                },
+                FieldSchema {
+                    name: "optional".to_string(),
+                    count: 1,
+                    optional: true,
+                    types: vec!["Bool".to_string()],
+                },
+            ]
+        );
+
+        let souffle = inferred.to_souffle();
+        assert!(souffle.contains(".type Root_deps_item = ["));
+        assert!(souffle.contains("    name: symbol, // Str"));
+    }
+
+    #[cfg(feature = "json_schema")]
+    #[test]
+    fn run_schema_guide() {
+        use serde_datalog::schema_guide::SchemaGuide;
+
+        let openapi = serde_json::json!({
+            "components": {"schemas": {
+                "Pet": {
+                    "properties": {
+                        "name": {"type": "string"},
+                        "owner": {"$ref": "#/components/schemas/User"},
+                    },
+                    "additionalProperties": {"type": "integer"},
+                },
+                "User": {
+                    "properties": {
+                        "friends": {"items": {"$ref": "#/components/schemas/User"}},
+                    },
+                },
+            }},
+        });
+        let guide = SchemaGuide::from_json_schema(&openapi, "#/components/schemas/Pet").unwrap();
+
+        let value = serde_json::json!({
+            "name": "rex",
+            "owner": {"friends": [{"friends": []}]},
+            "age": 3,
+        });
+        let mut extractor =
+            DatalogExtractor::new(backend::vector::Backend::default()).with_schema_guide(guide);
+        value.serialize(&mut extractor).unwrap();
+        let data = extractor.get_backend().get_data();
+
+        let mut schema_refs: Vec<&str> = data
+            .schema_type_table
+            .values()
+            .map(|schema_ref| data.symbol(schema_ref).unwrap().as_str())
+            .collect();
+        schema_refs.sort();
+        assert_eq!(
+            schema_refs,
+            vec![
+                "#/components/schemas/Pet",
+                "#/components/schemas/Pet/additionalProperties",
+                "#/components/schemas/Pet/properties/name",
+                "#/components/schemas/User",
+                "#/components/schemas/User",
+                "#/components/schemas/User/properties/friends",
+                "#/components/schemas/User/properties/friends",
+            ]
+        );
+
+        // map keys are not annotated
+        assert_eq!(data.string_table.len(), 6);
+        assert_eq!(data.schema_type_table.len(), 7);
+
+        assert!(SchemaGuide::from_json_schema(&openapi, "#/components/schemas/Dog").is_err());
+    }
+
+    #[test]
+    fn run_datetimes() {
+        use serde_datalog::datetime::{Datetime, DatetimeStr};
+
+        assert_eq!(
+            Datetime::parse_rfc3339("1979-05-27T07:32:00.5-08:00"),
+            Some(Datetime {
+                epoch_micros: 296_667_120_500_000,
+                tz_offset: -8 * 3600,
+            })
+        );
+        assert_eq!(
+            Datetime::parse("2001-12-14 21:59:43.10 -5"),
+            Datetime::parse_rfc3339("2001-12-14T21:59:43.10-05:00")
+        );
+        assert_eq!(
+            Datetime::parse("1969-12-31").unwrap().epoch_micros,
+            -86_400_000_000
+        );
+        assert_eq!(Datetime::parse_rfc3339("1969-12-31"), None);
+        assert_eq!(Datetime::parse("07:32:00"), None);
+        assert_eq!(Datetime::parse("2024-13-01"), None);
+
+        // marked strings and values of TOML date-time maps are date-times
+        let value = serde_json::json!({
+            "$__toml_private_datetime": "1979-05-27T07:32:00Z",
+            "plain": "1979-05-27T07:32:00Z",
+        });
+        let mut extractor = DatalogExtractor::new(backend::vector::Backend::default());
+        value.serialize(&mut extractor).unwrap();
+        (DatetimeStr("1979-05-27"), DatetimeStr("not a date"))
+            .serialize(&mut extractor)
+            .unwrap();
+        let data = extractor.get_backend().get_data();
+
+        let mut datetimes: Vec<(i64, i32)> = data.datetime_table.values().copied().collect();
+        datetimes.sort();
+        assert_eq!(
+            datetimes,
+            vec![(296_611_200_000_000, 0), (296_638_320_000_000, 0)]
+        );
+
+        // the marker newtype struct is not extracted
+        assert_eq!(data.tuple_table.len(), 2);
+
+        // all date-time strings are date-times if enabled
+        let mut extractor = DatalogExtractor::new(backend::vector::Backend::default());
+        extractor.set_datetime_strings(true);
+        value.serialize(&mut extractor).unwrap();
+        assert_eq!(extractor.get_backend().get_data().datetime_table.len(), 2);
+    }
+
+    #[test]
+    fn run_recognizers() {
+        use serde_datalog::recognize::{Recognizers, Scalar};
+
+        let recognizers = Recognizers::all();
+        assert_eq!(
+            recognizers.recognize("f81d4fae-7dec-11d0-A765-00a0c91e6bf6"),
+            Some(Scalar::Uuid {
+                hi: 0xf81d4fae7dec11d0,
+                lo: 0xa76500a0c91e6bf6,
+            })
+        );
+        assert_eq!(
+            recognizers.recognize("0:0:0:0:0:0:0:1"),
+            Some(Scalar::Ip("::1".parse().unwrap()))
+        );
+        assert_eq!(
+            recognizers.recognize("https://ada@Example.com:8080/a?b"),
+            Some(Scalar::Url {
+                scheme: "https".to_string(),
+                host: "example.com".to_string(),
+            })
+        );
+        assert_eq!(recognizers.recognize("f81d4fae-7dec-11d0-a765"), None);
+        assert_eq!(recognizers.recognize("10.0.0.256"), None);
+        assert_eq!(recognizers.recognize("a@b"), None);
+        assert_eq!(recognizers.recognize("see https://example.com"), None);
+
+        let value = serde_json::json!({
+            "id": "f81d4fae-7dec-11d0-a765-00a0c91e6bf6",
+            "ip": "10.0.0.1",
+            "home": "http://example.com",
+            "mail": "ada@example.com",
+        });
+        let mut extractor = DatalogExtractor::new(backend::vector::Backend::default())
+            .with_recognizers(Recognizers::default().ip(true).email(true));
+        value.serialize(&mut extractor).unwrap();
+        let data = extractor.get_backend().get_data();
+
+        // the strings themselves are still extracted
+        assert_eq!(data.string_table.len(), 8);
+        assert_eq!(data.uuid_table.len(), 0);
+        assert_eq!(data.ip_table.len(), 1);
+        assert_eq!(data.url_table.len(), 0);
+        assert_eq!(data.email_table.len(), 1);
+    }
+
+    #[test]
+    fn run_flatten() {
+        use std::collections::BTreeMap;
+
+        #[derive(Serialize)]
+        struct Name {
+            first: &'static str,
+            last: &'static str,
+        }
+
+        #[derive(Serialize)]
+        enum Contact {
+            Email(&'static str),
+            Phone { country: u32, number: u64 },
+        }
+
+        #[derive(Serialize)]
+        struct User {
+            id: u32,
+            #[serde(flatten)]
+            name: Name,
+            #[serde(flatten)]
+            extra: BTreeMap<&'static str, u32>,
+            #[serde(flatten)]
+            nickname: Option<Name>,
+            #[serde(flatten)]
+            contact: Contact,
+        }
+
+        let user = |contact| User {
+            id: 1,
+            name: Name {
+                first: "Ada",
+                last: "Lovelace",
+            },
+            extra: BTreeMap::from([("born", 1815)]),
+            nickname: None,
+            contact,
+        };
+        let users = vec![
+            user(Contact::Email("ada@example.com")),
+            user(Contact::Phone {
+                country: 44,
+                number: 2079460000,
+            }),
+        ];
+
+        let mut extractor = DatalogExtractor::new(testing::FactRecorder::new(
+            backend::vector::Backend::default(),
+        ));
+        let mut json = serde_json::Serializer::new(Vec::new());
+        users
+            .serialize(TeeSerializer::new(&mut json, &mut extractor))
+            .unwrap();
+        let json = String::from_utf8(json.into_inner()).unwrap();
+        assert_eq!(json, serde_json::to_string(&users).unwrap());
+
+        let (backend, facts) = extractor.get_backend().into_parts();
+        testing::check_invariants(&facts).unwrap();
+        let data = backend.get_data();
+        let sym = |s: &str| *data.symbol_table.get_by_left(s).unwrap();
+
+        // users are maps whose entries are their fields and the entries of
+        // their flattened fields
+        let user_elems: Vec<ElemId> = data
+            .type_table
+            .iter()
+            .filter(|(_, elem_type)| **elem_type == sym("Map"))
+            .map(|(elem, _)| *elem)
+            .collect();
+        assert_eq!(user_elems.len(), 2);
+
+        let keys = |elem: ElemId| {
+            let mut keys: Vec<&str> = data
+                .map_table
+                .iter()
+                .filter(|((map, _), _)| *map == elem)
+                .map(|((_, key), _)| data.symbol(&data.string_table[key]).unwrap().as_str())
+                .collect();
+            keys.sort();
+            keys
+        };
+        let mut all_keys: Vec<Vec<&str>> = user_elems.iter().map(|elem| keys(*elem)).collect();
+        all_keys.sort();
+        assert_eq!(
+            all_keys,
+            vec![
+                vec!["Email", "born", "first", "id", "last"],
+                vec!["Phone", "born", "first", "id", "last"],
+            ]
+        );
+
+        // the content of a flattened struct variant is a struct named after
+        // the variant
+        assert_eq!(data.struct_type_table.len(), 1);
+        assert!(data
+            .struct_type_table
+            .values()
+            .all(|name| *name == sym("Phone")));
+        assert_eq!(data.struct_table.len(), 2);
+    }
+
+    #[test]
+    fn run_reset_after_failure() {
+        #[derive(Serialize)]
+        enum Status {
+            Active,
+        }
+
+        #[derive(Serialize)]
+        struct Account {
+            id: u32,
+            #[serde(flatten)]
+            status: Status,
+        }
+
+        let mut extractor =
+            DatalogExtractor::new(backend::vector::Backend::default()).with_batch_size(100);
+
+        // serde cannot flatten unit variants, so extraction fails midway
+        // through the account's map
+        let account = Account {
+            id: 1,
+            status: Status::Active,
+        };
+        assert!(account.serialize(&mut extractor).is_err());
+
+        // without a reset, the next root value would be taken as part of the
+        // failed value and its facts would never be flushed
+        extractor.reset();
+        vec![true, false].serialize(&mut extractor).unwrap();
+
+        let data = extractor.get_backend().get_data();
+        assert_eq!(data.bool_table.len(), 2);
+        assert_eq!(data.seq_table.len(), 2);
+    }
+
+    #[test]
+    fn run_unit_policy() {
+        use serde_datalog::unit_policy::UnitPolicy;
+        use std::collections::HashSet;
+
+        struct Marker;
+
+        impl Serialize for Marker {
+            fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                serializer.serialize_unit_struct("Marker")
+            }
+        }
+
+        let value = ((), vec![(), ()], (Marker, Marker));
+        let extract = |policy| {
+            let mut extractor = DatalogExtractor::new(testing::FactRecorder::new(
+                backend::vector::Backend::default(),
+            ))
+            .with_unit_policy(policy);
+            value.serialize(&mut extractor).unwrap();
+            let (backend, facts) = extractor.get_backend().into_parts();
+            testing::check_invariants(&facts).unwrap();
+            backend.get_data()
+        };
+
+        let data = extract(UnitPolicy::Elements);
+        assert_eq!(data.type_table.len(), 8);
+        assert!(data.unit_table.is_empty());
+
+        let data = extract(UnitPolicy::Facts);
+        assert_eq!(data.type_table.len(), 8);
+        assert_eq!(data.unit_table.len(), 5);
+
+        // one shared element for () and one for Marker, besides the tuples
+        // and the sequence
+        let data = extract(UnitPolicy::Singletons);
+        assert_eq!(data.type_table.len(), 5);
+        assert_eq!(data.struct_type_table.len(), 1);
+        assert_eq!(data.seq_table.len(), 2);
+        let seq_units: HashSet<ElemId> = data.seq_table.values().copied().collect();
+        assert_eq!(seq_units.len(), 1);
+        assert!(data
+            .tuple_table
+            .values()
+            .any(|elem| seq_units.contains(elem)));
+
+        // root unit values are not shared
+        let mut extractor = DatalogExtractor::new(backend::vector::Backend::default())
+            .with_unit_policy(UnitPolicy::Singletons);
+        ().serialize(&mut extractor).unwrap();
+        ().serialize(&mut extractor).unwrap();
+        assert_eq!(extractor.get_backend().get_data().type_table.len(), 2);
+    }
+
+    #[test]
+    fn run_sparse_structs() {
+        use serde::ser::SerializeStruct;
+
+        /// A struct that skips its `email` field when it is `None`, as
+        /// `#[serde(skip_serializing_if = "Option::is_none")]` does.
+        struct User {
+            name: &'static str,
+            email: Option<&'static str>,
+        }
+
+        impl Serialize for User {
+            fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                let len = 1 + self.email.is_some() as usize;
+                let mut state = serializer.serialize_struct("User", len)?;
+                state.serialize_field("name", self.name)?;
+                match self.email {
+                    Some(email) => state.serialize_field("email", email)?,
+                    None => state.skip_field("email")?,
+                }
+                state.end()
+            }
+        }
+
+        let users = vec![
+            User {
+                name: "ada",
+                email: Some("ada@example.com"),
+            },
+            User {
+                name: "bob",
+                email: None,
+            },
+        ];
+
+        // without sparse structs, skipped fields leave no trace
+        let mut extractor = DatalogExtractor::new(backend::vector::Backend::default());
+        users.serialize(&mut extractor).unwrap();
+        let data = extractor.get_backend().get_data();
+        assert!(data.declared_len_table.is_empty());
+        assert!(data.absent_field_table.is_empty());
+
+        let hints = StructHints::new("User")
+            .fields(&["name", "email", "phone"])
+            .rename("phone", "tel");
+        let mut extractor = DatalogExtractor::new(backend::vector::Backend::default())
+            .with_sparse_structs(true)
+            .with_hints(hints);
+        users.serialize(&mut extractor).unwrap();
+        let data = extractor.get_backend().get_data();
+
+        let mut lens: Vec<usize> = data.declared_len_table.values().copied().collect();
+        lens.sort();
+        assert_eq!(lens, vec![1, 2]);
+
+        let field = |name| data.symbol_id(SymbolNamespace::Field, name).unwrap();
+        let bob = data
+            .declared_len_table
+            .iter()
+            .find_map(|(elem, len)| (*len == 1).then_some(*elem))
+            .unwrap();
+        assert_eq!(data.absent_field_table.len(), 3);
+        assert!(data.absent_field_table.contains(&(bob, field("email"))));
+        assert!(data.absent_field_table.contains(&(bob, field("tel"))));
+    }
+
+    #[test]
+    fn run_bytes_policy() {
+        use serde_datalog::bytes_policy::BytesPolicy;
+
+        struct Payload(&'static [u8]);
+
+        impl Serialize for Payload {
+            fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                serializer.serialize_bytes(self.0)
+            }
+        }
+
+        let value = vec![Payload(b"hi!"), Payload(&[0; 1024])];
+
+        // byte arrays cannot be extracted without a policy
+        let mut extractor = DatalogExtractor::new(backend::vector::Backend::default());
+        assert!(matches!(
+            value.serialize(&mut extractor),
+            Err(DatalogExtractionError::UnextractableData(_))
+        ));
+
+        let mut extractor = DatalogExtractor::new(
+            backend::vector::Backend::default().with_bytes_policy(BytesPolicy::Base64),
+        );
+        value.serialize(&mut extractor).unwrap();
+        let data = extractor.get_backend().get_data();
+
+        let hi = data
+            .symbol_id(SymbolNamespace::Value, "aGkh")
+            .expect("encoded value is interned");
+        let encoding = data.symbol_id(SymbolNamespace::Value, "base64").unwrap();
+        let (elem, _) = data.bytes_table.iter().find(|(_, v)| **v == hi).unwrap();
+        assert_eq!(data.bytes_encoding_table[elem], (encoding, 3));
+
+        let lengths: Vec<usize> = data
+            .bytes_encoding_table
+            .values()
+            .map(|(_, len)| *len)
+            .collect();
+        assert_eq!(data.bytes_table.len(), 2);
+        assert!(lengths.contains(&1024));
+    }
+
+    #[test]
+    fn run_number_pool() {
+        use serde_datalog::snapshot::Snapshot;
+
+        let value: Vec<u64> = vec![0, 1, 1, 0, 300];
+
+        let mut extractor = DatalogExtractor::new(backend::vector::Backend::default());
+        value.serialize(&mut extractor).unwrap();
+        let unpooled = extractor.get_backend().get_data().snapshot();
+
+        let mut extractor =
+            DatalogExtractor::new(backend::vector::Backend::default().with_number_pool(0..=255));
+        value.serialize(&mut extractor).unwrap();
+        let data = extractor.get_backend().get_data();
+
+        // only values in the pool are interned, and shared between elements
+        assert_eq!(data.number_pool.len(), 2);
+        assert_eq!(data.pooled_number_table.len(), 4);
+        assert_eq!(data.number_table.len(), 1);
+
+        let values: Vec<i64> = data.numbers().map(|(_, value)| value).collect();
+        assert_eq!(values.len(), 5);
+        for (elem, id) in data.pooled_number_table.iter() {
+            assert_eq!(
+                data.number(elem),
+                data.number_pool.get_by_right(id).copied()
+            );
+        }
+
+        assert_eq!(data.snapshot(), unpooled);
+    }
+
+    #[test]
+    fn run_fuzzer() {
+        /// Arbitrary JSON values that can be serialized.
+        #[derive(Debug)]
+        struct Json(Value);
+
+        impl Serialize for Json {
+            fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                self.0.serialize(serializer)
+            }
+        }
+
+        impl<'a> Arbitrary<'a> for Json {
+            fn arbitrary(u: &mut Unstructured<'a>) -> arbitrary::Result<Self> {
+                ArbitraryValue::arbitrary(u).map(|value| Json(value.clone().into()))
+            }
+        }
+
+        let mut data = [0u8; 16384];
+        rand::thread_rng().fill_bytes(&mut data);
+        let mut u = Unstructured::new(&data);
+
+        let summary = testing::check_arbitrary_with(
+            &mut u,
+            10000,
+            backend::vector::Backend::default,
+            |value: &Json, backend| check_counts(&value.0, &backend.get_data()),
+        )
+        .unwrap_or_else(|failure| panic!("{}", failure));
+
+        println!(
+            "generated {} arbitrary JSON values in total, tested {}",
+            summary.generated, summary.extracted
+        );
+    }
+
+    #[cfg(all(feature = "input_format", feature = "avro"))]
+    #[test]
+    fn run_avro() {
+        use serde_datalog::input_format::avro::InputFormatAvro;
+
+        // the records of the fixture are split across two blocks, and
+        // exercise every type except floats, which the vector backend does
+        // not extract; the second fixture has the same records in a single
+        // block compressed with deflate
+        let avro = std::fs::read("examples/test1.avro").unwrap();
+        let paths = extract_input(&InputFormatAvro, &avro).unwrap();
+        assert_eq!(
+            paths,
+            vec![
+                r#"input:1.attrs["x"] = 1"#,
+                r#"input:1.big = -5000000000"#,
+                r#"input:1.color: test.Color::GREEN"#,
+                r#"input:1.count = 7"#,
+                r#"input:1.day = 19000"#,
+                r#"input:1.flag = true"#,
+                r#"input:1.id = "6162""#,
+                r#"input:1.name = "root""#,
+                r#"input:1.next.big = 0"#,
+                r#"input:1.next.color: test.Color::RED"#,
+                r#"input:1.next.count = -1"#,
+                r#"input:1.next.day = 0"#,
+                r#"input:1.next.flag = false"#,
+                r#"input:1.next.id = "0001""#,
+                r#"input:1.next.name = "leaf""#,
+                r#"input:1.next.raw = "6661656c""#,
+                r#"input:1.next: test.Node"#,
+                r#"input:1.raw = "746f6f72""#,
+                r#"input:1.tags[0] = "a""#,
+                r#"input:1.tags[1] = "b""#,
+                r#"input:1: test.Node"#,
+                r#"input:2.attrs["y"] = -2"#,
+                r#"input:2.attrs["z"] = 3"#,
+                r#"input:2.big = 1"#,
+                r#"input:2.color: test.Color::RED"#,
+                r#"input:2.count = 0"#,
+                r#"input:2.day = 1"#,
+                r#"input:2.flag = false"#,
+                r#"input:2.id = "ff00""#,
+                r#"input:2.name = """#,
+                r#"input:2.raw = """#,
+                r#"input:2: test.Node"#,
+                r#"input:3.big = 9223372036854775807"#,
+                r#"input:3.color: test.Color::GREEN"#,
+                r#"input:3.count = 2147483647"#,
+                r#"input:3.day = -1"#,
+                r#"input:3.flag = true"#,
+                r#"input:3.id = "7a7a""#,
+                r#"input:3.name = "héllo""#,
+                r#"input:3.raw = "6f6c6cc3a968""#,
+                r#"input:3.tags[0] = "c""#,
+                r#"input:3: test.Node"#,
+            ]
+        );
+
+        #[cfg(feature = "compression")]
+        {
+            let deflate = std::fs::read("examples/test1_deflate.avro").unwrap();
+            assert_eq!(extract_input(&InputFormatAvro, &deflate).unwrap(), paths);
+        }
+
+        // a container with the given schema and a single block of `count`
+        // records
+        fn container(schema: &str, count: u64, block: &[u8]) -> Vec<u8> {
+            fn long(value: i64) -> Vec<u8> {
+                let mut value = ((value << 1) ^ (value >> 63)) as u64;
+                let mut bytes = Vec::new();
+                while value >= 0x80 {
+                    bytes.push((value & 0x7f) as u8 | 0x80);
+                    value >>= 7;
+                }
+                bytes.push(value as u8);
+                bytes
+            }
+
+            let sync = [7u8; 16];
+            let mut bytes = b"Obj\x01".to_vec();
+            bytes.extend(long(1));
+            bytes.extend(long(11));
+            bytes.extend(b"avro.schema");
+            bytes.extend(long(schema.len() as i64));
+            bytes.extend(schema.as_bytes());
+            bytes.extend(long(0));
+            bytes.extend(sync);
+            bytes.extend(long(count as i64));
+            bytes.extend(long(block.len() as i64));
+            bytes.extend(block);
+            bytes.extend(sync);
+            bytes
+        }
+
+        let array = r#"{"type": "array", "items": "long"}"#;
+        assert_eq!(
+            extract_input(&InputFormatAvro, &container(array, 1, b"\x04\x02\x04\x00")).unwrap(),
+            vec!["input:1[0] = 1", "input:1[1] = 2"]
+        );
+
+        // truncated input, a corrupted sync marker, and counts that the rest
+        // of the input cannot hold are rejected
+        let error = |contents: &[u8]| extract_input(&InputFormatAvro, contents).unwrap_err();
+        assert_eq!(
+            error(&avro[..avro.len() - 20]),
+            "unexpected end of input at byte 840"
+        );
+        let mut corrupted = avro.clone();
+        *corrupted.last_mut().unwrap() ^= 0xff;
+        assert_eq!(error(&corrupted), "invalid sync marker at byte 844");
+        assert_eq!(
+            error(&container(array, 1, b"\xfe\xff\xff\xff\x0f\x02")),
+            "count 2147483647 at byte 5 exceeds the remaining 1 bytes"
+        );
+        assert_eq!(
+            error(&container(array, 1 << 40, b"\x00")),
+            "count 1099511627776 at byte 0 exceeds the remaining 1 bytes"
+        );
+
+        // deeply nested schemas and values are rejected
+        let nested = (0..200).fold(r#""long""#.to_string(), |items, _| {
+            format!(r#"{{"type": "array", "items": {}}}"#, items)
+        });
+        assert_eq!(
+            error(&container(&nested, 0, b"")),
+            "invalid Avro schema: recursion limit exceeded at line 1 column 3430"
+        );
+
+        // each item of the list is nested in a record and a union
+        let list = r#"{"type": "record", "name": "List", "fields": [
+            {"name": "next", "type": ["null", "List"]}
+        ]}"#;
+        let items = |count: usize| {
+            let mut block = vec![2u8; count];
+            block.push(0);
+            container(list, 1, &block)
+        };
+        assert!(extract_input(&InputFormatAvro, &items(60)).is_ok());
+        assert_eq!(
+            error(&items(100)),
+            "value at byte 64 is nested more than 128 levels"
+        );
+    }
+
+    #[cfg(all(feature = "input_format", feature = "graphql"))]
+    #[test]
+    fn run_graphql() {
+        use serde_datalog::input_format::graphql::{
+            InputFormatGraphQL, InputFormatGraphQLResponse,
+        };
+
+        let sdl = r#"
+schema { query: Query }
+
+"The root of all queries"
+type Query {
+  user(id: ID! = 1): [User!]!
+}
+
+type User implements Node @key(fields: "id") {
+  id: ID!
+  name: String @deprecated(reason: "use fullName")
+}
+
+enum Role { ADMIN USER }
+
+extend type User { role: Role }
+
+directive @key(fields: String!) repeatable on OBJECT | INTERFACE
+"#;
+        let paths = extract_input(&InputFormatGraphQL, sdl.as_bytes()).unwrap();
+        let expected = [
+            r#"input.directives[0].arguments[0].name = "fields""#,
+            r#"input.directives[0].arguments[0].named_type = "String""#,
+            r#"input.directives[0].arguments[0].type = "String!""#,
+            r#"input.directives[0].locations[0] = "OBJECT""#,
+            r#"input.directives[0].locations[1] = "INTERFACE""#,
+            r#"input.directives[0].name = "key""#,
+            r#"input.directives[0].repeatable = true"#,
+            r#"input.schema[0].operations["query"] = "Query""#,
+            r#"input.types[0].description = "The root of all queries""#,
+            r#"input.types[0].fields[0].arguments[0].default_value = "1""#,
+            r#"input.types[0].fields[0].arguments[0].named_type = "ID""#,
+            r#"input.types[0].fields[0].arguments[0].type = "ID!""#,
+            r#"input.types[0].fields[0].named_type = "User""#,
+            r#"input.types[0].fields[0].type = "[User!]!""#,
+            r#"input.types[0]: TypeDefinition::Object"#,
+            r#"input.types[1].directives[0].arguments["fields"] = "\"id\"""#,
+            r#"input.types[1].fields[1].directives[0].arguments["reason"] = "\"use fullName\"""#,
+            r#"input.types[1].fields[1]: FieldDefinition"#,
+            r#"input.types[1].interfaces[0] = "Node""#,
+            r#"input.types[2].values[1].name = "USER""#,
+            r#"input.types[2]: TypeDefinition::Enum"#,
+            r#"input.types[3].extend = true"#,
+            r#"input.types[3].fields[0].named_type = "Role""#,
+            r#"input.types[3].name = "User""#,
+            r#"input: GraphQLSchema"#,
+        ];
+        for path in expected {
+            assert!(paths.contains(&path.to_string()), "missing {}", path);
+        }
+
+        // malformed schemas are reported with the line of the offending token
+        for (sdl, message) in [
+            (
+                "type Query { name: String",
+                "expected a name, found end of input",
+            ),
+            (
+                "query { user }",
+                "expected a type system definition, not an operation, found `query` at line 1",
+            ),
+            (
+                "type Query {\n name: \"String }",
+                "unterminated string at line 2",
+            ),
+            (
+                "type User {\n  id: [ID\n}",
+                "expected `]`, found `}` at line 3",
+            ),
+            ("\"\"\"doc", "unterminated block string at line 1"),
+        ] {
+            assert_eq!(
+                extract_input(&InputFormatGraphQL, sdl.as_bytes()),
+                Result::Err(message.to_string())
+            );
+        }
+
+        // objects of responses with a type name are structs of that type
+        let response = r#"{
+            "data": {"user": {"__typename": "User", "id": "1", "meta": {"a": 1}}},
+            "errors": [{"message": "boom", "path": ["user", "name"]}]
+        }"#;
+        assert_eq!(
+            extract_input(&InputFormatGraphQLResponse, response.as_bytes()).unwrap(),
+            vec![
+                r#"input.data["user"].__typename = "User""#,
+                r#"input.data["user"].id = "1""#,
+                r#"input.data["user"].meta["a"] = 1"#,
+                r#"input.data["user"]: User"#,
+                r#"input.errors[0]["message"] = "boom""#,
+                r#"input.errors[0]["path"][0] = "user""#,
+                r#"input.errors[0]["path"][1] = "name""#,
+                r#"input: GraphQLResponse"#,
+            ]
+        );
+        assert_eq!(
+            extract_input(&InputFormatGraphQLResponse, b"[1]"),
+            Result::Err("GraphQL response must be an object".to_string())
+        );
+    }
+
+    #[cfg(all(feature = "input_format", feature = "protobuf"))]
+    #[test]
+    fn run_protobuf() {
+        use prost_reflect::{
+            prost::Message,
+            prost_types::{
+                field_descriptor_proto::{Label, Type},
+                DescriptorProto, FieldDescriptorProto, FileDescriptorProto, FileDescriptorSet,
+                MessageOptions,
+            },
+            DescriptorPool, DynamicMessage, MapKey, Value,
+        };
+        use serde_datalog::input_format::protobuf::InputFormatProtobuf;
+        use std::collections::HashMap;
+
+        let field = |name: &str, number: i32, label: Label, ty: Type, type_name: Option<&str>| {
+            FieldDescriptorProto {
+                name: Some(name.to_string()),
+                number: Some(number),
+                label: Some(label as i32),
+                r#type: Some(ty as i32),
+                type_name: type_name.map(str::to_string),
+                ..Default::default()
+            }
+        };
+        let item = DescriptorProto {
+            name: Some("Item".to_string()),
+            field: vec![
+                field("id", 1, Label::Optional, Type::Int64, None),
+                field("name", 2, Label::Optional, Type::String, None),
+                field("tags", 3, Label::Repeated, Type::String, None),
+                field(
+                    "inner",
+                    4,
+                    Label::Optional,
+                    Type::Message,
+                    Some(".test.Inner"),
+                ),
+                field(
+                    "counts",
+                    5,
+                    Label::Repeated,
+                    Type::Message,
+                    Some(".test.Item.CountsEntry"),
+                ),
+            ],
+            nested_type: vec![DescriptorProto {
+                name: Some("CountsEntry".to_string()),
+                field: vec![
+                    field("key", 1, Label::Optional, Type::String, None),
+                    field("value", 2, Label::Optional, Type::Int32, None),
+                ],
+                options: Some(MessageOptions {
+                    map_entry: Some(true),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        let inner = DescriptorProto {
+            name: Some("Inner".to_string()),
+            field: vec![field("ok", 1, Label::Optional, Type::Bool, None)],
+            ..Default::default()
+        };
+        let descriptor_set = FileDescriptorSet {
+            file: vec![FileDescriptorProto {
+                name: Some("test.proto".to_string()),
+                package: Some("test".to_string()),
+                message_type: vec![item, inner],
+                syntax: Some("proto3".to_string()),
+                ..Default::default()
+            }],
+        }
+        .encode_to_vec();
+
+        let pool = DescriptorPool::decode(descriptor_set.as_slice()).unwrap();
+        let mut message = DynamicMessage::new(pool.get_message_by_name("test.Item").unwrap());
+        message.set_field_by_name("id", Value::I64(1 << 40));
+        message.set_field_by_name("name", Value::String("abc".to_string()));
+        message.set_field_by_name(
+            "tags",
+            Value::List(vec![
+                Value::String("x".to_string()),
+                Value::String("y".to_string()),
+            ]),
+        );
+        message.set_field_by_name(
+            "counts",
+            Value::Map(HashMap::from([(
+                MapKey::String("a".to_string()),
+                Value::I32(2),
+            )])),
+        );
+        let inner = DynamicMessage::new(pool.get_message_by_name("test.Inner").unwrap());
+        message.set_field_by_name("inner", Value::Message(inner));
+        let contents = message.encode_to_vec();
+
+        // 64-bit integers are numbers and fields with default values are
+        // included, such as the unset field `ok` of `inner`
+        let format = InputFormatProtobuf::new(&descriptor_set, "test.Item").unwrap();
+        assert_eq!(
+            extract_input(&format, &contents).unwrap(),
+            vec![
+                r#"input["counts"]["a"] = 2"#,
+                r#"input["id"] = 1099511627776"#,
+                r#"input["inner"]["ok"] = false"#,
+                r#"input["name"] = "abc""#,
+                r#"input["tags"][0] = "x""#,
+                r#"input["tags"][1] = "y""#,
+            ]
+        );
+
+        assert_eq!(
+            InputFormatProtobuf::new(&descriptor_set, "test.Missing").err(),
+            Some("unknown protobuf message type test.Missing".to_string())
+        );
+        assert!(extract_input(&format, &[0xff]).is_err());
+        assert_eq!(
+            extract_input(&InputFormatProtobuf::unconfigured(), &contents),
+            Result::Err(
+                "the message type of protobuf input must be specified with --proto-descriptor and --proto-message"
+                    .to_string()
+            )
+        );
+    }
+
+    #[cfg(all(feature = "input_format", feature = "ini"))]
+    #[test]
+    fn run_ini() {
+        use serde_datalog::input_format::ini::InputFormatINI;
+
+        // keys before the first section are in the section named ""; the last
+        // definition of duplicate keys is extracted, also across repeated
+        // sections
+        let ini = r#"
+top = 1
+
+[server]
+host = "example.com"
+port = 80
+port = 8080
+; a comment
+path = a\tb
+
+[client]
+name = 'quoted value'
+
+[server]
+host = localhost
+"#;
+        assert_eq!(
+            extract_input(&InputFormatINI, ini.as_bytes()).unwrap(),
+            vec![
+                r#"input[""]["top"] = "1""#,
+                r#"input["client"]["name"] = "quoted value""#,
+                r#"input["server"]["host"] = "localhost""#,
+                r#"input["server"]["path"] = "a\tb""#,
+                r#"input["server"]["port"] = "8080""#,
+            ]
+        );
+
+        assert!(extract_input(&InputFormatINI, b"[server\nhost = a").is_err());
+    }
+
+    #[cfg(all(feature = "input_format", feature = "env"))]
+    #[test]
+    fn run_env() {
+        use serde_datalog::input_format::env::InputFormatEnv;
+
+        let env = r#"
+# database settings
+export HOST=localhost
+PORT = 5432 # the default port
+URL=http://host/#anchor
+MESSAGE="line one\nsay \"hi\" # not a comment"
+LITERAL='no \n escapes'
+HOME_DIR=${HOME}/app
+EMPTY=
+PORT=6543
+"#;
+        assert_eq!(
+            extract_input(&InputFormatEnv, env.as_bytes()).unwrap(),
+            vec![
+                r#"input["EMPTY"] = """#,
+                r#"input["HOME_DIR"] = "${HOME}/app""#,
+                r#"input["HOST"] = "localhost""#,
+                r#"input["LITERAL"] = "no \\n escapes""#,
+                r#"input["MESSAGE"] = "line one\nsay \"hi\" # not a comment""#,
+                r#"input["PORT"] = "6543""#,
+                r#"input["URL"] = "http://host/#anchor""#,
+            ]
+        );
+
+        // errors are reported with the line of the offending variable
+        for (env, message) in [
+            ("A=1\nB", "line 2: expected KEY=VALUE"),
+            ("MY VAR=1", "line 1: invalid variable name 'MY VAR'"),
+            ("=1", "line 1: invalid variable name ''"),
+            ("A=\"abc", "line 1: unterminated double-quoted value"),
+            ("A=\"abc\\", "line 1: unterminated escape sequence"),
+            ("A='abc", "line 1: unterminated single-quoted value"),
+        ] {
+            assert_eq!(
+                extract_input(&InputFormatEnv, env.as_bytes()),
+                Result::Err(message.to_string())
+            );
+        }
+    }
+
+    #[cfg(all(feature = "input_format", feature = "edn"))]
+    #[test]
+    fn run_edn() {
+        use serde_datalog::input_format::edn::InputFormatEDN;
+
+        let edn = r#"{:name "x" :tags #{:a b} #_ :ignored 1N 2.5M
+ :at #inst "2024-01-01T00:00:00Z" :point #geo/point [1 2] [3] (nil \c)}"#;
+        assert_eq!(
+            extract_input(&InputFormatEDN, edn.as_bytes()).unwrap(),
+            vec![
+                r#"input[Edn::BigInt("1")]: Edn::BigDecimal"#,
+                r#"input[Edn::BigInt("1")][0] = "2.5""#,
+                r#"input[Edn::Keyword("at")]: Edn::Tagged"#,
+                r#"input[Edn::Keyword("at")][0] = "inst""#,
+                r#"input[Edn::Keyword("at")][1] = "2024-01-01T00:00:00Z""#,
+                r#"input[Edn::Keyword("name")] = "x""#,
+                r#"input[Edn::Keyword("point")]: Edn::Tagged"#,
+                r#"input[Edn::Keyword("point")][0] = "geo/point""#,
+                r#"input[Edn::Keyword("point")][1][0] = 1"#,
+                r#"input[Edn::Keyword("point")][1][1] = 2"#,
+                r#"input[Edn::Keyword("tags")]: Edn::Set"#,
+                r#"input[Edn::Keyword("tags")][0][0]: Edn::Keyword"#,
+                r#"input[Edn::Keyword("tags")][0][0][0] = "a""#,
+                r#"input[Edn::Keyword("tags")][0][1]: Edn::Symbol"#,
+                r#"input[Edn::Keyword("tags")][0][1][0] = "b""#,
+                r#"input[[3]][1] = "c""#,
+            ]
+        );
+
+        // each top-level value is a separate root
+        assert_eq!(
+            extract_input(&InputFormatEDN, b"1 ; comment\n[true]").unwrap(),
+            vec!["input:1 = 1", "input:2[0] = true"]
+        );
+
+        // malformed values are reported with the position following the
+        // offending token
+        for (edn, message) in [
+            ("[1\n 2", "unexpected end of input at line 2 column 3"),
+            ("{:a}", "map has a key without a value at line 1 column 5"),
+            ("(1))", "unexpected closing delimiter at line 1 column 4"),
+            ("\"abc", "unterminated string at line 1 column 5"),
+            ("#_", "expected a value to discard at line 1 column 3"),
+            ("#!x", "invalid dispatch character at line 1 column 2"),
+            (":", "empty keyword at line 1 column 2"),
+            ("12abc", "invalid number 12abc at line 1 column 6"),
+            ("\\foo", "invalid character \\foo at line 1 column 5"),
+        ] {
+            assert_eq!(
+                extract_input(&InputFormatEDN, edn.as_bytes()),
+                Result::Err(message.to_string())
+            );
+        }
+    }
+
+    #[cfg(all(feature = "input_format", feature = "rust"))]
+    #[test]
+    fn run_rust() {
+        use serde_datalog::input_format::rust::InputFormatRust;
+
+        let source = r#"//! Shapes.
+#![allow(dead_code)]
+
+#[derive(Debug)]
+pub struct Point<T: Copy> {
+    x: T,
+    pub y: T,
+}
+
+impl Point<f64> {
+    pub fn norm(&self) -> f64 {
+        let sum = self.x * self.x + self.y * self.y;
+        sum.sqrt()
+    }
+}
+
+fn main() {
+    println!("{}", Point { x: 1.0, y: 2.0 }.norm());
+}
+"#;
+        let paths = extract_input(&InputFormatRust, source.as_bytes()).unwrap();
+        let expected = [
+            r#"input.attrs[0] = "doc = \" Shapes.\"""#,
+            r#"input.attrs[1] = "allow (dead_code)""#,
+            r#"input.items[0].attrs[0] = "derive (Debug)""#,
+            r#"input.items[0].fields[1].name[0] = "y""#,
+            r#"input.items[0].fields[1].ty = "T""#,
+            r#"input.items[0].fields[1].vis = "pub""#,
+            r#"input.items[0].generics = "< T : Copy >""#,
+            r#"input.items[0].line = 4"#,
+            r#"input.items[0].name = "Point""#,
+            r#"input.items[0]: Item::Struct"#,
+            r#"input.items[1].items[0].body[0].init[0].op = "+""#,
+            r#"input.items[1].items[0].body[0].init[0].left.left.member = "x""#,
+            r#"input.items[1].items[0].body[0].init[0]: Expr::Binary"#,
+            r#"input.items[1].items[0].body[0].pat = "sum""#,
+            r#"input.items[1].items[0].body[0]: Stmt::Local"#,
+            r#"input.items[1].items[0].body[1].expr.line = 13"#,
+            r#"input.items[1].items[0].body[1].expr.method = "sqrt""#,
+            r#"input.items[1].items[0].body[1].expr: Expr::MethodCall"#,
+            r#"input.items[1].items[0].body[1].semi = false"#,
+            r#"input.items[1].items[0].sig.inputs[0].reference = true"#,
+            r#"input.items[1].items[0].sig.inputs[0]: FnArg::Receiver"#,
+            r#"input.items[1].items[0].sig.output[0] = "f64""#,
+            r#"input.items[1].items[0]: ImplItem::Fn"#,
+            r#"input.items[1].self_ty = "Point < f64 >""#,
+            r#"input.items[1].trait: Option::None"#,
+            r#"input.items[1]: Item::Impl"#,
+            r#"input.items[2].body[0].line = 18"#,
+            r#"input.items[2].body[0].path = "println""#,
+            r#"input.items[2].body[0].tokens = "\"{}\" , Point { x : 1.0 , y : 2.0 } . norm ()""#,
+            r#"input.items[2].body[0]: Stmt::Macro"#,
+            r#"input.items[2].name = "main""#,
+            r#"input.items[2].sig.output: Option::None"#,
+            r#"input.items[2]: Item::Fn"#,
+            r#"input: File"#,
+        ];
+        for path in expected {
+            assert!(paths.contains(&path.to_string()), "missing {}", path);
+        }
+
+        // syntax errors are reported with the position of the offending token
+        assert_eq!(
+            extract_input(&InputFormatRust, b"fn main() {\n    let x = ;\n}"),
+            Result::Err("expected an expression at line 2 column 13".to_string())
+        );
+        assert_eq!(
+            extract_input(&InputFormatRust, b"struct A {"),
+            Result::Err("cannot parse string into token stream at line 1 column 10".to_string())
+        );
+    }
+
+    #[cfg(all(feature = "input_format", feature = "url"))]
+    #[test]
+    fn run_url() {
+        use serde_datalog::input_format::url::{InputFormatURL, InputFormatURLEncoded};
+
+        // each non-empty line is a root, and repeated keys keep their values
+        // in order of occurrence
+        let urls = "https://example.com:8080/a%20b?q=data+log&tag=x&tag=y&empty#top\n\n/search?q=%C3%A9&q=2\n";
+        assert_eq!(
+            extract_input(&InputFormatURL, urls.as_bytes()).unwrap(),
+            vec![
+                r#"input:1.fragment: Option::Some"#,
+                r#"input:1.fragment[0] = "top""#,
+                r#"input:1.host: Option::Some"#,
+                r#"input:1.host[0] = "example.com""#,
+                r#"input:1.path = "/a%20b""#,
+                r#"input:1.port: Option::Some"#,
+                r#"input:1.port[0] = 8080"#,
+                r#"input:1.query["empty"][0] = """#,
+                r#"input:1.query["q"][0] = "data log""#,
+                r#"input:1.query["tag"][0] = "x""#,
+                r#"input:1.query["tag"][1] = "y""#,
+                r#"input:1.scheme: Option::Some"#,
+                r#"input:1.scheme[0] = "https""#,
+                r#"input:1: Url"#,
+                r#"input:3.fragment: Option::None"#,
+                r#"input:3.host: Option::None"#,
+                r#"input:3.path = "/search""#,
+                r#"input:3.port: Option::None"#,
+                r#"input:3.query["q"][0] = "é""#,
+                r#"input:3.query["q"][1] = "2""#,
+                r#"input:3.scheme: Option::None"#,
+                r#"input:3: Url"#,
+            ]
+        );
+        assert_eq!(
+            extract_input(&InputFormatURL, b"https://example.com\nhttp://[::1\n"),
+            Result::Err("line 2: invalid IPv6 address".to_string())
+        );
+
+        // bare query strings may start with `?`
+        assert_eq!(
+            extract_input(&InputFormatURLEncoded, b" ?a=1&b=%26&a=2&c\n").unwrap(),
+            vec![
+                r#"input["a"][0] = "1""#,
+                r#"input["a"][1] = "2""#,
+                r#"input["b"][0] = "&""#,
+                r#"input["c"][0] = """#,
+            ]
+        );
+    }
+
+    #[cfg(all(feature = "input_format", feature = "spreadsheet"))]
+    #[test]
+    fn run_spreadsheet() {
+        use serde_datalog::input_format::spreadsheet::InputFormatSpreadsheet;
+
+        let workbook = std::fs::read("examples/test1.xlsx").unwrap();
+        // columns with an empty or repeated header are keyed by their letter,
+        // empty rows and cells are omitted, and the whole number 3.0 is an
+        // integer
+        assert_eq!(
+            extract_input(&InputFormatSpreadsheet, &workbook).unwrap(),
+            vec![
+                r#"input:1.rows[0]["C"] = "x""#,
+                r#"input:1.rows[0]["D"] = "Al""#,
+                r#"input:1.rows[0]["active"] = true"#,
+                r#"input:1.rows[0]["age"] = 30"#,
+                r#"input:1.rows[0]["joined"] = "2024-01-15T00:00:00""#,
+                r#"input:1.rows[0]["name"] = "Alice""#,
+                r##"input:1.rows[0]["ratio"] = "#DIV/0!""##,
+                r#"input:1.rows[1]["active"] = false"#,
+                r#"input:1.rows[1]["name"] = "Bob""#,
+                r#"input:1.rows[1]["ratio"] = 3"#,
+                r#"input:1: People"#,
+                r#"input:2.rows[0]["B"] = "Oslo""#,
+                r#"input:2.rows[0]["code"] = 1"#,
+                r#"input:2: Stations"#,
+            ]
+        );
+
+        assert_eq!(
+            extract_input(&InputFormatSpreadsheet, b"PK\x03\x04 not a workbook"),
+            Result::Err("Cannot detect file format".to_string())
+        );
+    }
+
+    #[cfg(all(feature = "input_format", feature = "logs"))]
+    #[test]
+    fn run_logs() {
+        use serde_datalog::input_format::logs::{InputFormatLogfmt, InputFormatSyslog};
+
+        let logfmt = r#"time=2024-05-01T12:30:00Z level=info msg="request \"done\"" debug
+ts=yesterday path=/a=b
+
+t=2024-05-01T14:30:00+02:00 empty= status=200
+"#;
+        // keys without a value map to "", and the timestamp is only set for
+        // RFC 3339 values of the timestamp keys
+        assert_eq!(
+            extract_input(&InputFormatLogfmt, logfmt.as_bytes()).unwrap(),
+            vec![
+                r#"input:1.fields["debug"] = """#,
+                r#"input:1.fields["level"] = "info""#,
+                r#"input:1.fields["msg"] = "request \"done\"""#,
+                r#"input:1.fields["time"] = "2024-05-01T12:30:00Z""#,
+                r#"input:1.timestamp: Option::Some"#,
+                r#"input:1.timestamp[0] = 1714566600"#,
+                r#"input:1: LogfmtRecord"#,
+                r#"input:2.fields["path"] = "/a=b""#,
+                r#"input:2.fields["ts"] = "yesterday""#,
+                r#"input:2.timestamp: Option::None"#,
+                r#"input:2: LogfmtRecord"#,
+                r#"input:4.fields["empty"] = """#,
+                r#"input:4.fields["status"] = "200""#,
+                r#"input:4.fields["t"] = "2024-05-01T14:30:00+02:00""#,
+                r#"input:4.timestamp: Option::Some"#,
+                r#"input:4.timestamp[0] = 1714566600"#,
+                r#"input:4: LogfmtRecord"#,
+            ]
+        );
+
+        for (logfmt, message) in [
+            ("a=1\nb=\"c", "line 2: unterminated quoted value"),
+            ("=b", "line 1: expected key before '='"),
+            ("a=1 =2", "line 1: expected key before '='"),
+        ] {
+            assert_eq!(
+                extract_input(&InputFormatLogfmt, logfmt.as_bytes()),
+                Result::Err(message.to_string())
+            );
+        }
+
+        // the last value of a repeated structured data parameter is extracted,
+        // and a byte order mark at the start of the message is dropped
+        let syslog = [
+            r#"<34>1 2024-05-01T12:30:00Z host app 1234 ID47 [origin ip="10.0.0.1"][meta a="x\"y\]" a="z"] hello world"#,
+            "<165>1 - - - - - -",
+            "",
+            "<0>2 2024-05-01T12:30:00.5+02:00 h a p m - \u{feff}hi",
+        ]
+        .join("\n");
+        assert_eq!(
+            extract_input(&InputFormatSyslog, syslog.as_bytes()).unwrap(),
+            vec![
+                r#"input:1.app_name: Option::Some"#,
+                r#"input:1.app_name[0] = "app""#,
+                r#"input:1.facility = 4"#,
+                r#"input:1.hostname: Option::Some"#,
+                r#"input:1.hostname[0] = "host""#,
+                r#"input:1.message: Option::Some"#,
+                r#"input:1.message[0] = "hello world""#,
+                r#"input:1.msgid: Option::Some"#,
+                r#"input:1.msgid[0] = "ID47""#,
+                r#"input:1.procid: Option::Some"#,
+                r#"input:1.procid[0] = "1234""#,
+                r#"input:1.severity = 2"#,
+                r#"input:1.structured_data["meta"]["a"] = "z""#,
+                r#"input:1.structured_data["origin"]["ip"] = "10.0.0.1""#,
+                r#"input:1.timestamp: Option::Some"#,
+                r#"input:1.timestamp[0] = 1714566600"#,
+                r#"input:1.version = 1"#,
+                r#"input:1: SyslogRecord"#,
+                r#"input:2.app_name: Option::None"#,
+                r#"input:2.facility = 20"#,
+                r#"input:2.hostname: Option::None"#,
+                r#"input:2.message: Option::None"#,
+                r#"input:2.msgid: Option::None"#,
+                r#"input:2.procid: Option::None"#,
+                r#"input:2.severity = 5"#,
+                r#"input:2.timestamp: Option::None"#,
+                r#"input:2.version = 1"#,
+                r#"input:2: SyslogRecord"#,
+                r#"input:4.app_name: Option::Some"#,
+                r#"input:4.app_name[0] = "a""#,
+                r#"input:4.facility = 0"#,
+                r#"input:4.hostname: Option::Some"#,
+                r#"input:4.hostname[0] = "h""#,
+                r#"input:4.message: Option::Some"#,
+                r#"input:4.message[0] = "hi""#,
+                r#"input:4.msgid: Option::Some"#,
+                r#"input:4.msgid[0] = "m""#,
+                r#"input:4.procid: Option::Some"#,
+                r#"input:4.procid[0] = "p""#,
+                r#"input:4.severity = 0"#,
+                r#"input:4.timestamp: Option::Some"#,
+                r#"input:4.timestamp[0] = 1714559400"#,
+                r#"input:4.version = 2"#,
+                r#"input:4: SyslogRecord"#,
+            ]
+        );
+
+        for (syslog, message) in [
+            ("34>1 - - - - - -", "line 1: expected <PRI>"),
+            ("<192>1 - - - - - -", "line 1: invalid priority 192"),
+            ("<1>x - - - - - -", "line 1: invalid version x"),
+            (
+                "<1>1 yesterday - - - - -",
+                "line 1: invalid timestamp yesterday",
+            ),
+            ("<1>1 - - -", "line 1: missing process id"),
+            (
+                "<1>1 - - - - - [a b]",
+                "line 1: expected PARAM-NAME=\"PARAM-VALUE\"",
+            ),
+            (
+                "<1>1 - - - - - [a b=\"c]",
+                "line 1: unterminated parameter value",
+            ),
+            (
+                "<1>1 - - - - - [a",
+                "line 1: unterminated structured data element",
+            ),
+            ("<1>1 - - - - - x", "line 1: expected structured data"),
+            (
+                "<1>1 - - - - - -x",
+                "line 1: expected space after structured data",
+            ),
+            ("<1>1 - - - - - -\nhello", "line 2: expected <PRI>"),
+        ] {
+            assert_eq!(
+                extract_input(&InputFormatSyslog, syslog.as_bytes()),
+                Result::Err(message.to_string())
+            );
+        }
     }
 }